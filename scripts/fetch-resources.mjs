@@ -0,0 +1,88 @@
+// 下载随安装包打包的资源到 src-tauri/resources：本地 OCR 引擎自带的 PP-OCRv4 模型，
+// 以及图片标注使用的中日文字体。已存在的文件不会重复下载。
+// 每个目录的 SHA256SUMS 中有记录的文件必须与记录一致；首次下载的文件会把校验值追加到
+// SHA256SUMS，请把它一并提交，之后的构建都按它校验。
+// 设置 ORCAPP_SKIP_RESOURCES=1 可跳过（离线开发时），此时安装包不带这些资源。
+import { createHash } from 'node:crypto'
+import { existsSync, readFileSync, appendFileSync, writeFileSync, renameSync, rmSync } from 'node:fs'
+import { dirname, join } from 'node:path'
+import { fileURLToPath } from 'node:url'
+
+const RESOURCES = [
+    {
+        dir: 'rapidocr',
+        files: [
+            {
+                file: 'ch_PP-OCRv4_det_infer.onnx',
+                url: 'https://huggingface.co/SWHL/RapidOCR/resolve/main/PP-OCRv4/ch_PP-OCRv4_det_infer.onnx'
+            },
+            {
+                file: 'ch_PP-OCRv4_rec_infer.onnx',
+                url: 'https://huggingface.co/SWHL/RapidOCR/resolve/main/PP-OCRv4/ch_PP-OCRv4_rec_infer.onnx'
+            },
+            {
+                file: 'ppocr_keys_v1.txt',
+                url: 'https://raw.githubusercontent.com/PaddlePaddle/PaddleOCR/release/2.7/ppocr/utils/ppocr_keys_v1.txt'
+            }
+        ]
+    },
+    {
+        dir: 'fonts',
+        files: [
+            {
+                file: 'NotoSansCJKsc-Regular.otf',
+                url: 'https://github.com/notofonts/noto-cjk/raw/main/Sans/OTF/SimplifiedChinese/NotoSansCJKsc-Regular.otf'
+            }
+        ]
+    }
+]
+
+const root = join(dirname(fileURLToPath(import.meta.url)), '..', 'src-tauri', 'resources')
+
+if (process.env.ORCAPP_SKIP_RESOURCES === '1') {
+    console.log('[resources] skipped, the bundle will not include the OCR models and fonts')
+    process.exit(0)
+}
+
+const sha256 = (path) => createHash('sha256').update(readFileSync(path)).digest('hex')
+
+// `<sha256>  <file>` per line, as written by sha256sum
+const readSums = (sumsPath) =>
+    new Map(
+        (existsSync(sumsPath) ? readFileSync(sumsPath, 'utf8') : '')
+            .split('\n')
+            .map((line) => line.trim().split(/\s+/))
+            .filter((parts) => parts.length === 2)
+            .map(([sum, file]) => [file, sum])
+    )
+
+for (const { dir, files } of RESOURCES) {
+    const sumsPath = join(root, dir, 'SHA256SUMS')
+    const knownSums = readSums(sumsPath)
+
+    for (const { file, url } of files) {
+        const path = join(root, dir, file)
+        if (!existsSync(path)) {
+            console.log(`[resources] downloading ${url}`)
+            const response = await fetch(url)
+            if (!response.ok) {
+                console.error(`[resources] ${dir}/${file}: HTTP ${response.status}`)
+                process.exit(1)
+            }
+            writeFileSync(`${path}.part`, Buffer.from(await response.arrayBuffer()))
+            renameSync(`${path}.part`, path)
+        }
+
+        const sum = sha256(path)
+        const known = knownSums.get(file)
+        if (!known) {
+            appendFileSync(sumsPath, `${sum}  ${file}\n`)
+            console.log(`[resources] ${dir}/${file}: recorded ${sum}, commit ${dir}/SHA256SUMS`)
+        } else if (known !== sum) {
+            rmSync(path)
+            console.error(`[resources] ${dir}/${file}: checksum ${sum} does not match SHA256SUMS (${known}), removed`)
+            process.exit(1)
+        }
+    }
+}
+console.log('[resources] ready')
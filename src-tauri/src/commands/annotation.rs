@@ -0,0 +1,49 @@
+use crate::error::AppError;
+use crate::services::annotate;
+use crate::services::i18n::ErrorCode;
+use crate::services::layout::BoundingBox;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::fs;
+use tauri_plugin_dialog::DialogExt;
+
+/// Draw boxes with their labels onto the image; returns a PNG as base64
+#[tauri::command]
+pub async fn render_annotations(
+    image_data: String,
+    boxes: Vec<BoundingBox>,
+    labels: Vec<String>,
+) -> Result<String, AppError> {
+    tokio::task::spawn_blocking(move || annotate::render_annotations(&image_data, &boxes, &labels))
+        .await
+        .map_err(AppError::internal)?
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail }.into())
+}
+
+/// Save the annotated image as PNG through the save dialog
+#[tauri::command]
+pub async fn export_annotated_image(
+    app: tauri::AppHandle,
+    image_data: String,
+    boxes: Vec<BoundingBox>,
+    labels: Vec<String>,
+    default_name: String,
+) -> Result<bool, AppError> {
+    let rendered = render_annotations(image_data, boxes, labels).await?;
+    let data = BASE64.decode(rendered).map_err(AppError::internal)?;
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("PNG", &["png"])
+        .set_file_name(format!("{}.png", default_name))
+        .blocking_save_file();
+
+    match file_path {
+        Some(file_path) => {
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            fs::write(&path, data).map_err(|e| AppError::internal(format!("保存文件失败: {}", e)))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
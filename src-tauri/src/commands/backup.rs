@@ -0,0 +1,22 @@
+use crate::db::backup::{self, RestoreReport};
+use crate::error::AppError;
+use crate::services::i18n::ErrorCode;
+use std::path::Path;
+
+#[tauri::command]
+pub fn backup_database(path: String) -> Result<(), AppError> {
+    backup::backup_database(Path::new(&path))
+        .map_err(|e| ErrorCode::BackupFailed { detail: e.to_string() }.into())
+}
+
+#[tauri::command]
+pub fn restore_database(path: String) -> Result<RestoreReport, AppError> {
+    let path = Path::new(&path);
+    let problems = backup::validate_backup(path)
+        .map_err(|e| ErrorCode::InvalidBackup { detail: e.to_string() })?;
+    if !problems.is_empty() {
+        return Err(ErrorCode::InvalidBackup { detail: problems.join("; ") }.into());
+    }
+
+    backup::restore_database(path).map_err(|e| ErrorCode::RestoreFailed { detail: e.to_string() }.into())
+}
@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
 use tauri_plugin_clipboard_manager::ClipboardExt;
@@ -10,7 +11,7 @@ pub struct ClipboardImage {
 }
 
 #[tauri::command]
-pub async fn read_clipboard_image(app: tauri::AppHandle) -> Result<Option<ClipboardImage>, String> {
+pub async fn read_clipboard_image(app: tauri::AppHandle) -> Result<Option<ClipboardImage>, AppError> {
     // Try to read image from clipboard
     match app.clipboard().read_image() {
         Ok(img) => {
@@ -33,8 +34,8 @@ pub async fn read_clipboard_image(app: tauri::AppHandle) -> Result<Option<Clipbo
 }
 
 #[tauri::command]
-pub async fn write_clipboard_text(app: tauri::AppHandle, text: String) -> Result<(), String> {
+pub async fn write_clipboard_text(app: tauri::AppHandle, text: String) -> Result<(), AppError> {
     app.clipboard()
         .write_text(text)
-        .map_err(|e| format!("写入剪贴板失败: {}", e))
+        .map_err(|e| AppError::internal(format!("写入剪贴板失败: {}", e)))
 }
@@ -1,7 +1,13 @@
 use crate::db::model_config::{
-    self, ModelConfig, ModelConfigInput, ModelConfigListItem, ModelConfigUpdate,
+    self, ConfigQueryParams, ModelConfig, ModelConfigInput, ModelConfigListItem, ModelConfigUpdate,
 };
+use crate::db::PaginatedResult;
+use crate::error::AppError;
+use crate::services::budget::{self, BudgetStatus};
+use crate::services::config_health::{self, ConfigHealth};
+use crate::services::i18n::{self, ErrorCode};
 use crate::services::llm;
+use crate::services::provider_cache;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,67 +17,221 @@ pub struct TestConnectionData {
     pub api_url: String,
     pub api_key: String,
     pub model_name: String,
+    pub reasoning_model: Option<bool>,
+    pub organization: Option<String>,
+    pub project: Option<String>,
+    pub skip_tls_verify: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TestConnectionResult {
     pub success: bool,
     pub message: String,
+    pub error_code: Option<ErrorCode>,
+}
+
+impl From<Result<(), ErrorCode>> for TestConnectionResult {
+    fn from(result: Result<(), ErrorCode>) -> Self {
+        let language = i18n::current_language();
+        match result {
+            Ok(()) => Self {
+                success: true,
+                message: i18n::connection_succeeded(&language).to_string(),
+                error_code: None,
+            },
+            Err(code) => Self {
+                success: false,
+                message: code.message(&language),
+                error_code: Some(code),
+            },
+        }
+    }
 }
 
 #[tauri::command]
-pub fn get_all_configs() -> Result<Vec<ModelConfigListItem>, String> {
-    model_config::get_all_configs().map_err(|e| e.to_string())
+pub fn get_all_configs() -> Result<Vec<ModelConfigListItem>, AppError> {
+    model_config::get_all_configs().map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_active_configs() -> Result<Vec<ModelConfigListItem>, String> {
-    model_config::get_active_configs().map_err(|e| e.to_string())
+pub fn query_configs(params: Option<ConfigQueryParams>) -> Result<PaginatedResult<ModelConfigListItem>, AppError> {
+    model_config::query_configs(params.unwrap_or_default()).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_config_by_id(id: i64) -> Result<Option<ModelConfig>, String> {
-    model_config::get_config_by_id(id).map_err(|e| e.to_string())
+pub fn get_active_configs() -> Result<Vec<ModelConfigListItem>, AppError> {
+    model_config::get_active_configs().map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_default_config() -> Result<Option<ModelConfig>, String> {
-    model_config::get_default_config().map_err(|e| e.to_string())
+pub fn get_config_by_id(id: i64) -> Result<Option<ModelConfig>, AppError> {
+    model_config::get_config_by_id(id).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn create_config(input: ModelConfigInput) -> Result<ModelConfigListItem, String> {
-    model_config::create_config(input).map_err(|e| e.to_string())
+pub fn get_default_config() -> Result<Option<ModelConfig>, AppError> {
+    model_config::get_default_config().map_err(AppError::from)
 }
 
+/// Create a config. With `validate` the credentials are tested first and
+/// rejected when the provider refuses them.
 #[tauri::command]
-pub fn update_config(id: i64, input: ModelConfigUpdate) -> Result<Option<ModelConfigListItem>, String> {
-    model_config::update_config(id, input).map_err(|e| e.to_string())
+pub async fn create_config(input: ModelConfigInput, validate: Option<bool>) -> Result<ModelConfigListItem, AppError> {
+    let validated = validate.unwrap_or(false);
+    if validated {
+        llm::test_connection_with_config(
+            &input.provider,
+            &input.api_url,
+            &input.api_key,
+            &input.model_name,
+            input.reasoning_model.unwrap_or(false),
+            input.organization.clone(),
+            input.project.clone(),
+            input.skip_tls_verify.unwrap_or(false),
+        )
+        .await?;
+    }
+
+    let config = model_config::create_config(input).map_err(AppError::from)?;
+    if !validated {
+        return Ok(config);
+    }
+    model_config::set_validation_status(config.id, true).map_err(AppError::from)?;
+    Ok(model_config::get_all_configs()
+        .map_err(AppError::from)?
+        .into_iter()
+        .find(|c| c.id == config.id)
+        .unwrap_or(config))
 }
 
+/// Update a config. With `validate` the resulting credentials are tested first
+/// and the update is rejected when the provider refuses them. Otherwise a
+/// change of provider, endpoint, key or model clears the validation status.
 #[tauri::command]
-pub fn delete_config(id: i64) -> Result<bool, String> {
-    model_config::delete_config(id).map_err(|e| e.to_string())
+pub async fn update_config(
+    id: i64,
+    input: ModelConfigUpdate,
+    validate: Option<bool>,
+) -> Result<Option<ModelConfigListItem>, AppError> {
+    let validated = validate.unwrap_or(false);
+    if validated {
+        let Some(current) = model_config::get_config_by_id(id).map_err(AppError::from)? else {
+            return Ok(None);
+        };
+        llm::test_connection_with_config(
+            input.provider.as_deref().unwrap_or(&current.provider),
+            input.api_url.as_deref().unwrap_or(&current.api_url),
+            input.api_key.as_deref().unwrap_or(&current.api_key),
+            input.model_name.as_deref().unwrap_or(&current.model_name),
+            input.reasoning_model.unwrap_or(current.reasoning_model),
+            input.organization.clone().or(current.organization.clone()),
+            input.project.clone().or(current.project.clone()),
+            input.skip_tls_verify.unwrap_or(current.skip_tls_verify),
+        )
+        .await?;
+    }
+
+    let updated = model_config::update_config(id, input).map_err(AppError::from)?;
+    provider_cache::invalidate(id);
+    let (true, Some(config)) = (validated, updated.clone()) else {
+        return Ok(updated);
+    };
+    model_config::set_validation_status(id, true).map_err(AppError::from)?;
+    Ok(Some(
+        model_config::get_all_configs()
+            .map_err(AppError::from)?
+            .into_iter()
+            .find(|c| c.id == id)
+            .unwrap_or(config),
+    ))
 }
 
 #[tauri::command]
-pub fn set_default_config(id: i64) -> Result<bool, String> {
-    model_config::set_default_config(id).map_err(|e| e.to_string())
+pub fn delete_config(id: i64) -> Result<bool, AppError> {
+    model_config::delete_config(id).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn test_connection(id: i64) -> Result<TestConnectionResult, String> {
-    let (success, message) = llm::test_connection(id).await;
-    Ok(TestConnectionResult { success, message })
+pub fn set_default_config(id: i64) -> Result<bool, AppError> {
+    model_config::set_default_config(id).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn test_connection_with_data(data: TestConnectionData) -> Result<TestConnectionResult, String> {
-    let (success, message) = llm::test_connection_with_config(
+pub async fn test_connection(id: i64) -> Result<TestConnectionResult, AppError> {
+    let result = llm::test_connection(id).await;
+    model_config::set_validation_status(id, result.is_ok()).map_err(AppError::from)?;
+    Ok(result.into())
+}
+
+/// Outcome of re-checking one config's credentials
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValidation {
+    pub config_id: i64,
+    pub config_name: String,
+    #[serde(flatten)]
+    pub result: TestConnectionResult,
+}
+
+/// Test the credentials of every config, enabled or not, and record the outcome
+#[tauri::command]
+pub async fn revalidate_all_configs() -> Result<Vec<ConfigValidation>, AppError> {
+    let configs = model_config::get_all_configs().map_err(AppError::from)?;
+    let mut validations = Vec::with_capacity(configs.len());
+    for config in configs {
+        let result = llm::test_connection(config.id).await;
+        model_config::set_validation_status(config.id, result.is_ok()).map_err(AppError::from)?;
+        validations.push(ConfigValidation {
+            config_id: config.id,
+            config_name: config.name,
+            result: result.into(),
+        });
+    }
+    Ok(validations)
+}
+
+#[tauri::command]
+pub async fn test_connection_with_data(data: TestConnectionData) -> Result<TestConnectionResult, AppError> {
+    let result = llm::test_connection_with_config(
         &data.provider,
         &data.api_url,
         &data.api_key,
         &data.model_name,
+        data.reasoning_model.unwrap_or(false),
+        data.organization,
+        data.project,
+        data.skip_tls_verify.unwrap_or(false),
     ).await;
-    Ok(TestConnectionResult { success, message })
+    Ok(result.into())
+}
+
+/// Health of the active configs from the last background check
+#[tauri::command]
+pub fn get_provider_status() -> Vec<ConfigHealth> {
+    config_health::get_cached_health()
+}
+
+/// Check the active configs now instead of waiting for the next round
+#[tauri::command]
+pub async fn refresh_provider_status() -> Result<Vec<ConfigHealth>, AppError> {
+    config_health::check_active_configs().await.map_err(AppError::from)
+}
+
+/// Models available to a config's key, cached for a day
+#[tauri::command]
+pub async fn list_models(config_id: i64) -> Result<Vec<String>, AppError> {
+    provider_cache::list_models(config_id, false).await.map_err(AppError::from)
+}
+
+/// Fetch the model list from the provider again, replacing the cached one
+#[tauri::command]
+pub async fn refresh_models(config_id: i64) -> Result<Vec<String>, AppError> {
+    provider_cache::list_models(config_id, true).await.map_err(AppError::from)
+}
+
+/// This month's estimated spend of every config with a budget
+#[tauri::command]
+pub fn get_budget_status() -> Result<Vec<BudgetStatus>, AppError> {
+    budget::all_budget_statuses().map_err(AppError::from)
 }
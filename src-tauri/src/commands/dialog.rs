@@ -1,7 +1,16 @@
+use crate::error::AppError;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tauri_plugin_dialog::DialogExt;
+use crate::services::i18n::ErrorCode;
+use crate::services::image;
+
+/// Largest image accepted when dropped onto the window
+const MAX_DROPPED_IMAGE_MB: usize = 10;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -9,6 +18,26 @@ pub struct SelectedImage {
     pub base64: String,
     pub mime_type: String,
     pub file_name: String,
+    /// Absolute path, recorded in history so the file can be revealed later
+    pub file_path: String,
+}
+
+/// Save location picked in the dialog. The renderer only passes the token
+/// back, so it can't make the backend write anywhere else.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectedSavePath {
+    pub token: String,
+    /// For display
+    pub path: String,
+}
+
+/// Paths picked with `select_save_path`, by token
+pub type SavePathsHandle = Arc<parking_lot::Mutex<HashMap<String, PathBuf>>>;
+
+/// The path picked for `token`, if any
+pub fn save_path(paths: &SavePathsHandle, token: &str) -> Option<PathBuf> {
+    paths.lock().get(token).cloned()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,7 +55,7 @@ pub struct FileFilter {
 }
 
 #[tauri::command]
-pub async fn select_image(app: tauri::AppHandle) -> Result<Option<SelectedImage>, String> {
+pub async fn select_image(app: tauri::AppHandle) -> Result<Option<SelectedImage>, AppError> {
     let file_path = app
         .dialog()
         .file()
@@ -36,42 +65,91 @@ pub async fn select_image(app: tauri::AppHandle) -> Result<Option<SelectedImage>
     match file_path {
         Some(file_path) => {
             // FilePath in Tauri 2 can be converted to PathBuf
-            let path = file_path.into_path().map_err(|e| format!("无效路径: {}", e))?;
-            let file_name = path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("image")
-                .to_string();
-
-            let data = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
-            let base64 = BASE64.encode(&data);
-
-            let ext = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("jpg")
-                .to_lowercase();
-
-            let mime_type = match ext.as_str() {
-                "png" => "image/png",
-                "gif" => "image/gif",
-                "webp" => "image/webp",
-                _ => "image/jpeg",
-            }
-            .to_string();
-
-            Ok(Some(SelectedImage {
-                base64,
-                mime_type,
-                file_name,
-            }))
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            read_selected_image(&path).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Read an image dropped onto the window from the file manager. The path comes
+/// from the renderer, so only image files up to the size limit are read.
+#[tauri::command]
+pub async fn read_image_file(path: String) -> Result<SelectedImage, AppError> {
+    let path = Path::new(&path);
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if !image::is_valid_format(name) || !path.is_file() {
+        return Err(ErrorCode::UnsupportedImageFile { name: name.to_string() }.into());
+    }
+    let size = fs::metadata(path).map_err(|e| AppError::internal(format!("读取文件失败: {}", e)))?.len();
+    if size > (MAX_DROPPED_IMAGE_MB * 1024 * 1024) as u64 {
+        return Err(ErrorCode::ImageTooLarge { max_mb: MAX_DROPPED_IMAGE_MB }.into());
+    }
+    read_selected_image(path)
+}
+
+fn read_selected_image(path: &Path) -> Result<SelectedImage, AppError> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("image")
+        .to_string();
+
+    let data = fs::read(path).map_err(|e| AppError::internal(format!("读取文件失败: {}", e)))?;
+    let base64 = BASE64.encode(&data);
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("jpg")
+        .to_lowercase();
+
+    let mime_type = match ext.as_str() {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    }
+    .to_string();
+
+    let file_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    Ok(SelectedImage {
+        base64,
+        mime_type,
+        file_name,
+        file_path: file_path.display().to_string(),
+    })
+}
+
+/// Ask where to save a file without writing it yet, e.g. to stream a
+/// recognition into it as it arrives
+#[tauri::command]
+pub async fn select_save_path(
+    app: tauri::AppHandle,
+    paths: tauri::State<'_, SavePathsHandle>,
+    default_name: String,
+    filters: Vec<FileFilter>,
+) -> Result<Option<SelectedSavePath>, AppError> {
+    let mut dialog = app.dialog().file().set_file_name(&default_name);
+    for filter in &filters {
+        let extensions: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
+        dialog = dialog.add_filter(&filter.name, &extensions);
+    }
+
+    match dialog.blocking_save_file() {
+        Some(file_path) => {
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            let token = uuid::Uuid::new_v4().to_string();
+            let selected = SelectedSavePath { token: token.clone(), path: path.to_string_lossy().into_owned() };
+            paths.lock().insert(token, path);
+            Ok(Some(selected))
         }
         None => Ok(None),
     }
 }
 
 #[tauri::command]
-pub async fn save_file(app: tauri::AppHandle, options: SaveFileOptions) -> Result<bool, String> {
+pub async fn save_file(app: tauri::AppHandle, options: SaveFileOptions) -> Result<bool, AppError> {
     let mut dialog = app.dialog().file();
 
     // Add filters
@@ -87,8 +165,8 @@ pub async fn save_file(app: tauri::AppHandle, options: SaveFileOptions) -> Resul
 
     match file_path {
         Some(file_path) => {
-            let path = file_path.into_path().map_err(|e| format!("无效路径: {}", e))?;
-            fs::write(&path, &options.content).map_err(|e| format!("保存文件失败: {}", e))?;
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            fs::write(&path, &options.content).map_err(|e| AppError::internal(format!("保存文件失败: {}", e)))?;
             Ok(true)
         }
         None => Ok(false),
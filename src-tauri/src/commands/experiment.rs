@@ -0,0 +1,88 @@
+use crate::db::ab_test::{self, AbTest};
+use crate::db::experiment::{self, Experiment, ExperimentInput};
+use crate::error::AppError;
+use crate::services::ab_test::{self as ab_runner, AbTestRequest};
+use crate::services::experiment::{self as runner, ExperimentReport};
+use crate::services::i18n::ErrorCode;
+use tauri::Emitter;
+
+#[tauri::command]
+pub fn get_all_experiments() -> Result<Vec<Experiment>, AppError> {
+    experiment::get_all_experiments().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn create_experiment(input: ExperimentInput) -> Result<Experiment, AppError> {
+    if input.image_paths.is_empty() {
+        return Err(ErrorCode::NoImagesSelected.into());
+    }
+    experiment::create_experiment(input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_experiment(id: i64) -> Result<bool, AppError> {
+    experiment::delete_experiment(id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn run_experiment(app: tauri::AppHandle, id: i64) -> Result<ExperimentReport, AppError> {
+    runner::run_experiment(id, |progress| {
+        if let Err(e) = app.emit("experiment-progress", progress) {
+            eprintln!("Failed to emit experiment progress: {}", e);
+        }
+    })
+    .await
+}
+
+/// Stop sending further runs of a running experiment; runs already sent finish
+#[tauri::command]
+pub fn pause_experiment(id: i64) -> Result<bool, AppError> {
+    runner::pause_experiment(id)
+}
+
+/// Continue a paused experiment, retrying its failed runs
+#[tauri::command]
+pub async fn resume_experiment(app: tauri::AppHandle, id: i64) -> Result<ExperimentReport, AppError> {
+    runner::resume_experiment(id, |progress| {
+        if let Err(e) = app.emit("experiment-progress", progress) {
+            eprintln!("Failed to emit experiment progress: {}", e);
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn rate_experiment_run(run_id: i64, rating: Option<i32>) -> Result<bool, AppError> {
+    if let Some(r) = rating {
+        if !(1..=5).contains(&r) {
+            return Err(ErrorCode::InvalidRating.into());
+        }
+    }
+    experiment::rate_experiment_run(run_id, rating).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_experiment_report(id: i64) -> Result<ExperimentReport, AppError> {
+    runner::build_report(id)
+}
+
+/// Run 2-4 prompt variants on one image with one config
+#[tauri::command]
+pub async fn ab_test(request: AbTestRequest) -> Result<AbTest, AppError> {
+    ab_runner::run_ab_test(request).await
+}
+
+#[tauri::command]
+pub fn get_all_ab_tests() -> Result<Vec<AbTest>, AppError> {
+    ab_test::get_all_ab_tests().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_ab_test(id: i64) -> Result<Option<AbTest>, AppError> {
+    ab_test::get_ab_test(id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_ab_test(id: i64) -> Result<bool, AppError> {
+    ab_test::delete_ab_test(id).map_err(AppError::from)
+}
@@ -0,0 +1,37 @@
+use crate::db::extraction::{self, ExtractionQueryParams, ExtractionRecord};
+use crate::db::PaginatedResult;
+use crate::error::AppError;
+use crate::services::extraction::{to_vcard, BusinessCard};
+use std::fs;
+use tauri_plugin_dialog::DialogExt;
+
+#[tauri::command]
+pub fn query_extractions(params: ExtractionQueryParams) -> Result<PaginatedResult<ExtractionRecord>, AppError> {
+    extraction::query_extractions(params).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_extraction_by_history(history_id: i64) -> Result<Option<ExtractionRecord>, AppError> {
+    extraction::get_extraction_by_history(history_id).map_err(AppError::from)
+}
+
+/// Save a business card as a .vcf file through the save dialog
+#[tauri::command]
+pub async fn save_vcard(app: tauri::AppHandle, card: BusinessCard) -> Result<bool, AppError> {
+    let default_name = card.name.as_deref().or(card.company.as_deref()).unwrap_or("contact");
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("vCard", &["vcf"])
+        .set_file_name(format!("{}.vcf", default_name))
+        .blocking_save_file();
+
+    match file_path {
+        Some(file_path) => {
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            fs::write(&path, to_vcard(&card)).map_err(|e| AppError::internal(format!("保存文件失败: {}", e)))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,22 @@
+use crate::db::glossary::{self, GlossaryEntry};
+use crate::error::AppError;
+
+#[tauri::command]
+pub fn get_glossary() -> Result<Vec<GlossaryEntry>, AppError> {
+    glossary::get_entries().map_err(AppError::from)
+}
+
+/// Add a term or change its preferred spelling
+#[tauri::command]
+pub fn save_glossary_entry(term: String, preferred: String) -> Result<GlossaryEntry, AppError> {
+    let (term, preferred) = (term.trim(), preferred.trim());
+    if term.is_empty() || preferred.is_empty() {
+        return Err(AppError::Validation { code: None, message: "Term and preferred spelling must not be empty".to_string() });
+    }
+    glossary::save_entry(term, preferred).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_glossary_entry(id: i64) -> Result<bool, AppError> {
+    glossary::delete_entry(id).map_err(AppError::from)
+}
@@ -1,35 +1,219 @@
 use crate::db::history::{
-    self, HistoryPaginatedResult, HistoryQueryParams, HistoryRecord,
+    self, HistoryPage, HistoryPaginatedResult, HistoryQueryParams, HistoryRecord, IntegrityReport, ReviewBatch,
+    SimilarHistory,
 };
+use crate::db::stats::{self, HistoryStatsRow, StatsGranularity};
+use crate::error::AppError;
+use crate::services::image::{load_image_file, perceptual_hash, select_frame};
+use crate::services::history_bundle::{self, ImportReport};
+use crate::services::image_store;
+use crate::services::file_manager::{self, DragFile};
+use crate::services::notebook;
+use crate::services::rerun::{self, RerunReport};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::Emitter;
+use tauri_plugin_dialog::DialogExt;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryImage {
+    pub base64: String,
+    pub mime_type: String,
+}
 
 #[tauri::command]
-pub fn get_history_records(params: Option<HistoryQueryParams>) -> Result<HistoryPaginatedResult, String> {
+pub fn get_history_records(params: Option<HistoryQueryParams>) -> Result<HistoryPage, AppError> {
     let params = params.unwrap_or_default();
-    history::get_history_records(params).map_err(|e| e.to_string())
+    history::get_history_page(params).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_history_by_id(id: i64) -> Result<Option<HistoryRecord>, AppError> {
+    history::get_history_by_id(id).map_err(AppError::from)
+}
+
+/// Load the full-resolution original of a history record from the image store.
+/// Returns None when the record has no stored image.
+#[tauri::command]
+pub fn get_history_image(id: i64) -> Result<Option<HistoryImage>, AppError> {
+    let Some(file_name) = history::get_history_by_id(id)
+        .map_err(AppError::from)?
+        .and_then(|record| record.image_file)
+    else {
+        return Ok(None);
+    };
+
+    let (base64, mime_type) =
+        load_image_file(&image_store::image_file_path(&file_name)?).map_err(AppError::image)?;
+    Ok(Some(HistoryImage { base64, mime_type }))
+}
+
+/// Batches with records in the review queue and their counts per status
+#[tauri::command]
+pub fn get_review_batches() -> Result<Vec<ReviewBatch>, AppError> {
+    history::get_review_batches().map_err(AppError::from)
+}
+
+/// Records of a batch still waiting for review, newest first
+#[tauri::command]
+pub fn list_pending_reviews(
+    batch_id: String,
+    page: Option<i32>,
+    page_size: Option<i32>,
+) -> Result<HistoryPaginatedResult, AppError> {
+    let params = HistoryQueryParams {
+        page,
+        page_size,
+        batch_id: Some(batch_id),
+        review_status: Some("pending".to_string()),
+        ..Default::default()
+    };
+    history::get_history_records(params).map_err(AppError::from)
+}
+
+/// Set the review status of a record, storing the corrected text apart from the result
+#[tauri::command]
+pub fn review_history(id: i64, status: String, edited_result: Option<String>) -> Result<bool, AppError> {
+    if !history::REVIEW_STATUSES.contains(&status.as_str()) {
+        return Err(AppError::Validation { code: None, message: format!("Invalid review status: {}", status) });
+    }
+    history::review_record(id, &status, edited_result).map_err(AppError::from)
+}
+
+/// Approved records with their corrected text as the result, of one batch or all
+#[tauri::command]
+pub fn export_approved_history(batch_id: Option<String>) -> Result<Vec<HistoryRecord>, AppError> {
+    history::export_approved(batch_id).map_err(AppError::from)
+}
+
+/// Recognize the images of the selected records again with another config,
+/// emitting `rerun-progress` per record. The new records link back to their
+/// originals and wait in the review queue of the returned batch.
+#[tauri::command]
+pub async fn rerun_many(app: tauri::AppHandle, ids: Vec<i64>, config_id: i64) -> Result<RerunReport, AppError> {
+    rerun::rerun_many(&ids, config_id, |progress| {
+        if let Err(e) = app.emit("rerun-progress", progress) {
+            eprintln!("Failed to emit rerun progress: {}", e);
+        }
+    })
+    .await
+}
+
+/// Clear the low-confidence review flag of a record
+#[tauri::command]
+pub fn mark_history_reviewed(id: i64) -> Result<bool, AppError> {
+    history::mark_reviewed(id).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_history_by_id(id: i64) -> Result<Option<HistoryRecord>, String> {
-    history::get_history_by_id(id).map_err(|e| e.to_string())
+pub fn delete_history(id: i64) -> Result<bool, AppError> {
+    history::delete_history_record(id).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn delete_history(id: i64) -> Result<bool, String> {
-    history::delete_history_record(id).map_err(|e| e.to_string())
+pub fn delete_multiple_history(ids: Vec<i64>) -> Result<usize, AppError> {
+    history::delete_history_records(&ids).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn delete_multiple_history(ids: Vec<i64>) -> Result<usize, String> {
-    history::delete_history_records(&ids).map_err(|e| e.to_string())
+pub fn clear_all_history() -> Result<usize, AppError> {
+    history::clear_all_history().map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn clear_all_history() -> Result<usize, String> {
-    history::clear_all_history().map_err(|e| e.to_string())
+pub fn export_history(params: Option<HistoryQueryParams>) -> Result<Vec<HistoryRecord>, AppError> {
+    let params = params.unwrap_or_default();
+    history::export_history(params).map_err(AppError::from)
 }
 
+/// Save the matching history with its original images as a ZIP bundle through
+/// the save dialog
 #[tauri::command]
-pub fn export_history(params: Option<HistoryQueryParams>) -> Result<Vec<HistoryRecord>, String> {
+pub async fn export_history_bundle(
+    app: tauri::AppHandle,
+    params: Option<HistoryQueryParams>,
+    default_name: String,
+) -> Result<bool, AppError> {
     let params = params.unwrap_or_default();
-    history::export_history(params).map_err(|e| e.to_string())
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter("ZIP", &["zip"])
+        .set_file_name(format!("{}.zip", default_name))
+        .blocking_save_file();
+
+    match file_path {
+        Some(file_path) => {
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            // Written straight to the file, a bundle of a large history doesn't fit in memory
+            tokio::task::spawn_blocking(move || history_bundle::export_history_bundle(params, &path))
+                .await
+                .map_err(AppError::internal)??;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Merge history from a ZIP bundle or JSON export, e.g. from another machine.
+/// `config_map` maps config names in the file to local config ids.
+#[tauri::command]
+pub async fn import_history(path: String, config_map: Option<HashMap<String, i64>>) -> Result<ImportReport, AppError> {
+    let config_map = config_map.unwrap_or_default();
+    tokio::task::spawn_blocking(move || history_bundle::import_history_bundle(&PathBuf::from(path), &config_map))
+        .await
+        .map_err(AppError::internal)?
+}
+
+#[tauri::command]
+pub fn verify_history_integrity(clear_dangling: Option<bool>) -> Result<IntegrityReport, AppError> {
+    history::verify_image_integrity(clear_dangling.unwrap_or(false)).map_err(AppError::from)
+}
+
+/// Turn at-rest encryption of prompts and results on or off, converting
+/// existing records; returns how many were converted
+#[tauri::command]
+pub fn set_history_encryption(enabled: bool) -> Result<usize, AppError> {
+    history::set_encryption(enabled).map_err(AppError::from)
+}
+
+/// Usage per day, week or month and config, for charts
+#[tauri::command]
+pub fn get_history_stats(granularity: StatsGranularity) -> Result<Vec<HistoryStatsRow>, AppError> {
+    stats::get_history_stats(granularity).map_err(AppError::from)
+}
+
+/// Bits two perceptual hashes may differ by and still count as the same image
+const DEFAULT_SIMILAR_DISTANCE: u32 = 8;
+const SIMILAR_LIMIT: usize = 5;
+
+/// Earlier recognitions of the same or a near-identical image, closest first
+#[tauri::command]
+pub fn find_similar_history(image_data: String, max_distance: Option<u32>) -> Result<Vec<SimilarHistory>, AppError> {
+    // Hash the frame that recognition would send for animated images
+    let (image_data, _) = select_frame(&image_data, None).map_err(AppError::image)?;
+    let hash = perceptual_hash(&image_data).map_err(AppError::image)?;
+    history::find_similar_history(hash, max_distance.unwrap_or(DEFAULT_SIMILAR_DISTANCE), SIMILAR_LIMIT)
+        .map_err(AppError::from)
+}
+
+/// Append a record to the notebook file from settings; returns the notebook path
+#[tauri::command]
+pub fn append_to_notebook(history_id: i64) -> Result<String, AppError> {
+    notebook::append_to_notebook(history_id)
+}
+
+/// Write a record's result to a temp file (`md`, `txt` or `csv`) and return
+/// its path, for the frontend to drag it out of the window
+#[tauri::command]
+pub fn create_drag_file(history_id: i64, format: String) -> Result<DragFile, AppError> {
+    file_manager::create_drag_file(history_id, &format)
+}
+
+/// Open the file manager at the file a record's image was loaded from
+#[tauri::command]
+pub fn reveal_in_folder(history_id: i64) -> Result<(), AppError> {
+    file_manager::reveal_history_image(history_id)
 }
@@ -0,0 +1,99 @@
+use crate::error::AppError;
+use crate::services::i18n::ErrorCode;
+use crate::services::lock::{self, LockState};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+
+pub type LockStateHandle = Arc<Mutex<LockState>>;
+
+/// Commands that keep working while the app is locked
+const ALWAYS_ALLOWED: &[&str] = &["get_lock_status", "unlock_app", "lock_app"];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockStatus {
+    /// A passcode is set
+    pub enabled: bool,
+    pub locked: bool,
+}
+
+/// Whether `command` is refused. Allowed commands count as activity and
+/// postpone the idle lock; status polling doesn't.
+pub fn refuses(state: &LockStateHandle, command: &str) -> bool {
+    if ALWAYS_ALLOWED.contains(&command) {
+        return false;
+    }
+    let mut state = state.lock();
+    if !state.enabled() {
+        return false;
+    }
+    let now = Instant::now();
+    if state.is_locked(lock::idle_minutes(), now) {
+        return true;
+    }
+    state.touch(now);
+    false
+}
+
+/// Wrap the invoke handler so locked commands are rejected before they run
+pub fn guard<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let refused = invoke
+            .message
+            .webview()
+            .try_state::<LockStateHandle>()
+            .is_some_and(|state| refuses(&state, invoke.message.command()));
+        if refused {
+            invoke.resolver.reject(AppError::from(ErrorCode::AppLocked));
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+#[tauri::command]
+pub fn get_lock_status(state: tauri::State<'_, LockStateHandle>) -> LockStatus {
+    let mut state = state.lock();
+    LockStatus {
+        enabled: state.enabled(),
+        locked: state.is_locked(lock::idle_minutes(), Instant::now()),
+    }
+}
+
+#[tauri::command]
+pub fn unlock_app(state: tauri::State<'_, LockStateHandle>, passcode: String) -> Result<(), AppError> {
+    if !lock::verify(&passcode)? {
+        return Err(ErrorCode::WrongPasscode.into());
+    }
+    state.lock().unlock(Instant::now());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn lock_app(state: tauri::State<'_, LockStateHandle>) {
+    state.lock().lock();
+}
+
+/// Set, change or (with no `passcode`) remove the passcode; `current` is
+/// required while one is set
+#[tauri::command]
+pub fn set_passcode(
+    state: tauri::State<'_, LockStateHandle>,
+    current: Option<String>,
+    passcode: Option<String>,
+) -> Result<LockStatus, AppError> {
+    lock::set_passcode(current.as_deref(), passcode.as_deref())?;
+
+    let mut state = state.lock();
+    state.set_enabled(passcode.is_some(), Instant::now());
+    Ok(LockStatus {
+        enabled: state.enabled(),
+        locked: false,
+    })
+}
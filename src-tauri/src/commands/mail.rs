@@ -0,0 +1,20 @@
+use crate::error::AppError;
+use crate::services::mail;
+
+/// Email a history record's result with its image attached; `to` overrides the
+/// default recipients. Returns the addresses it was sent to.
+#[tauri::command]
+pub async fn send_result_email(history_id: i64, to: Option<String>) -> Result<Vec<String>, AppError> {
+    mail::send_result(history_id, to.as_deref()).await
+}
+
+/// Store the SMTP password; empty removes it
+#[tauri::command]
+pub fn set_smtp_password(password: String) -> Result<(), AppError> {
+    mail::set_password(&password).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn has_smtp_password() -> Result<bool, AppError> {
+    mail::has_password().map_err(AppError::from)
+}
@@ -0,0 +1,7 @@
+use crate::services::math::{self, FormulaCheck};
+
+/// Validate the LaTeX formulas in a text, optionally converting valid ones to MathML
+#[tauri::command]
+pub fn check_formulas(content: String, mathml: Option<bool>) -> Vec<FormulaCheck> {
+    math::check_formulas(&content, mathml.unwrap_or(false))
+}
@@ -5,3 +5,24 @@ pub mod settings;
 pub mod recognition;
 pub mod dialog;
 pub mod clipboard;
+pub mod experiment;
+pub mod backup;
+pub mod workspace;
+pub mod recipe;
+pub mod table;
+pub mod math;
+pub mod preset;
+pub mod updater;
+pub mod window;
+pub mod prompt_history;
+pub mod profile;
+pub mod lock;
+pub mod extraction;
+pub mod speech;
+pub mod annotation;
+pub mod sync;
+pub mod glossary;
+pub mod postprocess;
+pub mod share;
+pub mod mail;
+pub mod notion;
@@ -0,0 +1,19 @@
+use crate::error::AppError;
+use crate::services::notion;
+
+/// Add a history record's result to the Notion database and return the page link
+#[tauri::command]
+pub async fn export_history_to_notion(history_id: i64) -> Result<String, AppError> {
+    notion::export_history(history_id).await
+}
+
+/// Store the Notion integration token; empty removes it
+#[tauri::command]
+pub fn set_notion_token(token: String) -> Result<(), AppError> {
+    notion::set_token(token.trim()).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn has_notion_token() -> Result<bool, AppError> {
+    notion::has_token().map_err(AppError::from)
+}
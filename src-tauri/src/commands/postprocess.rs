@@ -0,0 +1,38 @@
+use crate::db::postprocess_rule::{self, PostprocessRule, PostprocessRuleInput};
+use crate::error::AppError;
+use crate::services::postprocess::{self, RuleTestResult};
+
+#[tauri::command]
+pub fn get_postprocess_rules() -> Result<Vec<PostprocessRule>, AppError> {
+    postprocess_rule::get_rules().map_err(AppError::from)
+}
+
+/// Fails with `InvalidRegex` when the pattern doesn't compile
+#[tauri::command]
+pub fn create_postprocess_rule(input: PostprocessRuleInput) -> Result<PostprocessRule, AppError> {
+    postprocess::compile(&input.pattern)?;
+    postprocess_rule::create_rule(input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn update_postprocess_rule(id: i64, input: PostprocessRuleInput) -> Result<Option<PostprocessRule>, AppError> {
+    postprocess::compile(&input.pattern)?;
+    postprocess_rule::update_rule(id, input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_postprocess_rule(id: i64) -> Result<bool, AppError> {
+    postprocess_rule::delete_rule(id).map_err(AppError::from)
+}
+
+/// Apply the rules in the order of `ids`
+#[tauri::command]
+pub fn reorder_postprocess_rules(ids: Vec<i64>) -> Result<(), AppError> {
+    postprocess_rule::reorder_rules(&ids).map_err(AppError::from)
+}
+
+/// Try a pattern and replacement on sample text without saving them
+#[tauri::command]
+pub fn test_postprocess_rule(pattern: String, replacement: String, sample: String) -> Result<RuleTestResult, AppError> {
+    postprocess::test_rule(&pattern, &replacement, &sample).map_err(AppError::from)
+}
@@ -0,0 +1,32 @@
+use crate::db::preset::{self, Preset, PresetInput};
+use crate::error::AppError;
+
+#[tauri::command]
+pub fn get_all_presets() -> Result<Vec<Preset>, AppError> {
+    preset::get_all_presets().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_default_preset() -> Result<Option<Preset>, AppError> {
+    preset::get_default_preset().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn create_preset(input: PresetInput) -> Result<Preset, AppError> {
+    preset::create_preset(input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn update_preset(id: i64, input: PresetInput) -> Result<Option<Preset>, AppError> {
+    preset::update_preset(id, input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_preset(id: i64) -> Result<bool, AppError> {
+    preset::delete_preset(id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn set_default_preset(id: i64) -> Result<bool, AppError> {
+    preset::set_default_preset(id).map_err(AppError::from)
+}
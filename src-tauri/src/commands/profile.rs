@@ -0,0 +1,47 @@
+use crate::commands::settings::emit_all_changed;
+use crate::db::profile::{self, Profile};
+use crate::error::AppError;
+use crate::services::i18n::ErrorCode;
+
+#[tauri::command]
+pub fn get_all_profiles() -> Result<Vec<Profile>, AppError> {
+    profile::get_all_profiles().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_active_profile() -> Result<Option<Profile>, AppError> {
+    profile::get_active_profile().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn create_profile(name: String) -> Result<Profile, AppError> {
+    profile::create_profile(&name).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn rename_profile(id: i64, name: String) -> Result<Option<Profile>, AppError> {
+    profile::rename_profile(id, &name).map_err(AppError::from)
+}
+
+/// Only empty profiles can be deleted, their configs would otherwise become shared
+#[tauri::command]
+pub fn delete_profile(id: i64) -> Result<bool, AppError> {
+    let count = profile::count_owned(id).map_err(AppError::from)?;
+    if count > 0 {
+        return Err(ErrorCode::ProfileNotEmpty { count }.into());
+    }
+    profile::delete_profile(id).map_err(AppError::from)
+}
+
+/// Switch to a profile; configs, templates and settings of other profiles are
+/// hidden until switching back. Pass no id to see everything again.
+#[tauri::command]
+pub fn set_active_profile(app: tauri::AppHandle, id: Option<i64>) -> Result<Option<Profile>, AppError> {
+    let profile = profile::set_active_profile(id).map_err(AppError::from)?;
+    if id.is_some() && profile.is_none() {
+        return Err(ErrorCode::ProfileNotFound.into());
+    }
+    // Another profile's overrides apply now
+    emit_all_changed(&app, None, None);
+    Ok(profile)
+}
@@ -0,0 +1,17 @@
+use crate::db::prompt_history::{self, PromptHistoryEntry};
+use crate::error::AppError;
+
+#[tauri::command]
+pub fn get_prompt_suggestions(prefix: String, limit: Option<i32>) -> Result<Vec<PromptHistoryEntry>, AppError> {
+    prompt_history::get_prompt_suggestions(&prefix, limit).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_prompt_history(id: i64) -> Result<bool, AppError> {
+    prompt_history::delete_prompt_history(id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn clear_prompt_history() -> Result<usize, AppError> {
+    prompt_history::clear_prompt_history().map_err(AppError::from)
+}
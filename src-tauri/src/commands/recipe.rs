@@ -0,0 +1,14 @@
+use crate::error::AppError;
+use crate::services::recipe::{self, Recipe, RecipeRunReport};
+use std::path::PathBuf;
+
+/// Describe a workspace's pipeline as a recipe; the frontend saves it as JSON
+#[tauri::command]
+pub fn export_recipe(workspace_id: i64) -> Result<Recipe, AppError> {
+    recipe::export_recipe(workspace_id)
+}
+
+#[tauri::command]
+pub async fn run_recipe(path: String, inputs: Vec<String>) -> Result<RecipeRunReport, AppError> {
+    recipe::run_recipe(&PathBuf::from(path), &inputs).await
+}
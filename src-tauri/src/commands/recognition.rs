@@ -1,9 +1,19 @@
-use crate::db::settings;
-use crate::services::image::process_image_for_api;
+use crate::db::{preset, prompt_history, prompt_template, settings, template_usage};
+use crate::error::AppError;
+use crate::commands::dialog::{self, SavePathsHandle};
+use crate::services::barcode::{self, DecodedCode};
+use crate::services::i18n::ErrorCode;
+use crate::services::image::{self, process_image_for_api, AnimationFrame};
 use crate::services::llm::{self, RecognitionOptions, RecognitionResult};
+use crate::services::progress::{self, Observer, Stage, StageEvent};
+use crate::services::provider::ImagePart;
+use crate::services::stitch::{self, StitchedImage};
 use serde::{Deserialize, Serialize};
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
+use std::fs::File;
+use std::io::{Seek, Write};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +24,19 @@ pub struct RecognitionRequest {
     pub image_mime_type: String,
     pub prompt: String,
     pub options: Option<RecognitionOptions>,
+    /// Template the prompt came from, for usage analytics
+    pub template_id: Option<i64>,
+}
+
+/// Two images asked about in one request, like "what changed between these screenshots?"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareImagesRequest {
+    pub config_id: i64,
+    pub first_image: String,
+    pub second_image: String,
+    pub prompt: String,
+    pub options: Option<RecognitionOptions>,
 }
 
 // Global state to track active recognition
@@ -36,22 +59,127 @@ pub async fn recognize(
     window: tauri::Window,
     state: tauri::State<'_, RecognitionStateHandle>,
     data: RecognitionRequest,
-) -> Result<RecognitionResult, String> {
+) -> Result<RecognitionResult, AppError> {
+    run_recognition(window, state, data).await
+}
+
+/// Recognize with a saved preset, or the default preset when no id is given
+#[tauri::command]
+pub async fn recognize_with_preset(
+    window: tauri::Window,
+    state: tauri::State<'_, RecognitionStateHandle>,
+    preset_id: Option<i64>,
+    image_data: String,
+    image_mime_type: String,
+) -> Result<RecognitionResult, AppError> {
+    let preset = match preset_id {
+        Some(id) => preset::get_preset_by_id(id),
+        None => preset::get_default_preset(),
+    }
+    .map_err(AppError::from)?
+    .ok_or(ErrorCode::PresetNotFound)?;
+
+    let template = match preset.template_id {
+        Some(id) => prompt_template::get_template_by_id(id)
+            .map_err(AppError::from)?
+            .ok_or(ErrorCode::TemplateNotFound { id })?,
+        None => prompt_template::get_default_template()
+            .map_err(AppError::from)?
+            .ok_or(ErrorCode::TemplateNotFound { id: 0 })?,
+    };
+    let _ = prompt_template::increment_use_count(template.id);
+
+    let data = RecognitionRequest {
+        config_id: preset.config_id,
+        image_data,
+        image_mime_type,
+        prompt: template.content,
+        options: Some(preset.options),
+        template_id: Some(template.id),
+    };
+    run_recognition(window, state, data).await
+}
+
+async fn run_recognition(
+    window: tauri::Window,
+    state: tauri::State<'_, RecognitionStateHandle>,
+    data: RecognitionRequest,
+) -> Result<RecognitionResult, AppError> {
+    // Tee the stream to the chosen file unbuffered, so nothing received is lost on a crash
+    let stream_file = match data.options.as_ref().and_then(|o| o.stream_to_file.as_deref()) {
+        Some(token) => Some(
+            dialog::save_path(&window.state::<SavePathsHandle>(), token)
+                .ok_or_else(|| AppError::internal("未选择写入的文件"))?,
+        ),
+        None => None,
+    };
+    let tee = match &stream_file {
+        Some(path) => Some(Arc::new(parking_lot::Mutex::new(
+            File::create(path).map_err(|e| AppError::internal(format!("创建文件失败: {}", e)))?,
+        ))),
+        None => None,
+    };
+
+    // Report what is happening instead of a generic spinner; large images take
+    // a while to upload before the first token arrives
+    let (stage_window, upload_window) = (window.clone(), window.clone());
+    let stage_tee = tee.clone();
+    let observer = Observer {
+        on_stage: Arc::new(move |event| {
+            // Every request starts uploading anew; drop what an earlier attempt streamed
+            if let (Some(file), Stage::Uploading) = (&stage_tee, event.stage) {
+                if let Err(e) = restart_file(&mut file.lock()) {
+                    eprintln!("Failed to truncate stream file: {}", e);
+                }
+            }
+            if let Err(e) = stage_window.emit_to(stage_window.label(), "recognition-stage", event) {
+                eprintln!("Failed to emit recognition stage: {}", e);
+            }
+        }),
+        on_upload: Arc::new(move |progress| {
+            if let Err(e) = upload_window.emit_to(upload_window.label(), "recognition-upload-progress", progress) {
+                eprintln!("Failed to emit upload progress: {}", e);
+            }
+        }),
+    };
+
     // Get settings to check compression options
-    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let app_settings = settings::get_all_settings().map_err(AppError::from)?;
     let auto_compress = app_settings.auto_compress;
     let threshold_bytes = (app_settings.compress_threshold as usize) * 1024;
 
+    // Send a single frame of animated images
+    let frame_index = data.options.as_ref().and_then(|o| o.frame_index);
+    let frame = image::extract_frame(&data.image_data, frame_index)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })?;
+    let was_extracted = frame.is_some();
+    let image_data = frame.map_or_else(|| data.image_data.clone(), |f| f.base64);
+
     // Process image (compress if needed)
-    let processed = process_image_for_api(&data.image_data, auto_compress, threshold_bytes)
-        .map_err(|e| format!("图片处理失败: {}", e))?;
+    (observer.on_stage)(StageEvent { stage: Stage::Compressing, queue_position: None });
+    let processed = process_image_for_api(&image_data, auto_compress, threshold_bytes)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })?;
+
+    if let Err(e) = prompt_history::record_prompt(&data.prompt) {
+        eprintln!("Failed to record prompt history: {}", e);
+    }
 
     let prompt_preview: String = data.prompt.chars().take(50).collect();
     println!("[Recognition Command] Received prompt: {}", prompt_preview);
 
+    // Content streamed so far, kept in case the recognition is cancelled
+    let streamed = Arc::new(parking_lot::Mutex::new(String::new()));
+    let streamed_clone = streamed.clone();
     let window_clone = window.clone();
     let callback: Option<Box<dyn Fn(String) + Send + Sync>> = Some(Box::new(move |chunk| {
-        if let Err(e) = window_clone.emit("recognition-stream", chunk) {
+        streamed_clone.lock().push_str(&chunk);
+        if let Some(file) = &tee {
+            if let Err(e) = file.lock().write_all(chunk.as_bytes()) {
+                eprintln!("Failed to write stream to file: {}", e);
+            }
+        }
+        // Only the requesting window renders this stream
+        if let Err(e) = window_clone.emit_to(window_clone.label(), "recognition-stream", chunk) {
             eprintln!("Failed to emit streaming event: {}", e);
         }
     }));
@@ -61,21 +189,32 @@ pub async fn recognize(
     let image_base64 = processed.base64.clone();
     let image_mime_type = processed.mime_type.clone();
     let prompt = data.prompt.clone();
-    let options = data.options.clone();
-    let was_compressed = processed.was_compressed;
+    let mut options = data.options.clone().unwrap_or_default();
+    options.auto_rotate = options.auto_rotate.or(Some(app_settings.auto_rotate));
+    options.auto_crop = options.auto_crop.or(Some(app_settings.auto_crop));
+    options.decode_codes = options.decode_codes.or(Some(app_settings.decode_codes));
+    options.estimate_confidence = options.estimate_confidence.or(Some(app_settings.estimate_confidence));
+    options.output_language = options.output_language.or(Some(app_settings.output_language));
+    if let Some(template_id) = data.template_id {
+        options.examples = llm::load_examples(template_id);
+        options.template_id = Some(template_id);
+    }
+    let was_replaced = processed.was_compressed || was_extracted;
     let processed_base64 = processed.base64.clone();
+    let partial_options = options.clone();
+    let started = Instant::now();
 
-    let task = tokio::spawn(async move {
+    let task = tokio::spawn(progress::observe(observer, async move {
         llm::recognize(
             config_id,
             &image_base64,
             &image_mime_type,
             &prompt,
-            options,
+            Some(options),
             callback,
         )
         .await
-    });
+    }));
 
     // Store the abort handle
     {
@@ -86,23 +225,57 @@ pub async fn recognize(
     // Wait for the task to complete
     let result = match task.await {
         Ok(mut result) => {
-            // If compression happened, return the processed image
-            if was_compressed {
+            // If compression or frame extraction happened, return the processed image (unless it was also rotated)
+            if was_replaced && result.processed_image.is_none() {
                 result.processed_image = Some(processed_base64);
             }
+            // Post-processing may change the answer, and providers without streaming send it only now
+            if let (Some(path), Some(content), true) = (&stream_file, &result.content, result.success) {
+                if let Err(e) = std::fs::write(path, content) {
+                    eprintln!("Failed to write result to {}: {}", path.display(), e);
+                }
+            }
+            if let Some(template_id) = data.template_id {
+                if let Err(e) = template_usage::record_usage(template_id, config_id, result.success) {
+                    eprintln!("Failed to record template usage: {}", e);
+                }
+            }
+            // Notify every window once a budget threshold is crossed
+            if let Some(alert) = result.budget_alert.as_ref().filter(|a| a.newly_reached) {
+                if let Err(e) = window.emit("budget-alert", alert) {
+                    eprintln!("Failed to emit budget alert: {}", e);
+                }
+            }
+            // Export without holding the result back; the outcome arrives as an event
+            if let Some(page) = result.notion_page.take() {
+                let window = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = window.emit_to(window.label(), "notion-export", page.export().await) {
+                        eprintln!("Failed to emit Notion export: {}", e);
+                    }
+                });
+            }
             Ok(result)
         }
         Err(e) if e.is_cancelled() => {
-            Ok(RecognitionResult {
-                success: false,
-                content: None,
-                error: Some("识别已取消".to_string()),
-                tokens_used: None,
-                duration_ms: None,
-                processed_image: None,
-            })
+            let duration_ms = Some(started.elapsed().as_millis() as i64);
+            let mut result = RecognitionResult::from_code(ErrorCode::RecognitionCancelled, duration_ms);
+            let content = std::mem::take(&mut *streamed.lock());
+            if !content.is_empty() {
+                result.content = Some(content);
+                result.partial = true;
+                llm::save_partial_result(
+                    config_id,
+                    &processed.base64,
+                    &processed.mime_type,
+                    &data.prompt,
+                    &partial_options,
+                    &result,
+                );
+            }
+            Ok(result)
         }
-        Err(e) => Err(format!("识别任务失败: {}", e)),
+        Err(e) => Err(ErrorCode::RecognitionTaskFailed { detail: e.to_string() }.into()),
     };
 
     // Clear the abort handle
@@ -114,16 +287,77 @@ pub async fn recognize(
     result
 }
 
+fn restart_file(file: &mut File) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.rewind()
+}
+
+/// Preview a frame of an animated GIF/WebP; `None` for still images
+#[tauri::command]
+pub fn extract_frame(image_data: String, frame_index: Option<u32>) -> Result<Option<AnimationFrame>, AppError> {
+    image::extract_frame(&image_data, frame_index)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail }.into())
+}
+
+/// Read QR codes and barcodes locally, without a model
+#[tauri::command]
+pub async fn decode_codes(image_data: String) -> Result<Vec<DecodedCode>, AppError> {
+    tokio::task::spawn_blocking(move || barcode::decode_codes(&image_data))
+        .await
+        .map_err(AppError::internal)?
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail }.into())
+}
+
+/// Stitch sequential screenshots of a scrolled view into one image to recognize
+#[tauri::command]
+pub async fn stitch_screenshots(images: Vec<String>) -> Result<StitchedImage, AppError> {
+    tokio::task::spawn_blocking(move || stitch::stitch_screenshots(&images))
+        .await
+        .map_err(AppError::internal)?
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail }.into())
+}
+
+/// Send two images and a prompt as one request and return the single analysis
+#[tauri::command]
+pub async fn compare_images(data: CompareImagesRequest) -> Result<RecognitionResult, AppError> {
+    let app_settings = settings::get_all_settings().map_err(AppError::from)?;
+    let threshold_bytes = (app_settings.compress_threshold as usize) * 1024;
+    let process = |image_data: &str| {
+        process_image_for_api(image_data, app_settings.auto_compress, threshold_bytes)
+            .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })
+    };
+    let (first, second) = (process(&data.first_image)?, process(&data.second_image)?);
+
+    if let Err(e) = prompt_history::record_prompt(&data.prompt) {
+        eprintln!("Failed to record prompt history: {}", e);
+    }
+
+    let mut options = data.options.unwrap_or_default();
+    options.output_language = options.output_language.or(Some(app_settings.output_language));
+
+    Ok(llm::compare_images(
+        data.config_id,
+        ImagePart { base64: &first.base64, mime_type: &first.mime_type },
+        ImagePart { base64: &second.base64, mime_type: &second.mime_type },
+        &data.prompt,
+        Some(options),
+    )
+    .await)
+}
+
 #[tauri::command]
 pub async fn cancel_recognition(
     state: tauri::State<'_, RecognitionStateHandle>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let state_guard = state.lock().await;
     if let Some(handle) = &state_guard.abort_handle {
         handle.abort();
         println!("[Recognition] Cancellation requested - task aborted");
         Ok(())
     } else {
-        Err("No active recognition to cancel".to_string())
+        Err(AppError::Validation {
+            code: None,
+            message: "No active recognition to cancel".to_string(),
+        })
     }
 }
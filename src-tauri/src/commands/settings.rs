@@ -1,17 +1,118 @@
-use crate::db::settings::{self, AppSettings};
+use crate::db::connection;
+use crate::db::defaults::{self, InitializationReport};
+use crate::db::settings::{self, AppSettings, SettingsScope};
+use crate::error::AppError;
+use crate::services::inference::InferenceStatus;
+use crate::services::rapidocr;
+use crate::services::settings_schema::{self, SettingSchema};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tauri::Emitter;
 
+/// Emitted to every window after settings are stored or reset, or the active
+/// profile or workspace changes which overrides apply
+pub const SETTINGS_CHANGED: &str = "settings-changed";
+
+/// Payload of `settings-changed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub keys: Vec<String>,
+    /// Set when the values are overrides of a workspace
+    pub workspace_id: Option<i64>,
+    /// Set when the values are overrides of a profile
+    pub profile_id: Option<i64>,
+}
+
+fn emit_changed(app: &tauri::AppHandle, keys: Vec<String>, workspace_id: Option<i64>, profile_id: Option<i64>) {
+    let change = SettingsChanged { keys, workspace_id, profile_id };
+    if let Err(e) = app.emit(SETTINGS_CHANGED, change) {
+        eprintln!("Failed to emit settings change: {}", e);
+    }
+}
+
+/// Every setting may have changed, e.g. after a reset or a profile switch
+pub fn emit_all_changed(app: &tauri::AppHandle, workspace_id: Option<i64>, profile_id: Option<i64>) {
+    let keys = settings_schema::settings_schema().iter().map(|s| s.key.to_string()).collect();
+    emit_changed(app, keys, workspace_id, profile_id);
+}
+
+#[tauri::command]
+pub fn get_all_settings() -> Result<AppSettings, AppError> {
+    settings::get_all_settings().map_err(AppError::from)
+}
+
+/// A workspace wins over a profile when both are given
+fn scope(workspace_id: Option<i64>, profile_id: Option<i64>) -> SettingsScope {
+    match (workspace_id, profile_id) {
+        (Some(id), _) => SettingsScope::Workspace(id),
+        (None, Some(id)) => SettingsScope::Profile(id),
+        (None, None) => SettingsScope::Global,
+    }
+}
+
+/// Pass `workspace_id` or `profile_id` to store the values as overrides of that workspace or profile.
+/// Unknown keys and invalid values reject the whole update.
+#[tauri::command]
+pub fn update_settings(
+    app: tauri::AppHandle,
+    updates: HashMap<String, serde_json::Value>,
+    workspace_id: Option<i64>,
+    profile_id: Option<i64>,
+) -> Result<AppSettings, AppError> {
+    settings_schema::validate_updates(&updates)?;
+    let mut keys: Vec<String> = updates.keys().cloned().collect();
+    keys.sort();
+    let settings = settings::update_settings(updates, scope(workspace_id, profile_id))?;
+    emit_changed(&app, keys, workspace_id, profile_id);
+    Ok(settings)
+}
+
+/// Type, range and default of every setting, for rendering settings forms
+#[tauri::command]
+pub fn get_settings_schema() -> Vec<SettingSchema> {
+    settings_schema::settings_schema()
+}
+
+#[tauri::command]
+pub fn reset_settings(
+    app: tauri::AppHandle,
+    workspace_id: Option<i64>,
+    profile_id: Option<i64>,
+) -> Result<AppSettings, AppError> {
+    let settings = settings::reset_settings(scope(workspace_id, profile_id))?;
+    emit_all_changed(&app, workspace_id, profile_id);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_workspace_settings(workspace_id: i64) -> Result<HashMap<String, String>, AppError> {
+    settings::get_overrides(SettingsScope::Workspace(workspace_id)).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_profile_settings(profile_id: i64) -> Result<HashMap<String, String>, AppError> {
+    settings::get_overrides(SettingsScope::Profile(profile_id)).map_err(AppError::from)
+}
+
+/// Seed first-run data; `language` defaults to the current UI language
 #[tauri::command]
-pub fn get_all_settings() -> Result<AppSettings, String> {
-    settings::get_all_settings().map_err(|e| e.to_string())
+pub fn initialize_defaults(language: Option<String>) -> Result<InitializationReport, AppError> {
+    let language = match language {
+        Some(language) => language,
+        None => settings::get_all_settings().map_err(AppError::from)?.language,
+    };
+    defaults::initialize_defaults(&language).map_err(AppError::from)
 }
 
+/// Whether this is a guest session whose data is discarded on exit
 #[tauri::command]
-pub fn update_settings(updates: HashMap<String, serde_json::Value>) -> Result<AppSettings, String> {
-    settings::update_settings(updates).map_err(|e| e.to_string())
+pub fn is_guest_mode() -> bool {
+    connection::is_in_memory()
 }
 
+/// Devices local models can run on here and the one they actually use
 #[tauri::command]
-pub fn reset_settings() -> Result<AppSettings, String> {
-    settings::reset_settings().map_err(|e| e.to_string())
+pub fn get_inference_status() -> InferenceStatus {
+    rapidocr::inference_status()
 }
@@ -0,0 +1,24 @@
+use crate::error::AppError;
+use crate::services::share;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Upload a result to the share target from settings and copy the link to the clipboard
+#[tauri::command]
+pub async fn share_result(app: tauri::AppHandle, content: String, title: Option<String>) -> Result<String, AppError> {
+    let link = share::share(&content, title.as_deref().unwrap_or("OCR result")).await?;
+    if let Err(e) = app.clipboard().write_text(link.clone()) {
+        eprintln!("Failed to copy share link: {}", e);
+    }
+    Ok(link)
+}
+
+/// Store the GitHub token or the custom endpoint's bearer token; empty removes it
+#[tauri::command]
+pub fn set_share_token(token: String) -> Result<(), AppError> {
+    share::set_token(token.trim()).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn has_share_token() -> Result<bool, AppError> {
+    share::has_token().map_err(AppError::from)
+}
@@ -0,0 +1,18 @@
+use crate::error::AppError;
+use crate::services::speech::{self, VoiceInfo};
+
+/// Read recognized text aloud, interrupting anything still being spoken
+#[tauri::command]
+pub fn speak_result(text: String) -> Result<(), AppError> {
+    speech::speak(&text)
+}
+
+#[tauri::command]
+pub fn stop_speaking() -> Result<(), AppError> {
+    speech::stop()
+}
+
+#[tauri::command]
+pub fn get_tts_voices() -> Result<Vec<VoiceInfo>, AppError> {
+    speech::voices()
+}
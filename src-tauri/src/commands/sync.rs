@@ -0,0 +1,20 @@
+use crate::error::AppError;
+use crate::services::sync::{self, SyncCredentialsState, SyncReport};
+
+/// Pull the remote snapshot, apply what is newer there and push the merged one
+#[tauri::command]
+pub async fn sync_now() -> Result<SyncReport, AppError> {
+    sync::sync_now().await
+}
+
+/// Store the WebDAV password or S3 secret key and the snapshot passphrase.
+/// Omitted values are kept, empty ones removed.
+#[tauri::command]
+pub fn set_sync_credentials(secret: Option<String>, passphrase: Option<String>) -> Result<(), AppError> {
+    sync::set_credentials(secret, passphrase).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_sync_credentials_state() -> Result<SyncCredentialsState, AppError> {
+    sync::credentials_state().map_err(AppError::from)
+}
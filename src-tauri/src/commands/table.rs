@@ -0,0 +1,47 @@
+use crate::error::AppError;
+use crate::services::i18n::ErrorCode;
+use crate::services::table::{self, Table};
+use std::fs;
+use tauri_plugin_dialog::DialogExt;
+
+#[tauri::command]
+pub fn extract_tables(content: String) -> Vec<Table> {
+    table::extract_tables(&content)
+}
+
+/// Save the tables of a result as CSV or XLSX through the save dialog.
+/// Multiple tables go to separate sheets (XLSX) or blocks separated by an empty line (CSV).
+#[tauri::command]
+pub async fn export_tables(
+    app: tauri::AppHandle,
+    content: String,
+    format: String,
+    default_name: String,
+) -> Result<bool, AppError> {
+    let tables = table::extract_tables(&content);
+    if tables.is_empty() {
+        return Err(ErrorCode::NoTablesFound.into());
+    }
+
+    let (data, filter_name) = match format.as_str() {
+        "csv" => (table::to_csv(&tables).into_bytes(), "CSV"),
+        "xlsx" => (table::to_xlsx(&tables), "Excel"),
+        _ => return Err(ErrorCode::UnsupportedExportFormat { format }.into()),
+    };
+
+    let file_path = app
+        .dialog()
+        .file()
+        .add_filter(filter_name, &[format.as_str()])
+        .set_file_name(format!("{}.{}", default_name, format))
+        .blocking_save_file();
+
+    match file_path {
+        Some(file_path) => {
+            let path = file_path.into_path().map_err(|e| AppError::internal(format!("无效路径: {}", e)))?;
+            fs::write(&path, data).map_err(|e| AppError::internal(format!("保存文件失败: {}", e)))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
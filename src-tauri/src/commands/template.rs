@@ -1,37 +1,133 @@
-use crate::db::prompt_template::{self, PromptTemplate, TemplateUpdate};
+use crate::db::model_config;
+use crate::db::prompt_template::{self, PromptTemplate, TemplateMerge, TemplateQueryParams, TemplateUpdate};
+use crate::db::template_example::{self, TemplateExample, TemplateExampleInput, MAX_EXAMPLES_PER_TEMPLATE};
+use crate::db::template_usage::{self, TemplateStats, TemplateStatsParams};
+use crate::db::PaginatedResult;
+use crate::error::AppError;
+use crate::commands::history::HistoryImage;
+use crate::services::image::load_image_file;
+use crate::services::image_store;
+use crate::services::capabilities::get_capabilities;
+use crate::services::i18n::ErrorCode;
+use crate::services::prompt_lint::{self, PromptLintIssue};
 
 #[tauri::command]
-pub fn get_all_templates() -> Result<Vec<PromptTemplate>, String> {
-    prompt_template::get_all_templates().map_err(|e| e.to_string())
+pub fn get_all_templates() -> Result<Vec<PromptTemplate>, AppError> {
+    prompt_template::get_all_templates().map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_default_template() -> Result<Option<PromptTemplate>, String> {
-    prompt_template::get_default_template().map_err(|e| e.to_string())
+pub fn query_templates(params: Option<TemplateQueryParams>) -> Result<PaginatedResult<PromptTemplate>, AppError> {
+    prompt_template::query_templates(params.unwrap_or_default()).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn get_recent_templates(limit: Option<i32>) -> Result<Vec<PromptTemplate>, String> {
-    prompt_template::get_recent_templates(limit).map_err(|e| e.to_string())
+pub fn get_default_template() -> Result<Option<PromptTemplate>, AppError> {
+    prompt_template::get_default_template().map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn create_template(name: String, content: String, is_default: Option<bool>) -> Result<PromptTemplate, String> {
-    prompt_template::create_template(&name, &content, is_default.unwrap_or(false))
-        .map_err(|e| e.to_string())
+pub fn get_recent_templates(limit: Option<i32>) -> Result<Vec<PromptTemplate>, AppError> {
+    prompt_template::get_recent_templates(limit).map_err(AppError::from)
 }
 
+/// `profile_id` defaults to the active profile; zero or less makes the template shared.
+/// Fails with `DuplicateTemplate` when a visible template has the same normalized
+/// content, unless `allow_duplicate` is set.
 #[tauri::command]
-pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<PromptTemplate>, String> {
-    prompt_template::update_template(id, updates).map_err(|e| e.to_string())
+pub fn create_template(
+    name: String,
+    content: String,
+    is_default: Option<bool>,
+    profile_id: Option<i64>,
+    allow_duplicate: Option<bool>,
+) -> Result<PromptTemplate, AppError> {
+    if !allow_duplicate.unwrap_or(false) {
+        if let Some(existing) = prompt_template::find_duplicate(&content).map_err(AppError::from)? {
+            return Err(ErrorCode::DuplicateTemplate { id: existing.id, name: existing.name }.into());
+        }
+    }
+    prompt_template::create_template(&name, &content, is_default.unwrap_or(false), profile_id)
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn delete_template(id: i64) -> Result<bool, String> {
-    prompt_template::delete_template(id).map_err(|e| e.to_string())
+pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<PromptTemplate>, AppError> {
+    prompt_template::update_template(id, updates).map_err(AppError::from)
 }
 
 #[tauri::command]
-pub fn increment_template_use(id: i64) -> Result<(), String> {
-    prompt_template::increment_use_count(id).map_err(|e| e.to_string())
+pub fn delete_template(id: i64) -> Result<bool, AppError> {
+    prompt_template::delete_template(id).map_err(AppError::from)
+}
+
+/// Merge templates with the same normalized content into one
+#[tauri::command]
+pub fn dedupe_templates() -> Result<Vec<TemplateMerge>, AppError> {
+    prompt_template::dedupe_templates().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_template_examples(template_id: i64) -> Result<Vec<TemplateExample>, AppError> {
+    template_example::get_examples(template_id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_template_example_image(id: i64) -> Result<Option<HistoryImage>, AppError> {
+    let Some(example) = template_example::get_example(id).map_err(AppError::from)? else {
+        return Ok(None);
+    };
+    let (base64, mime_type) =
+        load_image_file(&image_store::image_file_path(&example.image_file)?).map_err(AppError::image)?;
+    Ok(Some(HistoryImage { base64, mime_type }))
+}
+
+/// Fails with `TooManyExamples` once the template has `MAX_EXAMPLES_PER_TEMPLATE`
+#[tauri::command]
+pub fn add_template_example(input: TemplateExampleInput) -> Result<TemplateExample, AppError> {
+    let count = template_example::get_examples(input.template_id).map_err(AppError::from)?.len();
+    if count >= MAX_EXAMPLES_PER_TEMPLATE {
+        return Err(ErrorCode::TooManyExamples { max: MAX_EXAMPLES_PER_TEMPLATE }.into());
+    }
+    template_example::add_example(input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn update_template_example(id: i64, output: String) -> Result<bool, AppError> {
+    template_example::update_example_output(id, &output).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_template_example(id: i64) -> Result<bool, AppError> {
+    template_example::delete_example(id).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn increment_template_use(id: i64) -> Result<(), AppError> {
+    prompt_template::increment_use_count(id).map_err(AppError::from)
+}
+
+/// Runs, success rate and last use per template
+#[tauri::command]
+pub fn get_template_stats(params: Option<TemplateStatsParams>) -> Result<PaginatedResult<TemplateStats>, AppError> {
+    template_usage::get_template_stats(params.unwrap_or_default()).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn lint_prompt(
+    content: String,
+    config_id: Option<i64>,
+    json_mode: Option<bool>,
+) -> Result<Vec<PromptLintIssue>, AppError> {
+    // Lint against the chosen config, or the default one when editing a template on its own
+    let config = match config_id {
+        Some(id) => model_config::get_config_by_id(id),
+        None => model_config::get_default_config(),
+    }
+    .map_err(AppError::from)?;
+
+    let provider = config.map(|c| c.provider).unwrap_or_else(|| "custom".to_string());
+    let capabilities = get_capabilities(&provider);
+
+    Ok(prompt_lint::lint_prompt(&content, &capabilities, json_mode.unwrap_or(false)))
 }
@@ -0,0 +1,127 @@
+use crate::db::settings;
+use crate::error::AppError;
+use crate::services::i18n::ErrorCode;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::Emitter;
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tokio::sync::Mutex;
+
+const STABLE_ENDPOINT: &str = "https://github.com/cbackup1986-dev/orcapp/releases/latest/download/latest.json";
+/// Beta builds are published to a rolling `beta` release
+const BETA_ENDPOINT: &str = "https://github.com/cbackup1986-dev/orcapp/releases/download/beta/latest.json";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+// Update found by the last check and, once downloaded, its package
+#[derive(Default)]
+pub struct UpdateState {
+    update: Option<Update>,
+    package: Option<Vec<u8>>,
+}
+
+pub type UpdateStateHandle = Arc<Mutex<UpdateState>>;
+
+fn endpoint(channel: &str) -> &'static str {
+    match channel {
+        "beta" => BETA_ENDPOINT,
+        _ => STABLE_ENDPOINT,
+    }
+}
+
+fn update_failed(e: impl ToString) -> AppError {
+    ErrorCode::UpdateFailed { detail: e.to_string() }.into()
+}
+
+/// Check the release channel selected in settings for a newer version
+#[tauri::command]
+pub async fn check_for_update(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, UpdateStateHandle>,
+) -> Result<Option<UpdateInfo>, AppError> {
+    let channel = settings::get_all_settings().map_err(AppError::from)?.update_channel;
+    let url = tauri::Url::parse(endpoint(&channel)).map_err(update_failed)?;
+
+    let update = app
+        .updater_builder()
+        .endpoints(vec![url])
+        .map_err(update_failed)?
+        .build()
+        .map_err(update_failed)?
+        .check()
+        .await
+        .map_err(update_failed)?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        channel: channel.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|date| date.to_string()),
+    });
+
+    let mut state_guard = state.lock().await;
+    state_guard.update = update;
+    state_guard.package = None;
+
+    Ok(info)
+}
+
+/// Download the update found by the last check, emitting `update-progress`
+#[tauri::command]
+pub async fn download_update(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, UpdateStateHandle>,
+) -> Result<(), AppError> {
+    let update = state.lock().await.update.clone().ok_or(ErrorCode::NoPendingUpdate)?;
+
+    let mut downloaded = 0u64;
+    let package = update
+        .download(
+            |chunk_length, total| {
+                downloaded += chunk_length as u64;
+                if let Err(e) = app.emit("update-progress", UpdateProgress { downloaded, total }) {
+                    eprintln!("Failed to emit update progress: {}", e);
+                }
+            },
+            || {},
+        )
+        .await
+        .map_err(update_failed)?;
+
+    state.lock().await.package = Some(package);
+    Ok(())
+}
+
+/// Install the downloaded update and restart into the new version
+#[tauri::command]
+pub async fn install_update(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, UpdateStateHandle>,
+) -> Result<(), AppError> {
+    let (update, package) = {
+        let mut state_guard = state.lock().await;
+        match (state_guard.update.clone(), state_guard.package.take()) {
+            (Some(update), Some(package)) => (update, package),
+            _ => return Err(ErrorCode::NoPendingUpdate.into()),
+        }
+    };
+
+    update.install(package).map_err(update_failed)?;
+    app.restart()
+}
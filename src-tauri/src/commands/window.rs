@@ -0,0 +1,126 @@
+use crate::db::{history, settings};
+use crate::error::AppError;
+use crate::services::i18n::ErrorCode;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Window};
+
+const MAIN_WINDOW_STATE_KEY: &str = "window:main";
+
+/// Size and position of the main window, in physical pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+/// Remember the main window geometry. While maximized only the flag is
+/// updated so restoring un-maximizes to the previous size.
+pub fn save_window_state(window: &Window) {
+    let maximized = window.is_maximized().unwrap_or(false);
+    let previous = load_window_state();
+
+    let state = match (maximized, previous) {
+        (true, Some(previous)) => WindowState { maximized, ..previous },
+        _ => {
+            let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                return;
+            };
+            WindowState {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+            }
+        }
+    };
+
+    if let Ok(json) = serde_json::to_string(&state) {
+        if let Err(e) = settings::set_setting(MAIN_WINDOW_STATE_KEY, &json) {
+            eprintln!("Failed to save window state: {}", e);
+        }
+    }
+}
+
+/// Apply the saved geometry, skipping the position when it is no longer on any monitor
+pub fn restore_window_state(window: &WebviewWindow) {
+    let Some(state) = load_window_state() else {
+        return;
+    };
+
+    let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+
+    let on_screen = window.available_monitors().unwrap_or_default().iter().any(|monitor| {
+        let (position, size) = (monitor.position(), monitor.size());
+        state.x >= position.x
+            && state.y >= position.y
+            && state.x < position.x + size.width as i32
+            && state.y < position.y + size.height as i32
+    });
+    if on_screen {
+        let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+    }
+
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+fn load_window_state() -> Option<WindowState> {
+    settings::get_setting(MAIN_WINDOW_STATE_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// Open a lightweight window showing a single history result, or focus it if already open
+#[tauri::command]
+pub fn open_result_window(app: tauri::AppHandle, history_id: i64) -> Result<(), AppError> {
+    let record = history::get_history_by_id(history_id)
+        .map_err(AppError::from)?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+
+    let label = format!("result-{}", history_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        return window.set_focus().map_err(AppError::internal);
+    }
+
+    let url = WebviewUrl::App(format!("index.html#/result/{}", history_id).into());
+    WebviewWindowBuilder::new(&app, label, url)
+        .title(format!("识别结果 - {}", record.config_name))
+        .inner_size(640.0, 720.0)
+        .min_inner_size(360.0, 300.0)
+        .visible(false)
+        .build()
+        .map_err(AppError::internal)?;
+
+    Ok(())
+}
+
+/// Open the compact always-on-top quick OCR window, or close it when open.
+/// Returns whether the window is open afterwards.
+#[tauri::command]
+pub fn toggle_mini_window(app: tauri::AppHandle) -> Result<bool, AppError> {
+    if let Some(window) = app.get_webview_window("mini") {
+        window.close().map_err(AppError::internal)?;
+        return Ok(false);
+    }
+
+    let url = WebviewUrl::App("index.html#/mini".into());
+    WebviewWindowBuilder::new(&app, "mini", url)
+        .title("快速识别")
+        .inner_size(360.0, 420.0)
+        .min_inner_size(280.0, 240.0)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(AppError::internal)?;
+
+    Ok(true)
+}
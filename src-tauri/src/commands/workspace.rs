@@ -0,0 +1,38 @@
+use crate::commands::settings::emit_all_changed;
+use crate::db::workspace::{self, Workspace, WorkspaceInput};
+use crate::error::AppError;
+
+#[tauri::command]
+pub fn get_all_workspaces() -> Result<Vec<Workspace>, AppError> {
+    workspace::get_all_workspaces().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn get_active_workspace() -> Result<Option<Workspace>, AppError> {
+    workspace::get_active_workspace().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn create_workspace(input: WorkspaceInput) -> Result<Workspace, AppError> {
+    workspace::create_workspace(input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn update_workspace(id: i64, input: WorkspaceInput) -> Result<Option<Workspace>, AppError> {
+    workspace::update_workspace(id, input).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn delete_workspace(id: i64) -> Result<bool, AppError> {
+    workspace::delete_workspace(id).map_err(AppError::from)
+}
+
+/// Switch to a workspace, making its preset the default config and template.
+/// Pass no id to leave workspaces altogether.
+#[tauri::command]
+pub fn set_active_workspace(app: tauri::AppHandle, id: Option<i64>) -> Result<Option<Workspace>, AppError> {
+    let workspace = workspace::set_active_workspace(id).map_err(AppError::from)?;
+    // Another workspace's overrides apply now
+    emit_all_changed(&app, None, None);
+    Ok(workspace)
+}
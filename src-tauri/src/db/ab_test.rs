@@ -0,0 +1,182 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// Prompt variants run on one image with one config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbTest {
+    pub id: i64,
+    pub config_id: i64,
+    pub config_name: String,
+    pub image_thumbnail: Option<String>,
+    /// Variant the model judged best, when judging was requested and succeeded
+    pub judge_winner: Option<i32>,
+    pub judge_reason: Option<String>,
+    pub judge_error: Option<String>,
+    pub created_at: String,
+    pub results: Vec<AbTestResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbTestResult {
+    pub id: i64,
+    pub ab_test_id: i64,
+    /// Position of the variant in the request, from 0
+    pub variant: i32,
+    pub prompt: String,
+    pub template_id: Option<i64>,
+    pub success: bool,
+    pub content: Option<String>,
+    pub error: Option<String>,
+    pub tokens_used: Option<i32>,
+    pub duration_ms: Option<i32>,
+    pub char_count: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AbTestInput {
+    pub config_id: i64,
+    pub config_name: String,
+    pub image_thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AbTestResultInput {
+    pub variant: i32,
+    pub prompt: String,
+    pub template_id: Option<i64>,
+    pub success: bool,
+    pub content: Option<String>,
+    pub error: Option<String>,
+    pub tokens_used: Option<i32>,
+    pub duration_ms: Option<i32>,
+    pub char_count: Option<i32>,
+}
+
+const AB_TEST_COLUMNS: &str =
+    "id, config_id, config_name, image_thumbnail, judge_winner, judge_reason, judge_error, created_at";
+
+const RESULT_COLUMNS: &str = "id, ab_test_id, variant, prompt, template_id, success, content, error, \
+    tokens_used, duration_ms, char_count";
+
+fn row_to_ab_test(row: &rusqlite::Row) -> Result<AbTest> {
+    Ok(AbTest {
+        id: row.get("id")?,
+        config_id: row.get("config_id")?,
+        config_name: row.get("config_name")?,
+        image_thumbnail: row.get("image_thumbnail")?,
+        judge_winner: row.get("judge_winner")?,
+        judge_reason: row.get("judge_reason")?,
+        judge_error: row.get("judge_error")?,
+        created_at: row.get("created_at")?,
+        results: Vec::new(),
+    })
+}
+
+fn row_to_result(row: &rusqlite::Row) -> Result<AbTestResult> {
+    Ok(AbTestResult {
+        id: row.get("id")?,
+        ab_test_id: row.get("ab_test_id")?,
+        variant: row.get("variant")?,
+        prompt: row.get("prompt")?,
+        template_id: row.get("template_id")?,
+        success: row.get::<_, i32>("success")? == 1,
+        content: row.get("content")?,
+        error: row.get("error")?,
+        tokens_used: row.get("tokens_used")?,
+        duration_ms: row.get("duration_ms")?,
+        char_count: row.get("char_count")?,
+    })
+}
+
+fn load_results(conn: &rusqlite::Connection, ab_test_id: i64) -> Result<Vec<AbTestResult>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ab_test_results WHERE ab_test_id = ?1 ORDER BY variant",
+        RESULT_COLUMNS
+    ))?;
+    let rows = stmt.query_map([ab_test_id], row_to_result)?;
+    rows.collect()
+}
+
+/// Store a finished test with the results of all its variants
+pub fn save_ab_test(input: AbTestInput, results: Vec<AbTestResultInput>) -> Result<i64> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO ab_tests (config_id, config_name, image_thumbnail) VALUES (?1, ?2, ?3)",
+        params![input.config_id, input.config_name, input.image_thumbnail],
+    )?;
+    let id = tx.last_insert_rowid();
+
+    for result in results {
+        tx.execute(
+            "INSERT INTO ab_test_results (ab_test_id, variant, prompt, template_id, success, content, error,
+                tokens_used, duration_ms, char_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                result.variant,
+                result.prompt,
+                result.template_id,
+                if result.success { 1 } else { 0 },
+                result.content,
+                result.error,
+                result.tokens_used,
+                result.duration_ms,
+                result.char_count,
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(id)
+}
+
+pub fn set_judgement(id: i64, winner: Option<i32>, reason: Option<&str>, error: Option<&str>) -> Result<()> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "UPDATE ab_tests SET judge_winner = ?1, judge_reason = ?2, judge_error = ?3 WHERE id = ?4",
+        params![winner, reason, error, id],
+    )?;
+    Ok(())
+}
+
+pub fn get_ab_test(id: i64) -> Result<Option<AbTest>> {
+    let conn = get_connection().lock();
+    let mut test = match conn.query_row(
+        &format!("SELECT {} FROM ab_tests WHERE id = ?1", AB_TEST_COLUMNS),
+        [id],
+        row_to_ab_test,
+    ) {
+        Ok(test) => test,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    test.results = load_results(&conn, id)?;
+    Ok(Some(test))
+}
+
+/// Every test with its results, newest first
+pub fn get_all_ab_tests() -> Result<Vec<AbTest>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ab_tests ORDER BY created_at DESC, id DESC",
+        AB_TEST_COLUMNS
+    ))?;
+    let mut tests = stmt.query_map([], row_to_ab_test)?.collect::<Result<Vec<_>>>()?;
+
+    for test in &mut tests {
+        test.results = load_results(&conn, test.id)?;
+    }
+    Ok(tests)
+}
+
+pub fn delete_ab_test(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM ab_tests WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
@@ -0,0 +1,104 @@
+use crate::db::get_connection;
+use serde::Serialize;
+use rusqlite::{Connection, DatabaseName, OpenFlags, Result};
+use std::path::{Path, PathBuf};
+
+/// Tables a file must contain to be accepted as a backup
+const REQUIRED_TABLES: &[&str] = &["model_configs", "prompt_templates", "recognition_history", "app_settings"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    pub configs: i64,
+    pub templates: i64,
+    pub history_records: i64,
+    /// Copy of the database as it was before the restore
+    pub previous_backup: Option<String>,
+}
+
+/// Write a consistent snapshot of the live database to `dest`.
+/// Images stored on disk are not included.
+/// Refuses a `dest` that is the live database file itself.
+pub fn backup_database(dest: &Path) -> Result<()> {
+    let invalid = || rusqlite::Error::InvalidPath(dest.to_path_buf());
+    let dest = canonical_path(dest).ok_or_else(invalid)?;
+    let conn = get_connection().lock();
+    if let Some(live) = conn.path().filter(|path| !path.is_empty()).and_then(|path| canonical_path(Path::new(path))) {
+        let live_files = ["", "-wal", "-shm", "-journal"].map(|suffix| format!("{}{}", live.display(), suffix));
+        if live_files.iter().any(|file| Path::new(file) == dest) {
+            return Err(invalid());
+        }
+    }
+    if dest.exists() {
+        std::fs::remove_file(&dest).map_err(|_| invalid())?;
+    }
+    conn.backup(DatabaseName::Main, &dest, None)
+}
+
+/// Absolute path with links resolved; `path` itself need not exist yet
+fn canonical_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+/// Check that `src` is an intact database with our schema.
+/// Returns the problems found; an empty list means it can be restored.
+pub fn validate_backup(src: &Path) -> Result<Vec<String>> {
+    let conn = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    let mut problems = Vec::new();
+    if integrity != "ok" {
+        problems.push(integrity);
+    }
+
+    for table in REQUIRED_TABLES {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            problems.push(format!("missing table {}", table));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Replace the live database with `src`, keeping a copy of the current one
+/// next to it, then bring the restored schema up to date.
+/// Callers should run `validate_backup` first.
+pub fn restore_database(src: &Path) -> Result<RestoreReport> {
+    let mut conn = get_connection().lock();
+
+    // An in-memory (guest) database has no file to keep a copy next to
+    let previous_backup = conn
+        .path()
+        .filter(|path| !path.is_empty())
+        .map(|path| PathBuf::from(format!("{}.before-restore", path)));
+    if let Some(ref path) = previous_backup {
+        let _ = std::fs::remove_file(path);
+        conn.backup(DatabaseName::Main, path, None)?;
+    }
+
+    conn.restore(DatabaseName::Main, src, None::<fn(rusqlite::backup::Progress)>)?;
+    super::connection::init_tables(&conn)?;
+
+    let count = |table: &str| -> Result<i64> {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+    };
+
+    Ok(RestoreReport {
+        configs: count("model_configs")?,
+        templates: count("prompt_templates")?,
+        history_records: count("recognition_history")?,
+        previous_backup: previous_backup.map(|path| path.to_string_lossy().into_owned()),
+    })
+}
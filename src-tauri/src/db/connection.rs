@@ -2,16 +2,13 @@ use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use rusqlite::{Connection, Result};
 use std::path::Path;
+use super::defaults::{default_prompts, insert_missing_prompts};
+use crate::utils::crypto;
 
 static DB_CONNECTION: OnceCell<Mutex<Connection>> = OnceCell::new();
 
-const DEFAULT_PROMPTS: &[(&str, &str, bool)] = &[
-    ("通用识别", "请识别这张图片的内容，并用中文详细描述。", true),
-    ("文字提取", "请提取图片中的所有文字内容，保持原有格式。", false),
-    ("表格识别", "请识别图片中的表格，并以 Markdown 格式输出。", false),
-    ("代码识别", "请识别图片中的代码，保持原有格式和缩进。", false),
-    ("公式识别", "请识别图片中的数学公式，并以 LaTeX 格式输出。", false),
-];
+/// Path that opens a private in-memory database, gone when the app exits
+pub const IN_MEMORY: &str = ":memory:";
 
 pub fn init_database(app_data_dir: &Path) -> Result<()> {
     let db_dir = app_data_dir.join("database");
@@ -19,8 +16,24 @@ pub fn init_database(app_data_dir: &Path) -> Result<()> {
         rusqlite::Error::InvalidPath(db_dir.join(e.to_string()))
     })?;
     
-    let db_path = db_dir.join("data.db");
-    let conn = Connection::open(&db_path)?;
+    init_database_at(&db_dir.join("data.db"))
+}
+
+/// Open the global connection on the database file at `path`, or in memory
+/// for [`IN_MEMORY`]
+pub fn init_database_at(path: &Path) -> Result<()> {
+    let conn = open_database(path)?;
+    
+    DB_CONNECTION.set(Mutex::new(conn)).map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    
+    Ok(())
+}
+
+/// Open a connection with foreign keys enabled and the tables created
+pub fn open_database(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)?;
     
     // Enable foreign keys
     conn.execute("PRAGMA foreign_keys = ON", [])?;
@@ -28,18 +41,28 @@ pub fn init_database(app_data_dir: &Path) -> Result<()> {
     // Initialize tables
     init_tables(&conn)?;
     
-    DB_CONNECTION.set(Mutex::new(conn)).map_err(|_| {
-        rusqlite::Error::InvalidQuery
-    })?;
-    
-    Ok(())
+    Ok(conn)
+}
+
+/// Whether nothing is persisted, e.g. in guest mode
+pub fn is_in_memory() -> bool {
+    DB_CONNECTION
+        .get()
+        .is_some_and(|conn| conn.lock().path().is_none_or(str::is_empty))
+}
+
+/// Put the tests of a process on one shared in-memory database
+#[cfg(test)]
+pub fn init_test_database() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| init_database_at(Path::new(IN_MEMORY)).expect("initialize test database"));
 }
 
 pub fn get_connection() -> &'static Mutex<Connection> {
     DB_CONNECTION.get().expect("Database not initialized")
 }
 
-fn init_tables(conn: &Connection) -> Result<()> {
+pub(crate) fn init_tables(conn: &Connection) -> Result<()> {
     // Model configs table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS model_configs (
@@ -50,10 +73,24 @@ fn init_tables(conn: &Connection) -> Result<()> {
             api_key_encrypted TEXT NOT NULL,
             model_name TEXT NOT NULL,
             max_tokens INTEGER DEFAULT 4096,
+            requests_per_minute INTEGER,
+            monthly_budget_usd REAL,
+            input_price_per_mtok REAL,
+            output_price_per_mtok REAL,
+            last_validation_status TEXT,
+            last_validated_at TEXT,
+            redact_sensitive INTEGER NOT NULL DEFAULT 0,
+            image_detail TEXT,
+            reasoning_model INTEGER NOT NULL DEFAULT 0,
+            thinking_budget_tokens INTEGER,
+            organization TEXT,
+            project TEXT,
+            skip_tls_verify INTEGER NOT NULL DEFAULT 0,
+            profile_id INTEGER,
             is_active INTEGER DEFAULT 1,
             is_default INTEGER DEFAULT 0,
             created_at TEXT DEFAULT (datetime('now', 'localtime')),
-            updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+            updated_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
         )",
         [],
     )?;
@@ -65,12 +102,39 @@ fn init_tables(conn: &Connection) -> Result<()> {
             config_id INTEGER NOT NULL,
             config_name TEXT NOT NULL,
             image_path TEXT,
+            image_file TEXT,
             image_thumbnail TEXT,
+            compare_thumbnail TEXT,
             prompt TEXT NOT NULL,
             result TEXT NOT NULL,
+            reasoning TEXT,
             tokens_used INTEGER,
+            input_tokens INTEGER,
+            output_tokens INTEGER,
             duration_ms INTEGER,
-            created_at TEXT DEFAULT (datetime('now', 'localtime')),
+            ttft_ms INTEGER,
+            tokens_per_second REAL,
+            char_count INTEGER,
+            word_count INTEGER,
+            language TEXT,
+            reading_time_secs INTEGER,
+            provider TEXT,
+            model_name TEXT,
+            temperature REAL,
+            top_p REAL,
+            max_tokens INTEGER,
+            app_version TEXT,
+            status TEXT NOT NULL DEFAULT 'success',
+            error TEXT,
+            error_code TEXT,
+            image_hash INTEGER,
+            confidence REAL,
+            needs_review INTEGER NOT NULL DEFAULT 0,
+            batch_id TEXT,
+            review_status TEXT,
+            reviewed_result TEXT,
+            rerun_of INTEGER,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
             FOREIGN KEY (config_id) REFERENCES model_configs(id)
         )",
         [],
@@ -84,7 +148,9 @@ fn init_tables(conn: &Connection) -> Result<()> {
             content TEXT NOT NULL,
             is_default INTEGER DEFAULT 0,
             use_count INTEGER DEFAULT 0,
-            created_at TEXT DEFAULT (datetime('now', 'localtime'))
+            profile_id INTEGER,
+            created_at TEXT DEFAULT (datetime('now', 'localtime')),
+            updated_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
         )",
         [],
     )?;
@@ -99,6 +165,238 @@ fn init_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    // Template A/B experiments
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS experiments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            config_id INTEGER NOT NULL,
+            template_a_id INTEGER NOT NULL,
+            template_b_id INTEGER NOT NULL,
+            image_paths TEXT NOT NULL DEFAULT '[]',
+            status TEXT NOT NULL DEFAULT 'draft',
+            created_at TEXT DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS experiment_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            experiment_id INTEGER NOT NULL,
+            image_path TEXT NOT NULL,
+            variant TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            content TEXT,
+            error TEXT,
+            tokens_used INTEGER,
+            input_tokens INTEGER,
+            output_tokens INTEGER,
+            duration_ms INTEGER,
+            char_count INTEGER,
+            rating INTEGER,
+            created_at TEXT DEFAULT (datetime('now', 'localtime')),
+            FOREIGN KEY (experiment_id) REFERENCES experiments(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Workspaces bundling a preset, glossary and output defaults
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS workspaces (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            config_id INTEGER,
+            template_id INTEGER,
+            glossary TEXT NOT NULL DEFAULT '[]',
+            tags TEXT NOT NULL DEFAULT '[]',
+            export_format TEXT NOT NULL DEFAULT 'json',
+            output_dir TEXT,
+            is_active INTEGER DEFAULT 0,
+            created_at TEXT DEFAULT (datetime('now', 'localtime')),
+            updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+
+    // Named recognition presets: config, template and options in one bundle
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS presets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            config_id INTEGER NOT NULL,
+            template_id INTEGER,
+            options TEXT NOT NULL DEFAULT '{}',
+            is_default INTEGER DEFAULT 0,
+            created_at TEXT DEFAULT (datetime('now', 'localtime')),
+            updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+
+    // Every prompt used for a recognition, deduplicated, for autocomplete
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS prompt_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            prompt TEXT NOT NULL UNIQUE,
+            use_count INTEGER DEFAULT 1,
+            last_used_at TEXT DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+
+    // Prompt variants compared on a single image
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ab_tests (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            config_id INTEGER NOT NULL,
+            config_name TEXT NOT NULL,
+            image_thumbnail TEXT,
+            judge_winner INTEGER,
+            judge_reason TEXT,
+            judge_error TEXT,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ab_test_results (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            ab_test_id INTEGER NOT NULL,
+            variant INTEGER NOT NULL,
+            prompt TEXT NOT NULL,
+            template_id INTEGER,
+            success INTEGER NOT NULL,
+            content TEXT,
+            error TEXT,
+            tokens_used INTEGER,
+            duration_ms INTEGER,
+            char_count INTEGER,
+            FOREIGN KEY (ab_test_id) REFERENCES ab_tests(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // One row per recognition run with a template, for usage analytics
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS template_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            template_id INTEGER NOT NULL,
+            config_id INTEGER,
+            success INTEGER NOT NULL,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+            FOREIGN KEY (template_id) REFERENCES prompt_templates(id)
+        )",
+        [],
+    )?;
+
+    // Few-shot examples of a template, sent as earlier conversation turns
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS template_examples (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            template_id INTEGER NOT NULL,
+            image_file TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            output TEXT NOT NULL,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+            FOREIGN KEY (template_id) REFERENCES prompt_templates(id)
+        )",
+        [],
+    )?;
+
+    // Domain terms fixed in recognized text, misrecognized spelling -> preferred one
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS glossary_terms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            term TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            preferred TEXT NOT NULL,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+        )",
+        [],
+    )?;
+
+    // Regex find/replace rules run over every successful result
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS postprocess_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            replacement TEXT NOT NULL DEFAULT '',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+        )",
+        [],
+    )?;
+
+    // Profiles partition configs, templates and settings; NULL owner means shared
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            default_config_id INTEGER,
+            default_template_id INTEGER,
+            is_active INTEGER DEFAULT 0,
+            created_at TEXT DEFAULT (datetime('now', 'localtime')),
+            updated_at TEXT DEFAULT (datetime('now', 'localtime'))
+        )",
+        [],
+    )?;
+
+    // Typed fields of structured extractions, queryable by field
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS extractions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            history_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            merchant TEXT,
+            date TEXT,
+            currency TEXT,
+            subtotal REAL,
+            tax REAL,
+            total REAL,
+            valid INTEGER NOT NULL,
+            data TEXT NOT NULL,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+            FOREIGN KEY (history_id) REFERENCES recognition_history(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_extractions_kind_date ON extractions (kind, date)",
+        [],
+    )?;
+
+    // Provider responses worth reusing for a while, like model lists
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS provider_cache (
+            config_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            fetched_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+            PRIMARY KEY (config_id, kind),
+            FOREIGN KEY (config_id) REFERENCES model_configs(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Configs, templates and history records deleted here, for sync
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_deletions (
+            kind TEXT NOT NULL,
+            key TEXT NOT NULL,
+            deleted_at TEXT NOT NULL,
+            PRIMARY KEY (kind, key)
+        )",
+        [],
+    )?;
+
+    // Bring tables created by older versions up to date
+    migrate_tables(conn)?;
+    reencrypt_legacy_values(conn)?;
+
     // Create indexes
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_history_created_at ON recognition_history(created_at DESC)",
@@ -108,17 +406,287 @@ fn init_tables(conn: &Connection) -> Result<()> {
         "CREATE INDEX IF NOT EXISTS idx_history_config_id ON recognition_history(config_id)",
         [],
     )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_experiment_runs_experiment_id ON experiment_runs(experiment_id)",
+        [],
+    )?;
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_templates_use_count ON prompt_templates(use_count DESC)",
         [],
     )?;
 
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_prompt_history_use_count ON prompt_history(use_count DESC, last_used_at DESC)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_ab_test_results_ab_test_id ON ab_test_results(ab_test_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_template_usage_template ON template_usage(template_id, created_at DESC)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_template_examples_template ON template_examples(template_id, sort_order)",
+        [],
+    )?;
+
+    // At most one default config. Older versions could leave several, keep the newest.
+    conn.execute(
+        "UPDATE model_configs SET is_default = 0
+         WHERE is_default = 1 AND id != (SELECT MAX(id) FROM model_configs WHERE is_default = 1)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_model_configs_single_default ON model_configs(is_default)
+         WHERE is_default = 1",
+        [],
+    )?;
+
     // Initialize default prompts
     init_default_prompts(conn)?;
 
     Ok(())
 }
 
+fn migrate_tables(conn: &Connection) -> Result<()> {
+    // Per-config throttling
+    add_column_if_missing(conn, "model_configs", "requests_per_minute", "INTEGER")?;
+    add_column_if_missing(conn, "model_configs", "monthly_budget_usd", "REAL")?;
+    add_column_if_missing(conn, "model_configs", "input_price_per_mtok", "REAL")?;
+    add_column_if_missing(conn, "model_configs", "output_price_per_mtok", "REAL")?;
+    add_column_if_missing(conn, "model_configs", "last_validation_status", "TEXT")?;
+    add_column_if_missing(conn, "model_configs", "last_validated_at", "TEXT")?;
+    add_column_if_missing(conn, "model_configs", "redact_sensitive", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "model_configs", "image_detail", "TEXT")?;
+    add_column_if_missing(conn, "model_configs", "reasoning_model", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "model_configs", "thinking_budget_tokens", "INTEGER")?;
+    add_column_if_missing(conn, "model_configs", "organization", "TEXT")?;
+    add_column_if_missing(conn, "model_configs", "project", "TEXT")?;
+    add_column_if_missing(conn, "model_configs", "skip_tls_verify", "INTEGER NOT NULL DEFAULT 0")?;
+
+    // Owning profile of configs and templates
+    add_column_if_missing(conn, "model_configs", "profile_id", "INTEGER")?;
+    add_column_if_missing(conn, "prompt_templates", "profile_id", "INTEGER")?;
+
+    // Edit time of templates, for resolving sync conflicts
+    add_column_if_missing(conn, "prompt_templates", "updated_at", "TEXT")?;
+    conn.execute("UPDATE prompt_templates SET updated_at = created_at WHERE updated_at IS NULL", [])?;
+
+    // Result statistics
+    add_column_if_missing(conn, "recognition_history", "char_count", "INTEGER")?;
+    add_column_if_missing(conn, "recognition_history", "word_count", "INTEGER")?;
+    add_column_if_missing(conn, "recognition_history", "language", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "reading_time_secs", "INTEGER")?;
+
+    // Split token usage. tokens_used keeps the total; older rows only have that total,
+    // so their input/output split stays NULL rather than being guessed.
+    add_column_if_missing(conn, "recognition_history", "input_tokens", "INTEGER")?;
+    add_column_if_missing(conn, "recognition_history", "output_tokens", "INTEGER")?;
+
+    // Original images stored on disk
+    add_column_if_missing(conn, "recognition_history", "image_file", "TEXT")?;
+
+    // Config and parameter snapshot per record; older rows keep NULLs
+    add_column_if_missing(conn, "recognition_history", "provider", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "model_name", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "temperature", "REAL")?;
+    add_column_if_missing(conn, "recognition_history", "top_p", "REAL")?;
+    add_column_if_missing(conn, "recognition_history", "max_tokens", "INTEGER")?;
+    add_column_if_missing(conn, "recognition_history", "app_version", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "status", "TEXT NOT NULL DEFAULT 'success'")?;
+    add_column_if_missing(conn, "recognition_history", "error", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "error_code", "TEXT")?;
+    // Perceptual hash of the image, for spotting images recognized before
+    add_column_if_missing(conn, "recognition_history", "image_hash", "INTEGER")?;
+    // Reasoning of reasoning models, kept when `storeReasoning` is on
+    add_column_if_missing(conn, "recognition_history", "reasoning", "TEXT")?;
+    // Thumbnail of the second image of a comparison
+    add_column_if_missing(conn, "recognition_history", "compare_thumbnail", "TEXT")?;
+    // Latency of streamed recognitions
+    add_column_if_missing(conn, "recognition_history", "ttft_ms", "INTEGER")?;
+    add_column_if_missing(conn, "recognition_history", "tokens_per_second", "REAL")?;
+    // Confidence estimation; low-confidence records are flagged for review
+    add_column_if_missing(conn, "recognition_history", "confidence", "REAL")?;
+    add_column_if_missing(conn, "recognition_history", "needs_review", "INTEGER NOT NULL DEFAULT 0")?;
+    // Review queue of batch recognitions; the reviewer's correction is kept apart from the result
+    add_column_if_missing(conn, "recognition_history", "batch_id", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "review_status", "TEXT")?;
+    add_column_if_missing(conn, "recognition_history", "reviewed_result", "TEXT")?;
+    // Original record of a bulk re-run with another config
+    add_column_if_missing(conn, "recognition_history", "rerun_of", "INTEGER")?;
+    add_column_if_missing(conn, "recognition_history", "tags", "TEXT NOT NULL DEFAULT '[]'")?;
+    // Template whose few-shot examples were sent, so a re-run sends them again
+    add_column_if_missing(
+        conn,
+        "recognition_history",
+        "template_id",
+        "INTEGER REFERENCES prompt_templates(id) ON DELETE SET NULL",
+    )?;
+
+    // `rerun_of` was added without a foreign key, which SQLite can't add later.
+    // Unlink re-runs when their original is deleted, as ON DELETE SET NULL would.
+    conn.execute(
+        "UPDATE recognition_history SET rerun_of = NULL
+         WHERE rerun_of IS NOT NULL AND rerun_of NOT IN (SELECT id FROM recognition_history)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS trg_history_rerun_of_delete AFTER DELETE ON recognition_history
+         BEGIN
+            UPDATE recognition_history SET rerun_of = NULL WHERE rerun_of = OLD.id;
+         END",
+        [],
+    )?;
+
+    // History times were local `YYYY-MM-DD HH:MM:SS` strings, which neither compare
+    // with ISO dates nor sort across DST changes. Store them as UTC ISO-8601.
+    conn.execute(
+        "UPDATE recognition_history SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc')
+         WHERE created_at NOT LIKE '%T%' AND strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') IS NOT NULL",
+        [],
+    )?;
+
+    // Id of each record across machines. Older records get one derived from
+    // their creation time and config name, the same on every machine.
+    add_column_if_missing(conn, "recognition_history", "uuid", "TEXT")?;
+    let missing: Vec<(i64, String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, created_at, config_name FROM recognition_history WHERE uuid IS NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+    for (id, created_at, config_name) in missing {
+        let uuid = super::history::legacy_uuid(&created_at, &config_name);
+        // Two records of the same second and config: only the first keeps the derived id
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM recognition_history WHERE uuid = ?1)",
+            [&uuid],
+            |row| row.get(0),
+        )?;
+        let uuid = if taken { uuid::Uuid::new_v4().to_string() } else { uuid };
+        conn.execute("UPDATE recognition_history SET uuid = ?1 WHERE id = ?2", rusqlite::params![uuid, id])?;
+    }
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_history_uuid ON recognition_history(uuid)", [])?;
+
+    // Edit times of configs and templates decide sync conflicts, so they move
+    // to UTC ISO-8601 like history times
+    for table in ["model_configs", "prompt_templates"] {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at, 'utc')
+                 WHERE updated_at NOT LIKE '%T%' AND strftime('%Y-%m-%dT%H:%M:%SZ', updated_at, 'utc') IS NOT NULL",
+                table
+            ),
+            [],
+        )?;
+    }
+
+    // Budgets count every provider call, including ones never saved to history.
+    // A new ledger starts from the spend history already shows.
+    let has_ledger: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'usage_ledger')",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            config_id INTEGER NOT NULL,
+            input_tokens INTEGER,
+            output_tokens INTEGER,
+            tokens_used INTEGER,
+            created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+        )",
+        [],
+    )?;
+    if !has_ledger {
+        conn.execute(
+            "INSERT INTO usage_ledger (config_id, input_tokens, output_tokens, tokens_used, created_at)
+             SELECT config_id, input_tokens, output_tokens, tokens_used, created_at FROM recognition_history
+             WHERE config_id IS NOT NULL
+                AND COALESCE(input_tokens, output_tokens, tokens_used) IS NOT NULL",
+            [],
+        )?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_ledger_config ON usage_ledger(config_id, created_at)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Settings key recording that values of older versions were moved off the built-in key
+const DATA_KEY_MIGRATED: &str = "security:dataKeyMigrated";
+
+/// Older versions encrypted API keys, secrets and history with a key compiled
+/// into the app. Re-encrypt them once with the install's own key.
+fn reencrypt_legacy_values(conn: &Connection) -> Result<()> {
+    let migrated: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM app_settings WHERE key = ?1)",
+        [DATA_KEY_MIGRATED],
+        |row| row.get(0),
+    )?;
+    if migrated {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let columns = [
+        ("model_configs", "id", "api_key_encrypted"),
+        ("app_settings", "key", "value"),
+        ("recognition_history", "id", "prompt"),
+        ("recognition_history", "id", "result"),
+        ("recognition_history", "id", "reasoning"),
+        ("recognition_history", "id", "reviewed_result"),
+    ];
+    for (table, id_column, column) in columns {
+        let values: Vec<(rusqlite::types::Value, String)> = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {id}, {column} FROM {table} WHERE {column} IS NOT NULL",
+                id = id_column,
+                column = column,
+                table = table
+            ))?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        for (id, value) in values {
+            if let Some(reencrypted) = crypto::reencrypt_legacy(&value) {
+                tx.execute(
+                    &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, id_column),
+                    rusqlite::params![reencrypted, id],
+                )?;
+            }
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, '1')",
+        [DATA_KEY_MIGRATED],
+    )?;
+    tx.commit()
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
 fn init_default_prompts(conn: &Connection) -> Result<()> {
     let count: i32 = conn.query_row(
         "SELECT COUNT(*) FROM prompt_templates",
@@ -127,14 +695,53 @@ fn init_default_prompts(conn: &Connection) -> Result<()> {
     )?;
 
     if count == 0 {
-        let mut stmt = conn.prepare(
-            "INSERT INTO prompt_templates (name, content, is_default) VALUES (?1, ?2, ?3)"
-        )?;
-
-        for (name, content, is_default) in DEFAULT_PROMPTS {
-            stmt.execute([*name, *content, if *is_default { "1" } else { "0" }])?;
-        }
+        insert_missing_prompts(conn, default_prompts("zh-CN"))?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_in_memory() {
+        let conn = open_database(Path::new(IN_MEMORY)).unwrap();
+        let foreign_keys: i64 = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+        assert_eq!(foreign_keys, 1);
+        let configs: i64 = conn.query_row("SELECT COUNT(*) FROM model_configs", [], |row| row.get(0)).unwrap();
+        assert_eq!(configs, 0);
+        assert_eq!(conn.path(), Some(""));
+    }
+
+    #[test]
+    fn test_history_links_cleared_on_delete() {
+        let conn = open_database(Path::new(IN_MEMORY)).unwrap();
+        conn.execute(
+            "INSERT INTO model_configs (name, provider, api_url, api_key_encrypted, model_name)
+             VALUES ('c', 'openai', 'http://localhost', '', 'gpt')",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO prompt_templates (name, content) VALUES ('t', 'p')", []).unwrap();
+        let template_id = conn.last_insert_rowid();
+        let insert = "INSERT INTO recognition_history (config_id, config_name, prompt, result, rerun_of, template_id)
+             VALUES (1, 'c', 'p', 'r', ?1, ?2)";
+        conn.execute(insert, rusqlite::params![None::<i64>, None::<i64>]).unwrap();
+        let original = conn.last_insert_rowid();
+        conn.execute(insert, rusqlite::params![original, template_id]).unwrap();
+        let rerun = conn.last_insert_rowid();
+
+        conn.execute("DELETE FROM recognition_history WHERE id = ?1", [original]).unwrap();
+        conn.execute("DELETE FROM prompt_templates WHERE id = ?1", [template_id]).unwrap();
+        let links: (Option<i64>, Option<i64>) = conn
+            .query_row(
+                "SELECT rerun_of, template_id FROM recognition_history WHERE id = ?1",
+                [rerun],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(links, (None, None));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::db::get_connection;
+use serde::Serialize;
+use rusqlite::{params, Connection, Result};
+
+const DEFAULT_PROMPTS_ZH: &[(&str, &str, bool)] = &[
+    ("通用识别", "请识别这张图片的内容，并用中文详细描述。", true),
+    ("文字提取", "请提取图片中的所有文字内容，保持原有格式。", false),
+    ("表格识别", "请识别图片中的表格，并以 Markdown 格式输出。", false),
+    ("代码识别", "请识别图片中的代码，保持原有格式和缩进。", false),
+    ("公式识别", "请识别图片中的数学公式，并以 LaTeX 格式输出。", false),
+];
+
+const DEFAULT_PROMPTS_EN: &[(&str, &str, bool)] = &[
+    ("General", "Describe the content of this image in detail.", true),
+    ("Text Extraction", "Extract all text from the image, keeping the original formatting.", false),
+    ("Table", "Recognize the table in the image and output it as Markdown.", false),
+    ("Code", "Transcribe the code in the image, keeping the original formatting and indentation.", false),
+    ("Formula", "Recognize the math formulas in the image and output them as LaTeX.", false),
+];
+
+/// (name, provider, api_url, model_name) of the disabled configs seeded on first run
+const PLACEHOLDER_CONFIGS: &[(&str, &str, &str, &str)] = &[
+    ("OpenAI", "openai", "https://api.openai.com/v1/chat/completions", "gpt-4o"),
+    ("Anthropic", "anthropic", "https://api.anthropic.com/v1/messages", "claude-3-5-sonnet-20241022"),
+];
+
+/// Default prompt templates for a UI language, falling back to Chinese
+pub fn default_prompts(language: &str) -> &'static [(&'static str, &'static str, bool)] {
+    match language {
+        "en-US" => DEFAULT_PROMPTS_EN,
+        _ => DEFAULT_PROMPTS_ZH,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializationReport {
+    /// No configs and no history existed before this call
+    pub fresh_install: bool,
+    pub configs_created: usize,
+    pub templates_created: usize,
+}
+
+/// Seed first-run data: placeholder configs on a fresh install, plus any
+/// default templates of `language` that are missing. Safe to call repeatedly.
+pub fn initialize_defaults(language: &str) -> Result<InitializationReport> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+
+    let fresh_install = count(&tx, "model_configs")? == 0 && count(&tx, "recognition_history")? == 0;
+
+    let mut configs_created = 0;
+    if fresh_install {
+        // Placeholders stay disabled until the user fills in a key
+        let mut stmt = tx.prepare(
+            "INSERT INTO model_configs (name, provider, api_url, api_key_encrypted, model_name, is_active, is_default, updated_at)
+             VALUES (?1, ?2, ?3, '', ?4, 0, 0, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))"
+        )?;
+        for (name, provider, api_url, model_name) in PLACEHOLDER_CONFIGS {
+            stmt.execute(params![name, provider, api_url, model_name])?;
+            configs_created += 1;
+        }
+    }
+
+    let templates_created = insert_missing_prompts(&tx, default_prompts(language))?;
+    tx.commit()?;
+
+    Ok(InitializationReport {
+        fresh_install,
+        configs_created,
+        templates_created,
+    })
+}
+
+/// Insert templates whose name is not taken yet; the default flag is only
+/// honoured when no template is marked default
+pub(crate) fn insert_missing_prompts(conn: &Connection, prompts: &[(&str, &str, bool)]) -> Result<usize> {
+    let mut has_default: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM prompt_templates WHERE is_default = 1)",
+        [],
+        |row| row.get(0),
+    )?;
+
+    let mut created = 0;
+    for (name, content, is_default) in prompts {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM prompt_templates WHERE name = ?1)",
+            [name],
+            |row| row.get(0),
+        )?;
+        if exists {
+            continue;
+        }
+
+        let make_default = *is_default && !has_default;
+        conn.execute(
+            "INSERT INTO prompt_templates (name, content, is_default, updated_at) VALUES (?1, ?2, ?3, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
+            params![name, content, if make_default { 1 } else { 0 }],
+        )?;
+        has_default |= make_default;
+        created += 1;
+    }
+
+    Ok(created)
+}
+
+fn count(conn: &Connection, table: &str) -> Result<i64> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+}
@@ -0,0 +1,225 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Experiment {
+    pub id: i64,
+    pub name: String,
+    pub config_id: i64,
+    pub template_a_id: i64,
+    pub template_b_id: i64,
+    pub image_paths: Vec<String>,
+    pub status: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentInput {
+    pub name: String,
+    pub config_id: i64,
+    pub template_a_id: i64,
+    pub template_b_id: i64,
+    pub image_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentRun {
+    pub id: i64,
+    pub experiment_id: i64,
+    pub image_path: String,
+    /// "a" or "b"
+    pub variant: String,
+    pub success: bool,
+    pub content: Option<String>,
+    pub error: Option<String>,
+    pub tokens_used: Option<i32>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub duration_ms: Option<i32>,
+    pub char_count: Option<i32>,
+    pub rating: Option<i32>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExperimentRunInput {
+    pub experiment_id: i64,
+    pub image_path: String,
+    pub variant: String,
+    pub success: bool,
+    pub content: Option<String>,
+    pub error: Option<String>,
+    pub tokens_used: Option<i32>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub duration_ms: Option<i32>,
+    pub char_count: Option<i32>,
+}
+
+const EXPERIMENT_COLUMNS: &str =
+    "id, name, config_id, template_a_id, template_b_id, image_paths, status, created_at";
+
+const RUN_COLUMNS: &str = "id, experiment_id, image_path, variant, success, content, error, \
+    tokens_used, input_tokens, output_tokens, duration_ms, char_count, rating, created_at";
+
+fn row_to_experiment(row: &rusqlite::Row) -> Result<Experiment> {
+    let image_paths: String = row.get("image_paths")?;
+    Ok(Experiment {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        config_id: row.get("config_id")?,
+        template_a_id: row.get("template_a_id")?,
+        template_b_id: row.get("template_b_id")?,
+        image_paths: serde_json::from_str(&image_paths).unwrap_or_default(),
+        status: row.get("status")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+fn row_to_run(row: &rusqlite::Row) -> Result<ExperimentRun> {
+    Ok(ExperimentRun {
+        id: row.get("id")?,
+        experiment_id: row.get("experiment_id")?,
+        image_path: row.get("image_path")?,
+        variant: row.get("variant")?,
+        success: row.get::<_, i32>("success")? == 1,
+        content: row.get("content")?,
+        error: row.get("error")?,
+        tokens_used: row.get("tokens_used")?,
+        input_tokens: row.get("input_tokens")?,
+        output_tokens: row.get("output_tokens")?,
+        duration_ms: row.get("duration_ms")?,
+        char_count: row.get("char_count")?,
+        rating: row.get("rating")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+pub fn get_all_experiments() -> Result<Vec<Experiment>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM experiments ORDER BY created_at DESC",
+        EXPERIMENT_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([], row_to_experiment)?;
+    rows.collect()
+}
+
+pub fn get_experiment_by_id(id: i64) -> Result<Option<Experiment>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM experiments WHERE id = ?1",
+        EXPERIMENT_COLUMNS
+    ))?;
+
+    match stmt.query_row([id], row_to_experiment) {
+        Ok(experiment) => Ok(Some(experiment)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn create_experiment(input: ExperimentInput) -> Result<Experiment> {
+    let conn = get_connection().lock();
+    let image_paths = serde_json::to_string(&input.image_paths).unwrap_or_else(|_| "[]".to_string());
+
+    conn.execute(
+        "INSERT INTO experiments (name, config_id, template_a_id, template_b_id, image_paths)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            input.name,
+            input.config_id,
+            input.template_a_id,
+            input.template_b_id,
+            image_paths,
+        ],
+    )?;
+
+    let id = conn.last_insert_rowid();
+    drop(conn);
+
+    get_experiment_by_id(id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+pub fn delete_experiment(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM experiments WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
+
+pub fn set_experiment_status(id: i64, status: &str) -> Result<()> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "UPDATE experiments SET status = ?1 WHERE id = ?2",
+        params![status, id],
+    )?;
+    Ok(())
+}
+
+/// Drop results of a previous run so re-running starts from a clean slate
+pub fn clear_experiment_runs(experiment_id: i64) -> Result<usize> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "DELETE FROM experiment_runs WHERE experiment_id = ?1",
+        [experiment_id],
+    )
+}
+
+/// Drop failed runs so resuming the experiment retries them
+pub fn clear_failed_runs(experiment_id: i64) -> Result<usize> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "DELETE FROM experiment_runs WHERE experiment_id = ?1 AND success = 0",
+        [experiment_id],
+    )
+}
+
+pub fn create_experiment_run(input: ExperimentRunInput) -> Result<i64> {
+    let conn = get_connection().lock();
+
+    conn.execute(
+        "INSERT INTO experiment_runs (experiment_id, image_path, variant, success, content, error,
+            tokens_used, input_tokens, output_tokens, duration_ms, char_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            input.experiment_id,
+            input.image_path,
+            input.variant,
+            if input.success { 1 } else { 0 },
+            input.content,
+            input.error,
+            input.tokens_used,
+            input.input_tokens,
+            input.output_tokens,
+            input.duration_ms,
+            input.char_count,
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn get_experiment_runs(experiment_id: i64) -> Result<Vec<ExperimentRun>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM experiment_runs WHERE experiment_id = ?1 ORDER BY image_path, variant",
+        RUN_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([experiment_id], row_to_run)?;
+    rows.collect()
+}
+
+pub fn rate_experiment_run(run_id: i64, rating: Option<i32>) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute(
+        "UPDATE experiment_runs SET rating = ?1 WHERE id = ?2",
+        params![rating, run_id],
+    )?;
+    Ok(changes > 0)
+}
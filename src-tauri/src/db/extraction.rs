@@ -0,0 +1,176 @@
+use crate::db::{get_connection, settings, PaginatedResult};
+use crate::services::extraction::{BusinessCard, Extraction, ExtractionKind, Receipt};
+use crate::services::redaction;
+use crate::utils::crypto::{decrypt_tagged, encrypt_tagged};
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// Stored extraction of a history record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionRecord {
+    pub id: i64,
+    pub history_id: i64,
+    pub kind: ExtractionKind,
+    pub receipt: Option<Receipt>,
+    pub business_card: Option<BusinessCard>,
+    pub issues: Vec<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionQueryParams {
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+    pub kind: Option<ExtractionKind>,
+    /// Match against the merchant name
+    pub merchant: Option<String>,
+    pub currency: Option<String>,
+    /// Inclusive ISO date range
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub min_total: Option<f64>,
+    pub max_total: Option<f64>,
+    /// Only extractions that passed validation
+    pub valid_only: Option<bool>,
+}
+
+const EXTRACTION_COLUMNS: &str = "id, history_id, data, created_at";
+
+fn row_to_record(row: &rusqlite::Row) -> Result<ExtractionRecord> {
+    let data: String = row.get("data")?;
+    let data = decrypt_tagged(&data)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, e.into()))?;
+    let extraction: Extraction = serde_json::from_str(&data)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+    Ok(ExtractionRecord {
+        id: row.get("id")?,
+        history_id: row.get("history_id")?,
+        kind: extraction.kind,
+        receipt: extraction.receipt,
+        business_card: extraction.business_card,
+        issues: extraction.issues,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Store the extraction of a history record. The fields are also kept in
+/// their own columns so they can be filtered on. Personal data is masked and
+/// the JSON encrypted like the history text, following the settings.
+pub fn save_extraction(history_id: i64, extraction: &Extraction) -> Result<i64> {
+    let app_settings = settings::get_all_settings()?;
+    let scrubbed;
+    let extraction = if app_settings.scrub_history_pii {
+        scrubbed = redaction::scrub_extraction(extraction);
+        &scrubbed
+    } else {
+        extraction
+    };
+    let data = serde_json::to_string(extraction)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let data = if app_settings.encrypt_history { encrypt_tagged(&data) } else { data };
+    // Business cards have no typed columns besides the kind
+    let receipt = extraction.receipt.clone().unwrap_or_default();
+    // Unreadable dates stay in the JSON only, they would break range queries
+    let date = receipt.date.as_ref().filter(|_| !extraction.issues.iter().any(|issue| issue == "invalid_date"));
+
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO extractions (history_id, kind, merchant, date, currency, subtotal, tax, total, valid, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            history_id,
+            extraction.kind.as_str(),
+            receipt.merchant,
+            date,
+            receipt.currency,
+            receipt.subtotal,
+            receipt.tax,
+            receipt.total,
+            extraction.is_valid() as i32,
+            data,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn get_extraction_by_history(history_id: i64) -> Result<Option<ExtractionRecord>> {
+    let conn = get_connection().lock();
+    match conn.query_row(
+        &format!("SELECT {} FROM extractions WHERE history_id = ?1 ORDER BY id DESC LIMIT 1", EXTRACTION_COLUMNS),
+        [history_id],
+        row_to_record,
+    ) {
+        Ok(record) => Ok(Some(record)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn query_extractions(params: ExtractionQueryParams) -> Result<PaginatedResult<ExtractionRecord>> {
+    let conn = get_connection().lock();
+
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params.page_size.unwrap_or(20).max(1);
+    let offset = (page - 1) * page_size;
+
+    let mut where_clauses = vec!["1 = 1"];
+    let mut bind_values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(kind) = params.kind {
+        where_clauses.push("kind = ?");
+        bind_values.push(Box::new(kind.as_str()));
+    }
+    if let Some(merchant) = params.merchant.as_deref().map(str::trim).filter(|m| !m.is_empty()) {
+        where_clauses.push("merchant LIKE ?");
+        bind_values.push(Box::new(format!("%{}%", merchant)));
+    }
+    if let Some(currency) = params.currency.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        where_clauses.push("currency = ?");
+        bind_values.push(Box::new(currency.to_uppercase()));
+    }
+    if let Some(date_from) = params.date_from {
+        where_clauses.push("date >= ?");
+        bind_values.push(Box::new(date_from));
+    }
+    if let Some(date_to) = params.date_to {
+        where_clauses.push("date <= ?");
+        bind_values.push(Box::new(date_to));
+    }
+    if let Some(min_total) = params.min_total {
+        where_clauses.push("total >= ?");
+        bind_values.push(Box::new(min_total));
+    }
+    if let Some(max_total) = params.max_total {
+        where_clauses.push("total <= ?");
+        bind_values.push(Box::new(max_total));
+    }
+    if params.valid_only.unwrap_or(false) {
+        where_clauses.push("valid = 1");
+    }
+
+    let where_sql = format!("WHERE {}", where_clauses.join(" AND "));
+
+    let count_sql = format!("SELECT COUNT(*) FROM extractions {}", where_sql);
+    let count_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let total: i64 = conn.query_row(&count_sql, count_params.as_slice(), |row| row.get(0))?;
+
+    let query_sql = format!(
+        "SELECT {} FROM extractions {} ORDER BY date DESC, id DESC LIMIT ? OFFSET ?",
+        EXTRACTION_COLUMNS, where_sql
+    );
+    bind_values.push(Box::new(page_size));
+    bind_values.push(Box::new(offset));
+
+    let query_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let mut stmt = conn.prepare(&query_sql)?;
+    let rows = stmt.query_map(query_params.as_slice(), row_to_record)?;
+
+    Ok(PaginatedResult {
+        records: rows.collect::<Result<_>>()?,
+        total,
+        page,
+        page_size,
+    })
+}
@@ -0,0 +1,57 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// A domain term and how it should be spelled. After recognition, occurrences
+/// of `term` are replaced with `preferred`, see `services::glossary`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryEntry {
+    pub id: i64,
+    /// Spelling the model tends to produce, matched case-insensitively
+    pub term: String,
+    pub preferred: String,
+    pub created_at: String,
+}
+
+const GLOSSARY_COLUMNS: &str = "id, term, preferred, created_at";
+
+fn row_to_entry(row: &rusqlite::Row) -> Result<GlossaryEntry> {
+    Ok(GlossaryEntry {
+        id: row.get("id")?,
+        term: row.get("term")?,
+        preferred: row.get("preferred")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+pub fn get_entries() -> Result<Vec<GlossaryEntry>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM glossary_terms ORDER BY term COLLATE NOCASE",
+        GLOSSARY_COLUMNS
+    ))?;
+    let entries = stmt.query_map([], row_to_entry)?.collect();
+    entries
+}
+
+/// Add a term, or change the preferred spelling of an existing one
+pub fn save_entry(term: &str, preferred: &str) -> Result<GlossaryEntry> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO glossary_terms (term, preferred) VALUES (?1, ?2)
+         ON CONFLICT(term) DO UPDATE SET preferred = excluded.preferred",
+        params![term, preferred],
+    )?;
+    conn.query_row(
+        &format!("SELECT {} FROM glossary_terms WHERE term = ?1", GLOSSARY_COLUMNS),
+        [term],
+        row_to_entry,
+    )
+}
+
+pub fn delete_entry(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM glossary_terms WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
@@ -1,5 +1,12 @@
-use crate::db::get_connection;
+use crate::db::{escape_like, get_connection, settings, sync, PaginatedResult};
+use crate::services::image::hash_distance;
+use crate::services::redaction::scrub_text;
+use crate::services::image_store::{self, ImageFileState};
+use crate::services::text_stats::TextStats;
+use crate::utils::crypto::{decrypt_tagged, encrypt_tagged, is_tagged, tagged_like_pattern};
+use crate::utils::time;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use rusqlite::{params, Result};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,12 +16,61 @@ pub struct HistoryRecord {
     pub config_id: i64,
     pub config_name: String,
     pub image_path: Option<String>,
+    /// File name of the original image inside the app's image store
+    pub image_file: Option<String>,
     pub image_thumbnail: Option<String>,
+    /// Thumbnail of the second image when the record compares two images
+    pub compare_thumbnail: Option<String>,
     pub prompt: String,
     pub result: String,
+    /// Reasoning returned apart from the result, when `storeReasoning` was on
+    pub reasoning: Option<String>,
     pub tokens_used: Option<i32>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
     pub duration_ms: Option<i32>,
+    /// Time to first token and streaming throughput, for streamed recognitions
+    pub ttft_ms: Option<i32>,
+    pub tokens_per_second: Option<f64>,
+    pub char_count: Option<i32>,
+    pub word_count: Option<i32>,
+    pub language: Option<String>,
+    pub reading_time_secs: Option<i32>,
+    /// Snapshot of the config and parameters used, so later config edits don't rewrite history.
+    /// Empty for records created before snapshots were stored.
+    pub provider: Option<String>,
+    pub model_name: Option<String>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<i32>,
+    pub app_version: Option<String>,
+    /// "success", "failed" or "partial" (cancelled mid-stream); failed records keep the image so they can be retried
+    pub status: String,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    /// Estimated transcription confidence from 0 to 1, when it was scored
+    pub confidence: Option<f64>,
+    /// Scored below the low-confidence threshold and not yet marked reviewed
+    #[serde(default)]
+    pub needs_review: bool,
+    /// Batch the record was recognized in, e.g. `--batch` of the command line
+    pub batch_id: Option<String>,
+    /// "pending", "approved" or "rejected" for records in the review queue
+    pub review_status: Option<String>,
+    /// Result text as corrected by the reviewer; `result` keeps the model's answer
+    pub reviewed_result: Option<String>,
+    /// Record this one re-ran with another config
+    pub rerun_of: Option<i64>,
+    /// Tags of the workspace the record was recognized in
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Template whose few-shot examples were sent
+    #[serde(default)]
+    pub template_id: Option<i64>,
     pub created_at: String,
+    /// Identifies the record across machines for sync and imports
+    #[serde(default)]
+    pub uuid: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,11 +78,43 @@ pub struct HistoryRecord {
 pub struct HistoryInput {
     pub config_id: i64,
     pub config_name: String,
+    /// Original file the image was opened from, if any
+    pub image_path: Option<String>,
+    pub image_file: Option<String>,
     pub image_thumbnail: Option<String>,
+    pub compare_thumbnail: Option<String>,
     pub prompt: String,
     pub result: String,
+    pub reasoning: Option<String>,
     pub tokens_used: Option<i32>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
     pub duration_ms: Option<i32>,
+    pub ttft_ms: Option<i32>,
+    pub tokens_per_second: Option<f64>,
+    pub stats: TextStats,
+    pub provider: String,
+    pub model_name: String,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: i32,
+    pub app_version: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    /// Perceptual hash of the image, see `image::perceptual_hash`
+    pub image_hash: Option<u64>,
+    pub confidence: Option<f64>,
+    pub needs_review: bool,
+    pub batch_id: Option<String>,
+    pub review_status: Option<String>,
+    pub reviewed_result: Option<String>,
+    pub rerun_of: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub template_id: Option<i64>,
+    /// Id of a record made elsewhere; new records get a random one
+    pub uuid: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -36,46 +124,202 @@ pub struct HistoryQueryParams {
     pub page_size: Option<i32>,
     pub config_id: Option<i64>,
     pub keyword: Option<String>,
+    /// Local calendar days (`YYYY-MM-DD`), both inclusive
     pub start_date: Option<String>,
     pub end_date: Option<String>,
+    /// Unix epoch milliseconds, both inclusive; take precedence over the dates
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    /// "success", "failed" or "partial"; all records when unset
+    pub status: Option<String>,
+    /// Only records flagged (or not flagged) for review
+    pub needs_review: Option<bool>,
+    pub batch_id: Option<String>,
+    /// "pending", "approved" or "rejected"
+    pub review_status: Option<String>,
+    /// Return only these record fields (camelCase); `id` is always included
+    pub fields: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct HistoryPaginatedResult {
-    pub records: Vec<HistoryRecord>,
-    pub total: i64,
-    pub page: i32,
-    pub page_size: i32,
-}
-
-fn row_to_record(
-    id: i64,
-    config_id: i64,
-    config_name: String,
-    image_path: Option<String>,
-    image_thumbnail: Option<String>,
-    prompt: String,
-    result: String,
-    tokens_used: Option<i32>,
-    duration_ms: Option<i32>,
-    created_at: String,
-) -> HistoryRecord {
-    HistoryRecord {
-        id,
-        config_id,
-        config_name,
-        image_path,
-        image_thumbnail,
-        prompt,
-        result,
-        tokens_used,
-        duration_ms,
-        created_at,
+pub type HistoryPaginatedResult<T = HistoryRecord> = PaginatedResult<T>;
+
+/// A history record restricted to the requested fields
+pub type HistoryFields = serde_json::Map<String, serde_json::Value>;
+
+/// A history page with either full records or only the requested fields
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum HistoryPage {
+    Full(HistoryPaginatedResult),
+    Partial(HistoryPaginatedResult<HistoryFields>),
+}
+
+const HISTORY_COLUMNS: &str = "id, config_id, config_name, image_path, image_file, image_thumbnail, compare_thumbnail, \
+    prompt, result, reasoning, tokens_used, input_tokens, output_tokens, duration_ms, ttft_ms, tokens_per_second, char_count, word_count, \
+    language, reading_time_secs, provider, model_name, temperature, top_p, max_tokens, app_version, status, error, error_code, confidence, needs_review, batch_id, review_status, reviewed_result, rerun_of, tags, template_id, created_at, uuid";
+
+/// Record fields that can be selected individually, with their columns
+const HISTORY_FIELDS: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("configId", "config_id"),
+    ("configName", "config_name"),
+    ("imagePath", "image_path"),
+    ("imageFile", "image_file"),
+    ("imageThumbnail", "image_thumbnail"),
+    ("compareThumbnail", "compare_thumbnail"),
+    ("prompt", "prompt"),
+    ("result", "result"),
+    ("reasoning", "reasoning"),
+    ("tokensUsed", "tokens_used"),
+    ("inputTokens", "input_tokens"),
+    ("outputTokens", "output_tokens"),
+    ("durationMs", "duration_ms"),
+    ("ttftMs", "ttft_ms"),
+    ("tokensPerSecond", "tokens_per_second"),
+    ("charCount", "char_count"),
+    ("wordCount", "word_count"),
+    ("language", "language"),
+    ("readingTimeSecs", "reading_time_secs"),
+    ("provider", "provider"),
+    ("modelName", "model_name"),
+    ("temperature", "temperature"),
+    ("topP", "top_p"),
+    ("maxTokens", "max_tokens"),
+    ("appVersion", "app_version"),
+    ("status", "status"),
+    ("error", "error"),
+    ("errorCode", "error_code"),
+    ("confidence", "confidence"),
+    ("needsReview", "needs_review"),
+    ("batchId", "batch_id"),
+    ("reviewStatus", "review_status"),
+    ("reviewedResult", "reviewed_result"),
+    ("rerunOf", "rerun_of"),
+    ("tags", "tags"),
+    ("templateId", "template_id"),
+    ("createdAt", "created_at"),
+    ("uuid", "uuid"),
+];
+
+/// Columns encrypted at rest when `encryptHistory` is on
+const ENCRYPTED_COLUMNS: [&str; 4] = ["prompt", "result", "reasoning", "reviewed_result"];
+
+/// Review states of records in the review queue
+pub const REVIEW_STATUSES: [&str; 3] = ["pending", "approved", "rejected"];
+
+/// Plain text of a prompt or result column. Values that fail to decrypt, e.g.
+/// after the key changed, are shown as stored rather than failing the query.
+fn open_text(value: String) -> String {
+    match decrypt_tagged(&value) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("[History] Failed to decrypt a record: {}", e);
+            value
+        }
     }
 }
 
+fn row_to_record(row: &rusqlite::Row) -> Result<HistoryRecord> {
+    Ok(HistoryRecord {
+        id: row.get("id")?,
+        config_id: row.get("config_id")?,
+        config_name: row.get("config_name")?,
+        image_path: row.get("image_path")?,
+        image_file: row.get("image_file")?,
+        image_thumbnail: row.get("image_thumbnail")?,
+        compare_thumbnail: row.get("compare_thumbnail")?,
+        prompt: open_text(row.get("prompt")?),
+        result: open_text(row.get("result")?),
+        reasoning: row.get::<_, Option<String>>("reasoning")?.map(open_text),
+        tokens_used: row.get("tokens_used")?,
+        input_tokens: row.get("input_tokens")?,
+        output_tokens: row.get("output_tokens")?,
+        duration_ms: row.get("duration_ms")?,
+        ttft_ms: row.get("ttft_ms")?,
+        tokens_per_second: row.get("tokens_per_second")?,
+        char_count: row.get("char_count")?,
+        word_count: row.get("word_count")?,
+        language: row.get("language")?,
+        reading_time_secs: row.get("reading_time_secs")?,
+        provider: row.get("provider")?,
+        model_name: row.get("model_name")?,
+        temperature: row.get("temperature")?,
+        top_p: row.get("top_p")?,
+        max_tokens: row.get("max_tokens")?,
+        app_version: row.get("app_version")?,
+        status: row.get("status")?,
+        error: row.get("error")?,
+        error_code: row.get("error_code")?,
+        confidence: row.get("confidence")?,
+        needs_review: row.get("needs_review")?,
+        batch_id: row.get("batch_id")?,
+        review_status: row.get("review_status")?,
+        reviewed_result: row.get::<_, Option<String>>("reviewed_result")?.map(open_text),
+        rerun_of: row.get("rerun_of")?,
+        tags: serde_json::from_str(&row.get::<_, String>("tags")?).unwrap_or_default(),
+        template_id: row.get("template_id")?,
+        created_at: row.get("created_at")?,
+        uuid: row.get("uuid")?,
+    })
+}
+
+/// Resolve requested field names to (field, column) pairs, always including `id`
+fn resolve_fields(fields: &[String]) -> Result<Vec<(&'static str, &'static str)>> {
+    let mut selected = vec![HISTORY_FIELDS[0]];
+    for field in fields {
+        let entry = HISTORY_FIELDS
+            .iter()
+            .find(|(name, _)| name == field)
+            .ok_or_else(|| rusqlite::Error::InvalidColumnName(field.clone()))?;
+        if !selected.contains(entry) {
+            selected.push(*entry);
+        }
+    }
+    Ok(selected)
+}
+
+fn row_to_fields(row: &rusqlite::Row, fields: &[(&str, &str)]) -> Result<HistoryFields> {
+    use rusqlite::types::ValueRef;
+
+    let mut map = HistoryFields::new();
+    for (name, column) in fields {
+        let value = match row.get_ref(*column)? {
+            ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+            ValueRef::Integer(i) => i.into(),
+            ValueRef::Real(f) => f.into(),
+            ValueRef::Text(t) if *column == "tags" => serde_json::from_slice(t).unwrap_or_default(),
+            ValueRef::Text(t) if ENCRYPTED_COLUMNS.contains(column) => {
+                open_text(String::from_utf8_lossy(t).into_owned()).into()
+            }
+            ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned().into(),
+        };
+        map.insert(name.to_string(), value);
+    }
+    Ok(map)
+}
+
 pub fn get_history_records(params: HistoryQueryParams) -> Result<HistoryPaginatedResult> {
+    query_history(&params, HISTORY_COLUMNS, row_to_record)
+}
+
+/// Query a page of history, honouring `params.fields` when it is set
+pub fn get_history_page(params: HistoryQueryParams) -> Result<HistoryPage> {
+    match params.fields {
+        Some(ref fields) => {
+            let selected = resolve_fields(fields)?;
+            let columns: Vec<&str> = selected.iter().map(|(_, column)| *column).collect();
+            let page = query_history(&params, &columns.join(", "), |row| row_to_fields(row, &selected))?;
+            Ok(HistoryPage::Partial(page))
+        }
+        None => Ok(HistoryPage::Full(get_history_records(params)?)),
+    }
+}
+
+fn query_history<T>(
+    params: &HistoryQueryParams,
+    columns: &str,
+    map_row: impl FnMut(&rusqlite::Row) -> Result<T>,
+) -> Result<HistoryPaginatedResult<T>> {
     let conn = get_connection().lock();
     
     let page = params.page.unwrap_or(1);
@@ -91,20 +335,52 @@ pub fn get_history_records(params: HistoryQueryParams) -> Result<HistoryPaginate
     }
     
     if let Some(ref keyword) = params.keyword {
-        where_clauses.push("(prompt LIKE ? OR result LIKE ?)");
-        let pattern = format!("%{}%", keyword);
+        // Encrypted records can't be matched in SQL, they are decrypted and matched below
+        where_clauses.push(
+            "(prompt LIKE ? ESCAPE '\\' OR result LIKE ? ESCAPE '\\' OR prompt LIKE ? OR result LIKE ?)",
+        );
+        let pattern = format!("%{}%", escape_like(keyword));
         bind_values.push(Box::new(pattern.clone()));
         bind_values.push(Box::new(pattern));
+        bind_values.push(Box::new(tagged_like_pattern()));
+        bind_values.push(Box::new(tagged_like_pattern()));
     }
     
-    if let Some(ref start_date) = params.start_date {
+    // created_at is UTC ISO-8601 of fixed width, so bounds compare as strings
+    if let Some(start) = params.start_time {
         where_clauses.push("created_at >= ?");
-        bind_values.push(Box::new(start_date.clone()));
+        bind_values.push(Box::new(time_bound(time::millis_to_utc_iso(start), "startTime")?));
+    } else if let Some(ref start_date) = params.start_date {
+        where_clauses.push("created_at >= ?");
+        bind_values.push(Box::new(time_bound(time::local_day_start_utc_iso(start_date), "startDate")?));
     }
     
-    if let Some(ref end_date) = params.end_date {
+    if let Some(end) = params.end_time {
         where_clauses.push("created_at <= ?");
-        bind_values.push(Box::new(end_date.clone()));
+        bind_values.push(Box::new(time_bound(time::millis_to_utc_iso(end), "endTime")?));
+    } else if let Some(ref end_date) = params.end_date {
+        where_clauses.push("created_at < ?");
+        bind_values.push(Box::new(time_bound(time::local_day_end_utc_iso(end_date), "endDate")?));
+    }
+    
+    if let Some(ref status) = params.status {
+        where_clauses.push("status = ?");
+        bind_values.push(Box::new(status.clone()));
+    }
+
+    if let Some(needs_review) = params.needs_review {
+        where_clauses.push("needs_review = ?");
+        bind_values.push(Box::new(needs_review));
+    }
+
+    if let Some(ref batch_id) = params.batch_id {
+        where_clauses.push("batch_id = ?");
+        bind_values.push(Box::new(batch_id.clone()));
+    }
+
+    if let Some(ref review_status) = params.review_status {
+        where_clauses.push("review_status = ?");
+        bind_values.push(Box::new(review_status.clone()));
     }
     
     let where_sql = if where_clauses.is_empty() {
@@ -113,6 +389,10 @@ pub fn get_history_records(params: HistoryQueryParams) -> Result<HistoryPaginate
         format!("WHERE {}", where_clauses.join(" AND "))
     };
     
+    if let Some(ref keyword) = params.keyword {
+        return query_history_by_keyword(&conn, keyword, &where_sql, &bind_values, columns, page, page_size, map_row);
+    }
+    
     // Get total count
     let count_sql = format!("SELECT COUNT(*) FROM recognition_history {}", where_sql);
     let count_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
@@ -120,9 +400,8 @@ pub fn get_history_records(params: HistoryQueryParams) -> Result<HistoryPaginate
     
     // Get records
     let query_sql = format!(
-        "SELECT id, config_id, config_name, image_path, image_thumbnail, prompt, result, tokens_used, duration_ms, created_at 
-         FROM recognition_history {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
-        where_sql
+        "SELECT {} FROM recognition_history {} ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+        columns, where_sql
     );
     
     bind_values.push(Box::new(page_size));
@@ -131,22 +410,9 @@ pub fn get_history_records(params: HistoryQueryParams) -> Result<HistoryPaginate
     let query_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
     let mut stmt = conn.prepare(&query_sql)?;
     
-    let rows = stmt.query_map(query_params.as_slice(), |row| {
-        Ok(row_to_record(
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-            row.get(5)?,
-            row.get(6)?,
-            row.get(7)?,
-            row.get(8)?,
-            row.get(9)?,
-        ))
-    })?;
-    
-    let records: Vec<HistoryRecord> = rows.collect::<Result<_>>()?;
+    let rows = stmt.query_map(query_params.as_slice(), map_row)?;
+    
+    let records: Vec<T> = rows.collect::<Result<_>>()?;
     
     Ok(HistoryPaginatedResult {
         records,
@@ -156,27 +422,69 @@ pub fn get_history_records(params: HistoryQueryParams) -> Result<HistoryPaginate
     })
 }
 
+/// Page of the records matching `keyword` after decryption. Candidates come from
+/// the other filters; only the page's rows are loaded in full.
+#[allow(clippy::too_many_arguments)]
+fn query_history_by_keyword<T>(
+    conn: &rusqlite::Connection,
+    keyword: &str,
+    where_sql: &str,
+    bind_values: &[Box<dyn rusqlite::ToSql>],
+    columns: &str,
+    page: i32,
+    page_size: i32,
+    map_row: impl FnMut(&rusqlite::Row) -> Result<T>,
+) -> Result<HistoryPaginatedResult<T>> {
+    let keyword = keyword.to_lowercase();
+    let matches = |value: String| open_text(value).to_lowercase().contains(&keyword);
+
+    let candidate_sql = format!(
+        "SELECT id, prompt, result FROM recognition_history {} ORDER BY created_at DESC",
+        where_sql
+    );
+    let candidate_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let mut stmt = conn.prepare(&candidate_sql)?;
+    let mut ids = Vec::new();
+    let mut rows = stmt.query(candidate_params.as_slice())?;
+    while let Some(row) = rows.next()? {
+        if matches(row.get("prompt")?) || matches(row.get("result")?) {
+            ids.push(row.get::<_, i64>("id")?);
+        }
+    }
+
+    let offset = ((page - 1) * page_size).max(0) as usize;
+    let page_ids: Vec<i64> = ids.iter().skip(offset).take(page_size.max(0) as usize).copied().collect();
+    let records = if page_ids.is_empty() {
+        Vec::new()
+    } else {
+        let placeholders: Vec<&str> = page_ids.iter().map(|_| "?").collect();
+        let query_sql = format!(
+            "SELECT {} FROM recognition_history WHERE id IN ({}) ORDER BY created_at DESC",
+            columns,
+            placeholders.join(", ")
+        );
+        let query_params: Vec<&dyn rusqlite::ToSql> = page_ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+        let mut stmt = conn.prepare(&query_sql)?;
+        let rows = stmt.query_map(query_params.as_slice(), map_row)?;
+        rows.collect::<Result<_>>()?
+    };
+
+    Ok(HistoryPaginatedResult {
+        records,
+        total: ids.len() as i64,
+        page,
+        page_size,
+    })
+}
+
 pub fn get_history_by_id(id: i64) -> Result<Option<HistoryRecord>> {
     let conn = get_connection().lock();
-    let mut stmt = conn.prepare(
-        "SELECT id, config_id, config_name, image_path, image_thumbnail, prompt, result, tokens_used, duration_ms, created_at 
-         FROM recognition_history WHERE id = ?1"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM recognition_history WHERE id = ?1",
+        HISTORY_COLUMNS
+    ))?;
     
-    let result = stmt.query_row([id], |row| {
-        Ok(row_to_record(
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-            row.get(5)?,
-            row.get(6)?,
-            row.get(7)?,
-            row.get(8)?,
-            row.get(9)?,
-        ))
-    });
+    let result = stmt.query_row([id], row_to_record);
     
     match result {
         Ok(record) => Ok(Some(record)),
@@ -185,29 +493,317 @@ pub fn get_history_by_id(id: i64) -> Result<Option<HistoryRecord>> {
     }
 }
 
+fn time_bound(bound: Option<String>, param: &str) -> Result<String> {
+    bound.ok_or_else(|| rusqlite::Error::InvalidParameterName(format!("Invalid {}", param)))
+}
+
 pub fn create_history_record(input: HistoryInput) -> Result<i64> {
+    insert_history_record(input, &time::now_utc_iso())
+}
+
+/// Insert a record made on another machine, keeping its original creation time.
+/// Its text is masked like local results when `scrubHistoryPii` is on.
+pub fn import_history_record(mut input: HistoryInput, created_at: &str) -> Result<i64> {
+    if settings::get_all_settings()?.scrub_history_pii {
+        input.result = scrub_text(&input.result);
+        input.reasoning = input.reasoning.map(|text| scrub_text(&text));
+        input.reviewed_result = input.reviewed_result.map(|text| scrub_text(&text));
+    }
+    insert_history_record(input, created_at)
+}
+
+/// Id of a record that predates per-record ids, derived from what used to
+/// identify it (creation time and config name) so every machine derives the same one
+pub fn legacy_uuid(created_at: &str, config_name: &str) -> String {
+    let digest = Sha256::digest(format!("{}\u{0}{}", created_at, config_name));
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid().to_string()
+}
+
+pub fn history_uuid_exists(uuid: &str) -> Result<bool> {
+    let conn = get_connection().lock();
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM recognition_history WHERE uuid = ?1)",
+        [uuid],
+        |row| row.get(0),
+    )
+}
+
+/// Whether a record created at `created_at` exists, with the same image when a
+/// hash is given
+pub fn history_record_exists(created_at: &str, image_hash: Option<u64>) -> Result<bool> {
+    let conn = get_connection().lock();
+    match image_hash {
+        Some(hash) => conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM recognition_history WHERE created_at = ?1 AND image_hash = ?2)",
+            params![created_at, hash as i64],
+            |row| row.get(0),
+        ),
+        None => conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM recognition_history WHERE created_at = ?1)",
+            [created_at],
+            |row| row.get(0),
+        ),
+    }
+}
+
+fn insert_history_record(input: HistoryInput, created_at: &str) -> Result<i64> {
+    let encrypt = settings::get_all_settings()?.encrypt_history;
+    let seal = |text: String| if encrypt { encrypt_tagged(&text) } else { text };
     let conn = get_connection().lock();
     
     conn.execute(
-        "INSERT INTO recognition_history (config_id, config_name, image_thumbnail, prompt, result, tokens_used, duration_ms)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        "INSERT INTO recognition_history (config_id, config_name, image_path, image_file, image_thumbnail, compare_thumbnail,
+            prompt, result, reasoning, tokens_used, input_tokens, output_tokens, duration_ms, ttft_ms, tokens_per_second,
+            char_count, word_count, language, reading_time_secs, provider, model_name, temperature, top_p, max_tokens,
+            app_version, status, error, error_code, image_hash, confidence, needs_review,
+            batch_id, review_status, reviewed_result, rerun_of, tags, template_id, created_at, uuid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+            ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39)",
         params![
             input.config_id,
             input.config_name,
+            input.image_path,
+            input.image_file,
             input.image_thumbnail,
-            input.prompt,
-            input.result,
+            input.compare_thumbnail,
+            seal(input.prompt),
+            seal(input.result),
+            input.reasoning.map(seal),
             input.tokens_used,
+            input.input_tokens,
+            input.output_tokens,
             input.duration_ms,
+            input.ttft_ms,
+            input.tokens_per_second,
+            input.stats.char_count,
+            input.stats.word_count,
+            input.stats.language,
+            input.stats.reading_time_secs,
+            input.provider,
+            input.model_name,
+            input.temperature,
+            input.top_p,
+            input.max_tokens,
+            input.app_version,
+            input.status,
+            input.error,
+            input.error_code,
+            // Stored bit-for-bit in SQLite's signed integer
+            input.image_hash.map(|hash| hash as i64),
+            input.confidence,
+            input.needs_review,
+            input.batch_id,
+            input.review_status,
+            input.reviewed_result.map(seal),
+            input.rerun_of,
+            serde_json::to_string(&input.tags).unwrap_or_else(|_| "[]".to_string()),
+            input.template_id,
+            created_at,
+            input.uuid.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
         ],
     )?;
     
     Ok(conn.last_insert_rowid())
 }
 
+/// Turn at-rest encryption of prompts and results on or off, converting the
+/// existing records and storing the setting in one transaction. Returns the
+/// number of records converted.
+pub fn set_encryption(enabled: bool) -> Result<usize> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+
+    type Texts = (i64, String, String, Option<String>, Option<String>);
+    let records: Vec<Texts> = {
+        let mut stmt = tx.prepare("SELECT id, prompt, result, reasoning, reviewed_result FROM recognition_history")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+
+    let convert = |value: &str| -> Option<String> {
+        match (enabled, is_tagged(value)) {
+            (true, false) => Some(encrypt_tagged(value)),
+            // Values that don't decrypt stay encrypted rather than being lost
+            (false, true) => decrypt_tagged(value).ok(),
+            _ => None,
+        }
+    };
+    let mut converted = 0;
+    for (id, prompt, result, reasoning, reviewed) in records {
+        let (new_prompt, new_result) = (convert(&prompt), convert(&result));
+        let new_reasoning = reasoning.as_deref().and_then(convert);
+        let new_reviewed = reviewed.as_deref().and_then(convert);
+        if new_prompt.is_none() && new_result.is_none() && new_reasoning.is_none() && new_reviewed.is_none() {
+            continue;
+        }
+        tx.execute(
+            "UPDATE recognition_history SET prompt = ?1, result = ?2, reasoning = ?3, reviewed_result = ?4 WHERE id = ?5",
+            params![
+                new_prompt.unwrap_or(prompt),
+                new_result.unwrap_or(result),
+                new_reasoning.or(reasoning),
+                new_reviewed.or(reviewed),
+                id
+            ],
+        )?;
+        converted += 1;
+    }
+
+    // Extractions repeat the fields of their record's result
+    let extractions: Vec<(i64, String)> = {
+        let mut stmt = tx.prepare("SELECT id, data FROM extractions")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+    for (id, data) in extractions {
+        if let Some(data) = convert(&data) {
+            tx.execute("UPDATE extractions SET data = ?1 WHERE id = ?2", params![data, id])?;
+        }
+    }
+
+    tx.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at)
+         VALUES ('encryptHistory', ?1, datetime('now', 'localtime'))",
+        [enabled.to_string()],
+    )?;
+    tx.commit()?;
+    Ok(converted)
+}
+
+/// A prior recognition of the same or a near-identical image
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarHistory {
+    pub record: HistoryRecord,
+    /// Differing bits between the perceptual hashes, 0 for the same image
+    pub distance: u32,
+}
+
+/// Successful records whose image hash is within `max_distance` bits of `hash`,
+/// closest and then newest first
+pub fn find_similar_history(hash: u64, max_distance: u32, limit: usize) -> Result<Vec<SimilarHistory>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, image_hash FROM recognition_history
+         WHERE image_hash IS NOT NULL AND status = 'success' ORDER BY created_at DESC",
+        HISTORY_COLUMNS
+    ))?;
+
+    let mut similar = Vec::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let distance = hash_distance(hash, row.get::<_, i64>("image_hash")? as u64);
+        if distance <= max_distance {
+            similar.push(SimilarHistory { record: row_to_record(row)?, distance });
+        }
+    }
+    // Stable sort keeps the newest first among equal distances
+    similar.sort_by_key(|s| s.distance);
+    similar.truncate(limit);
+    Ok(similar)
+}
+
+/// Pending, approved and rejected counts of a batch
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewBatch {
+    pub batch_id: String,
+    pub pending: i64,
+    pub approved: i64,
+    pub rejected: i64,
+    /// Creation time of the batch's latest record
+    pub last_created_at: String,
+}
+
+/// Batches with records in the review queue, latest first
+pub fn get_review_batches() -> Result<Vec<ReviewBatch>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(
+        "SELECT batch_id,
+            SUM(review_status = 'pending'), SUM(review_status = 'approved'), SUM(review_status = 'rejected'),
+            MAX(created_at)
+         FROM recognition_history
+         WHERE batch_id IS NOT NULL AND review_status IS NOT NULL
+         GROUP BY batch_id
+         ORDER BY MAX(created_at) DESC",
+    )?;
+    let batches = stmt
+        .query_map([], |row| {
+            Ok(ReviewBatch {
+                batch_id: row.get(0)?,
+                pending: row.get(1)?,
+                approved: row.get(2)?,
+                rejected: row.get(3)?,
+                last_created_at: row.get(4)?,
+            })
+        })?
+        .collect();
+    batches
+}
+
+/// Approve or reject a record, or put it back in the queue. `edited_result`
+/// stores the reviewer's correction; `None` keeps the current one.
+pub fn review_record(id: i64, status: &str, edited_result: Option<String>) -> Result<bool> {
+    if !REVIEW_STATUSES.contains(&status) {
+        return Err(rusqlite::Error::InvalidParameterName(format!("Invalid review status: {}", status)));
+    }
+    let app_settings = settings::get_all_settings()?;
+    let encrypt = app_settings.encrypt_history;
+    // The correction is kept at rest like the result it replaces
+    let edited_result = edited_result.map(|text| if app_settings.scrub_history_pii { scrub_text(&text) } else { text });
+    let conn = get_connection().lock();
+    let changes = match edited_result {
+        Some(text) => conn.execute(
+            "UPDATE recognition_history SET review_status = ?1, reviewed_result = ?2 WHERE id = ?3",
+            params![status, if encrypt { encrypt_tagged(&text) } else { text }, id],
+        )?,
+        None => conn.execute(
+            "UPDATE recognition_history SET review_status = ?1 WHERE id = ?2",
+            params![status, id],
+        )?,
+    };
+    Ok(changes > 0)
+}
+
+/// Approved records of a batch, or of all batches, with the reviewer's
+/// correction as their `result`
+pub fn export_approved(batch_id: Option<String>) -> Result<Vec<HistoryRecord>> {
+    let params = HistoryQueryParams {
+        batch_id,
+        review_status: Some("approved".to_string()),
+        ..Default::default()
+    };
+    let records = export_history(params)?
+        .into_iter()
+        .map(|record| match record.reviewed_result {
+            Some(ref edited) => HistoryRecord { result: edited.clone(), ..record },
+            None => record,
+        })
+        .collect();
+    Ok(records)
+}
+
+/// Clear the review flag of a low-confidence record once it was checked
+pub fn mark_reviewed(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("UPDATE recognition_history SET needs_review = 0 WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
+
 pub fn delete_history_record(id: i64) -> Result<bool> {
     let conn = get_connection().lock();
+    let image_file: Option<String> = conn
+        .query_row("SELECT image_file FROM recognition_history WHERE id = ?1", [id], |row| row.get(0))
+        .unwrap_or(None);
+    let uuids = record_uuids(&conn, "WHERE id = ?1", &[&id])?;
+    sync::record_deletions(&conn, sync::HISTORY, &uuids)?;
     let changes = conn.execute("DELETE FROM recognition_history WHERE id = ?1", [id])?;
+
+    if let Some(file) = image_file {
+        image_store::delete_image(&file);
+    }
     Ok(changes > 0)
 }
 
@@ -224,22 +820,132 @@ pub fn delete_history_records(ids: &[i64]) -> Result<usize> {
     );
     
     let params: Vec<&dyn rusqlite::ToSql> = ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+
+    let image_files: Vec<String> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT image_file FROM recognition_history WHERE image_file IS NOT NULL AND id IN ({})",
+            placeholders.join(", ")
+        ))?;
+        let rows = stmt.query_map(params.as_slice(), |row| row.get(0))?;
+        rows.collect::<Result<_>>()?
+    };
+
+    let uuids = record_uuids(&conn, &format!("WHERE id IN ({})", placeholders.join(", ")), params.as_slice())?;
+    sync::record_deletions(&conn, sync::HISTORY, &uuids)?;
     let changes = conn.execute(&sql, params.as_slice())?;
+
+    for file in image_files {
+        image_store::delete_image(&file);
+    }
     Ok(changes)
 }
 
 pub fn clear_all_history() -> Result<usize> {
     let conn = get_connection().lock();
+    let image_files = get_image_references(&conn)?;
+    let uuids = record_uuids(&conn, "", &[])?;
+    sync::record_deletions(&conn, sync::HISTORY, &uuids)?;
     let changes = conn.execute("DELETE FROM recognition_history", [])?;
+
+    for (_, file) in image_files {
+        image_store::delete_image(&file);
+    }
     Ok(changes)
 }
 
+/// Ids of the records matching `filter`, remembered when they are deleted so
+/// sync removes them on other machines too
+fn record_uuids(conn: &rusqlite::Connection, filter: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("SELECT uuid FROM recognition_history {}", filter))?;
+    let rows = stmt.query_map(params, |row| row.get(0))?;
+    rows.collect()
+}
+
+fn get_image_references(conn: &rusqlite::Connection) -> Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, image_file FROM recognition_history WHERE image_file IS NOT NULL"
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub history_id: i64,
+    pub image_file: String,
+    /// "missing" or "corrupted"
+    pub problem: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub checked: usize,
+    pub issues: Vec<IntegrityIssue>,
+    pub cleared: usize,
+}
+
+/// Check every image file referenced by history, optionally dropping dangling references
+pub fn verify_image_integrity(clear_dangling: bool) -> Result<IntegrityReport> {
+    let references = {
+        let conn = get_connection().lock();
+        get_image_references(&conn)?
+    };
+
+    // File checks run without holding the database lock
+    let issues: Vec<IntegrityIssue> = references
+        .iter()
+        .filter_map(|(id, file)| {
+            let problem = match image_store::check_image(file) {
+                ImageFileState::Ok => return None,
+                ImageFileState::Missing => "missing",
+                ImageFileState::Corrupted => "corrupted",
+            };
+            Some(IntegrityIssue {
+                history_id: *id,
+                image_file: file.clone(),
+                problem: problem.to_string(),
+            })
+        })
+        .collect();
+
+    let mut cleared = 0;
+    if clear_dangling && !issues.is_empty() {
+        let conn = get_connection().lock();
+        for issue in &issues {
+            cleared += conn.execute(
+                "UPDATE recognition_history SET image_file = NULL WHERE id = ?1",
+                [issue.history_id],
+            )?;
+            if issue.problem == "corrupted" {
+                image_store::delete_image(&issue.image_file);
+            }
+        }
+    }
+
+    Ok(IntegrityReport {
+        checked: references.len(),
+        issues,
+        cleared,
+    })
+}
+
+/// Every record matching `params`, read a page at a time
 pub fn export_history(params: HistoryQueryParams) -> Result<Vec<HistoryRecord>> {
-    // Reuse the paginated query but with a large page size
-    let mut full_params = params;
-    full_params.page = Some(1);
-    full_params.page_size = Some(10000);
-    
-    let result = get_history_records(full_params)?;
-    Ok(result.records)
+    const PAGE_SIZE: i32 = 1000;
+    let mut records = Vec::new();
+    for page in 1.. {
+        let result = get_history_records(HistoryQueryParams {
+            page: Some(page),
+            page_size: Some(PAGE_SIZE),
+            ..params.clone()
+        })?;
+        let done = result.records.len() < PAGE_SIZE as usize;
+        records.extend(result.records);
+        if done {
+            break;
+        }
+    }
+    Ok(records)
 }
@@ -3,5 +3,46 @@ pub mod model_config;
 pub mod history;
 pub mod prompt_template;
 pub mod settings;
+pub mod experiment;
+pub mod defaults;
+pub mod backup;
+pub mod workspace;
+pub mod preset;
+pub mod prompt_history;
+pub mod stats;
 
 pub use connection::{init_database, get_connection};
+
+use serde::{Deserialize, Serialize};
+
+/// Escape LIKE wildcards so `value` matches literally; use with `ESCAPE '\\'`
+pub(crate) fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// One page of a list query with the total number of matching rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedResult<T> {
+    pub records: Vec<T>,
+    pub total: i64,
+    pub page: i32,
+    pub page_size: i32,
+}
+pub mod template_usage;
+pub mod template_example;
+pub mod ab_test;
+pub mod profile;
+pub mod extraction;
+pub mod provider_cache;
+pub mod sync;
+pub mod glossary;
+pub mod postprocess_rule;
+pub mod usage_ledger;
@@ -1,7 +1,8 @@
-use crate::db::get_connection;
+use crate::db::{get_connection, profile, sync, PaginatedResult};
+use crate::services::llm::ImageDetail;
 use crate::utils::crypto::{encrypt, decrypt, mask_api_key};
 use serde::{Deserialize, Serialize};
-use rusqlite::{params, Result};
+use rusqlite::{params, OptionalExtension, Result};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,6 +15,32 @@ pub struct ModelConfig {
     pub api_key_encrypted: String,
     pub model_name: String,
     pub max_tokens: i32,
+    /// Client-side throttle; None means unlimited
+    pub requests_per_minute: Option<i32>,
+    /// Monthly spending alert threshold in USD
+    pub monthly_budget_usd: Option<f64>,
+    /// USD per million input / output tokens, for spend estimates
+    pub input_price_per_mtok: Option<f64>,
+    pub output_price_per_mtok: Option<f64>,
+    /// "valid" or "invalid" from the last credential check; None if never checked
+    pub last_validation_status: Option<String>,
+    pub last_validated_at: Option<String>,
+    /// Mask sensitive text in images before they are sent to this config's provider
+    pub redact_sensitive: bool,
+    /// Default OpenAI image `detail`; `None` leaves it to the provider
+    pub image_detail: Option<ImageDetail>,
+    /// Reasoning model (OpenAI o-series, Anthropic extended thinking)
+    pub reasoning_model: bool,
+    /// Anthropic thinking budget of a reasoning model; `None` uses the default
+    pub thinking_budget_tokens: Option<i32>,
+    /// OpenAI `OpenAI-Organization` / `OpenAI-Project` headers; `None` sends neither
+    pub organization: Option<String>,
+    pub project: Option<String>,
+    /// Accept any TLS certificate from this config's endpoint. Insecure, only
+    /// meant for internal gateways whose CA cannot be installed
+    pub skip_tls_verify: bool,
+    /// Owning profile; `None` means shared by all profiles
+    pub profile_id: Option<i64>,
     pub is_active: bool,
     pub is_default: bool,
     pub created_at: String,
@@ -30,6 +57,32 @@ pub struct ModelConfigListItem {
     pub api_key_masked: String,
     pub model_name: String,
     pub max_tokens: i32,
+    /// Client-side throttle; None means unlimited
+    pub requests_per_minute: Option<i32>,
+    /// Monthly spending alert threshold in USD
+    pub monthly_budget_usd: Option<f64>,
+    /// USD per million input / output tokens, for spend estimates
+    pub input_price_per_mtok: Option<f64>,
+    pub output_price_per_mtok: Option<f64>,
+    /// "valid" or "invalid" from the last credential check; None if never checked
+    pub last_validation_status: Option<String>,
+    pub last_validated_at: Option<String>,
+    /// Mask sensitive text in images before they are sent to this config's provider
+    pub redact_sensitive: bool,
+    /// Default OpenAI image `detail`; `None` leaves it to the provider
+    pub image_detail: Option<ImageDetail>,
+    /// Reasoning model (OpenAI o-series, Anthropic extended thinking)
+    pub reasoning_model: bool,
+    /// Anthropic thinking budget of a reasoning model; `None` uses the default
+    pub thinking_budget_tokens: Option<i32>,
+    /// OpenAI `OpenAI-Organization` / `OpenAI-Project` headers; `None` sends neither
+    pub organization: Option<String>,
+    pub project: Option<String>,
+    /// Accept any TLS certificate from this config's endpoint. Insecure, only
+    /// meant for internal gateways whose CA cannot be installed
+    pub skip_tls_verify: bool,
+    /// Owning profile; `None` means shared by all profiles
+    pub profile_id: Option<i64>,
     pub is_active: bool,
     pub is_default: bool,
     pub created_at: String,
@@ -45,6 +98,19 @@ pub struct ModelConfigInput {
     pub api_key: String,
     pub model_name: String,
     pub max_tokens: Option<i32>,
+    pub requests_per_minute: Option<i32>,
+    pub monthly_budget_usd: Option<f64>,
+    pub input_price_per_mtok: Option<f64>,
+    pub output_price_per_mtok: Option<f64>,
+    pub redact_sensitive: Option<bool>,
+    pub image_detail: Option<ImageDetail>,
+    pub reasoning_model: Option<bool>,
+    pub thinking_budget_tokens: Option<i32>,
+    pub organization: Option<String>,
+    pub project: Option<String>,
+    pub skip_tls_verify: Option<bool>,
+    /// Owning profile, the active one when omitted; zero or less makes the config shared
+    pub profile_id: Option<i64>,
     pub is_active: Option<bool>,
     pub is_default: Option<bool>,
 }
@@ -58,143 +124,196 @@ pub struct ModelConfigUpdate {
     pub api_key: Option<String>,
     pub model_name: Option<String>,
     pub max_tokens: Option<i32>,
+    pub requests_per_minute: Option<i32>,
+    pub monthly_budget_usd: Option<f64>,
+    pub input_price_per_mtok: Option<f64>,
+    pub output_price_per_mtok: Option<f64>,
+    pub redact_sensitive: Option<bool>,
+    /// "low", "high" or "auto"; an empty value clears the default
+    pub image_detail: Option<String>,
+    pub reasoning_model: Option<bool>,
+    /// Zero or less restores the default budget
+    pub thinking_budget_tokens: Option<i32>,
+    /// Empty values clear the OpenAI organization / project
+    pub organization: Option<String>,
+    pub project: Option<String>,
+    pub skip_tls_verify: Option<bool>,
+    /// Zero or less makes the config shared
+    pub profile_id: Option<i64>,
     pub is_active: Option<bool>,
     pub is_default: Option<bool>,
 }
 
-fn row_to_list_item(
-    id: i64,
-    name: String,
-    provider: String,
-    api_url: String,
-    api_key_encrypted: String,
-    model_name: String,
-    max_tokens: i32,
-    is_active: i32,
-    is_default: i32,
-    created_at: String,
-    updated_at: String,
-) -> ModelConfigListItem {
-    let decrypted_key = decrypt(&api_key_encrypted).unwrap_or_default();
-    ModelConfigListItem {
-        id,
-        name,
-        provider,
-        api_url,
-        api_key_masked: mask_api_key(&decrypted_key),
-        model_name,
-        max_tokens,
-        is_active: is_active == 1,
-        is_default: is_default == 1,
-        created_at,
-        updated_at,
-    }
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigQueryParams {
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+    /// Match against config name or model name
+    pub keyword: Option<String>,
+    pub provider: Option<String>,
+    pub active_only: Option<bool>,
+}
+
+const CONFIG_COLUMNS: &str = "id, name, provider, api_url, api_key_encrypted, model_name, max_tokens, \
+    requests_per_minute, monthly_budget_usd, input_price_per_mtok, output_price_per_mtok, last_validation_status, \
+    last_validated_at, redact_sensitive, image_detail, \
+    reasoning_model, thinking_budget_tokens, organization, project, skip_tls_verify, \
+    profile_id, is_active, is_default, created_at, updated_at";
+
+fn row_to_list_item(row: &rusqlite::Row) -> Result<ModelConfigListItem> {
+    let config = row_to_model(row)?;
+    Ok(ModelConfigListItem {
+        id: config.id,
+        name: config.name,
+        provider: config.provider,
+        api_url: config.api_url,
+        api_key_masked: mask_api_key(&config.api_key),
+        model_name: config.model_name,
+        max_tokens: config.max_tokens,
+        requests_per_minute: config.requests_per_minute,
+        monthly_budget_usd: config.monthly_budget_usd,
+        input_price_per_mtok: config.input_price_per_mtok,
+        output_price_per_mtok: config.output_price_per_mtok,
+        last_validation_status: config.last_validation_status,
+        last_validated_at: config.last_validated_at,
+        redact_sensitive: config.redact_sensitive,
+        image_detail: config.image_detail,
+        reasoning_model: config.reasoning_model,
+        thinking_budget_tokens: config.thinking_budget_tokens,
+        organization: config.organization,
+        project: config.project,
+        skip_tls_verify: config.skip_tls_verify,
+        profile_id: config.profile_id,
+        is_active: config.is_active,
+        is_default: config.is_default,
+        created_at: config.created_at,
+        updated_at: config.updated_at,
+    })
 }
 
-fn row_to_model(
-    id: i64,
-    name: String,
-    provider: String,
-    api_url: String,
-    api_key_encrypted: String,
-    model_name: String,
-    max_tokens: i32,
-    is_active: i32,
-    is_default: i32,
-    created_at: String,
-    updated_at: String,
-) -> ModelConfig {
+fn row_to_model(row: &rusqlite::Row) -> Result<ModelConfig> {
+    let api_key_encrypted: String = row.get("api_key_encrypted")?;
     let decrypted_key = decrypt(&api_key_encrypted).unwrap_or_default();
-    ModelConfig {
-        id,
-        name,
-        provider,
-        api_url,
+    Ok(ModelConfig {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        provider: row.get("provider")?,
+        api_url: row.get("api_url")?,
         api_key: decrypted_key,
         api_key_encrypted,
-        model_name,
-        max_tokens,
-        is_active: is_active == 1,
-        is_default: is_default == 1,
-        created_at,
-        updated_at,
-    }
+        model_name: row.get("model_name")?,
+        max_tokens: row.get("max_tokens")?,
+        requests_per_minute: row.get("requests_per_minute")?,
+        monthly_budget_usd: row.get("monthly_budget_usd")?,
+        input_price_per_mtok: row.get("input_price_per_mtok")?,
+        output_price_per_mtok: row.get("output_price_per_mtok")?,
+        last_validation_status: row.get("last_validation_status")?,
+        last_validated_at: row.get("last_validated_at")?,
+        redact_sensitive: row.get::<_, i32>("redact_sensitive")? == 1,
+        image_detail: row
+            .get::<_, Option<String>>("image_detail")?
+            .and_then(|detail| ImageDetail::parse(&detail)),
+        reasoning_model: row.get::<_, i32>("reasoning_model")? == 1,
+        thinking_budget_tokens: row.get("thinking_budget_tokens")?,
+        organization: row.get("organization")?,
+        project: row.get("project")?,
+        skip_tls_verify: row.get::<_, i32>("skip_tls_verify")? == 1,
+        profile_id: row.get("profile_id")?,
+        is_active: row.get::<_, i32>("is_active")? == 1,
+        is_default: row.get::<_, i32>("is_default")? == 1,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
 }
 
+/// Configs visible in the active profile
 pub fn get_all_configs() -> Result<Vec<ModelConfigListItem>> {
     let conn = get_connection().lock();
-    let mut stmt = conn.prepare(
-        "SELECT id, name, provider, api_url, api_key_encrypted, model_name, max_tokens, is_active, is_default, created_at, updated_at 
-         FROM model_configs ORDER BY created_at DESC"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM model_configs WHERE {} ORDER BY created_at DESC",
+        CONFIG_COLUMNS,
+        profile::visible_sql("profile_id")
+    ))?;
     
-    let rows = stmt.query_map([], |row| {
-        Ok(row_to_list_item(
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-            row.get(5)?,
-            row.get(6)?,
-            row.get(7)?,
-            row.get(8)?,
-            row.get(9)?,
-            row.get(10)?,
-        ))
-    })?;
+    let rows = stmt.query_map([], row_to_list_item)?;
     
     rows.collect()
 }
 
+pub fn query_configs(params: ConfigQueryParams) -> Result<PaginatedResult<ModelConfigListItem>> {
+    let conn = get_connection().lock();
+    
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params.page_size.unwrap_or(20).max(1);
+    let offset = (page - 1) * page_size;
+    
+    let visible = profile::visible_sql("profile_id");
+    let mut where_clauses = vec![visible.as_str()];
+    let mut bind_values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    
+    if let Some(keyword) = params.keyword.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        where_clauses.push("(name LIKE ? OR model_name LIKE ?)");
+        let pattern = format!("%{}%", keyword);
+        bind_values.push(Box::new(pattern.clone()));
+        bind_values.push(Box::new(pattern));
+    }
+    
+    if let Some(ref provider) = params.provider {
+        where_clauses.push("provider = ?");
+        bind_values.push(Box::new(provider.clone()));
+    }
+    
+    if params.active_only.unwrap_or(false) {
+        where_clauses.push("is_active = 1");
+    }
+    
+    let where_sql = format!("WHERE {}", where_clauses.join(" AND "));
+    
+    let count_sql = format!("SELECT COUNT(*) FROM model_configs {}", where_sql);
+    let count_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let total: i64 = conn.query_row(&count_sql, count_params.as_slice(), |row| row.get(0))?;
+    
+    let query_sql = format!(
+        "SELECT {} FROM model_configs {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
+        CONFIG_COLUMNS, where_sql
+    );
+    bind_values.push(Box::new(page_size));
+    bind_values.push(Box::new(offset));
+    
+    let query_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let mut stmt = conn.prepare(&query_sql)?;
+    let rows = stmt.query_map(query_params.as_slice(), row_to_list_item)?;
+    
+    Ok(PaginatedResult {
+        records: rows.collect::<Result<_>>()?,
+        total,
+        page,
+        page_size,
+    })
+}
+
 pub fn get_active_configs() -> Result<Vec<ModelConfigListItem>> {
     let conn = get_connection().lock();
-    let mut stmt = conn.prepare(
-        "SELECT id, name, provider, api_url, api_key_encrypted, model_name, max_tokens, is_active, is_default, created_at, updated_at 
-         FROM model_configs WHERE is_active = 1 ORDER BY is_default DESC, created_at DESC"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM model_configs WHERE is_active = 1 AND {} ORDER BY is_default DESC, created_at DESC",
+        CONFIG_COLUMNS,
+        profile::visible_sql("profile_id")
+    ))?;
     
-    let rows = stmt.query_map([], |row| {
-        Ok(row_to_list_item(
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-            row.get(5)?,
-            row.get(6)?,
-            row.get(7)?,
-            row.get(8)?,
-            row.get(9)?,
-            row.get(10)?,
-        ))
-    })?;
+    let rows = stmt.query_map([], row_to_list_item)?;
     
     rows.collect()
 }
 
 pub fn get_config_by_id(id: i64) -> Result<Option<ModelConfig>> {
     let conn = get_connection().lock();
-    let mut stmt = conn.prepare(
-        "SELECT id, name, provider, api_url, api_key_encrypted, model_name, max_tokens, is_active, is_default, created_at, updated_at 
-         FROM model_configs WHERE id = ?1"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM model_configs WHERE id = ?1",
+        CONFIG_COLUMNS
+    ))?;
     
-    let result = stmt.query_row([id], |row| {
-        Ok(row_to_model(
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-            row.get(5)?,
-            row.get(6)?,
-            row.get(7)?,
-            row.get(8)?,
-            row.get(9)?,
-            row.get(10)?,
-        ))
-    });
+    let result = stmt.query_row([id], row_to_model);
     
     match result {
         Ok(config) => Ok(Some(config)),
@@ -203,28 +322,30 @@ pub fn get_config_by_id(id: i64) -> Result<Option<ModelConfig>> {
     }
 }
 
+/// List view of one config regardless of the active profile
+fn get_config_list_item(id: i64) -> Result<Option<ModelConfigListItem>> {
+    let conn = get_connection().lock();
+    match conn.query_row(
+        &format!("SELECT {} FROM model_configs WHERE id = ?1", CONFIG_COLUMNS),
+        [id],
+        row_to_list_item,
+    ) {
+        Ok(config) => Ok(Some(config)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The default config, unless it belongs to another profile than the active one
 pub fn get_default_config() -> Result<Option<ModelConfig>> {
     let conn = get_connection().lock();
-    let mut stmt = conn.prepare(
-        "SELECT id, name, provider, api_url, api_key_encrypted, model_name, max_tokens, is_active, is_default, created_at, updated_at 
-         FROM model_configs WHERE is_default = 1 AND is_active = 1"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM model_configs WHERE is_default = 1 AND is_active = 1 AND {}",
+        CONFIG_COLUMNS,
+        profile::visible_sql("profile_id")
+    ))?;
     
-    let result = stmt.query_row([], |row| {
-        Ok(row_to_model(
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-            row.get(5)?,
-            row.get(6)?,
-            row.get(7)?,
-            row.get(8)?,
-            row.get(9)?,
-            row.get(10)?,
-        ))
-    });
+    let result = stmt.query_row([], row_to_model);
     
     match result {
         Ok(config) => Ok(Some(config)),
@@ -233,13 +354,34 @@ pub fn get_default_config() -> Result<Option<ModelConfig>> {
     }
 }
 
+/// Trimmed header value, `None` when blank
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
 pub fn create_config(input: ModelConfigInput) -> Result<ModelConfigListItem> {
-    let conn = get_connection().lock();
+    let profile_id = match input.profile_id {
+        Some(id) => (id > 0).then_some(id),
+        None => profile::active_profile_id()?,
+    };
+    let mut conn = get_connection().lock();
     let encrypted_key = encrypt(&input.api_key);
-    
-    conn.execute(
-        "INSERT INTO model_configs (name, provider, api_url, api_key_encrypted, model_name, max_tokens, is_active, is_default)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    let is_default = input.is_default.unwrap_or(false);
+
+    // Unset the previous default first so the single-default index holds
+    let tx = conn.transaction()?;
+    if is_default {
+        tx.execute("UPDATE model_configs SET is_default = 0 WHERE is_default = 1", [])?;
+    }
+    tx.execute(
+        "INSERT INTO model_configs (name, provider, api_url, api_key_encrypted, model_name, max_tokens,
+            requests_per_minute, monthly_budget_usd, input_price_per_mtok, output_price_per_mtok, redact_sensitive,
+            image_detail, reasoning_model, thinking_budget_tokens, organization, project, skip_tls_verify, profile_id,
+            is_active, is_default, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+            strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
         params![
             input.name,
             input.provider,
@@ -247,32 +389,36 @@ pub fn create_config(input: ModelConfigInput) -> Result<ModelConfigListItem> {
             encrypted_key,
             input.model_name,
             input.max_tokens.unwrap_or(4096),
+            input.requests_per_minute.filter(|rpm| *rpm > 0),
+            input.monthly_budget_usd.filter(|usd| *usd > 0.0),
+            input.input_price_per_mtok.filter(|usd| *usd > 0.0),
+            input.output_price_per_mtok.filter(|usd| *usd > 0.0),
+            if input.redact_sensitive.unwrap_or(false) { 1 } else { 0 },
+            input.image_detail.map(|detail| detail.as_str()),
+            if input.reasoning_model.unwrap_or(false) { 1 } else { 0 },
+            input.thinking_budget_tokens.filter(|tokens| *tokens > 0),
+            non_empty(input.organization),
+            non_empty(input.project),
+            if input.skip_tls_verify.unwrap_or(false) { 1 } else { 0 },
+            profile_id,
             if input.is_active.unwrap_or(true) { 1 } else { 0 },
-            if input.is_default.unwrap_or(false) { 1 } else { 0 },
+            if is_default { 1 } else { 0 },
         ],
     )?;
-    
-    let id = conn.last_insert_rowid();
-    
-    // If set as default, unset others
-    if input.is_default.unwrap_or(false) {
-        conn.execute(
-            "UPDATE model_configs SET is_default = 0 WHERE id != ?1",
-            [id],
-        )?;
-    }
-    
+    let id = tx.last_insert_rowid();
+    tx.commit()?;
+
     drop(conn);
     
-    let configs = get_all_configs()?;
-    Ok(configs.into_iter().find(|c| c.id == id).unwrap())
+    get_config_list_item(id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
 }
 
 pub fn update_config(id: i64, input: ModelConfigUpdate) -> Result<Option<ModelConfigListItem>> {
-    let conn = get_connection().lock();
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
     
     // Check if exists
-    let exists: bool = conn.query_row(
+    let exists: bool = tx.query_row(
         "SELECT 1 FROM model_configs WHERE id = ?1",
         [id],
         |_| Ok(true),
@@ -284,8 +430,28 @@ pub fn update_config(id: i64, input: ModelConfigUpdate) -> Result<Option<ModelCo
     
     let mut updates = Vec::new();
     let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    // The last check was of other credentials, it says nothing about the new ones
+    let (provider, api_url, api_key_encrypted, model_name): (String, String, String, String) = tx.query_row(
+        "SELECT provider, api_url, api_key_encrypted, model_name FROM model_configs WHERE id = ?1",
+        [id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+    let credentials_changed = input.provider.as_ref().is_some_and(|value| *value != provider)
+        || input.api_url.as_ref().is_some_and(|value| *value != api_url)
+        || input.model_name.as_ref().is_some_and(|value| *value != model_name)
+        || input.api_key.as_ref().is_some_and(|value| decrypt(&api_key_encrypted).ok().as_ref() != Some(value));
+    if credentials_changed {
+        updates.push("last_validation_status = NULL");
+        updates.push("last_validated_at = NULL");
+    }
     
     if let Some(ref name) = input.name {
+        // Other machines know the config by its old name, sync removes it there
+        let old_name: String = tx.query_row("SELECT name FROM model_configs WHERE id = ?1", [id], |row| row.get(0))?;
+        if old_name != *name {
+            sync::record_deletions(&tx, sync::CONFIG, &[old_name])?;
+        }
         updates.push("name = ?");
         values.push(Box::new(name.clone()));
     }
@@ -309,6 +475,52 @@ pub fn update_config(id: i64, input: ModelConfigUpdate) -> Result<Option<ModelCo
         updates.push("max_tokens = ?");
         values.push(Box::new(max_tokens));
     }
+    if let Some(rpm) = input.requests_per_minute {
+        // Zero or negative clears the limit
+        updates.push("requests_per_minute = ?");
+        values.push(Box::new(if rpm > 0 { Some(rpm) } else { None }));
+    }
+    // Budget and prices are cleared the same way
+    for (column, value) in [
+        ("monthly_budget_usd = ?", input.monthly_budget_usd),
+        ("input_price_per_mtok = ?", input.input_price_per_mtok),
+        ("output_price_per_mtok = ?", input.output_price_per_mtok),
+    ] {
+        if let Some(usd) = value {
+            updates.push(column);
+            values.push(Box::new(if usd > 0.0 { Some(usd) } else { None }));
+        }
+    }
+    if let Some(redact_sensitive) = input.redact_sensitive {
+        updates.push("redact_sensitive = ?");
+        values.push(Box::new(if redact_sensitive { 1 } else { 0 }));
+    }
+    if let Some(ref image_detail) = input.image_detail {
+        updates.push("image_detail = ?");
+        values.push(Box::new(ImageDetail::parse(image_detail).map(|detail| detail.as_str())));
+    }
+    if let Some(reasoning_model) = input.reasoning_model {
+        updates.push("reasoning_model = ?");
+        values.push(Box::new(if reasoning_model { 1 } else { 0 }));
+    }
+    if let Some(tokens) = input.thinking_budget_tokens {
+        updates.push("thinking_budget_tokens = ?");
+        values.push(Box::new(if tokens > 0 { Some(tokens) } else { None }));
+    }
+    for (column, value) in [("organization = ?", input.organization), ("project = ?", input.project)] {
+        if value.is_some() {
+            updates.push(column);
+            values.push(Box::new(non_empty(value)));
+        }
+    }
+    if let Some(skip_tls_verify) = input.skip_tls_verify {
+        updates.push("skip_tls_verify = ?");
+        values.push(Box::new(if skip_tls_verify { 1 } else { 0 }));
+    }
+    if let Some(profile_id) = input.profile_id {
+        updates.push("profile_id = ?");
+        values.push(Box::new(if profile_id > 0 { Some(profile_id) } else { None }));
+    }
     if let Some(is_active) = input.is_active {
         updates.push("is_active = ?");
         values.push(Box::new(if is_active { 1 } else { 0 }));
@@ -318,50 +530,70 @@ pub fn update_config(id: i64, input: ModelConfigUpdate) -> Result<Option<ModelCo
         values.push(Box::new(if is_default { 1 } else { 0 }));
     }
     
-    updates.push("updated_at = datetime('now', 'localtime')");
-    
-    if !updates.is_empty() {
-        let sql = format!(
-            "UPDATE model_configs SET {} WHERE id = ?",
-            updates.join(", ")
-        );
-        values.push(Box::new(id));
-        
-        let params: Vec<&dyn rusqlite::ToSql> = values.iter().map(|v| v.as_ref()).collect();
-        conn.execute(&sql, params.as_slice())?;
-    }
+    // UTC, so sync compares edit times across time zones
+    updates.push("updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')");
     
-    // If set as default, unset others
+    // Unset the previous default first so the single-default index holds
     if input.is_default == Some(true) {
-        conn.execute(
-            "UPDATE model_configs SET is_default = 0 WHERE id != ?1",
+        tx.execute(
+            "UPDATE model_configs SET is_default = 0 WHERE is_default = 1 AND id != ?1",
             [id],
         )?;
     }
+
+    let sql = format!(
+        "UPDATE model_configs SET {} WHERE id = ?",
+        updates.join(", ")
+    );
+    values.push(Box::new(id));
+    
+    let params: Vec<&dyn rusqlite::ToSql> = values.iter().map(|v| v.as_ref()).collect();
+    tx.execute(&sql, params.as_slice())?;
+    tx.commit()?;
     
     drop(conn);
     
-    let configs = get_all_configs()?;
-    Ok(configs.into_iter().find(|c| c.id == id))
+    get_config_list_item(id)
+}
+
+/// Record the outcome of a credential check without touching `updated_at`
+pub fn set_validation_status(id: i64, valid: bool) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute(
+        "UPDATE model_configs SET last_validation_status = ?1, last_validated_at = datetime('now', 'localtime')
+         WHERE id = ?2",
+        params![if valid { "valid" } else { "invalid" }, id],
+    )?;
+    Ok(changes > 0)
 }
 
 pub fn delete_config(id: i64) -> Result<bool> {
     let conn = get_connection().lock();
+    let names: Vec<String> = conn
+        .query_row("SELECT name FROM model_configs WHERE id = ?1", [id], |row| row.get(0))
+        .optional()?
+        .into_iter()
+        .collect();
+    sync::record_deletions(&conn, sync::CONFIG, &names)?;
     let changes = conn.execute("DELETE FROM model_configs WHERE id = ?1", [id])?;
     Ok(changes > 0)
 }
 
 pub fn set_default_config(id: i64) -> Result<bool> {
-    let conn = get_connection().lock();
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
     
     // Unset all defaults
-    conn.execute("UPDATE model_configs SET is_default = 0", [])?;
+    tx.execute("UPDATE model_configs SET is_default = 0", [])?;
     
-    // Set new default
-    let changes = conn.execute(
+    // Set new default; without a match the old default is kept
+    let changes = tx.execute(
         "UPDATE model_configs SET is_default = 1 WHERE id = ?1",
         [id],
     )?;
+    if changes > 0 {
+        tx.commit()?;
+    }
     
     Ok(changes > 0)
 }
@@ -0,0 +1,102 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, OptionalExtension, Result};
+
+/// A regex find/replace applied to every successful result, in `sort_order`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostprocessRule {
+    pub id: i64,
+    pub name: String,
+    pub pattern: String,
+    /// May refer to capture groups as `$1` or `${name}`
+    pub replacement: String,
+    pub enabled: bool,
+    pub sort_order: i32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostprocessRuleInput {
+    pub name: String,
+    pub pattern: String,
+    pub replacement: String,
+    pub enabled: bool,
+}
+
+const RULE_COLUMNS: &str = "id, name, pattern, replacement, enabled, sort_order, created_at";
+
+fn row_to_rule(row: &rusqlite::Row) -> Result<PostprocessRule> {
+    Ok(PostprocessRule {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        pattern: row.get("pattern")?,
+        replacement: row.get("replacement")?,
+        enabled: row.get("enabled")?,
+        sort_order: row.get("sort_order")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Rules in the order they are applied
+pub fn get_rules() -> Result<Vec<PostprocessRule>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM postprocess_rules ORDER BY sort_order, id",
+        RULE_COLUMNS
+    ))?;
+    let rules = stmt.query_map([], row_to_rule)?.collect();
+    rules
+}
+
+pub fn get_enabled_rules() -> Result<Vec<PostprocessRule>> {
+    Ok(get_rules()?.into_iter().filter(|rule| rule.enabled).collect())
+}
+
+fn get_rule(conn: &rusqlite::Connection, id: i64) -> Result<Option<PostprocessRule>> {
+    conn.query_row(
+        &format!("SELECT {} FROM postprocess_rules WHERE id = ?1", RULE_COLUMNS),
+        [id],
+        row_to_rule,
+    )
+    .optional()
+}
+
+/// Add a rule after the existing ones
+pub fn create_rule(input: PostprocessRuleInput) -> Result<PostprocessRule> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO postprocess_rules (name, pattern, replacement, enabled, sort_order)
+         VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(sort_order), -1) + 1 FROM postprocess_rules))",
+        params![input.name, input.pattern, input.replacement, input.enabled],
+    )?;
+    get_rule(&conn, conn.last_insert_rowid())?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+pub fn update_rule(id: i64, input: PostprocessRuleInput) -> Result<Option<PostprocessRule>> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "UPDATE postprocess_rules SET name = ?1, pattern = ?2, replacement = ?3, enabled = ?4 WHERE id = ?5",
+        params![input.name, input.pattern, input.replacement, input.enabled, id],
+    )?;
+    get_rule(&conn, id)
+}
+
+pub fn delete_rule(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM postprocess_rules WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
+
+/// Apply rules in the order of `ids`; rules not listed keep their relative order after them
+pub fn reorder_rules(ids: &[i64]) -> Result<()> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+    let listed = ids.len() as i32;
+    tx.execute("UPDATE postprocess_rules SET sort_order = sort_order + ?1", [listed])?;
+    for (order, id) in ids.iter().enumerate() {
+        tx.execute("UPDATE postprocess_rules SET sort_order = ?1 WHERE id = ?2", params![order as i32, id])?;
+    }
+    tx.commit()
+}
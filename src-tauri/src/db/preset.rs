@@ -0,0 +1,162 @@
+use crate::db::get_connection;
+use crate::services::llm::RecognitionOptions;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// A named recognition setup: config, template, parameters and post-processing flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preset {
+    pub id: i64,
+    pub name: String,
+    pub config_id: i64,
+    /// Falls back to the default template when unset
+    pub template_id: Option<i64>,
+    pub options: RecognitionOptions,
+    /// Used when no preset is given, e.g. by the hotkey and tray flows
+    pub is_default: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetInput {
+    pub name: String,
+    pub config_id: i64,
+    pub template_id: Option<i64>,
+    #[serde(default)]
+    pub options: RecognitionOptions,
+    pub is_default: Option<bool>,
+}
+
+const PRESET_COLUMNS: &str = "id, name, config_id, template_id, options, is_default, created_at, updated_at";
+
+fn row_to_preset(row: &rusqlite::Row) -> Result<Preset> {
+    let options: String = row.get("options")?;
+    Ok(Preset {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        config_id: row.get("config_id")?,
+        template_id: row.get("template_id")?,
+        options: serde_json::from_str(&options).unwrap_or_default(),
+        is_default: row.get::<_, i32>("is_default")? == 1,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn options_json(options: &RecognitionOptions) -> String {
+    serde_json::to_string(options).unwrap_or_else(|_| "{}".to_string())
+}
+
+pub fn get_all_presets() -> Result<Vec<Preset>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM presets ORDER BY is_default DESC, name",
+        PRESET_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([], row_to_preset)?;
+    rows.collect()
+}
+
+pub fn get_preset_by_id(id: i64) -> Result<Option<Preset>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM presets WHERE id = ?1",
+        PRESET_COLUMNS
+    ))?;
+
+    match stmt.query_row([id], row_to_preset) {
+        Ok(preset) => Ok(Some(preset)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn get_default_preset() -> Result<Option<Preset>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM presets WHERE is_default = 1",
+        PRESET_COLUMNS
+    ))?;
+
+    match stmt.query_row([], row_to_preset) {
+        Ok(preset) => Ok(Some(preset)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn create_preset(input: PresetInput) -> Result<Preset> {
+    let mut conn = get_connection().lock();
+    let is_default = input.is_default.unwrap_or(false);
+
+    let tx = conn.transaction()?;
+    if is_default {
+        tx.execute("UPDATE presets SET is_default = 0", [])?;
+    }
+    tx.execute(
+        "INSERT INTO presets (name, config_id, template_id, options, is_default)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            input.name,
+            input.config_id,
+            input.template_id,
+            options_json(&input.options),
+            if is_default { 1 } else { 0 },
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    tx.commit()?;
+    drop(conn);
+
+    get_preset_by_id(id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+/// Replace a preset. `is_default: None` keeps the current default flag.
+pub fn update_preset(id: i64, input: PresetInput) -> Result<Option<Preset>> {
+    let mut conn = get_connection().lock();
+
+    let tx = conn.transaction()?;
+    if input.is_default == Some(true) {
+        tx.execute("UPDATE presets SET is_default = 0 WHERE id != ?1", [id])?;
+    }
+    let changes = tx.execute(
+        "UPDATE presets SET name = ?1, config_id = ?2, template_id = ?3, options = ?4,
+            is_default = COALESCE(?5, is_default), updated_at = datetime('now', 'localtime')
+         WHERE id = ?6",
+        params![
+            input.name,
+            input.config_id,
+            input.template_id,
+            options_json(&input.options),
+            input.is_default.map(|d| if d { 1 } else { 0 }),
+            id,
+        ],
+    )?;
+    if changes == 0 {
+        return Ok(None);
+    }
+    tx.commit()?;
+    drop(conn);
+
+    get_preset_by_id(id)
+}
+
+pub fn delete_preset(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM presets WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
+
+pub fn set_default_preset(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute(
+        "UPDATE presets SET is_default = CASE WHEN id = ?1 THEN 1 ELSE 0 END
+         WHERE EXISTS (SELECT 1 FROM presets WHERE id = ?1)",
+        [id],
+    )?;
+    Ok(changes > 0)
+}
@@ -0,0 +1,201 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// A named partition of configs, templates and settings, e.g. "Work" or "Personal".
+/// While a profile is active only its own and shared items are listed or usable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    /// Defaults remembered from the last time the profile was active
+    pub default_config_id: Option<i64>,
+    pub default_template_id: Option<i64>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const PROFILE_COLUMNS: &str = "id, name, default_config_id, default_template_id, is_active, created_at, updated_at";
+
+/// SQL condition matching rows of `column`'s table visible in the active
+/// profile: shared rows (NULL) and those of the active profile. Without an
+/// active profile every row matches.
+pub fn visible_sql(column: &str) -> String {
+    format!(
+        "({0} IS NULL OR NOT EXISTS (SELECT 1 FROM profiles WHERE is_active = 1) \
+         OR {0} = (SELECT id FROM profiles WHERE is_active = 1))",
+        column
+    )
+}
+
+/// Whether an item owned by `owner` may be used while `active` is the active profile
+pub fn is_visible(active: Option<i64>, owner: Option<i64>) -> bool {
+    match (active, owner) {
+        (Some(active), Some(owner)) => active == owner,
+        _ => true,
+    }
+}
+
+fn row_to_profile(row: &rusqlite::Row) -> Result<Profile> {
+    Ok(Profile {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        default_config_id: row.get("default_config_id")?,
+        default_template_id: row.get("default_template_id")?,
+        is_active: row.get::<_, i32>("is_active")? == 1,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+pub fn get_all_profiles() -> Result<Vec<Profile>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles ORDER BY name", PROFILE_COLUMNS))?;
+
+    let rows = stmt.query_map([], row_to_profile)?;
+    rows.collect()
+}
+
+pub fn get_profile_by_id(id: i64) -> Result<Option<Profile>> {
+    let conn = get_connection().lock();
+    match conn.query_row(
+        &format!("SELECT {} FROM profiles WHERE id = ?1", PROFILE_COLUMNS),
+        [id],
+        row_to_profile,
+    ) {
+        Ok(profile) => Ok(Some(profile)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn get_active_profile() -> Result<Option<Profile>> {
+    let conn = get_connection().lock();
+    match conn.query_row(
+        &format!("SELECT {} FROM profiles WHERE is_active = 1", PROFILE_COLUMNS),
+        [],
+        row_to_profile,
+    ) {
+        Ok(profile) => Ok(Some(profile)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn active_profile_id() -> Result<Option<i64>> {
+    Ok(get_active_profile()?.map(|profile| profile.id))
+}
+
+pub fn create_profile(name: &str) -> Result<Profile> {
+    let conn = get_connection().lock();
+    conn.execute("INSERT INTO profiles (name) VALUES (?1)", [name.trim()])?;
+    let id = conn.last_insert_rowid();
+    drop(conn);
+
+    get_profile_by_id(id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+pub fn rename_profile(id: i64, name: &str) -> Result<Option<Profile>> {
+    let conn = get_connection().lock();
+    let changes = conn.execute(
+        "UPDATE profiles SET name = ?1, updated_at = datetime('now', 'localtime') WHERE id = ?2",
+        params![name.trim(), id],
+    )?;
+    drop(conn);
+
+    if changes == 0 {
+        return Ok(None);
+    }
+    get_profile_by_id(id)
+}
+
+/// Configs and templates still owned by the profile
+pub fn count_owned(id: i64) -> Result<i64> {
+    let conn = get_connection().lock();
+    conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM model_configs WHERE profile_id = ?1)
+              + (SELECT COUNT(*) FROM prompt_templates WHERE profile_id = ?1)",
+        [id],
+        |row| row.get(0),
+    )
+}
+
+/// Delete a profile and its settings overrides. Callers make sure it owns no
+/// configs or templates, which would otherwise become shared.
+pub fn delete_profile(id: i64) -> Result<bool> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+    let changes = tx.execute("DELETE FROM profiles WHERE id = ?1", [id])?;
+    tx.execute(
+        "DELETE FROM app_settings WHERE substr(key, 1, length(?1)) = ?1",
+        [format!("profile:{}:", id)],
+    )?;
+    tx.commit()?;
+    Ok(changes > 0)
+}
+
+/// Switch the active profile in one transaction. The outgoing profile
+/// remembers the current default config and template, the incoming one gets
+/// its remembered defaults back; defaults owned by another profile are unset.
+/// `None` deactivates all profiles and leaves the current defaults in place.
+pub fn set_active_profile(id: Option<i64>) -> Result<Option<Profile>> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "UPDATE profiles SET
+            default_config_id = (SELECT id FROM model_configs WHERE is_default = 1),
+            default_template_id = (SELECT id FROM prompt_templates WHERE is_default = 1)
+         WHERE is_active = 1",
+        [],
+    )?;
+
+    let Some(id) = id else {
+        tx.execute("UPDATE profiles SET is_active = 0", [])?;
+        tx.commit()?;
+        return Ok(None);
+    };
+
+    let exists: bool = tx.query_row("SELECT 1 FROM profiles WHERE id = ?1", [id], |_| Ok(true)).unwrap_or(false);
+    if !exists {
+        // Unknown profile, the current one stays active
+        return Ok(None);
+    }
+    tx.execute("UPDATE profiles SET is_active = CASE WHEN id = ?1 THEN 1 ELSE 0 END", [id])?;
+
+    for (table, column) in [("model_configs", "default_config_id"), ("prompt_templates", "default_template_id")] {
+        // Unset the old default before setting the new one, the single-default index is checked per row
+        let remembered: Option<i64> = tx.query_row(
+            &format!("SELECT p.{1} FROM profiles p JOIN {0} t ON t.id = p.{1} WHERE p.id = ?1", table, column),
+            [id],
+            |row| row.get(0),
+        ).ok();
+        if let Some(default_id) = remembered {
+            tx.execute(&format!("UPDATE {} SET is_default = 0 WHERE is_default = 1", table), [])?;
+            tx.execute(&format!("UPDATE {} SET is_default = 1 WHERE id = ?1", table), [default_id])?;
+        }
+        tx.execute(
+            &format!("UPDATE {} SET is_default = 0 WHERE is_default = 1 AND profile_id != ?1", table),
+            [id],
+        )?;
+    }
+    tx.commit()?;
+    drop(conn);
+
+    get_profile_by_id(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_visible() {
+        assert!(is_visible(None, Some(2)));
+        assert!(is_visible(Some(1), None));
+        assert!(is_visible(Some(1), Some(1)));
+        assert!(!is_visible(Some(1), Some(2)));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::db::{escape_like, get_connection};
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// A prompt that was used for a recognition, saved as a template or not
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptHistoryEntry {
+    pub id: i64,
+    pub prompt: String,
+    pub use_count: i32,
+    pub last_used_at: String,
+}
+
+/// Record a used prompt; repeats only bump its count and last use
+pub fn record_prompt(prompt: &str) -> Result<()> {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Ok(());
+    }
+
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO prompt_history (prompt) VALUES (?1)
+         ON CONFLICT(prompt) DO UPDATE SET
+            use_count = use_count + 1,
+            last_used_at = datetime('now', 'localtime')",
+        [prompt],
+    )?;
+    Ok(())
+}
+
+/// Previously used prompts starting with `prefix`, most used first
+pub fn get_prompt_suggestions(prefix: &str, limit: Option<i32>) -> Result<Vec<PromptHistoryEntry>> {
+    let conn = get_connection().lock();
+    let pattern = format!("{}%", escape_like(prefix.trim_start()));
+    let mut stmt = conn.prepare(
+        "SELECT id, prompt, use_count, last_used_at FROM prompt_history
+         WHERE prompt LIKE ?1 ESCAPE '\\'
+         ORDER BY use_count DESC, last_used_at DESC LIMIT ?2",
+    )?;
+
+    let rows = stmt.query_map(params![pattern, limit.unwrap_or(10)], |row| {
+        Ok(PromptHistoryEntry {
+            id: row.get("id")?,
+            prompt: row.get("prompt")?,
+            use_count: row.get("use_count")?,
+            last_used_at: row.get("last_used_at")?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn delete_prompt_history(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM prompt_history WHERE id = ?1", [id])?;
+    Ok(changes > 0)
+}
+
+pub fn clear_prompt_history() -> Result<usize> {
+    let conn = get_connection().lock();
+    conn.execute("DELETE FROM prompt_history", [])
+}
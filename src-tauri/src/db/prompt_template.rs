@@ -1,6 +1,6 @@
-use crate::db::get_connection;
+use crate::db::{get_connection, profile, sync, template_example, PaginatedResult};
 use serde::{Deserialize, Serialize};
-use rusqlite::{params, Result};
+use rusqlite::{params, OptionalExtension, Result};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -10,6 +10,8 @@ pub struct PromptTemplate {
     pub content: String,
     pub is_default: bool,
     pub use_count: i32,
+    /// Owning profile; `None` means shared by all profiles
+    pub profile_id: Option<i64>,
     pub created_at: String,
 }
 
@@ -19,6 +21,17 @@ pub struct TemplateUpdate {
     pub name: Option<String>,
     pub content: Option<String>,
     pub is_default: Option<bool>,
+    /// Zero or less makes the template shared
+    pub profile_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateQueryParams {
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+    /// Match against template name or content
+    pub keyword: Option<String>,
 }
 
 fn row_to_template(
@@ -27,6 +40,7 @@ fn row_to_template(
     content: String,
     is_default: i32,
     use_count: i32,
+    profile_id: Option<i64>,
     created_at: String,
 ) -> PromptTemplate {
     PromptTemplate {
@@ -35,16 +49,19 @@ fn row_to_template(
         content,
         is_default: is_default == 1,
         use_count,
+        profile_id,
         created_at,
     }
 }
 
+/// Templates visible in the active profile
 pub fn get_all_templates() -> Result<Vec<PromptTemplate>> {
     let conn = get_connection().lock();
-    let mut stmt = conn.prepare(
-        "SELECT id, name, content, is_default, use_count, created_at 
-         FROM prompt_templates ORDER BY is_default DESC, use_count DESC, created_at DESC"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at 
+         FROM prompt_templates WHERE {} ORDER BY is_default DESC, use_count DESC, created_at DESC",
+        profile::visible_sql("profile_id")
+    ))?;
     
     let rows = stmt.query_map([], |row| {
         Ok(row_to_template(
@@ -54,20 +71,99 @@ pub fn get_all_templates() -> Result<Vec<PromptTemplate>> {
             row.get(3)?,
             row.get(4)?,
             row.get(5)?,
+            row.get(6)?,
         ))
     })?;
     
     rows.collect()
 }
 
+pub fn query_templates(params: TemplateQueryParams) -> Result<PaginatedResult<PromptTemplate>> {
+    let conn = get_connection().lock();
+    
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params.page_size.unwrap_or(20).max(1);
+    let offset = (page - 1) * page_size;
+    
+    let mut bind_values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    let mut where_sql = format!("WHERE {}", profile::visible_sql("profile_id"));
+    if let Some(keyword) = params.keyword.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        let pattern = format!("%{}%", keyword);
+        bind_values.push(Box::new(pattern.clone()));
+        bind_values.push(Box::new(pattern));
+        where_sql.push_str(" AND (name LIKE ? OR content LIKE ?)");
+    }
+    
+    let count_sql = format!("SELECT COUNT(*) FROM prompt_templates {}", where_sql);
+    let count_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let total: i64 = conn.query_row(&count_sql, count_params.as_slice(), |row| row.get(0))?;
+    
+    let query_sql = format!(
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at FROM prompt_templates {} \
+         ORDER BY is_default DESC, use_count DESC, created_at DESC LIMIT ? OFFSET ?",
+        where_sql
+    );
+    bind_values.push(Box::new(page_size));
+    bind_values.push(Box::new(offset));
+    
+    let query_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let mut stmt = conn.prepare(&query_sql)?;
+    let rows = stmt.query_map(query_params.as_slice(), |row| {
+        Ok(row_to_template(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+            row.get(5)?,
+            row.get(6)?,
+        ))
+    })?;
+    
+    Ok(PaginatedResult {
+        records: rows.collect::<Result<_>>()?,
+        total,
+        page,
+        page_size,
+    })
+}
+
+/// The default template, unless it belongs to another profile than the active one
 pub fn get_default_template() -> Result<Option<PromptTemplate>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at 
+         FROM prompt_templates WHERE is_default = 1 AND {}",
+        profile::visible_sql("profile_id")
+    ))?;
+    
+    let result = stmt.query_row([], |row| {
+        Ok(row_to_template(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+            row.get(5)?,
+            row.get(6)?,
+        ))
+    });
+    
+    match result {
+        Ok(template) => Ok(Some(template)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn get_template_by_id(id: i64) -> Result<Option<PromptTemplate>> {
     let conn = get_connection().lock();
     let mut stmt = conn.prepare(
-        "SELECT id, name, content, is_default, use_count, created_at 
-         FROM prompt_templates WHERE is_default = 1"
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at 
+         FROM prompt_templates WHERE id = ?1"
     )?;
     
-    let result = stmt.query_row([], |row| {
+    let result = stmt.query_row([id], |row| {
         Ok(row_to_template(
             row.get(0)?,
             row.get(1)?,
@@ -75,6 +171,7 @@ pub fn get_default_template() -> Result<Option<PromptTemplate>> {
             row.get(3)?,
             row.get(4)?,
             row.get(5)?,
+            row.get(6)?,
         ))
     });
     
@@ -88,10 +185,11 @@ pub fn get_default_template() -> Result<Option<PromptTemplate>> {
 pub fn get_recent_templates(limit: Option<i32>) -> Result<Vec<PromptTemplate>> {
     let conn = get_connection().lock();
     let limit_val = limit.unwrap_or(5);
-    let mut stmt = conn.prepare(
-        "SELECT id, name, content, is_default, use_count, created_at 
-         FROM prompt_templates ORDER BY use_count DESC, created_at DESC LIMIT ?1"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at 
+         FROM prompt_templates WHERE {} ORDER BY use_count DESC, created_at DESC LIMIT ?1",
+        profile::visible_sql("profile_id")
+    ))?;
     
     let rows = stmt.query_map([limit_val], |row| {
         Ok(row_to_template(
@@ -101,18 +199,30 @@ pub fn get_recent_templates(limit: Option<i32>) -> Result<Vec<PromptTemplate>> {
             row.get(3)?,
             row.get(4)?,
             row.get(5)?,
+            row.get(6)?,
         ))
     })?;
     
     rows.collect()
 }
 
-pub fn create_template(name: &str, content: &str, is_default: bool) -> Result<PromptTemplate> {
+/// `profile_id` defaults to the active profile; zero or less makes the template shared
+pub fn create_template(
+    name: &str,
+    content: &str,
+    is_default: bool,
+    profile_id: Option<i64>,
+) -> Result<PromptTemplate> {
+    let profile_id = match profile_id {
+        Some(id) => (id > 0).then_some(id),
+        None => profile::active_profile_id()?,
+    };
     let conn = get_connection().lock();
     
     conn.execute(
-        "INSERT INTO prompt_templates (name, content, is_default) VALUES (?1, ?2, ?3)",
-        params![name, content, if is_default { 1 } else { 0 }],
+        "INSERT INTO prompt_templates (name, content, is_default, profile_id, updated_at)
+         VALUES (?1, ?2, ?3, ?4, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
+        params![name, content, if is_default { 1 } else { 0 }, profile_id],
     )?;
     
     let id = conn.last_insert_rowid();
@@ -126,7 +236,7 @@ pub fn create_template(name: &str, content: &str, is_default: bool) -> Result<Pr
     }
     
     let mut stmt = conn.prepare(
-        "SELECT id, name, content, is_default, use_count, created_at 
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at 
          FROM prompt_templates WHERE id = ?1"
     )?;
     
@@ -138,6 +248,7 @@ pub fn create_template(name: &str, content: &str, is_default: bool) -> Result<Pr
             row.get(3)?,
             row.get(4)?,
             row.get(5)?,
+            row.get(6)?,
         ))
     })
 }
@@ -160,6 +271,11 @@ pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<Prompt
     let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
     
     if let Some(ref name) = updates.name {
+        // Other machines know the template by its old name, sync removes it there
+        let old_name: String = conn.query_row("SELECT name FROM prompt_templates WHERE id = ?1", [id], |row| row.get(0))?;
+        if old_name != *name {
+            sync::record_deletions(&conn, sync::TEMPLATE, &[old_name])?;
+        }
         update_stmts.push("name = ?");
         values.push(Box::new(name.clone()));
     }
@@ -171,8 +287,13 @@ pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<Prompt
         update_stmts.push("is_default = ?");
         values.push(Box::new(if is_default { 1 } else { 0 }));
     }
+    if let Some(profile_id) = updates.profile_id {
+        update_stmts.push("profile_id = ?");
+        values.push(Box::new(if profile_id > 0 { Some(profile_id) } else { None }));
+    }
     
     if !update_stmts.is_empty() {
+        update_stmts.push("updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')");
         let sql = format!(
             "UPDATE prompt_templates SET {} WHERE id = ?",
             update_stmts.join(", ")
@@ -192,7 +313,7 @@ pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<Prompt
     }
     
     let mut stmt = conn.prepare(
-        "SELECT id, name, content, is_default, use_count, created_at 
+        "SELECT id, name, content, is_default, use_count, profile_id, created_at 
          FROM prompt_templates WHERE id = ?1"
     )?;
     
@@ -204,6 +325,7 @@ pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<Prompt
             row.get(3)?,
             row.get(4)?,
             row.get(5)?,
+            row.get(6)?,
         ))
     });
     
@@ -215,8 +337,18 @@ pub fn update_template(id: i64, updates: TemplateUpdate) -> Result<Option<Prompt
 }
 
 pub fn delete_template(id: i64) -> Result<bool> {
-    let conn = get_connection().lock();
-    let changes = conn.execute("DELETE FROM prompt_templates WHERE id = ?1", [id])?;
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+    let names: Vec<String> = tx
+        .query_row("SELECT name FROM prompt_templates WHERE id = ?1", [id], |row| row.get(0))
+        .optional()?
+        .into_iter()
+        .collect();
+    sync::record_deletions(&tx, sync::TEMPLATE, &names)?;
+    tx.execute("DELETE FROM template_usage WHERE template_id = ?1", [id])?;
+    template_example::delete_examples_of(&tx, id)?;
+    let changes = tx.execute("DELETE FROM prompt_templates WHERE id = ?1", [id])?;
+    tx.commit()?;
     Ok(changes > 0)
 }
 
@@ -228,3 +360,130 @@ pub fn increment_use_count(id: i64) -> Result<()> {
     )?;
     Ok(())
 }
+
+/// Content as compared for duplicates: case, whitespace and full-width forms
+/// of ASCII characters don't count
+pub fn normalize_content(content: &str) -> String {
+    content
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A visible template whose normalized content matches `content`
+pub fn find_duplicate(content: &str) -> Result<Option<PromptTemplate>> {
+    let normalized = normalize_content(content);
+    Ok(get_all_templates()?
+        .into_iter()
+        .find(|template| normalize_content(&template.content) == normalized))
+}
+
+/// Duplicates folded into the template that was kept
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateMerge {
+    pub kept_id: i64,
+    pub removed_ids: Vec<i64>,
+}
+
+/// Templates sharing an owner profile and normalized content
+type TemplateGroup<'a> = ((Option<i64>, String), Vec<&'a PromptTemplate>);
+
+/// Group templates of the same owner by normalized content. The default
+/// template is kept, then the most used, then the oldest.
+fn plan_merges(templates: &[PromptTemplate]) -> Vec<TemplateMerge> {
+    let mut groups: Vec<TemplateGroup> = Vec::new();
+    for template in templates {
+        let key = (template.profile_id, normalize_content(&template.content));
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(template),
+            None => groups.push((key, vec![template])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(_, mut members)| {
+            members.sort_by_key(|t| (!t.is_default, std::cmp::Reverse(t.use_count), t.id));
+            TemplateMerge {
+                kept_id: members[0].id,
+                removed_ids: members[1..].iter().map(|t| t.id).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Columns referring to a template, repointed when duplicates are merged
+const TEMPLATE_REFERENCES: [(&str, &str); 8] = [
+    ("template_usage", "template_id"),
+    ("template_examples", "template_id"),
+    ("workspaces", "template_id"),
+    ("presets", "template_id"),
+    ("ab_test_results", "template_id"),
+    ("experiments", "template_a_id"),
+    ("experiments", "template_b_id"),
+    ("profiles", "default_template_id"),
+];
+
+/// Merge visible templates with the same normalized content: references and
+/// use counts move to the kept template and the others are deleted
+pub fn dedupe_templates() -> Result<Vec<TemplateMerge>> {
+    let merges = plan_merges(&get_all_templates()?);
+
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+    for merge in &merges {
+        for removed in &merge.removed_ids {
+            for (table, column) in TEMPLATE_REFERENCES {
+                tx.execute(
+                    &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, column),
+                    params![merge.kept_id, removed],
+                )?;
+            }
+            tx.execute(
+                "UPDATE prompt_templates SET use_count = use_count
+                    + (SELECT use_count FROM prompt_templates WHERE id = ?2)
+                 WHERE id = ?1",
+                params![merge.kept_id, removed],
+            )?;
+            tx.execute("DELETE FROM prompt_templates WHERE id = ?1", [removed])?;
+        }
+    }
+    tx.commit()?;
+    Ok(merges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(id: i64, content: &str, is_default: bool, use_count: i32, profile_id: Option<i64>) -> PromptTemplate {
+        row_to_template(id, format!("T{}", id), content.to_string(), is_default as i32, use_count, profile_id, String::new())
+    }
+
+    #[test]
+    fn test_normalize_content() {
+        assert_eq!(normalize_content("Read  the\ntext："), normalize_content("read the text："));
+        assert_eq!(normalize_content("识别 图片（全部）"), normalize_content("识别图片（全部）"));
+        assert_eq!(normalize_content("ＯＣＲ！"), "ocr!");
+        assert_ne!(normalize_content("识别图片"), normalize_content("描述图片"));
+    }
+
+    #[test]
+    fn test_plan_merges() {
+        let templates = vec![
+            template(1, "识别文字", false, 2, None),
+            template(2, "识别 文字", false, 9, None),
+            template(3, "识别文字", true, 0, None),
+            template(4, "识别文字", false, 5, Some(7)),
+            template(5, "描述图片", false, 0, None),
+        ];
+        assert_eq!(plan_merges(&templates), vec![TemplateMerge { kept_id: 3, removed_ids: vec![2, 1] }]);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::db::get_connection;
+use rusqlite::{params, OptionalExtension, Result};
+
+/// Models available to a config's key, as a JSON array of ids
+pub const MODELS: &str = "models";
+/// Outcome of the last health check, as JSON
+pub const HEALTH: &str = "health";
+
+/// Cached value stored less than `max_age_secs` ago
+pub fn get_entry(config_id: i64, kind: &str, max_age_secs: i64) -> Result<Option<String>> {
+    let conn = get_connection().lock();
+    conn.query_row(
+        "SELECT value FROM provider_cache
+         WHERE config_id = ?1 AND kind = ?2
+           AND fetched_at > CAST(strftime('%s', 'now') AS INTEGER) - ?3",
+        params![config_id, kind, max_age_secs],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Store a value, replacing the previous one of the same kind
+pub fn set_entry(config_id: i64, kind: &str, value: &str) -> Result<()> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO provider_cache (config_id, kind, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(config_id, kind) DO UPDATE SET
+            value = excluded.value,
+            fetched_at = excluded.fetched_at",
+        params![config_id, kind, value],
+    )?;
+    Ok(())
+}
+
+/// Forget everything cached for a config, e.g. after its URL or key changed
+pub fn delete_entries(config_id: i64) -> Result<usize> {
+    let conn = get_connection().lock();
+    conn.execute("DELETE FROM provider_cache WHERE config_id = ?1", [config_id])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::init_test_database;
+
+    #[test]
+    fn test_entries_expire() {
+        init_test_database();
+        let config_id: i64 = {
+            let conn = get_connection().lock();
+            conn.execute(
+                "INSERT INTO model_configs (name, provider, api_url, api_key_encrypted, model_name)
+                 VALUES ('cache-test', 'openai', 'http://localhost', '', 'gpt')",
+                [],
+            )
+            .unwrap();
+            conn.last_insert_rowid()
+        };
+
+        set_entry(config_id, MODELS, "[\"a\"]").unwrap();
+        set_entry(config_id, MODELS, "[\"a\",\"b\"]").unwrap();
+        assert_eq!(get_entry(config_id, MODELS, 60).unwrap().as_deref(), Some("[\"a\",\"b\"]"));
+        assert_eq!(get_entry(config_id, HEALTH, 60).unwrap(), None);
+
+        get_connection()
+            .lock()
+            .execute("UPDATE provider_cache SET fetched_at = fetched_at - 120 WHERE config_id = ?1", [config_id])
+            .unwrap();
+        assert_eq!(get_entry(config_id, MODELS, 60).unwrap(), None);
+        assert!(get_entry(config_id, MODELS, 600).unwrap().is_some());
+
+        assert_eq!(delete_entries(config_id).unwrap(), 1);
+        assert_eq!(get_entry(config_id, MODELS, 600).unwrap(), None);
+    }
+}
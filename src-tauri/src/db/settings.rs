@@ -11,10 +11,103 @@ pub struct AppSettings {
     pub image_max_size: i32,
     pub compress_threshold: i32,
     pub auto_compress: bool,
+    pub auto_rotate: bool,
+    /// Trim uniform margins and letterboxing before upload
+    pub auto_crop: bool,
+    /// Read QR codes and barcodes locally alongside every recognition
+    pub decode_codes: bool,
     pub default_temperature: f32,
     pub default_top_p: f32,
     pub default_max_tokens: i32,
     pub default_stream: bool,
+    /// Language answers are asked in, e.g. `ja`; empty leaves it to the prompt
+    pub output_language: String,
+    /// Overrides the User-Agent sent to providers; empty uses the app default
+    pub user_agent: String,
+    /// Headers added to every provider request, e.g. gateway client ids
+    pub extra_headers: HashMap<String, String>,
+    /// Allow HTTP/2; some proxies break HTTP/2 streaming
+    pub http2_enabled: bool,
+    /// TCP keepalive interval in seconds, 0 disables it
+    pub tcp_keepalive_secs: i32,
+    /// HTTP/2 ping interval in seconds, 0 disables it; keeps long SSE streams
+    /// alive through proxies that drop quiet connections
+    pub http2_keep_alive_secs: i32,
+    /// Limit on establishing a connection in seconds, 0 leaves it to the request timeout
+    pub connect_timeout_secs: i32,
+    /// How long idle pooled connections are kept, 0 disables pooling
+    pub pool_idle_timeout_secs: i32,
+    /// PEM bundle of extra root certificates trusted for provider requests,
+    /// e.g. a corporate CA; empty uses the system roots only
+    pub ca_bundle_path: String,
+    /// Parallel requests allowed per account (endpoint and key) of a provider
+    /// type, e.g. `{"openai": 4, "ollama": 1}`; unlisted providers use the default limit
+    pub provider_concurrency: HashMap<String, u32>,
+    /// Minutes between background health checks of the active configs, 0 (the
+    /// default) disables them: each check is a billable test request
+    pub health_check_minutes: i32,
+    /// Keep failed recognitions in history so they can be retried and analyzed
+    pub save_failed_recognitions: bool,
+    /// Keep the reasoning of reasoning models with their history records
+    pub store_reasoning: bool,
+    /// Mask emails, phone numbers and ID numbers in the result and reasoning
+    /// stored in history; the recognition itself still returns the full text
+    pub scrub_history_pii: bool,
+    /// Score every recognition's confidence, see `RecognitionOptions::estimate_confidence`
+    pub estimate_confidence: bool,
+    /// Recognitions scored below this confidence (0 to 1) are flagged for review
+    pub low_confidence_threshold: f32,
+    /// Release channel checked for updates: "stable" or "beta"
+    pub update_channel: String,
+    /// Local config whose OCR pass finds sensitive text for configs that redact uploads
+    pub redaction_config_id: Option<i64>,
+    /// Encrypt history prompts and results at rest; toggled through `history::set_encryption`
+    pub encrypt_history: bool,
+    /// Minutes without activity before a passcode-protected app locks itself, 0 disables it
+    pub lock_idle_minutes: i32,
+    /// Voice id used to read results aloud, empty uses the system default
+    pub tts_voice: String,
+    /// Speech rate relative to the voice's normal rate, 0.5 to 2.0
+    pub tts_rate: f32,
+    /// Markdown file that `append_to_notebook` adds results to, empty when not set
+    pub notebook_path: String,
+    /// Save thumbnails next to the notebook and link them in appended entries
+    pub notebook_thumbnails: bool,
+    /// Hardware for local models: `auto`, `cpu`, `cuda`, `directml` or `metal`
+    pub inference_device: String,
+    /// Sync configs, templates and history through the endpoint below at startup
+    pub sync_enabled: bool,
+    /// Sync storage: `webdav` or `s3`
+    pub sync_backend: String,
+    /// WebDAV folder URL, or S3 service URL such as `https://s3.us-east-1.amazonaws.com`
+    pub sync_endpoint: String,
+    /// S3 bucket; unused with WebDAV
+    pub sync_bucket: String,
+    /// S3 signing region
+    pub sync_region: String,
+    /// WebDAV user name or S3 access key id; the password or secret key is
+    /// stored apart, see `sync::set_credentials`
+    pub sync_username: String,
+    /// Where results are shared: `gist` or `custom`
+    pub share_target: String,
+    /// Endpoint a custom share target posts the text to; it answers with the link
+    pub share_endpoint: String,
+    /// Create public gists instead of secret ones
+    pub share_public: bool,
+    /// SMTP server results are emailed through; the password is stored apart,
+    /// see `mail::set_password`
+    pub smtp_host: String,
+    pub smtp_port: i32,
+    /// `starttls`, `tls` (implicit TLS, usually port 465) or `none`
+    pub smtp_security: String,
+    pub smtp_username: String,
+    /// Sender address, `Name <user@example.com>` or a bare address; the user name when empty
+    pub smtp_from: String,
+    /// Comma-separated addresses results are emailed to by default
+    pub email_recipients: String,
+    /// Notion database results are added to as pages; the integration token is
+    /// stored apart, see `notion::set_token`
+    pub notion_database_id: String,
 }
 
 impl AppSettings {
@@ -25,14 +118,83 @@ impl AppSettings {
             image_max_size: 10,
             compress_threshold: 2048,
             auto_compress: true,
+            auto_rotate: false,
+            auto_crop: false,
+            decode_codes: true,
             default_temperature: 0.0,
             default_top_p: 0.4,
             default_max_tokens: 2048,
             default_stream: true,
+            output_language: String::new(),
+            user_agent: String::new(),
+            extra_headers: HashMap::new(),
+            http2_enabled: true,
+            tcp_keepalive_secs: 60,
+            http2_keep_alive_secs: 30,
+            connect_timeout_secs: 10,
+            pool_idle_timeout_secs: 90,
+            ca_bundle_path: String::new(),
+            provider_concurrency: HashMap::new(),
+            health_check_minutes: 0,
+            save_failed_recognitions: true,
+            store_reasoning: false,
+            scrub_history_pii: false,
+            estimate_confidence: false,
+            low_confidence_threshold: 0.7,
+            update_channel: "stable".to_string(),
+            redaction_config_id: None,
+            encrypt_history: false,
+            lock_idle_minutes: 10,
+            tts_voice: String::new(),
+            tts_rate: 1.0,
+            notebook_path: String::new(),
+            notebook_thumbnails: true,
+            inference_device: "auto".to_string(),
+            sync_enabled: false,
+            sync_backend: "webdav".to_string(),
+            sync_endpoint: String::new(),
+            sync_bucket: String::new(),
+            sync_region: "us-east-1".to_string(),
+            sync_username: String::new(),
+            share_target: "gist".to_string(),
+            share_endpoint: String::new(),
+            share_public: false,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            smtp_security: "starttls".to_string(),
+            smtp_username: String::new(),
+            smtp_from: String::new(),
+            email_recipients: String::new(),
+            notion_database_id: String::new(),
         }
     }
 }
 
+/// Key prefix of values like the passcode hash that the generic settings
+/// commands neither write nor reset
+const SECURITY_PREFIX: &str = "security:";
+
+/// Where settings are stored: globally or as overrides of one profile or workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsScope {
+    Global,
+    Profile(i64),
+    Workspace(i64),
+}
+
+impl SettingsScope {
+    /// Key prefix of the scope's values
+    fn prefix(self) -> String {
+        match self {
+            Self::Global => String::new(),
+            Self::Profile(id) => format!("profile:{}:", id),
+            Self::Workspace(id) => format!("workspace:{}:", id),
+        }
+    }
+}
+
+/// Effective settings: global values, then the active profile's overrides,
+/// then the active workspace's overrides on top
 pub fn get_all_settings() -> Result<AppSettings> {
     let conn = get_connection().lock();
     let mut stmt = conn.prepare("SELECT key, value FROM app_settings")?;
@@ -42,9 +204,33 @@ pub fn get_all_settings() -> Result<AppSettings> {
     })?;
     
     let mut settings_map: HashMap<String, String> = HashMap::new();
+    let mut overrides: Vec<(String, String)> = Vec::new();
     for row in rows {
         let (key, value) = row?;
-        settings_map.insert(key, value);
+        if key.starts_with("workspace:") || key.starts_with("profile:") {
+            overrides.push((key, value));
+        } else {
+            settings_map.insert(key, value);
+        }
+    }
+
+    let active_profile: Option<i64> = conn
+        .query_row("SELECT id FROM profiles WHERE is_active = 1", [], |row| row.get(0))
+        .ok();
+    let active_workspace: Option<i64> = conn
+        .query_row("SELECT id FROM workspaces WHERE is_active = 1", [], |row| row.get(0))
+        .ok();
+    let scopes = [
+        active_profile.map(SettingsScope::Profile),
+        active_workspace.map(SettingsScope::Workspace),
+    ];
+    for scope in scopes.into_iter().flatten() {
+        let prefix = scope.prefix();
+        for (key, value) in &overrides {
+            if let Some(key) = key.strip_prefix(&prefix) {
+                settings_map.insert(key.to_string(), value.clone());
+            }
+        }
     }
     
     let defaults = AppSettings::default_settings();
@@ -61,6 +247,15 @@ pub fn get_all_settings() -> Result<AppSettings> {
         auto_compress: settings_map.get("autoCompress")
             .map(|v| v == "true")
             .unwrap_or(defaults.auto_compress),
+        auto_rotate: settings_map.get("autoRotate")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.auto_rotate),
+        auto_crop: settings_map.get("autoCrop")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.auto_crop),
+        decode_codes: settings_map.get("decodeCodes")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.decode_codes),
         default_temperature: settings_map.get("defaultTemperature")
             .and_then(|v| v.parse().ok())
             .unwrap_or(defaults.default_temperature),
@@ -73,13 +268,106 @@ pub fn get_all_settings() -> Result<AppSettings> {
         default_stream: settings_map.get("defaultStream")
             .map(|v| v == "true")
             .unwrap_or(defaults.default_stream),
+        output_language: settings_map.get("outputLanguage").cloned().unwrap_or(defaults.output_language),
+        user_agent: settings_map.get("userAgent").cloned().unwrap_or(defaults.user_agent),
+        extra_headers: settings_map.get("extraHeaders")
+            .and_then(|v| serde_json::from_str(v).ok())
+            .unwrap_or(defaults.extra_headers),
+        http2_enabled: settings_map.get("http2Enabled")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.http2_enabled),
+        tcp_keepalive_secs: settings_map.get("tcpKeepaliveSecs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.tcp_keepalive_secs),
+        http2_keep_alive_secs: settings_map.get("http2KeepAliveSecs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.http2_keep_alive_secs),
+        connect_timeout_secs: settings_map.get("connectTimeoutSecs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.connect_timeout_secs),
+        pool_idle_timeout_secs: settings_map.get("poolIdleTimeoutSecs")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.pool_idle_timeout_secs),
+        ca_bundle_path: settings_map.get("caBundlePath").cloned().unwrap_or(defaults.ca_bundle_path),
+        provider_concurrency: settings_map.get("providerConcurrency")
+            .and_then(|v| serde_json::from_str(v).ok())
+            .unwrap_or(defaults.provider_concurrency),
+        health_check_minutes: settings_map.get("healthCheckMinutes")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.health_check_minutes),
+        save_failed_recognitions: settings_map.get("saveFailedRecognitions")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.save_failed_recognitions),
+        store_reasoning: settings_map.get("storeReasoning")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.store_reasoning),
+        scrub_history_pii: settings_map.get("scrubHistoryPii")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.scrub_history_pii),
+        estimate_confidence: settings_map.get("estimateConfidence")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.estimate_confidence),
+        low_confidence_threshold: settings_map.get("lowConfidenceThreshold")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.low_confidence_threshold),
+        update_channel: settings_map.get("updateChannel").cloned().unwrap_or(defaults.update_channel),
+        redaction_config_id: settings_map.get("redactionConfigId")
+            .and_then(|v| v.parse().ok())
+            .or(defaults.redaction_config_id),
+        encrypt_history: settings_map.get("encryptHistory")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.encrypt_history),
+        lock_idle_minutes: settings_map.get("lockIdleMinutes")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.lock_idle_minutes),
+        tts_voice: settings_map.get("ttsVoice").cloned().unwrap_or(defaults.tts_voice),
+        tts_rate: settings_map.get("ttsRate")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.tts_rate),
+        notebook_path: settings_map.get("notebookPath").cloned().unwrap_or(defaults.notebook_path),
+        notebook_thumbnails: settings_map.get("notebookThumbnails")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.notebook_thumbnails),
+        inference_device: settings_map.get("inferenceDevice").cloned().unwrap_or(defaults.inference_device),
+        sync_enabled: settings_map.get("syncEnabled")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.sync_enabled),
+        sync_backend: settings_map.get("syncBackend").cloned().unwrap_or(defaults.sync_backend),
+        sync_endpoint: settings_map.get("syncEndpoint").cloned().unwrap_or(defaults.sync_endpoint),
+        sync_bucket: settings_map.get("syncBucket").cloned().unwrap_or(defaults.sync_bucket),
+        sync_region: settings_map.get("syncRegion").cloned().unwrap_or(defaults.sync_region),
+        sync_username: settings_map.get("syncUsername").cloned().unwrap_or(defaults.sync_username),
+        share_target: settings_map.get("shareTarget").cloned().unwrap_or(defaults.share_target),
+        share_endpoint: settings_map.get("shareEndpoint").cloned().unwrap_or(defaults.share_endpoint),
+        share_public: settings_map.get("sharePublic")
+            .map(|v| v == "true")
+            .unwrap_or(defaults.share_public),
+        smtp_host: settings_map.get("smtpHost").cloned().unwrap_or(defaults.smtp_host),
+        smtp_port: settings_map.get("smtpPort")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.smtp_port),
+        smtp_security: settings_map.get("smtpSecurity").cloned().unwrap_or(defaults.smtp_security),
+        smtp_username: settings_map.get("smtpUsername").cloned().unwrap_or(defaults.smtp_username),
+        smtp_from: settings_map.get("smtpFrom").cloned().unwrap_or(defaults.smtp_from),
+        email_recipients: settings_map.get("emailRecipients").cloned().unwrap_or(defaults.email_recipients),
+        notion_database_id: settings_map.get("notionDatabaseId").cloned().unwrap_or(defaults.notion_database_id),
     })
 }
 
-pub fn update_settings(updates: HashMap<String, serde_json::Value>) -> Result<AppSettings> {
+/// Store settings globally or as overrides of one profile or workspace
+pub fn update_settings(
+    updates: HashMap<String, serde_json::Value>,
+    scope: SettingsScope,
+) -> Result<AppSettings> {
     let conn = get_connection().lock();
+    let prefix = scope.prefix();
     
     for (key, value) in updates {
+        // Security values have their own commands
+        if key.starts_with(SECURITY_PREFIX) {
+            continue;
+        }
+        let key = format!("{}{}", prefix, key);
         let value_str = match value {
             serde_json::Value::String(s) => s,
             serde_json::Value::Bool(b) => b.to_string(),
@@ -98,9 +386,92 @@ pub fn update_settings(updates: HashMap<String, serde_json::Value>) -> Result<Ap
     get_all_settings()
 }
 
-pub fn reset_settings() -> Result<AppSettings> {
+/// Reset the global settings (keeping profile and workspace overrides), or
+/// drop all overrides of one profile or workspace
+pub fn reset_settings(scope: SettingsScope) -> Result<AppSettings> {
     let conn = get_connection().lock();
-    conn.execute("DELETE FROM app_settings", [])?;
+    match scope {
+        // Window geometry is app state rather than a setting and survives resets
+        SettingsScope::Global => conn.execute(
+            "DELETE FROM app_settings
+             WHERE key NOT LIKE 'workspace:%' AND key NOT LIKE 'profile:%' AND key NOT LIKE 'window:%'
+                AND key NOT LIKE 'security:%'",
+            [],
+        )?,
+        _ => conn.execute(
+            "DELETE FROM app_settings WHERE substr(key, 1, length(?1)) = ?1",
+            [scope.prefix()],
+        )?,
+    };
     drop(conn);
     get_all_settings()
 }
+
+/// Raw values a profile or workspace overrides, keyed like the global settings
+pub fn get_overrides(scope: SettingsScope) -> Result<HashMap<String, String>> {
+    let conn = get_connection().lock();
+    let prefix = scope.prefix();
+    let mut stmt = conn.prepare(
+        "SELECT key, value FROM app_settings WHERE substr(key, 1, length(?1)) = ?1",
+    )?;
+
+    let rows = stmt.query_map([&prefix], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut overrides = HashMap::new();
+    for row in rows {
+        let (key, value) = row?;
+        overrides.insert(key[prefix.len()..].to_string(), value);
+    }
+    Ok(overrides)
+}
+
+/// Raw value of a single key
+pub fn get_setting(key: &str) -> Result<Option<String>> {
+    let conn = get_connection().lock();
+    match conn.query_row("SELECT value FROM app_settings WHERE key = ?1", [key], |row| row.get(0)) {
+        Ok(value) => Ok(Some(value)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn set_setting(key: &str, value: &str) -> Result<()> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at)
+         VALUES (?1, ?2, datetime('now', 'localtime'))",
+        [key, value],
+    )?;
+    Ok(())
+}
+
+pub fn delete_setting(key: &str) -> Result<()> {
+    let conn = get_connection().lock();
+    conn.execute("DELETE FROM app_settings WHERE key = ?1", [key])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::init_test_database;
+
+    #[test]
+    fn test_workspace_overrides() {
+        init_test_database();
+        let scope = || SettingsScope::Workspace(9001);
+        let updates = HashMap::from([
+            ("compressThreshold".to_string(), serde_json::json!(512)),
+            ("security:passcode".to_string(), serde_json::json!("ignored")),
+        ]);
+        update_settings(updates, scope()).unwrap();
+
+        let overrides = get_overrides(scope()).unwrap();
+        assert_eq!(overrides, HashMap::from([("compressThreshold".to_string(), "512".to_string())]));
+
+        reset_settings(scope()).unwrap();
+        assert!(get_overrides(scope()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsGranularity {
+    Day,
+    Week,
+    Month,
+}
+
+impl StatsGranularity {
+    /// SQL expression naming the local-time bucket of `created_at`: the date
+    /// for days, the Monday for weeks and `YYYY-MM` for months
+    fn bucket_sql(self) -> &'static str {
+        match self {
+            Self::Day => "date(created_at, 'localtime')",
+            Self::Week => "date(created_at, 'localtime', 'weekday 0', '-6 days')",
+            Self::Month => "strftime('%Y-%m', created_at, 'localtime')",
+        }
+    }
+}
+
+/// Usage of one config within one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStatsRow {
+    pub bucket: String,
+    pub config_id: i64,
+    pub config_name: String,
+    /// Successful recognitions
+    pub count: i64,
+    /// Failed recognitions, when failures are recorded
+    pub failure_count: i64,
+    /// Averages over successful recognitions
+    pub avg_duration_ms: Option<f64>,
+    pub avg_tokens: Option<f64>,
+    /// Averages over streamed recognitions
+    pub avg_ttft_ms: Option<f64>,
+    pub avg_tokens_per_second: Option<f64>,
+}
+
+/// Success and failure counts with average duration, tokens and streaming
+/// latency, per bucket and config
+pub fn get_history_stats(granularity: StatsGranularity) -> Result<Vec<HistoryStatsRow>> {
+    let conn = get_connection().lock();
+    let sql = format!(
+        "SELECT {} AS bucket, config_id, MAX(config_name) AS config_name,
+            SUM(status = 'success') AS count,
+            SUM(status = 'failed') AS failure_count,
+            AVG(CASE WHEN status = 'success' THEN duration_ms END) AS avg_duration_ms,
+            AVG(CASE WHEN status = 'success' THEN tokens_used END) AS avg_tokens,
+            AVG(CASE WHEN status = 'success' THEN ttft_ms END) AS avg_ttft_ms,
+            AVG(CASE WHEN status = 'success' THEN tokens_per_second END) AS avg_tokens_per_second
+         FROM recognition_history
+         GROUP BY bucket, config_id
+         ORDER BY bucket, config_id",
+        granularity.bucket_sql()
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(HistoryStatsRow {
+            bucket: row.get("bucket")?,
+            config_id: row.get("config_id")?,
+            config_name: row.get("config_name")?,
+            count: row.get("count")?,
+            failure_count: row.get("failure_count")?,
+            avg_duration_ms: row.get("avg_duration_ms")?,
+            avg_tokens: row.get("avg_tokens")?,
+            avg_ttft_ms: row.get("avg_ttft_ms")?,
+            avg_tokens_per_second: row.get("avg_tokens_per_second")?,
+        })
+    })?;
+    rows.collect()
+}
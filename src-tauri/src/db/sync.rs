@@ -0,0 +1,291 @@
+//! Local side of sync: configs and templates as they go into a snapshot, and
+//! writing newer ones from another machine back with their edit time.
+//! Configs and templates are matched across machines by name, history records
+//! by their uuid. Deletions are remembered as tombstones so they sync too.
+
+use crate::db::{get_connection, history, model_config, profile, prompt_template};
+use crate::db::model_config::{ModelConfigInput, ModelConfigUpdate};
+use crate::services::llm::ImageDetail;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Connection, Result};
+
+/// Kinds of deleted items
+pub const CONFIG: &str = "config";
+pub const TEMPLATE: &str = "template";
+pub const HISTORY: &str = "history";
+
+/// An item deleted on some machine: a config or template by name, a history record by uuid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncDeletion {
+    pub kind: String,
+    pub key: String,
+    /// UTC ISO-8601; an item edited after this comes back
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+    pub name: String,
+    pub provider: String,
+    pub api_url: String,
+    pub api_key: String,
+    pub model_name: String,
+    pub max_tokens: i32,
+    pub requests_per_minute: Option<i32>,
+    pub monthly_budget_usd: Option<f64>,
+    pub input_price_per_mtok: Option<f64>,
+    pub output_price_per_mtok: Option<f64>,
+    pub redact_sensitive: bool,
+    pub image_detail: Option<ImageDetail>,
+    pub reasoning_model: bool,
+    pub thinking_budget_tokens: Option<i32>,
+    #[serde(default)]
+    pub organization: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub skip_tls_verify: bool,
+    pub is_active: bool,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncTemplate {
+    pub name: String,
+    pub content: String,
+    pub updated_at: String,
+}
+
+/// Configs visible in the active profile, with their API keys
+pub fn local_configs() -> Result<Vec<SyncConfig>> {
+    let mut configs = Vec::new();
+    for item in model_config::get_all_configs()? {
+        let Some(config) = model_config::get_config_by_id(item.id)? else {
+            continue;
+        };
+        configs.push(SyncConfig {
+            name: config.name,
+            provider: config.provider,
+            api_url: config.api_url,
+            api_key: config.api_key,
+            model_name: config.model_name,
+            max_tokens: config.max_tokens,
+            requests_per_minute: config.requests_per_minute,
+            monthly_budget_usd: config.monthly_budget_usd,
+            input_price_per_mtok: config.input_price_per_mtok,
+            output_price_per_mtok: config.output_price_per_mtok,
+            redact_sensitive: config.redact_sensitive,
+            image_detail: config.image_detail,
+            reasoning_model: config.reasoning_model,
+            thinking_budget_tokens: config.thinking_budget_tokens,
+            organization: config.organization,
+            project: config.project,
+            skip_tls_verify: config.skip_tls_verify,
+            is_active: config.is_active,
+            updated_at: config.updated_at,
+        });
+    }
+    Ok(configs)
+}
+
+/// Create or overwrite the config of the same name, keeping the remote edit time
+pub fn apply_config(config: &SyncConfig) -> Result<()> {
+    let existing = model_config::get_all_configs()?
+        .into_iter()
+        .find(|item| item.name == config.name)
+        .map(|item| item.id);
+
+    let id = match existing {
+        Some(id) => {
+            model_config::update_config(
+                id,
+                ModelConfigUpdate {
+                    name: None,
+                    provider: Some(config.provider.clone()),
+                    api_url: Some(config.api_url.clone()),
+                    api_key: Some(config.api_key.clone()),
+                    model_name: Some(config.model_name.clone()),
+                    max_tokens: Some(config.max_tokens),
+                    // Zero clears the optional limits
+                    requests_per_minute: Some(config.requests_per_minute.unwrap_or(0)),
+                    monthly_budget_usd: Some(config.monthly_budget_usd.unwrap_or(0.0)),
+                    input_price_per_mtok: Some(config.input_price_per_mtok.unwrap_or(0.0)),
+                    output_price_per_mtok: Some(config.output_price_per_mtok.unwrap_or(0.0)),
+                    redact_sensitive: Some(config.redact_sensitive),
+                    image_detail: Some(config.image_detail.map_or("", |detail| detail.as_str()).to_string()),
+                    reasoning_model: Some(config.reasoning_model),
+                    thinking_budget_tokens: Some(config.thinking_budget_tokens.unwrap_or(0)),
+                    organization: Some(config.organization.clone().unwrap_or_default()),
+                    project: Some(config.project.clone().unwrap_or_default()),
+                    skip_tls_verify: Some(config.skip_tls_verify),
+                    profile_id: None,
+                    is_active: Some(config.is_active),
+                    is_default: None,
+                },
+            )?;
+            id
+        }
+        None => {
+            model_config::create_config(ModelConfigInput {
+                name: config.name.clone(),
+                provider: config.provider.clone(),
+                api_url: config.api_url.clone(),
+                api_key: config.api_key.clone(),
+                model_name: config.model_name.clone(),
+                max_tokens: Some(config.max_tokens),
+                requests_per_minute: config.requests_per_minute,
+                monthly_budget_usd: config.monthly_budget_usd,
+                input_price_per_mtok: config.input_price_per_mtok,
+                output_price_per_mtok: config.output_price_per_mtok,
+                redact_sensitive: Some(config.redact_sensitive),
+                image_detail: config.image_detail,
+                reasoning_model: Some(config.reasoning_model),
+                thinking_budget_tokens: config.thinking_budget_tokens,
+                organization: config.organization.clone(),
+                project: config.project.clone(),
+                skip_tls_verify: Some(config.skip_tls_verify),
+                profile_id: None,
+                is_active: Some(config.is_active),
+                is_default: None,
+            })?
+            .id
+        }
+    };
+
+    let conn = get_connection().lock();
+    conn.execute(
+        "UPDATE model_configs SET updated_at = ?1 WHERE id = ?2",
+        params![config.updated_at, id],
+    )?;
+    Ok(())
+}
+
+/// Templates visible in the active profile
+pub fn local_templates() -> Result<Vec<SyncTemplate>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT name, content, COALESCE(updated_at, created_at) FROM prompt_templates WHERE {} ORDER BY id",
+        profile::visible_sql("profile_id")
+    ))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SyncTemplate {
+            name: row.get(0)?,
+            content: row.get(1)?,
+            updated_at: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Create or overwrite the template of the same name, keeping the remote edit time
+pub fn apply_template(template: &SyncTemplate) -> Result<()> {
+    let profile_id = profile::active_profile_id()?;
+    let conn = get_connection().lock();
+    let existing: Option<i64> = conn
+        .query_row(
+            &format!(
+                "SELECT id FROM prompt_templates WHERE name = ?1 AND {} ORDER BY id LIMIT 1",
+                profile::visible_sql("profile_id")
+            ),
+            [&template.name],
+            |row| row.get(0),
+        )
+        .ok();
+
+    match existing {
+        Some(id) => conn.execute(
+            "UPDATE prompt_templates SET content = ?1, updated_at = ?2 WHERE id = ?3",
+            params![template.content, template.updated_at, id],
+        )?,
+        None => conn.execute(
+            "INSERT INTO prompt_templates (name, content, profile_id, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![template.name, template.content, profile_id, template.updated_at],
+        )?,
+    };
+    Ok(())
+}
+
+/// Remember that items were deleted now
+pub(crate) fn record_deletions(conn: &Connection, kind: &str, keys: &[String]) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO sync_deletions (kind, key, deleted_at) VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+         ON CONFLICT(kind, key) DO UPDATE SET deleted_at = excluded.deleted_at",
+    )?;
+    for key in keys {
+        stmt.execute(params![kind, key])?;
+    }
+    Ok(())
+}
+
+pub fn local_deletions() -> Result<Vec<SyncDeletion>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare("SELECT kind, key, deleted_at FROM sync_deletions ORDER BY kind, key")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SyncDeletion {
+            kind: row.get(0)?,
+            key: row.get(1)?,
+            deleted_at: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Store the merged tombstones, replacing the times of local ones
+pub fn save_deletions(deletions: &[SyncDeletion]) -> Result<()> {
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO sync_deletions (kind, key, deleted_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(kind, key) DO UPDATE SET deleted_at = excluded.deleted_at",
+        )?;
+        for deletion in deletions {
+            stmt.execute(params![deletion.kind, deletion.key, deletion.deleted_at])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Delete the config of this name deleted on another machine
+pub fn delete_config(name: &str) -> Result<()> {
+    if let Some(item) = model_config::get_all_configs()?.into_iter().find(|item| item.name == name) {
+        model_config::delete_config(item.id)?;
+    }
+    Ok(())
+}
+
+/// Delete the templates of this name deleted on another machine
+pub fn delete_template(name: &str) -> Result<()> {
+    let ids: Vec<i64> = {
+        let conn = get_connection().lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM prompt_templates WHERE name = ?1 AND {}",
+            profile::visible_sql("profile_id")
+        ))?;
+        let rows = stmt.query_map([name], |row| row.get(0))?;
+        rows.collect::<Result<_>>()?
+    };
+    for id in ids {
+        prompt_template::delete_template(id)?;
+    }
+    Ok(())
+}
+
+/// Delete the history records with these uuids deleted on another machine
+pub fn delete_history(uuids: &[String]) -> Result<usize> {
+    let ids: Vec<i64> = {
+        let conn = get_connection().lock();
+        let mut stmt = conn.prepare("SELECT id FROM recognition_history WHERE uuid = ?1")?;
+        let mut ids = Vec::new();
+        for uuid in uuids {
+            if let Some(id) = stmt.query_map([uuid], |row| row.get(0))?.next() {
+                ids.push(id?);
+            }
+        }
+        ids
+    };
+    history::delete_history_records(&ids)
+}
@@ -0,0 +1,117 @@
+use crate::db::get_connection;
+use crate::services::image_store;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, OptionalExtension, Result};
+
+/// An example image and the answer expected for it. Examples of a template
+/// are sent before the real image as earlier turns of the conversation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateExample {
+    pub id: i64,
+    pub template_id: i64,
+    /// Image in the app image store
+    pub image_file: String,
+    pub mime_type: String,
+    pub output: String,
+    pub sort_order: i32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateExampleInput {
+    pub template_id: i64,
+    pub image_data: String,
+    pub image_mime_type: String,
+    pub output: String,
+}
+
+/// Examples a template may carry; each adds an image to every request
+pub const MAX_EXAMPLES_PER_TEMPLATE: usize = 5;
+
+const EXAMPLE_COLUMNS: &str = "id, template_id, image_file, mime_type, output, sort_order, created_at";
+
+fn row_to_example(row: &rusqlite::Row) -> Result<TemplateExample> {
+    Ok(TemplateExample {
+        id: row.get("id")?,
+        template_id: row.get("template_id")?,
+        image_file: row.get("image_file")?,
+        mime_type: row.get("mime_type")?,
+        output: row.get("output")?,
+        sort_order: row.get("sort_order")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Examples of a template in the order they are sent
+pub fn get_examples(template_id: i64) -> Result<Vec<TemplateExample>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM template_examples WHERE template_id = ?1 ORDER BY sort_order, id",
+        EXAMPLE_COLUMNS
+    ))?;
+    let examples = stmt.query_map([template_id], row_to_example)?.collect();
+    examples
+}
+
+pub fn get_example(id: i64) -> Result<Option<TemplateExample>> {
+    let conn = get_connection().lock();
+    conn.query_row(
+        &format!("SELECT {} FROM template_examples WHERE id = ?1", EXAMPLE_COLUMNS),
+        [id],
+        row_to_example,
+    )
+    .optional()
+}
+
+/// Add an example after the existing ones; the image is copied to the image store
+pub fn add_example(input: TemplateExampleInput) -> Result<TemplateExample> {
+    let image_file = image_store::save_image(&input.image_data, &input.image_mime_type)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    let conn = get_connection().lock();
+    let inserted = conn.execute(
+        "INSERT INTO template_examples (template_id, image_file, mime_type, output, sort_order)
+         VALUES (?1, ?2, ?3, ?4,
+            (SELECT COALESCE(MAX(sort_order), -1) + 1 FROM template_examples WHERE template_id = ?1))",
+        params![input.template_id, image_file, input.image_mime_type, input.output],
+    );
+    if let Err(e) = inserted {
+        image_store::delete_image(&image_file);
+        return Err(e);
+    }
+    conn.query_row(
+        &format!("SELECT {} FROM template_examples WHERE id = ?1", EXAMPLE_COLUMNS),
+        [conn.last_insert_rowid()],
+        row_to_example,
+    )
+}
+
+pub fn update_example_output(id: i64, output: &str) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("UPDATE template_examples SET output = ?1 WHERE id = ?2", params![output, id])?;
+    Ok(changes > 0)
+}
+
+pub fn delete_example(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let image_file: Option<String> = conn
+        .query_row("SELECT image_file FROM template_examples WHERE id = ?1", [id], |row| row.get(0))
+        .ok();
+    let changes = conn.execute("DELETE FROM template_examples WHERE id = ?1", [id])?;
+    if let Some(file) = image_file {
+        image_store::delete_image(&file);
+    }
+    Ok(changes > 0)
+}
+
+/// Remove the examples of a deleted template along with their images
+pub fn delete_examples_of(conn: &rusqlite::Connection, template_id: i64) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT image_file FROM template_examples WHERE template_id = ?1")?;
+    let files: Vec<String> = stmt.query_map([template_id], |row| row.get(0))?.collect::<Result<_>>()?;
+    conn.execute("DELETE FROM template_examples WHERE template_id = ?1", [template_id])?;
+    for file in files {
+        image_store::delete_image(&file);
+    }
+    Ok(())
+}
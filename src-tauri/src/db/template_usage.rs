@@ -0,0 +1,132 @@
+use crate::db::{get_connection, profile, PaginatedResult};
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TemplateStatsSort {
+    #[default]
+    LastUsed,
+    Runs,
+    SuccessRate,
+}
+
+impl TemplateStatsSort {
+    /// ORDER BY clause; templates never run go last
+    fn order_sql(self) -> &'static str {
+        match self {
+            Self::LastUsed => "last_used_at IS NULL, last_used_at DESC, t.use_count DESC",
+            Self::Runs => "run_count DESC, last_used_at DESC",
+            Self::SuccessRate => {
+                "run_count = 0, CAST(success_count AS REAL) / MAX(run_count, 1) DESC, run_count DESC"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateStatsParams {
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+    /// Match against template name or content
+    pub keyword: Option<String>,
+    /// Count only runs with this config
+    pub config_id: Option<i64>,
+    pub sort: Option<TemplateStatsSort>,
+}
+
+/// Recognition runs of one template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateStats {
+    pub template_id: i64,
+    pub template_name: String,
+    /// Times the template was picked, including before runs were tracked
+    pub use_count: i32,
+    pub run_count: i64,
+    pub success_count: i64,
+    /// `None` until the template has run
+    pub success_rate: Option<f64>,
+    pub last_used_at: Option<String>,
+}
+
+/// Record one finished recognition that used a template
+pub fn record_usage(template_id: i64, config_id: i64, success: bool) -> Result<()> {
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO template_usage (template_id, config_id, success) VALUES (?1, ?2, ?3)",
+        params![template_id, config_id, success],
+    )?;
+    Ok(())
+}
+
+pub fn get_template_stats(params: TemplateStatsParams) -> Result<PaginatedResult<TemplateStats>> {
+    let conn = get_connection().lock();
+
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params.page_size.unwrap_or(20).max(1);
+    let offset = (page - 1) * page_size;
+
+    let mut bind_values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    let mut where_sql = format!("WHERE {}", profile::visible_sql("t.profile_id"));
+    if let Some(keyword) = params.keyword.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        let pattern = format!("%{}%", keyword);
+        bind_values.push(Box::new(pattern.clone()));
+        bind_values.push(Box::new(pattern));
+        where_sql.push_str(" AND (t.name LIKE ? OR t.content LIKE ?)");
+    }
+
+    let count_sql = format!("SELECT COUNT(*) FROM prompt_templates t {}", where_sql);
+    let count_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let total: i64 = conn.query_row(&count_sql, count_params.as_slice(), |row| row.get(0))?;
+
+    // The config filter belongs to the join, so templates without matching runs still show up
+    let join_sql = match params.config_id {
+        Some(config_id) => {
+            bind_values.insert(0, Box::new(config_id));
+            "AND u.config_id = ?"
+        }
+        None => "",
+    };
+    let query_sql = format!(
+        "SELECT t.id AS template_id, t.name AS template_name, t.use_count,
+            COUNT(u.id) AS run_count,
+            COALESCE(SUM(u.success), 0) AS success_count,
+            MAX(u.created_at) AS last_used_at
+         FROM prompt_templates t
+         LEFT JOIN template_usage u ON u.template_id = t.id {}
+         {}
+         GROUP BY t.id
+         ORDER BY {}
+         LIMIT ? OFFSET ?",
+        join_sql,
+        where_sql,
+        params.sort.unwrap_or_default().order_sql()
+    );
+    bind_values.push(Box::new(page_size));
+    bind_values.push(Box::new(offset));
+
+    let query_params: Vec<&dyn rusqlite::ToSql> = bind_values.iter().map(|v| v.as_ref()).collect();
+    let mut stmt = conn.prepare(&query_sql)?;
+    let rows = stmt.query_map(query_params.as_slice(), |row| {
+        let run_count: i64 = row.get("run_count")?;
+        let success_count: i64 = row.get("success_count")?;
+        Ok(TemplateStats {
+            template_id: row.get("template_id")?,
+            template_name: row.get("template_name")?,
+            use_count: row.get("use_count")?,
+            run_count,
+            success_count,
+            success_rate: (run_count > 0).then(|| success_count as f64 / run_count as f64),
+            last_used_at: row.get("last_used_at")?,
+        })
+    })?;
+
+    Ok(PaginatedResult {
+        records: rows.collect::<Result<_>>()?,
+        total,
+        page,
+        page_size,
+    })
+}
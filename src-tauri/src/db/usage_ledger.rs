@@ -0,0 +1,76 @@
+use crate::db::get_connection;
+use rusqlite::{params, Result};
+
+/// Tokens a config used in the current calendar month
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonthTokens {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Totals of calls without an input/output split
+    pub unsplit_tokens: i64,
+}
+
+/// Record the tokens of one provider call. Calls that report no usage are skipped.
+pub fn record_usage(
+    config_id: i64,
+    input_tokens: Option<i32>,
+    output_tokens: Option<i32>,
+    tokens_used: Option<i32>,
+) -> Result<()> {
+    if input_tokens.is_none() && output_tokens.is_none() && tokens_used.is_none() {
+        return Ok(());
+    }
+    let conn = get_connection().lock();
+    conn.execute(
+        "INSERT INTO usage_ledger (config_id, input_tokens, output_tokens, tokens_used) VALUES (?1, ?2, ?3, ?4)",
+        params![config_id, input_tokens, output_tokens, tokens_used],
+    )?;
+    Ok(())
+}
+
+pub fn get_month_tokens(config_id: i64) -> Result<MonthTokens> {
+    let conn = get_connection().lock();
+    conn.query_row(
+        "SELECT COALESCE(SUM(input_tokens), 0) AS input_tokens,
+            COALESCE(SUM(output_tokens), 0) AS output_tokens,
+            COALESCE(SUM(CASE WHEN input_tokens IS NULL AND output_tokens IS NULL THEN tokens_used END), 0)
+                AS unsplit_tokens
+         FROM usage_ledger
+         WHERE config_id = ?1
+            AND strftime('%Y-%m', created_at, 'localtime') = strftime('%Y-%m', 'now', 'localtime')",
+        [config_id],
+        |row| {
+            Ok(MonthTokens {
+                input_tokens: row.get("input_tokens")?,
+                output_tokens: row.get("output_tokens")?,
+                unsplit_tokens: row.get("unsplit_tokens")?,
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::init_test_database;
+
+    #[test]
+    fn test_month_tokens_count_only_this_month() {
+        init_test_database();
+        let config_id = -3343;
+        record_usage(config_id, Some(100), Some(20), Some(120)).unwrap();
+        record_usage(config_id, None, None, Some(50)).unwrap();
+        record_usage(config_id, None, None, None).unwrap();
+        get_connection()
+            .lock()
+            .execute(
+                "INSERT INTO usage_ledger (config_id, input_tokens, output_tokens, created_at)
+                 VALUES (?1, 1000, 1000, '2000-01-01T00:00:00Z')",
+                [config_id],
+            )
+            .unwrap();
+
+        let tokens = get_month_tokens(config_id).unwrap();
+        assert_eq!((tokens.input_tokens, tokens.output_tokens, tokens.unsplit_tokens), (100, 20, 50));
+    }
+}
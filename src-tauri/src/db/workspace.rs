@@ -0,0 +1,201 @@
+use crate::db::get_connection;
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result};
+
+/// A named bundle of defaults, e.g. "Accounting" or "Thesis"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    /// Preset: model config and prompt template made default on activation
+    pub config_id: Option<i64>,
+    pub template_id: Option<i64>,
+    /// Domain terms the model is asked to spell exactly as given
+    pub glossary: Vec<String>,
+    /// Stored with the history records recognized in the workspace
+    pub tags: Vec<String>,
+    /// Export profile: format of the files written to the output sink ("json" or "csv")
+    pub export_format: String,
+    /// Output sink: directory successful results are also written to
+    pub output_dir: Option<String>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInput {
+    pub name: String,
+    pub config_id: Option<i64>,
+    pub template_id: Option<i64>,
+    #[serde(default)]
+    pub glossary: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub export_format: Option<String>,
+    pub output_dir: Option<String>,
+}
+
+const WORKSPACE_COLUMNS: &str = "id, name, config_id, template_id, glossary, tags, export_format, \
+    output_dir, is_active, created_at, updated_at";
+
+fn row_to_workspace(row: &rusqlite::Row) -> Result<Workspace> {
+    let glossary: String = row.get("glossary")?;
+    let tags: String = row.get("tags")?;
+    Ok(Workspace {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        config_id: row.get("config_id")?,
+        template_id: row.get("template_id")?,
+        glossary: serde_json::from_str(&glossary).unwrap_or_default(),
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        export_format: row.get("export_format")?,
+        output_dir: row.get("output_dir")?,
+        is_active: row.get::<_, i32>("is_active")? == 1,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn to_json(values: &[String]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+pub fn get_all_workspaces() -> Result<Vec<Workspace>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workspaces ORDER BY name",
+        WORKSPACE_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([], row_to_workspace)?;
+    rows.collect()
+}
+
+pub fn get_workspace_by_id(id: i64) -> Result<Option<Workspace>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workspaces WHERE id = ?1",
+        WORKSPACE_COLUMNS
+    ))?;
+
+    match stmt.query_row([id], row_to_workspace) {
+        Ok(workspace) => Ok(Some(workspace)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn get_active_workspace() -> Result<Option<Workspace>> {
+    let conn = get_connection().lock();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workspaces WHERE is_active = 1",
+        WORKSPACE_COLUMNS
+    ))?;
+
+    match stmt.query_row([], row_to_workspace) {
+        Ok(workspace) => Ok(Some(workspace)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn create_workspace(input: WorkspaceInput) -> Result<Workspace> {
+    let conn = get_connection().lock();
+
+    conn.execute(
+        "INSERT INTO workspaces (name, config_id, template_id, glossary, tags, export_format, output_dir)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            input.name,
+            input.config_id,
+            input.template_id,
+            to_json(&input.glossary),
+            to_json(&input.tags),
+            input.export_format.unwrap_or_else(|| "json".to_string()),
+            input.output_dir.filter(|dir| !dir.trim().is_empty()),
+        ],
+    )?;
+
+    let id = conn.last_insert_rowid();
+    drop(conn);
+
+    get_workspace_by_id(id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+pub fn update_workspace(id: i64, input: WorkspaceInput) -> Result<Option<Workspace>> {
+    let conn = get_connection().lock();
+
+    let changes = conn.execute(
+        "UPDATE workspaces SET name = ?1, config_id = ?2, template_id = ?3, glossary = ?4, tags = ?5,
+            export_format = ?6, output_dir = ?7, updated_at = datetime('now', 'localtime')
+         WHERE id = ?8",
+        params![
+            input.name,
+            input.config_id,
+            input.template_id,
+            to_json(&input.glossary),
+            to_json(&input.tags),
+            input.export_format.unwrap_or_else(|| "json".to_string()),
+            input.output_dir.filter(|dir| !dir.trim().is_empty()),
+            id,
+        ],
+    )?;
+    drop(conn);
+
+    if changes == 0 {
+        return Ok(None);
+    }
+    get_workspace_by_id(id)
+}
+
+pub fn delete_workspace(id: i64) -> Result<bool> {
+    let conn = get_connection().lock();
+    let changes = conn.execute("DELETE FROM workspaces WHERE id = ?1", [id])?;
+    // Drop the workspace's settings overrides too
+    conn.execute(
+        "DELETE FROM app_settings WHERE substr(key, 1, length(?1)) = ?1",
+        [format!("workspace:{}:", id)],
+    )?;
+    Ok(changes > 0)
+}
+
+/// Make a workspace active and switch the default config and template to its
+/// preset in one transaction. `None` deactivates all workspaces and leaves the
+/// current defaults in place.
+pub fn set_active_workspace(id: Option<i64>) -> Result<Option<Workspace>> {
+    let Some(id) = id else {
+        let conn = get_connection().lock();
+        conn.execute("UPDATE workspaces SET is_active = 0", [])?;
+        return Ok(None);
+    };
+
+    let Some(workspace) = get_workspace_by_id(id)? else {
+        return Ok(None);
+    };
+
+    let mut conn = get_connection().lock();
+    let tx = conn.transaction()?;
+    tx.execute("UPDATE workspaces SET is_active = CASE WHEN id = ?1 THEN 1 ELSE 0 END", [id])?;
+
+    if let Some(config_id) = workspace.config_id {
+        tx.execute(
+            "UPDATE model_configs SET is_default = CASE WHEN id = ?1 THEN 1 ELSE 0 END
+             WHERE EXISTS (SELECT 1 FROM model_configs WHERE id = ?1)",
+            [config_id],
+        )?;
+    }
+    if let Some(template_id) = workspace.template_id {
+        tx.execute(
+            "UPDATE prompt_templates SET is_default = CASE WHEN id = ?1 THEN 1 ELSE 0 END
+             WHERE EXISTS (SELECT 1 FROM prompt_templates WHERE id = ?1)",
+            [template_id],
+        )?;
+    }
+    tx.commit()?;
+    drop(conn);
+
+    get_workspace_by_id(id)
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::fmt;
+use crate::services::i18n::ErrorCode;
+
+/// Error returned by commands.
+///
+/// Serialized as `{ "kind": "...", "message": "...", ... }` so the frontend can
+/// branch on the kind instead of matching messages; `message` is localized for
+/// display.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum AppError {
+    /// Reading or writing the local database failed
+    Db { message: String },
+    /// The provider failed the request; `status` is set when it answered over HTTP
+    Provider {
+        status: Option<u16>,
+        code: Option<ErrorCode>,
+        message: String,
+    },
+    /// The image couldn't be read, decoded or processed
+    Image { code: Option<ErrorCode>, message: String },
+    /// The request can't be carried out: missing records, invalid input or state
+    Validation { code: Option<ErrorCode>, message: String },
+    Cancelled { message: String },
+    /// Not classified further, e.g. file system and window errors
+    Internal { message: String },
+}
+
+impl AppError {
+    pub fn internal(e: impl fmt::Display) -> Self {
+        Self::Internal { message: e.to_string() }
+    }
+
+    pub fn image(e: impl fmt::Display) -> Self {
+        Self::Image { code: None, message: e.to_string() }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Db { message }
+            | Self::Provider { message, .. }
+            | Self::Image { message, .. }
+            | Self::Validation { message, .. }
+            | Self::Cancelled { message }
+            | Self::Internal { message } => message,
+        }
+    }
+
+    fn with_message(code: ErrorCode, message: String) -> Self {
+        let provider = |status: Option<u16>, code: ErrorCode, message: String| Self::Provider {
+            status,
+            code: Some(code),
+            message,
+        };
+        match code {
+            ErrorCode::RecognitionCancelled => Self::Cancelled { message },
+            ErrorCode::ConfigLoadFailed { .. } => Self::Db { message },
+            ErrorCode::EmptyImage
+            | ErrorCode::ImageProcessingFailed { .. }
+            | ErrorCode::UnsupportedImageFile { .. }
+            | ErrorCode::ImageTooLarge { .. } => {
+                Self::Image { code: Some(code), message }
+            }
+            ErrorCode::InvalidApiKey => provider(Some(401), code, message),
+            ErrorCode::ApiKeyForbidden => provider(Some(403), code, message),
+            ErrorCode::EndpointNotFound => provider(Some(404), code, message),
+            ErrorCode::RateLimited => provider(Some(429), code, message),
+            ErrorCode::ProviderMessage { status, .. } | ErrorCode::ServerError { status, .. } => {
+                provider(Some(status), code, message)
+            }
+            ErrorCode::HttpClientFailed { .. }
+            | ErrorCode::RequestTimeout
+            | ErrorCode::ConnectFailed
+            | ErrorCode::RequestFailed { .. }
+            | ErrorCode::InvalidResponse { .. }
+            | ErrorCode::UnexpectedResponse
+            | ErrorCode::LocalOcrFailed { .. } => provider(None, code, message),
+            _ => Self::Validation { code: Some(code), message },
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Db { message: e.to_string() }
+    }
+}
+
+impl From<ErrorCode> for AppError {
+    fn from(code: ErrorCode) -> Self {
+        let message = code.localized();
+        Self::with_message(code, message)
+    }
+}
+
+/// Plain string failures of services, e.g. file system errors
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Internal { message }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(code: ErrorCode) -> AppError {
+        AppError::with_message(code, String::new())
+    }
+
+    #[test]
+    fn test_error_code_kinds() {
+        assert!(matches!(kind(ErrorCode::RecognitionCancelled), AppError::Cancelled { .. }));
+        assert!(matches!(kind(ErrorCode::EmptyImage), AppError::Image { .. }));
+        assert!(matches!(kind(ErrorCode::RateLimited), AppError::Provider { status: Some(429), .. }));
+        assert!(matches!(
+            kind(ErrorCode::ServerError { status: 502, body: String::new() }),
+            AppError::Provider { status: Some(502), .. }
+        ));
+        assert!(matches!(kind(ErrorCode::RequestTimeout), AppError::Provider { status: None, .. }));
+        assert!(matches!(kind(ErrorCode::HistoryNotFound), AppError::Validation { .. }));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let value = serde_json::to_value(AppError::Provider {
+            status: Some(429),
+            code: Some(ErrorCode::RateLimited),
+            message: "slow down".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "kind": "provider",
+                "status": 429,
+                "code": { "code": "rate_limited" },
+                "message": "slow down",
+            })
+        );
+
+        let value = serde_json::to_value(AppError::from(rusqlite::Error::QueryReturnedNoRows)).unwrap();
+        assert_eq!(value["kind"], "db");
+    }
+}
@@ -3,20 +3,66 @@
 
 mod commands;
 mod db;
+mod error;
 mod services;
 mod utils;
 
-use tauri::Manager;
+use tauri::{Listener, Manager};
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 pub fn run() {
-    tauri::Builder::default()
+    // `--recognize <image>` runs headlessly instead of opening the window
+    let args: Vec<String> = std::env::args().collect();
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let cli_request = match services::cli::parse_args(&args, &cwd) {
+        Ok(request) => request,
+        Err(e) => {
+            services::cli::attach_parent_console();
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let headless = cli_request.is_some();
+    if headless {
+        services::cli::attach_parent_console();
+    }
+    let guest = services::cli::is_guest(&args);
+
+    let mut builder = tauri::Builder::default();
+    // A second launch forwards its arguments to this instance and exits. A guest
+    // session runs next to the normal one instead of being handed to it.
+    if !guest {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            match services::cli::parse_args(&argv, Path::new(&cwd)) {
+                // The caller's console isn't ours, so only `--output` gets the result
+                Ok(Some(request)) => {
+                    if request.output.is_none() {
+                        eprintln!("[CLI] Forwarded request without --output, the result is only kept in history");
+                    }
+                    tauri::async_runtime::spawn(services::cli::run_and_report(request));
+                }
+                Ok(None) => {
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.unminimize();
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        }));
+    }
+
+    let app = builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_fs::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_drag::init())
+        .setup(move |app| {
             // Remove default menu on Windows to prevent "overflow menu"
             #[cfg(target_os = "windows")]
             {
@@ -25,19 +71,74 @@ pub fn run() {
                 app.set_menu(menu)?;
             }
 
-            // Initialize database
-            let app_data_dir = app.path().app_data_dir().expect("Failed to get app data dir");
-            db::init_database(&app_data_dir).expect("Failed to initialize database");
+            // Initialize database. Guest mode keeps it in memory and stores no images.
+            if guest {
+                db::connection::init_database_at(Path::new(db::connection::IN_MEMORY))
+                    .expect("Failed to initialize database");
+            } else {
+                let app_data_dir = app.path().app_data_dir().expect("Failed to get app data dir");
+                std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data dir");
+                utils::crypto::init_key(&app_data_dir).expect("Failed to load the data key");
+                db::init_database(&app_data_dir).expect("Failed to initialize database");
+                services::image_store::init_image_store(&app_data_dir).expect("Failed to initialize image store");
+                if let Ok(app_cache_dir) = app.path().app_cache_dir() {
+                    services::file_manager::init_drag_dir(&app_cache_dir);
+                }
+            }
+
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                services::rapidocr::init_bundled_models(&resource_dir);
+                services::annotate::init_bundled_font(&resource_dir);
+            }
 
             // Initialize recognition state
             let recognition_state = Arc::new(Mutex::new(commands::recognition::RecognitionState::new()));
             app.manage(recognition_state);
+            app.manage(commands::updater::UpdateStateHandle::default());
+            app.manage(commands::dialog::SavePathsHandle::default());
+            // With a passcode set, commands are refused until it is entered
+            app.manage(commands::lock::LockStateHandle::new(parking_lot::Mutex::new(
+                services::lock::LockState::new(services::lock::has_passcode()),
+            )));
+
+            // Background subsystems pick up changed settings without a restart
+            app.listen_any(commands::settings::SETTINGS_CHANGED, |event| {
+                if let Ok(change) = serde_json::from_str::<commands::settings::SettingsChanged>(event.payload()) {
+                    services::config_health::settings_changed(&change.keys);
+                }
+            });
+
+            // Headless run: drop the window and exit once the result is delivered.
+            // Otherwise bring the main window back to where it was last closed and
+            // start checking the configured providers and syncing in the background.
+            if let Some(request) = cli_request {
+                if let Some(window) = app.get_webview_window("main") {
+                    window.destroy()?;
+                }
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let code = services::cli::run_and_report(request).await;
+                    handle.exit(code);
+                });
+            } else {
+                if let Some(window) = app.get_webview_window("main") {
+                    commands::window::restore_window_state(&window);
+                }
+                tauri::async_runtime::spawn(services::config_health::run_health_checks());
+                tauri::async_runtime::spawn(services::sync::sync_on_startup());
+            }
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .on_window_event(|window, event| {
+            if window.label() == "main" && matches!(event, tauri::WindowEvent::CloseRequested { .. }) {
+                commands::window::save_window_state(window);
+            }
+        })
+        .invoke_handler(commands::lock::guard(tauri::generate_handler![
             // Config commands
             commands::config::get_all_configs,
+            commands::config::query_configs,
             commands::config::get_active_configs,
             commands::config::get_config_by_id,
             commands::config::get_default_config,
@@ -47,35 +148,180 @@ pub fn run() {
             commands::config::set_default_config,
             commands::config::test_connection,
             commands::config::test_connection_with_data,
+            commands::config::revalidate_all_configs,
+            commands::config::get_provider_status,
+            commands::config::refresh_provider_status,
+            commands::config::list_models,
+            commands::config::refresh_models,
+            commands::config::get_budget_status,
             // History commands
             commands::history::get_history_records,
             commands::history::get_history_by_id,
+            commands::history::mark_history_reviewed,
+            commands::history::get_review_batches,
+            commands::history::list_pending_reviews,
+            commands::history::review_history,
+            commands::history::export_approved_history,
+            commands::history::rerun_many,
             commands::history::delete_history,
             commands::history::delete_multiple_history,
             commands::history::clear_all_history,
             commands::history::export_history,
+            commands::history::export_history_bundle,
+            commands::history::import_history,
+            commands::history::verify_history_integrity,
+            commands::history::set_history_encryption,
+            commands::history::get_history_stats,
+            commands::history::get_history_image,
+            commands::history::find_similar_history,
+            commands::history::append_to_notebook,
+            commands::history::reveal_in_folder,
+            commands::history::create_drag_file,
             // Template commands
             commands::template::get_all_templates,
+            commands::template::query_templates,
+            commands::prompt_history::get_prompt_suggestions,
+            commands::prompt_history::delete_prompt_history,
+            commands::prompt_history::clear_prompt_history,
             commands::template::get_default_template,
             commands::template::get_recent_templates,
             commands::template::create_template,
             commands::template::update_template,
             commands::template::delete_template,
+            commands::template::dedupe_templates,
+            commands::template::get_template_examples,
+            commands::template::get_template_example_image,
+            commands::template::add_template_example,
+            commands::template::update_template_example,
+            commands::template::delete_template_example,
             commands::template::increment_template_use,
+            commands::template::get_template_stats,
+            commands::template::lint_prompt,
             // Settings commands
             commands::settings::get_all_settings,
             commands::settings::update_settings,
+            commands::settings::get_settings_schema,
             commands::settings::reset_settings,
+            commands::settings::get_workspace_settings,
+            commands::settings::get_profile_settings,
+            commands::settings::initialize_defaults,
+            commands::settings::is_guest_mode,
+            commands::settings::get_inference_status,
             // Recognition commands
             commands::recognition::recognize,
+            commands::recognition::recognize_with_preset,
             commands::recognition::cancel_recognition,
+            commands::recognition::extract_frame,
+            commands::recognition::decode_codes,
+            commands::recognition::stitch_screenshots,
+            commands::recognition::compare_images,
+            // Experiment commands
+            commands::experiment::get_all_experiments,
+            commands::experiment::create_experiment,
+            commands::experiment::delete_experiment,
+            commands::experiment::run_experiment,
+            commands::experiment::pause_experiment,
+            commands::experiment::resume_experiment,
+            commands::experiment::rate_experiment_run,
+            commands::experiment::get_experiment_report,
+            commands::experiment::ab_test,
+            commands::experiment::get_all_ab_tests,
+            commands::experiment::get_ab_test,
+            commands::experiment::delete_ab_test,
+            commands::backup::backup_database,
+            commands::backup::restore_database,
+            // Workspace commands
+            commands::workspace::get_all_workspaces,
+            commands::workspace::get_active_workspace,
+            commands::workspace::create_workspace,
+            commands::workspace::update_workspace,
+            commands::workspace::delete_workspace,
+            commands::workspace::set_active_workspace,
+            // Glossary commands
+            commands::glossary::get_glossary,
+            commands::glossary::save_glossary_entry,
+            commands::glossary::delete_glossary_entry,
+            // Post-processing rule commands
+            commands::postprocess::get_postprocess_rules,
+            commands::postprocess::create_postprocess_rule,
+            commands::postprocess::update_postprocess_rule,
+            commands::postprocess::delete_postprocess_rule,
+            commands::postprocess::reorder_postprocess_rules,
+            commands::postprocess::test_postprocess_rule,
+            // Profile commands
+            commands::profile::get_all_profiles,
+            commands::profile::get_active_profile,
+            commands::profile::create_profile,
+            commands::profile::rename_profile,
+            commands::profile::delete_profile,
+            commands::profile::set_active_profile,
+            commands::preset::get_all_presets,
+            commands::preset::get_default_preset,
+            commands::preset::create_preset,
+            commands::preset::update_preset,
+            commands::preset::delete_preset,
+            commands::preset::set_default_preset,
+            commands::recipe::export_recipe,
+            commands::recipe::run_recipe,
+            commands::table::extract_tables,
+            commands::table::export_tables,
+            commands::annotation::render_annotations,
+            commands::annotation::export_annotated_image,
+            commands::math::check_formulas,
+            // Updater commands
+            commands::updater::check_for_update,
+            commands::updater::download_update,
+            commands::updater::install_update,
+            // Window commands
+            commands::window::open_result_window,
+            commands::window::toggle_mini_window,
             // Dialog commands
             commands::dialog::select_image,
+            commands::dialog::read_image_file,
+            commands::dialog::select_save_path,
             commands::dialog::save_file,
             // Clipboard commands
             commands::clipboard::read_clipboard_image,
             commands::clipboard::write_clipboard_text,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+            // Extraction commands
+            commands::extraction::query_extractions,
+            commands::extraction::get_extraction_by_history,
+            commands::extraction::save_vcard,
+            // Speech commands
+            commands::speech::speak_result,
+            commands::speech::stop_speaking,
+            commands::speech::get_tts_voices,
+            // Lock commands
+            commands::lock::get_lock_status,
+            commands::lock::unlock_app,
+            commands::lock::lock_app,
+            commands::lock::set_passcode,
+            // Sync commands
+            commands::sync::sync_now,
+            commands::sync::set_sync_credentials,
+            commands::sync::get_sync_credentials_state,
+            // Share commands
+            commands::share::share_result,
+            commands::share::set_share_token,
+            commands::share::has_share_token,
+            // Mail commands
+            commands::mail::send_result_email,
+            commands::mail::set_smtp_password,
+            commands::mail::has_smtp_password,
+            // Notion commands
+            commands::notion::export_history_to_notion,
+            commands::notion::set_notion_token,
+            commands::notion::has_notion_token,
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application");
+
+    app.run(move |_, event| {
+        // Closing the last window must not end a headless run early
+        if let tauri::RunEvent::ExitRequested { code: None, api, .. } = event {
+            if headless {
+                api.prevent_exit();
+            }
+        }
+    });
 }
@@ -0,0 +1,194 @@
+use futures::future;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::db::ab_test::{self, AbTest, AbTestInput, AbTestResultInput};
+use crate::db::{settings, template_usage};
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::image::{generate_thumbnail, process_image_for_api};
+use super::llm::{self, RecognitionOptions, THUMBNAIL_SIZE};
+use super::text_stats;
+
+pub const MIN_VARIANTS: usize = 2;
+pub const MAX_VARIANTS: usize = 4;
+
+const JUDGE_INSTRUCTION: &str = "Several answers were produced for the image above, each from its own task. \
+Pick the answer that fulfils its task most accurately and completely. Reply with JSON only, \
+without any other text, in this format: {\"best\": <answer number>, \"reason\": \"<one sentence>\"}.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbTestVariant {
+    pub prompt: String,
+    /// Template the prompt came from, if any
+    pub template_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbTestRequest {
+    pub config_id: i64,
+    pub image_data: String,
+    pub image_mime_type: String,
+    pub variants: Vec<AbTestVariant>,
+    /// Ask the model which output is best once all variants finished
+    pub judge: Option<bool>,
+}
+
+/// Run every prompt variant on the image, store the results under one test
+/// and optionally let the model judge them
+pub async fn run_ab_test(request: AbTestRequest) -> Result<AbTest, AppError> {
+    if !(MIN_VARIANTS..=MAX_VARIANTS).contains(&request.variants.len()) {
+        return Err(ErrorCode::InvalidVariantCount { min: MIN_VARIANTS, max: MAX_VARIANTS }.into());
+    }
+    let config = llm::load_active_config(request.config_id)?;
+
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let threshold_bytes = (app_settings.compress_threshold as usize) * 1024;
+    let processed = process_image_for_api(&request.image_data, app_settings.auto_compress, threshold_bytes)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })?;
+    let mime_type = if processed.was_compressed { processed.mime_type.as_str() } else { &request.image_mime_type };
+
+    // Variants go out in parallel up to the provider's concurrency limit
+    let results = future::join_all(request.variants.iter().map(|variant| {
+        llm::call_provider(&config, &processed.base64, mime_type, &variant.prompt, None, None)
+    }))
+    .await;
+
+    for (variant, result) in request.variants.iter().zip(&results) {
+        if let Some(template_id) = variant.template_id {
+            if let Err(e) = template_usage::record_usage(template_id, config.id, result.success) {
+                eprintln!("[AbTest] Failed to record template usage: {}", e);
+            }
+        }
+    }
+
+    let inputs = request
+        .variants
+        .iter()
+        .zip(&results)
+        .enumerate()
+        .map(|(index, (variant, result))| AbTestResultInput {
+            variant: index as i32,
+            prompt: variant.prompt.clone(),
+            template_id: variant.template_id,
+            success: result.success,
+            content: result.content.clone(),
+            error: result.error.clone(),
+            tokens_used: result.tokens_used,
+            duration_ms: result.duration_ms.map(|ms| ms as i32),
+            char_count: result
+                .content
+                .as_deref()
+                .map(|content| text_stats::analyze(content).char_count),
+        })
+        .collect();
+    let id = ab_test::save_ab_test(
+        AbTestInput {
+            config_id: config.id,
+            config_name: config.name.clone(),
+            image_thumbnail: generate_thumbnail(&processed.base64, THUMBNAIL_SIZE, THUMBNAIL_SIZE).ok(),
+        },
+        inputs,
+    )
+    .map_err(|e| e.to_string())?;
+
+    if request.judge.unwrap_or(false) {
+        let outputs: Vec<(usize, &str, &str)> = request
+            .variants
+            .iter()
+            .zip(&results)
+            .enumerate()
+            .filter_map(|(index, (variant, result))| {
+                Some((index, variant.prompt.as_str(), result.content.as_deref().filter(|_| result.success)?))
+            })
+            .collect();
+
+        // Nothing to compare unless at least two variants produced an answer
+        if outputs.len() >= MIN_VARIANTS {
+            let judge_options = RecognitionOptions {
+                temperature: Some(0.0),
+                stream: Some(false),
+                ..Default::default()
+            };
+            let verdict = llm::call_provider(
+                &config,
+                &processed.base64,
+                mime_type,
+                &judge_prompt(&outputs),
+                Some(judge_options),
+                None,
+            )
+            .await;
+
+            let judgement = match verdict.content.as_deref().filter(|_| verdict.success) {
+                Some(answer) => parse_judgement(answer, outputs.len())
+                    .map(|(best, reason)| (outputs[best - 1].0 as i32, reason))
+                    .ok_or_else(|| ErrorCode::UnexpectedResponse.localized()),
+                None => Err(verdict.error.unwrap_or_else(|| ErrorCode::UnexpectedResponse.localized())),
+            };
+            let stored = match judgement {
+                Ok((winner, reason)) => ab_test::set_judgement(id, Some(winner), reason.as_deref(), None),
+                Err(error) => ab_test::set_judgement(id, None, None, Some(&error)),
+            };
+            stored.map_err(|e| e.to_string())?;
+        }
+    }
+
+    ab_test::get_ab_test(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| ErrorCode::AbTestNotFound.into())
+}
+
+/// Prompt listing each answer with the task it was given, numbered from 1
+fn judge_prompt(outputs: &[(usize, &str, &str)]) -> String {
+    let answers: Vec<String> = outputs
+        .iter()
+        .enumerate()
+        .map(|(number, (_, prompt, content))| {
+            format!("Answer {}\nTask: {}\nOutput:\n{}", number + 1, prompt.trim(), content.trim())
+        })
+        .collect();
+    format!("{}\n\n{}", answers.join("\n\n---\n\n"), JUDGE_INSTRUCTION)
+}
+
+/// The judged answer number (from 1) and the reason, tolerating text around the
+/// JSON. `None` when the answer names no valid number.
+fn parse_judgement(answer: &str, answers: usize) -> Option<(usize, Option<String>)> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    let data: Value = serde_json::from_str(answer.get(start..=end)?).ok()?;
+
+    let best = match &data["best"] {
+        Value::Number(n) => n.as_u64()? as usize,
+        Value::String(s) => s.trim().parse().ok()?,
+        _ => return None,
+    };
+    let reason = data["reason"].as_str().map(str::trim).filter(|r| !r.is_empty()).map(str::to_string);
+
+    (1..=answers).contains(&best).then_some((best, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_judgement() {
+        assert_eq!(
+            parse_judgement("```json\n{\"best\": 2, \"reason\": \"Keeps the layout\"}\n```", 3),
+            Some((2, Some("Keeps the layout".to_string())))
+        );
+        assert_eq!(parse_judgement("{\"best\": \"1\"}", 2), Some((1, None)));
+        assert_eq!(parse_judgement("{\"best\": 3}", 2), None);
+        assert_eq!(parse_judgement("Answer 1 is best", 2), None);
+    }
+
+    #[test]
+    fn test_judge_prompt_numbers_answers_from_one() {
+        let prompt = judge_prompt(&[(0, "Describe", "A cat"), (2, "List objects", "cat, sofa")]);
+        assert!(prompt.contains("Answer 1\nTask: Describe\nOutput:\nA cat"));
+        assert!(prompt.contains("Answer 2\nTask: List objects"));
+        assert!(prompt.ends_with(JUDGE_INSTRUCTION));
+    }
+}
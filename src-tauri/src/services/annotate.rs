@@ -0,0 +1,188 @@
+//! Boxes and labels drawn onto an image, to check layout-aware OCR output
+//! against the source and to export it as an annotated PNG.
+
+use ab_glyph::{FontArc, FontVec, PxScale};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use once_cell::sync::{Lazy, OnceCell};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use super::image::decode_image;
+use super::layout::BoundingBox;
+
+/// Box colours, cycled so neighbouring boxes can be told apart
+const PALETTE: [[u8; 3]; 6] = [
+    [230, 57, 70],
+    [29, 120, 214],
+    [46, 160, 67],
+    [245, 140, 0],
+    [142, 68, 173],
+    [0, 150, 150],
+];
+
+/// Label font size and tag padding per step of the image size, in pixels
+const FONT_SIZE: u32 = 12;
+const TAG_PADDING: u32 = 2;
+
+/// Images get one step of label size (and stroke pixel) per this many pixels of their shorter side
+const SCALE_STEP: u32 = 250;
+
+/// Font shipped in the bundle's resources, fetched at build time by `npm run fetch:resources`
+const BUNDLED_FONT: &str = "fonts/NotoSansCJKsc-Regular.otf";
+
+/// CJK fonts that come with the system, used when the bundled one is missing.
+/// DejaVu comes last so Latin labels still render on Linux without CJK fonts.
+const SYSTEM_FONTS: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+static RESOURCE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Loaded on first use; `None` when no font could be found
+static LABEL_FONT: Lazy<Option<FontArc>> = Lazy::new(|| {
+    RESOURCE_DIR
+        .get()
+        .map(|dir| dir.join(BUNDLED_FONT))
+        .into_iter()
+        .chain(SYSTEM_FONTS.iter().map(PathBuf::from))
+        .find_map(|path| load_font(&path))
+});
+
+/// Remember where the bundled font is installed; call once at startup
+pub fn init_bundled_font(resource_dir: &Path) {
+    let _ = RESOURCE_DIR.set(resource_dir.to_path_buf());
+}
+
+/// First face of a font or font collection file
+fn load_font(path: &Path) -> Option<FontArc> {
+    let data = std::fs::read(path).ok()?;
+    FontVec::try_from_vec_and_index(data, 0).ok().map(FontArc::new)
+}
+
+/// Draw `boxes` (fractions of the image size) with the label of the same index
+/// in a tag above each. Boxes without a label get only the outline. The result
+/// is PNG so thin strokes and text stay sharp.
+///
+/// Labels use the bundled Noto Sans CJK font, so Chinese and Japanese text
+/// renders as well as Latin; without any usable font only the boxes are drawn.
+pub fn render_annotations(input_base64: &str, boxes: &[BoundingBox], labels: &[String]) -> Result<String, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let mut img = decode_image(&image_data)?.to_rgba8();
+    let scale = (img.width().min(img.height()) / SCALE_STEP).max(1);
+
+    for (i, bbox) in boxes.iter().enumerate() {
+        let [r, g, b] = PALETTE[i % PALETTE.len()];
+        let color = Rgba([r, g, b, 255]);
+        let rect = pixel_rect(&img, bbox);
+        draw_outline(&mut img, rect, scale.max(2), color);
+        let label = labels.get(i).filter(|label| !label.is_empty());
+        if let (Some(label), Some(font)) = (label, LABEL_FONT.as_ref()) {
+            draw_label(&mut img, rect.0, rect.1, label, font, scale, color);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    DynamicImage::ImageRgba8(img)
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(BASE64.encode(&buffer))
+}
+
+/// Left, top, right and bottom in pixels, right and bottom exclusive
+fn pixel_rect(img: &RgbaImage, bbox: &BoundingBox) -> (u32, u32, u32, u32) {
+    let (width, height) = (img.width() as f32, img.height() as f32);
+    let left = (bbox.x.clamp(0.0, 1.0) * width) as u32;
+    let top = (bbox.y.clamp(0.0, 1.0) * height) as u32;
+    let right = ((bbox.x + bbox.width).clamp(0.0, 1.0) * width).ceil() as u32;
+    let bottom = ((bbox.y + bbox.height).clamp(0.0, 1.0) * height).ceil() as u32;
+    (left, top, right.max(left + 1), bottom.max(top + 1))
+}
+
+/// Fill a rectangle, clipped to the image
+fn fill_rect(img: &mut RgbaImage, (left, top, right, bottom): (u32, u32, u32, u32), color: Rgba<u8>) {
+    for y in top..bottom.min(img.height()) {
+        for x in left..right.min(img.width()) {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
+
+fn draw_outline(img: &mut RgbaImage, (left, top, right, bottom): (u32, u32, u32, u32), thickness: u32, color: Rgba<u8>) {
+    fill_rect(img, (left, top, right, top + thickness), color);
+    fill_rect(img, (left, bottom.saturating_sub(thickness), right, bottom), color);
+    fill_rect(img, (left, top, left + thickness, bottom), color);
+    fill_rect(img, (right.saturating_sub(thickness), top, right, bottom), color);
+}
+
+/// White text on a tag in the box colour, above the box or inside it when the
+/// box touches the top edge
+fn draw_label(img: &mut RgbaImage, left: u32, top: u32, label: &str, font: &FontArc, scale: u32, color: Rgba<u8>) {
+    let font_size = PxScale::from((FONT_SIZE * scale) as f32);
+    let padding = TAG_PADDING * scale;
+    let (text_width, _) = text_size(font_size, font, label);
+    let tag_width = text_width + 2 * padding;
+    let tag_height = FONT_SIZE * scale + 2 * padding;
+    let x = left.min(img.width().saturating_sub(tag_width));
+    let y = top.checked_sub(tag_height).unwrap_or(top);
+    fill_rect(img, (x, y, x + tag_width, y + tag_height), color);
+
+    let white = Rgba([255, 255, 255, 255]);
+    draw_text_mut(img, white, (x + padding) as i32, (y + padding) as i32, font_size, font, label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white_png(width: u32, height: u32) -> String {
+        let mut buffer = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255])))
+            .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+            .unwrap();
+        BASE64.encode(&buffer)
+    }
+
+    #[test]
+    fn test_render_annotations() {
+        let boxes = [
+            BoundingBox { x: 0.2, y: 0.5, width: 0.5, height: 0.3 },
+            BoundingBox { x: 0.0, y: 0.0, width: 0.1, height: 0.1 },
+        ];
+        let labels = ["1 标题".to_string()];
+        let rendered = render_annotations(&white_png(100, 100), &boxes, &labels).unwrap();
+        let img = decode_image(&BASE64.decode(rendered).unwrap()).unwrap().to_rgba8();
+
+        let [r, g, b] = PALETTE[0];
+        // Outline of the first box, its inside left untouched
+        assert_eq!(img.get_pixel(20, 60).0, [r, g, b, 255]);
+        assert_eq!(img.get_pixel(69, 60).0, [r, g, b, 255]);
+        assert_eq!(img.get_pixel(40, 65).0, [255, 255, 255, 255]);
+        // Second box in the next colour, without a label
+        let [r, g, b] = PALETTE[1];
+        assert_eq!(img.get_pixel(0, 5).0, [r, g, b, 255]);
+        assert_eq!(img.get_pixel(5, 5).0, [255, 255, 255, 255]);
+
+        if LABEL_FONT.is_some() {
+            // Tag above the box: background at its corner, text drawn in white on it
+            let [r, g, b] = PALETTE[0];
+            let tag_top = 50 - FONT_SIZE - 2 * TAG_PADDING;
+            assert_eq!(img.get_pixel(20, tag_top).0, [r, g, b, 255]);
+            let text_pixels = (tag_top..50)
+                .flat_map(|y| (20..img.width()).map(move |x| (x, y)))
+                .filter(|&(x, y)| img.get_pixel(x, y).0 == [255, 255, 255, 255])
+                .count();
+            assert!(text_pixels > 0);
+        }
+    }
+}
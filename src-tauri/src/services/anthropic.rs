@@ -1,294 +1,185 @@
-use reqwest::Client;
-use serde_json::json;
-use std::time::Instant;
-use super::llm::{AdapterConfig, RecognitionOptions, RecognitionResult};
-
-pub async fn call_anthropic(
-    config: &AdapterConfig,
-    image_base64: &str,
-    image_mime_type: &str,
-    prompt: &str,
-    options: &RecognitionOptions,
-    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
-) -> RecognitionResult {
-    let start_time = Instant::now();
-    
-    if image_base64.is_empty() {
-        return RecognitionResult {
-            success: false,
-            content: None,
-            error: Some("Image data is empty".to_string()),
-            tokens_used: None,
-            duration_ms: None,
-            processed_image: None,
-        };
+use serde_json::{json, Value};
+use super::i18n::ErrorCode;
+use super::llm::{AdapterConfig, RecognitionOptions};
+use super::provider::{
+    default_error_code, sibling_models_url, ImagePart, ParsedResponse, ProviderRequest, StreamDelta, TokenUsage, VisionProvider,
+};
+use super::sse::SseEvent;
+
+/// Thinking budget of reasoning configs that don't set one
+const DEFAULT_THINKING_BUDGET: i32 = 4096;
+/// Smallest budget the API accepts
+const MIN_THINKING_BUDGET: i32 = 1024;
+
+/// Anthropic Messages API
+pub struct AnthropicProvider;
+
+impl AnthropicProvider {
+    fn headers(config: &AdapterConfig) -> Vec<(&'static str, String)> {
+        vec![
+            ("x-api-key", config.api_key.clone()),
+            ("anthropic-version", "2023-06-01".to_string()),
+        ]
     }
+}
 
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(120))
-        .build()
-        .unwrap();
-
-    // Convert mime type for Anthropic format
-    let media_type = match image_mime_type {
-        "image/jpeg" => "image/jpeg",
-        "image/png" => "image/png",
-        "image/gif" => "image/gif",
-        "image/webp" => "image/webp",
-        _ => "image/jpeg",
-    };
-
-    let mut request_body = json!({
-        "model": config.model_name,
-        "max_tokens": options.max_tokens.unwrap_or(config.max_tokens),
-        "messages": [{
-            "role": "user",
-            "content": [
-                {
-                    "type": "image",
-                    "source": {
-                        "type": "base64",
-                        "media_type": media_type,
-                        "data": image_base64
-                    }
-                },
-                {
-                    "type": "text",
-                    "text": prompt
-                }
-            ]
-        }]
-    });
+impl VisionProvider for AnthropicProvider {
+    fn build_request(
+        &self,
+        config: &AdapterConfig,
+        images: &[ImagePart],
+        prompt: &str,
+        options: &RecognitionOptions,
+        stream: bool,
+    ) -> ProviderRequest {
+        // Images go before the text, as the API recommends
+        let user_content = |images: &[ImagePart]| {
+            let mut content: Vec<Value> = images
+                .iter()
+                .map(|image| {
+                    // Convert mime type for Anthropic format
+                    let media_type = match image.mime_type {
+                        "image/jpeg" => "image/jpeg",
+                        "image/png" => "image/png",
+                        "image/gif" => "image/gif",
+                        "image/webp" => "image/webp",
+                        _ => "image/jpeg",
+                    };
+                    json!({
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "media_type": media_type,
+                            "data": image.base64
+                        }
+                    })
+                })
+                .collect();
+            content.push(json!({ "type": "text", "text": prompt }));
+            content
+        };
+        // Few-shot examples go first as earlier exchanges
+        let mut messages = Vec::new();
+        for example in &options.examples {
+            let image = ImagePart { base64: &example.base64, mime_type: &example.mime_type };
+            messages.push(json!({ "role": "user", "content": user_content(&[image]) }));
+            messages.push(json!({ "role": "assistant", "content": example.output }));
+        }
+        messages.push(json!({ "role": "user", "content": user_content(images) }));
+
+        let max_tokens = options.max_tokens.unwrap_or(config.max_tokens);
+        let mut request_body = json!({
+            "model": config.model_name,
+            "max_tokens": max_tokens,
+            "messages": messages,
+            "stream": stream
+        });
+
+        if config.reasoning_model {
+            // The budget counts towards max_tokens, so the answer keeps its own limit on top.
+            // Thinking rules out custom temperature and top_p.
+            let budget = config.thinking_budget_tokens.unwrap_or(DEFAULT_THINKING_BUDGET).max(MIN_THINKING_BUDGET);
+            request_body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+            request_body["max_tokens"] = json!(max_tokens + budget);
+        } else {
+            if let Some(temp) = options.temperature {
+                request_body["temperature"] = json!(temp);
+            }
+            if let Some(top_p) = options.top_p {
+                request_body["top_p"] = json!(top_p);
+            }
+        }
 
-    // Set stream flag
-    let is_streaming = options.stream.unwrap_or(false) && callback.is_some();
-    if let Some(obj) = request_body.as_object_mut() {
-        obj.insert("stream".to_string(), json!(is_streaming));
+        ProviderRequest {
+            headers: Self::headers(config),
+            body: request_body,
+        }
     }
 
-    if let Some(temp) = options.temperature {
-        request_body["temperature"] = json!(temp);
-    }
-    if let Some(top_p) = options.top_p {
-        request_body["top_p"] = json!(top_p);
+    fn auth_headers(&self, config: &AdapterConfig) -> Vec<(&'static str, String)> {
+        Self::headers(config)
     }
 
-    let response = client
-        .post(&config.api_url)
-        .header("Content-Type", "application/json")
-        .header("x-api-key", &config.api_key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&request_body)
-        .send()
-        .await;
-
-    let duration_ms = start_time.elapsed().as_millis() as i64;
-
-    match response {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                if is_streaming {
-                    use futures::StreamExt;
-                    let mut full_content = String::new();
-                    let mut stream = resp.bytes_stream();
-                    let mut buffer = String::new();
-
-                    while let Some(item) = stream.next().await {
-                        if let Ok(chunk) = item {
-                            let text = String::from_utf8_lossy(&chunk);
-                            buffer.push_str(&text);
-
-                            while let Some(idx) = buffer.find('\n') {
-                                let line = buffer[..idx].trim().to_string();
-                                buffer = buffer[idx + 1..].to_string();
-
-                                if line.starts_with("data: ") {
-                                    let data_str = &line[6..];
-                                    if let Ok(data) = serde_json::from_str::<serde_json::Value>(data_str) {
-                                        if data["type"] == "content_block_delta" {
-                                            if let Some(delta) = data["delta"].as_object() {
-                                                if delta["type"] == "text_delta" {
-                                                    if let Some(text) = delta["text"].as_str() {
-                                                        full_content.push_str(text);
-                                                        if let Some(cb) = &callback {
-                                                            cb(text.to_string());
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    
-                    // Process remaining buffer
-                    if !buffer.is_empty() {
-                         let line = buffer.trim();
-                         if line.starts_with("data: ") {
-                             let data_str = &line[6..];
-                             if let Ok(data) = serde_json::from_str::<serde_json::Value>(data_str) {
-                                 if data["type"] == "content_block_delta" {
-                                     if let Some(delta) = data["delta"].as_object() {
-                                         if delta["type"] == "text_delta" {
-                                             if let Some(text) = delta["text"].as_str() {
-                                                 full_content.push_str(text);
-                                                 if let Some(cb) = &callback {
-                                                     cb(text.to_string());
-                                                 }
-                                             }
-                                         }
-                                     }
-                                 }
-                             }
-                         }
-                    }
-
-                    RecognitionResult {
-                        success: true,
-                        content: Some(full_content),
-                        error: None,
-                        tokens_used: None,
-                        duration_ms: Some(duration_ms),
-                        processed_image: None,
-                    }
-                } else {
-                    // Non-streaming handling
-                    match resp.json::<serde_json::Value>().await {
-                        Ok(data) => {
-                            let content = data["content"]
-                                .as_array()
-                                .and_then(|arr| arr.first())
-                                .and_then(|block| block["text"].as_str())
-                                .map(|s| s.to_string())
-                                .unwrap_or_default();
-
-                            let input_tokens = data["usage"]["input_tokens"].as_i64().unwrap_or(0);
-                            let output_tokens = data["usage"]["output_tokens"].as_i64().unwrap_or(0);
-                            let tokens_used = Some((input_tokens + output_tokens) as i32);
-
-                            RecognitionResult {
-                                success: true,
-                                content: Some(content),
-                                error: None,
-                                tokens_used,
-                                duration_ms: Some(duration_ms),
-                                processed_image: None,
-                            }
-                        }
-                        Err(e) => RecognitionResult {
-                            success: false,
-                            content: None,
-                            error: Some(format!("解析响应失败: {}", e)),
-                            tokens_used: None,
-                            duration_ms: Some(duration_ms),
-                            processed_image: None,
-                        },
-                    }
-                }
-            } else {
-                let status = resp.status();
-                let error_text = resp.text().await.unwrap_or_default();
-                let error_message = parse_error_message(status.as_u16(), &error_text);
-                
-                RecognitionResult {
-                    success: false,
-                    content: None,
-                    error: Some(error_message),
-                    tokens_used: None,
-                    duration_ms: Some(duration_ms),
-                    processed_image: None,
-                }
-            }
-        }
-        Err(e) => {
-            let error_message = if e.is_timeout() {
-                "请求超时，请检查网络连接".to_string()
-            } else if e.is_connect() {
-                "连接失败，请检查网络连接或 API 地址".to_string()
-            } else {
-                format!("请求失败: {}", e)
-            };
+    fn models_url(&self, config: &AdapterConfig) -> String {
+        sibling_models_url(&config.api_url, "/messages")
+    }
 
-            RecognitionResult {
-                success: false,
-                content: None,
-                error: Some(error_message),
-                tokens_used: None,
-                duration_ms: Some(duration_ms),
-                processed_image: None,
-            }
+    fn build_test_request(&self, config: &AdapterConfig) -> ProviderRequest {
+        ProviderRequest {
+            headers: Self::headers(config),
+            body: json!({
+                "model": config.model_name,
+                "max_tokens": 10,
+                "messages": [{
+                    "role": "user",
+                    "content": "Hello"
+                }]
+            }),
         }
     }
-}
 
-pub async fn test_connection(config: &AdapterConfig) -> (bool, String) {
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    fn parse_response(&self, data: &Value) -> Result<ParsedResponse, ErrorCode> {
+        // With extended thinking the answer follows one or more thinking blocks
+        let blocks = data["content"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let joined = |kind: &str, field: &str| -> String {
+            blocks
+                .iter()
+                .filter(|block| block["type"] == kind)
+                .filter_map(|block| block[field].as_str())
+                .collect()
+        };
+        let content = joined("text", "text");
+        let reasoning = joined("thinking", "thinking");
+
+        let input_tokens = data["usage"]["input_tokens"].as_i64().unwrap_or(0) as i32;
+        let output_tokens = data["usage"]["output_tokens"].as_i64().unwrap_or(0) as i32;
+
+        Ok(ParsedResponse {
+            content,
+            reasoning: (!reasoning.is_empty()).then_some(reasoning),
+            usage: TokenUsage {
+                input_tokens: Some(input_tokens),
+                output_tokens: Some(output_tokens),
+                total_tokens: None,
+            },
+            // The Messages API reports no token probabilities
+            token_logprobs: None,
+        })
+    }
 
-    let request_body = json!({
-        "model": config.model_name,
-        "max_tokens": 10,
-        "messages": [{
-            "role": "user",
-            "content": "Hello"
-        }]
-    });
+    fn is_valid_test_response(&self, data: &Value) -> bool {
+        data["content"].is_array()
+    }
 
-    let response = client
-        .post(&config.api_url)
-        .header("Content-Type", "application/json")
-        .header("x-api-key", &config.api_key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&request_body)
-        .send()
-        .await;
+    fn parse_stream_event(&self, event: &SseEvent, usage: &mut TokenUsage) -> Option<StreamDelta> {
+        let data = serde_json::from_str::<Value>(&event.data).ok()?;
 
-    match response {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(data) => {
-                        if data["content"].is_array() {
-                            (true, "连接成功".to_string())
-                        } else {
-                            (false, "响应格式异常".to_string())
-                        }
-                    }
-                    Err(_) => (false, "响应解析失败".to_string()),
-                }
-            } else {
-                let status = resp.status().as_u16();
-                let error_text = resp.text().await.unwrap_or_default();
-                (false, parse_error_message(status, &error_text))
+        // message_start carries the prompt usage, message_delta the cumulative output usage
+        let reported = match data["type"].as_str() {
+            Some("content_block_delta") if data["delta"]["type"] == "text_delta" => {
+                return data["delta"]["text"].as_str().map(|text| StreamDelta::Text(text.to_string()));
             }
-        }
-        Err(e) => {
-            if e.is_timeout() {
-                (false, "连接超时".to_string())
-            } else {
-                (false, format!("连接失败: {}", e))
+            Some("content_block_delta") if data["delta"]["type"] == "thinking_delta" => {
+                return data["delta"]["thinking"].as_str().map(|text| StreamDelta::Reasoning(text.to_string()));
             }
+            Some("message_start") => &data["message"]["usage"],
+            Some("message_delta") => &data["usage"],
+            _ => return None,
+        };
+
+        if let Some(tokens) = reported["input_tokens"].as_i64() {
+            usage.input_tokens = Some(tokens as i32);
+        }
+        if let Some(tokens) = reported["output_tokens"].as_i64() {
+            usage.output_tokens = Some(tokens as i32);
         }
+        None
     }
-}
 
-fn parse_error_message(status: u16, body: &str) -> String {
-    match status {
-        401 => "API 密钥无效".to_string(),
-        403 => "API 密钥权限不足".to_string(),
-        404 => "API 地址错误或模型不存在".to_string(),
-        429 => "请求频率过高或配额已用尽".to_string(),
-        _ => {
-            if let Ok(data) = serde_json::from_str::<serde_json::Value>(body) {
-                if let Some(msg) = data["error"]["message"].as_str() {
-                    return msg.to_string();
-                }
-            }
-            format!("服务器错误 ({}): {}", status, body)
+    fn parse_error(&self, status: u16, body: &str) -> ErrorCode {
+        match status {
+            403 => ErrorCode::ApiKeyForbidden,
+            _ => default_error_code(status, body),
         }
     }
 }
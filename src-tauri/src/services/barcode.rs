@@ -0,0 +1,66 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::ImageReader;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// A QR code or barcode read locally from the image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedCode {
+    /// rxing format name, e.g. `QR_CODE` or `EAN_13`
+    pub format: String,
+    pub text: String,
+}
+
+/// Decode all QR codes and barcodes in an image. Models often misread or
+/// invent QR payloads, so these come from a local decoder instead.
+pub fn decode_codes(input_base64: &str) -> Result<Vec<DecodedCode>, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let gray = ImageReader::new(Cursor::new(&image_data))
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to read image: {}", e))?
+        .decode()
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .to_luma8();
+    let (width, height) = gray.dimensions();
+
+    // rxing reports an image without any codes as an error
+    let results = rxing::helpers::detect_multiple_in_luma(gray.into_raw(), width, height).unwrap_or_default();
+    Ok(dedup(
+        results
+            .iter()
+            .map(|result| DecodedCode {
+                format: format!("{:?}", result.getBarcodeFormat()),
+                text: result.getText().to_string(),
+            })
+            .collect(),
+    ))
+}
+
+/// Drop repeated detections of the same code, keeping the first
+fn dedup(codes: Vec<DecodedCode>) -> Vec<DecodedCode> {
+    let mut unique: Vec<DecodedCode> = Vec::with_capacity(codes.len());
+    for code in codes {
+        if !code.text.is_empty() && !unique.contains(&code) {
+            unique.push(code);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup() {
+        let code = |format: &str, text: &str| DecodedCode { format: format.to_string(), text: text.to_string() };
+        let codes = dedup(vec![
+            code("QR_CODE", "https://example.com"),
+            code("QR_CODE", ""),
+            code("QR_CODE", "https://example.com"),
+            code("EAN_13", "4006381333931"),
+        ]);
+        assert_eq!(codes, [code("QR_CODE", "https://example.com"), code("EAN_13", "4006381333931")]);
+    }
+}
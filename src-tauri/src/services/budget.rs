@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use crate::db::model_config::{self, ModelConfig};
+use crate::db::usage_ledger::{self, MonthTokens};
+use super::llm::RecognitionResult;
+
+/// Share of the budget at which the warning level starts
+const WARNING_RATIO: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetLevel {
+    Ok,
+    /// 80% of the budget reached
+    Warning,
+    /// The whole budget spent
+    Exceeded,
+}
+
+impl BudgetLevel {
+    fn for_spend(spent_usd: f64, budget_usd: f64) -> Self {
+        if spent_usd >= budget_usd {
+            Self::Exceeded
+        } else if spent_usd >= budget_usd * WARNING_RATIO {
+            Self::Warning
+        } else {
+            Self::Ok
+        }
+    }
+}
+
+/// Estimated spend of a config against its monthly budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub config_id: i64,
+    pub config_name: String,
+    pub budget_usd: f64,
+    pub spent_usd: f64,
+    pub level: BudgetLevel,
+    /// The recognition that produced this status moved the spend into `level`
+    pub newly_reached: bool,
+}
+
+/// Price tokens at the config's rates; totals without a split are priced as input
+fn estimate_cost(tokens: &MonthTokens, input_price: f64, output_price: f64) -> f64 {
+    ((tokens.input_tokens + tokens.unsplit_tokens) as f64 * input_price + tokens.output_tokens as f64 * output_price)
+        / 1_000_000.0
+}
+
+/// This month's status of a config, `None` when it has no budget or no prices
+pub fn budget_status(config: &ModelConfig) -> Result<Option<BudgetStatus>, String> {
+    let Some(budget_usd) = config.monthly_budget_usd else {
+        return Ok(None);
+    };
+    if config.input_price_per_mtok.is_none() && config.output_price_per_mtok.is_none() {
+        return Ok(None);
+    }
+
+    let tokens = usage_ledger::get_month_tokens(config.id).map_err(|e| e.to_string())?;
+    let spent_usd = estimate_cost(
+        &tokens,
+        config.input_price_per_mtok.unwrap_or_default(),
+        config.output_price_per_mtok.unwrap_or_default(),
+    );
+
+    Ok(Some(BudgetStatus {
+        config_id: config.id,
+        config_name: config.name.clone(),
+        budget_usd,
+        spent_usd,
+        level: BudgetLevel::for_spend(spent_usd, budget_usd),
+        newly_reached: false,
+    }))
+}
+
+/// Status of every config with a budget
+pub fn all_budget_statuses() -> Result<Vec<BudgetStatus>, String> {
+    let mut statuses = Vec::new();
+    for item in model_config::get_all_configs().map_err(|e| e.to_string())? {
+        let Some(config) = model_config::get_config_by_id(item.id).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        statuses.extend(budget_status(&config)?);
+    }
+    Ok(statuses)
+}
+
+/// Budget warning after a recognition already in the usage ledger; `None` while
+/// the spend stays below 80% of the budget
+pub fn check_after(config: &ModelConfig, result: &RecognitionResult) -> Option<BudgetStatus> {
+    let mut status = budget_status(config)
+        .map_err(|e| eprintln!("[Budget] Failed to estimate spend: {}", e))
+        .ok()
+        .flatten()?;
+    if status.level == BudgetLevel::Ok {
+        return None;
+    }
+
+    let this_request = MonthTokens {
+        input_tokens: result.input_tokens.unwrap_or_default() as i64,
+        output_tokens: result.output_tokens.unwrap_or_default() as i64,
+        unsplit_tokens: match (result.input_tokens, result.output_tokens) {
+            (None, None) => result.tokens_used.unwrap_or_default() as i64,
+            _ => 0,
+        },
+    };
+    let before = status.spent_usd
+        - estimate_cost(
+            &this_request,
+            config.input_price_per_mtok.unwrap_or_default(),
+            config.output_price_per_mtok.unwrap_or_default(),
+        );
+    status.newly_reached = BudgetLevel::for_spend(before, status.budget_usd) < status.level;
+    Some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_levels() {
+        assert_eq!(BudgetLevel::for_spend(7.99, 10.0), BudgetLevel::Ok);
+        assert_eq!(BudgetLevel::for_spend(8.0, 10.0), BudgetLevel::Warning);
+        assert_eq!(BudgetLevel::for_spend(10.0, 10.0), BudgetLevel::Exceeded);
+    }
+
+    #[test]
+    fn test_estimate_cost_prices_unsplit_tokens_as_input() {
+        let tokens = MonthTokens {
+            input_tokens: 1_000_000,
+            output_tokens: 500_000,
+            unsplit_tokens: 1_000_000,
+        };
+        assert!((estimate_cost(&tokens, 2.5, 10.0) - 10.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,134 @@
+use serde::Serialize;
+
+/// Formats every provider accepts; images are re-encoded to one of these
+const BASE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg"];
+const COMMON_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp", "image/gif"];
+/// Local models and gateways often can't decode GIF
+const GATEWAY_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp"];
+
+const MB: usize = 1024 * 1024;
+
+/// What a provider family can do with a vision request
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCapabilities {
+    pub provider: String,
+    /// Returns reliable pixel coordinates for detected text/objects
+    pub bounding_boxes: bool,
+    /// Supports a native JSON output mode (e.g. `response_format`)
+    pub json_mode: bool,
+    pub streaming: bool,
+    /// Returns token probabilities (`logprobs`); relays and local servers may
+    /// reject the parameter outright
+    pub logprobs: bool,
+    /// Accepts images in earlier conversation turns, used for few-shot examples
+    pub few_shot_images: bool,
+    /// Image formats accepted as input
+    pub mime_types: &'static [&'static str],
+    /// Largest accepted image after base64 decoding
+    pub max_image_bytes: usize,
+    /// Longest side in pixels; larger images are scaled down by the provider anyway
+    pub max_image_dimension: u32,
+    /// Output token cap per request, `None` when it depends on the deployed model
+    pub max_tokens: Option<i32>,
+}
+
+impl ProviderCapabilities {
+    pub fn accepts(&self, mime_type: &str) -> bool {
+        self.mime_types.contains(&mime_type)
+    }
+}
+
+pub fn get_capabilities(provider: &str) -> ProviderCapabilities {
+    let (bounding_boxes, json_mode, streaming) = match provider {
+        "openai" | "azure" => (false, true, true),
+        // Relays and self-hosted gateways may or may not forward response_format
+        "oneapi" | "custom" => (false, false, true),
+        "anthropic" => (false, false, true),
+        // Detection boxes come straight from the local model
+        "rapidocr" => (true, false, false),
+        _ => (false, false, false),
+    };
+    let logprobs = matches!(provider, "openai" | "azure");
+    // Many self-hosted vision models only look at a single image per request
+    let few_shot_images = matches!(provider, "openai" | "azure" | "anthropic" | "oneapi");
+    let (mime_types, max_image_bytes, max_image_dimension, max_tokens) = match provider {
+        "openai" | "azure" => (COMMON_MIME_TYPES, 20 * MB, 2048, Some(16384)),
+        "anthropic" => (COMMON_MIME_TYPES, 5 * MB, 8000, Some(8192)),
+        "oneapi" | "custom" => (GATEWAY_MIME_TYPES, 10 * MB, 4096, None),
+        // Nothing is uploaded, only decoding time limits the size
+        "rapidocr" => (BASE_MIME_TYPES, 50 * MB, 8000, None),
+        _ => (BASE_MIME_TYPES, 5 * MB, 2048, None),
+    };
+
+    ProviderCapabilities {
+        provider: provider.to_string(),
+        bounding_boxes,
+        json_mode,
+        streaming,
+        logprobs,
+        few_shot_images,
+        mime_types,
+        max_image_bytes,
+        max_image_dimension,
+        max_tokens,
+    }
+}
+
+/// Output token limits of known models, matched by prefix of the model name;
+/// more specific prefixes come first
+const MODEL_MAX_TOKENS: &[(&str, i32)] = &[
+    ("gpt-4o-mini", 16384),
+    ("gpt-4o", 16384),
+    ("gpt-4.1", 32768),
+    ("gpt-4-turbo", 4096),
+    ("gpt-4-vision", 4096),
+    ("o1-mini", 65536),
+    ("o1", 100000),
+    ("o3", 100000),
+    ("o4-mini", 100000),
+    ("claude-3-7-sonnet", 64000),
+    ("claude-3-5", 8192),
+    ("claude-3-", 4096),
+    ("claude-sonnet-4", 64000),
+    ("claude-opus-4", 32000),
+];
+
+/// Output token cap for `model_name`, falling back to the provider's cap for
+/// models the registry doesn't know. Gateways may route any model name, so
+/// their limit is looked up by name as well.
+pub fn max_output_tokens(provider: &str, model_name: &str) -> Option<i32> {
+    let model = model_name.to_ascii_lowercase();
+    // Deployments and gateways often prefix the vendor, e.g. `openai/gpt-4o`
+    let model = model.rsplit('/').next().unwrap_or(&model);
+    MODEL_MAX_TOKENS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|&(_, limit)| limit)
+        .or_else(|| get_capabilities(provider).max_tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_provider_accepts_the_fallback_formats() {
+        for provider in ["openai", "azure", "anthropic", "oneapi", "custom", "rapidocr", "unknown"] {
+            let caps = get_capabilities(provider);
+            assert!(BASE_MIME_TYPES.iter().all(|mime| caps.accepts(mime)), "{}", provider);
+        }
+        assert!(!get_capabilities("custom").accepts("image/gif"));
+    }
+
+    #[test]
+    fn test_max_output_tokens() {
+        assert_eq!(max_output_tokens("openai", "gpt-4o-mini-2024-07-18"), Some(16384));
+        assert_eq!(max_output_tokens("oneapi", "openai/GPT-4-Turbo"), Some(4096));
+        assert_eq!(max_output_tokens("anthropic", "claude-3-7-sonnet-latest"), Some(64000));
+        assert_eq!(max_output_tokens("anthropic", "claude-3-haiku-20240307"), Some(4096));
+        // Unknown models keep the provider's cap, or none at all
+        assert_eq!(max_output_tokens("azure", "my-deployment"), Some(16384));
+        assert_eq!(max_output_tokens("custom", "llava"), None);
+    }
+}
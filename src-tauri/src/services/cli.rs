@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+use crate::db::{model_config, prompt_template, settings, template_usage};
+use super::i18n::ErrorCode;
+use super::image::{load_image_file, process_image_for_api};
+use super::llm::{self, RecognitionOptions, RecognitionResult};
+
+/// Launch flag for a session that keeps everything in memory
+const GUEST_FLAG: &str = "--guest";
+
+/// A headless recognition requested on the command line, e.g.
+/// `orcapp --recognize scan.png --template 3 --output scan.md`.
+/// When another instance is already running the request is forwarded to it,
+/// which has no console of the caller: the result is then only written to
+/// `--output` (and kept in history), nothing is printed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliRequest {
+    pub image: PathBuf,
+    /// Falls back to the default template
+    pub template_id: Option<i64>,
+    /// Falls back to the default config
+    pub config_id: Option<i64>,
+    /// Write the result here instead of printing it to stdout; the only way to
+    /// get the result of a request forwarded to a running instance
+    pub output: Option<PathBuf>,
+    /// Language to answer in, e.g. `ja`; falls back to the output language setting
+    pub language: Option<String>,
+    /// Batch name; the record waits in the review queue of that batch
+    pub batch: Option<String>,
+}
+
+/// Parse the process arguments (program name first). Relative paths are resolved
+/// against `cwd`, which differs from ours when the arguments were forwarded by a
+/// second instance. Returns `None` when no recognition was requested.
+pub fn parse_args(args: &[String], cwd: &Path) -> Result<Option<CliRequest>, String> {
+    let mut image = None;
+    let mut template_id = None;
+    let mut config_id = None;
+    let mut output = None;
+    let mut language = None;
+    let mut batch = None;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if !matches!(flag, "--recognize" | "--template" | "--config" | "--output" | "--language" | "--batch") {
+            continue;
+        }
+
+        let value = inline
+            .or_else(|| iter.next().cloned())
+            .ok_or_else(|| format!("参数 {} 缺少取值", flag))?;
+        match flag {
+            "--recognize" => image = Some(cwd.join(value)),
+            "--output" => output = Some(cwd.join(value)),
+            "--language" => language = Some(value),
+            "--batch" => batch = Some(value),
+            "--template" => template_id = Some(parse_id(flag, &value)?),
+            _ => config_id = Some(parse_id(flag, &value)?),
+        }
+    }
+
+    Ok(image.map(|image| CliRequest {
+        image,
+        template_id,
+        config_id,
+        output,
+        language,
+        batch,
+    }))
+}
+
+/// Whether the arguments ask for guest mode, which persists nothing to disk
+pub fn is_guest(args: &[String]) -> bool {
+    args.iter().skip(1).any(|arg| arg == GUEST_FLAG)
+}
+
+fn parse_id(flag: &str, value: &str) -> Result<i64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("参数 {} 的取值无效: {}", flag, value))
+}
+
+/// Recognize the requested image with the current settings; the result is
+/// recorded in history like any other recognition
+pub async fn run(request: &CliRequest) -> Result<RecognitionResult, String> {
+    let (base64, _) = load_image_file(&request.image)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })?;
+
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let threshold_bytes = (app_settings.compress_threshold.max(1) as usize) * 1024;
+    let processed = process_image_for_api(&base64, app_settings.auto_compress, threshold_bytes)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })?;
+
+    let config_id = match request.config_id {
+        Some(id) => id,
+        None => model_config::get_default_config()
+            .map_err(|e| e.to_string())?
+            .ok_or(ErrorCode::ConfigNotFound)?
+            .id,
+    };
+
+    let template = match request.template_id {
+        Some(id) => prompt_template::get_template_by_id(id)
+            .map_err(|e| e.to_string())?
+            .ok_or(ErrorCode::TemplateNotFound { id })?,
+        None => prompt_template::get_default_template()
+            .map_err(|e| e.to_string())?
+            .ok_or(ErrorCode::TemplateNotFound { id: 0 })?,
+    };
+    let _ = prompt_template::increment_use_count(template.id);
+
+    let options = RecognitionOptions {
+        auto_rotate: Some(app_settings.auto_rotate),
+        auto_crop: Some(app_settings.auto_crop),
+        estimate_confidence: Some(app_settings.estimate_confidence),
+        decode_codes: Some(app_settings.decode_codes),
+        output_language: request.language.clone().or(Some(app_settings.output_language)),
+        source_path: std::path::absolute(&request.image).ok().map(|path| path.display().to_string()),
+        examples: llm::load_examples(template.id),
+        template_id: Some(template.id),
+        batch_id: request.batch.clone(),
+        ..Default::default()
+    };
+
+    let result = llm::recognize(
+        config_id,
+        &processed.base64,
+        &processed.mime_type,
+        &template.content,
+        Some(options),
+        None,
+    )
+    .await;
+    if let Err(e) = template_usage::record_usage(template.id, config_id, result.success) {
+        eprintln!("Failed to record template usage: {}", e);
+    }
+    Ok(result)
+}
+
+/// Release builds are GUI-subsystem apps on Windows and start without a console.
+/// Attach to the one of the terminal that launched us, if any, so a headless
+/// run can print its result and errors there.
+pub fn attach_parent_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Run a CLI request and deliver the result to the output file or stdout.
+/// Errors go to stderr; returns the process exit code.
+pub async fn run_and_report(request: CliRequest) -> i32 {
+    let result = match run(&request).await {
+        Ok(result) if result.success => result,
+        Ok(result) => {
+            eprintln!("{}", result.error.unwrap_or_default());
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    let content = result.content.unwrap_or_default();
+    match request.output {
+        Some(path) => match std::fs::write(&path, &content) {
+            Ok(()) => {
+                println!("{}", path.display());
+                0
+            }
+            Err(e) => {
+                eprintln!("写入输出文件失败: {}", e);
+                1
+            }
+        },
+        None => {
+            println!("{}", content);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        std::iter::once("orcapp").chain(values.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cwd = Path::new("/work");
+        assert_eq!(parse_args(&args(&[]), cwd).unwrap(), None);
+        assert_eq!(parse_args(&args(&["--template", "3"]), cwd).unwrap(), None);
+
+        let request = parse_args(&args(&["--recognize", "scan.png", "--template=3", "--output", "/tmp/out.md"]), cwd)
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.image, Path::new("/work/scan.png"));
+        assert_eq!(request.template_id, Some(3));
+        assert_eq!(request.config_id, None);
+        assert_eq!(request.output.as_deref(), Some(Path::new("/tmp/out.md")));
+        assert_eq!(request.language, None);
+
+        let request = parse_args(&args(&["--recognize", "scan.png", "--language", "ja"]), cwd).unwrap().unwrap();
+        assert_eq!(request.language.as_deref(), Some("ja"));
+        assert_eq!(request.batch, None);
+
+        let request = parse_args(&args(&["--recognize", "scan.png", "--batch=invoices-03"]), cwd).unwrap().unwrap();
+        assert_eq!(request.batch.as_deref(), Some("invoices-03"));
+    }
+
+    #[test]
+    fn test_is_guest() {
+        assert!(is_guest(&args(&["--guest"])));
+        assert!(is_guest(&args(&["--recognize", "scan.png", "--guest"])));
+        assert!(!is_guest(&args(&[])));
+        assert!(!is_guest(&["--guest".to_string()]));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        let cwd = Path::new("/work");
+        assert!(parse_args(&args(&["--recognize"]), cwd).is_err());
+        assert!(parse_args(&args(&["--recognize", "a.png", "--config", "x"]), cwd).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::db::model_config::ModelConfig;
+use super::llm::{self, RecognitionOptions};
+use super::provider::TokenUsage;
+
+/// How a confidence score was obtained
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfidenceMethod {
+    /// Mean token probability reported with the answer
+    Logprobs,
+    /// A second request asking the model to rate its own transcription
+    SelfRating,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SectionConfidence {
+    /// Heading or first words of the section
+    pub section: String,
+    pub score: f64,
+}
+
+/// Confidence of a transcription, from 0 to 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Confidence {
+    pub score: f64,
+    pub method: ConfidenceMethod,
+    /// Per-section scores of a self-rating; empty for logprobs
+    pub sections: Vec<SectionConfidence>,
+    /// Score is below the `lowConfidenceThreshold` setting
+    pub needs_review: bool,
+}
+
+const RATING_PROMPT: &str = "Below is a transcription of this image. Compare it with the image and rate how \
+confident you are that each section was transcribed correctly, from 0 (certainly wrong) to 1 (certainly right). \
+Answer only with JSON of the form {\"overall\": 0.9, \"sections\": [{\"section\": \"first words of the section\", \
+\"confidence\": 0.9}]}.\n\nTranscription:\n";
+
+/// Geometric mean of the token probabilities, so a few unsure tokens pull it down
+pub fn from_logprobs(logprobs: &[f64]) -> Option<Confidence> {
+    if logprobs.is_empty() {
+        return None;
+    }
+    let mean = logprobs.iter().sum::<f64>() / logprobs.len() as f64;
+    Some(Confidence {
+        score: mean.exp().clamp(0.0, 1.0),
+        method: ConfidenceMethod::Logprobs,
+        sections: Vec::new(),
+        needs_review: false,
+    })
+}
+
+/// Ask the model to rate the transcription `content` of the image. The tokens
+/// of the request are returned whether or not it produced a rating.
+pub async fn rate(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    content: &str,
+) -> (Result<Confidence, String>, TokenUsage) {
+    let options = RecognitionOptions {
+        temperature: Some(0.0),
+        stream: Some(false),
+        json_mode: Some(true),
+        ..Default::default()
+    };
+    let prompt = format!("{}{}", RATING_PROMPT, content);
+    let result = llm::call_provider(config, image_base64, image_mime_type, &prompt, Some(options), None).await;
+    let usage = TokenUsage {
+        input_tokens: result.input_tokens,
+        output_tokens: result.output_tokens,
+        total_tokens: result.tokens_used,
+    };
+    if !result.success {
+        return (Err(result.error.unwrap_or_default()), usage);
+    }
+    let answer = result.content.unwrap_or_default();
+    (parse_rating(&answer).ok_or_else(|| format!("Unexpected confidence rating: {}", answer)), usage)
+}
+
+/// Scores are clamped to 0..=1; without `overall` the sections are averaged
+fn parse_rating(answer: &str) -> Option<Confidence> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    let data: Value = serde_json::from_str(answer.get(start..=end)?).ok()?;
+
+    let sections: Vec<SectionConfidence> = data["sections"]
+        .as_array()
+        .map(|sections| {
+            sections
+                .iter()
+                .filter_map(|s| {
+                    Some(SectionConfidence {
+                        section: s["section"].as_str().unwrap_or_default().to_string(),
+                        score: s["confidence"].as_f64()?.clamp(0.0, 1.0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let score = match data["overall"].as_f64() {
+        Some(overall) => overall.clamp(0.0, 1.0),
+        None if !sections.is_empty() => sections.iter().map(|s| s.score).sum::<f64>() / sections.len() as f64,
+        None => return None,
+    };
+    Some(Confidence { score, method: ConfidenceMethod::SelfRating, sections, needs_review: false })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_logprobs() {
+        assert_eq!(from_logprobs(&[]), None);
+        let confidence = from_logprobs(&[0.0, 0.0]).unwrap();
+        assert_eq!(confidence.score, 1.0);
+        assert_eq!(confidence.method, ConfidenceMethod::Logprobs);
+        let score = from_logprobs(&[0.0, -2.0]).unwrap().score;
+        assert!((score - (-1.0f64).exp()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_rating() {
+        let answer = "```json\n{\"overall\": 0.8, \"sections\": [{\"section\": \"Total\", \"confidence\": 1.4}]}\n```";
+        let confidence = parse_rating(answer).unwrap();
+        assert_eq!(confidence.score, 0.8);
+        assert_eq!(confidence.sections, vec![SectionConfidence { section: "Total".to_string(), score: 1.0 }]);
+
+        let averaged = parse_rating(r#"{"sections": [{"section": "a", "confidence": 0.5}, {"section": "b", "confidence": 1}]}"#);
+        assert_eq!(averaged.unwrap().score, 0.75);
+        assert_eq!(parse_rating("I am quite sure"), None);
+        assert_eq!(parse_rating("{}"), None);
+    }
+}
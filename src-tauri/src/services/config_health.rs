@@ -0,0 +1,145 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use crate::db::model_config::{self, ModelConfig};
+use crate::db::settings;
+use super::i18n::ErrorCode;
+use super::llm::{self, AdapterConfig};
+use super::provider_cache;
+
+/// Test requests slower than this mark the config as degraded
+const DEGRADED_LATENCY: Duration = Duration::from_secs(5);
+/// Delay before the first round, so checks don't compete with startup
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+/// How often a disabled checker looks at the setting again
+const DISABLED_POLL: Duration = Duration::from_secs(60);
+
+/// Wakes the check loop when its interval setting changes
+static INTERVAL_CHANGED: Lazy<Notify> = Lazy::new(Notify::new);
+/// Interval the loop is waiting out; saving the settings form resends it unchanged
+static INTERVAL_MINUTES: AtomicI32 = AtomicI32::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Down,
+}
+
+/// Outcome of the last health check of a config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHealth {
+    pub config_id: i64,
+    pub status: HealthStatus,
+    pub latency_ms: i64,
+    pub error: Option<String>,
+    pub error_code: Option<ErrorCode>,
+    pub checked_at: String,
+}
+
+static HEALTH: Lazy<Mutex<HashMap<i64, ConfigHealth>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cached health of the active configs checked so far, including recent
+/// checks from before a restart
+pub fn get_cached_health() -> Vec<ConfigHealth> {
+    let unchecked: Vec<i64> = match model_config::get_active_configs() {
+        Ok(active) => {
+            let checked = HEALTH.lock();
+            active.into_iter().map(|c| c.id).filter(|id| !checked.contains_key(id)).collect()
+        }
+        Err(_) => Vec::new(),
+    };
+    let restored: Vec<ConfigHealth> = unchecked.into_iter().filter_map(provider_cache::load_health).collect();
+
+    let mut cache = HEALTH.lock();
+    for health in restored {
+        cache.entry(health.config_id).or_insert(health);
+    }
+    let mut health: Vec<ConfigHealth> = cache.values().cloned().collect();
+    health.sort_by_key(|h| h.config_id);
+    health
+}
+
+/// Send a test request for one config and cache the outcome
+pub async fn check_config(config: &ModelConfig) -> ConfigHealth {
+    let started = Instant::now();
+    let outcome = llm::test_adapter(&config.provider, &AdapterConfig::from(config)).await;
+    let latency = started.elapsed();
+
+    let (status, error_code) = match outcome {
+        Ok(()) if latency > DEGRADED_LATENCY => (HealthStatus::Degraded, None),
+        Ok(()) => (HealthStatus::Healthy, None),
+        Err(code) => (HealthStatus::Down, Some(code)),
+    };
+    let health = ConfigHealth {
+        config_id: config.id,
+        status,
+        latency_ms: latency.as_millis() as i64,
+        error: error_code.as_ref().map(ErrorCode::localized),
+        error_code,
+        checked_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+
+    HEALTH.lock().insert(config.id, health.clone());
+    provider_cache::store_health(&health);
+    health
+}
+
+/// Check every active config one after another, forgetting configs that were
+/// disabled or deleted since the last round
+pub async fn check_active_configs() -> Result<Vec<ConfigHealth>, String> {
+    let active = model_config::get_active_configs().map_err(|e| e.to_string())?;
+    HEALTH.lock().retain(|id, _| active.iter().any(|c| c.id == *id));
+
+    let mut results = Vec::with_capacity(active.len());
+    for item in active {
+        if let Ok(Some(config)) = model_config::get_config_by_id(item.id) {
+            results.push(check_config(&config).await);
+        }
+    }
+    Ok(results)
+}
+
+/// Background loop re-checking the active configs at the interval set in settings
+pub async fn run_health_checks() {
+    tokio::time::sleep(STARTUP_DELAY).await;
+    loop {
+        let minutes = settings::get_all_settings().map(|s| s.health_check_minutes).unwrap_or(0);
+        INTERVAL_MINUTES.store(minutes, Ordering::Relaxed);
+        if minutes <= 0 {
+            wait(DISABLED_POLL).await;
+            continue;
+        }
+
+        if let Err(e) = check_active_configs().await {
+            eprintln!("[ConfigHealth] Health check failed: {}", e);
+        }
+        wait(Duration::from_secs(minutes as u64 * 60)).await;
+    }
+}
+
+/// Sleep for `duration`, or until the interval setting changes
+async fn wait(duration: Duration) {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {}
+        _ = INTERVAL_CHANGED.notified() => {}
+    }
+}
+
+/// Apply a new check interval right away instead of after the current one.
+/// Only a different value wakes the loop, which then checks every config.
+pub fn settings_changed(keys: &[String]) {
+    if !keys.iter().any(|key| key == "healthCheckMinutes") {
+        return;
+    }
+    let minutes = settings::get_all_settings().map(|s| s.health_check_minutes).unwrap_or(0);
+    if minutes != INTERVAL_MINUTES.load(Ordering::Relaxed) {
+        INTERVAL_CHANGED.notify_one();
+    }
+}
@@ -0,0 +1,357 @@
+use futures::{future, stream, StreamExt, TryStreamExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::db::experiment::{self, Experiment, ExperimentRun, ExperimentRunInput};
+use crate::db::prompt_template::get_template_by_id;
+use crate::db::settings;
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::image::{load_image_file, process_image_for_api};
+use super::llm;
+use super::text_stats;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentProgress {
+    pub experiment_id: i64,
+    pub completed: usize,
+    pub total: usize,
+    pub image_path: String,
+    pub variant: String,
+    /// Set while the run waits for a free provider slot; `None` once it finished
+    pub queue_position: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantSummary {
+    pub variant: String,
+    pub template_id: i64,
+    pub template_name: Option<String>,
+    pub runs: usize,
+    pub successes: usize,
+    pub avg_chars: Option<f64>,
+    pub avg_duration_ms: Option<f64>,
+    pub total_tokens: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub avg_rating: Option<f64>,
+    pub rated_runs: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentReport {
+    pub experiment: Experiment,
+    pub variants: Vec<VariantSummary>,
+    /// Images where both variants were rated, counted by which one scored higher
+    pub wins_a: usize,
+    pub wins_b: usize,
+    pub ties: usize,
+    pub runs: Vec<ExperimentRun>,
+}
+
+/// Images loaded and waiting for their runs at once; the provider slots decide
+/// how many runs actually go out in parallel
+const MAX_IMAGES_IN_FLIGHT: usize = 8;
+
+/// Experiments running in this process, with whether a pause was requested
+static RUNNERS: Lazy<Mutex<HashMap<i64, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Registers a running experiment for as long as it lives
+struct ActiveRun(i64);
+
+impl ActiveRun {
+    fn start(experiment_id: i64) -> Result<Self, ErrorCode> {
+        let mut runners = RUNNERS.lock();
+        if runners.contains_key(&experiment_id) {
+            return Err(ErrorCode::ExperimentRunning);
+        }
+        runners.insert(experiment_id, false);
+        Ok(Self(experiment_id))
+    }
+
+    fn pause_requested(&self) -> bool {
+        RUNNERS.lock().get(&self.0).copied().unwrap_or(false)
+    }
+}
+
+impl Drop for ActiveRun {
+    fn drop(&mut self) {
+        RUNNERS.lock().remove(&self.0);
+    }
+}
+
+/// Run both template variants over every image of the experiment. Successful
+/// runs are also saved to history, in the review batch `experiment-<name>-<variant>`.
+pub async fn run_experiment(
+    experiment_id: i64,
+    on_progress: impl Fn(ExperimentProgress) + Send + Sync,
+) -> Result<ExperimentReport, AppError> {
+    run(experiment_id, false, on_progress).await
+}
+
+/// Continue a paused, failed or interrupted experiment: runs that failed are retried
+/// and runs that never went out are sent, keeping the successful ones
+pub async fn resume_experiment(
+    experiment_id: i64,
+    on_progress: impl Fn(ExperimentProgress) + Send + Sync,
+) -> Result<ExperimentReport, AppError> {
+    run(experiment_id, true, on_progress).await
+}
+
+/// Stop a running experiment from sending further runs. Runs already sent or
+/// waiting for a provider slot finish and are kept; the experiment is left
+/// `paused` until resumed. Returns false when the experiment isn't running.
+pub fn pause_experiment(experiment_id: i64) -> Result<bool, AppError> {
+    match RUNNERS.lock().get_mut(&experiment_id) {
+        Some(pause) => *pause = true,
+        None => return Ok(false),
+    }
+    experiment::set_experiment_status(experiment_id, "paused").map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+async fn run(
+    experiment_id: i64,
+    resume: bool,
+    on_progress: impl Fn(ExperimentProgress) + Send + Sync,
+) -> Result<ExperimentReport, AppError> {
+    let active = ActiveRun::start(experiment_id)?;
+    let outcome = execute(experiment_id, resume, &active, on_progress).await;
+    // Left `failed` rather than `running`; it can be resumed like a paused one
+    if outcome.is_err() {
+        if let Err(e) = experiment::set_experiment_status(experiment_id, "failed") {
+            eprintln!("[Experiment] Failed to set status: {}", e);
+        }
+    }
+    outcome
+}
+
+async fn execute(
+    experiment_id: i64,
+    resume: bool,
+    active: &ActiveRun,
+    on_progress: impl Fn(ExperimentProgress) + Send + Sync,
+) -> Result<ExperimentReport, AppError> {
+    let experiment = experiment::get_experiment_by_id(experiment_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::ExperimentNotFound)?;
+    let config = llm::load_active_config(experiment.config_id)?;
+
+    // Each variant's successful runs land in the review queue of its own batch
+    let mut variants = Vec::new();
+    for (variant, template_id) in [("a", experiment.template_a_id), ("b", experiment.template_b_id)] {
+        let template = get_template_by_id(template_id)
+            .map_err(|e| e.to_string())?
+            .ok_or(ErrorCode::TemplateNotFound { id: template_id })?;
+        let batch_id = format!("experiment-{}-{}", experiment.name, variant);
+        variants.push((variant, template.content, batch_id));
+    }
+
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let threshold_bytes = (app_settings.compress_threshold as usize) * 1024;
+
+    if resume {
+        experiment::clear_failed_runs(experiment_id).map_err(|e| e.to_string())?;
+    } else {
+        experiment::clear_experiment_runs(experiment_id).map_err(|e| e.to_string())?;
+    }
+    let done: HashSet<(String, String)> = experiment::get_experiment_runs(experiment_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|run| (run.image_path, run.variant))
+        .collect();
+    experiment::set_experiment_status(experiment_id, "running").map_err(|e| e.to_string())?;
+
+    let total = experiment.image_paths.len() * variants.len();
+    let completed = AtomicUsize::new(done.len());
+    let on_progress = &on_progress;
+
+    // Runs go out in parallel up to the provider's concurrency limit
+    let run_image = |image_path: &String| {
+        let (config, variants, completed, done) = (&config, &variants, &completed, &done);
+        let image_path = image_path.clone();
+        async move {
+            let pending: Vec<_> = variants
+                .iter()
+                .filter(|(variant, _, _)| !done.contains(&(image_path.clone(), variant.to_string())))
+                .collect();
+            if pending.is_empty() || active.pause_requested() {
+                return Ok(());
+            }
+
+            let image = load_image_file(Path::new(&image_path)).and_then(|(base64, mime_type)| {
+                let processed = process_image_for_api(&base64, app_settings.auto_compress, threshold_bytes)?;
+                let mime_type = if processed.was_compressed { processed.mime_type } else { mime_type };
+                Ok((processed.base64, mime_type))
+            });
+
+            let runs = pending.into_iter().map(|(variant, prompt, batch_id)| {
+                let (image, image_path) = (&image, &image_path);
+                async move {
+                    // Left for resuming; runs already sent finish normally
+                    if active.pause_requested() {
+                        return Ok(());
+                    }
+                    let on_queued = |position: usize| {
+                        on_progress(ExperimentProgress {
+                            experiment_id,
+                            completed: completed.load(Ordering::SeqCst),
+                            total,
+                            image_path: image_path.clone(),
+                            variant: variant.to_string(),
+                            queue_position: Some(position),
+                        })
+                    };
+                    let result = match image {
+                        Ok((base64, mime_type)) => {
+                            let options = llm::RecognitionOptions {
+                                batch_id: Some(batch_id.clone()),
+                                source_path: Some(image_path.clone()),
+                                ..Default::default()
+                            };
+                            let result = llm::call_provider_queued(
+                                config,
+                                base64,
+                                mime_type,
+                                prompt,
+                                Some(options.clone()),
+                                None,
+                                &on_queued,
+                            )
+                            .await;
+                            if result.success {
+                                llm::save_result(config, base64, mime_type, prompt, &options, &result);
+                            }
+                            result
+                        }
+                        Err(e) => llm::RecognitionResult::failure(e.clone(), None),
+                    };
+
+                    let char_count = result
+                        .content
+                        .as_deref()
+                        .map(|content| text_stats::analyze(content).char_count);
+
+                    experiment::create_experiment_run(ExperimentRunInput {
+                        experiment_id,
+                        image_path: image_path.clone(),
+                        variant: variant.to_string(),
+                        success: result.success,
+                        content: result.content,
+                        error: result.error,
+                        tokens_used: result.tokens_used,
+                        input_tokens: result.input_tokens,
+                        output_tokens: result.output_tokens,
+                        duration_ms: result.duration_ms.map(|ms| ms as i32),
+                        char_count,
+                    })
+                    .map_err(|e| e.to_string())?;
+
+                    on_progress(ExperimentProgress {
+                        experiment_id,
+                        completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                        total,
+                        image_path: image_path.clone(),
+                        variant: variant.to_string(),
+                        queue_position: None,
+                    });
+                    Ok::<_, String>(())
+                }
+            });
+            future::try_join_all(runs).await.map(|_| ())
+        }
+    };
+
+    stream::iter(&experiment.image_paths)
+        .map(run_image)
+        .buffer_unordered(MAX_IMAGES_IN_FLIGHT)
+        .try_collect::<Vec<()>>()
+        .await?;
+
+    // Paused when runs were left for resuming
+    let runs = experiment::get_experiment_runs(experiment_id).map_err(|e| e.to_string())?;
+    let status = if runs.len() < total { "paused" } else { "completed" };
+    experiment::set_experiment_status(experiment_id, status).map_err(|e| e.to_string())?;
+    build_report(experiment_id)
+}
+
+/// Aggregate the stored runs of an experiment into a side-by-side comparison
+pub fn build_report(experiment_id: i64) -> Result<ExperimentReport, AppError> {
+    let experiment = experiment::get_experiment_by_id(experiment_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::ExperimentNotFound)?;
+    let runs = experiment::get_experiment_runs(experiment_id).map_err(|e| e.to_string())?;
+
+    let variants = [("a", experiment.template_a_id), ("b", experiment.template_b_id)]
+        .iter()
+        .map(|(variant, template_id)| {
+            let variant_runs: Vec<&ExperimentRun> = runs.iter().filter(|r| r.variant == *variant).collect();
+            summarize(variant, *template_id, &variant_runs)
+        })
+        .collect();
+
+    // Pair up ratings per image
+    let mut ratings: HashMap<&str, (Option<i32>, Option<i32>)> = HashMap::new();
+    for run in &runs {
+        let entry = ratings.entry(run.image_path.as_str()).or_default();
+        match run.variant.as_str() {
+            "a" => entry.0 = run.rating,
+            "b" => entry.1 = run.rating,
+            _ => {}
+        }
+    }
+
+    let (mut wins_a, mut wins_b, mut ties) = (0, 0, 0);
+    for (a, b) in ratings.values() {
+        if let (Some(a), Some(b)) = (a, b) {
+            match a.cmp(b) {
+                std::cmp::Ordering::Greater => wins_a += 1,
+                std::cmp::Ordering::Less => wins_b += 1,
+                std::cmp::Ordering::Equal => ties += 1,
+            }
+        }
+    }
+
+    Ok(ExperimentReport {
+        experiment,
+        variants,
+        wins_a,
+        wins_b,
+        ties,
+        runs,
+    })
+}
+
+fn summarize(variant: &str, template_id: i64, runs: &[&ExperimentRun]) -> VariantSummary {
+    let successful: Vec<&&ExperimentRun> = runs.iter().filter(|r| r.success).collect();
+    let rated: Vec<i32> = runs.iter().filter_map(|r| r.rating).collect();
+
+    let average = |values: Vec<i32>| {
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64)
+        }
+    };
+
+    VariantSummary {
+        variant: variant.to_string(),
+        template_id,
+        template_name: get_template_by_id(template_id).ok().flatten().map(|t| t.name),
+        runs: runs.len(),
+        successes: successful.len(),
+        avg_chars: average(successful.iter().filter_map(|r| r.char_count).collect()),
+        avg_duration_ms: average(successful.iter().filter_map(|r| r.duration_ms).collect()),
+        total_tokens: runs.iter().filter_map(|r| r.tokens_used).map(|t| t as i64).sum(),
+        input_tokens: runs.iter().filter_map(|r| r.input_tokens).map(|t| t as i64).sum(),
+        output_tokens: runs.iter().filter_map(|r| r.output_tokens).map(|t| t as i64).sum(),
+        avg_rating: average(rated.clone()),
+        rated_runs: rated.len(),
+    }
+}
@@ -0,0 +1,377 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Built-in structured extraction schemas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionKind {
+    /// Receipts and invoices: merchant, date, line items, totals and tax
+    Receipt,
+    /// Business cards: name, company, phone numbers and emails
+    BusinessCard,
+}
+
+impl ExtractionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Receipt => "receipt",
+            Self::BusinessCard => "business_card",
+        }
+    }
+}
+
+/// Appended to the prompt when a receipt extraction is requested
+const RECEIPT_INSTRUCTION: &str = "Extract the receipt or invoice in the image and return JSON only, \
+without any other text, in this format: {\"merchant\": \"...\", \"date\": \"YYYY-MM-DD\", \
+\"currency\": \"ISO 4217 code\", \"lineItems\": [{\"description\": \"...\", \"quantity\": 1, \
+\"unitPrice\": 0.00, \"amount\": 0.00}], \"subtotal\": 0.00, \"tax\": 0.00, \"total\": 0.00}. \
+Amounts are plain numbers without currency symbols. Use null for anything not printed on the receipt.";
+
+/// Appended to the prompt when a business card extraction is requested
+const BUSINESS_CARD_INSTRUCTION: &str = "Extract the contact details on the business card in the image and \
+return JSON only, without any other text, in this format: {\"name\": \"...\", \"title\": \"...\", \
+\"company\": \"...\", \"phones\": [\"...\"], \"emails\": [\"...\"], \"website\": \"...\", \"address\": \"...\"}. \
+Keep phone numbers as printed, including the country code. Use null or an empty list for anything \
+not printed on the card.";
+
+/// Rounding differences tolerated when checking the sums, per summed amount
+const SUM_TOLERANCE: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptLineItem {
+    pub description: String,
+    pub quantity: Option<f64>,
+    pub unit_price: Option<f64>,
+    pub amount: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    pub merchant: Option<String>,
+    /// ISO date when the printed date could be read, otherwise as printed
+    pub date: Option<String>,
+    pub currency: Option<String>,
+    pub line_items: Vec<ReceiptLineItem>,
+    pub subtotal: Option<f64>,
+    pub tax: Option<f64>,
+    pub total: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessCard {
+    pub name: Option<String>,
+    /// Job title
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub phones: Vec<String>,
+    pub emails: Vec<String>,
+    pub website: Option<String>,
+    pub address: Option<String>,
+}
+
+/// Typed result of a structured extraction; the field matching `kind` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extraction {
+    pub kind: ExtractionKind,
+    #[serde(default)]
+    pub receipt: Option<Receipt>,
+    #[serde(default)]
+    pub business_card: Option<BusinessCard>,
+    /// Validation problems, the fields are kept as returned: `missing_total`,
+    /// `invalid_date`, `line_items_mismatch` or `total_mismatch` for receipts,
+    /// `missing_name` or `invalid_email` for business cards
+    pub issues: Vec<String>,
+}
+
+impl Extraction {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+pub fn extraction_prompt(prompt: &str, kind: ExtractionKind) -> String {
+    let instruction = match kind {
+        ExtractionKind::Receipt => RECEIPT_INSTRUCTION,
+        ExtractionKind::BusinessCard => BUSINESS_CARD_INSTRUCTION,
+    };
+    format!("{}\n\n{}", prompt.trim_end(), instruction)
+}
+
+/// Parse and validate the model's JSON, tolerating markdown code fences,
+/// snake_case keys and amounts given as strings. Returns None when the
+/// response holds no JSON object.
+pub fn parse_extraction(content: &str, kind: ExtractionKind) -> Option<Extraction> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    let data: Value = serde_json::from_str(content.get(start..=end)?).ok()?;
+    if !data.is_object() {
+        return None;
+    }
+
+    let extraction = match kind {
+        ExtractionKind::Receipt => {
+            let receipt = parse_receipt(&data);
+            Extraction { kind, issues: validate_receipt(&receipt), receipt: Some(receipt), business_card: None }
+        }
+        ExtractionKind::BusinessCard => {
+            let card = parse_business_card(&data);
+            Extraction { kind, issues: validate_business_card(&card), receipt: None, business_card: Some(card) }
+        }
+    };
+    Some(extraction)
+}
+
+/// Field by its camelCase or snake_case name
+fn field<'a>(data: &'a Value, camel: &str, snake: &str) -> &'a Value {
+    match &data[camel] {
+        Value::Null => &data[snake],
+        value => value,
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Number from a JSON number or a string like "1,234.50" or "12,50 €"
+fn amount(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => parse_amount(s),
+        _ => None,
+    }
+}
+
+fn parse_amount(s: &str) -> Option<f64> {
+    let cleaned: String = s.chars().filter(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-')).collect();
+    // A lone comma followed by one or two digits is a decimal comma
+    let normalized = match (cleaned.rfind(','), cleaned.contains('.')) {
+        (Some(i), false) if cleaned.len() - i - 1 <= 2 => {
+            format!("{}.{}", cleaned[..i].replace(',', ""), &cleaned[i + 1..])
+        }
+        _ => cleaned.replace(',', ""),
+    };
+    normalized.parse().ok()
+}
+
+/// ISO date from the common printed forms, None when it can't be read
+fn normalize_date(date: &str) -> Option<String> {
+    const FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%Y年%m月%d日"];
+    let date = date.get(..date.find(['T', ' ']).unwrap_or(date.len())).unwrap_or(date);
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+fn parse_receipt(data: &Value) -> Receipt {
+    let line_items = field(data, "lineItems", "line_items")
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| ReceiptLineItem {
+                    description: text(&item["description"]).unwrap_or_default(),
+                    quantity: amount(&item["quantity"]),
+                    unit_price: amount(field(item, "unitPrice", "unit_price")),
+                    amount: amount(&item["amount"]),
+                })
+                .filter(|item| !item.description.is_empty() || item.amount.is_some())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let date = text(&data["date"]).map(|date| normalize_date(&date).unwrap_or(date));
+    Receipt {
+        merchant: text(&data["merchant"]),
+        date,
+        currency: text(&data["currency"]).map(|c| c.to_uppercase()),
+        line_items,
+        subtotal: amount(&data["subtotal"]),
+        tax: amount(&data["tax"]),
+        total: amount(&data["total"]),
+    }
+}
+
+/// Strings from a list, or from a single string for models that don't return one
+fn texts(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().filter_map(text).collect(),
+        value => text(value).into_iter().collect(),
+    }
+}
+
+fn parse_business_card(data: &Value) -> BusinessCard {
+    let list = |plural: &str, singular: &str| match &data[plural] {
+        Value::Null => texts(&data[singular]),
+        value => texts(value),
+    };
+    BusinessCard {
+        name: text(&data["name"]),
+        title: text(&data["title"]),
+        company: text(&data["company"]),
+        phones: list("phones", "phone"),
+        emails: list("emails", "email"),
+        website: text(&data["website"]),
+        address: text(&data["address"]),
+    }
+}
+
+fn validate_business_card(card: &BusinessCard) -> Vec<String> {
+    let mut issues = Vec::new();
+    if card.name.is_none() {
+        issues.push("missing_name".to_string());
+    }
+    let valid_email = |email: &str| {
+        email
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace))
+    };
+    if !card.emails.iter().all(|email| valid_email(email)) {
+        issues.push("invalid_email".to_string());
+    }
+    issues
+}
+
+/// Escape a vCard property value
+fn vcard_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// vCard 3.0 for a business card, with CRLF line endings
+pub fn to_vcard(card: &BusinessCard) -> String {
+    // FN is required, cards without a readable name are filed under the company
+    let name = card.name.as_deref().or(card.company.as_deref()).unwrap_or_default();
+    // "Given Family" names are split at the last space, others (e.g. CJK) are kept whole
+    let (given, family) = name.rsplit_once(' ').unwrap_or(("", name));
+
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:3.0".to_string(),
+        format!("N:{};{};;;", vcard_escape(family.trim()), vcard_escape(given.trim())),
+        format!("FN:{}", vcard_escape(name)),
+    ];
+    if let Some(ref company) = card.company {
+        lines.push(format!("ORG:{}", vcard_escape(company)));
+    }
+    if let Some(ref title) = card.title {
+        lines.push(format!("TITLE:{}", vcard_escape(title)));
+    }
+    lines.extend(card.phones.iter().map(|phone| format!("TEL;TYPE=WORK,VOICE:{}", vcard_escape(phone))));
+    lines.extend(card.emails.iter().map(|email| format!("EMAIL;TYPE=INTERNET:{}", vcard_escape(email))));
+    if let Some(ref website) = card.website {
+        lines.push(format!("URL:{}", vcard_escape(website)));
+    }
+    if let Some(ref address) = card.address {
+        lines.push(format!("ADR;TYPE=WORK:;;{};;;;", vcard_escape(address)));
+    }
+    lines.push("END:VCARD".to_string());
+    lines.join("\r\n") + "\r\n"
+}
+
+fn validate_receipt(receipt: &Receipt) -> Vec<String> {
+    let mut issues = Vec::new();
+    let close = |a: f64, b: f64, terms: usize| (a - b).abs() <= SUM_TOLERANCE * terms.max(1) as f64 + f64::EPSILON;
+
+    if receipt.total.is_none() {
+        issues.push("missing_total".to_string());
+    }
+    if receipt.date.as_deref().is_some_and(|date| normalize_date(date).is_none()) {
+        issues.push("invalid_date".to_string());
+    }
+
+    // Line items add up to the subtotal, or to the total before tax without one
+    let amounts: Vec<f64> = receipt.line_items.iter().filter_map(|item| item.amount).collect();
+    let items_total = receipt
+        .subtotal
+        .or_else(|| receipt.total.zip(receipt.tax).map(|(total, tax)| total - tax));
+    if let Some(expected) = items_total {
+        if !amounts.is_empty() && !close(amounts.iter().sum(), expected, amounts.len()) {
+            issues.push("line_items_mismatch".to_string());
+        }
+    }
+    if let (Some(subtotal), Some(total)) = (receipt.subtotal, receipt.total) {
+        if !close(subtotal + receipt.tax.unwrap_or(0.0), total, 2) {
+            issues.push("total_mismatch".to_string());
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_receipt() {
+        let content = "```json\n{\"merchant\": \"Corner Cafe\", \"date\": \"2024/03/05\", \"currency\": \"eur\",
+            \"line_items\": [{\"description\": \"Latte\", \"quantity\": 2, \"unit_price\": \"3,50\", \"amount\": 7.0},
+            {\"description\": \"Croissant\", \"amount\": \"2.80 €\"}], \"subtotal\": 9.8, \"tax\": 0.98, \"total\": \"10.78\"}\n```";
+        let extraction = parse_extraction(content, ExtractionKind::Receipt).unwrap();
+        let receipt = extraction.receipt.as_ref().unwrap();
+
+        assert_eq!(receipt.merchant.as_deref(), Some("Corner Cafe"));
+        assert_eq!(receipt.date.as_deref(), Some("2024-03-05"));
+        assert_eq!(receipt.currency.as_deref(), Some("EUR"));
+        assert_eq!(receipt.line_items.len(), 2);
+        assert_eq!(receipt.line_items[0].unit_price, Some(3.5));
+        assert_eq!(receipt.line_items[1].amount, Some(2.8));
+        assert_eq!(receipt.total, Some(10.78));
+        assert!(extraction.is_valid(), "{:?}", extraction.issues);
+
+        assert!(parse_extraction("no receipt here", ExtractionKind::Receipt).is_none());
+    }
+
+    #[test]
+    fn test_validate_receipt() {
+        let content = r#"{"date": "sometime in May", "lineItems": [{"description": "A", "amount": 5}],
+            "subtotal": 6, "tax": 1, "total": 8}"#;
+        let extraction = parse_extraction(content, ExtractionKind::Receipt).unwrap();
+        assert_eq!(extraction.issues, ["invalid_date", "line_items_mismatch", "total_mismatch"]);
+
+        let extraction = parse_extraction(r#"{"merchant": "Shop"}"#, ExtractionKind::Receipt).unwrap();
+        assert_eq!(extraction.issues, ["missing_total"]);
+    }
+
+    #[test]
+    fn test_business_card_to_vcard() {
+        let content = r#"{"name": "Jane Doe", "title": "CTO", "company": "Acme; Inc.", "phone": "+1 555 0100",
+            "emails": ["jane@acme.example", "not-an-email"]}"#;
+        let extraction = parse_extraction(content, ExtractionKind::BusinessCard).unwrap();
+        assert_eq!(extraction.issues, ["invalid_email"]);
+        let card = extraction.business_card.unwrap();
+        assert_eq!(card.phones, ["+1 555 0100"]);
+
+        let vcard = to_vcard(&card);
+        assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:3.0\r\nN:Doe;Jane;;;\r\nFN:Jane Doe\r\n"));
+        assert!(vcard.contains("ORG:Acme\\; Inc.\r\n"));
+        assert!(vcard.contains("TEL;TYPE=WORK,VOICE:+1 555 0100\r\n"));
+        assert!(vcard.ends_with("END:VCARD\r\n"));
+
+        let card = BusinessCard { name: Some("张三".to_string()), ..Default::default() };
+        assert!(to_vcard(&card).contains("N:张三;;;;\r\n"));
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("$1,234.50"), Some(1234.5));
+        assert_eq!(parse_amount("12,5"), Some(12.5));
+        assert_eq!(parse_amount("1,234"), Some(1234.0));
+        assert_eq!(parse_amount("-3.00"), Some(-3.0));
+        assert_eq!(parse_amount("n/a"), None);
+    }
+}
@@ -0,0 +1,142 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::time::Duration;
+use crate::db::history;
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::table;
+
+/// Drag files older than this are removed when the next one is written
+const DRAG_FILE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Shown under the cursor while dragging
+const DRAG_ICON: &[u8] = include_bytes!("../../icons/32x32.png");
+
+/// Folder holding files dragged out of the window
+static DRAG_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Keep drag files in the per-user cache dir rather than the shared temp dir
+pub fn init_drag_dir(app_cache_dir: &Path) {
+    let _ = DRAG_DIR.set(app_cache_dir.join("drag"));
+}
+
+/// The cache folder, or a private temp folder for guest sessions
+fn drag_dir() -> Result<&'static PathBuf, String> {
+    DRAG_DIR.get_or_try_init(|| {
+        tempfile::Builder::new()
+            .prefix("orcapp-drag-")
+            .tempdir()
+            .map(|dir| dir.keep())
+            .map_err(|e| format!("创建临时目录失败: {}", e))
+    })
+}
+
+/// A result written out to be dragged into other apps
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DragFile {
+    pub path: String,
+    /// Image shown under the cursor
+    pub icon: String,
+}
+
+/// Open the file manager at the image file a history record was recognized from
+pub fn reveal_history_image(history_id: i64) -> Result<(), AppError> {
+    let record = history::get_history_by_id(history_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+    let path = record.image_path.filter(|path| !path.is_empty()).ok_or(ErrorCode::NoSourceFile)?;
+    if !Path::new(&path).is_file() {
+        return Err(ErrorCode::SourceFileMissing { path }.into());
+    }
+    reveal(Path::new(&path)).map_err(|e| AppError::internal(format!("打开文件管理器失败: {}", e)))
+}
+
+/// Write a record's result as `md`, `txt` or `csv` (the tables found in it) to
+/// a file named after its source image, to be dragged into other apps.
+/// The reviewed text wins over the original result.
+pub fn create_drag_file(history_id: i64, format: &str) -> Result<DragFile, AppError> {
+    let record = history::get_history_by_id(history_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+    let content = record.reviewed_result.unwrap_or(record.result);
+    let data = match format {
+        "md" | "txt" => content,
+        "csv" => {
+            let tables = table::extract_tables(&content);
+            if tables.is_empty() {
+                return Err(ErrorCode::NoTablesFound.into());
+            }
+            table::to_csv(&tables)
+        }
+        _ => return Err(ErrorCode::UnsupportedExportFormat { format: format.to_string() }.into()),
+    };
+
+    let root = drag_dir()?;
+    remove_stale_drag_files(root);
+    // One folder per record so results of images with the same name don't collide
+    let dir = root.join(history_id.to_string());
+    fs::create_dir_all(&dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let stem = record
+        .image_path
+        .as_deref()
+        .and_then(|path| Path::new(path).file_stem())
+        .and_then(|stem| stem.to_str())
+        .map_or_else(|| format!("result-{}", history_id), str::to_string);
+    let path = dir.join(format!("{}.{}", stem, format));
+    fs::write(&path, data).map_err(|e| format!("写入临时文件失败: {}", e))?;
+    let icon = root.join("icon.png");
+    if !icon.is_file() {
+        fs::write(&icon, DRAG_ICON).map_err(|e| format!("写入临时文件失败: {}", e))?;
+    }
+    Ok(DragFile {
+        path: path.to_string_lossy().into_owned(),
+        icon: icon.to_string_lossy().into_owned(),
+    })
+}
+
+/// Best effort: a file still being dropped somewhere may be locked
+fn remove_stale_drag_files(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        // Record folders only; the drag icon stays
+        let stale = entry.metadata().is_ok_and(|meta| {
+            meta.is_dir() && meta.modified().is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > DRAG_FILE_TTL))
+        });
+        if stale {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// Show `path` selected in the platform file manager; Linux file managers
+/// have no common way to select a file, so its folder is opened instead
+fn reveal(path: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        // Explorer doesn't parse `/select,` like other arguments; Rust's quoting
+        // of a path with spaces breaks it, so pass the quoted path as is
+        let mut command = Command::new("explorer");
+        command.raw_arg(format!("/select,\"{}\"", path.display()));
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+    command.spawn().map(drop)
+}
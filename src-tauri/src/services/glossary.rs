@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use crate::db::glossary::GlossaryEntry;
+
+/// A glossary term that was replaced in a result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryCorrection {
+    pub term: String,
+    pub preferred: String,
+    pub count: usize,
+}
+
+/// Replace the glossary terms in `text` with their preferred spelling.
+///
+/// Terms match case-insensitively and the spaces inside a term match any run
+/// of spaces or tabs, but not line breaks. A term that starts or ends with an
+/// ASCII letter or digit only matches at a word boundary there, so `ai` leaves
+/// `said` alone. Longer terms win where terms overlap, and replaced text isn't
+/// matched again. Occurrences already spelled as preferred aren't counted.
+pub fn correct(text: &str, entries: &[GlossaryEntry]) -> (String, Vec<GlossaryCorrection>) {
+    let mut patterns: Vec<(Vec<char>, &GlossaryEntry)> = entries
+        .iter()
+        .map(|entry| (entry.term.trim().chars().collect::<Vec<char>>(), entry))
+        .filter(|(pattern, _)| !pattern.is_empty())
+        .collect();
+    patterns.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.len()));
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut corrected = String::with_capacity(text.len());
+    let mut corrections: Vec<GlossaryCorrection> = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let found = patterns
+            .iter()
+            .find_map(|(pattern, entry)| match_at(&chars, pos, pattern).map(|end| (end, *entry)));
+        let Some((end, entry)) = found else {
+            corrected.push(chars[pos]);
+            pos += 1;
+            continue;
+        };
+        let matched: String = chars[pos..end].iter().collect();
+        corrected.push_str(&entry.preferred);
+        if matched != entry.preferred {
+            match corrections.iter_mut().find(|c| c.term == entry.term) {
+                Some(correction) => correction.count += 1,
+                None => corrections.push(GlossaryCorrection {
+                    term: entry.term.clone(),
+                    preferred: entry.preferred.clone(),
+                    count: 1,
+                }),
+            }
+        }
+        pos = end;
+    }
+    (corrected, corrections)
+}
+
+/// End of `pattern` matched at `pos` of `chars`
+fn match_at(chars: &[char], pos: usize, pattern: &[char]) -> Option<usize> {
+    if is_word_char(pattern[0]) && pos > 0 && is_word_char(chars[pos - 1]) {
+        return None;
+    }
+    let (mut i, mut p) = (pos, 0);
+    while p < pattern.len() {
+        if pattern[p].is_whitespace() {
+            while p < pattern.len() && pattern[p].is_whitespace() {
+                p += 1;
+            }
+            let start = i;
+            while i < chars.len() && is_inline_space(chars[i]) {
+                i += 1;
+            }
+            if i == start {
+                return None;
+            }
+        } else {
+            let c = *chars.get(i)?;
+            if !c.to_lowercase().eq(pattern[p].to_lowercase()) {
+                return None;
+            }
+            i += 1;
+            p += 1;
+        }
+    }
+    if is_word_char(pattern[pattern.len() - 1]) && chars.get(i).is_some_and(|&c| is_word_char(c)) {
+        return None;
+    }
+    Some(i)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_inline_space(c: char) -> bool {
+    c.is_whitespace() && c != '\n' && c != '\r'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(term: &str, preferred: &str) -> GlossaryEntry {
+        GlossaryEntry { id: 0, term: term.to_string(), preferred: preferred.to_string(), created_at: String::new() }
+    }
+
+    #[test]
+    fn test_correct() {
+        let entries = vec![entry("Open AI", "OpenAI"), entry("ai", "AI"), entry("orc app", "OrcApp"), entry("张三丰", "张三峰")];
+        let (text, corrections) = correct("open  AI said ai is fine.\nOrc\napp by 张三丰, OpenAI", &entries);
+        assert_eq!(text, "OpenAI said AI is fine.\nOrc\napp by 张三峰, OpenAI");
+        assert_eq!(
+            corrections,
+            vec![
+                GlossaryCorrection { term: "Open AI".to_string(), preferred: "OpenAI".to_string(), count: 1 },
+                GlossaryCorrection { term: "ai".to_string(), preferred: "AI".to_string(), count: 1 },
+                GlossaryCorrection { term: "张三丰".to_string(), preferred: "张三峰".to_string(), count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_correct_without_matches() {
+        let (text, corrections) = correct("Nothing to fix", &[entry("orc app", "OrcApp"), entry(" ", "x")]);
+        assert_eq!(text, "Nothing to fix");
+        assert!(corrections.is_empty());
+    }
+}
@@ -0,0 +1,367 @@
+//! Self-contained history exports: a ZIP holding a JSON manifest, a CSV copy of
+//! it for spreadsheets and the original image of every record. Bundles and
+//! plain JSON exports can be imported again on another machine.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::db::history::{self, HistoryInput, HistoryQueryParams, HistoryRecord};
+use crate::db::model_config;
+use crate::utils::time;
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::image::{decode_image, perceptual_hash_of};
+use super::image_store;
+use super::table::{to_csv, Table};
+use super::text_stats;
+
+pub const BUNDLE_VERSION: u32 = 1;
+pub const MANIFEST_JSON: &str = "manifest.json";
+pub const MANIFEST_CSV: &str = "manifest.csv";
+const IMAGES_DIR: &str = "images";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub version: u32,
+    pub exported_at: String,
+    pub records: Vec<BundleRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRecord {
+    #[serde(flatten)]
+    pub record: HistoryRecord,
+    /// Path of the original image inside the bundle, None when the record has
+    /// no stored image or the file is missing
+    pub image: Option<String>,
+}
+
+/// Write the history matching `params` as a ZIP bundle to `path`
+pub fn export_history_bundle(params: HistoryQueryParams, path: &Path) -> Result<(), AppError> {
+    let records = history::export_history(params).map_err(|e| e.to_string())?;
+    let file = File::create(path).map_err(|e| format!("保存文件失败: {}", e))?;
+    build_bundle(BufWriter::new(file), records, image_store::load_image)?
+        .flush()
+        .map_err(|e| AppError::internal(format!("保存文件失败: {}", e)))
+}
+
+/// Write the bundle of `records` to `writer` one image at a time, reading
+/// stored images with `load_image`. Records whose image can't be read are kept
+/// without one. Large archives switch to ZIP64.
+pub fn build_bundle<W: Write + Seek>(
+    writer: W,
+    records: Vec<HistoryRecord>,
+    load_image: impl Fn(&str) -> Result<Vec<u8>, String>,
+) -> Result<W, String> {
+    let mut zip = ZipWriter::new(writer);
+    let mut entries = Vec::with_capacity(records.len());
+    for record in records {
+        let image = match record.image_file.as_deref().map(|file_name| (file_name, load_image(file_name))) {
+            Some((file_name, Ok(data))) => {
+                let path = format!("{}/{}", IMAGES_DIR, file_name);
+                add_file(&mut zip, &path, &data)?;
+                Some(path)
+            }
+            _ => None,
+        };
+        entries.push(BundleRecord { record, image });
+    }
+
+    let manifest = BundleManifest {
+        version: BUNDLE_VERSION,
+        exported_at: time::now_utc_iso(),
+        records: entries,
+    };
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    add_file(&mut zip, MANIFEST_JSON, json.as_bytes())?;
+    add_file(&mut zip, MANIFEST_CSV, manifest_csv(&manifest.records).as_bytes())?;
+    zip.finish().map_err(|e| e.to_string())
+}
+
+fn add_file<W: Write + Seek>(zip: &mut ZipWriter<W>, name: &str, data: &[u8]) -> Result<(), String> {
+    // Images are compressed already
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(data.len() as u64 >= u32::MAX as u64);
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    zip.write_all(data).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Records already present, by uuid or by creation time and image
+    pub skipped: usize,
+    pub images: usize,
+}
+
+/// Contents of an import file: a bundle manifest, or the record list written
+/// by the JSON export of the history page
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    Bundle(BundleManifest),
+    Records(Vec<HistoryRecord>),
+}
+
+/// Import records from a ZIP bundle or JSON export. Records already present
+/// (same uuid, or same creation time and image) are skipped. Config names are remapped with
+/// `config_map` (source name to local config id), then by matching a local
+/// config of the same name; other records keep their name without a config.
+pub fn import_history_bundle(path: &Path, config_map: &HashMap<String, i64>) -> Result<ImportReport, AppError> {
+    let file = File::open(path).map_err(|e| ErrorCode::InvalidHistoryImport { detail: e.to_string() })?;
+    let (records, mut archive) = parse_import(file)?;
+    let configs = local_config_names()?;
+
+    let mut report = ImportReport::default();
+    for entry in records {
+        // Images are read one at a time, bundles may be larger than memory
+        let image = match (&entry.image, archive.as_mut()) {
+            (Some(path), Some(archive)) => read_entry(archive, path).ok().map(|data| (path, data)),
+            _ => None,
+        };
+        let image_hash = image
+            .as_ref()
+            .and_then(|(_, data)| decode_image(data).ok())
+            .map(|img| perceptual_hash_of(&img));
+        let uuid = record_uuid(&entry.record.uuid, &entry.record.created_at, &entry.record.config_name);
+        if history::history_uuid_exists(&uuid).map_err(|e| e.to_string())?
+            || history::history_record_exists(&entry.record.created_at, image_hash).map_err(|e| e.to_string())?
+        {
+            report.skipped += 1;
+            continue;
+        }
+
+        let image_file = match image {
+            Some((path, data)) => {
+                let extension = path.rsplit('.').next().unwrap_or("jpg");
+                report.images += 1;
+                Some(image_store::save_image_data(&data, extension)?)
+            }
+            None => None,
+        };
+        let record = entry.record;
+        let created_at = record.created_at.clone();
+        let config = resolve_config(&record.config_name, config_map, &configs);
+        let input = history_input(record, config, image_file, image_hash);
+        history::import_history_record(input, &created_at).map_err(|e| e.to_string())?;
+        report.imported += 1;
+    }
+    Ok(report)
+}
+
+/// Uuid of an exported record; exports that predate uuids get the one every
+/// machine derives for the record
+pub fn record_uuid(uuid: &str, created_at: &str, config_name: &str) -> String {
+    if uuid.is_empty() {
+        history::legacy_uuid(created_at, config_name)
+    } else {
+        uuid.to_string()
+    }
+}
+
+/// (id, name) of the configs visible in the active profile
+pub fn local_config_names() -> Result<Vec<(i64, String)>, String> {
+    Ok(model_config::get_all_configs()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect())
+}
+
+/// Input recreating a record from another machine under the local config
+/// `(id, name)`, under the same uuid; text statistics are computed again
+pub fn history_input(
+    record: HistoryRecord,
+    (config_id, config_name): (i64, String),
+    image_file: Option<String>,
+    image_hash: Option<u64>,
+) -> HistoryInput {
+    HistoryInput {
+        config_id,
+        config_name,
+        image_path: record.image_path,
+        image_file,
+        image_thumbnail: record.image_thumbnail,
+        compare_thumbnail: record.compare_thumbnail,
+        stats: text_stats::analyze(&record.result),
+        prompt: record.prompt,
+        result: record.result,
+        reasoning: record.reasoning,
+        tokens_used: record.tokens_used,
+        input_tokens: record.input_tokens,
+        output_tokens: record.output_tokens,
+        duration_ms: record.duration_ms,
+        ttft_ms: record.ttft_ms,
+        tokens_per_second: record.tokens_per_second,
+        provider: record.provider.unwrap_or_default(),
+        model_name: record.model_name.unwrap_or_default(),
+        temperature: record.temperature,
+        top_p: record.top_p,
+        max_tokens: record.max_tokens.unwrap_or_default(),
+        app_version: record.app_version.unwrap_or_default(),
+        status: record.status,
+        error: record.error,
+        error_code: record.error_code,
+        image_hash,
+        confidence: record.confidence,
+        needs_review: record.needs_review,
+        batch_id: record.batch_id,
+        review_status: record.review_status,
+        reviewed_result: record.reviewed_result,
+        // Record ids differ on the importing machine
+        rerun_of: None,
+        tags: record.tags,
+        template_id: None,
+        uuid: Some(record_uuid(&record.uuid, &record.created_at, &record.config_name)),
+    }
+}
+
+/// Records of an import file, and the archive holding their images when it is a bundle
+fn parse_import<R: Read + Seek>(mut reader: R) -> Result<(Vec<BundleRecord>, Option<ZipArchive<R>>), ErrorCode> {
+    let invalid = |detail: String| ErrorCode::InvalidHistoryImport { detail };
+    let mut magic = [0u8; 2];
+    let is_zip = reader.read_exact(&mut magic).is_ok() && &magic == b"PK";
+    reader.seek(SeekFrom::Start(0)).map_err(|e| invalid(e.to_string()))?;
+
+    let (json, archive) = if is_zip {
+        let mut archive = ZipArchive::new(reader).map_err(|e| invalid(e.to_string()))?;
+        let manifest = read_entry(&mut archive, MANIFEST_JSON)?;
+        (manifest, Some(archive))
+    } else {
+        let mut json = Vec::new();
+        reader.read_to_end(&mut json).map_err(|e| invalid(e.to_string()))?;
+        (json, None)
+    };
+
+    match serde_json::from_slice(&json).map_err(|e| invalid(e.to_string()))? {
+        ImportFile::Bundle(manifest) if manifest.version > BUNDLE_VERSION => {
+            Err(invalid(format!("unsupported version {}", manifest.version)))
+        }
+        ImportFile::Bundle(manifest) => Ok((manifest.records, archive)),
+        ImportFile::Records(records) => Ok((
+            records.into_iter().map(|record| BundleRecord { record, image: None }).collect(),
+            archive,
+        )),
+    }
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>, ErrorCode> {
+    let invalid = |detail: String| ErrorCode::InvalidHistoryImport { detail };
+    let mut entry = archive.by_name(name).map_err(|e| invalid(format!("{}: {}", name, e)))?;
+    let mut data = Vec::with_capacity(entry.size() as usize);
+    entry.read_to_end(&mut data).map_err(|e| invalid(format!("{}: {}", name, e)))?;
+    Ok(data)
+}
+
+/// Local config id and name, among `configs` as (id, name), for a record made
+/// with config `name` elsewhere; id 0, like a deleted config, when nothing matches
+pub fn resolve_config(name: &str, config_map: &HashMap<String, i64>, configs: &[(i64, String)]) -> (i64, String) {
+    let local = match config_map.get(name) {
+        Some(id) => configs.iter().find(|(local_id, _)| local_id == id),
+        None => configs.iter().find(|(_, local_name)| local_name == name),
+    };
+    local.cloned().unwrap_or((0, name.to_string()))
+}
+
+fn manifest_csv(records: &[BundleRecord]) -> String {
+    let header = ["ID", "配置名称", "模型", "提示词", "结果", "Token消耗", "耗时(ms)", "状态", "图片", "时间"];
+    let mut rows = vec![header.iter().map(|h| h.to_string()).collect::<Vec<_>>()];
+    rows.extend(records.iter().map(|entry| {
+        let r = &entry.record;
+        vec![
+            r.id.to_string(),
+            r.config_name.clone(),
+            r.model_name.clone().unwrap_or_default(),
+            r.prompt.clone(),
+            r.result.clone(),
+            r.tokens_used.map(|v| v.to_string()).unwrap_or_default(),
+            r.duration_ms.map(|v| v.to_string()).unwrap_or_default(),
+            r.status.clone(),
+            entry.image.clone().unwrap_or_default(),
+            r.created_at.clone(),
+        ]
+    }));
+    to_csv(&[Table { rows }])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn record(id: i64, image_file: Option<&str>) -> HistoryRecord {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "configId": 1,
+            "configName": "GPT-4o",
+            "imageFile": image_file,
+            "prompt": "识别文字",
+            "result": "hello, world",
+            "status": "success",
+            "createdAt": "2026-01-01 00:00:00",
+        }))
+        .unwrap()
+    }
+
+    fn bundle(records: Vec<HistoryRecord>, load_image: impl Fn(&str) -> Result<Vec<u8>, String>) -> Vec<u8> {
+        build_bundle(Cursor::new(Vec::new()), records, load_image).unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_bundle_contains_manifest_and_images() {
+        let records = vec![record(1, Some("a.png")), record(2, Some("gone.png")), record(3, None)];
+        let bytes = bundle(records, |name| match name {
+            "a.png" => Ok(b"PNGDATA".to_vec()),
+            _ => Err("missing".to_string()),
+        });
+
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let names: Vec<_> = archive.file_names().collect();
+        assert_eq!(archive.len(), 3);
+        assert!(names.contains(&MANIFEST_CSV));
+        assert!(!names.contains(&"images/gone.png"));
+        assert_eq!(read_entry(&mut archive, "images/a.png").unwrap(), b"PNGDATA");
+
+        let manifest: BundleManifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST_JSON).unwrap()).unwrap();
+        assert_eq!(manifest.version, BUNDLE_VERSION);
+        let images: Vec<_> = manifest.records.iter().map(|r| r.image.as_deref()).collect();
+        assert_eq!(images, vec![Some("images/a.png"), None, None]);
+        assert_eq!(manifest.records[0].record.result, "hello, world");
+    }
+
+    #[test]
+    fn test_parse_import_bundle_and_json() {
+        let bytes = bundle(vec![record(1, Some("a.png")), record(2, None)], |_| Ok(b"PNGDATA".to_vec()));
+        let (records, archive) = parse_import(Cursor::new(bytes)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].image.as_deref(), Some("images/a.png"));
+        assert_eq!(read_entry(&mut archive.unwrap(), "images/a.png").unwrap(), b"PNGDATA");
+
+        let json = serde_json::to_vec(&vec![record(3, Some("b.png"))]).unwrap();
+        let (records, archive) = parse_import(Cursor::new(json)).unwrap();
+        assert_eq!(records[0].record.id, 3);
+        assert!(records[0].image.is_none());
+        assert!(archive.is_none());
+
+        let future = Cursor::new(br#"{ "version": 99, "exportedAt": "", "records": [] }"#);
+        assert!(matches!(parse_import(future), Err(ErrorCode::InvalidHistoryImport { .. })));
+        assert!(matches!(parse_import(Cursor::new(b"[1, 2]")), Err(ErrorCode::InvalidHistoryImport { .. })));
+    }
+
+    #[test]
+    fn test_resolve_config() {
+        let configs = vec![(5, "GPT-4o".to_string()), (6, "Claude".to_string())];
+        let map = HashMap::from([("OpenAI 工作".to_string(), 6)]);
+        assert_eq!(resolve_config("GPT-4o", &map, &configs), (5, "GPT-4o".to_string()));
+        assert_eq!(resolve_config("OpenAI 工作", &map, &configs), (6, "Claude".to_string()));
+        assert_eq!(resolve_config("Gemini", &map, &configs), (0, "Gemini".to_string()));
+    }
+}
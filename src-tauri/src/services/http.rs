@@ -0,0 +1,139 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use crate::db::settings::{self, AppSettings};
+
+/// The settings a client is built from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ClientSettings {
+    user_agent: String,
+    extra_headers: BTreeMap<String, String>,
+    ca_bundle_path: String,
+    http2_enabled: bool,
+    tcp_keepalive_secs: i32,
+    http2_keep_alive_secs: i32,
+    connect_timeout_secs: i32,
+    pool_idle_timeout_secs: i32,
+}
+
+impl ClientSettings {
+    fn from_app_settings(app_settings: AppSettings) -> Self {
+        Self {
+            user_agent: app_settings.user_agent,
+            extra_headers: app_settings.extra_headers.into_iter().collect(),
+            ca_bundle_path: app_settings.ca_bundle_path,
+            http2_enabled: app_settings.http2_enabled,
+            tcp_keepalive_secs: app_settings.tcp_keepalive_secs,
+            http2_keep_alive_secs: app_settings.http2_keep_alive_secs,
+            connect_timeout_secs: app_settings.connect_timeout_secs,
+            pool_idle_timeout_secs: app_settings.pool_idle_timeout_secs,
+        }
+    }
+}
+
+/// Settings, timeout, TLS verification and use of the extra headers a shared
+/// client was built with
+type ClientKey = (ClientSettings, Duration, bool, bool);
+
+/// Clients shared by all requests so pooled connections and TLS sessions are
+/// reused; a new one is only built when the settings or TLS profile differ
+static CLIENTS: Lazy<Mutex<HashMap<ClientKey, Client>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Shared HTTP client for provider requests, applying the global User-Agent,
+/// extra headers, trusted CA bundle and connection tuning from settings.
+/// `skip_tls_verify` accepts any certificate, see the config flag.
+pub fn shared_client(timeout: Duration, skip_tls_verify: bool) -> Result<Client, String> {
+    cached_client(timeout, skip_tls_verify, true)
+}
+
+/// Shared HTTP client for the app's own services (sharing, Notion, sync):
+/// the CA bundle and connection tuning of [`shared_client`], without the
+/// extra headers meant for providers
+pub fn service_client(timeout: Duration) -> Result<Client, String> {
+    cached_client(timeout, false, false)
+}
+
+fn cached_client(timeout: Duration, skip_tls_verify: bool, extra_headers: bool) -> Result<Client, String> {
+    let app_settings = settings::get_all_settings().unwrap_or_else(|_| AppSettings::default_settings());
+    let key = (ClientSettings::from_app_settings(app_settings), timeout, skip_tls_verify, extra_headers);
+
+    let mut clients = CLIENTS.lock();
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+    let client = build_client(&key.0, timeout, skip_tls_verify, extra_headers)?;
+    // Clients built from earlier settings are never asked for again
+    clients.retain(|(client_settings, _, _, _), _| *client_settings == key.0);
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+fn build_client(
+    client_settings: &ClientSettings,
+    timeout: Duration,
+    skip_tls_verify: bool,
+    extra_headers: bool,
+) -> Result<Client, String> {
+    let user_agent = match client_settings.user_agent.trim() {
+        "" => format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        custom => custom.to_string(),
+    };
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in client_settings.extra_headers.iter().filter(|_| extra_headers) {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("无效的请求头名称: {}", name))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("无效的请求头值: {}", name))?;
+        headers.insert(name, value);
+    }
+
+    let mut builder = Client::builder()
+        .timeout(timeout)
+        .user_agent(user_agent)
+        .default_headers(headers);
+
+    for cert in load_ca_bundle(&client_settings.ca_bundle_path)? {
+        builder = builder.add_root_certificate(cert);
+    }
+    if skip_tls_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    if !client_settings.http2_enabled {
+        builder = builder.http1_only();
+    }
+    builder = builder.tcp_keepalive(positive_secs(client_settings.tcp_keepalive_secs));
+    builder = builder.http2_keep_alive_interval(positive_secs(client_settings.http2_keep_alive_secs));
+    if let Some(connect_timeout) = positive_secs(client_settings.connect_timeout_secs) {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    builder = match positive_secs(client_settings.pool_idle_timeout_secs) {
+        Some(idle) => builder.pool_idle_timeout(idle),
+        None => builder.pool_max_idle_per_host(0),
+    };
+
+    builder
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// Certificates of the PEM bundle at `path`; none when the path is empty
+fn load_ca_bundle(path: &str) -> Result<Vec<Certificate>, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pem = std::fs::read(path).map_err(|e| format!("读取 CA 证书失败 {}: {}", path, e))?;
+    let certs = Certificate::from_pem_bundle(&pem).map_err(|e| format!("无效的 CA 证书 {}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("CA 证书文件中没有证书: {}", path));
+    }
+    Ok(certs)
+}
+
+fn positive_secs(secs: i32) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs as u64))
+}
@@ -0,0 +1,481 @@
+use serde::{Deserialize, Serialize};
+use crate::db::settings;
+
+/// Error codes surfaced to the frontend.
+///
+/// Serialized as `{ "code": "...", "params": { ... } }` so the frontend can map
+/// codes to its own translations; `message` renders the backend catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", content = "params", rename_all = "snake_case")]
+pub enum ErrorCode {
+    ConfigNotFound,
+    ConfigDisabled,
+    ConfigLoadFailed { detail: String },
+    UnsupportedProvider { provider: String },
+    EmptyImage,
+    ImageProcessingFailed { detail: String },
+    UnsupportedImageFile { name: String },
+    ImageTooLarge { max_mb: usize },
+    HttpClientFailed { detail: String },
+    RequestTimeout,
+    ConnectFailed,
+    RequestFailed { detail: String },
+    InvalidApiKey,
+    ApiKeyForbidden,
+    EndpointNotFound,
+    RateLimited,
+    /// Error message returned by the provider itself, shown verbatim
+    ProviderMessage { status: u16, message: String },
+    ServerError { status: u16, body: String },
+    InvalidResponse { detail: String },
+    UnexpectedResponse,
+    RecognitionCancelled,
+    RecognitionTaskFailed { detail: String },
+    OrientationFailed,
+    ExperimentNotFound,
+    ExperimentRunning,
+    TemplateNotFound { id: i64 },
+    /// A template with the same content already exists
+    DuplicateTemplate { id: i64, name: String },
+    TooManyExamples { max: usize },
+    NoImagesSelected,
+    InvalidRating,
+    InvalidVariantCount { min: usize, max: usize },
+    AbTestNotFound,
+    RedactionUnavailable,
+    RedactionConfigNotLocal { name: String },
+    RedactionFailed { detail: String },
+    BackupFailed { detail: String },
+    InvalidBackup { detail: String },
+    RestoreFailed { detail: String },
+    WorkspaceNotFound,
+    ProfileNotFound,
+    /// The config belongs to a profile other than the active one
+    ConfigOutsideProfile { name: String },
+    ProfileNotEmpty { count: i64 },
+    AppLocked,
+    WrongPasscode,
+    PasscodeTooShort { min: usize },
+    InvalidRecipe { detail: String },
+    NoMatchingConfig { provider: String, model: String },
+    NoTablesFound,
+    UnsupportedExportFormat { format: String },
+    PresetNotFound,
+    UpdateFailed { detail: String },
+    NoPendingUpdate,
+    HistoryNotFound,
+    NoSourceFile,
+    NoStoredImage,
+    SourceFileMissing { path: String },
+    SpeechUnavailable { detail: String },
+    NotebookNotConfigured,
+    LocalModelMissing { path: String },
+    LocalOcrFailed { detail: String },
+    UnknownSetting { key: String },
+    /// `expected` describes valid values, e.g. `0 – 2` or `light | dark | system`
+    InvalidSetting { key: String, expected: String },
+    InvalidHistoryImport { detail: String },
+    InvalidRegex { detail: String },
+    SyncNotConfigured,
+    SyncWrongPassphrase,
+    SyncInProgress,
+    SyncFailed { detail: String },
+    ShareNotConfigured,
+    ShareFailed { detail: String },
+    EmailNotConfigured,
+    InvalidEmailAddress { address: String },
+    EmailFailed { detail: String },
+    NotionNotConfigured,
+    NotionFailed { detail: String },
+}
+
+impl ErrorCode {
+    /// Render the message in a UI language; unknown languages fall back to Chinese
+    pub fn message(&self, language: &str) -> String {
+        match language {
+            "en-US" => self.message_en(),
+            "ja-JP" => self.message_ja(),
+            _ => self.message_zh(),
+        }
+    }
+
+    /// Render the message in the language selected in settings
+    pub fn localized(&self) -> String {
+        self.message(&current_language())
+    }
+
+    /// The serialized `code` tag, e.g. `rate_limited`
+    pub fn code(&self) -> Option<String> {
+        serde_json::to_value(self)
+            .ok()?
+            .get("code")?
+            .as_str()
+            .map(str::to_string)
+    }
+
+    fn message_zh(&self) -> String {
+        match self {
+            Self::ConfigNotFound => "配置不存在".to_string(),
+            Self::ConfigDisabled => "该配置已禁用".to_string(),
+            Self::ConfigLoadFailed { detail } => format!("获取配置失败: {}", detail),
+            Self::UnsupportedProvider { provider } => format!("不支持的供应商类型: {}", provider),
+            Self::EmptyImage => "图片数据为空".to_string(),
+            Self::UnsupportedImageFile { name } => format!("不支持的图片文件: {}", name),
+            Self::ImageTooLarge { max_mb } => format!("图片超过 {}MB", max_mb),
+            Self::ImageProcessingFailed { detail } => format!("图片处理失败: {}", detail),
+            Self::HttpClientFailed { detail } => format!("创建 HTTP 客户端失败: {}", detail),
+            Self::RequestTimeout => "请求超时，请检查网络连接".to_string(),
+            Self::ConnectFailed => "连接失败，请检查网络连接或 API 地址".to_string(),
+            Self::RequestFailed { detail } => format!("请求失败: {}", detail),
+            Self::InvalidApiKey => "API 密钥无效".to_string(),
+            Self::ApiKeyForbidden => "API 密钥权限不足".to_string(),
+            Self::EndpointNotFound => "API 地址错误或模型不存在".to_string(),
+            Self::RateLimited => "请求频率过高或配额已用尽".to_string(),
+            Self::ProviderMessage { message, .. } => message.clone(),
+            Self::ServerError { status, body } => format!("服务器错误 ({}): {}", status, body),
+            Self::InvalidResponse { detail } => format!("解析响应失败: {}", detail),
+            Self::UnexpectedResponse => "响应格式异常".to_string(),
+            Self::RecognitionCancelled => "识别已取消".to_string(),
+            Self::RecognitionTaskFailed { detail } => format!("识别任务失败: {}", detail),
+            Self::OrientationFailed => "方向检测失败".to_string(),
+            Self::ExperimentNotFound => "实验不存在".to_string(),
+            Self::ExperimentRunning => "实验正在运行，请等待进行中的请求完成".to_string(),
+            Self::TemplateNotFound { id } => format!("模板不存在: {}", id),
+            Self::DuplicateTemplate { name, .. } => format!("已有内容相同的模板「{}」", name),
+            Self::TooManyExamples { max } => format!("每个模板最多 {} 个示例", max),
+            Self::NoImagesSelected => "请至少选择一张图片".to_string(),
+            Self::InvalidRating => "评分必须在 1-5 之间".to_string(),
+            Self::InvalidVariantCount { min, max } => format!("提示词变体数量必须在 {}-{} 之间", min, max),
+            Self::AbTestNotFound => "A/B 测试不存在".to_string(),
+            Self::RedactionUnavailable => "该配置要求脱敏，请先在设置中选择本地识别配置".to_string(),
+            Self::RedactionConfigNotLocal { name } => format!("脱敏识别配置 {} 不是本地或内网地址", name),
+            Self::RedactionFailed { detail } => format!("敏感信息脱敏失败，图片未发送: {}", detail),
+            Self::BackupFailed { detail } => format!("备份数据库失败: {}", detail),
+            Self::InvalidBackup { detail } => format!("备份文件无效: {}", detail),
+            Self::RestoreFailed { detail } => format!("恢复数据库失败: {}", detail),
+            Self::WorkspaceNotFound => "工作区不存在".to_string(),
+            Self::ProfileNotFound => "配置档案不存在".to_string(),
+            Self::ConfigOutsideProfile { name } => format!("配置 {} 不属于当前配置档案", name),
+            Self::ProfileNotEmpty { count } => {
+                format!("配置档案中还有 {} 个模型配置或模板，请先删除或移出", count)
+            }
+            Self::AppLocked => "应用已锁定，请输入密码解锁".to_string(),
+            Self::WrongPasscode => "密码错误".to_string(),
+            Self::PasscodeTooShort { min } => format!("密码至少需要 {} 位", min),
+            Self::InvalidRecipe { detail } => format!("流程配方无效: {}", detail),
+            Self::NoMatchingConfig { provider, model } => {
+                format!("没有可用的 {} / {} 模型配置", provider, model)
+            }
+            Self::NoTablesFound => "结果中没有找到表格".to_string(),
+            Self::UnsupportedExportFormat { format } => format!("不支持的导出格式: {}", format),
+            Self::PresetNotFound => "预设不存在".to_string(),
+            Self::UpdateFailed { detail } => format!("更新失败: {}", detail),
+            Self::NoPendingUpdate => "没有可安装的更新".to_string(),
+            Self::HistoryNotFound => "历史记录不存在".to_string(),
+            Self::NoSourceFile => "该记录不是从本地文件识别的".to_string(),
+            Self::NoStoredImage => "该记录没有保存原图".to_string(),
+            Self::SourceFileMissing { path } => format!("原图片已被移动或删除: {}", path),
+            Self::SpeechUnavailable { detail } => format!("语音朗读不可用: {}", detail),
+            Self::NotebookNotConfigured => "请先在设置中选择笔记文件".to_string(),
+            Self::LocalModelMissing { path } => format!("本地 OCR 模型不完整，请检查目录: {}", path),
+            Self::LocalOcrFailed { detail } => format!("本地 OCR 识别失败: {}", detail),
+            Self::UnknownSetting { key } => format!("未知的设置项: {}", key),
+            Self::InvalidSetting { key, expected } => format!("设置项 {} 的值无效，应为 {}", key, expected),
+            Self::InvalidHistoryImport { detail } => format!("历史记录导入文件无效: {}", detail),
+            Self::InvalidRegex { detail } => format!("正则表达式无效: {}", detail),
+            Self::SyncNotConfigured => "请先在设置中填写同步地址、凭据和同步密码".to_string(),
+            Self::SyncWrongPassphrase => "无法解密同步数据，请检查同步密码".to_string(),
+            Self::SyncInProgress => "正在同步，请稍候".to_string(),
+            Self::SyncFailed { detail } => format!("同步失败: {}", detail),
+            Self::ShareNotConfigured => "请先在设置中填写分享的 GitHub Token 或自定义地址".to_string(),
+            Self::ShareFailed { detail } => format!("分享失败: {}", detail),
+            Self::EmailNotConfigured => "请先在设置中填写 SMTP 服务器、发件人和收件人".to_string(),
+            Self::InvalidEmailAddress { address } => format!("无效的邮箱地址: {}", address),
+            Self::EmailFailed { detail } => format!("发送邮件失败: {}", detail),
+            Self::NotionNotConfigured => "请先在设置中填写 Notion Token 和数据库 ID".to_string(),
+            Self::NotionFailed { detail } => format!("导出到 Notion 失败: {}", detail),
+        }
+    }
+
+    fn message_en(&self) -> String {
+        match self {
+            Self::ConfigNotFound => "Configuration not found".to_string(),
+            Self::ConfigDisabled => "This configuration is disabled".to_string(),
+            Self::ConfigLoadFailed { detail } => format!("Failed to load configuration: {}", detail),
+            Self::UnsupportedProvider { provider } => format!("Unsupported provider type: {}", provider),
+            Self::EmptyImage => "Image data is empty".to_string(),
+            Self::UnsupportedImageFile { name } => format!("Unsupported image file: {}", name),
+            Self::ImageTooLarge { max_mb } => format!("The image is larger than {} MB", max_mb),
+            Self::ImageProcessingFailed { detail } => format!("Image processing failed: {}", detail),
+            Self::HttpClientFailed { detail } => format!("Failed to create HTTP client: {}", detail),
+            Self::RequestTimeout => "Request timed out, please check your network connection".to_string(),
+            Self::ConnectFailed => "Connection failed, please check your network or the API URL".to_string(),
+            Self::RequestFailed { detail } => format!("Request failed: {}", detail),
+            Self::InvalidApiKey => "Invalid API key".to_string(),
+            Self::ApiKeyForbidden => "The API key does not have permission".to_string(),
+            Self::EndpointNotFound => "Wrong API URL or the model does not exist".to_string(),
+            Self::RateLimited => "Rate limit exceeded or quota exhausted".to_string(),
+            Self::ProviderMessage { message, .. } => message.clone(),
+            Self::ServerError { status, body } => format!("Server error ({}): {}", status, body),
+            Self::InvalidResponse { detail } => format!("Failed to parse response: {}", detail),
+            Self::UnexpectedResponse => "Unexpected response format".to_string(),
+            Self::RecognitionCancelled => "Recognition cancelled".to_string(),
+            Self::RecognitionTaskFailed { detail } => format!("Recognition task failed: {}", detail),
+            Self::OrientationFailed => "Orientation detection failed".to_string(),
+            Self::ExperimentNotFound => "Experiment not found".to_string(),
+            Self::ExperimentRunning => "The experiment is still running, wait for its requests to finish".to_string(),
+            Self::TemplateNotFound { id } => format!("Template not found: {}", id),
+            Self::DuplicateTemplate { name, .. } => format!("The template \"{}\" has the same content", name),
+            Self::TooManyExamples { max } => format!("A template can have at most {} examples", max),
+            Self::NoImagesSelected => "Please select at least one image".to_string(),
+            Self::InvalidRating => "Rating must be between 1 and 5".to_string(),
+            Self::InvalidVariantCount { min, max } => {
+                format!("The number of prompt variants must be between {} and {}", min, max)
+            }
+            Self::AbTestNotFound => "A/B test not found".to_string(),
+            Self::RedactionUnavailable => {
+                "This config requires redaction, choose a local recognition config in settings first".to_string()
+            }
+            Self::RedactionConfigNotLocal { name } => {
+                format!("The redaction config {} is not on a local or private network address", name)
+            }
+            Self::RedactionFailed { detail } => format!("Redaction failed, the image was not sent: {}", detail),
+            Self::BackupFailed { detail } => format!("Database backup failed: {}", detail),
+            Self::InvalidBackup { detail } => format!("Invalid backup file: {}", detail),
+            Self::RestoreFailed { detail } => format!("Database restore failed: {}", detail),
+            Self::WorkspaceNotFound => "Workspace not found".to_string(),
+            Self::ProfileNotFound => "Profile not found".to_string(),
+            Self::ConfigOutsideProfile { name } => format!("The config {} does not belong to the active profile", name),
+            Self::ProfileNotEmpty { count } => {
+                format!("The profile still owns {} configs or templates, delete or move them first", count)
+            }
+            Self::AppLocked => "The app is locked, enter the passcode to unlock it".to_string(),
+            Self::WrongPasscode => "Wrong passcode".to_string(),
+            Self::PasscodeTooShort { min } => format!("The passcode must have at least {} characters", min),
+            Self::InvalidRecipe { detail } => format!("Invalid recipe: {}", detail),
+            Self::NoMatchingConfig { provider, model } => {
+                format!("No enabled configuration for {} / {}", provider, model)
+            }
+            Self::NoTablesFound => "No tables found in the result".to_string(),
+            Self::UnsupportedExportFormat { format } => format!("Unsupported export format: {}", format),
+            Self::PresetNotFound => "Preset not found".to_string(),
+            Self::UpdateFailed { detail } => format!("Update failed: {}", detail),
+            Self::NoPendingUpdate => "No update is ready to install".to_string(),
+            Self::HistoryNotFound => "History record not found".to_string(),
+            Self::NoSourceFile => "This record was not recognized from a local file".to_string(),
+            Self::NoStoredImage => "This record has no stored image".to_string(),
+            Self::SourceFileMissing { path } => format!("The original image was moved or deleted: {}", path),
+            Self::SpeechUnavailable { detail } => format!("Text-to-speech is unavailable: {}", detail),
+            Self::NotebookNotConfigured => "Choose a notebook file in the settings first".to_string(),
+            Self::LocalModelMissing { path } => format!("Local OCR model files are missing, check the folder: {}", path),
+            Self::LocalOcrFailed { detail } => format!("Local OCR failed: {}", detail),
+            Self::UnknownSetting { key } => format!("Unknown setting: {}", key),
+            Self::InvalidSetting { key, expected } => format!("Invalid value for setting {}, expected {}", key, expected),
+            Self::InvalidHistoryImport { detail } => format!("Invalid history import file: {}", detail),
+            Self::InvalidRegex { detail } => format!("Invalid regular expression: {}", detail),
+            Self::SyncNotConfigured => "Set the sync endpoint, credentials and passphrase in the settings first".to_string(),
+            Self::SyncWrongPassphrase => "Could not decrypt the synced data, check the sync passphrase".to_string(),
+            Self::SyncInProgress => "A sync is already running".to_string(),
+            Self::SyncFailed { detail } => format!("Sync failed: {}", detail),
+            Self::ShareNotConfigured => "Set a GitHub token or custom share URL in the settings first".to_string(),
+            Self::ShareFailed { detail } => format!("Sharing failed: {}", detail),
+            Self::EmailNotConfigured => "Set the SMTP server, sender and recipients in the settings first".to_string(),
+            Self::InvalidEmailAddress { address } => format!("Invalid email address: {}", address),
+            Self::EmailFailed { detail } => format!("Sending the email failed: {}", detail),
+            Self::NotionNotConfigured => "Set the Notion token and database ID in the settings first".to_string(),
+            Self::NotionFailed { detail } => format!("Exporting to Notion failed: {}", detail),
+        }
+    }
+
+    fn message_ja(&self) -> String {
+        match self {
+            Self::ConfigNotFound => "設定が見つかりません".to_string(),
+            Self::ConfigDisabled => "この設定は無効になっています".to_string(),
+            Self::ConfigLoadFailed { detail } => format!("設定の読み込みに失敗しました: {}", detail),
+            Self::UnsupportedProvider { provider } => format!("サポートされていないプロバイダーです: {}", provider),
+            Self::EmptyImage => "画像データが空です".to_string(),
+            Self::UnsupportedImageFile { name } => format!("サポートされていない画像ファイルです: {}", name),
+            Self::ImageTooLarge { max_mb } => format!("画像が {}MB を超えています", max_mb),
+            Self::ImageProcessingFailed { detail } => format!("画像の処理に失敗しました: {}", detail),
+            Self::HttpClientFailed { detail } => format!("HTTP クライアントの作成に失敗しました: {}", detail),
+            Self::RequestTimeout => "リクエストがタイムアウトしました。ネットワーク接続を確認してください".to_string(),
+            Self::ConnectFailed => "接続に失敗しました。ネットワークまたは API URL を確認してください".to_string(),
+            Self::RequestFailed { detail } => format!("リクエストに失敗しました: {}", detail),
+            Self::InvalidApiKey => "API キーが無効です".to_string(),
+            Self::ApiKeyForbidden => "API キーの権限が不足しています".to_string(),
+            Self::EndpointNotFound => "API URL が間違っているか、モデルが存在しません".to_string(),
+            Self::RateLimited => "リクエストが多すぎるか、クォータを使い切りました".to_string(),
+            Self::ProviderMessage { message, .. } => message.clone(),
+            Self::ServerError { status, body } => format!("サーバーエラー ({}): {}", status, body),
+            Self::InvalidResponse { detail } => format!("レスポンスの解析に失敗しました: {}", detail),
+            Self::UnexpectedResponse => "レスポンスの形式が想定外です".to_string(),
+            Self::RecognitionCancelled => "認識はキャンセルされました".to_string(),
+            Self::RecognitionTaskFailed { detail } => format!("認識タスクが失敗しました: {}", detail),
+            Self::OrientationFailed => "向きの検出に失敗しました".to_string(),
+            Self::ExperimentNotFound => "実験が見つかりません".to_string(),
+            Self::ExperimentRunning => "実験は実行中です。処理中のリクエストが終わるまでお待ちください".to_string(),
+            Self::TemplateNotFound { id } => format!("テンプレートが見つかりません: {}", id),
+            Self::DuplicateTemplate { name, .. } => format!("同じ内容のテンプレート「{}」が既にあります", name),
+            Self::TooManyExamples { max } => format!("テンプレートの例は最大 {} 件です", max),
+            Self::NoImagesSelected => "画像を 1 枚以上選択してください".to_string(),
+            Self::InvalidRating => "評価は 1〜5 の範囲で指定してください".to_string(),
+            Self::InvalidVariantCount { min, max } => {
+                format!("プロンプトのバリエーションは {}〜{} 個で指定してください", min, max)
+            }
+            Self::AbTestNotFound => "A/B テストが見つかりません".to_string(),
+            Self::RedactionUnavailable => {
+                "この設定はマスキングが必要です。先に設定でローカルの認識設定を選択してください".to_string()
+            }
+            Self::RedactionConfigNotLocal { name } => {
+                format!("マスキング用の設定 {} はローカルまたはプライベートネットワークのアドレスではありません", name)
+            }
+            Self::RedactionFailed { detail } => format!("マスキングに失敗したため、画像は送信されませんでした: {}", detail),
+            Self::BackupFailed { detail } => format!("データベースのバックアップに失敗しました: {}", detail),
+            Self::InvalidBackup { detail } => format!("バックアップファイルが無効です: {}", detail),
+            Self::RestoreFailed { detail } => format!("データベースの復元に失敗しました: {}", detail),
+            Self::WorkspaceNotFound => "ワークスペースが見つかりません".to_string(),
+            Self::ProfileNotFound => "プロファイルが見つかりません".to_string(),
+            Self::ConfigOutsideProfile { name } => format!("設定 {} は現在のプロファイルに属していません", name),
+            Self::ProfileNotEmpty { count } => {
+                format!("プロファイルにはまだ {} 件の設定またはテンプレートがあります。先に削除または移動してください", count)
+            }
+            Self::AppLocked => "アプリはロックされています。パスコードを入力して解除してください".to_string(),
+            Self::WrongPasscode => "パスコードが違います".to_string(),
+            Self::PasscodeTooShort { min } => format!("パスコードは {} 文字以上にしてください", min),
+            Self::InvalidRecipe { detail } => format!("レシピが無効です: {}", detail),
+            Self::NoMatchingConfig { provider, model } => {
+                format!("{} / {} の有効な設定がありません", provider, model)
+            }
+            Self::NoTablesFound => "結果に表が見つかりません".to_string(),
+            Self::UnsupportedExportFormat { format } => format!("サポートされていないエクスポート形式です: {}", format),
+            Self::PresetNotFound => "プリセットが見つかりません".to_string(),
+            Self::UpdateFailed { detail } => format!("アップデートに失敗しました: {}", detail),
+            Self::NoPendingUpdate => "インストールできるアップデートがありません".to_string(),
+            Self::HistoryNotFound => "履歴が見つかりません".to_string(),
+            Self::NoSourceFile => "この履歴はローカルファイルから認識されていません".to_string(),
+            Self::NoStoredImage => "この履歴には元画像が保存されていません".to_string(),
+            Self::SourceFileMissing { path } => format!("元の画像が移動または削除されました: {}", path),
+            Self::SpeechUnavailable { detail } => format!("読み上げを利用できません: {}", detail),
+            Self::NotebookNotConfigured => "先に設定でノートファイルを選択してください".to_string(),
+            Self::LocalModelMissing { path } => format!("ローカル OCR モデルが不完全です。フォルダを確認してください: {}", path),
+            Self::LocalOcrFailed { detail } => format!("ローカル OCR に失敗しました: {}", detail),
+            Self::UnknownSetting { key } => format!("不明な設定項目です: {}", key),
+            Self::InvalidSetting { key, expected } => format!("設定項目 {} の値が無効です。有効な値: {}", key, expected),
+            Self::InvalidHistoryImport { detail } => format!("履歴のインポートファイルが無効です: {}", detail),
+            Self::InvalidRegex { detail } => format!("正規表現が無効です: {}", detail),
+            Self::SyncNotConfigured => "先に設定で同期先、認証情報、同期パスワードを入力してください".to_string(),
+            Self::SyncWrongPassphrase => "同期データを復号できません。同期パスワードを確認してください".to_string(),
+            Self::SyncInProgress => "同期中です。しばらくお待ちください".to_string(),
+            Self::SyncFailed { detail } => format!("同期に失敗しました: {}", detail),
+            Self::ShareNotConfigured => "先に設定で共有用の GitHub トークンまたはカスタム URL を入力してください".to_string(),
+            Self::ShareFailed { detail } => format!("共有に失敗しました: {}", detail),
+            Self::EmailNotConfigured => "先に設定で SMTP サーバー、送信者、宛先を入力してください".to_string(),
+            Self::InvalidEmailAddress { address } => format!("無効なメールアドレスです: {}", address),
+            Self::EmailFailed { detail } => format!("メールの送信に失敗しました: {}", detail),
+            Self::NotionNotConfigured => "先に設定で Notion トークンとデータベース ID を入力してください".to_string(),
+            Self::NotionFailed { detail } => format!("Notion へのエクスポートに失敗しました: {}", detail),
+        }
+    }
+}
+
+/// Lets `?` turn a code into the localized message in `Result<_, String>` code paths
+impl From<ErrorCode> for String {
+    fn from(code: ErrorCode) -> Self {
+        code.localized()
+    }
+}
+
+/// UI language from settings, defaulting to Chinese
+pub fn current_language() -> String {
+    settings::get_all_settings()
+        .map(|s| s.language)
+        .unwrap_or_else(|_| "zh-CN".to_string())
+}
+
+pub fn connection_succeeded(language: &str) -> &'static str {
+    match language {
+        "en-US" => "Connection successful",
+        "ja-JP" => "接続に成功しました",
+        _ => "连接成功",
+    }
+}
+
+pub fn provider_incident_notice(language: &str, description: &str) -> String {
+    match language {
+        "en-US" => format!("the provider reports an incident: {}", description),
+        "ja-JP" => format!("プロバイダーが障害を報告しています: {}", description),
+        _ => format!("供应商状态页报告故障: {}", description),
+    }
+}
+
+pub fn max_tokens_clamped_notice(language: &str, requested: i32, limit: i32) -> String {
+    match language {
+        "en-US" => format!("Max tokens lowered from {} to {}, the model's output limit", requested, limit),
+        "ja-JP" => format!("最大トークン数をモデルの出力上限 {} に下げました（指定値 {}）", limit, requested),
+        _ => format!("最大 Token 数 {} 超出模型输出上限，已调整为 {}", requested, limit),
+    }
+}
+
+pub fn glossary_hint(language: &str, terms: &str) -> String {
+    match language {
+        "en-US" => format!("Spell these terms exactly as given: {}", terms),
+        "ja-JP" => format!("次の用語は表記どおりに出力してください: {}", terms),
+        _ => format!("以下术语请按原样拼写: {}", terms),
+    }
+}
+
+/// Instruction to answer in `language`, a code such as `ja` or `zh-TW` or a
+/// language name. Written in English whatever the UI language, so every
+/// provider reads the same instruction.
+pub fn output_language_hint(language: &str) -> String {
+    let language = language.trim();
+    let (primary, region) = language.split_once(['-', '_']).unwrap_or((language, ""));
+    let name = match (primary.to_ascii_lowercase().as_str(), region.to_ascii_lowercase().as_str()) {
+        ("zh", "tw" | "hk" | "mo" | "hant") => "Traditional Chinese",
+        ("zh", _) => "Simplified Chinese",
+        ("en", _) => "English",
+        ("ja", _) => "Japanese",
+        ("ko", _) => "Korean",
+        ("fr", _) => "French",
+        ("de", _) => "German",
+        ("es", _) => "Spanish",
+        ("pt", _) => "Portuguese",
+        ("it", _) => "Italian",
+        ("ru", _) => "Russian",
+        _ => language,
+    };
+    format!("Respond in {}.", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_shape() {
+        let json = serde_json::to_value(ErrorCode::UnsupportedProvider { provider: "foo".to_string() }).unwrap();
+        assert_eq!(json, serde_json::json!({ "code": "unsupported_provider", "params": { "provider": "foo" } }));
+
+        let json = serde_json::to_value(ErrorCode::RequestTimeout).unwrap();
+        assert_eq!(json, serde_json::json!({ "code": "request_timeout" }));
+        assert_eq!(ErrorCode::RequestTimeout.code().as_deref(), Some("request_timeout"));
+    }
+
+    #[test]
+    fn test_catalog_languages() {
+        let code = ErrorCode::ConfigNotFound;
+        assert_eq!(code.message("zh-CN"), "配置不存在");
+        assert_eq!(code.message("en-US"), "Configuration not found");
+        assert_eq!(code.message("ja-JP"), "設定が見つかりません");
+        assert_eq!(code.message("fr-FR"), "配置不存在");
+    }
+
+    #[test]
+    fn test_output_language_hint() {
+        assert_eq!(output_language_hint("ja"), "Respond in Japanese.");
+        assert_eq!(output_language_hint("en-US"), "Respond in English.");
+        assert_eq!(output_language_hint("zh-CN"), "Respond in Simplified Chinese.");
+        assert_eq!(output_language_hint("zh_TW"), "Respond in Traditional Chinese.");
+        assert_eq!(output_language_hint(" Vietnamese "), "Respond in Vietnamese.");
+    }
+}
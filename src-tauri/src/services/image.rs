@@ -1,6 +1,13 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use image::{DynamicImage, ImageFormat, ImageReader};
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::imageops::FilterType;
+use image::{AnimationDecoder, DynamicImage, Frames, GrayImage, ImageFormat, ImageReader, RgbImage, RgbaImage};
+use serde::Serialize;
 use std::io::Cursor;
+use std::path::Path;
+use crate::db::settings;
+use super::layout::BoundingBox;
 
 #[allow(dead_code)]
 pub const SUPPORTED_FORMATS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif"];
@@ -30,7 +37,7 @@ pub fn process_image_for_api(
     if !auto_compress {
         return Ok(ProcessedImage {
             base64: input_base64.to_string(),
-            mime_type: "image/jpeg".to_string(),
+            mime_type: detect_mime_type(&image_data),
             original_size,
             compressed_size: None,
             was_compressed: false,
@@ -108,6 +115,149 @@ fn compress_image(img: &DynamicImage, max_size_bytes: usize) -> Result<(Vec<u8>,
     }
 }
 
+/// Re-encode an image a provider would reject: a format outside `mime_types`,
+/// a side longer than `max_dimension` or more than `max_bytes`. Re-encoded
+/// images are PNG or JPEG, shrinking further until they fit. `None` when the
+/// image is accepted as is.
+pub fn fit_image(
+    input_base64: &str,
+    mime_types: &[&str],
+    max_bytes: usize,
+    max_dimension: u32,
+) -> Result<Option<(String, String)>, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let format = image::guess_format(&image_data).map_err(|e| format!("Failed to read image: {}", e))?;
+    let (width, height) = ImageReader::with_format(Cursor::new(&image_data), format)
+        .into_dimensions()
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+
+    let fits = width.max(height) <= max_dimension && image_data.len() <= max_bytes;
+    if fits && mime_types.contains(&format.to_mime_type()) {
+        return Ok(None);
+    }
+
+    let mut img = decode_image(&image_data)?;
+    if width.max(height) > max_dimension {
+        img = img.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    }
+    loop {
+        let (buffer, mime_type) = compress_image(&img, max_bytes)?;
+        if buffer.len() <= max_bytes || img.width().max(img.height()) <= 64 {
+            return Ok(Some((BASE64.encode(&buffer), mime_type)));
+        }
+        img = img.resize(img.width() * 3 / 4, img.height() * 3 / 4, FilterType::Triangle);
+    }
+}
+
+/// Read an image file from disk as base64 plus its detected mime type.
+/// Animated GIF/WebP files are reduced to their representative frame.
+pub fn load_image_file(path: &Path) -> Result<(String, String), String> {
+    let data = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    select_frame(&BASE64.encode(&data), None)
+}
+
+/// One frame taken out of an animated GIF or WebP
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationFrame {
+    pub base64: String,
+    pub mime_type: String,
+    pub frame_index: u32,
+    /// `None` when decoding stopped at the requested frame
+    pub frame_count: Option<u32>,
+}
+
+/// Extract a frame from an animated GIF or WebP as PNG, or `None` for still images.
+/// Without an index the middle frame is used, since the first one is often a
+/// blank or fade-in; out-of-range indices are clamped to the last frame.
+/// Frames are decoded one at a time and only up to the one returned.
+pub fn extract_frame(input_base64: &str, frame_index: Option<u32>) -> Result<Option<AnimationFrame>, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let Some(mut frames) = animation_frames(&image_data)? else {
+        return Ok(None);
+    };
+
+    let (index, frame, frame_count) = match frame_index {
+        Some(wanted) => {
+            // Reaching a second frame tells animations from still images
+            let limit = wanted.saturating_add(1).max(2);
+            let mut chosen = None;
+            let mut decoded = 0;
+            for frame in frames.by_ref().take(limit as usize) {
+                let frame = frame.map_err(frame_error)?;
+                if decoded <= wanted {
+                    chosen = Some(frame.into_buffer());
+                }
+                decoded += 1;
+            }
+            let Some(frame) = chosen.filter(|_| decoded >= 2) else {
+                return Ok(None);
+            };
+            (wanted.min(decoded - 1), frame, (decoded < limit).then_some(decoded))
+        }
+        None => {
+            let frame_count = frames.try_fold(0u32, |count, frame| frame.map(|_| count + 1)).map_err(frame_error)?;
+            if frame_count < 2 {
+                return Ok(None);
+            }
+            let index = frame_count / 2;
+            let frame = animation_frames(&image_data)?
+                .and_then(|mut frames| frames.nth(index as usize))
+                .ok_or_else(|| "Animation frame missing".to_string())?
+                .map_err(frame_error)?;
+            (index, frame.into_buffer(), Some(frame_count))
+        }
+    };
+    let frame = DynamicImage::ImageRgba8(frame);
+
+    let mut buffer = Vec::new();
+    let mut cursor = Cursor::new(&mut buffer);
+    frame.write_to(&mut cursor, ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+
+    Ok(Some(AnimationFrame {
+        base64: BASE64.encode(&buffer),
+        mime_type: "image/png".to_string(),
+        frame_index: index,
+        frame_count,
+    }))
+}
+
+/// Lazy frames of an animated GIF or WebP, `None` for other images
+fn animation_frames(image_data: &[u8]) -> Result<Option<Frames<'_>>, String> {
+    let frames = match detect_mime_type(image_data).as_str() {
+        "image/gif" => GifDecoder::new(Cursor::new(image_data))
+            .map_err(|e| format!("Failed to read GIF: {}", e))?
+            .into_frames(),
+        "image/webp" => {
+            let decoder = WebPDecoder::new(Cursor::new(image_data))
+                .map_err(|e| format!("Failed to read WebP: {}", e))?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(frames))
+}
+
+fn frame_error(e: image::ImageError) -> String {
+    format!("Failed to decode animation frame: {}", e)
+}
+
+/// Replace an animated image by a single frame so providers receive a still image;
+/// still images are returned unchanged with their detected mime type
+pub fn select_frame(input_base64: &str, frame_index: Option<u32>) -> Result<(String, String), String> {
+    match extract_frame(input_base64, frame_index)? {
+        Some(frame) => Ok((frame.base64, frame.mime_type)),
+        None => {
+            let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+            Ok((input_base64.to_string(), detect_mime_type(&image_data)))
+        }
+    }
+}
+
 fn detect_mime_type(data: &[u8]) -> String {
     // Check magic bytes
     if data.len() >= 8 {
@@ -127,8 +277,274 @@ fn detect_mime_type(data: &[u8]) -> String {
     "image/jpeg".to_string()
 }
 
-/// Generate a thumbnail
-#[allow(dead_code)]
+/// Downscale an image to a small JPEG for cheap auxiliary requests
+pub fn downscale_image(input_base64: &str, max_dimension: u32) -> Result<String, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let img = decode_image(&image_data)?;
+    let small = img.thumbnail(max_dimension, max_dimension);
+
+    let mut buffer = Vec::new();
+    let mut cursor = Cursor::new(&mut buffer);
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, 80);
+    small.to_rgb8().write_with_encoder(encoder)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+
+    Ok(BASE64.encode(&buffer))
+}
+
+/// Rotate an image clockwise by 90/180/270 degrees, keeping PNG sources lossless
+pub fn rotate_image(input_base64: &str, degrees: u32) -> Result<(String, String), String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let img = decode_image(&image_data)?;
+
+    let rotated = match degrees {
+        90 => img.rotate90(),
+        180 => img.rotate180(),
+        270 => img.rotate270(),
+        _ => return Ok((input_base64.to_string(), detect_mime_type(&image_data))),
+    };
+
+    encode_like_source(&rotated, &image_data)
+}
+
+/// Encode as PNG when the source was PNG so screenshots stay lossless, JPEG otherwise
+fn encode_like_source(img: &DynamicImage, source: &[u8]) -> Result<(String, String), String> {
+    let mut buffer = Vec::new();
+    let mut cursor = Cursor::new(&mut buffer);
+    let mime_type = if detect_mime_type(source) == "image/png" {
+        img.write_to(&mut cursor, ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        "image/png"
+    } else {
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, 90);
+        img.to_rgb8().write_with_encoder(encoder)
+            .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+        "image/jpeg"
+    };
+
+    Ok((BASE64.encode(&buffer), mime_type.to_string()))
+}
+
+/// Largest difference per channel for a pixel to still match a margin's colour
+const CROP_TOLERANCE: u8 = 16;
+/// A margin line may have one pixel in this many off, for dust and JPEG noise
+const CROP_NOISE_DIVISOR: usize = 100;
+/// Pixels of margin kept around the content
+const CROP_PADDING: u32 = 4;
+/// Crops saving less than this share of the area aren't worth re-encoding
+const CROP_MIN_SAVING: f32 = 0.02;
+
+/// Trim uniform margins and letterboxing from the edges, keeping a few pixels
+/// around the content. `None` when there is little to trim or the image is blank.
+pub fn crop_to_content(input_base64: &str) -> Result<Option<(String, String)>, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let img = decode_image(&image_data)?;
+    let Some((x, y, width, height)) = content_bounds(&img.to_rgba8()) else {
+        return Ok(None);
+    };
+    let area = img.width() as f32 * img.height() as f32;
+    if (width as f32 * height as f32) > area * (1.0 - CROP_MIN_SAVING) {
+        return Ok(None);
+    }
+    encode_like_source(&img.crop_imm(x, y, width, height), &image_data).map(Some)
+}
+
+/// Left, top, width and height of what lies inside the margins, padded
+fn content_bounds(img: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = img.dimensions();
+    let row = |y: u32| (0..width).map(move |x| img.get_pixel(x, y).0);
+    let top = (0..height).take_while(|&y| is_margin(row(y))).count() as u32;
+    if top == height {
+        return None;
+    }
+    let bottom = height - (0..height).rev().take_while(|&y| is_margin(row(y))).count() as u32;
+
+    // Columns are only checked between the trimmed rows, letterbox bars would break them
+    let column = |x: u32| (top..bottom).map(move |y| img.get_pixel(x, y).0);
+    let left = (0..width).take_while(|&x| is_margin(column(x))).count() as u32;
+    let right = width - (0..width).rev().take_while(|&x| is_margin(column(x))).count() as u32;
+    if left >= right {
+        return None;
+    }
+
+    let (left, top) = (left.saturating_sub(CROP_PADDING), top.saturating_sub(CROP_PADDING));
+    let (right, bottom) = ((right + CROP_PADDING).min(width), (bottom + CROP_PADDING).min(height));
+    Some((left, top, right - left, bottom - top))
+}
+
+/// Whether a row or column is a single colour, give or take noise
+fn is_margin(mut pixels: impl Iterator<Item = [u8; 4]>) -> bool {
+    let Some(reference) = pixels.next() else {
+        return true;
+    };
+    let (mut count, mut off) = (1, 0);
+    for pixel in pixels {
+        count += 1;
+        if pixel.iter().zip(reference).any(|(a, b)| a.abs_diff(b) > CROP_TOLERANCE) {
+            off += 1;
+        }
+    }
+    off <= count / CROP_NOISE_DIVISOR
+}
+
+/// Margin added around masked regions, as a fraction of the image size, since
+/// model-reported boxes are approximate
+const MASK_MARGIN: f32 = 0.01;
+
+/// Paint black boxes over regions given as fractions of the image size. The
+/// result is always PNG so no compression artifacts leak the masked content.
+pub fn mask_regions(input_base64: &str, regions: &[BoundingBox]) -> Result<(String, String), String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let mut img = decode_image(&image_data)?.to_rgba8();
+    let (width, height) = (img.width() as f32, img.height() as f32);
+
+    for region in regions {
+        let left = ((region.x - MASK_MARGIN).max(0.0) * width) as u32;
+        let top = ((region.y - MASK_MARGIN).max(0.0) * height) as u32;
+        let right = (((region.x + region.width + MASK_MARGIN).min(1.0) * width).ceil() as u32).min(img.width());
+        let bottom = (((region.y + region.height + MASK_MARGIN).min(1.0) * height).ceil() as u32).min(img.height());
+        for y in top..bottom {
+            for x in left..right {
+                img.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+            }
+        }
+    }
+
+    let mut buffer = Vec::new();
+    DynamicImage::ImageRgba8(img)
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok((BASE64.encode(&buffer), "image/png".to_string()))
+}
+
+/// Handwriting scans smaller than this on their longest side are upscaled
+const HANDWRITING_MIN_DIMENSION: u32 = 2000;
+/// Upscaling is capped so small photos don't grow into huge uploads
+const HANDWRITING_MAX_SCALE: f32 = 2.0;
+/// Share of the darkest and brightest pixels clipped by the contrast stretch
+const STRETCH_CLIP: f32 = 0.01;
+/// Adaptive threshold window, as a fraction of the longest side
+const THRESHOLD_WINDOW: f32 = 1.0 / 32.0;
+/// How much darker than its surroundings a pixel must be to count as ink
+const THRESHOLD_OFFSET: i64 = 10;
+
+/// Stroke enhancement for handwritten notes: stretch the contrast, upscale
+/// small scans and binarize with a local mean threshold so faint pencil and
+/// uneven lighting end up as black strokes on white. The result is PNG.
+pub fn enhance_handwriting(input_base64: &str) -> Result<(String, String), String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let mut gray = decode_image(&image_data)?.to_luma8();
+
+    stretch_contrast(&mut gray);
+
+    let longest = gray.width().max(gray.height());
+    if longest < HANDWRITING_MIN_DIMENSION {
+        let scale = (HANDWRITING_MIN_DIMENSION as f32 / longest as f32).min(HANDWRITING_MAX_SCALE);
+        let width = (gray.width() as f32 * scale).round() as u32;
+        let height = (gray.height() as f32 * scale).round() as u32;
+        gray = image::imageops::resize(&gray, width, height, FilterType::CatmullRom);
+    }
+
+    adaptive_threshold(&mut gray);
+
+    let mut buffer = Vec::new();
+    DynamicImage::ImageLuma8(gray)
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok((BASE64.encode(&buffer), "image/png".to_string()))
+}
+
+/// Map the 1st..99th percentile of the brightness range onto 0..255
+fn stretch_contrast(gray: &mut GrayImage) {
+    let mut histogram = [0usize; 256];
+    for pixel in gray.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let clip = (gray.len() as f32 * STRETCH_CLIP) as usize;
+    let percentile = |mut levels: Box<dyn Iterator<Item = usize>>| {
+        let mut seen = 0;
+        levels.find(|&level| {
+            seen += histogram[level];
+            seen > clip
+        })
+    };
+    let (Some(low), Some(high)) = (percentile(Box::new(0..256)), percentile(Box::new((0..256).rev()))) else {
+        return;
+    };
+    if high <= low {
+        return;
+    }
+
+    let range = (high - low) as f32;
+    for pixel in gray.pixels_mut() {
+        let value = (pixel.0[0] as f32 - low as f32) / range * 255.0;
+        pixel.0[0] = value.clamp(0.0, 255.0).round() as u8;
+    }
+}
+
+/// Binarize each pixel against the mean of its neighbourhood, using an
+/// integral image so the cost doesn't depend on the window size
+fn adaptive_threshold(gray: &mut GrayImage) {
+    let (width, height) = (gray.width() as usize, gray.height() as usize);
+    let radius = ((width.max(height) as f32 * THRESHOLD_WINDOW) as usize / 2).max(1);
+
+    let mut integral = vec![0i64; (width + 1) * (height + 1)];
+    for y in 0..height {
+        let mut row_sum = 0i64;
+        for x in 0..width {
+            row_sum += gray.get_pixel(x as u32, y as u32).0[0] as i64;
+            integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row_sum;
+        }
+    }
+
+    for y in 0..height {
+        let (top, bottom) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        for x in 0..width {
+            let (left, right) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let sum = integral[bottom * (width + 1) + right] - integral[top * (width + 1) + right]
+                - integral[bottom * (width + 1) + left] + integral[top * (width + 1) + left];
+            let count = ((bottom - top) * (right - left)) as i64;
+            let pixel = gray.get_pixel_mut(x as u32, y as u32);
+            let ink = (pixel.0[0] as i64) * count < sum - THRESHOLD_OFFSET * count;
+            pixel.0[0] = if ink { 0 } else { 255 };
+        }
+    }
+}
+
+pub fn decode_image(image_data: &[u8]) -> Result<DynamicImage, String> {
+    ImageReader::new(Cursor::new(image_data))
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to read image: {}", e))?
+        .decode()
+        .map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+/// Background matching the UI theme, used behind transparent pixels.
+/// The OS theme isn't known here, so `system` falls back to light.
+fn theme_background(theme: &str) -> [u8; 3] {
+    match theme {
+        "dark" => [0x14, 0x14, 0x14],
+        _ => [0xff, 0xff, 0xff],
+    }
+}
+
+/// Alpha-blend an image onto a solid background
+fn flatten_onto(img: &DynamicImage, background: [u8; 3]) -> RgbImage {
+    let rgba = img.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let alpha = a as u32;
+        let blend = |fg: u8, bg: u8| ((fg as u32 * alpha + bg as u32 * (255 - alpha) + 127) / 255) as u8;
+        image::Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    })
+}
+
+/// Generate a thumbnail. Transparent images (e.g. screenshots) are composited
+/// onto the current theme's background instead of turning black.
 pub fn generate_thumbnail(input_base64: &str, width: u32, height: u32) -> Result<String, String> {
     let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
     
@@ -139,17 +555,81 @@ pub fn generate_thumbnail(input_base64: &str, width: u32, height: u32) -> Result
         .map_err(|e| format!("Failed to decode image: {}", e))?;
 
     let thumbnail = img.thumbnail(width, height);
+    let rgb = if thumbnail.color().has_alpha() {
+        let theme = settings::get_all_settings().map(|s| s.theme).unwrap_or_default();
+        flatten_onto(&thumbnail, theme_background(&theme))
+    } else {
+        thumbnail.to_rgb8()
+    };
     
     let mut buffer = Vec::new();
     let mut cursor = Cursor::new(&mut buffer);
     let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, 70);
-    thumbnail.to_rgb8().write_with_encoder(encoder)
+    rgb.write_with_encoder(encoder)
         .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
 
     Ok(format!("data:image/jpeg;base64,{}", BASE64.encode(&buffer)))
 }
 
-#[allow(dead_code)]
+/// Size of the grayscale image the DCT runs on
+const PHASH_SIZE: usize = 32;
+/// Low-frequency block kept from the DCT, giving a 64-bit hash
+const PHASH_BLOCK: usize = 8;
+
+/// 64-bit DCT perceptual hash. Re-encoded, resized or slightly edited copies of
+/// an image get hashes within a few bits of each other.
+pub fn perceptual_hash(input_base64: &str) -> Result<u64, String> {
+    let image_data = BASE64.decode(input_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let img = decode_image(&image_data)?;
+    Ok(perceptual_hash_of(&img))
+}
+
+pub fn perceptual_hash_of(img: &DynamicImage) -> u64 {
+    let gray = img
+        .resize_exact(PHASH_SIZE as u32, PHASH_SIZE as u32, FilterType::Triangle)
+        .to_luma8();
+    let pixels: Vec<f64> = gray.pixels().map(|p| p.0[0] as f64).collect();
+
+    // Separable 2D DCT-II, only computing the low-frequency block
+    let cosines: Vec<f64> = (0..PHASH_BLOCK * PHASH_SIZE)
+        .map(|i| {
+            let (u, x) = (i / PHASH_SIZE, i % PHASH_SIZE);
+            ((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI / (2 * PHASH_SIZE) as f64).cos()
+        })
+        .collect();
+    let mut rows = vec![0.0; PHASH_SIZE * PHASH_BLOCK];
+    for y in 0..PHASH_SIZE {
+        for u in 0..PHASH_BLOCK {
+            rows[y * PHASH_BLOCK + u] = (0..PHASH_SIZE)
+                .map(|x| pixels[y * PHASH_SIZE + x] * cosines[u * PHASH_SIZE + x])
+                .sum();
+        }
+    }
+    let mut block = [0.0; PHASH_BLOCK * PHASH_BLOCK];
+    for v in 0..PHASH_BLOCK {
+        for u in 0..PHASH_BLOCK {
+            block[v * PHASH_BLOCK + u] = (0..PHASH_SIZE)
+                .map(|y| rows[y * PHASH_BLOCK + u] * cosines[v * PHASH_SIZE + y])
+                .sum();
+        }
+    }
+
+    // Compare against the median, leaving out the DC term which only reflects brightness
+    let mut sorted = block[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    block
+        .iter()
+        .enumerate()
+        .filter(|(i, value)| *i > 0 && **value > median)
+        .fold(0u64, |hash, (i, _)| hash | (1 << i))
+}
+
+/// Number of differing bits between two perceptual hashes
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
 pub fn is_valid_format(filename: &str) -> bool {
     if let Some(ext) = filename.rsplit('.').next() {
         SUPPORTED_FORMATS.contains(&ext.to_lowercase().as_str())
@@ -157,3 +637,182 @@ pub fn is_valid_format(filename: &str) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgba};
+
+    #[test]
+    fn test_flatten_onto_theme_background() {
+        let mut img = RgbaImage::new(3, 1);
+        img.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        img.put_pixel(1, 0, Rgba([200, 10, 10, 255]));
+        img.put_pixel(2, 0, Rgba([0, 0, 0, 128]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let light = flatten_onto(&img, theme_background("light"));
+        assert_eq!(light.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(light.get_pixel(1, 0).0, [200, 10, 10]);
+        assert_eq!(light.get_pixel(2, 0).0, [127, 127, 127]);
+
+        let dark = flatten_onto(&img, theme_background("dark"));
+        assert_eq!(dark.get_pixel(0, 0).0, [0x14, 0x14, 0x14]);
+    }
+
+    #[test]
+    fn test_handwriting_strokes_survive_uneven_lighting() {
+        // A faint stroke on a background that darkens from left to right
+        let mut gray = GrayImage::from_fn(64, 64, |x, _| Luma([230 - x as u8 * 2]));
+        for y in 10..54 {
+            for x in [16, 48] {
+                let background = gray.get_pixel(x, y).0[0];
+                gray.put_pixel(x, y, Luma([background - 40]));
+            }
+        }
+
+        stretch_contrast(&mut gray);
+        adaptive_threshold(&mut gray);
+        assert_eq!(gray.get_pixel(16, 30).0[0], 0);
+        assert_eq!(gray.get_pixel(48, 30).0[0], 0);
+        // The dark right edge of the background is not taken for ink
+        assert_eq!(gray.get_pixel(60, 30).0[0], 255);
+        assert_eq!(gray.get_pixel(4, 30).0[0], 255);
+    }
+
+    fn encode_gif(colors: &[[u8; 4]]) -> String {
+        let mut buffer = Vec::new();
+        {
+            let mut encoder = image::codecs::gif::GifEncoder::new(&mut buffer);
+            let frames = colors
+                .iter()
+                .map(|color| image::Frame::new(RgbaImage::from_pixel(2, 2, Rgba(*color))));
+            encoder.encode_frames(frames).unwrap();
+        }
+        BASE64.encode(&buffer)
+    }
+
+    fn first_pixel(base64: &str) -> [u8; 4] {
+        decode_image(&BASE64.decode(base64).unwrap()).unwrap().to_rgba8().get_pixel(0, 0).0
+    }
+
+    #[test]
+    fn test_extract_frame_from_gif() {
+        let gif = encode_gif(&[[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]]);
+
+        let middle = extract_frame(&gif, None).unwrap().unwrap();
+        assert_eq!((middle.frame_index, middle.frame_count), (1, Some(3)));
+        assert_eq!(middle.mime_type, "image/png");
+        assert_eq!(first_pixel(&middle.base64), [0, 255, 0, 255]);
+
+        let last = extract_frame(&gif, Some(10)).unwrap().unwrap();
+        assert_eq!((last.frame_index, last.frame_count), (2, Some(3)));
+        assert_eq!(first_pixel(&last.base64), [0, 0, 255, 255]);
+
+        let first = extract_frame(&gif, Some(0)).unwrap().unwrap();
+        assert_eq!((first.frame_index, first.frame_count), (0, None));
+        assert_eq!(first_pixel(&first.base64), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_still_images_are_kept() {
+        let gif = encode_gif(&[[255, 0, 0, 255]]);
+        assert!(extract_frame(&gif, None).unwrap().is_none());
+        assert_eq!(select_frame(&gif, None).unwrap(), (gif, "image/gif".to_string()));
+    }
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            let v = ((x * 255 / width + y * 64 / height) % 256) as u8;
+            Rgba([v, v / 2, 255 - v, 255])
+        }))
+    }
+
+    #[test]
+    fn test_perceptual_hash_matches_resized_copy() {
+        let original = perceptual_hash_of(&gradient(200, 120));
+        let resized = perceptual_hash_of(&gradient(200, 120).resize_exact(80, 48, FilterType::Lanczos3));
+        assert!(hash_distance(original, resized) <= 4);
+
+        let flipped = perceptual_hash_of(&gradient(200, 120).fliph());
+        assert!(hash_distance(original, flipped) > 10);
+    }
+
+    #[test]
+    fn test_hash_distance() {
+        assert_eq!(hash_distance(0b1011, 0b0010), 2);
+        assert_eq!(hash_distance(u64::MAX, 0), 64);
+    }
+
+    #[test]
+    fn test_mask_regions_paints_boxes_with_margin() {
+        let mut buffer = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(100, 100, Rgba([255, 255, 255, 255])))
+            .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+            .unwrap();
+        let region = BoundingBox { x: 0.5, y: 0.5, width: 0.2, height: 0.1 };
+
+        let (masked, mime_type) = mask_regions(&BASE64.encode(&buffer), &[region]).unwrap();
+        let img = decode_image(&BASE64.decode(masked).unwrap()).unwrap().to_rgba8();
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(img.get_pixel(60, 55).0, [0, 0, 0, 255]);
+        assert_eq!(img.get_pixel(49, 49).0, [0, 0, 0, 255]);
+        assert_eq!(img.get_pixel(10, 10).0, [255, 255, 255, 255]);
+        assert_eq!(img.get_pixel(60, 70).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_crop_to_content_trims_letterbox_and_margins() {
+        // Black bars above and below, a white margin, and checkered content at (30, 30)-(60, 50)
+        let img = RgbaImage::from_fn(100, 80, |x, y| match (x, y) {
+            (_, 0..=9) | (_, 70..) => Rgba([0, 0, 0, 255]),
+            (30..=59, 30..=49) if (x + y) % 2 == 0 => Rgba([200, 0, 0, 255]),
+            (30..=59, 30..=49) => Rgba([0, 0, 200, 255]),
+            _ => Rgba([255, 255, 255, 255]),
+        });
+        let mut buffer = Vec::new();
+        DynamicImage::ImageRgba8(img).write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png).unwrap();
+
+        let (cropped, mime_type) = crop_to_content(&BASE64.encode(&buffer)).unwrap().unwrap();
+        let img = decode_image(&BASE64.decode(cropped).unwrap()).unwrap().to_rgba8();
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(img.dimensions(), (30 + 2 * CROP_PADDING, 20 + 2 * CROP_PADDING));
+        assert_eq!(img.get_pixel(CROP_PADDING, CROP_PADDING).0, [200, 0, 0, 255]);
+        assert_eq!(img.get_pixel(0, 0).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_crop_to_content_skips_full_and_blank_images() {
+        let encode = |img: RgbaImage| {
+            let mut buffer = Vec::new();
+            DynamicImage::ImageRgba8(img).write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png).unwrap();
+            BASE64.encode(&buffer)
+        };
+        let blank = encode(RgbaImage::from_pixel(50, 50, Rgba([255, 255, 255, 255])));
+        assert_eq!(crop_to_content(&blank), Ok(None));
+        let full = encode(RgbaImage::from_fn(50, 50, |x, y| Rgba([(x * 5) as u8, (y * 5) as u8, 0, 255])));
+        assert_eq!(crop_to_content(&full), Ok(None));
+    }
+
+    #[test]
+    fn test_fit_image_converts_unsupported_formats() {
+        let gif = encode_gif(&[[255, 0, 0, 255]]);
+        assert!(fit_image(&gif, &["image/png", "image/gif"], 1024, 100).unwrap().is_none());
+
+        let (png, mime_type) = fit_image(&gif, &["image/png", "image/jpeg"], 1024, 100).unwrap().unwrap();
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(detect_mime_type(&BASE64.decode(png).unwrap()), "image/png");
+    }
+
+    #[test]
+    fn test_fit_image_scales_down_large_images() {
+        let mut buffer = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(300, 150, Rgba([0, 128, 255, 255])))
+            .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+            .unwrap();
+
+        let (fitted, _) = fit_image(&BASE64.encode(&buffer), &["image/png"], 1024 * 1024, 100).unwrap().unwrap();
+        let img = decode_image(&BASE64.decode(fitted).unwrap()).unwrap();
+        assert_eq!((img.width(), img.height()), (100, 50));
+    }
+}
@@ -0,0 +1,87 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use once_cell::sync::OnceCell;
+use rand::Rng;
+use std::path::{Path, PathBuf};
+
+static IMAGES_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Create the directory holding the original images referenced by history
+pub fn init_image_store(app_data_dir: &Path) -> std::io::Result<()> {
+    let dir = app_data_dir.join("images");
+    std::fs::create_dir_all(&dir)?;
+    let _ = IMAGES_DIR.set(dir);
+    Ok(())
+}
+
+fn images_dir() -> Result<&'static PathBuf, String> {
+    IMAGES_DIR.get().ok_or_else(|| "图片存储未初始化".to_string())
+}
+
+/// Absolute path of a stored image file
+pub fn image_file_path(file_name: &str) -> Result<PathBuf, String> {
+    Ok(images_dir()?.join(file_name))
+}
+
+/// Write an image to the store and return its file name relative to the store
+pub fn save_image(image_base64: &str, mime_type: &str) -> Result<String, String> {
+    let data = BASE64.decode(image_base64).map_err(|e| format!("Invalid base64: {}", e))?;
+    let extension = match mime_type {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        _ => "jpg",
+    };
+    save_image_data(&data, extension)
+}
+
+/// Write raw image bytes to the store under a new name with the given extension
+pub fn save_image_data(data: &[u8], extension: &str) -> Result<String, String> {
+    let file_name = format!(
+        "{}_{:04x}.{}",
+        chrono::Local::now().format("%Y%m%d%H%M%S%3f"),
+        rand::thread_rng().gen::<u16>(),
+        extension
+    );
+    std::fs::write(images_dir()?.join(&file_name), data)
+        .map_err(|e| format!("保存图片失败: {}", e))?;
+
+    Ok(file_name)
+}
+
+pub fn load_image(file_name: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(image_file_path(file_name)?).map_err(|e| format!("读取图片失败: {}", e))
+}
+
+/// Remove a stored image, ignoring files that are already gone
+pub fn delete_image(file_name: &str) {
+    if let Ok(path) = image_file_path(file_name) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFileState {
+    Ok,
+    Missing,
+    Corrupted,
+}
+
+/// Check that a stored image exists and its header decodes
+pub fn check_image(file_name: &str) -> ImageFileState {
+    let Ok(path) = image_file_path(file_name) else {
+        return ImageFileState::Missing;
+    };
+    if !path.is_file() {
+        return ImageFileState::Missing;
+    }
+
+    let readable = image::ImageReader::open(&path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())
+        .and_then(|reader| reader.into_dimensions().map_err(|e| e.to_string()));
+
+    match readable {
+        Ok((width, height)) if width > 0 && height > 0 => ImageFileState::Ok,
+        _ => ImageFileState::Corrupted,
+    }
+}
@@ -0,0 +1,124 @@
+use ort::execution_providers::{
+    CPUExecutionProvider, CUDAExecutionProvider, CoreMLExecutionProvider, DirectMLExecutionProvider,
+    ExecutionProvider, ExecutionProviderDispatch,
+};
+use ort::session::Session;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Hardware a local model runs on. `Metal` goes through Core ML, which picks
+/// the GPU or Neural Engine on macOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    Cpu,
+    Cuda,
+    Directml,
+    Metal,
+}
+
+impl Device {
+    /// Registration errors out instead of silently running on the CPU, so
+    /// the device a session reports is the one it really uses
+    fn execution_provider(self) -> ExecutionProviderDispatch {
+        let provider = match self {
+            Self::Cpu => CPUExecutionProvider::default().build(),
+            Self::Cuda => CUDAExecutionProvider::default().build(),
+            Self::Directml => DirectMLExecutionProvider::default().build(),
+            Self::Metal => CoreMLExecutionProvider::default().build(),
+        };
+        provider.error_on_failure()
+    }
+
+    /// Whether the onnxruntime library we run with was built with the device's
+    /// backend. Its drivers and runtime libraries can still be missing.
+    fn is_built_in(self) -> bool {
+        let available = match self {
+            Self::Cpu => return true,
+            Self::Cuda => CUDAExecutionProvider::default().is_available(),
+            Self::Directml => DirectMLExecutionProvider::default().is_available(),
+            Self::Metal => CoreMLExecutionProvider::default().is_available(),
+        };
+        available.unwrap_or(false)
+    }
+}
+
+/// Devices local models can use here, and the one the loaded models run on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InferenceStatus {
+    /// Devices whose backend is part of the onnxruntime library, CPU last
+    pub available: Vec<Device>,
+    /// Device of the models loaded for the current setting; `None` until a
+    /// local recognition or connection test loaded them
+    pub in_use: Option<Device>,
+}
+
+pub fn available_devices() -> Vec<Device> {
+    [Device::Cuda, Device::Directml, Device::Metal, Device::Cpu]
+        .into_iter()
+        .filter(|device| device.is_built_in())
+        .collect()
+}
+
+/// Device and model time of one local recognition
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalInference {
+    pub device: Device,
+    /// Time spent in detection and recognition, without queueing and image decoding
+    pub inference_ms: i64,
+}
+
+/// Devices to try for an `inference_device` setting, best first. The CPU
+/// always comes last so a missing GPU driver doesn't break recognition.
+pub fn candidates(setting: &str) -> Vec<Device> {
+    let mut devices = match setting {
+        "cpu" => Vec::new(),
+        "cuda" => vec![Device::Cuda],
+        "directml" => vec![Device::Directml],
+        "metal" => vec![Device::Metal],
+        // auto: the GPU backends of the platform's onnxruntime build
+        _ if cfg!(target_os = "macos") => vec![Device::Metal],
+        _ if cfg!(windows) => vec![Device::Cuda, Device::Directml],
+        _ => vec![Device::Cuda],
+    };
+    devices.push(Device::Cpu);
+    devices
+}
+
+/// Open a model on the first of `devices` it can run on
+pub fn open_session(path: &Path, devices: &[Device]) -> Result<(Session, Device), ort::Error> {
+    let mut last_error = ort::Error::new("no device to run the model on");
+    for &device in devices {
+        if !device.is_built_in() {
+            eprintln!("[Inference] {:?} is not part of this onnxruntime build", device);
+            continue;
+        }
+        let session = Session::builder()
+            .and_then(|builder| builder.with_execution_providers([device.execution_provider()]))
+            .and_then(|builder| builder.commit_from_file(path));
+        match session {
+            Ok(session) => return Ok((session, device)),
+            Err(e) => {
+                eprintln!("[Inference] {:?} unavailable for {}: {}", device, path.display(), e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(candidates("cpu"), vec![Device::Cpu]);
+        assert_eq!(candidates("cuda"), vec![Device::Cuda, Device::Cpu]);
+        assert_eq!(candidates("metal"), vec![Device::Metal, Device::Cpu]);
+        assert_eq!(candidates("auto").last(), Some(&Device::Cpu));
+        assert!(candidates("auto").len() > 1);
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Appended to the prompt when layout output is requested
+const LAYOUT_INSTRUCTION: &str = "Return the recognized text as JSON only, without any other text, \
+in this format: {\"blocks\": [{\"bbox\": [x, y, w, h], \"lines\": [{\"text\": \"...\", \
+\"bbox\": [x, y, w, h], \"words\": [{\"text\": \"...\", \"bbox\": [x, y, w, h]}]}]}]}. \
+Blocks are paragraphs or table cells in reading order. Each bbox is the approximate \
+left, top, width and height as fractions (0 to 1) of the image size.";
+
+/// Approximate position of a piece of text, as fractions of the image size
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutWord {
+    pub text: String,
+    pub bbox: Option<BoundingBox>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutLine {
+    pub text: String,
+    pub bbox: Option<BoundingBox>,
+    pub words: Vec<LayoutWord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutBlock {
+    pub text: String,
+    pub bbox: Option<BoundingBox>,
+    pub lines: Vec<LayoutLine>,
+}
+
+/// Recognized text with approximate positions, for overlaying on the image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutResult {
+    pub blocks: Vec<LayoutBlock>,
+}
+
+impl LayoutResult {
+    /// Plain text with blocks separated by blank lines
+    pub fn text(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| block.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// JSON in the format asked of models, which `parse_layout` reads back
+    pub fn to_json(&self) -> String {
+        let bbox = |bbox: &Option<BoundingBox>| bbox.map(|b| json!([b.x, b.y, b.width, b.height]));
+        json!({
+            "blocks": self.blocks.iter().map(|block| json!({
+                "bbox": bbox(&block.bbox),
+                "lines": block.lines.iter().map(|line| json!({
+                    "text": line.text,
+                    "bbox": bbox(&line.bbox),
+                    "words": line.words.iter().map(|word| json!({
+                        "text": word.text,
+                        "bbox": bbox(&word.bbox),
+                    })).collect::<Vec<_>>(),
+                })).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+}
+
+pub fn layout_prompt(prompt: &str) -> String {
+    format!("{}\n\n{}", prompt.trim_end(), LAYOUT_INSTRUCTION)
+}
+
+/// Parse the model's JSON layout, tolerating markdown code fences and missing
+/// boxes. Returns None when the response holds no usable layout.
+pub fn parse_layout(content: &str) -> Option<LayoutResult> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    let data: Value = serde_json::from_str(content.get(start..=end)?).ok()?;
+
+    let blocks: Vec<LayoutBlock> = data["blocks"]
+        .as_array()?
+        .iter()
+        .map(parse_block)
+        .filter(|block| !block.text.is_empty())
+        .collect();
+
+    if blocks.is_empty() {
+        return None;
+    }
+    Some(LayoutResult { blocks })
+}
+
+fn parse_block(block: &Value) -> LayoutBlock {
+    let lines: Vec<LayoutLine> = block["lines"]
+        .as_array()
+        .map(|lines| lines.iter().map(parse_line).collect())
+        .unwrap_or_default();
+
+    let text = match block["text"].as_str() {
+        Some(text) => text.to_string(),
+        None => lines.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n"),
+    };
+
+    LayoutBlock {
+        text,
+        bbox: parse_bbox(&block["bbox"]),
+        lines,
+    }
+}
+
+fn parse_line(line: &Value) -> LayoutLine {
+    let words: Vec<LayoutWord> = line["words"]
+        .as_array()
+        .map(|words| {
+            words
+                .iter()
+                .filter_map(|word| {
+                    Some(LayoutWord {
+                        text: word["text"].as_str()?.to_string(),
+                        bbox: parse_bbox(&word["bbox"]),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let text = match line["text"].as_str() {
+        Some(text) => text.to_string(),
+        None => words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" "),
+    };
+
+    LayoutLine {
+        text,
+        bbox: parse_bbox(&line["bbox"]),
+        words,
+    }
+}
+
+/// Accept `[x, y, w, h]` fractions; boxes outside the image are clamped
+fn parse_bbox(value: &Value) -> Option<BoundingBox> {
+    let coords: Vec<f32> = value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect::<Option<_>>()?;
+    let [x, y, width, height] = coords[..] else {
+        return None;
+    };
+
+    let x = x.clamp(0.0, 1.0);
+    let y = y.clamp(0.0, 1.0);
+    Some(BoundingBox {
+        x,
+        y,
+        width: width.clamp(0.0, 1.0 - x),
+        height: height.clamp(0.0, 1.0 - y),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_layout() {
+        let content = "```json\n{\"blocks\": [{\"bbox\": [0.1, 0.1, 0.5, 0.2], \"lines\": [\
+            {\"text\": \"Hello world\", \"bbox\": [0.1, 0.1, 0.5, 0.1], \"words\": [\
+            {\"text\": \"Hello\", \"bbox\": [0.1, 0.1, 0.2, 0.1]}, {\"text\": \"world\"}]}, \
+            {\"words\": [{\"text\": \"Second\"}, {\"text\": \"line\"}]}]}, {\"lines\": []}]}\n```";
+
+        let layout = parse_layout(content).unwrap();
+        assert_eq!(layout.blocks.len(), 1);
+        assert_eq!(layout.text(), "Hello world\nSecond line");
+        assert_eq!(layout.blocks[0].lines[0].words[1].bbox, None);
+        assert_eq!(
+            layout.blocks[0].bbox,
+            Some(BoundingBox { x: 0.1, y: 0.1, width: 0.5, height: 0.2 })
+        );
+    }
+
+    #[test]
+    fn test_to_json_round_trip() {
+        let layout = parse_layout(
+            "{\"blocks\": [{\"bbox\": [0.5, 0.25, 0.5, 0.5], \"lines\": [{\"text\": \"Hi there\", \
+             \"words\": [{\"text\": \"Hi\", \"bbox\": [0.5, 0.25, 0.25, 0.25]}, {\"text\": \"there\"}]}]}]}",
+        )
+        .unwrap();
+        assert_eq!(parse_layout(&layout.to_json()), Some(layout));
+    }
+
+    #[test]
+    fn test_parse_layout_rejects_plain_text() {
+        assert_eq!(parse_layout("Just some text"), None);
+        assert_eq!(parse_layout("{\"blocks\": []}"), None);
+    }
+
+    #[test]
+    fn test_bbox_is_clamped() {
+        let bbox = parse_bbox(&serde_json::json!([0.75, -0.1, 0.5, 0.25])).unwrap();
+        assert_eq!(bbox, BoundingBox { x: 0.75, y: 0.0, width: 0.25, height: 0.25 });
+        assert_eq!(parse_bbox(&serde_json::json!([0.1, 0.2])), None);
+    }
+}
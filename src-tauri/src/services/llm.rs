@@ -1,21 +1,123 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
 use crate::db::model_config::{get_config_by_id, ModelConfig};
 use crate::db::history::{create_history_record, HistoryInput};
-use super::openai;
-use super::anthropic;
+use crate::db::{extraction as db_extraction, glossary as db_glossary, postprocess_rule, profile, settings, template_example, usage_ledger};
+use super::budget::{self, BudgetStatus};
+use super::provider::{self, ImagePart, TokenUsage};
+use super::provider_status::{self, ProviderIncident};
+use super::capabilities::{get_capabilities, max_output_tokens};
+use super::image::{crop_to_content, enhance_handwriting, fit_image, generate_thumbnail, perceptual_hash};
+use super::i18n::{self, ErrorCode};
+use super::image_store;
+use super::inference::LocalInference;
+use super::orientation;
+use super::progress::{self, Stage};
+use super::rapidocr;
+use super::redaction;
+use super::text_stats::{self, LanguageSegment};
+use super::layout::{self, LayoutResult};
+use super::math::{self, FormulaCheck};
+use super::barcode::{self, DecodedCode};
+use super::confidence::{self, Confidence};
+use super::extraction::{self, Extraction, ExtractionKind};
+use super::glossary::{self, GlossaryCorrection};
+use super::notion;
+use super::postprocess;
+use super::workspace::{apply_glossary, write_to_sink, PipelineContext};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct RecognitionResult {
     pub success: bool,
     pub content: Option<String>,
+    /// Reasoning a model returned apart from its answer (Anthropic thinking
+    /// blocks, `reasoning_content` of OpenAI-compatible gateways)
+    pub reasoning: Option<String>,
     pub error: Option<String>,
+    /// Machine-readable form of `error`, when it came from a known failure
+    pub error_code: Option<ErrorCode>,
+    /// Incident reported by the provider's status page after repeated failures
+    pub provider_incident: Option<ProviderIncident>,
+    /// Per-paragraph language tags, when requested
+    pub segments: Option<Vec<LanguageSegment>>,
+    /// Text blocks with approximate bounding boxes, when requested
+    pub layout: Option<LayoutResult>,
+    /// LaTeX validation of the formulas in the result, when requested
+    pub formulas: Option<Vec<FormulaCheck>>,
+    /// Typed fields of a structured extraction, when requested and the response was JSON
+    pub extraction: Option<Extraction>,
+    /// QR codes and barcodes read locally, when requested
+    pub codes: Option<Vec<DecodedCode>>,
+    /// Device and model time, when a local engine recognized the image
+    pub inference: Option<LocalInference>,
+    /// Estimated transcription confidence, when requested
+    pub confidence: Option<Confidence>,
+    /// Total of input and output tokens
     pub tokens_used: Option<i32>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
     pub duration_ms: Option<i64>,
+    /// Time from sending the request to the first streamed token
+    pub ttft_ms: Option<i64>,
+    /// Output tokens per second while the answer streamed
+    pub tokens_per_second: Option<f64>,
     pub processed_image: Option<String>,
+    /// Content was cut short by cancellation
+    pub partial: bool,
+    /// Set once the config's estimated monthly spend reaches 80% of its budget
+    pub budget_alert: Option<BudgetStatus>,
+    /// Localized notes on request settings that were adjusted before sending
+    pub warnings: Vec<String>,
+    /// Glossary terms replaced with their preferred spelling
+    pub glossary_corrections: Vec<GlossaryCorrection>,
+    /// Page to add to Notion once the result is returned, when requested;
+    /// the caller runs the export in the background
+    #[serde(skip)]
+    pub notion_page: Option<notion::PendingPage>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl RecognitionResult {
+    pub fn failure(error: impl Into<String>, duration_ms: Option<i64>) -> Self {
+        Self {
+            success: false,
+            error: Some(error.into()),
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Failure carrying both the code and its localized message
+    pub fn from_code(code: ErrorCode, duration_ms: Option<i64>) -> Self {
+        Self {
+            success: false,
+            error: Some(code.localized()),
+            error_code: Some(code),
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Count the tokens of a follow-up request made for this result
+    pub fn add_usage(&mut self, usage: TokenUsage) {
+        let add = |a: Option<i32>, b: Option<i32>| match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+        self.tokens_used = add(self.tokens_used, usage.total());
+        self.input_tokens = add(self.input_tokens, usage.input_tokens);
+        self.output_tokens = add(self.output_tokens, usage.output_tokens);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct RecognitionOptions {
     pub temperature: Option<f32>,
@@ -23,6 +125,124 @@ pub struct RecognitionOptions {
     pub max_tokens: Option<i32>,
     pub stream: Option<bool>,
     pub custom_params: Option<serde_json::Value>,
+    /// Probe the orientation first and rotate sideways/upside-down images
+    pub auto_rotate: Option<bool>,
+    /// Trim uniform margins and letterboxing before the image is sent
+    pub auto_crop: Option<bool>,
+    /// Return per-paragraph language tags with the result
+    pub segment_languages: Option<bool>,
+    /// Ask the model for a JSON layout with bounding boxes
+    pub layout: Option<bool>,
+    /// Validate returned LaTeX formulas
+    pub validate_math: Option<bool>,
+    /// Also convert valid formulas to MathML (implies `validate_math`)
+    pub mathml: Option<bool>,
+    /// Frame to recognize in an animated GIF/WebP, the middle frame by default
+    pub frame_index: Option<u32>,
+    /// Preprocessing pipeline applied before the image is sent
+    pub preprocess: Option<Preprocess>,
+    /// Extract structured fields with a built-in schema
+    pub extract: Option<ExtractionKind>,
+    /// Ask OpenAI-compatible providers for native JSON output (`response_format`)
+    pub json_mode: Option<bool>,
+    /// Also read QR codes and barcodes with the local decoder
+    pub decode_codes: Option<bool>,
+    /// Image resolution asked of OpenAI-compatible providers, the config default if unset
+    pub image_detail: Option<ImageDetail>,
+    /// Language the answer is asked in, e.g. `ja`; empty leaves it to the prompt
+    pub output_language: Option<String>,
+    /// Score the transcription's confidence, from token logprobs where the
+    /// provider returns them, otherwise with a second self-rating request
+    pub estimate_confidence: Option<bool>,
+    /// Batch the recognition belongs to; its history record waits in the review queue
+    pub batch_id: Option<String>,
+    /// History record this recognition re-runs, linked from the new record
+    pub rerun_of: Option<i64>,
+    /// Absolute path of the image file when it was opened from disk, kept in history
+    pub source_path: Option<String>,
+    /// Token from `select_save_path` of the file the streamed answer is written
+    /// to as it arrives, so a crash or cancellation keeps what was received;
+    /// replaced by the final answer
+    pub stream_to_file: Option<String>,
+    /// Add the result as a page of the Notion database from settings
+    pub export_to_notion: Option<bool>,
+    /// Few-shot examples of the template, loaded by the backend
+    #[serde(skip)]
+    pub examples: Vec<FewShotExample>,
+    /// Template the examples belong to, stored with the history record
+    #[serde(skip)]
+    pub template_id: Option<i64>,
+    /// Tags of the workspace, stored with the history record
+    #[serde(skip)]
+    pub tags: Vec<String>,
+}
+
+/// An example image with the answer expected for it, sent before the real
+/// image as an earlier user/assistant exchange
+#[derive(Debug, Clone)]
+pub struct FewShotExample {
+    pub base64: String,
+    pub mime_type: String,
+    pub output: String,
+}
+
+/// Examples stored for a template; ones whose image can't be read are skipped
+pub fn load_examples(template_id: i64) -> Vec<FewShotExample> {
+    let examples = template_example::get_examples(template_id).unwrap_or_else(|e| {
+        eprintln!("[Recognition] Failed to load template examples: {}", e);
+        Vec::new()
+    });
+    examples
+        .into_iter()
+        .filter_map(|example| match image_store::load_image(&example.image_file) {
+            Ok(data) => Some(FewShotExample {
+                base64: BASE64.encode(data),
+                mime_type: example.mime_type,
+                output: example.output,
+            }),
+            Err(e) => {
+                eprintln!("[Recognition] Skipping template example {}: {}", example.id, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Preset image preprocessing pipelines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preprocess {
+    /// Contrast stretch, upscale and adaptive threshold for handwritten notes
+    Handwriting,
+}
+
+/// OpenAI `detail` of an image: low is a fixed small tile and much cheaper,
+/// high keeps dense tables legible
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Low,
+    High,
+    Auto,
+}
+
+impl ImageDetail {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::High => "high",
+            Self::Auto => "auto",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(Self::Low),
+            "high" => Some(Self::High),
+            "auto" => Some(Self::Auto),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +251,11 @@ pub struct AdapterConfig {
     pub api_key: String,
     pub model_name: String,
     pub max_tokens: i32,
+    pub reasoning_model: bool,
+    pub thinking_budget_tokens: Option<i32>,
+    pub organization: Option<String>,
+    pub project: Option<String>,
+    pub skip_tls_verify: bool,
 }
 
 impl From<&ModelConfig> for AdapterConfig {
@@ -40,10 +265,17 @@ impl From<&ModelConfig> for AdapterConfig {
             api_key: config.api_key.clone(),
             model_name: config.model_name.clone(),
             max_tokens: config.max_tokens,
+            reasoning_model: config.reasoning_model,
+            thinking_budget_tokens: config.thinking_budget_tokens,
+            organization: config.organization.clone(),
+            project: config.project.clone(),
+            skip_tls_verify: config.skip_tls_verify,
         }
     }
 }
 
+pub const THUMBNAIL_SIZE: u32 = 320;
+
 pub async fn recognize(
     config_id: i64,
     image_base64: &str,
@@ -52,123 +284,814 @@ pub async fn recognize(
     options: Option<RecognitionOptions>,
     callback: Option<Box<dyn Fn(String) + Send + Sync>>,
 ) -> RecognitionResult {
-    let config = match get_config_by_id(config_id) {
-        Ok(Some(c)) => c,
-        Ok(None) => {
-            return RecognitionResult {
-                success: false,
-                content: None,
-                error: Some("配置不存在".to_string()),
-                tokens_used: None,
-                duration_ms: None,
-                processed_image: None,
-            };
+    let context = PipelineContext::from_active_workspace();
+    recognize_in(&context, config_id, image_base64, image_mime_type, prompt, options, callback).await
+}
+
+/// Recognize with an explicit glossary and output folder instead of the active workspace's
+pub async fn recognize_in(
+    context: &PipelineContext,
+    config_id: i64,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: Option<RecognitionOptions>,
+    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
+) -> RecognitionResult {
+    let config = match load_active_config(config_id) {
+        Ok(c) => c,
+        Err(code) => return RecognitionResult::from_code(code, None),
+    };
+
+    let mut options = options.unwrap_or_default();
+
+    let (rotated, probe_usage) = if options.auto_rotate.unwrap_or(false) {
+        let (rotated, usage) = orientation::auto_rotate(&config, image_base64).await;
+        let rotated = rotated.unwrap_or_else(|e| {
+            eprintln!("[Recognition] Orientation probe failed: {}", e);
+            None
+        });
+        (rotated, usage)
+    } else {
+        (None, TokenUsage::default())
+    };
+    let (image_base64, image_mime_type) = match &rotated {
+        Some((base64, mime_type)) => (base64.as_str(), mime_type.as_str()),
+        None => (image_base64, image_mime_type),
+    };
+
+    let cropped = if options.auto_crop.unwrap_or(false) {
+        crop_to_content(image_base64).unwrap_or_else(|e| {
+            eprintln!("[Recognition] Auto-crop failed: {}", e);
+            None
+        })
+    } else {
+        None
+    };
+    let (image_base64, image_mime_type) = match &cropped {
+        Some((base64, mime_type)) => (base64.as_str(), mime_type.as_str()),
+        None => (image_base64, image_mime_type),
+    };
+
+    // Decoded next to the provider call, codes are read from the unprocessed image
+    let codes_task = options.decode_codes.unwrap_or(false).then(|| {
+        let image_base64 = image_base64.to_string();
+        tokio::task::spawn_blocking(move || barcode::decode_codes(&image_base64))
+    });
+
+    let preprocessed = match options.preprocess {
+        Some(Preprocess::Handwriting) => match enhance_handwriting(image_base64) {
+            Ok(enhanced) => Some(enhanced),
+            Err(detail) => return RecognitionResult::from_code(ErrorCode::ImageProcessingFailed { detail }, None),
+        },
+        None => None,
+    };
+    let (image_base64, image_mime_type) = match &preprocessed {
+        Some((base64, mime_type)) => (base64.as_str(), mime_type.as_str()),
+        None => (image_base64, image_mime_type),
+    };
+
+    let mut provider_prompt = apply_glossary(prompt, &context.glossary, &i18n::current_language());
+    let want_layout = options.layout.unwrap_or(false);
+    if want_layout {
+        provider_prompt = layout::layout_prompt(&provider_prompt);
+    }
+    if let Some(kind) = options.extract {
+        provider_prompt = extraction::extraction_prompt(&provider_prompt, kind);
+        if get_capabilities(&config.provider).json_mode {
+            options.json_mode = options.json_mode.or(Some(true));
         }
+    }
+
+    provider_prompt = apply_output_language(&provider_prompt, &options);
+
+    // Parameter snapshot stored with the history record
+    options.tags = context.tags.clone();
+    let history_options = options.clone();
+
+    let segment_languages = options.segment_languages.unwrap_or(false);
+    let mathml = options.mathml.unwrap_or(false);
+    let validate_math = mathml || options.validate_math.unwrap_or(false);
+    let extract = options.extract;
+    let estimate_confidence = options.estimate_confidence.unwrap_or(false);
+    let export_to_notion = options.export_to_notion.unwrap_or(false);
+    let mut result = call_provider(&config, image_base64, image_mime_type, &provider_prompt, Some(options), callback).await;
+    // The orientation probe is billed like the recognition itself
+    result.add_usage(probe_usage);
+    if estimate_confidence && result.success {
+        estimate_result_confidence(&config, image_base64, image_mime_type, &mut result).await;
+    }
+    // A redacted image is what the provider saw, keep that one
+    if (rotated.is_some() || cropped.is_some() || preprocessed.is_some()) && result.processed_image.is_none() {
+        result.processed_image = Some(image_base64.to_string());
+    }
+    if want_layout {
+        // Keep the raw response as content when the model did not return a usable layout
+        result.layout = result.content.as_deref().and_then(layout::parse_layout);
+        if let Some(ref parsed) = result.layout {
+            result.content = Some(parsed.text());
+        }
+    }
+    if result.success {
+        apply_glossary_corrections(&mut result);
+        apply_postprocess_rules(&mut result);
+    }
+    if segment_languages {
+        result.segments = result.content.as_deref().map(text_stats::segment_by_language);
+    }
+    if validate_math {
+        result.formulas = result.content.as_deref().map(|content| math::check_formulas(content, mathml));
+    }
+    if let Some(kind) = extract {
+        result.extraction = result.content.as_deref().and_then(|content| extraction::parse_extraction(content, kind));
+    }
+    if let Some(task) = codes_task {
+        result.codes = match task.await {
+            Ok(Ok(codes)) => Some(codes),
+            Ok(Err(e)) => {
+                eprintln!("[Recognition] Failed to decode codes: {}", e);
+                None
+            }
+            Err(e) => {
+                eprintln!("[Recognition] Code decoding panicked: {}", e);
+                None
+            }
+        };
+    }
+
+    // Save to history; failures only when enabled in settings
+    let save_failure = !result.success
+        && settings::get_all_settings().map(|s| s.save_failed_recognitions).unwrap_or(true);
+    if result.success || save_failure {
+        progress::stage(Stage::Saving);
+        let status = if result.success { "success" } else { "failed" };
+        let history_id = save_history(&config, image_base64, image_mime_type, prompt, &history_options, &result, status);
+        if let (Some(history_id), Some(extraction)) = (history_id, &result.extraction) {
+            if let Err(e) = db_extraction::save_extraction(history_id, extraction) {
+                eprintln!("[Recognition] Failed to save extraction: {}", e);
+            }
+        }
+    }
+
+    if result.success {
+        result.budget_alert = budget::check_after(&config, &result);
+        if let Some(ref output_dir) = context.output_dir {
+            let content = result.content.as_deref().unwrap_or_default();
+            if let Err(e) = write_to_sink(output_dir, content, context.export_format.as_deref(), &context.tags) {
+                eprintln!("[Recognition] Failed to write workspace output: {}", e);
+            }
+        }
+        if export_to_notion {
+            let title = notion::page_title(
+                history_options.source_path.as_deref(),
+                &chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            );
+            let content = result.content.clone().unwrap_or_default();
+            result.notion_page = Some(notion::PendingPage { title, content });
+        }
+    }
+
+    result
+}
+
+/// Fix misrecognized domain terms with the glossary table before the result is stored
+fn apply_glossary_corrections(result: &mut RecognitionResult) {
+    let entries = match db_glossary::get_entries() {
+        Ok(entries) if !entries.is_empty() => entries,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("[Recognition] Failed to load glossary: {}", e);
+            return;
+        }
+    };
+    if let Some(content) = result.content.as_deref() {
+        let (corrected, corrections) = glossary::correct(content, &entries);
+        result.content = Some(corrected);
+        result.glossary_corrections = corrections;
+    }
+}
+
+/// Run the enabled regex rules over the result before it is stored
+fn apply_postprocess_rules(result: &mut RecognitionResult) {
+    let rules = match postprocess_rule::get_enabled_rules() {
+        Ok(rules) if !rules.is_empty() => rules,
+        Ok(_) => return,
         Err(e) => {
-            return RecognitionResult {
-                success: false,
-                content: None,
-                error: Some(format!("获取配置失败: {}", e)),
-                tokens_used: None,
-                duration_ms: None,
-                processed_image: None,
-            };
+            eprintln!("[Recognition] Failed to load post-processing rules: {}", e);
+            return;
+        }
+    };
+    result.content = result.content.as_deref().map(|content| postprocess::apply(content, &rules));
+}
+
+/// Ask `prompt` about two images in one request, e.g. what changed between two
+/// screenshots. The analysis is saved as one history record that shows the
+/// first image and carries a thumbnail of the second.
+pub async fn compare_images(
+    config_id: i64,
+    first: ImagePart<'_>,
+    second: ImagePart<'_>,
+    prompt: &str,
+    options: Option<RecognitionOptions>,
+) -> RecognitionResult {
+    let config = match load_active_config(config_id) {
+        Ok(c) => c,
+        Err(code) => return RecognitionResult::from_code(code, None),
+    };
+
+    // Configs that opted in only ever receive masked images
+    let mut redacted = Vec::with_capacity(2);
+    for image in [first, second] {
+        match redaction::redact_for(&config, image.base64, image.mime_type).await {
+            Ok(masked) => redacted.push(masked),
+            Err(code) => return RecognitionResult::from_code(code, None),
+        }
+    }
+    let images: Vec<ImagePart> = [first, second]
+        .iter()
+        .zip(&redacted)
+        .map(|(image, masked)| match masked {
+            Some((base64, mime_type)) => ImagePart { base64, mime_type },
+            None => *image,
+        })
+        .collect();
+
+    let options = options.unwrap_or_default();
+    let history_options = options.clone();
+    let provider_prompt = apply_output_language(prompt, &options);
+    let mut result = send_images(&config, &images, &provider_prompt, Some(options), None, &|_| {}).await;
+
+    let save_failure = !result.success
+        && settings::get_all_settings().map(|s| s.save_failed_recognitions).unwrap_or(true);
+    if result.success || save_failure {
+        let status = if result.success { "success" } else { "failed" };
+        let mut input =
+            history_input(&config, first.base64, first.mime_type, prompt, &history_options, &result, status);
+        input.compare_thumbnail = generate_thumbnail(second.base64, THUMBNAIL_SIZE, THUMBNAIL_SIZE).ok();
+        if let Err(e) = create_history_record(input) {
+            eprintln!("[Recognition] Failed to save history: {}", e);
+        }
+    }
+    if result.success {
+        result.budget_alert = budget::check_after(&config, &result);
+    }
+    result
+}
+
+/// Fill in `result.confidence` with a self-rating when the answer carried no
+/// token probabilities, and flag it for review below the configured threshold.
+/// Local engines can't rate themselves and are left unscored.
+async fn estimate_result_confidence(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    result: &mut RecognitionResult,
+) {
+    if result.confidence.is_none() && provider::provider_for(&config.provider).is_some() {
+        let content = result.content.as_deref().unwrap_or_default();
+        let (rating, usage) = confidence::rate(config, image_base64, image_mime_type, content).await;
+        // The rating request is billed like the recognition itself
+        result.add_usage(usage);
+        result.confidence = rating
+            .map_err(|e| eprintln!("[Recognition] Confidence rating failed: {}", e))
+            .ok();
+    }
+    if let Some(ref mut confidence) = result.confidence {
+        let threshold = settings::get_all_settings()
+            .map(|s| s.low_confidence_threshold)
+            .unwrap_or(settings::AppSettings::default_settings().low_confidence_threshold);
+        confidence.needs_review = confidence.score < threshold as f64;
+    }
+}
+
+/// Append the instruction to answer in the requested output language, so
+/// templates don't each carry their own language boilerplate
+fn apply_output_language(prompt: &str, options: &RecognitionOptions) -> String {
+    match options.output_language.as_deref().map(str::trim) {
+        Some(language) if !language.is_empty() => {
+            format!("{}\n\n{}", prompt.trim_end(), i18n::output_language_hint(language))
+        }
+        _ => prompt.to_string(),
+    }
+}
+
+/// Save the content streamed before a recognition was cancelled as a `partial`
+/// history record. Nothing is saved when no content arrived.
+pub fn save_partial_result(
+    config_id: i64,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: &RecognitionOptions,
+    result: &RecognitionResult,
+) {
+    if result.content.as_deref().unwrap_or_default().is_empty() {
+        return;
+    }
+    match load_active_config(config_id) {
+        Ok(config) => {
+            save_history(&config, image_base64, image_mime_type, prompt, options, result, "partial");
+        }
+        Err(code) => eprintln!("[Recognition] Failed to save partial result: {}", code.localized()),
+    }
+}
+
+/// Save the successful result of a request sent with `call_provider` as a
+/// history record, e.g. an experiment run that belongs to a review batch
+pub fn save_result(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: &RecognitionOptions,
+    result: &RecognitionResult,
+) -> Option<i64> {
+    save_history(config, image_base64, image_mime_type, prompt, options, result, "success")
+}
+
+fn save_history(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: &RecognitionOptions,
+    result: &RecognitionResult,
+    status: &str,
+) -> Option<i64> {
+    create_history_record(history_input(config, image_base64, image_mime_type, prompt, options, result, status))
+        .map_err(|e| eprintln!("[Recognition] Failed to save history: {}", e))
+        .ok()
+}
+
+/// History record of a recognition, with the image copied into the image store
+fn history_input(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: &RecognitionOptions,
+    result: &RecognitionResult,
+    status: &str,
+) -> HistoryInput {
+    let content = result.content.clone().unwrap_or_default();
+    let stats = text_stats::analyze(&content);
+    let app_settings = settings::get_all_settings().ok();
+    let store_reasoning = result.reasoning.is_some() && app_settings.as_ref().is_some_and(|s| s.store_reasoning);
+    let scrub = |text: String| {
+        if app_settings.as_ref().is_some_and(|s| s.scrub_history_pii) {
+            redaction::scrub_text(&text)
+        } else {
+            text
         }
     };
+    let image_file = image_store::save_image(image_base64, image_mime_type)
+        .map_err(|e| eprintln!("[Recognition] Failed to store image: {}", e))
+        .ok();
+    HistoryInput {
+        config_id: config.id,
+        config_name: config.name.clone(),
+        image_path: options.source_path.clone(),
+        image_file,
+        image_thumbnail: generate_thumbnail(image_base64, THUMBNAIL_SIZE, THUMBNAIL_SIZE).ok(),
+        compare_thumbnail: None,
+        prompt: prompt.to_string(),
+        result: scrub(content),
+        reasoning: result.reasoning.clone().filter(|_| store_reasoning).map(scrub),
+        tokens_used: result.tokens_used,
+        input_tokens: result.input_tokens,
+        output_tokens: result.output_tokens,
+        duration_ms: result.duration_ms.map(|ms| ms as i32),
+        ttft_ms: result.ttft_ms.map(|ms| ms as i32),
+        tokens_per_second: result.tokens_per_second,
+        stats,
+        provider: config.provider.clone(),
+        model_name: config.model_name.clone(),
+        temperature: options.temperature,
+        top_p: options.top_p,
+        max_tokens: options.max_tokens.unwrap_or(config.max_tokens),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        status: status.to_string(),
+        error: result.error.clone(),
+        error_code: result.error_code.as_ref().and_then(ErrorCode::code),
+        image_hash: perceptual_hash(image_base64).ok(),
+        confidence: result.confidence.as_ref().map(|c| c.score),
+        needs_review: result.confidence.as_ref().is_some_and(|c| c.needs_review),
+        batch_id: options.batch_id.clone(),
+        review_status: options.batch_id.as_ref().map(|_| "pending".to_string()),
+        reviewed_result: None,
+        rerun_of: options.rerun_of,
+        tags: options.tags.clone(),
+        template_id: options.template_id,
+        uuid: None,
+    }
+}
+
+/// Load a config for recognition, rejecting missing or disabled ones and
+/// those of another profile than the active one
+pub fn load_active_config(config_id: i64) -> Result<ModelConfig, ErrorCode> {
+    let config = match get_config_by_id(config_id) {
+        Ok(Some(c)) => c,
+        Ok(None) => return Err(ErrorCode::ConfigNotFound),
+        Err(e) => return Err(ErrorCode::ConfigLoadFailed { detail: e.to_string() }),
+    };
 
     if !config.is_active {
-        return RecognitionResult {
-            success: false,
-            content: None,
-            error: Some("该配置已禁用".to_string()),
-            tokens_used: None,
-            duration_ms: None,
-            processed_image: None,
+        return Err(ErrorCode::ConfigDisabled);
+    }
+    let active_profile = profile::active_profile_id().map_err(|e| ErrorCode::ConfigLoadFailed { detail: e.to_string() })?;
+    if !profile::is_visible(active_profile, config.profile_id) {
+        return Err(ErrorCode::ConfigOutsideProfile { name: config.name });
+    }
+
+    Ok(config)
+}
+
+/// Token bucket refilled continuously at `requests_per_minute`, allowing bursts up to one minute's worth
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(requests_per_minute: i32) -> Self {
+        let capacity = requests_per_minute.max(1) as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return how long to wait until one is available
+    fn try_acquire(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let per_second = self.capacity / 60.0;
+        self.tokens = (self.tokens + now.duration_since(self.last_refill).as_secs_f64() * per_second)
+            .min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        }
+    }
+}
+
+static RATE_LIMITERS: Lazy<Mutex<HashMap<i64, TokenBucket>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Wait until the config's request budget allows another call
+async fn acquire_rate_limit(config: &ModelConfig) {
+    let Some(rpm) = config.requests_per_minute.filter(|rpm| *rpm > 0) else {
+        return;
+    };
+
+    loop {
+        let wait = {
+            let mut limiters = RATE_LIMITERS.lock();
+            let bucket = limiters.entry(config.id).or_insert_with(|| TokenBucket::new(rpm));
+            // Pick up limit changes made in the config editor
+            if bucket.capacity != rpm as f64 {
+                *bucket = TokenBucket::new(rpm);
+            }
+            match bucket.try_acquire() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            }
         };
+        tokio::time::sleep(wait).await;
     }
+}
 
-    let adapter_config = AdapterConfig::from(&config);
-    let options = options.unwrap_or(RecognitionOptions {
-        temperature: None,
-        top_p: None,
-        max_tokens: None,
-        stream: None,
-        custom_params: None,
-    });
+/// Parallel requests per provider when settings don't configure a limit
+const DEFAULT_PROVIDER_CONCURRENCY: u32 = 4;
+
+/// Request slots of one account. Waiters take increasing tickets and the semaphore
+/// grants permits in FIFO order, so a waiter's queue position is its ticket minus
+/// the number of tickets already served.
+struct ProviderSlots {
+    limit: u32,
+    semaphore: Arc<Semaphore>,
+    next_ticket: u64,
+    served: watch::Sender<u64>,
+}
 
-    let result = match config.provider.as_str() {
-        "openai" | "azure" | "oneapi" | "custom" => {
-            openai::call_openai(&adapter_config, image_base64, image_mime_type, prompt, &options, callback).await
+impl ProviderSlots {
+    fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit as usize)),
+            next_ticket: 0,
+            served: watch::channel(0).0,
         }
-        "anthropic" => {
-            anthropic::call_anthropic(&adapter_config, image_base64, image_mime_type, prompt, &options, callback).await
+    }
+}
+
+/// Endpoint and SHA-256 of the API key: configs of the same account share its
+/// slots, while separate accounts or servers of one provider type don't
+type SlotKey = (String, [u8; 32]);
+
+static PROVIDER_SLOTS: Lazy<Mutex<HashMap<SlotKey, ProviderSlots>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Counts a ticket as served when its waiter gets a slot or gives up
+struct QueueTicket(watch::Sender<u64>);
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        self.0.send_modify(|served| *served += 1);
+    }
+}
+
+/// Wait for a free request slot of the config's account, reporting the 1-based
+/// queue position whenever it changes while waiting. The limit is set per
+/// provider type in settings.
+async fn acquire_provider_slot(config: &ModelConfig, on_queued: &(dyn Fn(usize) + Send + Sync)) -> OwnedSemaphorePermit {
+    let limit = settings::get_all_settings()
+        .ok()
+        .and_then(|s| s.provider_concurrency.get(&config.provider).copied())
+        .unwrap_or(DEFAULT_PROVIDER_CONCURRENCY)
+        .max(1);
+    let key = (config.api_url.trim().to_string(), Sha256::digest(config.api_key.as_bytes()).into());
+
+    let (semaphore, ticket, served) = {
+        let mut slots = PROVIDER_SLOTS.lock();
+        let entry = slots.entry(key).or_insert_with(|| ProviderSlots::new(limit));
+        // Pick up limit changes made in settings; requests holding old permits finish normally
+        if entry.limit != limit {
+            *entry = ProviderSlots::new(limit);
         }
-        _ => RecognitionResult {
-            success: false,
-            content: None,
-            error: Some(format!("不支持的供应商类型: {}", config.provider)),
-            tokens_used: None,
-            duration_ms: None,
-            processed_image: None,
-        },
+        let ticket = entry.next_ticket;
+        entry.next_ticket += 1;
+        (entry.semaphore.clone(), ticket, entry.served.clone())
     };
+    let mut served_rx = served.subscribe();
+    let _ticket = QueueTicket(served);
 
-    // Save to history if successful
-    if result.success {
-        let _ = create_history_record(HistoryInput {
-            config_id: config.id,
-            config_name: config.name.clone(),
-            image_thumbnail: Some(format!("data:{};base64,{}", image_mime_type, image_base64)),
-            prompt: prompt.to_string(),
-            result: result.content.clone().unwrap_or_default(),
-            tokens_used: result.tokens_used,
-            duration_ms: result.duration_ms.map(|ms| ms as i32),
-        });
+    if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+        return permit;
+    }
+
+    let acquire = semaphore.acquire_owned();
+    tokio::pin!(acquire);
+    loop {
+        let position = ticket.saturating_sub(*served_rx.borrow_and_update()) as usize + 1;
+        on_queued(position);
+        tokio::select! {
+            // The semaphore is never closed
+            permit = &mut acquire => return permit.expect("provider semaphore closed"),
+            _ = served_rx.changed() => {}
+        }
     }
+}
 
+/// Send a recognition request to the config's provider without recording history
+pub async fn call_provider(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: Option<RecognitionOptions>,
+    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
+) -> RecognitionResult {
+    call_provider_queued(config, image_base64, image_mime_type, prompt, options, callback, &|_| {}).await
+}
+
+/// Like `call_provider`, reporting the queue position while waiting for a free
+/// slot of the provider's concurrency limit
+pub async fn call_provider_queued(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: Option<RecognitionOptions>,
+    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
+    on_queued: &(dyn Fn(usize) + Send + Sync),
+) -> RecognitionResult {
+    // Configs that opted in only ever receive masked images, examples included
+    let mut options = options;
+    for example in options.iter_mut().flat_map(|options| options.examples.iter_mut()) {
+        match redaction::redact_for(config, &example.base64, &example.mime_type).await {
+            Ok(Some((base64, mime_type))) => {
+                example.base64 = base64;
+                example.mime_type = mime_type;
+            }
+            Ok(None) => {}
+            Err(code) => return RecognitionResult::from_code(code, None),
+        }
+    }
+    let redacted = match redaction::redact_for(config, image_base64, image_mime_type).await {
+        Ok(redacted) => redacted,
+        Err(code) => return RecognitionResult::from_code(code, None),
+    };
+    let Some((image_base64, image_mime_type)) = redacted else {
+        return send_to_provider(config, image_base64, image_mime_type, prompt, options, callback, on_queued).await;
+    };
+
+    let mut result =
+        send_to_provider(config, &image_base64, &image_mime_type, prompt, options, callback, on_queued).await;
+    result.processed_image = Some(image_base64);
     result
 }
 
-pub async fn test_connection(config_id: i64) -> (bool, String) {
-    let config = match get_config_by_id(config_id) {
-        Ok(Some(c)) => c,
-        Ok(None) => return (false, "配置不存在".to_string()),
-        Err(e) => return (false, format!("获取配置失败: {}", e)),
+/// Send the image as is, without redaction. Only for requests that stay on
+/// premises or carry an image that was already redacted.
+pub async fn send_to_provider(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+    prompt: &str,
+    options: Option<RecognitionOptions>,
+    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
+    on_queued: &(dyn Fn(usize) + Send + Sync),
+) -> RecognitionResult {
+    let image = ImagePart { base64: image_base64, mime_type: image_mime_type };
+    send_images(config, &[image], prompt, options, callback, on_queued).await
+}
+
+/// Send several images with one prompt, as is like `send_to_provider`
+async fn send_images(
+    config: &ModelConfig,
+    images: &[ImagePart<'_>],
+    prompt: &str,
+    options: Option<RecognitionOptions>,
+    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
+    on_queued: &(dyn Fn(usize) + Send + Sync),
+) -> RecognitionResult {
+    // Convert or shrink what the provider would reject with an opaque server error
+    let capabilities = get_capabilities(&config.provider);
+    let fitted = match images
+        .iter()
+        .map(|image| {
+            fit_image(
+                image.base64,
+                capabilities.mime_types,
+                capabilities.max_image_bytes,
+                capabilities.max_image_dimension,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(fitted) => fitted,
+        Err(detail) => return RecognitionResult::from_code(ErrorCode::ImageProcessingFailed { detail }, None),
     };
+    let images: Vec<ImagePart> = images
+        .iter()
+        .zip(&fitted)
+        .map(|(image, fitted)| match fitted {
+            Some((base64, mime_type)) => ImagePart { base64, mime_type },
+            None => *image,
+        })
+        .collect();
 
-    let adapter_config = AdapterConfig::from(&config);
-    
-    match config.provider.as_str() {
-        "openai" | "azure" | "oneapi" | "custom" => {
-            openai::test_connection(&adapter_config).await
+    let mut adapter_config = AdapterConfig::from(config);
+    let mut options = options.unwrap_or_default();
+    if !capabilities.few_shot_images {
+        options.examples.clear();
+    }
+    for example in &mut options.examples {
+        match fit_image(
+            &example.base64,
+            capabilities.mime_types,
+            capabilities.max_image_bytes,
+            capabilities.max_image_dimension,
+        ) {
+            Ok(Some((base64, mime_type))) => {
+                example.base64 = base64;
+                example.mime_type = mime_type;
+            }
+            Ok(None) => {}
+            Err(detail) => return RecognitionResult::from_code(ErrorCode::ImageProcessingFailed { detail }, None),
         }
-        "anthropic" => {
-            anthropic::test_connection(&adapter_config).await
+    }
+    options.image_detail = options.image_detail.or(config.image_detail);
+    // Above the model's limit providers answer with a 400 that doesn't say why
+    let mut warnings = Vec::new();
+    if let Some(limit) = max_output_tokens(&config.provider, &config.model_name) {
+        let requested = options.max_tokens.unwrap_or(adapter_config.max_tokens);
+        if requested > limit {
+            warnings.push(i18n::max_tokens_clamped_notice(&i18n::current_language(), requested, limit));
         }
-        _ => (false, format!("不支持的供应商类型: {}", config.provider)),
+        adapter_config.max_tokens = adapter_config.max_tokens.min(limit);
+        options.max_tokens = options.max_tokens.map(|tokens| tokens.min(limit));
     }
+    if !capabilities.streaming {
+        options.stream = Some(false);
+    }
+    // Confidence is then rated with a second request instead of logprobs
+    if !capabilities.logprobs {
+        options.estimate_confidence = Some(false);
+    }
+
+    let report_queued = |position: usize| {
+        progress::queued(position);
+        on_queued(position);
+    };
+    let _permit = acquire_provider_slot(config, &report_queued).await;
+    acquire_rate_limit(config).await;
+
+    if config.provider == rapidocr::PROVIDER {
+        // The local engine reads a single image and has no use for a prompt
+        return match images.as_slice() {
+            [image] => recognize_locally(&config.api_url, image.base64, &options).await,
+            _ => RecognitionResult::from_code(ErrorCode::UnsupportedProvider { provider: config.provider.clone() }, None),
+        };
+    }
+    let Some(vision_provider) = provider::provider_for(&config.provider) else {
+        return RecognitionResult::from_code(
+            ErrorCode::UnsupportedProvider { provider: config.provider.clone() },
+            None,
+        );
+    };
+
+    let mut result =
+        provider::call(vision_provider, &adapter_config, &images, prompt, &options, callback).await;
+    result.warnings = warnings;
+    if let Err(e) = usage_ledger::record_usage(config.id, result.input_tokens, result.output_tokens, result.tokens_used) {
+        eprintln!("[Budget] Failed to record usage: {}", e);
+    }
+
+    let error = if result.success { None } else { result.error_code.as_ref() };
+    if let Some(incident) = provider_status::record_outcome(&config.provider, error) {
+        if !result.success {
+            let notice = i18n::provider_incident_notice(&i18n::current_language(), &incident.description);
+            result.error = result.error.map(|e| format!("{} ({})", e, notice));
+            result.provider_incident = Some(incident);
+        }
+    }
+    result
+}
+
+/// Run the local ONNX engine of a `rapidocr` config, whose API URL is the
+/// model folder. The prompt doesn't apply; layout requests get the boxes as
+/// the JSON the layout prompt asks models for.
+async fn recognize_locally(model_dir: &str, image_base64: &str, options: &RecognitionOptions) -> RecognitionResult {
+    let started = Instant::now();
+    let (model_dir, image_base64) = (model_dir.to_string(), image_base64.to_string());
+    let outcome = tokio::task::spawn_blocking(move || rapidocr::recognize(&model_dir, &image_base64))
+        .await
+        .unwrap_or_else(|e| Err(ErrorCode::LocalOcrFailed { detail: e.to_string() }));
+    let duration_ms = Some(started.elapsed().as_millis() as i64);
+
+    match outcome {
+        Ok((layout, inference)) => RecognitionResult {
+            success: true,
+            content: Some(if options.layout.unwrap_or(false) { layout.to_json() } else { layout.text() }),
+            inference: Some(inference),
+            duration_ms,
+            ..Default::default()
+        },
+        Err(code) => RecognitionResult::from_code(code, duration_ms),
+    }
+}
+
+/// Send a test request for `provider`, or load the models of a local engine
+pub async fn test_adapter(provider: &str, adapter_config: &AdapterConfig) -> Result<(), ErrorCode> {
+    if provider == rapidocr::PROVIDER {
+        let model_dir = adapter_config.api_url.clone();
+        return tokio::task::spawn_blocking(move || rapidocr::check_models(&model_dir))
+            .await
+            .unwrap_or_else(|e| Err(ErrorCode::LocalOcrFailed { detail: e.to_string() }));
+    }
+    match provider::provider_for(provider) {
+        Some(vision_provider) => provider::test_connection(vision_provider, adapter_config).await,
+        None => Err(ErrorCode::UnsupportedProvider { provider: provider.to_string() }),
+    }
+}
+
+pub async fn test_connection(config_id: i64) -> Result<(), ErrorCode> {
+    let config = match get_config_by_id(config_id) {
+        Ok(Some(c)) => c,
+        Ok(None) => return Err(ErrorCode::ConfigNotFound),
+        Err(e) => return Err(ErrorCode::ConfigLoadFailed { detail: e.to_string() }),
+    };
+
+    test_adapter(&config.provider, &AdapterConfig::from(&config)).await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn test_connection_with_config(
     provider: &str,
     api_url: &str,
     api_key: &str,
     model_name: &str,
-) -> (bool, String) {
+    reasoning_model: bool,
+    organization: Option<String>,
+    project: Option<String>,
+    skip_tls_verify: bool,
+) -> Result<(), ErrorCode> {
     let adapter_config = AdapterConfig {
         api_url: api_url.to_string(),
         api_key: api_key.to_string(),
         model_name: model_name.to_string(),
         max_tokens: 100,
+        reasoning_model,
+        thinking_budget_tokens: None,
+        organization: organization.filter(|value| !value.trim().is_empty()),
+        project: project.filter(|value| !value.trim().is_empty()),
+        skip_tls_verify,
     };
 
-    match provider {
-        "openai" | "azure" | "oneapi" | "custom" => {
-            openai::test_connection(&adapter_config).await
-        }
-        "anthropic" => {
-            anthropic::test_connection(&adapter_config).await
-        }
-        _ => (false, format!("不支持的供应商类型: {}", provider)),
-    }
+    test_adapter(provider, &adapter_config).await
 }
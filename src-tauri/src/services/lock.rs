@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use crate::db::settings;
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+
+/// Settings key of the passcode hash. `security:` keys can't be written or
+/// reset through the generic settings commands.
+pub const PASSCODE_KEY: &str = "security:passcodeHash";
+
+pub const MIN_PASSCODE_LEN: usize = 4;
+
+/// Whether the app refuses commands until the passcode is entered
+#[derive(Debug)]
+pub struct LockState {
+    enabled: bool,
+    locked: bool,
+    last_activity: Instant,
+}
+
+impl LockState {
+    /// Start locked when a passcode is set
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            locked: enabled,
+            last_activity: Instant::now(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Locked right now; locks first when idle for more than `idle_minutes` (0 never)
+    pub fn is_locked(&mut self, idle_minutes: u32, now: Instant) -> bool {
+        let idle_limit = Duration::from_secs(u64::from(idle_minutes) * 60);
+        if self.enabled && idle_minutes > 0 && now.saturating_duration_since(self.last_activity) > idle_limit {
+            self.locked = true;
+        }
+        self.locked
+    }
+
+    /// Record user activity, which postpones the idle lock
+    pub fn touch(&mut self, now: Instant) {
+        self.last_activity = now;
+    }
+
+    pub fn lock(&mut self) {
+        self.locked = self.enabled;
+    }
+
+    pub fn unlock(&mut self, now: Instant) {
+        self.locked = false;
+        self.last_activity = now;
+    }
+
+    /// Passcode set or removed; removing it also unlocks
+    pub fn set_enabled(&mut self, enabled: bool, now: Instant) {
+        self.enabled = enabled;
+        if !enabled {
+            self.unlock(now);
+        }
+    }
+}
+
+pub fn has_passcode() -> bool {
+    matches!(settings::get_setting(PASSCODE_KEY), Ok(Some(_)))
+}
+
+/// Idle minutes before the app locks itself
+pub fn idle_minutes() -> u32 {
+    settings::get_all_settings().map(|s| s.lock_idle_minutes.max(0) as u32).unwrap_or(0)
+}
+
+/// Check a passcode against the stored hash; without a passcode everything matches
+pub fn verify(passcode: &str) -> Result<bool, AppError> {
+    let stored = settings::get_setting(PASSCODE_KEY)?;
+    Ok(stored.is_none_or(|hash| verify_hash(passcode, &hash)))
+}
+
+/// Set, change or (with `None`) remove the passcode. The current passcode is
+/// required whenever one is set.
+pub fn set_passcode(current: Option<&str>, passcode: Option<&str>) -> Result<(), AppError> {
+    if !verify(current.unwrap_or_default())? {
+        return Err(ErrorCode::WrongPasscode.into());
+    }
+
+    let stored = match passcode {
+        Some(passcode) => {
+            if passcode.chars().count() < MIN_PASSCODE_LEN {
+                return Err(ErrorCode::PasscodeTooShort { min: MIN_PASSCODE_LEN }.into());
+            }
+            settings::set_setting(PASSCODE_KEY, &hash_passcode(passcode)?)
+        }
+        None => settings::delete_setting(PASSCODE_KEY),
+    };
+    stored.map_err(AppError::from)
+}
+
+fn hash_passcode(passcode: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passcode.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn verify_hash(passcode: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|parsed| Argon2::default().verify_password(passcode.as_bytes(), &parsed).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::init_test_database;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hash = hash_passcode("1234").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_hash("1234", &hash));
+        assert!(!verify_hash("4321", &hash));
+        assert!(!verify_hash("1234", "not a hash"));
+    }
+
+    #[test]
+    fn test_passcode_errors_keep_their_kind() {
+        init_test_database();
+        assert!(matches!(
+            set_passcode(None, Some("1")),
+            Err(AppError::Validation { code: Some(ErrorCode::PasscodeTooShort { .. }), .. })
+        ));
+    }
+
+    #[test]
+    fn test_idle_lock() {
+        let start = Instant::now();
+        let mut state = LockState::new(true);
+        assert!(state.is_locked(5, start));
+
+        state.unlock(start);
+        assert!(!state.is_locked(5, start + Duration::from_secs(299)));
+        state.touch(start + Duration::from_secs(299));
+        assert!(!state.is_locked(5, start + Duration::from_secs(500)));
+        assert!(state.is_locked(5, start + Duration::from_secs(700)));
+
+        // Without a passcode, or with the idle lock off, nothing locks
+        let mut state = LockState::new(false);
+        assert!(!state.is_locked(5, start + Duration::from_secs(3600)));
+        let mut state = LockState::new(true);
+        state.unlock(start);
+        assert!(!state.is_locked(0, start + Duration::from_secs(3600)));
+    }
+}
@@ -0,0 +1,166 @@
+//! Email a history record over the SMTP server from settings: the result text
+//! as the body and the original image as an attachment.
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::Path;
+use std::time::Duration;
+use crate::db::{history, settings};
+use crate::utils::crypto::{decrypt, encrypt};
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::image_store;
+
+/// Settings key of the SMTP password.
+/// `security:` keys can't be written or reset through the generic settings commands.
+pub const PASSWORD_KEY: &str = "security:smtpPassword";
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Store the SMTP password encrypted; an empty password removes it
+pub fn set_password(password: &str) -> Result<(), String> {
+    if password.is_empty() {
+        settings::delete_setting(PASSWORD_KEY).map_err(|e| e.to_string())
+    } else {
+        settings::set_setting(PASSWORD_KEY, &encrypt(password)).map_err(|e| e.to_string())
+    }
+}
+
+pub fn has_password() -> Result<bool, String> {
+    Ok(stored_password()?.is_some())
+}
+
+fn stored_password() -> Result<Option<String>, String> {
+    let value = settings::get_setting(PASSWORD_KEY).map_err(|e| e.to_string())?;
+    Ok(value.and_then(|value| decrypt(&value).ok()).filter(|value| !value.is_empty()))
+}
+
+/// Email a record's result, with its stored image attached when there is one,
+/// to `to` or else the default recipients. Returns the addresses it went to.
+pub async fn send_result(history_id: i64, to: Option<&str>) -> Result<Vec<String>, AppError> {
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let record = history::get_history_by_id(history_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+
+    let host = app_settings.smtp_host.trim();
+    let from = match app_settings.smtp_from.trim() {
+        "" => app_settings.smtp_username.trim(),
+        from => from,
+    };
+    let recipients = parse_recipients(to.unwrap_or(&app_settings.email_recipients))?;
+    if host.is_empty() || from.is_empty() || recipients.is_empty() {
+        return Err(ErrorCode::EmailNotConfigured.into());
+    }
+    let from: Mailbox = from
+        .parse()
+        .map_err(|_| ErrorCode::InvalidEmailAddress { address: from.to_string() })?;
+
+    let file_name = record
+        .image_path
+        .as_deref()
+        .and_then(|path| Path::new(path).file_name())
+        .and_then(|name| name.to_str())
+        .or(record.image_file.as_deref());
+    let subject = match file_name {
+        Some(name) => format!("OCR: {}", name),
+        None => format!("OCR result {}", record.created_at),
+    };
+    let body = record.reviewed_result.unwrap_or(record.result);
+
+    let mut parts = MultiPart::mixed().singlepart(SinglePart::plain(body));
+    if let (Some(image_file), Some(file_name)) = (record.image_file.as_deref(), file_name) {
+        let data = image_store::load_image(image_file)?;
+        let content_type = ContentType::parse(image_mime_type(image_file))
+            .map_err(|e| ErrorCode::EmailFailed { detail: e.to_string() })?;
+        parts = parts.singlepart(Attachment::new(file_name.to_string()).body(data, content_type));
+    }
+
+    let mut builder = Message::builder().from(from).subject(subject);
+    for recipient in &recipients {
+        builder = builder.to(recipient.clone());
+    }
+    let email = builder
+        .multipart(parts)
+        .map_err(|e| ErrorCode::EmailFailed { detail: e.to_string() })?;
+
+    transport(host, &app_settings.smtp_security, app_settings.smtp_port, &app_settings.smtp_username)?
+        .send(email)
+        .await
+        .map_err(|e| ErrorCode::EmailFailed { detail: e.to_string() })?;
+    Ok(recipients.iter().map(|recipient| recipient.to_string()).collect())
+}
+
+fn transport(
+    host: &str,
+    security: &str,
+    port: i32,
+    username: &str,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, AppError> {
+    let builder = match security {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+        "none" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+        _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+    }
+    .map_err(|e| ErrorCode::EmailFailed { detail: e.to_string() })?;
+    let port = u16::try_from(port).map_err(|_| ErrorCode::EmailFailed { detail: format!("invalid port {}", port) })?;
+
+    let builder = builder.port(port).timeout(Some(SMTP_TIMEOUT));
+    let builder = match (username.trim(), stored_password()?) {
+        ("", _) => builder,
+        (username, password) => builder.credentials(Credentials::new(username.to_string(), password.unwrap_or_default())),
+    };
+    Ok(builder.build())
+}
+
+/// Addresses separated by commas, semicolons or line breaks
+fn parse_recipients(list: &str) -> Result<Vec<Mailbox>, ErrorCode> {
+    list.split([',', ';', '\n'])
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            address
+                .parse()
+                .map_err(|_| ErrorCode::InvalidEmailAddress { address: address.to_string() })
+        })
+        .collect()
+}
+
+fn image_mime_type(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recipients() {
+        let recipients = parse_recipients("clerk@example.com; Case 2024-117 <case-117@files.example.org>,\n").unwrap();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[1].email.to_string(), "case-117@files.example.org");
+        assert!(parse_recipients(" , ").unwrap().is_empty());
+        assert!(matches!(
+            parse_recipients("clerk@example.com, not an address"),
+            Err(ErrorCode::InvalidEmailAddress { address }) if address == "not an address"
+        ));
+    }
+
+    #[test]
+    fn test_image_mime_type() {
+        assert_eq!(image_mime_type("20240101_ab12.PNG"), "image/png");
+        assert_eq!(image_mime_type("20240101_ab12.jpg"), "image/jpeg");
+    }
+}
@@ -0,0 +1,741 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatexIssue {
+    pub code: String,
+    /// "error" for broken structure, "warning" for unknown commands
+    pub severity: String,
+    pub message: String,
+    /// Byte offset in the formula
+    pub offset: usize,
+}
+
+/// Validation result of one formula found in a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormulaCheck {
+    pub latex: String,
+    pub display: bool,
+    /// No errors; warnings are allowed
+    pub valid: bool,
+    pub issues: Vec<LatexIssue>,
+    /// Only produced for valid formulas when requested
+    pub mathml: Option<String>,
+}
+
+/// Validate every formula in a response and optionally convert valid ones to MathML
+pub fn check_formulas(content: &str, mathml: bool) -> Vec<FormulaCheck> {
+    extract_formulas(content)
+        .into_iter()
+        .map(|(latex, display)| {
+            let issues = validate_latex(&latex);
+            let valid = issues.iter().all(|issue| issue.severity != "error");
+            FormulaCheck {
+                mathml: (mathml && valid).then(|| to_mathml(&latex, display)),
+                latex,
+                display,
+                valid,
+                issues,
+            }
+        })
+        .collect()
+}
+
+/// Find `$$…$$`, `\[…\]`, `\(…\)` and `$…$` formulas. A response without
+/// delimiters is taken as a single display formula, which is how the formula
+/// template usually answers.
+pub fn extract_formulas(content: &str) -> Vec<(String, bool)> {
+    let content = strip_code_fence(content);
+    let mut formulas = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = find_opening(rest) {
+        let (open, close, display) = start.1;
+        let body_start = start.0 + open.len();
+        let Some(len) = find_unescaped(&rest[body_start..], close) else {
+            break;
+        };
+        let latex = rest[body_start..body_start + len].trim();
+        if !latex.is_empty() {
+            formulas.push((latex.to_string(), display));
+        }
+        rest = &rest[body_start + len + close.len()..];
+    }
+
+    if formulas.is_empty() && !content.trim().is_empty() {
+        formulas.push((content.trim().to_string(), true));
+    }
+    formulas
+}
+
+type Delimiter = (&'static str, &'static str, bool);
+
+const DELIMITERS: &[Delimiter] = &[("$$", "$$", true), ("\\[", "\\]", true), ("\\(", "\\)", false), ("$", "$", false)];
+
+fn find_opening(text: &str) -> Option<(usize, Delimiter)> {
+    DELIMITERS
+        .iter()
+        .filter_map(|&delimiter| find_unescaped(text, delimiter.0).map(|i| (i, delimiter)))
+        // Earliest match; "$$" is listed before "$" so it wins at the same position
+        .min_by_key(|(i, _)| *i)
+}
+
+/// Find `pattern` not preceded by a backslash (so `\$` is a literal dollar)
+fn find_unescaped(text: &str, pattern: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(i) = text[from..].find(pattern).map(|i| i + from) {
+        let escaped = pattern.starts_with('$') && text[..i].ends_with('\\');
+        if !escaped {
+            return Some(i);
+        }
+        from = i + pattern.len();
+    }
+    None
+}
+
+fn strip_code_fence(content: &str) -> &str {
+    let trimmed = content.trim();
+    let Some(inner) = trimmed.strip_prefix("```") else {
+        return content;
+    };
+    let inner = inner.split_once('\n').map_or("", |(_, body)| body);
+    inner.rsplit_once("```").map_or(inner, |(body, _)| body)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Command(String),
+    Char(char),
+    Open,
+    Close,
+    Sup,
+    Sub,
+    Amp,
+}
+
+fn tokenize(latex: &str) -> Vec<(usize, Token)> {
+    let mut tokens = Vec::new();
+    let mut chars = latex.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(&(_, next)) = chars.peek() {
+                    if !next.is_ascii_alphabetic() {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                if name.is_empty() {
+                    // Control symbol such as \, \{ or \\
+                    if let Some((_, next)) = chars.next() {
+                        name.push(next);
+                    }
+                }
+                Token::Command(name)
+            }
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '^' => Token::Sup,
+            '_' => Token::Sub,
+            '&' => Token::Amp,
+            '%' => {
+                // Comment until end of line
+                while chars.peek().is_some_and(|&(_, next)| next != '\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            c => Token::Char(c),
+        };
+        tokens.push((i, token));
+    }
+
+    tokens
+}
+
+fn issue(code: &str, severity: &str, message: String, offset: usize) -> LatexIssue {
+    LatexIssue {
+        code: code.to_string(),
+        severity: severity.to_string(),
+        message,
+        offset,
+    }
+}
+
+/// Check brace, environment and `\left`/`\right` balance, dangling scripts and unknown commands
+pub fn validate_latex(latex: &str) -> Vec<LatexIssue> {
+    let tokens = tokenize(latex);
+    let mut issues = Vec::new();
+    let mut braces: Vec<usize> = Vec::new();
+    let mut environments: Vec<(String, usize)> = Vec::new();
+    let mut left_right: Vec<usize> = Vec::new();
+
+    for (index, (offset, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Open => braces.push(*offset),
+            Token::Close if braces.pop().is_none() => {
+                issues.push(issue("unmatched_brace", "error", "多余的右花括号 }".to_string(), *offset));
+            }
+            Token::Sup | Token::Sub => {
+                let has_argument = matches!(
+                    tokens.get(index + 1),
+                    Some((_, Token::Char(_) | Token::Command(_) | Token::Open))
+                );
+                if !has_argument {
+                    issues.push(issue("missing_script", "error", "上标/下标缺少内容".to_string(), *offset));
+                }
+            }
+            Token::Command(name) => match name.as_str() {
+                "begin" | "end" => {
+                    let Some(env) = group_text(&tokens, index + 1) else {
+                        issues.push(issue("missing_environment", "error", format!("\\{} 缺少环境名", name), *offset));
+                        continue;
+                    };
+                    if name == "begin" {
+                        environments.push((env, *offset));
+                    } else {
+                        match environments.pop() {
+                            Some((open, _)) if open == env => {}
+                            Some((open, open_offset)) => issues.push(issue(
+                                "mismatched_environment",
+                                "error",
+                                format!("\\begin{{{}}} 与 \\end{{{}}} 不匹配", open, env),
+                                open_offset,
+                            )),
+                            None => issues.push(issue(
+                                "mismatched_environment",
+                                "error",
+                                format!("多余的 \\end{{{}}}", env),
+                                *offset,
+                            )),
+                        }
+                    }
+                }
+                "left" => left_right.push(*offset),
+                "right" if left_right.pop().is_none() => {
+                    issues.push(issue("unmatched_left_right", "error", "\\right 缺少对应的 \\left".to_string(), *offset));
+                }
+                "right" => {}
+                _ if !is_known_command(name) => issues.push(issue(
+                    "unknown_command",
+                    "warning",
+                    format!("未知命令 \\{}", name),
+                    *offset,
+                )),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    for offset in braces {
+        issues.push(issue("unclosed_brace", "error", "花括号 { 未闭合".to_string(), offset));
+    }
+    for (env, offset) in environments {
+        issues.push(issue("unclosed_environment", "error", format!("\\begin{{{}}} 未闭合", env), offset));
+    }
+    for offset in left_right {
+        issues.push(issue("unmatched_left_right", "error", "\\left 缺少对应的 \\right".to_string(), offset));
+    }
+
+    issues.sort_by_key(|issue| issue.offset);
+    issues
+}
+
+/// Plain text inside a `{…}` group starting at `index`, e.g. an environment name
+fn group_text(tokens: &[(usize, Token)], index: usize) -> Option<String> {
+    if tokens.get(index)?.1 != Token::Open {
+        return None;
+    }
+    let mut text = String::new();
+    for (_, token) in &tokens[index + 1..] {
+        match token {
+            Token::Char(c) => text.push(*c),
+            Token::Close => return Some(text),
+            _ => return None,
+        }
+    }
+    None
+}
+
+const GREEK: &[(&str, char)] = &[
+    ("alpha", 'α'), ("beta", 'β'), ("gamma", 'γ'), ("delta", 'δ'), ("epsilon", 'ϵ'),
+    ("varepsilon", 'ε'), ("zeta", 'ζ'), ("eta", 'η'), ("theta", 'θ'), ("vartheta", 'ϑ'),
+    ("iota", 'ι'), ("kappa", 'κ'), ("lambda", 'λ'), ("mu", 'μ'), ("nu", 'ν'), ("xi", 'ξ'),
+    ("pi", 'π'), ("varpi", 'ϖ'), ("rho", 'ρ'), ("varrho", 'ϱ'), ("sigma", 'σ'),
+    ("varsigma", 'ς'), ("tau", 'τ'), ("upsilon", 'υ'), ("phi", 'ϕ'), ("varphi", 'φ'),
+    ("chi", 'χ'), ("psi", 'ψ'), ("omega", 'ω'), ("Gamma", 'Γ'), ("Delta", 'Δ'),
+    ("Theta", 'Θ'), ("Lambda", 'Λ'), ("Xi", 'Ξ'), ("Pi", 'Π'), ("Sigma", 'Σ'),
+    ("Upsilon", 'Υ'), ("Phi", 'Φ'), ("Psi", 'Ψ'), ("Omega", 'Ω'),
+];
+
+const OPERATORS: &[(&str, &str)] = &[
+    ("times", "×"), ("cdot", "⋅"), ("div", "÷"), ("pm", "±"), ("mp", "∓"), ("ast", "∗"),
+    ("leq", "≤"), ("le", "≤"), ("geq", "≥"), ("ge", "≥"), ("neq", "≠"), ("ne", "≠"),
+    ("approx", "≈"), ("equiv", "≡"), ("sim", "∼"), ("simeq", "≃"), ("cong", "≅"),
+    ("propto", "∝"), ("ll", "≪"), ("gg", "≫"), ("in", "∈"), ("notin", "∉"), ("ni", "∋"),
+    ("subset", "⊂"), ("subseteq", "⊆"), ("supset", "⊃"), ("supseteq", "⊇"), ("cup", "∪"),
+    ("cap", "∩"), ("setminus", "∖"), ("emptyset", "∅"), ("varnothing", "∅"), ("forall", "∀"),
+    ("exists", "∃"), ("neg", "¬"), ("land", "∧"), ("wedge", "∧"), ("lor", "∨"), ("vee", "∨"),
+    ("to", "→"), ("rightarrow", "→"), ("leftarrow", "←"), ("Rightarrow", "⇒"),
+    ("Leftarrow", "⇐"), ("leftrightarrow", "↔"), ("Leftrightarrow", "⇔"), ("implies", "⟹"),
+    ("iff", "⟺"), ("mapsto", "↦"), ("infty", "∞"), ("partial", "∂"), ("nabla", "∇"),
+    ("sum", "∑"), ("prod", "∏"), ("int", "∫"), ("iint", "∬"), ("iiint", "∭"), ("oint", "∮"),
+    ("ldots", "…"), ("cdots", "⋯"), ("vdots", "⋮"), ("ddots", "⋱"), ("dots", "…"),
+    ("circ", "∘"), ("bullet", "∙"), ("perp", "⊥"), ("parallel", "∥"), ("angle", "∠"),
+    ("triangle", "△"), ("degree", "°"), ("prime", "′"), ("langle", "⟨"), ("rangle", "⟩"),
+    ("lfloor", "⌊"), ("rfloor", "⌋"), ("lceil", "⌈"), ("rceil", "⌉"), ("mid", "∣"),
+    ("vert", "|"), ("Vert", "‖"), ("hbar", "ℏ"), ("ell", "ℓ"), ("Re", "ℜ"), ("Im", "ℑ"),
+    ("aleph", "ℵ"),
+    ("{", "{"), ("}", "}"), ("|", "‖"), ("%", "%"), ("$", "$"), ("&", "&"), ("#", "#"), ("_", "_"),
+];
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh",
+    "tanh", "log", "ln", "lg", "exp", "lim", "liminf", "limsup", "max", "min", "sup", "inf",
+    "det", "dim", "ker", "deg", "gcd", "arg", "Pr", "mod", "bmod", "pmod",
+];
+
+const SPACES: &[(&str, &str)] = &[
+    (",", "0.167em"), (":", "0.222em"), (";", "0.278em"), (" ", "0.25em"),
+    ("quad", "1em"), ("qquad", "2em"), ("!", "0em"),
+];
+
+const ACCENTS: &[(&str, &str)] = &[
+    ("hat", "^"), ("widehat", "^"), ("bar", "¯"), ("overline", "¯"), ("vec", "→"),
+    ("tilde", "~"), ("widetilde", "~"), ("dot", "˙"), ("ddot", "¨"), ("overrightarrow", "→"),
+];
+
+const STYLES: &[(&str, &str)] = &[
+    ("mathbf", "bold"), ("boldsymbol", "bold-italic"), ("mathit", "italic"),
+    ("mathbb", "double-struck"), ("mathcal", "script"), ("mathscr", "script"),
+    ("mathfrak", "fraktur"), ("mathsf", "sans-serif"), ("mathtt", "monospace"),
+];
+
+/// Structural and sizing commands without a direct symbol
+const STRUCTURAL: &[&str] = &[
+    "frac", "dfrac", "tfrac", "cfrac", "binom", "sqrt", "left", "right", "begin", "end", "text",
+    "textrm", "textbf", "mathrm", "operatorname", "underline", "displaystyle", "textstyle",
+    "limits", "nolimits", "big", "Big", "bigg", "Bigg", "bigl", "bigr", "Bigl", "Bigr",
+    "biggl", "biggr", "\\", "label", "tag", "nonumber", "notag", "hline",
+];
+
+fn is_known_command(name: &str) -> bool {
+    GREEK.iter().any(|(n, _)| *n == name)
+        || OPERATORS.iter().any(|(n, _)| *n == name)
+        || FUNCTIONS.contains(&name)
+        || SPACES.iter().any(|(n, _)| *n == name)
+        || ACCENTS.iter().any(|(n, _)| *n == name)
+        || STYLES.iter().any(|(n, _)| *n == name)
+        || STRUCTURAL.contains(&name)
+}
+
+/// Convert LaTeX to presentation MathML. Intended for formulas that passed
+/// `validate_latex`; unknown commands are rendered as `<merror>`.
+pub fn to_mathml(latex: &str, display: bool) -> String {
+    let tokens = tokenize(latex);
+    let mut parser = MathParser { tokens: &tokens, pos: 0 };
+    let body = parser.parse_row(&[]);
+    format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"{}\"><mrow>{}</mrow></math>",
+        if display { "block" } else { "inline" },
+        body
+    )
+}
+
+struct MathParser<'a> {
+    tokens: &'a [(usize, Token)],
+    pos: usize,
+}
+
+/// Tokens that end the current row inside groups and environments
+#[derive(Clone, Copy, PartialEq)]
+enum Stop {
+    Close,
+    Right,
+    Cell,
+}
+
+impl MathParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos).map(|(_, token)| token);
+        self.pos += 1;
+        token
+    }
+
+    fn at_stop(&self, stops: &[Stop]) -> bool {
+        match self.peek() {
+            None => true,
+            Some(Token::Close) => stops.contains(&Stop::Close),
+            Some(Token::Command(name)) if name == "right" => stops.contains(&Stop::Right),
+            Some(Token::Command(name)) if name == "end" || name == "\\" => stops.contains(&Stop::Cell),
+            Some(Token::Amp) => stops.contains(&Stop::Cell),
+            _ => false,
+        }
+    }
+
+    fn parse_row(&mut self, stops: &[Stop]) -> String {
+        let mut out = String::new();
+        while !self.at_stop(stops) {
+            // Stray closers outside their context are skipped
+            if matches!(self.peek(), Some(Token::Close | Token::Amp)) {
+                self.pos += 1;
+                continue;
+            }
+            if let Some(Token::Command(name)) = self.peek() {
+                if matches!(name.as_str(), "right" | "end" | "\\") {
+                    self.pos += 1;
+                    continue;
+                }
+            }
+            out.push_str(&self.parse_scripted());
+        }
+        out
+    }
+
+    fn parse_scripted(&mut self) -> String {
+        let base = self.parse_atom();
+        let mut sub = None;
+        let mut sup = None;
+
+        loop {
+            match self.peek() {
+                Some(Token::Sub) if sub.is_none() => {
+                    self.pos += 1;
+                    sub = Some(self.parse_atom());
+                }
+                Some(Token::Sup) if sup.is_none() => {
+                    self.pos += 1;
+                    sup = Some(self.parse_atom());
+                }
+                Some(Token::Char('\'')) => {
+                    self.pos += 1;
+                    sup = Some(format!("{}<mo>′</mo>", sup.unwrap_or_default()));
+                }
+                _ => break,
+            }
+        }
+
+        match (sub, sup) {
+            (Some(sub), Some(sup)) => format!("<msubsup>{}{}{}</msubsup>", wrap(&base), wrap(&sub), wrap(&sup)),
+            (Some(sub), None) => format!("<msub>{}{}</msub>", wrap(&base), wrap(&sub)),
+            (None, Some(sup)) => format!("<msup>{}{}</msup>", wrap(&base), wrap(&sup)),
+            (None, None) => base,
+        }
+    }
+
+    /// A required argument: a group or a single atom
+    fn parse_argument(&mut self) -> String {
+        self.parse_atom()
+    }
+
+    fn parse_group(&mut self) -> String {
+        let inner = self.parse_row(&[Stop::Close]);
+        if self.peek() == Some(&Token::Close) {
+            self.pos += 1;
+        }
+        format!("<mrow>{}</mrow>", inner)
+    }
+
+    /// Raw characters of a `{…}` group, for \text and environment names
+    fn parse_text_group(&mut self) -> String {
+        if self.peek() != Some(&Token::Open) {
+            return String::new();
+        }
+        self.pos += 1;
+        let mut text = String::new();
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token {
+                Token::Open => depth += 1,
+                Token::Close if depth == 0 => break,
+                Token::Close => depth -= 1,
+                Token::Char(c) => text.push(*c),
+                Token::Command(name) if name == " " => text.push(' '),
+                Token::Command(name) => text.push_str(name),
+                Token::Sub => text.push('_'),
+                Token::Sup => text.push('^'),
+                Token::Amp => text.push('&'),
+            }
+        }
+        text
+    }
+
+    fn parse_atom(&mut self) -> String {
+        let Some(token) = self.next().cloned() else {
+            return String::new();
+        };
+
+        match token {
+            Token::Open => self.parse_group(),
+            Token::Char(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(Token::Char(next)) = self.peek() {
+                    if !next.is_ascii_digit() && *next != '.' {
+                        break;
+                    }
+                    number.push(*next);
+                    self.pos += 1;
+                }
+                format!("<mn>{}</mn>", number)
+            }
+            Token::Char(c) if c.is_alphabetic() => format!("<mi>{}</mi>", escape(&c.to_string())),
+            Token::Char(c) => format!("<mo>{}</mo>", escape(&c.to_string())),
+            Token::Command(name) => self.parse_command(&name),
+            Token::Sup | Token::Sub | Token::Close | Token::Amp => String::new(),
+        }
+    }
+
+    fn parse_command(&mut self, name: &str) -> String {
+        if let Some((_, c)) = GREEK.iter().find(|(n, _)| *n == name) {
+            return format!("<mi>{}</mi>", c);
+        }
+        if let Some((_, symbol)) = OPERATORS.iter().find(|(n, _)| *n == name) {
+            return format!("<mo>{}</mo>", escape(symbol));
+        }
+        if FUNCTIONS.contains(&name) {
+            return format!("<mi>{}</mi>", name);
+        }
+        if let Some((_, width)) = SPACES.iter().find(|(n, _)| *n == name) {
+            return format!("<mspace width=\"{}\"/>", width);
+        }
+        if let Some((_, accent)) = ACCENTS.iter().find(|(n, _)| *n == name) {
+            let base = self.parse_argument();
+            return format!("<mover accent=\"true\">{}<mo>{}</mo></mover>", wrap(&base), accent);
+        }
+        if let Some((_, variant)) = STYLES.iter().find(|(n, _)| *n == name) {
+            let inner = self.parse_argument();
+            return format!("<mstyle mathvariant=\"{}\">{}</mstyle>", variant, inner);
+        }
+
+        match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.parse_argument();
+                let denominator = self.parse_argument();
+                format!("<mfrac>{}{}</mfrac>", wrap(&numerator), wrap(&denominator))
+            }
+            "binom" => {
+                let top = self.parse_argument();
+                let bottom = self.parse_argument();
+                format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                    wrap(&top),
+                    wrap(&bottom)
+                )
+            }
+            "sqrt" => {
+                let index = if self.peek() == Some(&Token::Char('[')) {
+                    self.pos += 1;
+                    let mut index = String::new();
+                    while !matches!(self.peek(), None | Some(Token::Char(']'))) {
+                        index.push_str(&self.parse_scripted());
+                    }
+                    self.pos += 1;
+                    Some(index)
+                } else {
+                    None
+                };
+                let radicand = self.parse_argument();
+                match index {
+                    Some(index) => format!("<mroot>{}{}</mroot>", wrap(&radicand), wrap(&index)),
+                    None => format!("<msqrt>{}</msqrt>", radicand),
+                }
+            }
+            "text" | "textrm" | "textbf" => format!("<mtext>{}</mtext>", escape(&self.parse_text_group())),
+            "mathrm" | "operatorname" => {
+                format!("<mi mathvariant=\"normal\">{}</mi>", escape(&self.parse_text_group()))
+            }
+            "underline" => {
+                let base = self.parse_argument();
+                format!("<munder accentunder=\"true\">{}<mo>_</mo></munder>", wrap(&base))
+            }
+            "left" => {
+                let open = self.parse_delimiter();
+                let inner = self.parse_row(&[Stop::Right]);
+                let close = if matches!(self.peek(), Some(Token::Command(n)) if n == "right") {
+                    self.pos += 1;
+                    self.parse_delimiter()
+                } else {
+                    String::new()
+                };
+                format!("<mrow>{}{}{}</mrow>", open, inner, close)
+            }
+            "begin" => self.parse_environment(),
+            "displaystyle" | "textstyle" | "limits" | "nolimits" | "big" | "Big" | "bigg" | "Bigg"
+            | "bigl" | "bigr" | "Bigl" | "Bigr" | "biggl" | "biggr" | "nonumber" | "notag" | "hline" => String::new(),
+            "label" | "tag" => {
+                self.parse_text_group();
+                String::new()
+            }
+            _ => format!("<merror><mtext>\\{}</mtext></merror>", escape(name)),
+        }
+    }
+
+    /// Delimiter after \left or \right; "." is the invisible delimiter
+    fn parse_delimiter(&mut self) -> String {
+        match self.next().cloned() {
+            Some(Token::Char('.')) | None => String::new(),
+            Some(Token::Char(c)) => format!("<mo stretchy=\"true\">{}</mo>", escape(&c.to_string())),
+            Some(Token::Command(name)) => {
+                let symbol = OPERATORS
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map_or(name.as_str(), |(_, s)| *s);
+                format!("<mo stretchy=\"true\">{}</mo>", escape(symbol))
+            }
+            Some(_) => String::new(),
+        }
+    }
+
+    fn parse_environment(&mut self) -> String {
+        let env = self.parse_text_group();
+        // Column spec of array, e.g. {cc|c}
+        if env == "array" && self.peek() == Some(&Token::Open) {
+            self.parse_text_group();
+        }
+
+        let mut rows = Vec::new();
+        let mut cells = Vec::new();
+        loop {
+            cells.push(self.parse_row(&[Stop::Cell, Stop::Close]));
+            match self.next().cloned() {
+                Some(Token::Amp) => {}
+                Some(Token::Command(name)) if name == "\\" => rows.push(std::mem::take(&mut cells)),
+                Some(Token::Command(name)) if name == "end" => {
+                    self.parse_text_group();
+                    break;
+                }
+                _ => break,
+            }
+        }
+        if cells.iter().any(|cell| !cell.is_empty()) {
+            rows.push(cells);
+        }
+
+        let table: String = rows
+            .iter()
+            .map(|row| {
+                let cells: String = row.iter().map(|cell| format!("<mtd>{}</mtd>", cell)).collect();
+                format!("<mtr>{}</mtr>", cells)
+            })
+            .collect();
+        let table = format!("<mtable>{}</mtable>", table);
+
+        let (open, close) = match env.as_str() {
+            "pmatrix" => ("(", ")"),
+            "bmatrix" => ("[", "]"),
+            "Bmatrix" => ("{", "}"),
+            "vmatrix" => ("|", "|"),
+            "Vmatrix" => ("‖", "‖"),
+            "cases" => ("{", ""),
+            _ => ("", ""),
+        };
+        if open.is_empty() && close.is_empty() {
+            return table;
+        }
+        let fence = |s: &str| if s.is_empty() { String::new() } else { format!("<mo>{}</mo>", s) };
+        format!("<mrow>{}{}{}</mrow>", fence(open), table, fence(close))
+    }
+}
+
+/// Script and fraction children must be single elements; wrap anything else in `<mrow>`
+fn wrap(content: &str) -> String {
+    if is_single_element(content) {
+        content.to_string()
+    } else {
+        format!("<mrow>{}</mrow>", content)
+    }
+}
+
+fn is_single_element(content: &str) -> bool {
+    if !content.starts_with('<') || !content.ends_with('>') {
+        return false;
+    }
+    let mut depth = 0;
+    let mut top_level = 0;
+    for tag in content.split('<').skip(1) {
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        if tag.starts_with('/') {
+            depth -= 1;
+        } else {
+            if depth == 0 {
+                top_level += 1;
+            }
+            if !tag.ends_with('/') {
+                depth += 1;
+            }
+        }
+    }
+    top_level == 1
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(latex: &str) -> Vec<String> {
+        validate_latex(latex).into_iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn test_extract_formulas() {
+        let formulas = extract_formulas("面积 $S = \\pi r^2$，价格 \\$5，以及\n$$E = mc^2$$");
+        assert_eq!(
+            formulas,
+            vec![("S = \\pi r^2".to_string(), false), ("E = mc^2".to_string(), true)]
+        );
+
+        let formulas = extract_formulas("```latex\n\\frac{a}{b}\n```");
+        assert_eq!(formulas, vec![("\\frac{a}{b}".to_string(), true)]);
+    }
+
+    #[test]
+    fn test_validate_latex() {
+        assert!(codes("\\frac{a}{b} + \\sqrt[3]{x^2}").is_empty());
+        assert_eq!(codes("\\frac{a}{b"), vec!["unclosed_brace"]);
+        assert_eq!(codes("a}"), vec!["unmatched_brace"]);
+        assert_eq!(codes("x^"), vec!["missing_script"]);
+        assert_eq!(codes("\\left( x"), vec!["unmatched_left_right"]);
+        assert_eq!(
+            codes("\\begin{pmatrix} 1 \\end{bmatrix}"),
+            vec!["mismatched_environment"]
+        );
+        assert_eq!(codes("\\foo x"), vec!["unknown_command"]);
+        assert_eq!(validate_latex("\\foo x")[0].severity, "warning");
+    }
+
+    #[test]
+    fn test_to_mathml() {
+        assert_eq!(
+            to_mathml("\\frac{1}{x^2}", false),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"inline\"><mrow>\
+             <mfrac><mrow><mn>1</mn></mrow><mrow><msup><mi>x</mi><mn>2</mn></msup></mrow></mfrac>\
+             </mrow></math>"
+        );
+        assert!(to_mathml("\\alpha \\leq \\sum_{i=1}^{n} a_i", true).contains("<msubsup><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mrow><mi>n</mi></mrow></msubsup>"));
+        assert!(to_mathml("\\begin{pmatrix} a & b \\\\ c & d \\end{pmatrix}", true)
+            .contains("<mo>(</mo><mtable><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr>"));
+    }
+
+    #[test]
+    fn test_check_formulas_skips_mathml_for_invalid() {
+        let checks = check_formulas("$a^{2$ and $b$", true);
+        assert_eq!(checks.len(), 2);
+        assert!(!checks[0].valid);
+        assert!(checks[0].mathml.is_none());
+        assert!(checks[1].mathml.is_some());
+    }
+}
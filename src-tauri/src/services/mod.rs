@@ -1,4 +1,49 @@
 pub mod llm;
+pub mod provider;
 pub mod openai;
 pub mod anthropic;
 pub mod image;
+pub mod text_stats;
+pub mod capabilities;
+pub mod prompt_lint;
+pub mod experiment;
+pub mod orientation;
+pub mod image_store;
+pub mod http;
+pub mod sse;
+pub mod i18n;
+pub mod provider_status;
+pub mod workspace;
+pub mod layout;
+pub mod recipe;
+pub mod table;
+pub mod math;
+pub mod cli;
+pub mod config_health;
+pub mod budget;
+pub mod ab_test;
+pub mod redaction;
+pub mod lock;
+pub mod extraction;
+pub mod barcode;
+pub mod speech;
+pub mod notebook;
+pub mod rapidocr;
+pub mod inference;
+pub mod progress;
+pub mod settings_schema;
+pub mod annotate;
+pub mod stitch;
+pub mod provider_cache;
+pub mod history_bundle;
+pub mod sync;
+pub mod file_manager;
+pub mod confidence;
+pub mod rerun;
+#[cfg(test)]
+mod provider_mock;
+pub mod glossary;
+pub mod postprocess;
+pub mod share;
+pub mod mail;
+pub mod notion;
@@ -0,0 +1,107 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Local};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use crate::db::history::{self, HistoryRecord};
+use crate::db::settings;
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+
+/// Folder next to the notebook that holds the linked thumbnails
+const ATTACHMENTS_DIR: &str = "attachments";
+
+/// Markdown section for a record: a local-time header with the config
+/// name, the optional thumbnail link and the result
+fn format_entry(record: &HistoryRecord, thumbnail_link: Option<&str>) -> String {
+    let timestamp = DateTime::parse_from_rfc3339(&record.created_at)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| record.created_at.clone());
+
+    let mut entry = format!("## {} · {}\n\n", timestamp, record.config_name);
+    if let Some(link) = thumbnail_link {
+        entry.push_str(&format!("![]({})\n\n", link));
+    }
+    entry.push_str(record.result.trim_end());
+    entry.push('\n');
+    entry
+}
+
+/// Newlines needed after the existing content so the new section starts after a blank line
+fn separator(existing: &str) -> &'static str {
+    if existing.is_empty() || existing.ends_with("\n\n") {
+        ""
+    } else if existing.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    }
+}
+
+/// Write the record's thumbnail into the attachments folder and return its
+/// link relative to the notebook
+fn save_thumbnail(notebook_dir: &Path, record: &HistoryRecord) -> Result<Option<String>, AppError> {
+    let Some(data) = record
+        .image_thumbnail
+        .as_deref()
+        .map(|thumbnail| thumbnail.split_once(',').map_or(thumbnail, |(_, data)| data))
+    else {
+        return Ok(None);
+    };
+    let bytes = BASE64.decode(data).map_err(|e| format!("缩略图数据无效: {}", e))?;
+
+    let file_name = format!("orcapp-{}.jpg", record.id);
+    let dir = notebook_dir.join(ATTACHMENTS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建附件目录失败: {}", e))?;
+    fs::write(dir.join(&file_name), bytes).map_err(|e| format!("保存缩略图失败: {}", e))?;
+    Ok(Some(format!("{}/{}", ATTACHMENTS_DIR, file_name)))
+}
+
+/// Append a history record to the notebook file from settings, creating the
+/// file when needed. Returns the notebook path.
+pub fn append_to_notebook(history_id: i64) -> Result<String, AppError> {
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let notebook_path = app_settings.notebook_path.trim();
+    if notebook_path.is_empty() {
+        return Err(ErrorCode::NotebookNotConfigured.into());
+    }
+    let record = history::get_history_by_id(history_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+
+    let path = Path::new(notebook_path);
+    let notebook_dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(notebook_dir).map_err(|e| format!("创建笔记目录失败: {}", e))?;
+
+    let thumbnail_link = if app_settings.notebook_thumbnails {
+        save_thumbnail(notebook_dir, &record)?
+    } else {
+        None
+    };
+    let entry = format_entry(&record, thumbnail_link.as_deref());
+
+    let existing = fs::read_to_string(path).unwrap_or_default();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("打开笔记文件失败: {}", e))?;
+    file.write_all(format!("{}{}", separator(&existing), entry).as_bytes())
+        .map_err(|e| format!("写入笔记文件失败: {}", e))?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separator() {
+        assert_eq!(separator(""), "");
+        assert_eq!(separator("# Notes"), "\n\n");
+        assert_eq!(separator("# Notes\n"), "\n");
+        assert_eq!(separator("# Notes\n\n"), "");
+    }
+}
@@ -0,0 +1,226 @@
+//! Add results as pages of a Notion database, through an internal integration
+//! the database is shared with.
+
+use reqwest::RequestBuilder;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use crate::db::{history, settings};
+use crate::utils::crypto::{decrypt, encrypt};
+use crate::error::AppError;
+use super::http;
+use super::i18n::ErrorCode;
+
+/// Settings key of the integration token.
+/// `security:` keys can't be written or reset through the generic settings commands.
+pub const TOKEN_KEY: &str = "security:notionToken";
+
+const API_URL: &str = "https://api.notion.com/v1";
+const API_VERSION: &str = "2022-06-28";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Notion limits: blocks per request and characters per rich text object
+const MAX_BLOCKS_PER_REQUEST: usize = 100;
+const MAX_TEXT_CHARS: usize = 2000;
+
+/// Store the integration token encrypted; an empty token removes it
+pub fn set_token(token: &str) -> Result<(), String> {
+    if token.is_empty() {
+        settings::delete_setting(TOKEN_KEY).map_err(|e| e.to_string())
+    } else {
+        settings::set_setting(TOKEN_KEY, &encrypt(token)).map_err(|e| e.to_string())
+    }
+}
+
+pub fn has_token() -> Result<bool, String> {
+    Ok(stored_token()?.is_some())
+}
+
+fn stored_token() -> Result<Option<String>, String> {
+    let value = settings::get_setting(TOKEN_KEY).map_err(|e| e.to_string())?;
+    Ok(value.and_then(|value| decrypt(&value).ok()).filter(|value| !value.is_empty()))
+}
+
+/// Add a history record's result to the database and return the page link
+pub async fn export_history(history_id: i64) -> Result<String, AppError> {
+    let record = history::get_history_by_id(history_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+    let title = page_title(record.image_path.as_deref(), &record.created_at);
+    create_page(&title, record.reviewed_result.as_deref().unwrap_or(&record.result)).await
+}
+
+/// A result to add to the database after its recognition has returned
+#[derive(Debug, Clone)]
+pub struct PendingPage {
+    pub title: String,
+    pub content: String,
+}
+
+/// Outcome of a background export, sent to the frontend as `notion-export`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotionExport {
+    pub title: String,
+    /// Link of the new page
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+impl PendingPage {
+    pub async fn export(self) -> NotionExport {
+        let (url, error) = match create_page(&self.title, &self.content).await {
+            Ok(url) => (Some(url), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        NotionExport { title: self.title, url, error }
+    }
+}
+
+/// Source file name of the image, or the time of the recognition
+pub fn page_title(source_path: Option<&str>, time: &str) -> String {
+    source_path
+        .and_then(|path| std::path::Path::new(path).file_name())
+        .and_then(|name| name.to_str())
+        .map_or_else(|| format!("OCR {}", time), str::to_string)
+}
+
+/// Create a page titled `title` in the database from settings, with `content`
+/// as its body, and return the page link
+pub async fn create_page(title: &str, content: &str) -> Result<String, AppError> {
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let database_id = app_settings.notion_database_id.trim().replace('-', "");
+    let token = stored_token()?;
+    let (Some(token), false) = (token, database_id.is_empty()) else {
+        return Err(ErrorCode::NotionNotConfigured.into());
+    };
+    let client = http::service_client(REQUEST_TIMEOUT).map_err(|detail| ErrorCode::NotionFailed { detail })?;
+    let request = |builder: RequestBuilder| builder.bearer_auth(&token).header("Notion-Version", API_VERSION);
+
+    // The title property is named by the database's owner
+    let database = send(request(client.get(format!("{}/databases/{}", API_URL, database_id)))).await?;
+    let title_property = title_property(&database)
+        .ok_or_else(|| ErrorCode::NotionFailed { detail: "the database has no title property".to_string() })?;
+
+    let blocks = to_blocks(content);
+    let mut batches = blocks.chunks(MAX_BLOCKS_PER_REQUEST);
+    let page = send(request(client.post(format!("{}/pages", API_URL))).json(&json!({
+        "parent": { "database_id": database_id },
+        "properties": { title_property: { "title": rich_text(title) } },
+        "children": batches.next().unwrap_or_default(),
+    })))
+    .await?;
+    let page_id = page["id"].as_str().unwrap_or_default();
+    for batch in batches {
+        send(request(client.patch(format!("{}/blocks/{}/children", API_URL, page_id))).json(&json!({ "children": batch }))).await?;
+    }
+    page["url"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| ErrorCode::NotionFailed { detail: "no page link in the response".to_string() }.into())
+}
+
+async fn send(request: RequestBuilder) -> Result<Value, AppError> {
+    let response = request.send().await.map_err(|e| ErrorCode::NotionFailed { detail: e.to_string() })?;
+    let status = response.status();
+    let body: Value = response.json().await.map_err(|e| ErrorCode::NotionFailed { detail: e.to_string() })?;
+    if !status.is_success() {
+        let detail = format!("HTTP {}: {}", status.as_u16(), body["message"].as_str().unwrap_or_default());
+        return Err(ErrorCode::NotionFailed { detail }.into());
+    }
+    Ok(body)
+}
+
+fn title_property(database: &Value) -> Option<String> {
+    database["properties"]
+        .as_object()?
+        .iter()
+        .find(|(_, property)| property["type"] == "title")
+        .map(|(name, _)| name.clone())
+}
+
+/// Notion blocks for Markdown text: headings, list items, fenced code and
+/// paragraphs. Other Markdown stays as plain text in paragraphs.
+fn to_blocks(content: &str) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            match code.take() {
+                Some(lines) => blocks.push(block("code", &lines.join("\n"))),
+                None => code = Some(Vec::new()),
+            }
+            continue;
+        }
+        if let Some(lines) = code.as_mut() {
+            lines.push(line);
+            continue;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (kind, text) = if let Some(text) = line.strip_prefix("### ") {
+            ("heading_3", text)
+        } else if let Some(text) = line.strip_prefix("## ") {
+            ("heading_2", text)
+        } else if let Some(text) = line.strip_prefix("# ") {
+            ("heading_1", text)
+        } else if let Some(text) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            ("bulleted_list_item", text)
+        } else {
+            ("paragraph", line)
+        };
+        blocks.push(block(kind, text));
+    }
+    // An unclosed fence keeps the rest as code
+    if let Some(lines) = code {
+        blocks.push(block("code", &lines.join("\n")));
+    }
+    blocks
+}
+
+fn block(kind: &str, text: &str) -> Value {
+    let mut body = json!({ "rich_text": rich_text(text) });
+    if kind == "code" {
+        body["language"] = json!("plain text");
+    }
+    json!({ "object": "block", "type": kind, kind: body })
+}
+
+/// Text split into rich text objects within Notion's length limit
+fn rich_text(text: &str) -> Vec<Value> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(MAX_TEXT_CHARS)
+        .map(|chunk| json!({ "type": "text", "text": { "content": chunk.iter().collect::<String>() } }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_blocks() {
+        let blocks = to_blocks("# Invoice\n\nTotal: 42\n- item one\n```\nlet x = 1;\n\n```\n## Notes");
+        let kinds: Vec<&str> = blocks.iter().map(|block| block["type"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec!["heading_1", "paragraph", "bulleted_list_item", "code", "heading_2"]);
+        assert_eq!(blocks[2]["bulleted_list_item"]["rich_text"][0]["text"]["content"], "item one");
+        assert_eq!(blocks[3]["code"]["rich_text"][0]["text"]["content"], "let x = 1;\n");
+    }
+
+    #[test]
+    fn test_rich_text_limit() {
+        let parts = rich_text(&"字".repeat(MAX_TEXT_CHARS + 5));
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1]["text"]["content"].as_str().unwrap().chars().count(), 5);
+    }
+
+    #[test]
+    fn test_title_property() {
+        let database = json!({ "properties": { "Tags": { "type": "multi_select" }, "案件": { "type": "title" } } });
+        assert_eq!(title_property(&database).as_deref(), Some("案件"));
+        assert_eq!(page_title(Some("/scans/contract.png"), "2024-05-01"), "contract.png");
+        assert_eq!(page_title(None, "2024-05-01 10:00"), "OCR 2024-05-01 10:00");
+    }
+}
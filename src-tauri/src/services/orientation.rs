@@ -0,0 +1,75 @@
+use crate::db::model_config::ModelConfig;
+use super::i18n::ErrorCode;
+use super::image::{downscale_image, rotate_image};
+use super::llm::{self, RecognitionOptions};
+use super::provider::{self, TokenUsage};
+
+const PROBE_MAX_DIMENSION: u32 = 512;
+
+const PROBE_PROMPT: &str = "How many degrees must this image be rotated clockwise so that its text reads upright? \
+Answer with exactly one number: 0, 90, 180 or 270.";
+
+/// Ask the model for the image orientation using a small thumbnail.
+/// Returns the rotated image and its mime type, or None when it is already
+/// upright, with the tokens of the probe. Local engines take no prompt and are
+/// left unprobed.
+pub async fn auto_rotate(
+    config: &ModelConfig,
+    image_base64: &str,
+) -> (Result<Option<(String, String)>, String>, TokenUsage) {
+    if provider::provider_for(&config.provider).is_none() {
+        return (Ok(None), TokenUsage::default());
+    }
+    let thumbnail = match downscale_image(image_base64, PROBE_MAX_DIMENSION) {
+        Ok(thumbnail) => thumbnail,
+        Err(e) => return (Err(e), TokenUsage::default()),
+    };
+    let options = RecognitionOptions {
+        temperature: Some(0.0),
+        max_tokens: Some(8),
+        stream: Some(false),
+        ..Default::default()
+    };
+
+    let result = llm::call_provider(config, &thumbnail, "image/jpeg", PROBE_PROMPT, Some(options), None).await;
+    let usage = TokenUsage {
+        input_tokens: result.input_tokens,
+        output_tokens: result.output_tokens,
+        total_tokens: result.tokens_used,
+    };
+    if !result.success {
+        return (Err(result.error.unwrap_or_else(|| ErrorCode::OrientationFailed.localized())), usage);
+    }
+
+    let rotated = match parse_rotation(result.content.as_deref().unwrap_or_default()) {
+        Some(degrees) if degrees != 0 => rotate_image(image_base64, degrees).map(Some),
+        _ => Ok(None),
+    };
+    (rotated, usage)
+}
+
+fn parse_rotation(answer: &str) -> Option<u32> {
+    let digits: String = answer
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+
+    match digits.parse().ok()? {
+        degrees @ (0 | 90 | 180 | 270) => Some(degrees),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rotation() {
+        assert_eq!(parse_rotation("90"), Some(90));
+        assert_eq!(parse_rotation("Rotate 270 degrees."), Some(270));
+        assert_eq!(parse_rotation("45"), None);
+        assert_eq!(parse_rotation("upright"), None);
+    }
+}
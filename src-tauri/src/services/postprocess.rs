@@ -0,0 +1,79 @@
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use crate::db::postprocess_rule::PostprocessRule;
+use super::i18n::ErrorCode;
+
+/// Compiled size cap, so a pathological pattern fails to save instead of
+/// eating memory on every recognition
+const MAX_COMPILED_BYTES: usize = 1024 * 1024;
+
+/// Outcome of trying a rule on sample text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleTestResult {
+    pub output: String,
+    pub matches: usize,
+}
+
+pub fn compile(pattern: &str) -> Result<Regex, ErrorCode> {
+    RegexBuilder::new(pattern)
+        .size_limit(MAX_COMPILED_BYTES)
+        .build()
+        .map_err(|e| ErrorCode::InvalidRegex { detail: e.to_string() })
+}
+
+/// Replace every match of `pattern` in `sample`
+pub fn test_rule(pattern: &str, replacement: &str, sample: &str) -> Result<RuleTestResult, ErrorCode> {
+    let regex = compile(pattern)?;
+    Ok(RuleTestResult {
+        output: regex.replace_all(sample, replacement).into_owned(),
+        matches: regex.find_iter(sample).count(),
+    })
+}
+
+/// Run `rules` over `text` in order. A rule whose pattern no longer compiles
+/// is skipped, so one broken rule doesn't hold back the others.
+pub fn apply(text: &str, rules: &[PostprocessRule]) -> String {
+    rules.iter().fold(text.to_string(), |text, rule| match compile(&rule.pattern) {
+        Ok(regex) => regex.replace_all(&text, rule.replacement.as_str()).into_owned(),
+        Err(e) => {
+            eprintln!("[Postprocess] Skipping rule {}: {}", rule.name, e.message("en-US"));
+            text
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str) -> PostprocessRule {
+        PostprocessRule {
+            id: 0,
+            name: pattern.to_string(),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            enabled: true,
+            sort_order: 0,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_in_order() {
+        let rules = vec![
+            rule(r"(\d{3})[ .-]?(\d{4})[ .-]?(\d{4})", "$1-$2-$3"),
+            rule(r"(?m)^\s*CONFIDENTIAL\s*\n?", ""),
+            rule("(", "skipped"),
+        ];
+        let text = "CONFIDENTIAL\nCall 138 1234.5678 now";
+        assert_eq!(apply(text, &rules), "Call 138-1234-5678 now");
+    }
+
+    #[test]
+    fn test_test_rule() {
+        let result = test_rule(r"(?P<y>\d{4})/(?P<m>\d{2})", "${y}-${m}", "2024/05 and 2025/12").unwrap();
+        assert_eq!(result, RuleTestResult { output: "2024-05 and 2025-12".to_string(), matches: 2 });
+        assert!(matches!(test_rule("[a-", "", ""), Err(ErrorCode::InvalidRegex { .. })));
+    }
+}
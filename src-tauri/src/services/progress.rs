@@ -0,0 +1,66 @@
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Step a recognition is at, for showing more than a generic spinner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Waiting for a free request slot of the provider
+    Queued,
+    Compressing,
+    Uploading,
+    /// Request sent, the provider hasn't answered yet
+    WaitingFirstToken,
+    Streaming,
+    Saving,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageEvent {
+    pub stage: Stage,
+    /// 1-based position while queued
+    pub queue_position: Option<usize>,
+}
+
+/// Bytes of a request body handed to the connection so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadProgress {
+    pub sent: u64,
+    pub total: u64,
+}
+
+/// Receives the stages and upload progress of one recognition
+#[derive(Clone)]
+pub struct Observer {
+    pub on_stage: Arc<dyn Fn(StageEvent) + Send + Sync>,
+    pub on_upload: Arc<dyn Fn(UploadProgress) + Send + Sync>,
+}
+
+tokio::task_local! {
+    /// Set around a recognition so the requests it makes can report without
+    /// an observer being threaded through every call
+    static OBSERVER: Observer;
+}
+
+/// Run `future` with `observer` receiving its progress
+pub async fn observe<F: Future>(observer: Observer, future: F) -> F::Output {
+    OBSERVER.scope(observer, future).await
+}
+
+/// Observer of the current task, if any
+pub fn current() -> Option<Observer> {
+    OBSERVER.try_with(Observer::clone).ok()
+}
+
+pub fn stage(stage: Stage) {
+    let _ = OBSERVER.try_with(|observer| (observer.on_stage)(StageEvent { stage, queue_position: None }));
+}
+
+pub fn queued(position: usize) {
+    let _ = OBSERVER.try_with(|observer| {
+        (observer.on_stage)(StageEvent { stage: Stage::Queued, queue_position: Some(position) })
+    });
+}
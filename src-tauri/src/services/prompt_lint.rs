@@ -0,0 +1,99 @@
+use serde::Serialize;
+use super::capabilities::ProviderCapabilities;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptLintIssue {
+    pub code: String,
+    /// "warning" or "info"
+    pub severity: String,
+    pub message: String,
+}
+
+const BOUNDING_BOX_KEYWORDS: &[&str] = &[
+    "bounding box", "bbox", "coordinates", "坐标", "边界框", "位置框", "矩形框",
+];
+
+const JSON_KEYWORDS: &[&str] = &["json"];
+
+const LANGUAGE_KEYWORDS: &[(&str, &[&str])] = &[
+    ("zh", &["用中文", "以中文", "中文回答", "中文输出", "简体中文", "in chinese"]),
+    ("en", &["用英文", "以英文", "英文回答", "英文输出", "用英语", "in english"]),
+    ("ja", &["用日文", "以日文", "日文回答", "用日语", "in japanese"]),
+];
+
+/// Flag prompt instructions the selected provider is unlikely to honour
+pub fn lint_prompt(
+    content: &str,
+    capabilities: &ProviderCapabilities,
+    json_mode_enabled: bool,
+) -> Vec<PromptLintIssue> {
+    let lowered = content.to_lowercase();
+    let mentions = |keywords: &[&str]| keywords.iter().any(|k| lowered.contains(k));
+    let mut issues = Vec::new();
+
+    if mentions(BOUNDING_BOX_KEYWORDS) && !capabilities.bounding_boxes {
+        issues.push(PromptLintIssue {
+            code: "bounding_boxes_unsupported".to_string(),
+            severity: "warning".to_string(),
+            message: format!(
+                "提示词要求输出坐标/边界框，但供应商 {} 无法可靠返回位置信息",
+                capabilities.provider
+            ),
+        });
+    }
+
+    if mentions(JSON_KEYWORDS) && !json_mode_enabled {
+        let message = if capabilities.json_mode {
+            "提示词要求 JSON 输出，但未启用 JSON 模式，结果可能夹带多余文本".to_string()
+        } else {
+            format!(
+                "提示词要求 JSON 输出，但供应商 {} 不支持 JSON 模式，建议在提示词中给出完整示例",
+                capabilities.provider
+            )
+        };
+        issues.push(PromptLintIssue {
+            code: "json_mode_disabled".to_string(),
+            severity: "info".to_string(),
+            message,
+        });
+    }
+
+    let languages: Vec<&str> = LANGUAGE_KEYWORDS
+        .iter()
+        .filter(|(_, keywords)| mentions(keywords))
+        .map(|(lang, _)| *lang)
+        .collect();
+    if languages.len() > 1 {
+        issues.push(PromptLintIssue {
+            code: "conflicting_languages".to_string(),
+            severity: "warning".to_string(),
+            message: format!("提示词包含相互冲突的输出语言要求: {}", languages.join(", ")),
+        });
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::capabilities::get_capabilities;
+
+    #[test]
+    fn test_default_prompt_is_clean() {
+        let caps = get_capabilities("openai");
+        assert!(lint_prompt("请识别这张图片的内容，并用中文详细描述。", &caps, false).is_empty());
+    }
+
+    #[test]
+    fn test_flags_common_problems() {
+        let caps = get_capabilities("anthropic");
+        let issues = lint_prompt("Return JSON with bbox coordinates, 用中文回答 in English", &caps, false);
+        let codes: Vec<&str> = issues.iter().map(|i| i.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            ["bounding_boxes_unsupported", "json_mode_disabled", "conflicting_languages"]
+        );
+    }
+}
@@ -0,0 +1,567 @@
+use futures::StreamExt;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use super::anthropic::AnthropicProvider;
+use super::confidence;
+use super::http::shared_client;
+use super::i18n::ErrorCode;
+use super::llm::{AdapterConfig, RecognitionOptions, RecognitionResult};
+use super::openai::OpenAiProvider;
+use super::progress::{self, Observer, Stage, StageEvent, UploadProgress};
+use super::sse::{SseDecoder, SseEvent};
+
+/// An image attached to a request
+#[derive(Debug, Clone, Copy)]
+pub struct ImagePart<'a> {
+    pub base64: &'a str,
+    pub mime_type: &'a str,
+}
+
+/// A provider-specific HTTP request: extra headers plus the JSON body
+pub struct ProviderRequest {
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Value,
+}
+
+/// Token usage as reported by a provider; any field may be missing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub total_tokens: Option<i32>,
+}
+
+impl TokenUsage {
+    /// Reported total, falling back to input + output
+    pub fn total(&self) -> Option<i32> {
+        match (self.total_tokens, self.input_tokens, self.output_tokens) {
+            (Some(total), _, _) => Some(total),
+            (None, None, None) => None,
+            (None, input, output) => Some(input.unwrap_or(0) + output.unwrap_or(0)),
+        }
+    }
+}
+
+/// Upload chunk size, small enough for a smooth progress bar
+const UPLOAD_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Chunks of a request body that report their progress to `observer` as the
+/// connection takes them, at most once per percent
+fn progress_stream(
+    body: Vec<u8>,
+    observer: Observer,
+) -> impl futures::Stream<Item = Result<Vec<u8>, std::io::Error>> {
+    let total = body.len() as u64;
+    let chunks: Vec<Vec<u8>> = body.chunks(UPLOAD_CHUNK_BYTES).map(<[u8]>::to_vec).collect();
+    let mut sent = 0u64;
+    let mut reported_percent = None;
+    futures::stream::iter(chunks).map(move |chunk| {
+        sent += chunk.len() as u64;
+        let percent = sent * 100 / total.max(1);
+        if reported_percent != Some(percent) {
+            reported_percent = Some(percent);
+            (observer.on_upload)(UploadProgress { sent, total });
+            if sent == total {
+                (observer.on_stage)(StageEvent { stage: Stage::WaitingFirstToken, queue_position: None });
+            }
+        }
+        Ok(chunk)
+    })
+}
+
+pub struct ParsedResponse {
+    pub content: String,
+    /// Reasoning returned apart from the answer, if any
+    pub reasoning: Option<String>,
+    pub usage: TokenUsage,
+    /// Log probabilities of the answer's tokens, when requested and returned
+    pub token_logprobs: Option<Vec<f64>>,
+}
+
+/// Text carried by one stream event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamDelta {
+    /// Part of the answer
+    Text(String),
+    /// Part of the reasoning, kept out of the answer
+    Reasoning(String),
+}
+
+/// Wire format of a vision-capable chat API.
+///
+/// Implementations only translate between our types and the provider's JSON;
+/// sending, streaming and transport errors are handled once in [`call`].
+pub trait VisionProvider: Send + Sync {
+    /// Request asking `prompt` about `images`, in the order given
+    fn build_request(
+        &self,
+        config: &AdapterConfig,
+        images: &[ImagePart],
+        prompt: &str,
+        options: &RecognitionOptions,
+        stream: bool,
+    ) -> ProviderRequest;
+
+    /// Smallest text-only request used to check a configuration
+    fn build_test_request(&self, config: &AdapterConfig) -> ProviderRequest;
+
+    fn parse_response(&self, data: &Value) -> Result<ParsedResponse, ErrorCode>;
+
+    /// Whether a successful test response has the expected shape
+    fn is_valid_test_response(&self, data: &Value) -> bool;
+
+    /// Apply a stream event to `usage` and return the delta it carries, if any
+    fn parse_stream_event(&self, event: &SseEvent, usage: &mut TokenUsage) -> Option<StreamDelta>;
+
+    fn parse_error(&self, status: u16, body: &str) -> ErrorCode {
+        default_error_code(status, body)
+    }
+
+    /// Headers authenticating requests with the config's key
+    fn auth_headers(&self, config: &AdapterConfig) -> Vec<(&'static str, String)>;
+
+    /// Endpoint listing the models available to the key, derived from the chat endpoint
+    fn models_url(&self, config: &AdapterConfig) -> String;
+
+    /// Model ids from a model list response; OpenAI and Anthropic both use `{"data": [{"id": ...}]}`
+    fn parse_models(&self, data: &Value) -> Option<Vec<String>> {
+        data["data"]
+            .as_array()
+            .map(|models| models.iter().filter_map(|m| m["id"].as_str().map(str::to_string)).collect())
+    }
+}
+
+/// Replace the `suffix` of an endpoint URL by `/models`, or append it when the URL doesn't end in `suffix`
+pub fn sibling_models_url(api_url: &str, suffix: &str) -> String {
+    let base = api_url.trim_end_matches('/');
+    format!("{}/models", base.strip_suffix(suffix).unwrap_or(base))
+}
+
+/// Look up the wire format for a provider type
+pub fn provider_for(provider: &str) -> Option<&'static dyn VisionProvider> {
+    match provider {
+        "openai" | "azure" | "oneapi" | "custom" => Some(&OpenAiProvider),
+        "anthropic" => Some(&AnthropicProvider),
+        _ => None,
+    }
+}
+
+/// Error code shared by providers for the common HTTP statuses
+pub fn default_error_code(status: u16, body: &str) -> ErrorCode {
+    match status {
+        401 => ErrorCode::InvalidApiKey,
+        404 => ErrorCode::EndpointNotFound,
+        429 => ErrorCode::RateLimited,
+        _ => {
+            // Try to extract error message from response
+            if let Ok(data) = serde_json::from_str::<Value>(body) {
+                if let Some(msg) = data["error"]["message"].as_str() {
+                    return ErrorCode::ProviderMessage { status, message: msg.to_string() };
+                }
+            }
+            ErrorCode::ServerError { status, body: body.to_string() }
+        }
+    }
+}
+
+fn transport_error(e: &reqwest::Error) -> ErrorCode {
+    if e.is_timeout() {
+        ErrorCode::RequestTimeout
+    } else if e.is_connect() {
+        ErrorCode::ConnectFailed
+    } else {
+        ErrorCode::RequestFailed { detail: e.to_string() }
+    }
+}
+
+pub async fn call(
+    provider: &dyn VisionProvider,
+    config: &AdapterConfig,
+    images: &[ImagePart<'_>],
+    prompt: &str,
+    options: &RecognitionOptions,
+    callback: Option<Box<dyn Fn(String) + Send + Sync>>,
+) -> RecognitionResult {
+    let start_time = Instant::now();
+
+    if images.is_empty() || images.iter().any(|image| image.base64.is_empty()) {
+        return RecognitionResult::from_code(ErrorCode::EmptyImage, None);
+    }
+
+    let client = match shared_client(Duration::from_secs(120), config.skip_tls_verify) {
+        Ok(client) => client,
+        Err(detail) => return RecognitionResult::from_code(ErrorCode::HttpClientFailed { detail }, None),
+    };
+
+    let is_streaming = options.stream.unwrap_or(false) && callback.is_some();
+    let request = provider.build_request(config, images, prompt, options, is_streaming);
+
+    let mut builder = client
+        .post(&config.api_url)
+        .header("Content-Type", "application/json");
+    for (name, value) in &request.headers {
+        builder = builder.header(*name, value);
+    }
+    progress::stage(Stage::Uploading);
+    let response = match progress::current() {
+        Some(observer) => {
+            let body = serde_json::to_vec(&request.body).unwrap_or_default();
+            // A streamed body would otherwise go out chunked, which some gateways reject
+            builder = builder.header(reqwest::header::CONTENT_LENGTH, body.len());
+            builder.body(reqwest::Body::wrap_stream(progress_stream(body, observer))).send().await
+        }
+        None => builder.json(&request.body).send().await,
+    };
+
+    let duration_ms = start_time.elapsed().as_millis() as i64;
+
+    let resp = match response {
+        Ok(resp) => resp,
+        Err(e) => return RecognitionResult::from_code(transport_error(&e), Some(duration_ms)),
+    };
+
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let error_text = resp.text().await.unwrap_or_default();
+        return RecognitionResult::from_code(provider.parse_error(status, &error_text), Some(duration_ms));
+    }
+
+    if is_streaming {
+        let mut full_content = String::new();
+        let mut reasoning = String::new();
+        let mut usage = TokenUsage::default();
+        let mut stream = resp.bytes_stream();
+        let mut decoder = SseDecoder::new();
+        // Reasoning counts as the first token: the model is generating
+        let mut first_token: Option<Instant> = None;
+
+        let mut handle_event = |event: &SseEvent| match provider.parse_stream_event(event, &mut usage) {
+            Some(StreamDelta::Text(delta)) if !delta.is_empty() => {
+                first_token.get_or_insert_with(Instant::now);
+                if full_content.is_empty() {
+                    progress::stage(Stage::Streaming);
+                }
+                full_content.push_str(&delta);
+                if let Some(cb) = &callback {
+                    cb(delta);
+                }
+            }
+            Some(StreamDelta::Reasoning(delta)) if !delta.is_empty() => {
+                first_token.get_or_insert_with(Instant::now);
+                reasoning.push_str(&delta);
+            }
+            _ => {}
+        };
+
+        while let Some(item) = stream.next().await {
+            if let Ok(chunk) = item {
+                for event in decoder.feed(&chunk) {
+                    handle_event(&event);
+                }
+            }
+        }
+
+        // Flush an event left without a trailing blank line
+        for event in decoder.finish() {
+            handle_event(&event);
+        }
+
+        let reasoning = (!reasoning.is_empty()).then_some(reasoning);
+        let mut result = success(full_content, reasoning, usage, duration_ms);
+        if let Some(first_token) = first_token {
+            result.ttft_ms = Some(first_token.duration_since(start_time).as_millis() as i64);
+            result.tokens_per_second = tokens_per_second(usage.output_tokens, first_token.elapsed());
+        }
+        return result;
+    }
+
+    match resp.json::<Value>().await {
+        Ok(data) => match provider.parse_response(&data) {
+            Ok(parsed) => {
+                let mut result = success(parsed.content, parsed.reasoning, parsed.usage, duration_ms);
+                result.confidence = parsed.token_logprobs.as_deref().and_then(confidence::from_logprobs);
+                result
+            }
+            Err(code) => RecognitionResult::from_code(code, Some(duration_ms)),
+        },
+        Err(e) => RecognitionResult::from_code(
+            ErrorCode::InvalidResponse { detail: e.to_string() },
+            Some(duration_ms),
+        ),
+    }
+}
+
+fn success(content: String, reasoning: Option<String>, usage: TokenUsage, duration_ms: i64) -> RecognitionResult {
+    RecognitionResult {
+        success: true,
+        content: Some(content),
+        reasoning,
+        tokens_used: usage.total(),
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        duration_ms: Some(duration_ms),
+        ..Default::default()
+    }
+}
+
+/// Output tokens per second from the first streamed token to the end of the
+/// stream; None when the provider reported no output tokens
+fn tokens_per_second(output_tokens: Option<i32>, streaming: Duration) -> Option<f64> {
+    let secs = streaming.as_secs_f64();
+    match output_tokens {
+        Some(tokens) if tokens > 0 && secs > 0.0 => Some(tokens as f64 / secs),
+        _ => None,
+    }
+}
+
+pub async fn test_connection(provider: &dyn VisionProvider, config: &AdapterConfig) -> Result<(), ErrorCode> {
+    let client = shared_client(Duration::from_secs(30), config.skip_tls_verify)
+        .map_err(|detail| ErrorCode::HttpClientFailed { detail })?;
+
+    let request = provider.build_test_request(config);
+    let mut builder = client
+        .post(&config.api_url)
+        .header("Content-Type", "application/json");
+    for (name, value) in &request.headers {
+        builder = builder.header(*name, value);
+    }
+
+    let resp = builder.json(&request.body).send().await.map_err(|e| transport_error(&e))?;
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let error_text = resp.text().await.unwrap_or_default();
+        return Err(provider.parse_error(status, &error_text));
+    }
+
+    match resp.json::<Value>().await {
+        Ok(data) if provider.is_valid_test_response(&data) => Ok(()),
+        Ok(_) => Err(ErrorCode::UnexpectedResponse),
+        Err(e) => Err(ErrorCode::InvalidResponse { detail: e.to_string() }),
+    }
+}
+
+/// Ids of the models available to the config's key, sorted
+pub async fn list_models(provider: &dyn VisionProvider, config: &AdapterConfig) -> Result<Vec<String>, ErrorCode> {
+    let client = shared_client(Duration::from_secs(30), config.skip_tls_verify)
+        .map_err(|detail| ErrorCode::HttpClientFailed { detail })?;
+
+    let mut builder = client.get(provider.models_url(config));
+    for (name, value) in provider.auth_headers(config) {
+        builder = builder.header(name, value);
+    }
+
+    let resp = builder.send().await.map_err(|e| transport_error(&e))?;
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let error_text = resp.text().await.unwrap_or_default();
+        return Err(provider.parse_error(status, &error_text));
+    }
+
+    let data = resp
+        .json::<Value>()
+        .await
+        .map_err(|e| ErrorCode::InvalidResponse { detail: e.to_string() })?;
+    let mut models = provider.parse_models(&data).ok_or(ErrorCode::UnexpectedResponse)?;
+    models.sort();
+    models.dedup();
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn replay(provider: &dyn VisionProvider, fixture: &str) -> (String, TokenUsage) {
+        let mut decoder = SseDecoder::new();
+        let mut events = decoder.feed(fixture.as_bytes());
+        events.extend(decoder.finish());
+
+        let mut content = String::new();
+        let mut usage = TokenUsage::default();
+        for event in &events {
+            if let Some(StreamDelta::Text(delta)) = provider.parse_stream_event(event, &mut usage) {
+                content.push_str(&delta);
+            }
+        }
+        (content, usage)
+    }
+
+    #[test]
+    fn test_openai_stream() {
+        let (content, usage) = replay(&OpenAiProvider, include_str!("fixtures/openai_stream.txt"));
+        assert_eq!(content, "图片中的文字：Hello 世界");
+        assert_eq!(usage.input_tokens, Some(812));
+        assert_eq!(usage.output_tokens, Some(9));
+        assert_eq!(usage.total(), Some(821));
+    }
+
+    #[test]
+    fn test_anthropic_stream() {
+        let (content, usage) = replay(&AnthropicProvider, include_str!("fixtures/anthropic_stream.txt"));
+        assert_eq!(content, "识别结果：こんにちは");
+        assert_eq!(usage.input_tokens, Some(1534));
+        assert_eq!(usage.output_tokens, Some(12));
+        assert_eq!(usage.total(), Some(1546));
+    }
+
+    #[test]
+    fn test_upload_progress() {
+        let reports = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let stages = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let (observed, observed_stages) = (reports.clone(), stages.clone());
+        let observer = Observer {
+            on_stage: Arc::new(move |event| observed_stages.lock().push(event.stage)),
+            on_upload: Arc::new(move |progress| observed.lock().push(progress)),
+        };
+        let stream = progress_stream(vec![0; 150 * 1024], observer);
+
+        let chunks: Vec<Vec<u8>> = futures::executor::block_on(stream.map(Result::unwrap).collect());
+        assert_eq!(chunks.len(), 3);
+        let reports = reports.lock();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0], UploadProgress { sent: 64 * 1024, total: 150 * 1024 });
+        assert_eq!(reports[2].sent, reports[2].total);
+        assert_eq!(*stages.lock(), vec![Stage::WaitingFirstToken]);
+    }
+
+    #[test]
+    fn test_models_url() {
+        let config = |api_url: &str| AdapterConfig {
+            api_url: api_url.to_string(),
+            api_key: String::new(),
+            model_name: String::new(),
+            max_tokens: 0,
+            reasoning_model: false,
+            thinking_budget_tokens: None,
+            organization: None,
+            project: None,
+            skip_tls_verify: false,
+        };
+        assert_eq!(
+            OpenAiProvider.models_url(&config("https://api.openai.com/v1/chat/completions")),
+            "https://api.openai.com/v1/models"
+        );
+        assert_eq!(
+            AnthropicProvider.models_url(&config("https://api.anthropic.com/v1/messages/")),
+            "https://api.anthropic.com/v1/models"
+        );
+        assert_eq!(OpenAiProvider.models_url(&config("https://gateway.local/v1")), "https://gateway.local/v1/models");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(
+            OpenAiProvider.parse_error(403, r#"{"error":{"message":"denied"}}"#),
+            ErrorCode::ProviderMessage { status: 403, message: "denied".to_string() }
+        );
+        assert_eq!(AnthropicProvider.parse_error(403, ""), ErrorCode::ApiKeyForbidden);
+        assert_eq!(AnthropicProvider.parse_error(401, ""), ErrorCode::InvalidApiKey);
+    }
+
+    #[test]
+    fn test_tokens_per_second() {
+        assert_eq!(tokens_per_second(Some(120), Duration::from_secs(4)), Some(30.0));
+        assert_eq!(tokens_per_second(None, Duration::from_secs(4)), None);
+        assert_eq!(tokens_per_second(Some(120), Duration::ZERO), None);
+    }
+
+    fn reasoning_config() -> AdapterConfig {
+        AdapterConfig {
+            api_url: String::new(),
+            api_key: String::new(),
+            model_name: "o3".to_string(),
+            max_tokens: 2048,
+            reasoning_model: true,
+            thinking_budget_tokens: Some(3000),
+            organization: None,
+            project: None,
+            skip_tls_verify: false,
+        }
+    }
+
+    #[test]
+    fn test_reasoning_requests() {
+        let options = RecognitionOptions { temperature: Some(0.2), top_p: Some(0.4), ..Default::default() };
+        let image = ImagePart { base64: "aGVsbG8=", mime_type: "image/png" };
+
+        let body = OpenAiProvider.build_request(&reasoning_config(), &[image], "Read", &options, false).body;
+        assert_eq!(body["max_completion_tokens"], 2048);
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("temperature").is_none() && body.get("top_p").is_none());
+        assert_eq!(OpenAiProvider.build_test_request(&reasoning_config()).body["max_completion_tokens"], 5);
+
+        let body = AnthropicProvider.build_request(&reasoning_config(), &[image], "Read", &options, false).body;
+        assert_eq!(body["thinking"]["budget_tokens"], 3000);
+        assert_eq!(body["max_tokens"], 5048);
+        assert!(body.get("temperature").is_none() && body.get("top_p").is_none());
+    }
+
+    #[test]
+    fn test_few_shot_examples_sent_as_prior_turns() {
+        let example = super::super::llm::FewShotExample {
+            base64: "ZXhhbXBsZQ==".to_string(),
+            mime_type: "image/png".to_string(),
+            output: "Total: 3.00".to_string(),
+        };
+        let options = RecognitionOptions { examples: vec![example], ..Default::default() };
+        let image = ImagePart { base64: "aGVsbG8=", mime_type: "image/png" };
+
+        for body in [
+            OpenAiProvider.build_request(&reasoning_config(), &[image], "Read", &options, false).body,
+            AnthropicProvider.build_request(&reasoning_config(), &[image], "Read", &options, false).body,
+        ] {
+            let roles: Vec<&str> = body["messages"].as_array().unwrap().iter().map(|m| m["role"].as_str().unwrap()).collect();
+            assert_eq!(roles, ["user", "assistant", "user"]);
+            assert_eq!(body["messages"][1]["content"], "Total: 3.00");
+            assert!(body["messages"][0].to_string().contains("ZXhhbXBsZQ=="));
+            assert!(body["messages"][2].to_string().contains("aGVsbG8="));
+        }
+    }
+
+    #[test]
+    fn test_openai_organization_headers() {
+        let header = |config: &AdapterConfig, name: &str| {
+            OpenAiProvider.auth_headers(config).into_iter().find(|(key, _)| *key == name).map(|(_, value)| value)
+        };
+        let mut config = reasoning_config();
+        assert_eq!(header(&config, "OpenAI-Organization"), None);
+
+        config.organization = Some("org-123".to_string());
+        config.project = Some("proj_456".to_string());
+        assert_eq!(header(&config, "OpenAI-Organization").as_deref(), Some("org-123"));
+        assert_eq!(header(&config, "OpenAI-Project").as_deref(), Some("proj_456"));
+        assert_eq!(AnthropicProvider.auth_headers(&config).len(), 2);
+    }
+
+    #[test]
+    fn test_reasoning_kept_out_of_content() {
+        let data = serde_json::json!({
+            "content": [
+                { "type": "thinking", "thinking": "The image shows a receipt.", "signature": "sig" },
+                { "type": "text", "text": "Total: 12.50" }
+            ],
+            "usage": { "input_tokens": 10, "output_tokens": 20 }
+        });
+        let parsed = AnthropicProvider.parse_response(&data).unwrap();
+        assert_eq!(parsed.content, "Total: 12.50");
+        assert_eq!(parsed.reasoning.as_deref(), Some("The image shows a receipt."));
+
+        let event = |data: &str| SseEvent { event: None, data: data.to_string() };
+        let mut usage = TokenUsage::default();
+        assert_eq!(
+            AnthropicProvider.parse_stream_event(
+                &event(r#"{"type":"content_block_delta","delta":{"type":"thinking_delta","thinking":"Hmm"}}"#),
+                &mut usage
+            ),
+            Some(StreamDelta::Reasoning("Hmm".to_string()))
+        );
+        assert_eq!(
+            OpenAiProvider.parse_stream_event(
+                &event(r#"{"choices":[{"delta":{"content":null,"reasoning_content":"Hmm"}}]}"#),
+                &mut usage
+            ),
+            Some(StreamDelta::Reasoning("Hmm".to_string()))
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Provider responses kept in the database for a while, so opening the config
+//! editor or restarting the app doesn't send a request per config each time.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::db::model_config::get_config_by_id;
+use crate::db::provider_cache::{self, HEALTH, MODELS};
+use super::config_health::ConfigHealth;
+use super::i18n::ErrorCode;
+use super::llm::AdapterConfig;
+use super::provider;
+
+/// Model lists rarely change
+const MODELS_TTL_SECS: i64 = 24 * 60 * 60;
+/// Health results older than this are checked again rather than shown
+const HEALTH_TTL_SECS: i64 = 15 * 60;
+
+fn load<T: DeserializeOwned>(config_id: i64, kind: &str, max_age_secs: i64) -> Option<T> {
+    match provider_cache::get_entry(config_id, kind, max_age_secs) {
+        Ok(value) => value.and_then(|v| serde_json::from_str(&v).ok()),
+        Err(e) => {
+            eprintln!("[ProviderCache] Failed to read {}: {}", kind, e);
+            None
+        }
+    }
+}
+
+fn store<T: Serialize>(config_id: i64, kind: &str, value: &T) {
+    let result = serde_json::to_string(value)
+        .map_err(|e| e.to_string())
+        .and_then(|json| provider_cache::set_entry(config_id, kind, &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("[ProviderCache] Failed to store {}: {}", kind, e);
+    }
+}
+
+/// Models available to a config's key, from the cache unless `refresh` is set
+/// or the cached list expired
+pub async fn list_models(config_id: i64, refresh: bool) -> Result<Vec<String>, ErrorCode> {
+    if !refresh {
+        if let Some(models) = load(config_id, MODELS, MODELS_TTL_SECS) {
+            return Ok(models);
+        }
+    }
+
+    let config = match get_config_by_id(config_id) {
+        Ok(Some(c)) => c,
+        Ok(None) => return Err(ErrorCode::ConfigNotFound),
+        Err(e) => return Err(ErrorCode::ConfigLoadFailed { detail: e.to_string() }),
+    };
+    let vision_provider = provider::provider_for(&config.provider)
+        .ok_or_else(|| ErrorCode::UnsupportedProvider { provider: config.provider.clone() })?;
+    let models = provider::list_models(vision_provider, &AdapterConfig::from(&config)).await?;
+    store(config_id, MODELS, &models);
+    Ok(models)
+}
+
+/// Last health check of a config if it is recent enough to show
+pub fn load_health(config_id: i64) -> Option<ConfigHealth> {
+    load(config_id, HEALTH, HEALTH_TTL_SECS)
+}
+
+pub fn store_health(health: &ConfigHealth) {
+    store(health.config_id, HEALTH, health);
+}
+
+/// Drop cached responses of a config whose URL, key or model may have changed
+pub fn invalidate(config_id: i64) {
+    if let Err(e) = provider_cache::delete_entries(config_id) {
+        eprintln!("[ProviderCache] Failed to clear config {}: {}", config_id, e);
+    }
+}
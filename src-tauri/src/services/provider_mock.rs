@@ -0,0 +1,381 @@
+//! A local HTTP server standing in for a provider, so requests, streaming and
+//! error mapping can be tested end to end without live keys.
+
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use crate::db::connection::init_test_database;
+use super::anthropic::AnthropicProvider;
+use super::i18n::ErrorCode;
+use super::llm::{AdapterConfig, ImageDetail, RecognitionOptions, RecognitionResult};
+use super::openai::OpenAiProvider;
+use super::provider::{self, ImagePart, VisionProvider};
+
+/// Bytes written per socket write, so streams arrive split mid-event like they do over the network
+const WRITE_CHUNK_BYTES: usize = 61;
+/// A tiny image sent with test requests
+const IMAGE: ImagePart = ImagePart { base64: "aGVsbG8=", mime_type: "image/png" };
+
+#[derive(Clone)]
+pub struct MockResponse {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl MockResponse {
+    pub fn json(status: u16, body: &str) -> Self {
+        Self { status, content_type: "application/json", body: body.to_string() }
+    }
+
+    pub fn sse(body: &str) -> Self {
+        Self { status: 200, content_type: "text/event-stream", body: body.to_string() }
+    }
+}
+
+/// A request as received by the mock server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    /// Header names in lowercase
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Answer requests with `responses` in order, repeating the last one
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let mut responses = VecDeque::from(responses);
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let Some(request) = read_request(&mut stream).await else {
+                    continue;
+                };
+                recorded.lock().push(request);
+                let response = match responses.len() {
+                    1 => responses.front().cloned(),
+                    _ => responses.pop_front(),
+                };
+                if let Some(response) = response {
+                    let _ = write_response(&mut stream, &response).await;
+                }
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 8192];
+    let header_end = loop {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let path = lines.next()?.split_whitespace().nth(1)?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while data.len() < header_end + length {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+
+    let body = serde_json::from_slice(&data[header_end..]).unwrap_or(Value::Null);
+    Some(RecordedRequest { path, headers, body })
+}
+
+async fn write_response(stream: &mut TcpStream, response: &MockResponse) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    for chunk in response.body.as_bytes().chunks(WRITE_CHUNK_BYTES) {
+        stream.write_all(chunk).await?;
+        stream.flush().await?;
+    }
+    stream.shutdown().await
+}
+
+fn adapter_config(api_url: String) -> AdapterConfig {
+    AdapterConfig {
+        api_url,
+        api_key: "sk-test".to_string(),
+        model_name: "test-model".to_string(),
+        max_tokens: 1024,
+        reasoning_model: false,
+        thinking_budget_tokens: None,
+        organization: None,
+        project: None,
+        skip_tls_verify: false,
+    }
+}
+
+/// Recognize a tiny image through `provider`, collecting the streamed deltas
+async fn recognize(
+    provider: &dyn VisionProvider,
+    server: &MockServer,
+    path: &str,
+    stream: bool,
+) -> (RecognitionResult, Vec<String>) {
+    init_test_database();
+    let deltas = Arc::new(Mutex::new(Vec::new()));
+    let collected = deltas.clone();
+    let options = RecognitionOptions { stream: Some(stream), ..Default::default() };
+    let result = provider::call(
+        provider,
+        &adapter_config(server.url(path)),
+        &[IMAGE],
+        "Read the text",
+        &options,
+        Some(Box::new(move |delta| collected.lock().push(delta))),
+    )
+    .await;
+    let deltas = deltas.lock().clone();
+    (result, deltas)
+}
+
+#[tokio::test]
+async fn test_openai_streaming() {
+    let server = MockServer::start(vec![MockResponse::sse(include_str!("fixtures/openai_stream.txt"))]).await;
+    let (result, deltas) = recognize(&OpenAiProvider, &server, "/v1/chat/completions", true).await;
+
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.content.as_deref(), Some("图片中的文字：Hello 世界"));
+    assert_eq!(deltas.concat(), "图片中的文字：Hello 世界");
+    assert_eq!(result.tokens_used, Some(821));
+    assert!(result.ttft_ms.is_some());
+
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "/v1/chat/completions");
+    assert_eq!(request.header("authorization"), Some("Bearer sk-test"));
+    assert_eq!(request.body["model"], "test-model");
+    assert_eq!(request.body["stream"], true);
+    assert_eq!(request.body["stream_options"]["include_usage"], true);
+    assert_eq!(
+        request.body["messages"][0]["content"][1]["image_url"]["url"],
+        "data:image/png;base64,aGVsbG8="
+    );
+    assert!(request.body["messages"][0]["content"][1]["image_url"].get("detail").is_none());
+}
+
+#[test]
+fn test_openai_image_detail() {
+    let options = RecognitionOptions { image_detail: Some(ImageDetail::Low), ..Default::default() };
+    let second = ImagePart { base64: "d29ybGQ=", mime_type: "image/jpeg" };
+    let request = OpenAiProvider.build_request(
+        &adapter_config("http://localhost/v1/chat/completions".to_string()),
+        &[IMAGE, second],
+        "What changed?",
+        &options,
+        false,
+    );
+    let content = &request.body["messages"][0]["content"];
+    assert_eq!(content[1]["image_url"]["detail"], "low");
+    assert_eq!(content[2]["image_url"]["url"], "data:image/jpeg;base64,d29ybGQ=");
+    assert_eq!(content[2]["image_url"]["detail"], "low");
+}
+
+#[test]
+fn test_anthropic_images_before_text() {
+    let second = ImagePart { base64: "d29ybGQ=", mime_type: "image/jpeg" };
+    let request = AnthropicProvider.build_request(
+        &adapter_config("http://localhost/v1/messages".to_string()),
+        &[IMAGE, second],
+        "What changed?",
+        &RecognitionOptions::default(),
+        false,
+    );
+    let content = &request.body["messages"][0]["content"];
+    assert_eq!(content[0]["source"]["data"], "aGVsbG8=");
+    assert_eq!(content[1]["source"]["media_type"], "image/jpeg");
+    assert_eq!(content[2]["text"], "What changed?");
+}
+
+#[tokio::test]
+async fn test_anthropic_streaming() {
+    let server = MockServer::start(vec![MockResponse::sse(include_str!("fixtures/anthropic_stream.txt"))]).await;
+    let (result, deltas) = recognize(&AnthropicProvider, &server, "/v1/messages", true).await;
+
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.content.as_deref(), Some("识别结果：こんにちは"));
+    assert_eq!(deltas.concat(), "识别结果：こんにちは");
+    assert_eq!(result.input_tokens, Some(1534));
+    assert_eq!(result.output_tokens, Some(12));
+
+    let request = &server.requests()[0];
+    assert_eq!(request.header("x-api-key"), Some("sk-test"));
+    assert_eq!(request.header("anthropic-version"), Some("2023-06-01"));
+    assert_eq!(request.body["stream"], true);
+}
+
+#[tokio::test]
+async fn test_json_responses() {
+    let server = MockServer::start(vec![MockResponse::json(200, include_str!("fixtures/openai_response.json"))]).await;
+    let (result, deltas) = recognize(&OpenAiProvider, &server, "/v1/chat/completions", false).await;
+    assert_eq!(result.content.as_deref(), Some("图片中的文字：Hello 世界"));
+    assert_eq!(result.tokens_used, Some(821));
+    assert!(deltas.is_empty());
+    assert_eq!(server.requests()[0].body["stream"], false);
+
+    let server = MockServer::start(vec![MockResponse::json(200, include_str!("fixtures/anthropic_response.json"))]).await;
+    let (result, _) = recognize(&AnthropicProvider, &server, "/v1/messages", false).await;
+    assert_eq!(result.content.as_deref(), Some("识别结果：こんにちは"));
+    assert_eq!(result.tokens_used, Some(1546));
+}
+
+#[tokio::test]
+async fn test_error_mapping() {
+    let cases: [(&dyn VisionProvider, u16, &str, ErrorCode); 5] = [
+        (&OpenAiProvider, 401, "", ErrorCode::InvalidApiKey),
+        (&OpenAiProvider, 429, "", ErrorCode::RateLimited),
+        (
+            &OpenAiProvider,
+            400,
+            include_str!("fixtures/openai_error.json"),
+            ErrorCode::ProviderMessage {
+                status: 400,
+                message: "You uploaded an unsupported image. Please make sure your image is below 20 MB \
+                          in size and is of one the following formats: ['png', 'jpeg', 'gif', 'webp']."
+                    .to_string(),
+            },
+        ),
+        (&AnthropicProvider, 403, "", ErrorCode::ApiKeyForbidden),
+        (
+            &AnthropicProvider,
+            529,
+            include_str!("fixtures/anthropic_error.json"),
+            ErrorCode::ProviderMessage { status: 529, message: "Overloaded".to_string() },
+        ),
+    ];
+
+    for (provider, status, body, expected) in cases {
+        let server = MockServer::start(vec![MockResponse::json(status, body)]).await;
+        for stream in [false, true] {
+            let (result, _) = recognize(provider, &server, "/", stream).await;
+            assert!(!result.success);
+            assert_eq!(result.error_code.as_ref(), Some(&expected), "status {} stream {}", status, stream);
+        }
+    }
+
+    let server = MockServer::start(vec![MockResponse::json(502, "<html>Bad Gateway</html>")]).await;
+    let (result, _) = recognize(&OpenAiProvider, &server, "/", false).await;
+    assert_eq!(
+        result.error_code,
+        Some(ErrorCode::ServerError { status: 502, body: "<html>Bad Gateway</html>".to_string() })
+    );
+}
+
+#[tokio::test]
+async fn test_connection_refused() {
+    // Take a free port and close it again so nothing is listening
+    let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    init_test_database();
+    let result = provider::call(
+        &OpenAiProvider,
+        &adapter_config(format!("http://{}/v1/chat/completions", addr)),
+        &[IMAGE],
+        "Read the text",
+        &RecognitionOptions::default(),
+        None,
+    )
+    .await;
+    assert_eq!(result.error_code, Some(ErrorCode::ConnectFailed));
+}
+
+#[tokio::test]
+async fn test_connection_check() {
+    init_test_database();
+    let server = MockServer::start(vec![
+        MockResponse::json(200, include_str!("fixtures/openai_response.json")),
+        MockResponse::json(200, "{}"),
+        MockResponse::json(429, ""),
+    ])
+    .await;
+    let config = adapter_config(server.url("/v1/chat/completions"));
+
+    assert_eq!(provider::test_connection(&OpenAiProvider, &config).await, Ok(()));
+    assert_eq!(
+        provider::test_connection(&OpenAiProvider, &config).await,
+        Err(ErrorCode::UnexpectedResponse)
+    );
+    assert_eq!(
+        provider::test_connection(&OpenAiProvider, &config).await,
+        Err(ErrorCode::RateLimited)
+    );
+    assert_eq!(server.requests()[0].body["max_tokens"], 5);
+}
+
+#[tokio::test]
+async fn test_list_models() {
+    init_test_database();
+    let server = MockServer::start(vec![
+        MockResponse::json(200, r#"{"data": [{"id": "gpt-4o"}, {"id": "gpt-4.1-mini"}, {"id": "gpt-4o"}]}"#),
+        MockResponse::json(401, ""),
+    ])
+    .await;
+    let config = adapter_config(server.url("/v1/chat/completions"));
+
+    assert_eq!(
+        provider::list_models(&OpenAiProvider, &config).await,
+        Ok(vec!["gpt-4.1-mini".to_string(), "gpt-4o".to_string()])
+    );
+    assert_eq!(provider::list_models(&OpenAiProvider, &config).await, Err(ErrorCode::InvalidApiKey));
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "/v1/models");
+    assert_eq!(request.header("authorization"), Some("Bearer sk-test"));
+}
@@ -0,0 +1,144 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use super::http::shared_client;
+use super::i18n::ErrorCode;
+
+/// Consecutive outage-like failures before the provider's status page is consulted
+const FAILURE_THRESHOLD: u32 = 3;
+/// Minimum time between two status page checks of the same provider
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// An incident reported on a provider's public status page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderIncident {
+    /// Statuspage indicator: minor, major or critical
+    pub indicator: String,
+    pub description: String,
+    pub status_page: String,
+}
+
+#[derive(Debug, Default)]
+struct ProviderHealth {
+    consecutive_failures: u32,
+    last_check: Option<Instant>,
+    incident: Option<ProviderIncident>,
+}
+
+static HEALTH: Lazy<Mutex<HashMap<&'static str, ProviderHealth>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Public status page of a provider type; custom endpoints have none
+fn status_page(provider: &str) -> Option<&'static str> {
+    match provider {
+        "openai" => Some("https://status.openai.com"),
+        "anthropic" => Some("https://status.anthropic.com"),
+        _ => None,
+    }
+}
+
+/// Failures that may be caused by the provider rather than the user's setup
+fn counts_as_outage(code: &ErrorCode) -> bool {
+    match code {
+        ErrorCode::RequestTimeout | ErrorCode::ConnectFailed | ErrorCode::RequestFailed { .. } => true,
+        ErrorCode::ServerError { status, .. } | ErrorCode::ProviderMessage { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+/// Record the outcome of a provider call (`None` on success) and return the
+/// incident last reported by the provider's status page, if any.
+///
+/// Once the failure threshold is reached the status page is fetched in the
+/// background, so the incident shows up on the following failures.
+pub fn record_outcome(provider: &str, error: Option<&ErrorCode>) -> Option<ProviderIncident> {
+    let page = status_page(provider)?;
+    let mut health = HEALTH.lock();
+    let entry = health.entry(page).or_default();
+
+    match error {
+        None => {
+            *entry = ProviderHealth::default();
+            return None;
+        }
+        Some(code) if counts_as_outage(code) => entry.consecutive_failures += 1,
+        Some(_) => return entry.incident.clone(),
+    }
+
+    let check_due = entry.last_check.is_none_or(|checked| checked.elapsed() >= CHECK_INTERVAL);
+    if entry.consecutive_failures >= FAILURE_THRESHOLD && check_due {
+        entry.last_check = Some(Instant::now());
+        tokio::spawn(check_status_page(page));
+    }
+
+    entry.incident.clone()
+}
+
+async fn check_status_page(page: &'static str) {
+    let incident = match fetch_status(page).await {
+        Ok(incident) => incident,
+        Err(e) => {
+            eprintln!("[ProviderStatus] Failed to check {}: {}", page, e);
+            return;
+        }
+    };
+
+    if let Some(entry) = HEALTH.lock().get_mut(page) {
+        entry.incident = incident;
+    }
+}
+
+async fn fetch_status(page: &str) -> Result<Option<ProviderIncident>, String> {
+    let client = shared_client(Duration::from_secs(10), false)?;
+    let data = client
+        .get(format!("{}/api/v2/status.json", page))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(parse_status(page, &data))
+}
+
+/// Parse a Statuspage `status.json` response; `none` means all systems operational
+fn parse_status(page: &str, data: &serde_json::Value) -> Option<ProviderIncident> {
+    let indicator = data["status"]["indicator"].as_str()?;
+    if indicator == "none" {
+        return None;
+    }
+
+    Some(ProviderIncident {
+        indicator: indicator.to_string(),
+        description: data["status"]["description"].as_str().unwrap_or_default().to_string(),
+        status_page: page.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_status() {
+        let operational = json!({ "status": { "indicator": "none", "description": "All Systems Operational" } });
+        assert_eq!(parse_status("https://status.openai.com", &operational), None);
+
+        let outage = json!({ "status": { "indicator": "major", "description": "Partial System Outage" } });
+        let incident = parse_status("https://status.openai.com", &outage).unwrap();
+        assert_eq!(incident.indicator, "major");
+        assert_eq!(incident.description, "Partial System Outage");
+    }
+
+    #[test]
+    fn test_only_outage_like_errors_count() {
+        assert!(counts_as_outage(&ErrorCode::RequestTimeout));
+        assert!(counts_as_outage(&ErrorCode::ServerError { status: 503, body: String::new() }));
+        assert!(!counts_as_outage(&ErrorCode::ServerError { status: 400, body: String::new() }));
+        assert!(!counts_as_outage(&ErrorCode::InvalidApiKey));
+    }
+}
@@ -0,0 +1,492 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::imageops::{self, FilterType};
+use image::{ImageReader, RgbImage};
+use once_cell::sync::{Lazy, OnceCell};
+use ort::session::Session;
+use ort::value::Tensor;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use crate::db::settings;
+use super::i18n::ErrorCode;
+use super::inference::{self, Device, InferenceStatus, LocalInference};
+use super::layout::{BoundingBox, LayoutBlock, LayoutLine, LayoutResult, LayoutWord};
+
+/// Provider type of configs that run PaddleOCR/RapidOCR ONNX models on this
+/// machine. Their API URL is the folder holding the models; left empty, the
+/// models bundled with the app are used.
+pub const PROVIDER: &str = "rapidocr";
+
+/// Folder of the bundled models inside the app's resources, filled at build
+/// time by `npm run fetch:resources`
+const BUNDLED_MODELS_DIR: &str = "rapidocr";
+
+/// Longest side of the image fed to the detection model
+const DET_MAX_SIDE: u32 = 960;
+/// Probability above which a pixel of the detection map counts as text
+const DET_THRESHOLD: f32 = 0.3;
+/// Mean probability a detected region needs to be kept
+const BOX_THRESHOLD: f32 = 0.5;
+/// How far regions are grown, the detection model marks only the text core
+const UNCLIP_RATIO: f32 = 1.6;
+const MIN_BOX_SIDE: u32 = 3;
+/// Input height of PP-OCRv3/v4 recognition models, used when the model doesn't declare one
+const DEFAULT_REC_HEIGHT: u32 = 48;
+const MAX_REC_WIDTH: u32 = 2000;
+/// Crops this much taller than wide are vertical text and are turned before recognition
+const VERTICAL_RATIO: f32 = 1.5;
+
+/// Detection and recognition models of one model folder
+struct OcrEngine {
+    // Running a session needs exclusive access
+    det: Mutex<Session>,
+    rec: Mutex<Session>,
+    /// Where both models run
+    device: Device,
+    rec_height: u32,
+    /// Characters of the recognition classes after the CTC blank
+    charset: Vec<String>,
+}
+
+/// Model folder and device setting an engine was loaded with
+type EngineKey = (PathBuf, String);
+
+static BUNDLED_MODELS: OnceCell<PathBuf> = OnceCell::new();
+
+/// Loaded engines; loading takes seconds, so they are kept
+static ENGINES: Lazy<Mutex<HashMap<EngineKey, Arc<OcrEngine>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A detected text region in pixels of the original image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TextBox {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl TextBox {
+    fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    fn to_bbox(self, image_width: u32, image_height: u32) -> BoundingBox {
+        BoundingBox {
+            x: self.x as f32 / image_width as f32,
+            y: self.y as f32 / image_height as f32,
+            width: self.width as f32 / image_width as f32,
+            height: self.height as f32 / image_height as f32,
+        }
+    }
+}
+
+/// Recognize the text of an image with the models in `model_dir`. Runs the
+/// models on this thread, callers in async code use `spawn_blocking`.
+pub fn recognize(model_dir: &str, image_base64: &str) -> Result<(LayoutResult, LocalInference), ErrorCode> {
+    let engine = load_engine(model_dir)?;
+    let failed = |detail: String| ErrorCode::LocalOcrFailed { detail };
+
+    let image_data = BASE64.decode(image_base64).map_err(|e| failed(format!("Invalid base64: {}", e)))?;
+    let image = ImageReader::new(Cursor::new(&image_data))
+        .with_guessed_format()
+        .map_err(|e| failed(format!("Failed to read image: {}", e)))?
+        .decode()
+        .map_err(|e| failed(format!("Failed to decode image: {}", e)))?
+        .to_rgb8();
+
+    let started = Instant::now();
+    let mut words = Vec::new();
+    for text_box in engine.detect(&image).map_err(failed)? {
+        let text = engine.read(&image, text_box).map_err(failed)?;
+        if !text.trim().is_empty() {
+            words.push((text_box, text.trim().to_string()));
+        }
+    }
+    let inference = LocalInference {
+        device: engine.device,
+        inference_ms: started.elapsed().as_millis() as i64,
+    };
+    Ok((build_layout(words, image.width(), image.height()), inference))
+}
+
+/// Load the models of `model_dir`, as the connection test of a local config
+pub fn check_models(model_dir: &str) -> Result<(), ErrorCode> {
+    load_engine(model_dir).map(|_| ())
+}
+
+/// Use the models shipped in the app's resources for configs without a model folder
+pub fn init_bundled_models(resource_dir: &Path) {
+    let _ = BUNDLED_MODELS.set(resource_dir.join(BUNDLED_MODELS_DIR));
+}
+
+/// Devices available to local models and the one the loaded models run on
+pub fn inference_status() -> InferenceStatus {
+    let device_setting = device_setting();
+    let in_use = ENGINES
+        .lock()
+        .iter()
+        .find(|((_, setting), _)| *setting == device_setting)
+        .map(|(_, engine)| engine.device);
+    InferenceStatus { available: inference::available_devices(), in_use }
+}
+
+fn device_setting() -> String {
+    settings::get_all_settings().map(|s| s.inference_device).unwrap_or_default()
+}
+
+/// The config's model folder, or the bundled models when it names none
+fn model_dir_path(model_dir: &str) -> Result<PathBuf, ErrorCode> {
+    match model_dir.trim() {
+        "" => BUNDLED_MODELS
+            .get()
+            .cloned()
+            .ok_or_else(|| ErrorCode::LocalModelMissing { path: BUNDLED_MODELS_DIR.to_string() }),
+        dir => Ok(PathBuf::from(dir)),
+    }
+}
+
+fn load_engine(model_dir: &str) -> Result<Arc<OcrEngine>, ErrorCode> {
+    let dir = model_dir_path(model_dir)?;
+    // A changed device setting loads the models again
+    let device_setting = device_setting();
+    let key = (dir, device_setting);
+    let mut engines = ENGINES.lock();
+    if let Some(engine) = engines.get(&key) {
+        return Ok(engine.clone());
+    }
+
+    let engine = Arc::new(OcrEngine::load(&key.0, &inference::candidates(&key.1))?);
+    eprintln!(
+        "[RapidOCR] Loaded models from {} on {:?} ({} characters)",
+        key.0.display(),
+        engine.device,
+        engine.charset.len()
+    );
+    engines.insert(key, engine.clone());
+    Ok(engine)
+}
+
+/// First file in `dir` with the extension whose name contains one of `hints`
+fn find_model_file(dir: &Path, extension: &str, hints: &[&str]) -> Option<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension)))
+        .collect();
+    paths.sort();
+    paths.into_iter().find(|path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+        hints.iter().any(|hint| name.contains(hint))
+    })
+}
+
+impl OcrEngine {
+    /// Load `*det*.onnx` and `*rec*.onnx` from `dir`. The character list comes
+    /// from a `*keys*`/`*dict*` text file, or from the recognition model's
+    /// metadata when the model embeds it. Both models run on the first of
+    /// `devices` the detection model can use.
+    fn load(dir: &Path, devices: &[Device]) -> Result<Self, ErrorCode> {
+        let missing = || ErrorCode::LocalModelMissing { path: dir.display().to_string() };
+        let det_path = find_model_file(dir, "onnx", &["det"]).ok_or_else(missing)?;
+        let rec_path = find_model_file(dir, "onnx", &["rec"]).ok_or_else(missing)?;
+
+        let load_failed = |e: ort::Error| ErrorCode::LocalOcrFailed { detail: e.to_string() };
+        let (det, device) = inference::open_session(&det_path, devices).map_err(load_failed)?;
+        let (rec, _) = inference::open_session(&rec_path, &[device]).map_err(load_failed)?;
+
+        let dictionary = match find_model_file(dir, "txt", &["key", "dict"]) {
+            Some(path) => std::fs::read_to_string(&path).map_err(|_| missing())?,
+            None => rec
+                .metadata()
+                .and_then(|metadata| metadata.custom("character"))
+                .map_err(load_failed)?
+                .ok_or_else(missing)?,
+        };
+        let mut charset: Vec<String> = dictionary
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        // PaddleOCR models are trained with a space class after the dictionary
+        charset.push(" ".to_string());
+
+        // [batch, channels, height, width], the height is fixed even when the width isn't
+        let rec_height = rec
+            .inputs
+            .first()
+            .and_then(|input| input.input_type.tensor_shape())
+            .and_then(|shape| shape.get(2).copied())
+            .filter(|height| *height > 0)
+            .map_or(DEFAULT_REC_HEIGHT, |height| height as u32);
+
+        Ok(Self {
+            det: Mutex::new(det),
+            rec: Mutex::new(rec),
+            device,
+            rec_height,
+            charset,
+        })
+    }
+
+    /// Find text regions with the DB detection model
+    fn detect(&self, image: &RgbImage) -> Result<Vec<TextBox>, String> {
+        let (width, height) = image.dimensions();
+        // The model needs sides divisible by 32
+        let scale = (DET_MAX_SIDE as f32 / width.max(height) as f32).min(1.0);
+        let fit = |side: u32| (((side as f32 * scale / 32.0).round() as u32) * 32).max(32);
+        let (det_width, det_height) = (fit(width), fit(height));
+        let resized = imageops::resize(image, det_width, det_height, FilterType::Triangle);
+
+        let input = to_tensor(&resized, [0.485, 0.456, 0.406], [0.229, 0.224, 0.225])?;
+        let mut session = self.det.lock();
+        let outputs = session.run(ort::inputs![input]).map_err(|e| e.to_string())?;
+        let (shape, probs) = outputs[0].try_extract_tensor::<f32>().map_err(|e| e.to_string())?;
+        let (map_height, map_width) = match shape[..] {
+            [.., h, w] => (h as usize, w as usize),
+            _ => return Err(format!("unexpected detection output shape {:?}", &shape[..])),
+        };
+
+        let scale_x = width as f32 / map_width as f32;
+        let scale_y = height as f32 / map_height as f32;
+        Ok(find_boxes(probs, map_width, map_height)
+            .into_iter()
+            .filter_map(|text_box| {
+                let text_box = TextBox {
+                    x: (text_box.x as f32 * scale_x) as u32,
+                    y: (text_box.y as f32 * scale_y) as u32,
+                    width: ((text_box.width as f32 * scale_x).round() as u32).min(width),
+                    height: ((text_box.height as f32 * scale_y).round() as u32).min(height),
+                };
+                (text_box.width >= MIN_BOX_SIDE && text_box.height >= MIN_BOX_SIDE
+                    && text_box.x + text_box.width <= width && text_box.bottom() <= height)
+                    .then_some(text_box)
+            })
+            .collect())
+    }
+
+    /// Read the text of one region with the CRNN recognition model
+    fn read(&self, image: &RgbImage, text_box: TextBox) -> Result<String, String> {
+        let mut crop = imageops::crop_imm(image, text_box.x, text_box.y, text_box.width, text_box.height).to_image();
+        if crop.height() as f32 >= crop.width() as f32 * VERTICAL_RATIO {
+            crop = imageops::rotate270(&crop);
+        }
+        let rec_width = ((self.rec_height as f32 * crop.width() as f32 / crop.height() as f32).ceil() as u32)
+            .clamp(self.rec_height / 4, MAX_REC_WIDTH);
+        let resized = imageops::resize(&crop, rec_width, self.rec_height, FilterType::Triangle);
+
+        let input = to_tensor(&resized, [0.5; 3], [0.5; 3])?;
+        let mut session = self.rec.lock();
+        let outputs = session.run(ort::inputs![input]).map_err(|e| e.to_string())?;
+        let (shape, probs) = outputs[0].try_extract_tensor::<f32>().map_err(|e| e.to_string())?;
+        let (steps, classes) = match shape[..] {
+            [_, steps, classes] => (steps as usize, classes as usize),
+            _ => return Err(format!("unexpected recognition output shape {:?}", &shape[..])),
+        };
+        Ok(ctc_decode(probs, steps, classes, &self.charset))
+    }
+}
+
+/// NCHW tensor of a normalized image, channels in the BGR order PaddleOCR was trained with
+fn to_tensor(image: &RgbImage, mean: [f32; 3], std: [f32; 3]) -> Result<Tensor<f32>, String> {
+    let (width, height) = image.dimensions();
+    let plane = (width * height) as usize;
+    let mut data = vec![0.0f32; plane * 3];
+    for (i, pixel) in image.pixels().enumerate() {
+        for (channel, rgb_index) in [2, 1, 0].into_iter().enumerate() {
+            let value = pixel.0[rgb_index] as f32 / 255.0;
+            data[channel * plane + i] = (value - mean[channel]) / std[channel];
+        }
+    }
+    Tensor::from_array(([1usize, 3, height as usize, width as usize], data)).map_err(|e| e.to_string())
+}
+
+/// Connected regions of the probability map above the threshold, grown by
+/// the unclip ratio and clamped to the map
+fn find_boxes(probs: &[f32], width: usize, height: usize) -> Vec<TextBox> {
+    let mut visited = vec![false; width * height];
+    let mut boxes = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for start in 0..width * height {
+        if visited[start] || probs[start] <= DET_THRESHOLD {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+        let (mut sum, mut count) = (0.0f32, 0usize);
+
+        while let Some(index) = queue.pop_front() {
+            let (x, y) = (index % width, index / width);
+            left = left.min(x);
+            right = right.max(x);
+            top = top.min(y);
+            bottom = bottom.max(y);
+            sum += probs[index];
+            count += 1;
+
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !visited[neighbor] && probs[neighbor] > DET_THRESHOLD {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let (box_width, box_height) = ((right - left + 1) as f32, (bottom - top + 1) as f32);
+        if sum / (count as f32) < BOX_THRESHOLD || box_width.min(box_height) < MIN_BOX_SIDE as f32 {
+            continue;
+        }
+        // Same offset as PaddleOCR's unclip: area * ratio / perimeter
+        let offset = box_width * box_height * UNCLIP_RATIO / (2.0 * (box_width + box_height));
+        let grow = |low: usize, high: usize, limit: usize| {
+            let low = (low as f32 - offset).max(0.0) as usize;
+            let high = ((high as f32 + offset).round() as usize).min(limit - 1);
+            (low, high - low + 1)
+        };
+        let (x, grown_width) = grow(left, right, width);
+        let (y, grown_height) = grow(top, bottom, height);
+        boxes.push(TextBox { x: x as u32, y: y as u32, width: grown_width as u32, height: grown_height as u32 });
+    }
+    boxes
+}
+
+/// Greedy CTC decoding of `[steps, classes]` probabilities: best class per
+/// step, dropping blanks (class 0) and repeats
+fn ctc_decode(probs: &[f32], steps: usize, classes: usize, charset: &[String]) -> String {
+    let mut text = String::new();
+    let mut previous = 0;
+    for step in probs.chunks_exact(classes).take(steps) {
+        let best = step
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(0, |(class, _)| class);
+        if best != 0 && best != previous {
+            if let Some(character) = charset.get(best - 1) {
+                text.push_str(character);
+            }
+        }
+        previous = best;
+    }
+    text
+}
+
+/// Arrange recognized regions into lines (regions overlapping vertically,
+/// left to right) and paragraphs (lines without a wide gap between them)
+fn build_layout(mut words: Vec<(TextBox, String)>, image_width: u32, image_height: u32) -> LayoutResult {
+    words.sort_by_key(|(text_box, _)| (text_box.y, text_box.x));
+
+    let mut lines: Vec<Vec<(TextBox, String)>> = Vec::new();
+    for word in words {
+        let center = word.0.y + word.0.height / 2;
+        match lines.iter_mut().rev().find(|line| line.iter().any(|(b, _)| b.y <= center && center < b.bottom())) {
+            Some(line) => line.push(word),
+            None => lines.push(vec![word]),
+        }
+    }
+
+    let mut blocks: Vec<(TextBox, Vec<LayoutLine>)> = Vec::new();
+    for mut line in lines {
+        line.sort_by_key(|(text_box, _)| text_box.x);
+        let bounds = union(line.iter().map(|(text_box, _)| *text_box));
+        let layout_line = LayoutLine {
+            text: line.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join(" "),
+            bbox: Some(bounds.to_bbox(image_width, image_height)),
+            words: line
+                .into_iter()
+                .map(|(text_box, text)| LayoutWord { text, bbox: Some(text_box.to_bbox(image_width, image_height)) })
+                .collect(),
+        };
+
+        // A gap taller than the line starts a new paragraph
+        match blocks.last_mut() {
+            Some((block_bounds, block_lines)) if bounds.y < block_bounds.bottom() + bounds.height => {
+                *block_bounds = union([*block_bounds, bounds]);
+                block_lines.push(layout_line);
+            }
+            _ => blocks.push((bounds, vec![layout_line])),
+        }
+    }
+
+    LayoutResult {
+        blocks: blocks
+            .into_iter()
+            .map(|(bounds, lines)| LayoutBlock {
+                text: lines.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n"),
+                bbox: Some(bounds.to_bbox(image_width, image_height)),
+                lines,
+            })
+            .collect(),
+    }
+}
+
+fn union(boxes: impl IntoIterator<Item = TextBox>) -> TextBox {
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for text_box in boxes {
+        left = left.min(text_box.x);
+        top = top.min(text_box.y);
+        right = right.max(text_box.x + text_box.width);
+        bottom = bottom.max(text_box.bottom());
+    }
+    TextBox { x: left, y: top, width: right.saturating_sub(left), height: bottom.saturating_sub(top) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ctc_decode() {
+        let charset: Vec<String> = ["你", "好", " "].iter().map(|c| c.to_string()).collect();
+        // blank, 你, 你, blank, 好, 好, blank, 好
+        let best = [0, 1, 1, 0, 2, 2, 0, 2];
+        let probs: Vec<f32> = best
+            .iter()
+            .flat_map(|&class| (0..4).map(move |c| if c == class { 0.9 } else { 0.05 }))
+            .collect();
+        assert_eq!(ctc_decode(&probs, best.len(), 4, &charset), "你好好");
+    }
+
+    #[test]
+    fn test_find_boxes() {
+        let (width, height) = (20, 10);
+        let mut probs = vec![0.0f32; width * height];
+        for y in 4..7 {
+            for x in 2..12 {
+                probs[y * width + x] = 0.9;
+            }
+        }
+        // A faint region stays below the box threshold
+        probs[width + 17] = 0.35;
+
+        let boxes = find_boxes(&probs, width, height);
+        assert_eq!(boxes.len(), 1);
+        let text_box = boxes[0];
+        assert!(text_box.x < 2 && text_box.y < 4);
+        assert!(text_box.x + text_box.width > 12 && text_box.bottom() > 7);
+        assert!(text_box.bottom() <= height as u32);
+    }
+
+    #[test]
+    fn test_build_layout() {
+        let word = |x, y, text: &str| (TextBox { x, y, width: 40, height: 10 }, text.to_string());
+        let layout = build_layout(
+            vec![word(50, 12, "world"), word(0, 10, "Hello"), word(0, 22, "second"), word(0, 60, "Next")],
+            100,
+            100,
+        );
+        assert_eq!(layout.blocks.len(), 2);
+        assert_eq!(layout.text(), "Hello world\nsecond\n\nNext");
+        assert_eq!(layout.blocks[0].lines[0].words.len(), 2);
+    }
+}
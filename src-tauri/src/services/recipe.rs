@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::db::{model_config, prompt_template, settings, workspace};
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::image::{load_image_file, process_image_for_api};
+use super::llm::{self, RecognitionOptions, RecognitionResult};
+use super::workspace::PipelineContext;
+
+pub const RECIPE_VERSION: u32 = 1;
+
+/// A shareable description of a recognition pipeline.
+///
+/// The model is referenced by provider and model name rather than by config id,
+/// so the recipe runs against whichever matching config the importer has.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recipe {
+    pub version: u32,
+    pub name: String,
+    pub model: RecipeModel,
+    pub template: RecipeTemplate,
+    #[serde(default)]
+    pub preprocessing: RecipePreprocessing,
+    #[serde(default)]
+    pub postprocessing: RecipePostprocessing,
+    #[serde(default)]
+    pub sink: RecipeSink,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeModel {
+    pub provider: String,
+    pub model_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeTemplate {
+    pub name: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecipePreprocessing {
+    pub auto_compress: bool,
+    /// Compress images larger than this many KB
+    pub compress_threshold_kb: Option<i32>,
+    pub auto_rotate: bool,
+    pub auto_crop: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecipePostprocessing {
+    pub glossary: Vec<String>,
+    pub layout: bool,
+    pub segment_languages: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecipeSink {
+    pub export_format: Option<String>,
+    pub output_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeRunItem {
+    pub input: String,
+    pub result: RecognitionResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeRunReport {
+    pub recipe: String,
+    pub config_id: i64,
+    pub items: Vec<RecipeRunItem>,
+}
+
+/// Build a recipe from a workspace's preset, glossary, output sink and the
+/// preprocessing settings currently in effect
+pub fn export_recipe(workspace_id: i64) -> Result<Recipe, AppError> {
+    let ws = workspace::get_workspace_by_id(workspace_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::WorkspaceNotFound)?;
+
+    let config = match ws.config_id {
+        Some(id) => model_config::get_config_by_id(id),
+        None => model_config::get_default_config(),
+    }
+    .map_err(|e| e.to_string())?
+    .ok_or(ErrorCode::ConfigNotFound)?;
+
+    let template = match ws.template_id {
+        Some(id) => prompt_template::get_template_by_id(id)
+            .map_err(|e| e.to_string())?
+            .ok_or(ErrorCode::TemplateNotFound { id })?,
+        None => prompt_template::get_default_template()
+            .map_err(|e| e.to_string())?
+            .ok_or(ErrorCode::TemplateNotFound { id: 0 })?,
+    };
+
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+
+    Ok(Recipe {
+        version: RECIPE_VERSION,
+        name: ws.name,
+        model: RecipeModel {
+            provider: config.provider,
+            model_name: config.model_name,
+        },
+        template: RecipeTemplate {
+            name: Some(template.name),
+            content: template.content,
+        },
+        preprocessing: RecipePreprocessing {
+            auto_compress: app_settings.auto_compress,
+            compress_threshold_kb: Some(app_settings.compress_threshold),
+            auto_rotate: app_settings.auto_rotate,
+            auto_crop: app_settings.auto_crop,
+        },
+        postprocessing: RecipePostprocessing {
+            glossary: ws.glossary,
+            ..Default::default()
+        },
+        sink: RecipeSink {
+            export_format: Some(ws.export_format),
+            output_dir: ws.output_dir,
+        },
+    })
+}
+
+pub fn parse_recipe(json: &str) -> Result<Recipe, ErrorCode> {
+    let recipe: Recipe = serde_json::from_str(json)
+        .map_err(|e| ErrorCode::InvalidRecipe { detail: e.to_string() })?;
+    if recipe.version > RECIPE_VERSION {
+        return Err(ErrorCode::InvalidRecipe {
+            detail: format!("unsupported version {}", recipe.version),
+        });
+    }
+    Ok(recipe)
+}
+
+/// Run a recipe file over the given image paths, using the enabled config
+/// matching the recipe's provider and model (the default one when several match)
+pub async fn run_recipe(path: &Path, inputs: &[String]) -> Result<RecipeRunReport, AppError> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| ErrorCode::InvalidRecipe { detail: e.to_string() })?;
+    let recipe = parse_recipe(&json)?;
+    if inputs.is_empty() {
+        return Err(ErrorCode::NoImagesSelected.into());
+    }
+
+    let config_id = model_config::get_active_configs()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|c| c.provider == recipe.model.provider && c.model_name == recipe.model.model_name)
+        .map(|c| c.id)
+        .ok_or_else(|| ErrorCode::NoMatchingConfig {
+            provider: recipe.model.provider.clone(),
+            model: recipe.model.model_name.clone(),
+        })?;
+
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let threshold_kb = recipe.preprocessing.compress_threshold_kb.unwrap_or(app_settings.compress_threshold);
+    let threshold_bytes = (threshold_kb.max(1) as usize) * 1024;
+
+    let context = PipelineContext {
+        glossary: recipe.postprocessing.glossary.clone(),
+        output_dir: recipe.sink.output_dir.clone(),
+        export_format: recipe.sink.export_format.clone(),
+        ..Default::default()
+    };
+    let options = RecognitionOptions {
+        auto_rotate: Some(recipe.preprocessing.auto_rotate),
+        auto_crop: Some(recipe.preprocessing.auto_crop),
+        layout: Some(recipe.postprocessing.layout),
+        segment_languages: Some(recipe.postprocessing.segment_languages),
+        output_language: Some(app_settings.output_language.clone()),
+        decode_codes: Some(app_settings.decode_codes),
+        ..Default::default()
+    };
+
+    let mut items = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let image = load_image_file(Path::new(input)).and_then(|(base64, mime_type)| {
+            let processed = process_image_for_api(&base64, recipe.preprocessing.auto_compress, threshold_bytes)?;
+            let mime_type = if processed.was_compressed { processed.mime_type } else { mime_type };
+            Ok((processed.base64, mime_type))
+        });
+
+        let result = match image {
+            Ok((base64, mime_type)) => {
+                llm::recognize_in(
+                    &context,
+                    config_id,
+                    &base64,
+                    &mime_type,
+                    &recipe.template.content,
+                    Some(options.clone()),
+                    None,
+                )
+                .await
+            }
+            Err(detail) => RecognitionResult::from_code(ErrorCode::ImageProcessingFailed { detail }, None),
+        };
+
+        items.push(RecipeRunItem {
+            input: input.clone(),
+            result,
+        });
+    }
+
+    Ok(RecipeRunReport {
+        recipe: recipe.name,
+        config_id,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_minimal_recipe() {
+        let recipe = parse_recipe(
+            r#"{
+                "version": 1,
+                "name": "Receipts",
+                "model": { "provider": "openai", "modelName": "gpt-4o" },
+                "template": { "content": "Extract the text" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(recipe.model.model_name, "gpt-4o");
+        assert_eq!(recipe.preprocessing, RecipePreprocessing::default());
+        assert!(recipe.postprocessing.glossary.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_newer_versions() {
+        let json = r#"{ "version": 99, "name": "x", "model": { "provider": "openai", "modelName": "m" },
+            "template": { "content": "p" } }"#;
+        assert!(matches!(parse_recipe(json), Err(ErrorCode::InvalidRecipe { .. })));
+        assert!(matches!(parse_recipe("not json"), Err(ErrorCode::InvalidRecipe { .. })));
+    }
+}
@@ -0,0 +1,435 @@
+use std::net::IpAddr;
+use std::ops::Range;
+use crate::db::model_config::ModelConfig;
+use crate::db::settings;
+use super::extraction::Extraction;
+use super::i18n::ErrorCode;
+use super::image::mask_regions;
+use super::layout::{self, BoundingBox, LayoutResult};
+use super::llm::{self, RecognitionOptions};
+use super::rapidocr;
+
+const OCR_PROMPT: &str = "Transcribe all text in the image exactly as written.";
+
+/// Weights of the first 17 digits of a Chinese resident ID and the check characters
+const ID_WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+const ID_CHECK_CHARS: &[u8; 11] = b"10X98765432";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensitiveKind {
+    Email,
+    CardNumber,
+    IdNumber,
+    Phone,
+}
+
+/// Mask sensitive text in the image before it is sent to `config`'s provider.
+/// Only configs that opted in are redacted, and never those on the local
+/// machine or network. Returns `None` when nothing had to be masked.
+///
+/// Text is found by an OCR pass through the local config chosen in settings;
+/// the image never leaves the machine before it is masked.
+pub async fn redact_for(
+    config: &ModelConfig,
+    image_base64: &str,
+    image_mime_type: &str,
+) -> Result<Option<(String, String)>, ErrorCode> {
+    if !config.redact_sensitive || is_local_config(config) {
+        return Ok(None);
+    }
+
+    let ocr_config_id = settings::get_all_settings()
+        .map_err(|e| ErrorCode::ConfigLoadFailed { detail: e.to_string() })?
+        .redaction_config_id
+        .ok_or(ErrorCode::RedactionUnavailable)?;
+    let ocr_config = llm::load_active_config(ocr_config_id)?;
+    if !is_local_config(&ocr_config) {
+        return Err(ErrorCode::RedactionConfigNotLocal { name: ocr_config.name });
+    }
+
+    let options = RecognitionOptions {
+        temperature: Some(0.0),
+        layout: Some(true),
+        stream: Some(false),
+        ..Default::default()
+    };
+    let ocr = llm::send_to_provider(
+        &ocr_config,
+        image_base64,
+        image_mime_type,
+        &layout::layout_prompt(OCR_PROMPT),
+        Some(options),
+        None,
+        &|_| {},
+    )
+    .await;
+    if !ocr.success {
+        return Err(ErrorCode::RedactionFailed { detail: ocr.error.unwrap_or_default() });
+    }
+
+    let content = ocr.content.unwrap_or_default();
+    let regions = match layout::parse_layout(&content) {
+        Some(layout) => sensitive_regions(&layout)
+            .ok_or_else(|| ErrorCode::RedactionFailed { detail: "sensitive text without position".to_string() })?,
+        // Without positions nothing can be masked, so only text without sensitive values passes
+        None if find_sensitive(&content).is_empty() => Vec::new(),
+        None => return Err(ErrorCode::RedactionFailed { detail: "sensitive text without position".to_string() }),
+    };
+    if regions.is_empty() {
+        return Ok(None);
+    }
+
+    eprintln!("[Redaction] Masked {} region(s) before upload to {}", regions.len(), config.name);
+    mask_regions(image_base64, &regions)
+        .map(Some)
+        .map_err(|detail| ErrorCode::ImageProcessingFailed { detail })
+}
+
+/// Config whose requests never leave the machine or network
+fn is_local_config(config: &ModelConfig) -> bool {
+    config.provider == rapidocr::PROVIDER || is_local_endpoint(&config.api_url)
+}
+
+/// Loopback or private network address, where requests stay on premises
+pub fn is_local_endpoint(api_url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(api_url) else {
+        return false;
+    };
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => ip.is_loopback(),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    }
+}
+
+/// Boxes to mask for every line holding a sensitive value: the matching words
+/// when the model located them, otherwise the whole line or block. `None` when
+/// a sensitive value has no box at all.
+fn sensitive_regions(layout: &LayoutResult) -> Option<Vec<BoundingBox>> {
+    let mut regions = Vec::new();
+
+    for block in &layout.blocks {
+        if block.lines.is_empty() {
+            if !find_sensitive(&block.text).is_empty() {
+                regions.push(block.bbox?);
+            }
+            continue;
+        }
+
+        for line in &block.lines {
+            let matches = find_sensitive(&line.text);
+            if matches.is_empty() {
+                continue;
+            }
+
+            let mut cursor = 0;
+            let mut word_boxes = Vec::new();
+            for word in &line.words {
+                let Some(start) = line.text[cursor..].find(word.text.as_str()).map(|i| cursor + i) else {
+                    continue;
+                };
+                let end = start + word.text.len();
+                cursor = end;
+                if matches.iter().any(|(range, _)| range.start < end && start < range.end) {
+                    word_boxes.push(word.bbox);
+                }
+            }
+
+            // Fall back to the line when any matching word lacks a box
+            match word_boxes.iter().copied().collect::<Option<Vec<_>>>() {
+                Some(boxes) if !boxes.is_empty() => regions.extend(boxes),
+                _ => regions.push(line.bbox.or(block.bbox)?),
+            }
+        }
+    }
+
+    Some(regions)
+}
+
+/// Byte ranges of email addresses, card numbers and resident ID numbers in the text
+pub fn find_sensitive(text: &str) -> Vec<(Range<usize>, SensitiveKind)> {
+    let mut found: Vec<(Range<usize>, SensitiveKind)> =
+        find_emails(text).into_iter().map(|range| (range, SensitiveKind::Email)).collect();
+
+    for (range, digits) in number_runs(text) {
+        let kind = if digits.len() == 18 && resident_id_valid(&digits) {
+            SensitiveKind::IdNumber
+        } else if (13..=19).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit()) && luhn_valid(&digits) {
+            SensitiveKind::CardNumber
+        } else {
+            continue;
+        };
+        // Digits inside an email are already covered
+        if !found.iter().any(|(r, _)| r.start <= range.start && range.end <= r.end) {
+            found.push((range, kind));
+        }
+    }
+
+    found.sort_by_key(|(range, _)| range.start);
+    found
+}
+
+/// `find_sensitive` plus phone numbers, for scrubbing text kept at rest
+pub fn find_pii(text: &str) -> Vec<(Range<usize>, SensitiveKind)> {
+    let mut found = find_sensitive(text);
+    for (range, digits) in number_runs(text) {
+        let international = range.start > 0 && text.as_bytes()[range.start - 1] == b'+';
+        let mobile = digits.len() == 11 && digits.starts_with('1') && matches!(digits.as_bytes()[1], b'3'..=b'9');
+        // Area code and number, written with a separator so plain 0-prefixed codes don't match
+        let landline = (10..=12).contains(&digits.len()) && digits.starts_with('0') && text[range.clone()].contains('-');
+        let is_phone = (international && (8..=15).contains(&digits.len())) || mobile || landline;
+        if is_phone && !found.iter().any(|(r, _)| r.start < range.end && range.start < r.end) {
+            let start = if international { range.start - 1 } else { range.start };
+            found.push((start..range.end, SensitiveKind::Phone));
+        }
+    }
+    found.sort_by_key(|(range, _)| range.start);
+    found
+}
+
+/// Mask the personal data in text kept at rest: all but the last four digits
+/// of numbers and all but the first character of an email's local part
+pub fn scrub_text(text: &str) -> String {
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (range, kind) in find_pii(text) {
+        scrubbed.push_str(&text[cursor..range.start]);
+        let value = &text[range.clone()];
+        match kind {
+            SensitiveKind::Email => {
+                let (local, domain) = value.split_once('@').unwrap_or((value, ""));
+                scrubbed.extend(local.chars().take(1));
+                scrubbed.push_str("***@");
+                scrubbed.push_str(domain);
+            }
+            SensitiveKind::CardNumber | SensitiveKind::IdNumber | SensitiveKind::Phone => {
+                let total = value.chars().filter(char::is_ascii_alphanumeric).count();
+                let mut seen = 0;
+                for c in value.chars() {
+                    if c.is_ascii_alphanumeric() {
+                        seen += 1;
+                        scrubbed.push(if seen + 4 > total { c } else { '*' });
+                    } else {
+                        scrubbed.push(c);
+                    }
+                }
+            }
+        }
+        cursor = range.end;
+    }
+    scrubbed.push_str(&text[cursor..]);
+    scrubbed
+}
+
+/// `scrub_text` over every text field of an extraction; amounts are kept
+pub fn scrub_extraction(extraction: &Extraction) -> Extraction {
+    let scrub = |text: &mut String| *text = scrub_text(text);
+    let mut scrubbed = extraction.clone();
+    if let Some(receipt) = scrubbed.receipt.as_mut() {
+        receipt.merchant.iter_mut().for_each(scrub);
+        receipt.line_items.iter_mut().for_each(|item| scrub(&mut item.description));
+    }
+    if let Some(card) = scrubbed.business_card.as_mut() {
+        for field in [&mut card.name, &mut card.title, &mut card.company, &mut card.website, &mut card.address] {
+            field.iter_mut().for_each(scrub);
+        }
+        card.phones.iter_mut().chain(card.emails.iter_mut()).for_each(scrub);
+    }
+    scrubbed
+}
+
+fn find_emails(text: &str) -> Vec<Range<usize>> {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+
+    let mut emails = Vec::new();
+    for (at, _) in text.match_indices('@') {
+        let start = text[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_local(*c))
+            .last()
+            .map_or(at, |(i, _)| i);
+        let domain: &str = {
+            let rest = &text[at + 1..];
+            let len = rest.find(|c: char| !is_domain(c)).unwrap_or(rest.len());
+            rest[..len].trim_end_matches(['.', '-'])
+        };
+
+        let tld_ok = domain
+            .rsplit_once('.')
+            .is_some_and(|(name, tld)| !name.is_empty() && tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()));
+        if start < at && tld_ok {
+            emails.push(start..at + 1 + domain.len());
+        }
+    }
+    emails
+}
+
+/// Runs of digits, allowing single spaces or hyphens between groups and a
+/// trailing `X` for resident IDs, with the digits joined
+fn number_runs(text: &str) -> Vec<(Range<usize>, String)> {
+    let bytes = text.as_bytes();
+    let mut runs = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() || (i > 0 && bytes[i - 1].is_ascii_alphanumeric()) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut digits = String::new();
+        let mut end = i;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'0'..=b'9' => {
+                    digits.push(bytes[i] as char);
+                    i += 1;
+                    end = i;
+                }
+                b' ' | b'-' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => i += 1,
+                b'X' | b'x' if digits.len() == 17 => {
+                    digits.push('X');
+                    i += 1;
+                    end = i;
+                    break;
+                }
+                _ => break,
+            }
+        }
+        i = end;
+
+        // A run glued to letters is part of some other token
+        if !bytes.get(end).is_some_and(u8::is_ascii_alphabetic) {
+            runs.push((start..end, digits));
+        }
+    }
+    runs
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = (b - b'0') as u32;
+            if i % 2 == 1 {
+                if d * 2 > 9 { d * 2 - 9 } else { d * 2 }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn resident_id_valid(id: &str) -> bool {
+    let bytes = id.as_bytes();
+    if bytes.len() != 18 || !bytes[..17].iter().all(u8::is_ascii_digit) {
+        return false;
+    }
+    let sum: u32 = bytes[..17]
+        .iter()
+        .zip(ID_WEIGHTS)
+        .map(|(b, weight)| (b - b'0') as u32 * weight)
+        .sum();
+    ID_CHECK_CHARS[(sum % 11) as usize] == bytes[17].to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::layout::{LayoutBlock, LayoutLine, LayoutWord};
+
+    fn kinds(text: &str) -> Vec<(&str, SensitiveKind)> {
+        find_sensitive(text).into_iter().map(|(range, kind)| (&text[range], kind)).collect()
+    }
+
+    #[test]
+    fn test_find_sensitive() {
+        assert_eq!(kinds("联系 zhang.san@example.com 获取"), vec![("zhang.san@example.com", SensitiveKind::Email)]);
+        assert_eq!(kinds("Card: 4111 1111 1111 1111."), vec![("4111 1111 1111 1111", SensitiveKind::CardNumber)]);
+        assert_eq!(kinds("身份证 11010519491231002X"), vec![("11010519491231002X", SensitiveKind::IdNumber)]);
+        // Failing checksums and ordinary numbers are left alone
+        assert!(kinds("Order 4111 1111 1111 1112, total 1234").is_empty());
+        assert!(kinds("110105194912310021").is_empty());
+        assert!(kinds("not an email: a@b, @example.com").is_empty());
+    }
+
+    #[test]
+    fn test_scrub_text() {
+        assert_eq!(
+            scrub_text("张三 13812345678，座机 010-12345678，zhang.san@example.com"),
+            "张三 *******5678，座机 ***-****5678，z***@example.com"
+        );
+        assert_eq!(scrub_text("ID 11010519491231002X, +86 138 1234 5678"), "ID **************002X, +** *** **** 5678");
+        // Order numbers and amounts stay readable
+        assert_eq!(scrub_text("Order 20240517001 total 1234"), "Order 20240517001 total 1234");
+    }
+
+    #[test]
+    fn test_scrub_extraction() {
+        let extraction: Extraction = serde_json::from_value(serde_json::json!({
+            "kind": "business_card",
+            "businessCard": {
+                "name": "张三",
+                "phones": ["13812345678"],
+                "emails": ["zhang.san@example.com"],
+                "address": "北京市朝阳区 010-12345678",
+            },
+            "issues": [],
+        }))
+        .unwrap();
+        let card = scrub_extraction(&extraction).business_card.unwrap();
+        assert_eq!(card.name.as_deref(), Some("张三"));
+        assert_eq!(card.phones, vec!["*******5678"]);
+        assert_eq!(card.emails, vec!["z***@example.com"]);
+        assert_eq!(card.address.as_deref(), Some("北京市朝阳区 ***-****5678"));
+    }
+
+    #[test]
+    fn test_is_local_endpoint() {
+        assert!(is_local_endpoint("http://localhost:11434/v1"));
+        assert!(is_local_endpoint("http://127.0.0.1:1234/v1/chat/completions"));
+        assert!(is_local_endpoint("http://192.168.1.20:8000"));
+        assert!(is_local_endpoint("http://[::1]:8080"));
+        assert!(!is_local_endpoint("https://api.openai.com/v1"));
+        assert!(!is_local_endpoint("not a url"));
+    }
+
+    #[test]
+    fn test_sensitive_regions_prefers_word_boxes() {
+        let bbox = |x: f32| Some(BoundingBox { x, y: 0.1, width: 0.1, height: 0.05 });
+        let layout = LayoutResult {
+            blocks: vec![LayoutBlock {
+                text: "Mail: a.b@example.com today".to_string(),
+                bbox: bbox(0.0),
+                lines: vec![
+                    LayoutLine {
+                        text: "Mail: a.b@example.com today".to_string(),
+                        bbox: bbox(0.0),
+                        words: ["Mail:", "a.b@example.com", "today"]
+                            .iter()
+                            .enumerate()
+                            .map(|(i, text)| LayoutWord { text: text.to_string(), bbox: bbox(0.2 * i as f32) })
+                            .collect(),
+                    },
+                    LayoutLine {
+                        text: "Card 4111111111111111".to_string(),
+                        bbox: bbox(0.7),
+                        words: Vec::new(),
+                    },
+                ],
+            }],
+        };
+
+        let regions = sensitive_regions(&layout).unwrap();
+        assert_eq!(regions, vec![bbox(0.2).unwrap(), bbox(0.7).unwrap()]);
+    }
+}
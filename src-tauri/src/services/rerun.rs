@@ -0,0 +1,113 @@
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::db::{history, settings};
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+use super::image::load_image_file;
+use super::image_store;
+use super::llm::{self, RecognitionOptions};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerunProgress {
+    pub batch_id: String,
+    pub completed: usize,
+    pub total: usize,
+    /// Original record that was just re-run
+    pub history_id: i64,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerunFailure {
+    pub history_id: i64,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RerunReport {
+    /// Batch of the new records, whose review queue holds them
+    pub batch_id: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: Vec<RerunFailure>,
+}
+
+/// Records loaded and waiting for a provider slot at once; the provider's
+/// concurrency limit decides how many requests actually go out in parallel
+const MAX_RECORDS_IN_FLIGHT: usize = 8;
+
+/// Recognize the stored images of history records again with another config.
+/// The new records keep the original prompt, sampling, token limit and few-shot
+/// examples, link back to their original through `rerun_of` and land in the
+/// review queue of a new batch.
+pub async fn rerun_many(
+    ids: &[i64],
+    config_id: i64,
+    on_progress: impl Fn(RerunProgress) + Send + Sync,
+) -> Result<RerunReport, AppError> {
+    if ids.is_empty() {
+        return Err(ErrorCode::NoImagesSelected.into());
+    }
+    // Fail before queueing anything when the config can't be used
+    let config = llm::load_active_config(config_id)?;
+    let batch_id = format!("rerun-{}-{}", config.name, chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let output_language = settings::get_all_settings().map_err(|e| e.to_string())?.output_language;
+
+    let total = ids.len();
+    let completed = AtomicUsize::new(0);
+    let (batch_id_ref, completed_ref, on_progress, output_language) =
+        (&batch_id, &completed, &on_progress, &output_language);
+    let outcomes: Vec<(i64, Result<(), String>)> = stream::iter(ids.iter().copied())
+        .map(|id| async move {
+            let outcome = rerun_one(id, config_id, batch_id_ref, output_language).await;
+            on_progress(RerunProgress {
+                batch_id: batch_id_ref.clone(),
+                completed: completed_ref.fetch_add(1, Ordering::SeqCst) + 1,
+                total,
+                history_id: id,
+                success: outcome.is_ok(),
+            });
+            (id, outcome)
+        })
+        .buffer_unordered(MAX_RECORDS_IN_FLIGHT)
+        .collect()
+        .await;
+
+    let failed: Vec<RerunFailure> = outcomes
+        .into_iter()
+        .filter_map(|(history_id, outcome)| outcome.err().map(|error| RerunFailure { history_id, error }))
+        .collect();
+    Ok(RerunReport { batch_id, total, succeeded: total - failed.len(), failed })
+}
+
+async fn rerun_one(id: i64, config_id: i64, batch_id: &str, output_language: &str) -> Result<(), String> {
+    let record = history::get_history_by_id(id)
+        .map_err(|e| e.to_string())?
+        .ok_or(ErrorCode::HistoryNotFound)?;
+    let image_file = record.image_file.ok_or(ErrorCode::NoStoredImage)?;
+    let (base64, mime_type) = load_image_file(&image_store::image_file_path(&image_file)?)?;
+
+    let options = RecognitionOptions {
+        temperature: record.temperature,
+        top_p: record.top_p,
+        max_tokens: record.max_tokens,
+        output_language: Some(output_language.to_string()),
+        stream: Some(false),
+        batch_id: Some(batch_id.to_string()),
+        rerun_of: Some(id),
+        source_path: record.image_path,
+        examples: record.template_id.map(llm::load_examples).unwrap_or_default(),
+        template_id: record.template_id,
+        ..Default::default()
+    };
+    let result = llm::recognize(config_id, &base64, &mime_type, &record.prompt, Some(options), None).await;
+    if result.success {
+        Ok(())
+    } else {
+        Err(result.error.unwrap_or_default())
+    }
+}
@@ -0,0 +1,216 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use crate::db::settings::AppSettings;
+use super::i18n::ErrorCode;
+
+/// Value a setting accepts
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum SettingType {
+    Boolean,
+    Integer { min: i64, max: i64 },
+    Number { min: f64, max: f64 },
+    String,
+    Enum { options: &'static [&'static str] },
+    /// Object of string values, e.g. extra headers
+    StringMap,
+    /// Object of integers in a range, e.g. limits per provider
+    IntegerMap { min: i64, max: i64 },
+    /// Id of a record, or null for none
+    OptionalId,
+}
+
+impl SettingType {
+    fn accepts(&self, value: &Value) -> bool {
+        let in_range = |n: Option<i64>, min: i64, max: i64| n.is_some_and(|n| (min..=max).contains(&n));
+        match self {
+            Self::Boolean => value.is_boolean(),
+            Self::Integer { min, max } => in_range(value.as_i64(), *min, *max),
+            Self::Number { min, max } => value.as_f64().is_some_and(|n| (*min..=*max).contains(&n)),
+            Self::String => value.is_string(),
+            Self::Enum { options } => value.as_str().is_some_and(|s| options.contains(&s)),
+            Self::StringMap => value.as_object().is_some_and(|map| map.values().all(Value::is_string)),
+            Self::IntegerMap { min, max } => value
+                .as_object()
+                .is_some_and(|map| map.values().all(|v| in_range(v.as_i64(), *min, *max))),
+            Self::OptionalId => value.is_null() || in_range(value.as_i64(), 1, i64::MAX),
+        }
+    }
+
+    /// What a valid value looks like, for error messages
+    fn expected(&self) -> String {
+        match self {
+            Self::Boolean => "true | false".to_string(),
+            Self::Integer { min, max } => format!("{} – {}", min, max),
+            Self::Number { min, max } => format!("{} – {}", min, max),
+            Self::String => "string".to_string(),
+            Self::Enum { options } => options.join(" | "),
+            Self::StringMap => "{\"name\": \"value\"}".to_string(),
+            Self::IntegerMap { min, max } => format!("{{\"name\": {} – {}}}", min, max),
+            Self::OptionalId => "id | null".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingSchema {
+    /// Key as used by `update_settings` and in `AppSettings`
+    pub key: &'static str,
+    #[serde(flatten)]
+    pub value_type: SettingType,
+    pub default: Value,
+}
+
+/// Type of every setting `update_settings` accepts
+fn setting_types() -> Vec<(&'static str, SettingType)> {
+    use SettingType::*;
+    vec![
+        ("theme", Enum { options: &["light", "dark", "system"] }),
+        ("language", Enum { options: &["zh-CN", "en-US", "ja-JP"] }),
+        ("imageMaxSize", Integer { min: 1, max: 50 }),
+        ("compressThreshold", Integer { min: 10, max: 10240 }),
+        ("autoCompress", Boolean),
+        ("autoRotate", Boolean),
+        ("autoCrop", Boolean),
+        ("decodeCodes", Boolean),
+        ("defaultTemperature", Number { min: 0.0, max: 2.0 }),
+        ("defaultTopP", Number { min: 0.0, max: 1.0 }),
+        ("defaultMaxTokens", Integer { min: 1, max: 200_000 }),
+        ("defaultStream", Boolean),
+        ("outputLanguage", String),
+        ("userAgent", String),
+        ("extraHeaders", StringMap),
+        ("http2Enabled", Boolean),
+        ("tcpKeepaliveSecs", Integer { min: 0, max: 3600 }),
+        ("http2KeepAliveSecs", Integer { min: 0, max: 3600 }),
+        ("connectTimeoutSecs", Integer { min: 0, max: 300 }),
+        ("poolIdleTimeoutSecs", Integer { min: 0, max: 3600 }),
+        ("caBundlePath", String),
+        ("providerConcurrency", IntegerMap { min: 1, max: 64 }),
+        ("healthCheckMinutes", Integer { min: 0, max: 1440 }),
+        ("saveFailedRecognitions", Boolean),
+        ("storeReasoning", Boolean),
+        ("scrubHistoryPii", Boolean),
+        ("estimateConfidence", Boolean),
+        ("lowConfidenceThreshold", Number { min: 0.0, max: 1.0 }),
+        ("updateChannel", Enum { options: &["stable", "beta"] }),
+        ("redactionConfigId", OptionalId),
+        ("encryptHistory", Boolean),
+        ("lockIdleMinutes", Integer { min: 0, max: 1440 }),
+        ("ttsVoice", String),
+        ("ttsRate", Number { min: 0.5, max: 2.0 }),
+        ("notebookPath", String),
+        ("notebookThumbnails", Boolean),
+        ("inferenceDevice", Enum { options: &["auto", "cpu", "cuda", "directml", "metal"] }),
+        ("syncEnabled", Boolean),
+        ("syncBackend", Enum { options: &["webdav", "s3"] }),
+        ("syncEndpoint", String),
+        ("syncBucket", String),
+        ("syncRegion", String),
+        ("syncUsername", String),
+        ("shareTarget", Enum { options: &["gist", "custom"] }),
+        ("shareEndpoint", String),
+        ("sharePublic", Boolean),
+        ("smtpHost", String),
+        ("smtpPort", Integer { min: 1, max: 65535 }),
+        ("smtpSecurity", Enum { options: &["starttls", "tls", "none"] }),
+        ("smtpUsername", String),
+        ("smtpFrom", String),
+        ("emailRecipients", String),
+        ("notionDatabaseId", String),
+    ]
+}
+
+/// Every setting with its type and default, for rendering settings forms
+pub fn settings_schema() -> Vec<SettingSchema> {
+    let defaults = serde_json::to_value(AppSettings::default_settings()).unwrap_or_default();
+    setting_types()
+        .into_iter()
+        .map(|(key, value_type)| SettingSchema {
+            key,
+            value_type,
+            default: defaults[key].clone(),
+        })
+        .collect()
+}
+
+/// Reject unknown keys and values of the wrong type or out of range
+pub fn validate_updates(updates: &HashMap<String, Value>) -> Result<(), ErrorCode> {
+    let types: HashMap<&str, SettingType> = setting_types().into_iter().collect();
+    for (key, value) in updates {
+        let value_type = types
+            .get(key.as_str())
+            .ok_or_else(|| ErrorCode::UnknownSetting { key: key.clone() })?;
+        if !value_type.accepts(value) {
+            return Err(ErrorCode::InvalidSetting {
+                key: key.clone(),
+                expected: value_type.expected(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(key: &str, value: Value) -> Result<(), ErrorCode> {
+        validate_updates(&HashMap::from([(key.to_string(), value)]))
+    }
+
+    #[test]
+    fn test_schema_covers_settings() {
+        let defaults = serde_json::to_value(AppSettings::default_settings()).unwrap();
+        let schema = settings_schema();
+        assert_eq!(schema.len(), defaults.as_object().unwrap().len());
+        for setting in &schema {
+            assert!(defaults.get(setting.key).is_some(), "{} is not a setting", setting.key);
+            assert!(
+                setting.value_type.accepts(&setting.default),
+                "default of {} is invalid",
+                setting.key
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_updates() {
+        assert_eq!(validate("defaultTemperature", json!(1.5)), Ok(()));
+        assert_eq!(validate("theme", json!("dark")), Ok(()));
+        assert_eq!(validate("redactionConfigId", json!(null)), Ok(()));
+        assert_eq!(validate("providerConcurrency", json!({"openai": 4})), Ok(()));
+
+        assert_eq!(
+            validate("defaultTopP", json!(1.5)),
+            Err(ErrorCode::InvalidSetting { key: "defaultTopP".to_string(), expected: "0 – 1".to_string() })
+        );
+        assert_eq!(
+            validate("theme", json!("blue")),
+            Err(ErrorCode::InvalidSetting {
+                key: "theme".to_string(),
+                expected: "light | dark | system".to_string(),
+            })
+        );
+        assert!(validate("autoRotate", json!("true")).is_err());
+        assert!(validate("imageMaxSize", json!(2.5)).is_err());
+        assert!(validate("extraHeaders", json!({"X-Id": 1})).is_err());
+        assert_eq!(validate("colour", json!(1)), Err(ErrorCode::UnknownSetting { key: "colour".to_string() }));
+    }
+
+    #[test]
+    fn test_schema_serialization() {
+        let schema = serde_json::to_value(settings_schema()).unwrap();
+        assert_eq!(
+            schema[0],
+            json!({ "key": "theme", "type": "enum", "options": ["light", "dark", "system"], "default": "system" })
+        );
+        assert_eq!(
+            schema[8],
+            json!({ "key": "defaultTemperature", "type": "number", "min": 0.0, "max": 2.0, "default": 0.0 })
+        );
+    }
+}
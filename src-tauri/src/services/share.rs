@@ -0,0 +1,119 @@
+//! Share a result through a paste service: a GitHub Gist created with the
+//! user's token, or a custom endpoint that takes the text as the request body
+//! and answers with the link.
+
+use serde_json::{json, Value};
+use std::time::Duration;
+use crate::db::settings;
+use crate::utils::crypto::{decrypt, encrypt};
+use crate::error::AppError;
+use super::http;
+use super::i18n::ErrorCode;
+
+/// Settings key of the GitHub token or the custom endpoint's bearer token.
+/// `security:` keys can't be written or reset through the generic settings commands.
+pub const TOKEN_KEY: &str = "security:shareToken";
+
+const GIST_API_URL: &str = "https://api.github.com/gists";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Store the share token encrypted; an empty token removes it
+pub fn set_token(token: &str) -> Result<(), String> {
+    if token.is_empty() {
+        settings::delete_setting(TOKEN_KEY).map_err(|e| e.to_string())
+    } else {
+        settings::set_setting(TOKEN_KEY, &encrypt(token)).map_err(|e| e.to_string())
+    }
+}
+
+pub fn has_token() -> Result<bool, String> {
+    Ok(stored_token()?.is_some())
+}
+
+fn stored_token() -> Result<Option<String>, String> {
+    let value = settings::get_setting(TOKEN_KEY).map_err(|e| e.to_string())?;
+    Ok(value.and_then(|value| decrypt(&value).ok()).filter(|value| !value.is_empty()))
+}
+
+/// Upload `content` to the share target from settings and return its link
+pub async fn share(content: &str, title: &str) -> Result<String, AppError> {
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    let token = stored_token()?;
+    let client = http::service_client(REQUEST_TIMEOUT).map_err(|detail| ErrorCode::ShareFailed { detail })?;
+
+    let request = match app_settings.share_target.as_str() {
+        "custom" => {
+            if app_settings.share_endpoint.trim().is_empty() {
+                return Err(ErrorCode::ShareNotConfigured.into());
+            }
+            let request = client
+                .post(app_settings.share_endpoint.trim())
+                .header("Content-Type", "text/plain; charset=utf-8")
+                .body(content.to_string());
+            match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        }
+        _ => {
+            let token = token.ok_or(ErrorCode::ShareNotConfigured)?;
+            client
+                .post(GIST_API_URL)
+                .bearer_auth(token)
+                .header("Accept", "application/vnd.github+json")
+                .json(&gist_body(content, title, app_settings.share_public))
+        }
+    };
+
+    let response = request.send().await.map_err(|e| ErrorCode::ShareFailed { detail: e.to_string() })?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| ErrorCode::ShareFailed { detail: e.to_string() })?;
+    if !status.is_success() {
+        let detail = format!("HTTP {}: {}", status.as_u16(), body.chars().take(200).collect::<String>());
+        return Err(ErrorCode::ShareFailed { detail }.into());
+    }
+    share_link(&body).ok_or_else(|| ErrorCode::ShareFailed { detail: "no link in the response".to_string() }.into())
+}
+
+fn gist_body(content: &str, title: &str, public: bool) -> Value {
+    json!({
+        "description": title,
+        "public": public,
+        "files": { "result.md": { "content": content } }
+    })
+}
+
+/// Link in a paste service response: `html_url` of a gist, a `url` or `link`
+/// field of other JSON APIs, or a body that is just the URL
+fn share_link(body: &str) -> Option<String> {
+    let link = match serde_json::from_str::<Value>(body) {
+        Ok(value) => ["html_url", "url", "link"]
+            .iter()
+            .find_map(|key| value[key].as_str())?
+            .to_string(),
+        Err(_) => body.trim().to_string(),
+    };
+    (link.starts_with("https://") || link.starts_with("http://")).then_some(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_link() {
+        let gist = r#"{"url": "https://api.github.com/gists/abc", "html_url": "https://gist.github.com/abc"}"#;
+        assert_eq!(share_link(gist).as_deref(), Some("https://gist.github.com/abc"));
+        assert_eq!(share_link(r#"{"link": "https://paste.example.com/x1"}"#).as_deref(), Some("https://paste.example.com/x1"));
+        assert_eq!(share_link("https://0x0.st/abc.txt\n").as_deref(), Some("https://0x0.st/abc.txt"));
+        assert_eq!(share_link(r#"{"id": 1}"#), None);
+        assert_eq!(share_link("Created"), None);
+    }
+
+    #[test]
+    fn test_gist_body() {
+        let body = gist_body("# Result", "Receipt", false);
+        assert_eq!(body["files"]["result.md"]["content"], "# Result");
+        assert_eq!(body["public"], false);
+    }
+}
@@ -0,0 +1,100 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tts::Tts;
+use crate::db::settings;
+use crate::error::AppError;
+use super::i18n::ErrorCode;
+
+/// Slowest and fastest speech rate settings, relative to the voice's normal rate
+pub const MIN_RATE: f32 = 0.5;
+pub const MAX_RATE: f32 = 2.0;
+
+/// The platform speech engine, created on first use
+static ENGINE: Lazy<Mutex<Option<Tts>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceInfo {
+    pub id: String,
+    pub name: String,
+    /// BCP 47 tag, e.g. `zh-CN`
+    pub language: String,
+}
+
+fn with_engine<T>(f: impl FnOnce(&mut Tts) -> Result<T, tts::Error>) -> Result<T, AppError> {
+    let unavailable = |e: tts::Error| -> AppError { ErrorCode::SpeechUnavailable { detail: e.to_string() }.into() };
+    let mut engine = ENGINE.lock();
+    if engine.is_none() {
+        *engine = Some(Tts::default().map_err(unavailable)?);
+    }
+    let tts = engine.as_mut().expect("engine was just created");
+    f(tts).map_err(unavailable)
+}
+
+/// Map a rate setting (1.0 normal, 0.5 half, 2.0 double speed) onto the
+/// backend's range, whose normal point and bounds differ per platform
+pub fn backend_rate(rate: f32, min: f32, normal: f32, max: f32) -> f32 {
+    let rate = rate.clamp(MIN_RATE, MAX_RATE);
+    if rate >= 1.0 {
+        normal + (max - normal) * (rate - 1.0) / (MAX_RATE - 1.0)
+    } else {
+        normal - (normal - min) * (1.0 - rate) / (1.0 - MIN_RATE)
+    }
+}
+
+/// Read text aloud with the voice and rate from settings, interrupting
+/// anything still being spoken
+pub fn speak(text: &str) -> Result<(), AppError> {
+    let app_settings = settings::get_all_settings().map_err(|e| e.to_string())?;
+    with_engine(|tts| {
+        if !app_settings.tts_voice.is_empty() {
+            // A voice that was uninstalled falls back to the system default
+            if let Some(voice) = tts.voices()?.into_iter().find(|v| v.id() == app_settings.tts_voice) {
+                tts.set_voice(&voice)?;
+            }
+        }
+        let rate = backend_rate(app_settings.tts_rate, tts.min_rate(), tts.normal_rate(), tts.max_rate());
+        tts.set_rate(rate)?;
+        tts.speak(text, true)?;
+        Ok(())
+    })
+}
+
+pub fn stop() -> Result<(), AppError> {
+    with_engine(|tts| {
+        tts.stop()?;
+        Ok(())
+    })
+}
+
+pub fn voices() -> Result<Vec<VoiceInfo>, AppError> {
+    with_engine(|tts| {
+        Ok(tts
+            .voices()?
+            .into_iter()
+            .map(|voice| VoiceInfo {
+                id: voice.id(),
+                name: voice.name(),
+                language: voice.language().to_string(),
+            })
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_rate() {
+        // speech-dispatcher style range around 0
+        assert_eq!(backend_rate(1.0, -100.0, 0.0, 100.0), 0.0);
+        assert_eq!(backend_rate(2.0, -100.0, 0.0, 100.0), 100.0);
+        assert_eq!(backend_rate(0.5, -100.0, 0.0, 100.0), -100.0);
+        assert_eq!(backend_rate(0.75, -100.0, 0.0, 100.0), -50.0);
+        // AVFoundation style range with an off-center normal rate
+        assert_eq!(backend_rate(1.5, 0.0, 0.5, 1.0), 0.75);
+        assert_eq!(backend_rate(9.0, 0.0, 0.5, 1.0), 1.0);
+    }
+}
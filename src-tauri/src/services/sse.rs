@@ -0,0 +1,172 @@
+/// A dispatched Server-Sent Event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incremental Server-Sent Events decoder.
+///
+/// Bytes are buffered until a full line is available, so UTF-8 characters
+/// split across network chunks are decoded intact. Handles LF, CRLF and CR
+/// line endings, `event:` fields, comments and multi-line `data:` payloads.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a network chunk and return the events completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+
+        while i < self.buffer.len() {
+            let line_end = match self.buffer[i] {
+                b'\n' => 1,
+                // A trailing CR may be the first half of a CRLF split across chunks
+                b'\r' if i + 1 == self.buffer.len() => break,
+                b'\r' if self.buffer[i + 1] == b'\n' => 2,
+                b'\r' => 1,
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            let line = String::from_utf8_lossy(&self.buffer[start..i]).into_owned();
+            self.process_line(&line, &mut events);
+            i += line_end;
+            start = i;
+        }
+
+        self.buffer.drain(..start);
+        events
+    }
+
+    /// Flush whatever is left when the stream ends without a trailing blank line
+    pub fn finish(&mut self) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+
+        if !self.buffer.is_empty() {
+            let rest = std::mem::take(&mut self.buffer);
+            let line = String::from_utf8_lossy(&rest);
+            self.process_line(line.trim_end_matches('\r'), &mut events);
+        }
+        self.process_line("", &mut events);
+
+        events
+    }
+
+    fn process_line(&mut self, line: &str, events: &mut Vec<SseEvent>) {
+        // A blank line dispatches the pending event
+        if line.is_empty() {
+            let event = self.event.take();
+            if !self.data.is_empty() {
+                events.push(SseEvent {
+                    event,
+                    data: std::mem::take(&mut self.data).join("\n"),
+                });
+            }
+            return;
+        }
+
+        // Comment / keep-alive
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            // id / retry and unknown fields are not needed by the adapters
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENAI_STREAM: &str = include_str!("fixtures/openai_stream.txt");
+    const ANTHROPIC_STREAM: &str = include_str!("fixtures/anthropic_stream.txt");
+
+    fn decode_in_chunks(input: &[u8], chunk_size: usize) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            events.extend(decoder.feed(chunk));
+        }
+        events.extend(decoder.finish());
+        events
+    }
+
+    #[test]
+    fn test_openai_fixture() {
+        let events = decode_in_chunks(OPENAI_STREAM.as_bytes(), 4096);
+        assert_eq!(events.len(), 6);
+        assert!(events[1].data.contains("图片中的"));
+        assert_eq!(events[5].data, "[DONE]");
+    }
+
+    #[test]
+    fn test_anthropic_fixture_event_names() {
+        let events = decode_in_chunks(ANTHROPIC_STREAM.as_bytes(), 4096);
+        let names: Vec<&str> = events.iter().filter_map(|e| e.event.as_deref()).collect();
+        assert_eq!(
+            names,
+            [
+                "message_start",
+                "content_block_start",
+                "ping",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_boundaries_do_not_matter() {
+        // Splitting at every byte cuts multi-byte characters and CRLF pairs in half
+        for fixture in [OPENAI_STREAM, ANTHROPIC_STREAM] {
+            let crlf = fixture.replace('\n', "\r\n");
+            let expected = decode_in_chunks(fixture.as_bytes(), 4096);
+            for input in [fixture.as_bytes(), crlf.as_bytes()] {
+                for chunk_size in [1, 2, 3, 7] {
+                    assert_eq!(decode_in_chunks(input, chunk_size), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_line_data_and_comments() {
+        let input = b": keep-alive\nevent: note\ndata: first\ndata:second\n\ndata: tail";
+        let events = decode_in_chunks(input, 5);
+        assert_eq!(
+            events,
+            [
+                SseEvent { event: Some("note".to_string()), data: "first\nsecond".to_string() },
+                SseEvent { event: None, data: "tail".to_string() },
+            ]
+        );
+    }
+}
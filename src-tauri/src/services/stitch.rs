@@ -0,0 +1,199 @@
+//! Stitching sequential screenshots of a scrolled view, like a long chat, into
+//! one tall image so it is recognized and stored as a single history entry.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GenericImageView, ImageFormat, RgbaImage};
+use serde::Serialize;
+use std::io::Cursor;
+use super::image::decode_image;
+
+/// Columns sampled per row when comparing rows
+const SAMPLE_COLUMNS: u32 = 64;
+/// Mean difference in gray levels up to which two rows count as equal, allowing
+/// for compression noise
+const ROW_TOLERANCE: f32 = 6.0;
+/// Fixed title bars and input boxes take at most this share of the height
+const MAX_FIXED_DIVISOR: usize = 3;
+/// Overlaps shorter than this are too ambiguous to trust
+const MIN_OVERLAP: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StitchedImage {
+    pub base64: String,
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// Rows of each screenshot found again in the one before it, 0 when no
+    /// overlap was found and the screenshot was appended as is
+    pub overlaps: Vec<u32>,
+}
+
+/// Sampled gray values of one row
+type Row = Vec<u8>;
+
+/// Stitch screenshots given top to bottom. Screenshots are scaled to the width
+/// of the first. Rows that stay put while scrolling (title bars, input boxes)
+/// are kept once, at the top and bottom of the result. The result is PNG.
+pub fn stitch_screenshots(inputs: &[String]) -> Result<StitchedImage, String> {
+    if inputs.len() < 2 {
+        return Err("At least two screenshots are needed".to_string());
+    }
+
+    let mut images: Vec<RgbaImage> = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let image_data = BASE64.decode(input).map_err(|e| format!("Invalid base64: {}", e))?;
+        let img = decode_image(&image_data)?.to_rgba8();
+        let width = images.first().map_or(img.width(), |first| first.width());
+        images.push(if img.width() == width {
+            img
+        } else {
+            let height = (img.height() as u64 * width as u64 / img.width() as u64).max(1) as u32;
+            imageops::resize(&img, width, height, FilterType::Triangle)
+        });
+    }
+    let rows: Vec<Vec<Row>> = images.iter().map(sample_rows).collect();
+
+    // Row range taken from each screenshot
+    let mut ranges: Vec<(usize, usize)> = rows.iter().map(|r| (0, r.len())).collect();
+    let mut overlaps = Vec::with_capacity(images.len() - 1);
+    for i in 1..rows.len() {
+        let (prev, next) = (&rows[i - 1], &rows[i]);
+        let header = fixed_rows(prev.iter(), next.iter(), prev.len().min(next.len()));
+        let footer = fixed_rows(prev.iter().rev(), next.iter().rev(), prev.len().min(next.len()));
+        let prev_content = &prev[header.min(prev.len() - footer)..prev.len() - footer];
+        let next_content = &next[header.min(next.len() - footer)..next.len() - footer];
+        let overlap = find_overlap(prev_content, next_content);
+
+        // The footer of the earlier screenshot reappears below, so only the last one keeps it
+        ranges[i - 1].1 = ranges[i - 1].1.saturating_sub(footer).max(ranges[i - 1].0);
+        ranges[i].0 = (header + overlap).min(ranges[i].1);
+        overlaps.push(overlap as u32);
+    }
+
+    let width = images[0].width();
+    let height: usize = ranges.iter().map(|(start, end)| end - start).sum();
+    let mut canvas = RgbaImage::new(width, height.max(1) as u32);
+    let mut y = 0;
+    for (img, (start, end)) in images.iter().zip(&ranges) {
+        if end > start {
+            let part = img.view(0, *start as u32, width, (end - start) as u32);
+            imageops::replace(&mut canvas, &*part, 0, y as i64);
+            y += end - start;
+        }
+    }
+
+    let mut buffer = Vec::new();
+    let (width, height) = canvas.dimensions();
+    DynamicImage::ImageRgba8(canvas)
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(StitchedImage {
+        base64: BASE64.encode(&buffer),
+        mime_type: "image/png".to_string(),
+        width,
+        height,
+        overlaps,
+    })
+}
+
+fn sample_rows(img: &RgbaImage) -> Vec<Row> {
+    let gray = DynamicImage::ImageRgba8(img.clone()).to_luma8();
+    let columns = SAMPLE_COLUMNS.min(gray.width());
+    (0..gray.height())
+        .map(|y| {
+            (0..columns)
+                .map(|i| gray.get_pixel((2 * i + 1) * gray.width() / (2 * columns), y).0[0])
+                .collect()
+        })
+        .collect()
+}
+
+fn rows_match(a: &Row, b: &Row) -> bool {
+    let total: u32 = a.iter().zip(b).map(|(x, y)| x.abs_diff(*y) as u32).sum();
+    total as f32 <= ROW_TOLERANCE * a.len().max(1) as f32
+}
+
+/// Rows equal in both screenshots at the same position, counted from where the
+/// iterators start
+fn fixed_rows<'a>(a: impl Iterator<Item = &'a Row>, b: impl Iterator<Item = &'a Row>, height: usize) -> usize {
+    a.zip(b)
+        .take(height / MAX_FIXED_DIVISOR)
+        .take_while(|(a, b)| rows_match(a, b))
+        .count()
+}
+
+/// Longest run of rows at the end of `prev` that starts `next`, or 0
+fn find_overlap(prev: &[Row], next: &[Row]) -> usize {
+    (MIN_OVERLAP..=prev.len().min(next.len()))
+        .rev()
+        .find(|&overlap| {
+            prev[prev.len() - overlap..]
+                .iter()
+                .zip(next)
+                .all(|(a, b)| rows_match(a, b))
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// A page of noise, so every row differs from every other
+    fn page(height: u32) -> RgbaImage {
+        RgbaImage::from_fn(40, height, |x, y| {
+            let v = ((x.wrapping_mul(374_761_393) ^ y.wrapping_mul(668_265_263)).wrapping_mul(1_274_126_177) >> 24) as u8;
+            Rgba([v, v, v, 255])
+        })
+    }
+
+    /// Window of `page` from `top`, with a fixed title bar and input box drawn over it
+    fn screenshot(page: &RgbaImage, top: u32, height: u32) -> String {
+        let mut shot = page.view(0, top, page.width(), height).to_image();
+        for y in (0..6).chain(height - 4..height) {
+            for x in 0..shot.width() {
+                shot.put_pixel(x, y, Rgba([30, 30, 30, 255]));
+            }
+        }
+        let mut buffer = Vec::new();
+        DynamicImage::ImageRgba8(shot)
+            .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+            .unwrap();
+        BASE64.encode(&buffer)
+    }
+
+    #[test]
+    fn test_stitch_scrolled_screenshots() {
+        let page = page(200);
+        let shots = [screenshot(&page, 0, 100), screenshot(&page, 60, 100), screenshot(&page, 100, 100)];
+        let stitched = stitch_screenshots(&shots).unwrap();
+
+        // The page once, between one title bar and one input box
+        assert_eq!(stitched.overlaps, vec![30, 50]);
+        assert_eq!(stitched.height, 200);
+        let img = decode_image(&BASE64.decode(&stitched.base64).unwrap()).unwrap().to_rgba8();
+        assert_eq!(img.get_pixel(5, 2).0, [30, 30, 30, 255]);
+        assert_eq!(img.get_pixel(5, 198).0, [30, 30, 30, 255]);
+        for y in [6, 90, 120, 195] {
+            assert_eq!(img.get_pixel(5, y), page.get_pixel(5, y), "row {}", y);
+        }
+    }
+
+    #[test]
+    fn test_stitch_without_overlap_appends() {
+        let page = page(200);
+        let shots = [screenshot(&page, 0, 100), screenshot(&page, 100, 100)];
+        let stitched = stitch_screenshots(&shots).unwrap();
+        assert_eq!(stitched.overlaps, vec![0]);
+        // Title bar of the second and input box of the first are dropped
+        assert_eq!(stitched.height, 190);
+    }
+
+    #[test]
+    fn test_stitch_needs_two_screenshots() {
+        assert!(stitch_screenshots(&[screenshot(&page(50), 0, 50)]).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::zip::ZipWriter;
+
+/// A table found in a model response, first row being the header when present
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Table {
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Extract every Markdown pipe table and HTML `<table>` in order of appearance
+pub fn extract_tables(content: &str) -> Vec<Table> {
+    let mut found: Vec<(usize, Table)> = markdown_tables(content);
+    found.extend(html_tables(content));
+    found.sort_by_key(|(position, _)| *position);
+
+    found
+        .into_iter()
+        .map(|(_, table)| table)
+        .filter(|table| !table.rows.is_empty())
+        .collect()
+}
+
+fn markdown_tables(content: &str) -> Vec<(usize, Table)> {
+    let mut tables = Vec::new();
+    let lines: Vec<(usize, &str)> = content
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim()))
+        })
+        .collect();
+
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        let (position, header) = lines[i];
+        if !header.contains('|') || !is_separator_row(lines[i + 1].1) {
+            i += 1;
+            continue;
+        }
+
+        let mut rows = vec![split_row(header)];
+        i += 2;
+        while i < lines.len() && lines[i].1.contains('|') {
+            rows.push(split_row(lines[i].1));
+            i += 1;
+        }
+        tables.push((position, Table { rows }));
+    }
+
+    tables
+}
+
+/// `|---|:---:|` style row between the header and the body
+fn is_separator_row(line: &str) -> bool {
+    let cells = split_row(line);
+    !cells.is_empty()
+        && cells.iter().all(|cell| {
+            let cell = cell.trim_matches(':');
+            !cell.is_empty() && cell.chars().all(|c| c == '-')
+        })
+}
+
+fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn html_tables(content: &str) -> Vec<(usize, Table)> {
+    // ASCII lowercasing keeps byte offsets aligned with the original
+    let lower = content.to_ascii_lowercase();
+    let mut tables = Vec::new();
+    let mut from = 0;
+
+    while let Some(start) = lower[from..].find("<table").map(|i| i + from) {
+        let end = lower[start..].find("</table>").map_or(lower.len(), |i| start + i);
+
+        let mut rows = Vec::new();
+        let mut row_from = start;
+        while let Some(row_start) = lower[row_from..end].find("<tr").map(|i| i + row_from) {
+            let row_end = lower[row_start + 3..end].find("<tr").map_or(end, |i| row_start + 3 + i);
+            rows.push(html_cells(content, &lower, row_start, row_end));
+            row_from = row_end;
+        }
+
+        tables.push((start, Table { rows }));
+        from = end;
+    }
+
+    tables
+}
+
+fn html_cells(content: &str, lower: &str, start: usize, end: usize) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut from = start;
+
+    loop {
+        let next_td = lower[from..end].find("<td").map(|i| i + from);
+        let next_th = lower[from..end].find("<th").map(|i| i + from);
+        let Some(cell_start) = next_td.into_iter().chain(next_th).min() else {
+            break;
+        };
+        let Some(open_end) = lower[cell_start..end].find('>').map(|i| cell_start + i + 1) else {
+            break;
+        };
+        let close = lower[open_end..end]
+            .find("</t")
+            .map_or(end, |i| open_end + i);
+
+        let colspan = attribute(&lower[cell_start..open_end], "colspan")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(1)
+            .clamp(1, 100);
+        cells.push(html_text(&content[open_end..close]));
+        cells.extend(std::iter::repeat_n(String::new(), colspan - 1));
+
+        from = close.max(open_end);
+        if from >= end {
+            break;
+        }
+        from += 1;
+    }
+
+    cells
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let value = &tag[tag.find(name)? + name.len()..];
+    let value = value.trim_start().strip_prefix('=')?.trim_start();
+    let value = value.trim_start_matches(['"', '\'']);
+    let end = value.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(value.len());
+    Some(&value[..end])
+}
+
+/// Cell text with tags removed, `<br>` kept as line breaks and common entities decoded
+fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    let mut tag = String::new();
+
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                if tag.trim_start().to_ascii_lowercase().starts_with("br") {
+                    text.push('\n');
+                }
+            }
+            _ if in_tag => tag.push(c),
+            _ => text.push(c),
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// Tables as CSV, separated by an empty line. Starts with a BOM so Excel
+/// detects UTF-8 and shows Chinese text correctly.
+pub fn to_csv(tables: &[Table]) -> String {
+    let mut csv = String::from("\u{feff}");
+    for (i, table) in tables.iter().enumerate() {
+        if i > 0 {
+            csv.push_str("\r\n");
+        }
+        for row in &table.rows {
+            let line: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+            csv.push_str(&line.join(","));
+            csv.push_str("\r\n");
+        }
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Tables as an XLSX workbook with one sheet per table
+pub fn to_xlsx(tables: &[Table]) -> Vec<u8> {
+    let mut zip = ZipWriter::new();
+
+    let sheet_overrides: String = (1..=tables.len())
+        .map(|i| {
+            format!(
+                "<Override PartName=\"/xl/worksheets/sheet{}.xml\" \
+                 ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>",
+                i
+            )
+        })
+        .collect();
+    zip.add_file(
+        "[Content_Types].xml",
+        format!(
+            "{}<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" \
+             ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             {}</Types>",
+            XML_HEADER, sheet_overrides
+        )
+        .as_bytes(),
+    );
+
+    zip.add_file(
+        "_rels/.rels",
+        format!(
+            "{}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" \
+             Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" \
+             Target=\"xl/workbook.xml\"/></Relationships>",
+            XML_HEADER
+        )
+        .as_bytes(),
+    );
+
+    let sheets: String = (1..=tables.len())
+        .map(|i| format!("<sheet name=\"Table {0}\" sheetId=\"{0}\" r:id=\"rId{0}\"/>", i))
+        .collect();
+    zip.add_file(
+        "xl/workbook.xml",
+        format!(
+            "{}<workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+             <sheets>{}</sheets></workbook>",
+            XML_HEADER, sheets
+        )
+        .as_bytes(),
+    );
+
+    let relationships: String = (1..=tables.len())
+        .map(|i| {
+            format!(
+                "<Relationship Id=\"rId{0}\" \
+                 Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" \
+                 Target=\"worksheets/sheet{0}.xml\"/>",
+                i
+            )
+        })
+        .collect();
+    zip.add_file(
+        "xl/_rels/workbook.xml.rels",
+        format!(
+            "{}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">{}</Relationships>",
+            XML_HEADER, relationships
+        )
+        .as_bytes(),
+    );
+
+    for (i, table) in tables.iter().enumerate() {
+        zip.add_file(&format!("xl/worksheets/sheet{}.xml", i + 1), sheet_xml(table).as_bytes());
+    }
+
+    zip.finish()
+}
+
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+
+fn sheet_xml(table: &Table) -> String {
+    let mut xml = format!(
+        "{}<worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>",
+        XML_HEADER
+    );
+
+    for (r, row) in table.rows.iter().enumerate() {
+        xml.push_str(&format!("<row r=\"{}\">", r + 1));
+        for (c, value) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(c), r + 1);
+            if is_number(value) {
+                xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, value));
+            } else if !value.is_empty() {
+                xml.push_str(&format!(
+                    "<c r=\"{}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+                    reference,
+                    xml_escape(value)
+                ));
+            }
+        }
+        xml.push_str("</row>");
+    }
+
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// Zero-based column index to a spreadsheet column name: 0 -> A, 26 -> AA
+fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        name.push(b'A' + rem as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Plain decimal numbers become numeric cells; codes with leading zeros stay text
+fn is_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    !digits.is_empty()
+        && !leading_zero
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && value.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_markdown_and_html_tables() {
+        let content = "说明文字\n\n| 名称 | 金额 |\n|:---|---:|\n| 苹果 | 3.5 |\n| a \\| b | 10 |\n\n\
+            <table><tr><th>A</th><th>B</th></tr><tr><td colspan=\"2\">x &amp; y<br/>z</td></tr></table>";
+
+        let tables = extract_tables(content);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].rows[0], vec!["名称", "金额"]);
+        assert_eq!(tables[0].rows[2], vec!["a | b", "10"]);
+        assert_eq!(tables[1].rows[1], vec!["x & y\nz".to_string(), String::new()]);
+    }
+
+    #[test]
+    fn test_plain_text_has_no_tables() {
+        assert!(extract_tables("a | b\nno separator here").is_empty());
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let tables = vec![
+            Table { rows: vec![vec!["a,b".to_string(), "say \"hi\"".to_string()]] },
+            Table { rows: vec![vec!["c".to_string()]] },
+        ];
+        assert_eq!(to_csv(&tables), "\u{feff}\"a,b\",\"say \"\"hi\"\"\"\r\n\r\nc\r\n");
+    }
+
+    #[test]
+    fn test_cell_helpers() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert!(is_number("3.5"));
+        assert!(is_number("-12"));
+        assert!(!is_number("007"));
+        assert!(!is_number("1e5"));
+    }
+
+    #[test]
+    fn test_xlsx_is_a_zip_with_one_sheet_per_table() {
+        let tables = vec![
+            Table { rows: vec![vec!["x".to_string()]] },
+            Table { rows: vec![vec!["1".to_string()]] },
+        ];
+        let bytes = to_xlsx(&tables);
+        assert!(bytes.starts_with(b"PK\x03\x04"));
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("xl/worksheets/sheet2.xml"));
+        assert!(!text.contains("sheet3.xml"));
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+// Average reading speeds used for the estimate
+const CJK_CHARS_PER_MINUTE: f64 = 300.0;
+const WORDS_PER_MINUTE: f64 = 200.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStats {
+    pub char_count: i32,
+    pub word_count: i32,
+    pub language: Option<String>,
+    pub reading_time_secs: i32,
+}
+
+/// Compute character/word counts, a best-effort language guess and an
+/// estimated reading time for a recognition result
+pub fn analyze(text: &str) -> TextStats {
+    let mut char_count = 0;
+    let mut cjk_count = 0;
+    let mut latin_words = 0;
+    let mut in_word = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            in_word = false;
+            continue;
+        }
+        char_count += 1;
+
+        if is_cjk(c) {
+            cjk_count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                latin_words += 1;
+                in_word = true;
+            }
+        } else {
+            in_word = false;
+        }
+    }
+
+    // Each CJK character reads roughly like a word
+    let word_count = cjk_count + latin_words;
+    let minutes = cjk_count as f64 / CJK_CHARS_PER_MINUTE + latin_words as f64 / WORDS_PER_MINUTE;
+
+    TextStats {
+        char_count,
+        word_count,
+        language: detect_language(text),
+        reading_time_secs: (minutes * 60.0).ceil() as i32,
+    }
+}
+
+/// A paragraph of a result with its detected language
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageSegment {
+    pub text: String,
+    pub language: Option<String>,
+}
+
+/// Split text into paragraphs (separated by blank lines) and tag each with
+/// its own language, so mixed-language documents can be handled per section
+pub fn segment_by_language(text: &str) -> Vec<LanguageSegment> {
+    let mut segments = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !paragraph.is_empty() {
+                let text = paragraph.join("\n");
+                segments.push(LanguageSegment {
+                    language: detect_language(&text),
+                    text,
+                });
+                paragraph.clear();
+            }
+        } else {
+            paragraph.push(line);
+        }
+    }
+
+    segments
+}
+
+/// Guess the dominant language from the Unicode scripts present in the text
+pub fn detect_language(text: &str) -> Option<String> {
+    let mut han = 0;
+    let mut kana = 0;
+    let mut hangul = 0;
+    let mut cyrillic = 0;
+    let mut latin = 0;
+
+    for c in text.chars() {
+        match c as u32 {
+            0x3040..=0x30FF => kana += 1,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => hangul += 1,
+            0x0400..=0x04FF => cyrillic += 1,
+            _ if is_cjk(c) => han += 1,
+            _ if c.is_ascii_alphabetic() => latin += 1,
+            _ => {}
+        }
+    }
+
+    // Japanese text mixes kanji with kana, so any meaningful amount of kana wins
+    if kana > 0 && kana * 5 >= han {
+        return Some("ja".to_string());
+    }
+
+    let candidates = [("zh", han), ("ko", hangul), ("ru", cyrillic), ("en", latin)];
+    candidates
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map(|(lang, _)| lang.to_string())
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF | 0x3040..=0x30FF | 0xAC00..=0xD7AF
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_mixed_text() {
+        let stats = analyze("Hello world 你好");
+        assert_eq!(stats.char_count, 12);
+        assert_eq!(stats.word_count, 4);
+        assert_eq!(stats.reading_time_secs, 1);
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("请识别这张图片").as_deref(), Some("zh"));
+        assert_eq!(detect_language("これはテストです").as_deref(), Some("ja"));
+        assert_eq!(detect_language("The quick brown fox").as_deref(), Some("en"));
+        assert_eq!(detect_language("1234 !!"), None);
+    }
+
+    #[test]
+    fn test_segment_by_language() {
+        let segments = segment_by_language("第一段中文内容\n第二行\n\n\nAn English paragraph\n\n2024\n");
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].text, "第一段中文内容\n第二行");
+        assert_eq!(segments[0].language.as_deref(), Some("zh"));
+        assert_eq!(segments[1].language.as_deref(), Some("en"));
+        assert_eq!(segments[2].language, None);
+    }
+}
@@ -0,0 +1,120 @@
+use serde_json::json;
+use std::path::Path;
+use crate::db::workspace;
+use super::i18n;
+
+/// Glossary, tags and output folder applied around a recognition
+#[derive(Debug, Clone, Default)]
+pub struct PipelineContext {
+    pub glossary: Vec<String>,
+    /// Stored with the history record
+    pub tags: Vec<String>,
+    pub output_dir: Option<String>,
+    /// Format of the files written to `output_dir`, see `write_to_sink`
+    pub export_format: Option<String>,
+}
+
+impl PipelineContext {
+    /// Context of the active workspace, empty when none is active
+    pub fn from_active_workspace() -> Self {
+        match workspace::get_active_workspace() {
+            Ok(Some(ws)) => Self {
+                glossary: ws.glossary,
+                tags: ws.tags,
+                output_dir: ws.output_dir,
+                export_format: Some(ws.export_format),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+/// Append the workspace glossary to a prompt so the model keeps domain terms intact
+pub fn apply_glossary(prompt: &str, glossary: &[String], language: &str) -> String {
+    let terms: Vec<&str> = glossary
+        .iter()
+        .map(|term| term.trim())
+        .filter(|term| !term.is_empty())
+        .collect();
+    if terms.is_empty() {
+        return prompt.to_string();
+    }
+
+    format!("{}\n\n{}", prompt.trim_end(), i18n::glossary_hint(language, &terms.join(", ")))
+}
+
+/// Write a result into the workspace output directory and return the file path.
+/// `json` and `csv` files carry the time and tags along with the text; without
+/// an export format the text is written as is to a Markdown file.
+pub fn write_to_sink(
+    output_dir: &str,
+    content: &str,
+    export_format: Option<&str>,
+    tags: &[String],
+) -> Result<String, String> {
+    let dir = Path::new(output_dir);
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+
+    let now = chrono::Local::now();
+    let (extension, data) = sink_file(content, export_format, tags, &now.to_rfc3339());
+    let path = dir.join(format!("{}.{}", now.format("%Y%m%d_%H%M%S_%3f"), extension));
+    std::fs::write(&path, data).map_err(|e| format!("写入输出文件失败: {}", e))?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// File extension and contents of a sink file
+fn sink_file(content: &str, export_format: Option<&str>, tags: &[String], created_at: &str) -> (&'static str, String) {
+    match export_format {
+        Some("json") => {
+            let value = json!({ "createdAt": created_at, "tags": tags, "content": content });
+            ("json", serde_json::to_string_pretty(&value).unwrap_or_default())
+        }
+        Some("csv") => {
+            let row = [created_at, &tags.join(";"), content].map(csv_field).join(",");
+            ("csv", format!("createdAt,tags,content\n{}\n", row))
+        }
+        _ => ("md", content.to_string()),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_glossary() {
+        assert_eq!(apply_glossary("识别文字", &[], "zh-CN"), "识别文字");
+        assert_eq!(apply_glossary("识别文字", &[" ".to_string()], "zh-CN"), "识别文字");
+
+        let glossary = vec!["OrcApp".to_string(), "Tauri".to_string()];
+        assert_eq!(
+            apply_glossary("Extract the text\n", &glossary, "en-US"),
+            "Extract the text\n\nSpell these terms exactly as given: OrcApp, Tauri"
+        );
+    }
+
+    #[test]
+    fn test_sink_file() {
+        let tags = vec!["财务".to_string(), "2024".to_string()];
+        assert_eq!(sink_file("# 发票", None, &tags, "t"), ("md", "# 发票".to_string()));
+
+        let (extension, data) = sink_file("合计: 42", Some("json"), &tags, "2024-05-01T10:00:00+08:00");
+        let value: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(extension, "json");
+        assert_eq!(value["tags"], json!(["财务", "2024"]));
+        assert_eq!(value["content"], "合计: 42");
+
+        let (extension, data) = sink_file("a, \"b\"", Some("csv"), &tags, "t");
+        assert_eq!(extension, "csv");
+        assert_eq!(data, "createdAt,tags,content\nt,财务;2024,\"a, \"\"b\"\"\"\n");
+    }
+}
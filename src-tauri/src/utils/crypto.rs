@@ -2,26 +2,128 @@ use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Nonce,
 };
+use argon2::Argon2;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use once_cell::sync::Lazy;
+use once_cell::sync::OnceCell;
 use rand::Rng;
+use std::path::Path;
 
-// A fixed key for encryption (in production, this should be stored securely)
-// This matches the behavior of the original TypeScript crypto module
-static ENCRYPTION_KEY: Lazy<[u8; 32]> = Lazy::new(|| {
-    // Use a fixed key derived from a passphrase for consistency
-    // In real applications, use proper key derivation
-    let passphrase = b"image-recognition-app-secret-key";
+/// Key of the values encrypted on this install, see `init_key`
+static DATA_KEY: OnceCell<[u8; 32]> = OnceCell::new();
+
+/// Key of versions that derived it from a passphrase compiled into the binary.
+/// Only used to re-encrypt values written by them, see `reencrypt_legacy`.
+const LEGACY_PASSPHRASE: &[u8] = b"image-recognition-app-secret-key";
+
+const KEYRING_SERVICE: &str = "orcapp";
+const KEYRING_USER: &str = "data-key";
+/// Key file used where no OS keychain is available, e.g. Linux without a Secret Service
+const KEY_FILE: &str = "data.key";
+
+/// Load the install's random data key, creating it on first run. Must run before
+/// the database is opened. The key lives in the OS keychain, or in a file only
+/// the user can read where there is no keychain.
+pub fn init_key(app_data_dir: &Path) -> Result<(), String> {
+    let keychain = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER);
+    let key = load_key(&app_data_dir.join(KEY_FILE), keychain)?;
+    DATA_KEY.set(key).map_err(|_| "Data key already initialized".to_string())
+}
+
+/// Where the data key can be stored, a keychain entry outside tests
+trait KeyStore {
+    fn get(&self) -> keyring::Result<String>;
+    fn set(&self, encoded: &str) -> keyring::Result<()>;
+}
+
+impl KeyStore for keyring::Entry {
+    fn get(&self) -> keyring::Result<String> {
+        self.get_password()
+    }
+
+    fn set(&self, encoded: &str) -> keyring::Result<()> {
+        self.set_password(encoded)
+    }
+}
+
+fn load_key(key_file: &Path, keychain: keyring::Result<impl KeyStore>) -> Result<[u8; 32], String> {
+    // An install that started without a keychain keeps its key file even once
+    // one shows up, or everything encrypted so far would become unreadable
+    if key_file.exists() {
+        let encoded = std::fs::read_to_string(key_file).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+        return parse_key(encoded.trim());
+    }
+    let keychain = match keychain {
+        Ok(keychain) => keychain,
+        Err(e) => return keychain_unavailable(key_file, e),
+    };
+    match keychain.get() {
+        Ok(encoded) => parse_key(&encoded),
+        Err(keyring::Error::NoEntry) => {
+            let key = random_key();
+            match keychain.set(&BASE64.encode(key)) {
+                Ok(()) => Ok(key),
+                Err(e) => keychain_unavailable(key_file, e),
+            }
+        }
+        Err(e) => keychain_unavailable(key_file, e),
+    }
+}
+
+/// Fall back to a key file only where there is no keychain at all. A keychain
+/// that is locked or whose prompt was denied may still hold the key, so a new
+/// one must not replace it.
+fn keychain_unavailable(key_file: &Path, e: keyring::Error) -> Result<[u8; 32], String> {
+    let unsupported = matches!(e, keyring::Error::PlatformFailure(_))
+        && !cfg!(any(target_os = "macos", target_os = "windows"));
+    if !unsupported {
+        return Err(format!("无法访问系统钥匙串: {}", e));
+    }
+    eprintln!("[Crypto] OS keychain unavailable ({}), keeping the data key in the app data folder", e);
+    let key = random_key();
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(key_file).map_err(|e| format!("创建密钥文件失败: {}", e))?;
+    std::io::Write::write_all(&mut file, BASE64.encode(key).as_bytes()).map_err(|e| format!("写入密钥文件失败: {}", e))?;
+    Ok(key)
+}
+
+fn parse_key(encoded: &str) -> Result<[u8; 32], String> {
+    BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| "Invalid data key".to_string())
+}
+
+fn random_key() -> [u8; 32] {
     let mut key = [0u8; 32];
-    for (i, byte) in passphrase.iter().cycle().take(32).enumerate() {
+    rand::thread_rng().fill(&mut key);
+    key
+}
+
+/// The install's key. Guest sessions and tests never call `init_key` and get a
+/// key that lives as long as the process, like their in-memory database.
+fn data_key() -> &'static [u8; 32] {
+    DATA_KEY.get_or_init(random_key)
+}
+
+fn legacy_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    for (i, byte) in LEGACY_PASSPHRASE.iter().cycle().take(32).enumerate() {
         key[i] = *byte;
     }
     key
-});
+}
 
 /// Encrypt a string value
 pub fn encrypt(plaintext: &str) -> String {
-    let cipher = Aes256Gcm::new_from_slice(&*ENCRYPTION_KEY).expect("Invalid key length");
+    encrypt_with(data_key(), plaintext)
+}
+
+fn encrypt_with(key: &[u8; 32], plaintext: &str) -> String {
+    let cipher = Aes256Gcm::new_from_slice(key).expect("Invalid key length");
     
     // Generate random nonce
     let mut nonce_bytes = [0u8; 12];
@@ -42,6 +144,10 @@ pub fn encrypt(plaintext: &str) -> String {
 
 /// Decrypt an encrypted string
 pub fn decrypt(encrypted: &str) -> Result<String, String> {
+    decrypt_with(data_key(), encrypted)
+}
+
+fn decrypt_with(key: &[u8; 32], encrypted: &str) -> Result<String, String> {
     let combined = BASE64.decode(encrypted).map_err(|e| e.to_string())?;
     
     if combined.len() < 12 {
@@ -51,7 +157,7 @@ pub fn decrypt(encrypted: &str) -> Result<String, String> {
     let (nonce_bytes, ciphertext) = combined.split_at(12);
     let nonce = Nonce::from_slice(nonce_bytes);
     
-    let cipher = Aes256Gcm::new_from_slice(&*ENCRYPTION_KEY).expect("Invalid key length");
+    let cipher = Aes256Gcm::new_from_slice(key).expect("Invalid key length");
     
     let plaintext = cipher
         .decrypt(nonce, ciphertext)
@@ -60,6 +166,88 @@ pub fn decrypt(encrypted: &str) -> Result<String, String> {
     String::from_utf8(plaintext).map_err(|e| e.to_string())
 }
 
+/// Re-encrypt a value written with the legacy built-in key, tagged or not,
+/// under the install's key. None when the value wasn't encrypted with that key.
+pub fn reencrypt_legacy(value: &str) -> Option<String> {
+    match value.strip_prefix(ENCRYPTED_TAG) {
+        Some(encrypted) => decrypt_with(&legacy_key(), encrypted).ok().map(|plain| encrypt_tagged(&plain)),
+        None => decrypt_with(&legacy_key(), value).ok().map(|plain| encrypt(&plain)),
+    }
+}
+
+/// Marks values written by `encrypt_tagged`, so plain and encrypted values can share a column
+const ENCRYPTED_TAG: &str = "enc:v1:";
+
+/// Encrypt a value and tag it as encrypted
+pub fn encrypt_tagged(plaintext: &str) -> String {
+    format!("{}{}", ENCRYPTED_TAG, encrypt(plaintext))
+}
+
+pub fn is_tagged(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_TAG)
+}
+
+/// Decrypt a value written by `encrypt_tagged`; untagged values are returned as they are
+pub fn decrypt_tagged(value: &str) -> Result<String, String> {
+    match value.strip_prefix(ENCRYPTED_TAG) {
+        Some(encrypted) => decrypt(encrypted),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// LIKE pattern matching tagged values
+pub fn tagged_like_pattern() -> String {
+    format!("{}%", ENCRYPTED_TAG)
+}
+
+/// Header of data sealed with `seal_with_passphrase`
+const SEALED_MAGIC: &[u8] = b"ORCSEAL1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Encrypt data with a key derived from a passphrase, so any machine that
+/// knows the passphrase can open it. Layout: magic, salt, nonce, ciphertext.
+pub fn seal_with_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill(&mut salt);
+    rand::thread_rng().fill(&mut nonce_bytes);
+
+    let cipher = Aes256Gcm::new_from_slice(&passphrase_key(passphrase, &salt)?).map_err(|e| e.to_string())?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), data)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut sealed = SEALED_MAGIC.to_vec();
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce_bytes);
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt data written by `seal_with_passphrase`; fails on a wrong passphrase
+pub fn open_with_passphrase(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    let rest = sealed
+        .strip_prefix(SEALED_MAGIC)
+        .filter(|rest| rest.len() >= SALT_LEN + NONCE_LEN)
+        .ok_or("Invalid encrypted data")?;
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce_bytes, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = Aes256Gcm::new_from_slice(&passphrase_key(passphrase, salt)?).map_err(|e| e.to_string())?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| "Decryption failed".to_string())
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
 /// Mask an API key for display (show first 4 and last 4 characters)
 pub fn mask_api_key(api_key: &str) -> String {
     if api_key.len() <= 8 {
@@ -85,6 +273,91 @@ mod tests {
         assert_eq!(original, decrypted);
     }
 
+    #[test]
+    fn test_reencrypt_legacy() {
+        let legacy = encrypt_with(&legacy_key(), "sk-legacy");
+        let reencrypted = reencrypt_legacy(&legacy).unwrap();
+        assert_eq!(decrypt(&reencrypted).unwrap(), "sk-legacy");
+        assert!(decrypt(&legacy).is_err());
+
+        let tagged = format!("{}{}", ENCRYPTED_TAG, encrypt_with(&legacy_key(), "结果"));
+        assert_eq!(decrypt_tagged(&reencrypt_legacy(&tagged).unwrap()).unwrap(), "结果");
+        assert_eq!(reencrypt_legacy(&encrypt("current")), None);
+        assert_eq!(reencrypt_legacy("plain text"), None);
+    }
+
+    #[test]
+    fn test_tagged_values() {
+        let encrypted = encrypt_tagged("合同编号 42");
+        assert!(is_tagged(&encrypted));
+        assert_eq!(decrypt_tagged(&encrypted).unwrap(), "合同编号 42");
+        assert_eq!(decrypt_tagged("plain text").unwrap(), "plain text");
+    }
+
+    #[test]
+    fn test_seal_with_passphrase() {
+        let sealed = seal_with_passphrase(b"snapshot", "correct horse").unwrap();
+        assert!(sealed.starts_with(SEALED_MAGIC));
+        assert_eq!(open_with_passphrase(&sealed, "correct horse").unwrap(), b"snapshot");
+        assert!(open_with_passphrase(&sealed, "wrong").is_err());
+        assert!(open_with_passphrase(b"ORCSEAL1", "correct horse").is_err());
+    }
+
+    /// In-memory keychain; `locked` makes every call fail like a denied prompt
+    #[derive(Default)]
+    struct FakeKeychain {
+        stored: std::cell::RefCell<Option<String>>,
+        locked: bool,
+    }
+
+    impl KeyStore for &FakeKeychain {
+        fn get(&self) -> keyring::Result<String> {
+            if self.locked {
+                return Err(keyring::Error::NoStorageAccess("locked".into()));
+            }
+            self.stored.borrow().clone().ok_or(keyring::Error::NoEntry)
+        }
+
+        fn set(&self, encoded: &str) -> keyring::Result<()> {
+            if self.locked {
+                return Err(keyring::Error::NoStorageAccess("locked".into()));
+            }
+            *self.stored.borrow_mut() = Some(encoded.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_key_file_kept_once_keychain_available() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join(KEY_FILE);
+        let file_key = random_key();
+        std::fs::write(&key_file, BASE64.encode(file_key)).unwrap();
+
+        let keychain = FakeKeychain::default();
+        assert_eq!(load_key(&key_file, Ok(&keychain)).unwrap(), file_key);
+        assert!(keychain.stored.borrow().is_none());
+    }
+
+    #[test]
+    fn test_keychain_key_created_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join(KEY_FILE);
+        let keychain = FakeKeychain::default();
+        let key = load_key(&key_file, Ok(&keychain)).unwrap();
+        assert_eq!(load_key(&key_file, Ok(&keychain)).unwrap(), key);
+        assert!(!key_file.exists());
+    }
+
+    #[test]
+    fn test_locked_keychain_never_replaces_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join(KEY_FILE);
+        let keychain = FakeKeychain { locked: true, ..Default::default() };
+        assert!(load_key(&key_file, Ok(&keychain)).is_err());
+        assert!(!key_file.exists());
+    }
+
     #[test]
     fn test_mask_api_key() {
         assert_eq!(mask_api_key("sk-1234567890abcdef"), "sk-1********cdef");
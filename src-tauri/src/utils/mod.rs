@@ -1 +1,3 @@
 pub mod crypto;
+pub mod zip;
+pub mod time;
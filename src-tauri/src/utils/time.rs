@@ -0,0 +1,73 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+
+/// Current time as stored in history: UTC ISO-8601 with second precision,
+/// e.g. `2024-03-31T01:30:00Z`. The fixed width keeps string order chronological.
+pub fn now_utc_iso() -> String {
+    to_utc_iso(Utc::now())
+}
+
+pub fn to_utc_iso(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Unix epoch milliseconds to the stored format, truncated to the second
+pub fn millis_to_utc_iso(millis: i64) -> Option<String> {
+    DateTime::from_timestamp_millis(millis).map(to_utc_iso)
+}
+
+/// Time in the stored format. Values already in it are kept; local
+/// `YYYY-MM-DD HH:MM:SS` strings written by older versions are converted.
+pub fn normalize_utc_iso(value: &str) -> Option<String> {
+    if DateTime::parse_from_rfc3339(value).is_ok() {
+        return Some(value.to_string());
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()?;
+    Some(to_utc_iso(Local.from_local_datetime(&local).earliest()?.with_timezone(&Utc)))
+}
+
+/// Start of a local calendar day (`YYYY-MM-DD`) in the stored format
+pub fn local_day_start_utc_iso(date: &str) -> Option<String> {
+    local_midnight_utc(NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?)
+}
+
+/// Start of the local day after `date`, the exclusive end of that day
+pub fn local_day_end_utc_iso(date: &str) -> Option<String> {
+    local_midnight_utc(NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?.succ_opt()?)
+}
+
+fn local_midnight_utc(date: NaiveDate) -> Option<String> {
+    // Midnight may be skipped by a DST change, take the first valid instant
+    let local = Local
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&date.and_hms_opt(1, 0, 0)?).earliest())?;
+    Some(to_utc_iso(local.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_millis_to_utc_iso() {
+        assert_eq!(millis_to_utc_iso(0).as_deref(), Some("1970-01-01T00:00:00Z"));
+        assert_eq!(millis_to_utc_iso(1_711_848_600_999).as_deref(), Some("2024-03-31T01:30:00Z"));
+    }
+
+    #[test]
+    fn test_normalize_utc_iso() {
+        assert_eq!(normalize_utc_iso("2024-03-31T01:30:00Z").as_deref(), Some("2024-03-31T01:30:00Z"));
+        let converted = normalize_utc_iso("2024-03-31 12:00:00").unwrap();
+        assert!(converted.starts_with("2024-03-31T") && converted.ends_with('Z'));
+        assert_eq!(normalize_utc_iso("yesterday"), None);
+    }
+
+    #[test]
+    fn test_local_day_bounds() {
+        let start = local_day_start_utc_iso("2024-03-31").unwrap();
+        let end = local_day_end_utc_iso("2024-03-31").unwrap();
+        assert_eq!(start.len(), "2024-03-31T00:00:00Z".len());
+        assert!(start < end);
+        assert!(local_day_start_utc_iso("31.03.2024").is_none());
+    }
+}
@@ -0,0 +1,137 @@
+/// Minimal in-memory writer for zip archives with uncompressed (stored)
+/// entries and 32-bit sizes, enough for small Office Open XML documents.
+/// History bundles, which can grow past 4 GB, use the `zip` crate.
+pub struct ZipWriter {
+    buffer: Vec<u8>,
+    entries: Vec<CentralEntry>,
+}
+
+struct CentralEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+// 1980-01-01 00:00, the earliest DOS timestamp
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+/// General purpose flag: file names are UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add_file(&mut self, name: &str, data: &[u8]) {
+        let crc = crc32fast::hash(data);
+        let entry = CentralEntry {
+            name: name.to_string(),
+            crc,
+            size: data.len() as u32,
+            offset: self.buffer.len() as u32,
+        };
+
+        let buf = &mut self.buffer;
+        put_u32(buf, 0x0403_4b50);
+        put_u16(buf, 20); // version needed
+        put_u16(buf, FLAG_UTF8);
+        put_u16(buf, 0); // stored
+        put_u16(buf, DOS_TIME);
+        put_u16(buf, DOS_DATE);
+        put_u32(buf, crc);
+        put_u32(buf, entry.size);
+        put_u32(buf, entry.size);
+        put_u16(buf, name.len() as u16);
+        put_u16(buf, 0); // extra field length
+        buf.extend_from_slice(name.as_bytes());
+        buf.extend_from_slice(data);
+
+        self.entries.push(entry);
+    }
+
+    /// Append the central directory and return the archive bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.buffer.len() as u32;
+        let buf = &mut self.buffer;
+
+        for entry in &self.entries {
+            put_u32(buf, 0x0201_4b50);
+            put_u16(buf, 20); // version made by
+            put_u16(buf, 20); // version needed
+            put_u16(buf, FLAG_UTF8);
+            put_u16(buf, 0);
+            put_u16(buf, DOS_TIME);
+            put_u16(buf, DOS_DATE);
+            put_u32(buf, entry.crc);
+            put_u32(buf, entry.size);
+            put_u32(buf, entry.size);
+            put_u16(buf, entry.name.len() as u16);
+            put_u16(buf, 0); // extra field length
+            put_u16(buf, 0); // comment length
+            put_u16(buf, 0); // disk number
+            put_u16(buf, 0); // internal attributes
+            put_u32(buf, 0); // external attributes
+            put_u32(buf, entry.offset);
+            buf.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let directory_size = buf.len() as u32 - directory_offset;
+        put_u32(buf, 0x0605_4b50);
+        put_u16(buf, 0);
+        put_u16(buf, 0);
+        put_u16(buf, self.entries.len() as u16);
+        put_u16(buf, self.entries.len() as u16);
+        put_u32(buf, directory_size);
+        put_u32(buf, directory_offset);
+        put_u16(buf, 0); // comment length
+
+        self.buffer
+    }
+}
+
+impl Default for ZipWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_read_back_written_entries() {
+        let mut zip = ZipWriter::new();
+        zip.add_file("manifest.json", b"{}");
+        zip.add_file("images/图片.png", &[0, 1, 2, 3]);
+        let mut archive = ::zip::ZipArchive::new(Cursor::new(zip.finish())).unwrap();
+        let mut entries = Vec::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).unwrap();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            entries.push((entry.name().to_string(), data));
+        }
+        assert_eq!(
+            entries,
+            vec![
+                ("manifest.json".to_string(), b"{}".to_vec()),
+                ("images/图片.png".to_string(), vec![0, 1, 2, 3]),
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-import { useEffect } from 'react'
+import { useEffect, useState } from 'react'
 import { HashRouter, Routes, Route, Navigate } from 'react-router-dom'
 import { ConfigProvider } from 'antd'
 import zhCN from 'antd/locale/zh_CN'
@@ -7,15 +7,55 @@ import RecognitionPage from './pages/Recognition'
 import ConfigPage from './pages/Config'
 import HistoryPage from './pages/History'
 import SettingsPage from './pages/Settings'
+import ResultWindowPage from './pages/ResultWindow'
+import MiniCapturePage from './pages/MiniCapture'
+import LockScreen from './components/LockScreen'
 import { useSettingsStore } from './store/settingsStore'
+import { api } from './api'
+
+// 检查闲置锁定的间隔
+const LOCK_POLL_MS = 30 * 1000
 
 function App() {
     const { loadSettings, initialized } = useSettingsStore()
+    const [locked, setLocked] = useState<boolean | null>(null)
+
+    // 锁定时所有命令都会被拒绝，先确认锁状态
+    useEffect(() => {
+        const checkLock = () => api.lock.getStatus()
+            .then(status => setLocked(status.locked))
+            .catch(() => setLocked(false))
+        checkLock()
+        const timer = setInterval(checkLock, LOCK_POLL_MS)
+        return () => clearInterval(timer)
+    }, [])
+
+    useEffect(() => {
+        if (locked === false && !initialized) {
+            loadSettings()
+        }
+    }, [locked, initialized, loadSettings])
 
+    // 其他窗口修改设置后同步
     useEffect(() => {
-        loadSettings()
+        const unlisten = api.settings.onChanged(() => loadSettings())
+        return () => {
+            unlisten.then(fn => fn())
+        }
     }, [loadSettings])
 
+    if (locked === null) {
+        return null
+    }
+
+    if (locked) {
+        return (
+            <ConfigProvider locale={zhCN}>
+                <LockScreen onUnlock={() => setLocked(false)} />
+            </ConfigProvider>
+        )
+    }
+
     if (!initialized) {
         return null
     }
@@ -31,15 +71,25 @@ function App() {
             }}
         >
             <HashRouter>
-                <Layout>
-                    <Routes>
-                        <Route path="/" element={<Navigate to="/recognition" replace />} />
-                        <Route path="/recognition" element={<RecognitionPage />} />
-                        <Route path="/config" element={<ConfigPage />} />
-                        <Route path="/history" element={<HistoryPage />} />
-                        <Route path="/settings" element={<SettingsPage />} />
-                    </Routes>
-                </Layout>
+                <Routes>
+                    {/* 独立窗口，不带侧边栏 */}
+                    <Route path="/result/:id" element={<ResultWindowPage />} />
+                    <Route path="/mini" element={<MiniCapturePage />} />
+                    <Route
+                        path="*"
+                        element={
+                            <Layout>
+                                <Routes>
+                                    <Route path="/" element={<Navigate to="/recognition" replace />} />
+                                    <Route path="/recognition" element={<RecognitionPage />} />
+                                    <Route path="/config" element={<ConfigPage />} />
+                                    <Route path="/history" element={<HistoryPage />} />
+                                    <Route path="/settings" element={<SettingsPage />} />
+                                </Routes>
+                            </Layout>
+                        }
+                    />
+                </Routes>
             </HashRouter>
         </ConfigProvider>
     )
@@ -1,31 +1,154 @@
 // API layer for Tauri
-import { invoke } from '@tauri-apps/api/core';
+import { invoke as tauriInvoke, type InvokeArgs } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import type {
     ModelConfigInput,
     ModelConfigListItem,
     ModelConfig,
+    ConfigQueryParams,
+    ConfigHealth,
+    ConfigValidation,
+    BudgetStatus,
+    PaginatedResult,
     HistoryQueryParams,
     HistoryPaginatedResult,
     HistoryRecord,
+    IntegrityReport,
+    ImportReport,
+    HistoryImage,
+    DragFile,
+    ReviewBatch,
+    ReviewStatus,
+    RerunProgress,
+    RerunReport,
+    HistoryStatsRow,
+    StatsGranularity,
+    SimilarHistory,
+    Experiment,
+    ExperimentInput,
+    ExperimentReport,
+    ExperimentProgress,
+    AbTest,
+    AbTestRequest,
     PromptTemplate,
+    TemplateMerge,
+    TemplateExample,
+    TemplateQueryParams,
+    TemplateStats,
+    TemplateStatsParams,
+    PromptHistoryEntry,
+    PromptLintIssue,
     AppSettings,
+    SettingSchema,
+    SettingsChanged,
+    PreprocessMode,
+    ImageDetail,
+    DecodedCode,
+    CompareImagesRequest,
+    UploadProgress,
+    RecognitionStageEvent,
+    NotionExport,
+    ExtractionKind,
+    ExtractionRecord,
+    ExtractionQueryParams,
+    BusinessCard,
+    UpdateInfo,
+    UpdateProgress,
+    LockStatus,
+    SyncReport,
+    SyncCredentialsState,
+    VoiceInfo,
+    InferenceStatus,
+    InitializationReport,
+    RestoreReport,
+    Workspace,
+    WorkspaceInput,
+    GlossaryEntry,
+    PostprocessRule,
+    PostprocessRuleInput,
+    RuleTestResult,
+    Profile,
+    Preset,
+    PresetInput,
+    Recipe,
+    RecipeRunReport,
     RecognitionResult,
-    ProviderType
+    SelectedImage,
+    SelectedSavePath,
+    AnimationFrame,
+    StitchedImage,
+    BoundingBox,
+    ExtractedTable,
+    FormulaCheck,
+    ProviderType,
+    TestConnectionResult,
+    AppErrorKind,
+    AppErrorPayload,
+    ErrorCode
 } from '../shared/types';
 
+// 命令失败时抛出的错误，可按 kind 判断类型，message 为可直接显示的文案
+export class AppError extends Error {
+    kind: AppErrorKind;
+    code?: ErrorCode;
+    status?: number;
+
+    constructor(payload: AppErrorPayload) {
+        super(payload.message);
+        this.name = 'AppError';
+        this.kind = payload.kind;
+        this.code = payload.code ?? undefined;
+        this.status = payload.status ?? undefined;
+    }
+
+    // String(error) 只显示文案
+    toString(): string {
+        return this.message;
+    }
+}
+
+const isAppErrorPayload = (error: unknown): error is AppErrorPayload =>
+    typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+
+// 将后端返回的错误包装为 AppError
+async function invoke<T>(cmd: string, args?: InvokeArgs): Promise<T> {
+    try {
+        return await tauriInvoke<T>(cmd, args);
+    } catch (error) {
+        throw isAppErrorPayload(error) ? new AppError(error) : error;
+    }
+}
+
 // Internal RecognitionRequest that matches Rust backend
 interface TauriRecognitionRequest {
     configId: number;
     imageData: string;
     imageMimeType: string;
     prompt: string;
+    templateId?: number;
     options?: {
         temperature?: number;
         topP?: number;
         maxTokens?: number;
         stream?: boolean;
         customParams?: Record<string, string | number | boolean>;
+        autoRotate?: boolean;
+        autoCrop?: boolean;
+        segmentLanguages?: boolean;
+        layout?: boolean;
+        validateMath?: boolean;
+        mathml?: boolean;
+        frameIndex?: number;
+        preprocess?: PreprocessMode;
+        extract?: ExtractionKind;
+        jsonMode?: boolean;
+        decodeCodes?: boolean;
+        imageDetail?: ImageDetail;
+        outputLanguage?: string;
+        estimateConfidence?: boolean;
+        batchId?: string;
+        sourcePath?: string;
     };
 }
 
@@ -35,32 +158,87 @@ export const api = {
     config: {
         getAll: (): Promise<ModelConfigListItem[]> =>
             invoke('get_all_configs'),
+        query: (params?: ConfigQueryParams): Promise<PaginatedResult<ModelConfigListItem>> =>
+            invoke('query_configs', { params }),
         getActive: (): Promise<ModelConfigListItem[]> =>
             invoke('get_active_configs'),
         getById: (id: number): Promise<ModelConfig | null> =>
             invoke('get_config_by_id', { id }),
         getDefault: (): Promise<ModelConfig | null> =>
             invoke('get_default_config'),
-        create: (input: ModelConfigInput): Promise<ModelConfigListItem> =>
-            invoke('create_config', { input }),
-        update: (id: number, input: Partial<ModelConfigInput>): Promise<ModelConfigListItem | null> =>
-            invoke('update_config', { id, input }),
+        // validate 为 true 时先测试密钥，无效则拒绝保存
+        create: (input: ModelConfigInput, validate?: boolean): Promise<ModelConfigListItem> =>
+            invoke('create_config', { input, validate }),
+        update: (id: number, input: Partial<ModelConfigInput>, validate?: boolean): Promise<ModelConfigListItem | null> =>
+            invoke('update_config', { id, input, validate }),
         delete: (id: number): Promise<boolean> =>
             invoke('delete_config', { id }),
         setDefault: (id: number): Promise<boolean> =>
             invoke('set_default_config', { id }),
-        testConnection: (id: number): Promise<{ success: boolean; message: string }> =>
+        testConnection: (id: number): Promise<TestConnectionResult> =>
             invoke('test_connection', { id }),
-        testConnectionWithData: (data: { provider: ProviderType; apiUrl: string; apiKey: string; modelName: string }): Promise<{ success: boolean; message: string }> =>
-            invoke('test_connection_with_data', { data })
+        testConnectionWithData: (data: {
+            provider: ProviderType
+            apiUrl: string
+            apiKey: string
+            modelName: string
+            reasoningModel?: boolean
+            organization?: string
+            project?: string
+            skipTlsVerify?: boolean
+        }): Promise<TestConnectionResult> =>
+            invoke('test_connection_with_data', { data }),
+        revalidateAll: (): Promise<ConfigValidation[]> =>
+            invoke('revalidate_all_configs'),
+        getHealth: (): Promise<ConfigHealth[]> =>
+            invoke('get_provider_status'),
+        refreshHealth: (): Promise<ConfigHealth[]> =>
+            invoke('refresh_provider_status'),
+        // 模型列表缓存一天，refreshModels 强制重新获取
+        listModels: (configId: number): Promise<string[]> =>
+            invoke('list_models', { configId }),
+        refreshModels: (configId: number): Promise<string[]> =>
+            invoke('refresh_models', { configId }),
+        getBudgetStatus: (): Promise<BudgetStatus[]> =>
+            invoke('get_budget_status'),
+        onBudgetAlert: async (callback: (status: BudgetStatus) => void) => {
+            return listen<BudgetStatus>('budget-alert', (event) => callback(event.payload));
+        }
     },
 
     // ===== 历史记录 =====
     history: {
-        getRecords: (params?: HistoryQueryParams): Promise<HistoryPaginatedResult> =>
+        getRecords: (params?: Omit<HistoryQueryParams, 'fields'>): Promise<HistoryPaginatedResult> =>
             invoke('get_history_records', { params }),
+        getRecordFields: <K extends keyof HistoryRecord>(
+            fields: K[],
+            params?: Omit<HistoryQueryParams, 'fields'>
+        ): Promise<HistoryPaginatedResult<Pick<HistoryRecord, K | 'id'>>> =>
+            invoke('get_history_records', { params: { ...params, fields } }),
         getById: (id: number): Promise<HistoryRecord | null> =>
             invoke('get_history_by_id', { id }),
+        getImage: (id: number): Promise<HistoryImage | null> =>
+            invoke('get_history_image', { id }),
+        // ===== 批次审核队列 =====
+        getReviewBatches: (): Promise<ReviewBatch[]> =>
+            invoke('get_review_batches'),
+        listPendingReviews: (batchId: string, page?: number, pageSize?: number): Promise<HistoryPaginatedResult> =>
+            invoke('list_pending_reviews', { batchId, page, pageSize }),
+        // editedResult 单独保存为 reviewedResult，不覆盖原始结果；未传时保留已有的修改
+        review: (id: number, status: ReviewStatus, editedResult?: string): Promise<boolean> =>
+            invoke('review_history', { id, status, editedResult }),
+        // 已通过的记录，result 为审核修改后的文本；未传 batchId 时导出所有批次
+        exportApproved: (batchId?: string): Promise<HistoryRecord[]> =>
+            invoke('export_approved_history', { batchId }),
+        // 用另一个模型重新识别选中记录的原图，新记录关联原记录并进入新批次的审核队列
+        rerunMany: (ids: number[], configId: number): Promise<RerunReport> =>
+            invoke('rerun_many', { ids, configId }),
+        onRerunProgress: async (callback: (progress: RerunProgress) => void) => {
+            return listen<RerunProgress>('rerun-progress', (event) => callback(event.payload));
+        },
+        // 清除低置信度记录的待复核标记
+        markReviewed: (id: number): Promise<boolean> =>
+            invoke('mark_history_reviewed', { id }),
         delete: (id: number): Promise<boolean> =>
             invoke('delete_history', { id }),
         deleteMultiple: (ids: number[]): Promise<number> =>
@@ -68,56 +246,439 @@ export const api = {
         clearAll: (): Promise<number> =>
             invoke('clear_all_history'),
         export: (params?: HistoryQueryParams): Promise<HistoryRecord[]> =>
-            invoke('export_history', { params })
+            invoke('export_history', { params }),
+        exportBundle: (params: HistoryQueryParams | undefined, defaultName: string): Promise<boolean> =>
+            invoke('export_history_bundle', { params, defaultName }),
+        // 导入 ZIP 包或 JSON 导出；configMap 将文件中的配置名称映射到本地配置 ID
+        import: (path: string, configMap?: Record<string, number>): Promise<ImportReport> =>
+            invoke('import_history', { path, configMap }),
+        getStats: (granularity: StatsGranularity): Promise<HistoryStatsRow[]> =>
+            invoke('get_history_stats', { granularity }),
+        findSimilar: (imageData: string, maxDistance?: number): Promise<SimilarHistory[]> =>
+            invoke('find_similar_history', { imageData, maxDistance }),
+        verifyIntegrity: (clearDangling?: boolean): Promise<IntegrityReport> =>
+            invoke('verify_history_integrity', { clearDangling }),
+        // 开启或关闭历史加密并转换已有记录，返回转换的记录数
+        setEncryption: (enabled: boolean): Promise<number> =>
+            invoke('set_history_encryption', { enabled }),
+        // 追加到设置中的 Markdown 笔记文件，返回笔记文件路径
+        appendToNotebook: (historyId: number): Promise<string> =>
+            invoke('append_to_notebook', { historyId }),
+        // 在文件管理器中定位识别的原图片文件
+        revealInFolder: (historyId: number): Promise<void> =>
+            invoke('reveal_in_folder', { historyId }),
+        // 把结果写入临时文件并返回路径，用于拖出到其他应用；csv 为结果中的表格
+        createDragFile: (historyId: number, format: 'md' | 'txt' | 'csv'): Promise<DragFile> =>
+            invoke('create_drag_file', { historyId, format })
     },
 
     // ===== 提示词模板 =====
     template: {
         getAll: (): Promise<PromptTemplate[]> =>
             invoke('get_all_templates'),
+        query: (params?: TemplateQueryParams): Promise<PaginatedResult<PromptTemplate>> =>
+            invoke('query_templates', { params }),
         getDefault: (): Promise<PromptTemplate | null> =>
             invoke('get_default_template'),
         getRecent: (limit?: number): Promise<PromptTemplate[]> =>
             invoke('get_recent_templates', { limit }),
-        create: (name: string, content: string, isDefault?: boolean): Promise<PromptTemplate> =>
-            invoke('create_template', { name, content, isDefault }),
-        update: (id: number, updates: { name?: string; content?: string; isDefault?: boolean }): Promise<PromptTemplate | null> =>
+        // profileId 未传时归属当前档案，0 表示共享；已有内容相同的模板时返回 duplicate_template 错误，allowDuplicate 为 true 时仍然创建
+        create: (
+            name: string,
+            content: string,
+            isDefault?: boolean,
+            profileId?: number,
+            allowDuplicate?: boolean
+        ): Promise<PromptTemplate> =>
+            invoke('create_template', { name, content, isDefault, profileId, allowDuplicate }),
+        update: (id: number, updates: { name?: string; content?: string; isDefault?: boolean; profileId?: number }): Promise<PromptTemplate | null> =>
             invoke('update_template', { id, updates }),
         delete: (id: number): Promise<boolean> =>
             invoke('delete_template', { id }),
+        // 合并内容相同（忽略大小写、空白和全角字符）的模板
+        dedupe: (): Promise<TemplateMerge[]> =>
+            invoke('dedupe_templates'),
+        // 少样本示例，仅支持多轮图片对话的服务商会发送
+        examples: (templateId: number): Promise<TemplateExample[]> =>
+            invoke('get_template_examples', { templateId }),
+        exampleImage: (id: number): Promise<HistoryImage | null> =>
+            invoke('get_template_example_image', { id }),
+        // 超过 MAX_TEMPLATE_EXAMPLES 时返回 too_many_examples 错误
+        addExample: (templateId: number, imageData: string, imageMimeType: string, output: string): Promise<TemplateExample> =>
+            invoke('add_template_example', { input: { templateId, imageData, imageMimeType, output } }),
+        updateExample: (id: number, output: string): Promise<boolean> =>
+            invoke('update_template_example', { id, output }),
+        deleteExample: (id: number): Promise<boolean> =>
+            invoke('delete_template_example', { id }),
         incrementUse: (id: number): Promise<void> =>
-            invoke('increment_template_use', { id })
+            invoke('increment_template_use', { id }),
+        getStats: (params?: TemplateStatsParams): Promise<PaginatedResult<TemplateStats>> =>
+            invoke('get_template_stats', { params }),
+        lint: (content: string, configId?: number, jsonMode?: boolean): Promise<PromptLintIssue[]> =>
+            invoke('lint_prompt', { content, configId, jsonMode })
+    },
+
+    // ===== 提示词历史 =====
+    promptHistory: {
+        getSuggestions: (prefix: string, limit?: number): Promise<PromptHistoryEntry[]> =>
+            invoke('get_prompt_suggestions', { prefix, limit }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_prompt_history', { id }),
+        clear: (): Promise<number> =>
+            invoke('clear_prompt_history')
     },
 
     // ===== 设置 =====
     settings: {
         getAll: (): Promise<AppSettings> =>
             invoke('get_all_settings'),
-        update: (updates: Partial<AppSettings>): Promise<AppSettings> =>
-            invoke('update_settings', { updates }),
-        reset: (): Promise<AppSettings> =>
-            invoke('reset_settings')
+        // 传入 workspaceId / profileId 时作为该工作区或档案的覆盖值保存，未覆盖的项沿用全局设置
+        update: (updates: Partial<AppSettings>, workspaceId?: number, profileId?: number): Promise<AppSettings> =>
+            invoke('update_settings', { updates, workspaceId, profileId }),
+        getSchema: (): Promise<SettingSchema[]> =>
+            invoke('get_settings_schema'),
+        reset: (workspaceId?: number, profileId?: number): Promise<AppSettings> =>
+            invoke('reset_settings', { workspaceId, profileId }),
+        getWorkspaceOverrides: (workspaceId: number): Promise<Record<string, string>> =>
+            invoke('get_workspace_settings', { workspaceId }),
+        getProfileOverrides: (profileId: number): Promise<Record<string, string>> =>
+            invoke('get_profile_settings', { profileId }),
+        initializeDefaults: (language?: AppSettings['language']): Promise<InitializationReport> =>
+            invoke('initialize_defaults', { language }),
+        // 访客模式（--guest 启动）下数据只保存在内存中，退出后丢弃
+        isGuestMode: (): Promise<boolean> =>
+            invoke('is_guest_mode'),
+        // 本地模型可用的计算设备，以及已加载模型实际使用的设备
+        getInferenceStatus: (): Promise<InferenceStatus> =>
+            invoke('get_inference_status'),
+        // 任意窗口保存或重置设置后触发，携带变更的键
+        onChanged: (callback: (change: SettingsChanged) => void) => {
+            return listen<SettingsChanged>('settings-changed', (event) => callback(event.payload));
+        }
+    },
+
+    // ===== 数据备份 =====
+    backup: {
+        backupDatabase: (path: string): Promise<void> =>
+            invoke('backup_database', { path }),
+        restoreDatabase: (path: string): Promise<RestoreReport> =>
+            invoke('restore_database', { path })
+    },
+
+    // ===== 工作区 =====
+    workspace: {
+        getAll: (): Promise<Workspace[]> =>
+            invoke('get_all_workspaces'),
+        getActive: (): Promise<Workspace | null> =>
+            invoke('get_active_workspace'),
+        create: (input: WorkspaceInput): Promise<Workspace> =>
+            invoke('create_workspace', { input }),
+        update: (id: number, input: WorkspaceInput): Promise<Workspace | null> =>
+            invoke('update_workspace', { id, input }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_workspace', { id }),
+        setActive: (id: number | null): Promise<Workspace | null> =>
+            invoke('set_active_workspace', { id })
+    },
+
+    // ===== 词表 =====
+    glossary: {
+        getAll: (): Promise<GlossaryEntry[]> =>
+            invoke('get_glossary'),
+        // 术语已存在时更新其正确写法
+        save: (term: string, preferred: string): Promise<GlossaryEntry> =>
+            invoke('save_glossary_entry', { term, preferred }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_glossary_entry', { id })
+    },
+
+    // ===== 后处理规则 =====
+    postprocess: {
+        getRules: (): Promise<PostprocessRule[]> =>
+            invoke('get_postprocess_rules'),
+        // 正则无法编译时返回 invalid_regex 错误
+        create: (input: PostprocessRuleInput): Promise<PostprocessRule> =>
+            invoke('create_postprocess_rule', { input }),
+        update: (id: number, input: PostprocessRuleInput): Promise<PostprocessRule | null> =>
+            invoke('update_postprocess_rule', { id, input }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_postprocess_rule', { id }),
+        // 按 ids 的顺序执行规则
+        reorder: (ids: number[]): Promise<void> =>
+            invoke('reorder_postprocess_rules', { ids }),
+        test: (pattern: string, replacement: string, sample: string): Promise<RuleTestResult> =>
+            invoke('test_postprocess_rule', { pattern, replacement, sample })
+    },
+
+    // ===== 配置档案 =====
+    profile: {
+        getAll: (): Promise<Profile[]> =>
+            invoke('get_all_profiles'),
+        getActive: (): Promise<Profile | null> =>
+            invoke('get_active_profile'),
+        create: (name: string): Promise<Profile> =>
+            invoke('create_profile', { name }),
+        rename: (id: number, name: string): Promise<Profile | null> =>
+            invoke('rename_profile', { id, name }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_profile', { id }),
+        // 切换后其他档案的配置、模板和设置不可见，传 null 退出档案
+        setActive: (id: number | null): Promise<Profile | null> =>
+            invoke('set_active_profile', { id })
+    },
+
+    // ===== 识别预设 =====
+    preset: {
+        getAll: (): Promise<Preset[]> =>
+            invoke('get_all_presets'),
+        getDefault: (): Promise<Preset | null> =>
+            invoke('get_default_preset'),
+        create: (input: PresetInput): Promise<Preset> =>
+            invoke('create_preset', { input }),
+        update: (id: number, input: PresetInput): Promise<Preset | null> =>
+            invoke('update_preset', { id, input }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_preset', { id }),
+        setDefault: (id: number): Promise<boolean> =>
+            invoke('set_default_preset', { id })
+    },
+
+    // ===== 流程配方 =====
+    recipe: {
+        export: (workspaceId: number): Promise<Recipe> =>
+            invoke('export_recipe', { workspaceId }),
+        run: (path: string, inputs: string[]): Promise<RecipeRunReport> =>
+            invoke('run_recipe', { path, inputs })
     },
 
     // ===== 识别 =====
     recognition: {
         recognize: (data: TauriRecognitionRequest): Promise<RecognitionResult> =>
             invoke('recognize', { data }),
+        recognizeWithPreset: (
+            presetId: number | null,
+            imageData: string,
+            imageMimeType: string
+        ): Promise<RecognitionResult> =>
+            invoke('recognize_with_preset', { presetId, imageData, imageMimeType }),
         cancel: (): Promise<void> =>
             invoke('cancel_recognition'),
+        extractFrame: (imageData: string, frameIndex?: number): Promise<AnimationFrame | null> =>
+            invoke('extract_frame', { imageData, frameIndex }),
+        // 本地识别二维码和条形码，不调用模型
+        decodeCodes: (imageData: string): Promise<DecodedCode[]> =>
+            invoke('decode_codes', { imageData }),
+        // 按从上到下的顺序传入滚动截图，通过重叠区域拼接为一张长图再识别
+        stitchScreenshots: (images: string[]): Promise<StitchedImage> =>
+            invoke('stitch_screenshots', { images }),
+        // 两张图片和提示词作为一次请求发送，返回一份分析结果
+        compareImages: (data: CompareImagesRequest): Promise<RecognitionResult> =>
+            invoke('compare_images', { data }),
         onStreamChunk: async (callback: (content: string) => void) => {
-            const unlisten = await listen<string>('recognition-stream', (event) => {
+            // 流式内容只发送给发起识别的窗口
+            const unlisten = await getCurrentWebviewWindow().listen<string>('recognition-stream', (event) => {
                 console.log('[API] Stream event received:', event);
                 callback(event.payload);
             });
             return unlisten;
+        },
+        // 请求体的上传进度，在流式内容到达之前显示
+        onUploadProgress: async (callback: (progress: UploadProgress) => void) => {
+            return getCurrentWebviewWindow().listen<UploadProgress>('recognition-upload-progress', (event) => callback(event.payload))
+        },
+        // 排队、压缩、上传、等待首个 Token 等阶段
+        onStage: async (callback: (event: RecognitionStageEvent) => void) => {
+            return getCurrentWebviewWindow().listen<RecognitionStageEvent>('recognition-stage', (event) => callback(event.payload))
+        },
+        // 开启导出到 Notion 时，识别结果先返回，导出完成后通过该事件通知
+        onNotionExport: async (callback: (result: NotionExport) => void) => {
+            return getCurrentWebviewWindow().listen<NotionExport>('notion-export', (event) => callback(event.payload))
+        }
+    },
+
+    // ===== 表格导出 =====
+    table: {
+        extract: (content: string): Promise<ExtractedTable[]> =>
+            invoke('extract_tables', { content }),
+        // 多个表格时 XLSX 每表一个工作表，CSV 以空行分隔
+        export: (content: string, format: 'csv' | 'xlsx', defaultName: string): Promise<boolean> =>
+            invoke('export_tables', { content, format, defaultName })
+    },
+
+    // ===== 标注图 =====
+    // 在图片上绘制边界框 (坐标为 0-1 比例) 及同序号的标签，用于核对版面识别结果；
+    // 标签仅支持数字、英文字母和少量符号，适合块序号
+    annotation: {
+        render: (imageData: string, boxes: BoundingBox[], labels: string[]): Promise<string> =>
+            invoke('render_annotations', { imageData, boxes, labels }),
+        export: (imageData: string, boxes: BoundingBox[], labels: string[], defaultName: string): Promise<boolean> =>
+            invoke('export_annotated_image', { imageData, boxes, labels, defaultName })
+    },
+
+    // ===== 公式校验 =====
+    math: {
+        checkFormulas: (content: string, mathml?: boolean): Promise<FormulaCheck[]> =>
+            invoke('check_formulas', { content, mathml })
+    },
+
+    // ===== 模板实验 =====
+    experiment: {
+        getAll: (): Promise<Experiment[]> =>
+            invoke('get_all_experiments'),
+        create: (input: ExperimentInput): Promise<Experiment> =>
+            invoke('create_experiment', { input }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_experiment', { id }),
+        run: (id: number): Promise<ExperimentReport> =>
+            invoke('run_experiment', { id }),
+        // 暂停后不再发出新的请求，进行中的请求会继续完成；未在运行时返回 false
+        pause: (id: number): Promise<boolean> =>
+            invoke('pause_experiment', { id }),
+        // 继续运行已暂停的实验，失败的任务会重试
+        resume: (id: number): Promise<ExperimentReport> =>
+            invoke('resume_experiment', { id }),
+        rateRun: (runId: number, rating?: number): Promise<boolean> =>
+            invoke('rate_experiment_run', { runId, rating }),
+        getReport: (id: number): Promise<ExperimentReport> =>
+            invoke('get_experiment_report', { id }),
+        onProgress: async (callback: (progress: ExperimentProgress) => void) => {
+            return listen<ExperimentProgress>('experiment-progress', (event) => callback(event.payload));
+        }
+    },
+
+    // ===== 提示词 A/B 测试 =====
+    abTest: {
+        run: (request: AbTestRequest): Promise<AbTest> =>
+            invoke('ab_test', { request }),
+        getAll: (): Promise<AbTest[]> =>
+            invoke('get_all_ab_tests'),
+        get: (id: number): Promise<AbTest | null> =>
+            invoke('get_ab_test', { id }),
+        delete: (id: number): Promise<boolean> =>
+            invoke('delete_ab_test', { id })
+    },
+
+    // ===== 应用更新 =====
+    updater: {
+        check: (): Promise<UpdateInfo | null> =>
+            invoke('check_for_update'),
+        download: (): Promise<void> =>
+            invoke('download_update'),
+        install: (): Promise<void> =>
+            invoke('install_update'),
+        onProgress: async (callback: (progress: UpdateProgress) => void) => {
+            return listen<UpdateProgress>('update-progress', (event) => callback(event.payload));
         }
     },
 
+    // ===== 结构化提取 =====
+    extraction: {
+        query: (params: ExtractionQueryParams = {}): Promise<PaginatedResult<ExtractionRecord>> =>
+            invoke('query_extractions', { params }),
+        getByHistory: (historyId: number): Promise<ExtractionRecord | null> =>
+            invoke('get_extraction_by_history', { historyId }),
+        // 通过保存对话框导出 .vcf，取消时返回 false
+        saveVcard: (card: BusinessCard): Promise<boolean> =>
+            invoke('save_vcard', { card })
+    },
+
+    // ===== 语音朗读 =====
+    speech: {
+        // 打断正在朗读的内容，使用设置中的语音和语速
+        speak: (text: string): Promise<void> =>
+            invoke('speak_result', { text }),
+        stop: (): Promise<void> =>
+            invoke('stop_speaking'),
+        getVoices: (): Promise<VoiceInfo[]> =>
+            invoke('get_tts_voices')
+    },
+
+    // ===== 应用锁 =====
+    lock: {
+        getStatus: (): Promise<LockStatus> =>
+            invoke('get_lock_status'),
+        unlock: (passcode: string): Promise<void> =>
+            invoke('unlock_app', { passcode }),
+        lock: (): Promise<void> =>
+            invoke('lock_app'),
+        // passcode 为空时移除密码；已设置密码时需提供 current
+        setPasscode: (current: string | null, passcode: string | null): Promise<LockStatus> =>
+            invoke('set_passcode', { current, passcode })
+    },
+
+    // ===== 同步 =====
+    sync: {
+        now: (): Promise<SyncReport> =>
+            invoke('sync_now'),
+        // 省略的值保持不变，空字符串表示删除
+        setCredentials: (secret?: string, passphrase?: string): Promise<void> =>
+            invoke('set_sync_credentials', { secret, passphrase }),
+        getCredentialsState: (): Promise<SyncCredentialsState> =>
+            invoke('get_sync_credentials_state')
+    },
+
+    // ===== 分享 =====
+    share: {
+        // 上传到设置中的分享服务，返回链接并复制到剪贴板
+        result: (content: string, title?: string): Promise<string> =>
+            invoke('share_result', { content, title }),
+        // GitHub Token 或自定义地址的 Bearer Token，空字符串表示删除
+        setToken: (token: string): Promise<void> =>
+            invoke('set_share_token', { token }),
+        hasToken: (): Promise<boolean> =>
+            invoke('has_share_token')
+    },
+
+    // ===== 邮件 =====
+    mail: {
+        // 通过 SMTP 发送识别结果并附带原图，未指定收件人时使用设置中的默认收件人；返回实际收件人
+        sendResult: (historyId: number, to?: string): Promise<string[]> =>
+            invoke('send_result_email', { historyId, to }),
+        // 空字符串表示删除
+        setPassword: (password: string): Promise<void> =>
+            invoke('set_smtp_password', { password }),
+        hasPassword: (): Promise<boolean> =>
+            invoke('has_smtp_password')
+    },
+
+    // ===== Notion =====
+    notion: {
+        // 将历史记录的结果添加为 Notion 数据库的新页面，返回页面链接
+        exportHistory: (historyId: number): Promise<string> =>
+            invoke('export_history_to_notion', { historyId }),
+        // 空字符串表示删除
+        setToken: (token: string): Promise<void> =>
+            invoke('set_notion_token', { token }),
+        hasToken: (): Promise<boolean> =>
+            invoke('has_notion_token')
+    },
+
+    // ===== 窗口 =====
+    window: {
+        openResult: (historyId: number): Promise<void> =>
+            invoke('open_result_window', { historyId }),
+        toggleMini: (): Promise<boolean> =>
+            invoke('toggle_mini_window')
+    },
+
     // ===== 对话框 =====
     dialog: {
-        selectImage: (): Promise<{ base64: string; mimeType: string; fileName: string } | null> =>
+        selectImage: (): Promise<SelectedImage | null> =>
             invoke('select_image'),
+        // 读取从文件管理器拖入窗口的图片
+        readImageFile: (path: string): Promise<SelectedImage> =>
+            invoke('read_image_file', { path }),
+        // 原生拖放的文件路径；网页的 drop 事件拿不到文件在磁盘上的位置
+        onFileDrop: async (callback: (paths: string[]) => void) => {
+            return getCurrentWebviewWindow().onDragDropEvent((event) => {
+                if (event.payload.type === 'drop') {
+                    callback(event.payload.paths)
+                }
+            })
+        },
+        // 只选择保存位置，不写入内容；取消时返回 null
+        selectSavePath: (defaultName: string, filters: { name: string; extensions: string[] }[]): Promise<SelectedSavePath | null> =>
+            invoke('select_save_path', { defaultName, filters }),
         saveFile: (options: { content: string; defaultName: string; filters: { name: string; extensions: string[] }[] }): Promise<boolean> =>
             invoke('save_file', { options })
     },
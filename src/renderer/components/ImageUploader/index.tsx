@@ -5,6 +5,17 @@ import { useRecognitionStore } from '../../store'
 import { api } from '../../api'
 
 const SUPPORTED_TYPES = ['image/jpeg', 'image/png', 'image/webp', 'image/gif']
+const SUPPORTED_EXTENSIONS = /\.(jpe?g|png|webp|gif)$/i
+
+// 读取文件的 base64 内容（不含 data URL 前缀）
+function readAsBase64(file: File): Promise<string> {
+    return new Promise((resolve, reject) => {
+        const reader = new FileReader()
+        reader.onload = (e) => resolve((e.target?.result as string).split(',')[1])
+        reader.onerror = () => reject(reader.error)
+        reader.readAsDataURL(file)
+    })
+}
 
 export default function ImageUploader() {
     const {
@@ -39,6 +50,37 @@ export default function ImageUploader() {
         reader.readAsDataURL(file)
     }, [setImage])
 
+    // 多张截图按文件名排序后拼接为一张长图，识别结果只生成一条历史记录
+    const handleFilesSelect = useCallback(async (files: File[]) => {
+        if (files.length < 2) {
+            if (files[0]) handleFileSelect(files[0])
+            return
+        }
+        const images = files.filter(file => SUPPORTED_TYPES.includes(file.type))
+        if (images.length < files.length) {
+            message.warning('已跳过不支持的图片格式')
+        }
+        if (images.length < 2) {
+            if (images[0]) handleFileSelect(images[0])
+            return
+        }
+
+        images.sort((a, b) => a.name.localeCompare(b.name, undefined, { numeric: true }))
+        try {
+            const contents = await Promise.all(images.map(readAsBase64))
+            const stitched = await api.recognition.stitchScreenshots(contents)
+            setImage(stitched.base64, stitched.mimeType, 'stitched.png')
+            const unmatched = stitched.overlaps.filter(rows => rows === 0).length
+            if (unmatched > 0) {
+                message.warning(`已拼接 ${images.length} 张截图，其中 ${unmatched} 处未找到重叠区域`)
+            } else {
+                message.success(`已拼接 ${images.length} 张截图`)
+            }
+        } catch (error) {
+            message.error(`拼接截图失败: ${String(error)}`)
+        }
+    }, [handleFileSelect, setImage])
+
     // 点击上传
     const handleClick = () => {
         fileInputRef.current?.click()
@@ -46,10 +88,7 @@ export default function ImageUploader() {
 
     // 文件输入变化
     const handleInputChange = (e: React.ChangeEvent<HTMLInputElement>) => {
-        const file = e.target.files?.[0]
-        if (file) {
-            handleFileSelect(file)
-        }
+        handleFilesSelect(Array.from(e.target.files ?? []))
         // 重置 input 以允许选择相同文件
         e.target.value = ''
     }
@@ -59,17 +98,45 @@ export default function ImageUploader() {
         e.preventDefault()
         e.stopPropagation()
 
-        const file = e.dataTransfer.files?.[0]
-        if (file && file.type.startsWith('image/')) {
-            handleFileSelect(file)
-        }
-    }, [handleFileSelect])
+        const files = Array.from(e.dataTransfer.files ?? []).filter(file => file.type.startsWith('image/'))
+        handleFilesSelect(files)
+    }, [handleFilesSelect])
 
     const handleDragOver = (e: React.DragEvent) => {
         e.preventDefault()
         e.stopPropagation()
     }
 
+    // 从文件管理器拖入的文件，读取时记下路径以便在历史记录中打开所在文件夹
+    useEffect(() => {
+        const handlePathsDrop = async (paths: string[]) => {
+            const images = paths.filter(path => SUPPORTED_EXTENSIONS.test(path))
+            if (images.length === 0) {
+                if (paths.length > 0) message.error('不支持的图片格式，请使用 JPG, PNG, WebP 或 GIF')
+                return
+            }
+            try {
+                if (images.length === 1) {
+                    const image = await api.dialog.readImageFile(images[0])
+                    setImage(image.base64, image.mimeType, image.fileName, image.filePath)
+                    return
+                }
+                images.sort((a, b) => a.localeCompare(b, undefined, { numeric: true }))
+                const contents = await Promise.all(images.map(path => api.dialog.readImageFile(path)))
+                const stitched = await api.recognition.stitchScreenshots(contents.map(image => image.base64))
+                setImage(stitched.base64, stitched.mimeType, 'stitched.png')
+                message.success(`已拼接 ${images.length} 张截图`)
+            } catch (error) {
+                message.error(`读取图片失败: ${String(error)}`)
+            }
+        }
+
+        const unlisten = api.dialog.onFileDrop(handlePathsDrop)
+        return () => {
+            unlisten.then(fn => fn())
+        }
+    }, [setImage])
+
     // 粘贴处理
     useEffect(() => {
         const handlePaste = async (e: ClipboardEvent) => {
@@ -109,7 +176,7 @@ export default function ImageUploader() {
         try {
             const result = await api.dialog.selectImage()
             if (result) {
-                setImage(result.base64, result.mimeType, result.fileName)
+                setImage(result.base64, result.mimeType, result.fileName, result.filePath)
             }
         } catch (error) {
             console.error('Failed to select file:', error)
@@ -162,6 +229,7 @@ export default function ImageUploader() {
                 ref={fileInputRef}
                 type="file"
                 accept="image/jpeg,image/png,image/webp,image/gif"
+                multiple
                 style={{ display: 'none' }}
                 onChange={handleInputChange}
             />
@@ -176,7 +244,7 @@ export default function ImageUploader() {
                     点击或拖拽图片到此处上传
                 </div>
                 <div className="upload-hint">
-                    支持 JPG, PNG, WebP, GIF 格式，最大 10MB
+                    支持 JPG, PNG, WebP, GIF 格式，最大 10MB；多选滚动截图将自动拼接为长图
                 </div>
                 <div className="upload-hint" style={{ marginTop: 8 }}>
                     <Button type="link" onClick={(e) => { e.stopPropagation(); handleSelectFile(); }}>
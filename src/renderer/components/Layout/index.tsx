@@ -1,13 +1,15 @@
-import { ReactNode } from 'react'
+import { ReactNode, useEffect, useState } from 'react'
 import { useNavigate, useLocation } from 'react-router-dom'
-import { Menu } from 'antd'
+import { Menu, Tag, Tooltip, notification } from 'antd'
 import {
     PictureOutlined,
     SettingOutlined,
     HistoryOutlined,
-    ApiOutlined
+    ApiOutlined,
+    PushpinOutlined
 } from '@ant-design/icons'
 import type { MenuProps } from 'antd'
+import { api } from '../../api'
 
 interface LayoutProps {
     children: ReactNode
@@ -33,14 +35,43 @@ const menuItems: MenuProps['items'] = [
         key: '/settings',
         icon: <SettingOutlined />,
         label: '设置'
+    },
+    {
+        key: 'mini',
+        icon: <PushpinOutlined />,
+        label: '快速识别窗口'
     }
 ]
 
 export default function Layout({ children }: LayoutProps) {
     const navigate = useNavigate()
     const location = useLocation()
+    const [guest, setGuest] = useState(false)
+
+    useEffect(() => {
+        api.settings.isGuestMode().then(setGuest).catch(() => setGuest(false))
+    }, [])
+
+    // 配置本月花费达到预算的 80% / 100% 时提醒一次
+    useEffect(() => {
+        const unlisten = api.config.onBudgetAlert((status) => {
+            const percent = Math.round((status.spentUsd / status.budgetUsd) * 100)
+            notification.warning({
+                message: status.level === 'exceeded' ? '已超出每月预算' : '即将达到每月预算',
+                description: `${status.configName} 本月估算花费 $${status.spentUsd.toFixed(2)} / $${status.budgetUsd.toFixed(2)}（${percent}%）`
+            })
+        })
+        return () => {
+            unlisten.then(fn => fn())
+        }
+    }, [])
 
     const handleMenuClick: MenuProps['onClick'] = ({ key }) => {
+        // 置顶小窗口单独打开，不切换页面
+        if (key === 'mini') {
+            api.window.toggleMini()
+            return
+        }
         navigate(key)
     }
 
@@ -50,6 +81,11 @@ export default function Layout({ children }: LayoutProps) {
                 <div className="sidebar-header">
                     <PictureOutlined style={{ marginRight: 8 }} />
                     图片识别工具
+                    {guest && (
+                        <Tooltip title="数据仅保存在内存中，退出后不会保留任何配置和记录">
+                            <Tag color="orange" style={{ marginLeft: 8 }}>访客</Tag>
+                        </Tooltip>
+                    )}
                 </div>
                 <div className="sidebar-menu">
                     <Menu
@@ -0,0 +1,54 @@
+import { useState } from 'react'
+import { Card, Input, Button, Typography, Space, message } from 'antd'
+import { LockOutlined } from '@ant-design/icons'
+import { api } from '../../api'
+
+const { Title, Text } = Typography
+
+interface LockScreenProps {
+    onUnlock: () => void
+}
+
+// 设置了应用密码时，启动和闲置超时后显示的锁定界面
+function LockScreen({ onUnlock }: LockScreenProps) {
+    const [passcode, setPasscode] = useState('')
+    const [unlocking, setUnlocking] = useState(false)
+
+    const handleUnlock = async () => {
+        if (!passcode) return
+        setUnlocking(true)
+        try {
+            await api.lock.unlock(passcode)
+            setPasscode('')
+            onUnlock()
+        } catch (error) {
+            message.error(String(error))
+        } finally {
+            setUnlocking(false)
+        }
+    }
+
+    return (
+        <div style={{ height: '100vh', display: 'flex', alignItems: 'center', justifyContent: 'center' }}>
+            <Card style={{ width: 360, textAlign: 'center' }}>
+                <LockOutlined style={{ fontSize: 40, color: '#1890ff' }} />
+                <Title level={4} style={{ marginTop: 16 }}>应用已锁定</Title>
+                <Text type="secondary">请输入密码解锁</Text>
+                <Space.Compact style={{ width: '100%', marginTop: 24 }}>
+                    <Input.Password
+                        autoFocus
+                        value={passcode}
+                        onChange={e => setPasscode(e.target.value)}
+                        onPressEnter={handleUnlock}
+                        placeholder="密码"
+                    />
+                    <Button type="primary" loading={unlocking} onClick={handleUnlock}>
+                        解锁
+                    </Button>
+                </Space.Compact>
+            </Card>
+        </div>
+    )
+}
+
+export default LockScreen
@@ -1,8 +1,8 @@
 import ReactMarkdown from 'react-markdown'
 import remarkGfm from 'remark-gfm'
-import { Spin, Empty, Typography } from 'antd'
+import { Spin, Empty, Typography, Alert, Tag, Progress } from 'antd'
 import { useRecognitionStore } from '../../store'
-import type { RecognitionStatus } from '@shared/types'
+import type { RecognitionStatus, RecognitionStageEvent, RecognitionStage } from '@shared/types'
 
 const { Text } = Typography
 
@@ -14,23 +14,54 @@ const statusMessages: Record<RecognitionStatus, string> = {
     error: '分析失败'
 }
 
+const stageMessages: Record<RecognitionStage, string> = {
+    queued: '排队等待中...',
+    compressing: '正在处理图片...',
+    uploading: '正在上传图片...',
+    waiting_first_token: '等待模型响应...',
+    streaming: '正在接收结果...',
+    saving: '正在保存记录...'
+}
+
+const stageMessage = (event: RecognitionStageEvent) =>
+    event.stage === 'queued' && event.queuePosition
+        ? `排队等待中（第 ${event.queuePosition} 位）...`
+        : stageMessages[event.stage]
+
 export type ViewMode = 'preview' | 'source'
 
 interface ResultViewerProps {
     viewMode: ViewMode
 }
 
+const formatMegabytes = (bytes: number) => `${(bytes / 1024 / 1024).toFixed(1)} MB`
+
 export default function ResultViewer({ viewMode }: ResultViewerProps) {
-    const { status, result } = useRecognitionStore()
+    const { status, result, uploadProgress, stage } = useRecognitionStore()
 
     // 加载中状态 - 但如果有流式内容则显示内容
     const hasStreamContent = result?.content && result.content.length > 0
 
     if ((status === 'uploading' || status === 'analyzing') && !hasStreamContent) {
+        // 图片仍在上传时显示字节进度
+        if (uploadProgress && uploadProgress.sent < uploadProgress.total) {
+            return (
+                <div className="result-area" style={{ display: 'flex', flexDirection: 'column', alignItems: 'center', justifyContent: 'center' }}>
+                    <Progress
+                        type="circle"
+                        percent={Math.floor((uploadProgress.sent / uploadProgress.total) * 100)}
+                    />
+                    <Text style={{ marginTop: 16 }}>
+                        正在上传图片 {formatMegabytes(uploadProgress.sent)} / {formatMegabytes(uploadProgress.total)}
+                    </Text>
+                </div>
+            )
+        }
+
         return (
             <div className="result-area" style={{ display: 'flex', flexDirection: 'column', alignItems: 'center', justifyContent: 'center' }}>
                 <Spin size="large" />
-                <Text style={{ marginTop: 16 }}>{statusMessages[status]}</Text>
+                <Text style={{ marginTop: 16 }}>{stage ? stageMessage(stage) : statusMessages[status]}</Text>
             </div>
         )
     }
@@ -58,6 +89,21 @@ export default function ResultViewer({ viewMode }: ResultViewerProps) {
     // 成功状态
     return (
         <div className="result-area">
+            {/* 本地识别出的二维码/条形码 */}
+            {result.codes && result.codes.length > 0 && (
+                <Alert
+                    type="info"
+                    message="识别到的二维码/条形码"
+                    description={result.codes.map((code, index) => (
+                        <div key={index}>
+                            <Tag>{code.format}</Tag>
+                            <Text copyable>{code.text}</Text>
+                        </div>
+                    ))}
+                    style={{ marginTop: 16 }}
+                />
+            )}
+
             {/* 内容区域 */}
             <div className="result-content" style={{ marginTop: 16 }}>
                 {viewMode === 'preview' ? (
@@ -13,7 +13,9 @@ import {
     Popconfirm,
     Tag,
     Tooltip,
-    Divider
+    Divider,
+    AutoComplete,
+    Alert
 } from 'antd'
 import {
     PlusOutlined,
@@ -23,11 +25,12 @@ import {
     StarOutlined,
     StarFilled,
     CheckCircleOutlined,
-    CloseCircleOutlined
+    CloseCircleOutlined,
+    ReloadOutlined
 } from '@ant-design/icons'
 import type { ColumnsType } from 'antd/es/table'
 import { useConfigStore } from '../../store'
-import type { ModelConfigListItem, ModelConfigInput } from '@shared/types'
+import type { ModelConfigListItem, ModelConfigInput, ConfigHealth } from '@shared/types'
 import { api } from '../../api'
 
 const providerOptions = [
@@ -35,15 +38,23 @@ const providerOptions = [
     { value: 'anthropic', label: 'Anthropic' },
     { value: 'azure', label: 'Azure OpenAI' },
     { value: 'oneapi', label: 'OneAPI' },
-    { value: 'custom', label: '自定义' }
+    { value: 'custom', label: '自定义' },
+    { value: 'rapidocr', label: 'RapidOCR（本地）' }
 ]
 
+const healthTags: Record<ConfigHealth['status'], { color: string; label: string }> = {
+    healthy: { color: 'success', label: '正常' },
+    degraded: { color: 'warning', label: '响应慢' },
+    down: { color: 'error', label: '不可用' }
+}
+
 const providerColors: Record<string, string> = {
     openai: 'green',
     anthropic: 'orange',
     azure: 'blue',
     oneapi: 'purple',
-    custom: 'default'
+    custom: 'default',
+    rapidocr: 'cyan'
 }
 
 export default function ConfigPage() {
@@ -54,15 +65,59 @@ export default function ConfigPage() {
     const [testingForm, setTestingForm] = useState(false)
     const [submitting, setSubmitting] = useState(false)
     const [form] = Form.useForm()
+    const [health, setHealth] = useState<Record<number, ConfigHealth>>({})
+    const [refreshingHealth, setRefreshingHealth] = useState(false)
+    const [revalidating, setRevalidating] = useState(false)
+    const [models, setModels] = useState<string[]>([])
+    const [loadingModels, setLoadingModels] = useState(false)
+    // 本地引擎的 API 地址是模型目录，不需要 API Key
+    const selectedProvider = Form.useWatch('provider', form)
+    const isLocalEngine = selectedProvider === 'rapidocr'
+    const reasoningModel = Form.useWatch('reasoningModel', form)
+    const usesThinkingBudget = selectedProvider === 'anthropic' && reasoningModel
+    const usesOpenAiHeaders = selectedProvider === 'openai'
+    const skipTlsVerify = Form.useWatch('skipTlsVerify', form)
 
     useEffect(() => {
         fetchConfigs()
+        api.config.getHealth().then(applyHealth).catch(() => {})
     }, [fetchConfigs])
 
+    const applyHealth = (items: ConfigHealth[]) => {
+        setHealth(Object.fromEntries(items.map(h => [h.configId, h])))
+    }
+
+    const handleRefreshHealth = async () => {
+        setRefreshingHealth(true)
+        try {
+            applyHealth(await api.config.refreshHealth())
+        } catch (error) {
+            message.error((error as Error).message || String(error))
+        } finally {
+            setRefreshingHealth(false)
+        }
+    }
+
+    // 编辑已保存的配置时提供可用模型作为候选，失败时仍可手动输入
+    const loadModels = async (configId: number, refresh = false) => {
+        setLoadingModels(true)
+        try {
+            setModels(await (refresh ? api.config.refreshModels(configId) : api.config.listModels(configId)))
+        } catch (error) {
+            setModels([])
+            if (refresh) {
+                message.error(`获取模型列表失败: ${String(error)}`)
+            }
+        } finally {
+            setLoadingModels(false)
+        }
+    }
+
     const handleAdd = () => {
+        setModels([])
         setEditingId(null)
         form.resetFields()
-        form.setFieldsValue({ maxTokens: 4096, isActive: true })
+        form.setFieldsValue({ maxTokens: 4096, isActive: true, validate: true })
         setDrawerVisible(true)
     }
 
@@ -77,8 +132,23 @@ export default function ConfigPage() {
                 apiKey: fullConfig.apiKey,
                 modelName: fullConfig.modelName,
                 maxTokens: fullConfig.maxTokens,
+                requestsPerMinute: fullConfig.requestsPerMinute,
+                monthlyBudgetUsd: fullConfig.monthlyBudgetUsd,
+                inputPricePerMtok: fullConfig.inputPricePerMtok,
+                outputPricePerMtok: fullConfig.outputPricePerMtok,
+                redactSensitive: fullConfig.redactSensitive,
+                imageDetail: fullConfig.imageDetail,
+                reasoningModel: fullConfig.reasoningModel,
+                thinkingBudgetTokens: fullConfig.thinkingBudgetTokens,
+                organization: fullConfig.organization,
+                project: fullConfig.project,
+                skipTlsVerify: fullConfig.skipTlsVerify,
                 isActive: fullConfig.isActive
             })
+            setModels([])
+            if (fullConfig.provider !== 'rapidocr') {
+                loadModels(record.id)
+            }
         }
         setDrawerVisible(true)
     }
@@ -88,19 +158,35 @@ export default function ConfigPage() {
         message.success('删除成功')
     }
 
-    const handleSubmit = async (values: ModelConfigInput) => {
+    const handleSubmit = async ({ validate, ...formValues }: ModelConfigInput & { validate?: boolean }) => {
+        // Empty limits and prices are sent as 0 so the backend clears them
+        const values = {
+            ...formValues,
+            // A local engine without a model folder uses the bundled models
+            apiUrl: formValues.apiUrl ?? '',
+            apiKey: formValues.apiKey ?? '',
+            requestsPerMinute: formValues.requestsPerMinute ?? 0,
+            monthlyBudgetUsd: formValues.monthlyBudgetUsd ?? 0,
+            inputPricePerMtok: formValues.inputPricePerMtok ?? 0,
+            outputPricePerMtok: formValues.outputPricePerMtok ?? 0,
+            thinkingBudgetTokens: formValues.thinkingBudgetTokens ?? 0,
+            organization: formValues.organization ?? '',
+            project: formValues.project ?? '',
+            // An empty string clears the default on update; creation just omits it
+            imageDetail: formValues.imageDetail || (editingId ? '' : undefined)
+        }
         setSubmitting(true)
         try {
             if (editingId) {
-                await updateConfig(editingId, values)
+                await updateConfig(editingId, values, validate)
                 message.success('更新成功')
             } else {
-                await createConfig(values)
+                await createConfig(values, validate)
                 message.success('创建成功')
             }
             setDrawerVisible(false)
         } catch (error) {
-            message.error((error as Error).message)
+            message.error((error as Error).message || String(error))
         } finally {
             setSubmitting(false)
         }
@@ -122,9 +208,18 @@ export default function ConfigPage() {
 
     const handleTestForm = async () => {
         try {
-            const values = await form.validateFields(['provider', 'apiUrl', 'apiKey', 'modelName'])
+            const values = await form.validateFields([
+                'provider',
+                'apiUrl',
+                'apiKey',
+                'modelName',
+                'reasoningModel',
+                'organization',
+                'project',
+                'skipTlsVerify'
+            ])
             setTestingForm(true)
-            const result = await api.config.testConnectionWithData(values)
+            const result = await api.config.testConnectionWithData({ ...values, apiKey: values.apiKey ?? '' })
             if (result.success) {
                 message.success(result.message)
             } else {
@@ -137,6 +232,24 @@ export default function ConfigPage() {
         }
     }
 
+    const handleRevalidateAll = async () => {
+        setRevalidating(true)
+        try {
+            const results = await api.config.revalidateAll()
+            await fetchConfigs()
+            const invalid = results.filter(r => !r.success)
+            if (invalid.length === 0) {
+                message.success(`已验证 ${results.length} 个配置，全部有效`)
+            } else {
+                message.warning(`${invalid.length} 个配置验证失败：${invalid.map(r => r.configName).join('、')}`)
+            }
+        } catch (error) {
+            message.error((error as Error).message || String(error))
+        } finally {
+            setRevalidating(false)
+        }
+    }
+
     const handleSetDefault = async (id: number) => {
         await setDefaultConfig(id)
         message.success('已设为默认')
@@ -187,7 +300,21 @@ export default function ConfigPage() {
             key: 'apiKeyMasked',
             width: 200,
             ellipsis: true,
-            render: (key: string) => <code>{key}</code>
+            render: (key: string, record) => (
+                <Space>
+                    <code>{key}</code>
+                    {record.lastValidationStatus === 'invalid' && (
+                        <Tooltip title={`验证失败（${record.lastValidatedAt}）`}>
+                            <Tag color="error">无效</Tag>
+                        </Tooltip>
+                    )}
+                    {record.skipTlsVerify && (
+                        <Tooltip title="已跳过 TLS 证书校验">
+                            <Tag color="warning">不安全</Tag>
+                        </Tooltip>
+                    )}
+                </Space>
+            )
         },
         {
             title: '状态',
@@ -199,6 +326,33 @@ export default function ConfigPage() {
                     : <Tag icon={<CloseCircleOutlined />} color="default">禁用</Tag>
             )
         },
+        {
+            title: (
+                <Space size={4}>
+                    连通性
+                    <Tooltip title="立即检查">
+                        <Button
+                            type="text"
+                            size="small"
+                            icon={<ReloadOutlined />}
+                            loading={refreshingHealth}
+                            onClick={handleRefreshHealth}
+                        />
+                    </Tooltip>
+                </Space>
+            ),
+            key: 'health',
+            render: (_, record) => {
+                const item = health[record.id]
+                if (!record.isActive || !item) return '-'
+                const tag = healthTags[item.status]
+                return (
+                    <Tooltip title={`${item.error ?? `${item.latencyMs} ms`}（${item.checkedAt}）`}>
+                        <Tag color={tag.color}>{tag.label}</Tag>
+                    </Tooltip>
+                )
+            }
+        },
         {
             title: '操作',
             key: 'actions',
@@ -252,14 +406,22 @@ export default function ConfigPage() {
             </div>
             <div className="page-content">
                 <div className="card">
-                    <Button
-                        type="primary"
-                        icon={<PlusOutlined />}
-                        onClick={handleAdd}
-                        style={{ marginBottom: 16 }}
-                    >
-                        添加配置
-                    </Button>
+                    <Space style={{ marginBottom: 16 }}>
+                        <Button
+                            type="primary"
+                            icon={<PlusOutlined />}
+                            onClick={handleAdd}
+                        >
+                            添加配置
+                        </Button>
+                        <Button
+                            icon={<ApiOutlined />}
+                            loading={revalidating}
+                            onClick={handleRevalidateAll}
+                        >
+                            重新验证全部
+                        </Button>
+                    </Space>
 
                     <Table
                         columns={columns}
@@ -313,16 +475,20 @@ export default function ConfigPage() {
 
                     <Form.Item
                         name="apiUrl"
-                        label="API 地址"
-                        rules={[{ required: true, message: '请输入 API 地址' }]}
+                        label={isLocalEngine ? '模型目录' : 'API 地址'}
+                        rules={[{ required: !isLocalEngine, message: '请输入 API 地址' }]}
+                        extra={isLocalEngine ? '目录中需包含检测模型 (*det*.onnx)、识别模型 (*rec*.onnx) 和字典文件 (*keys*.txt)；留空使用应用自带的 PP-OCRv4 模型' : undefined}
                     >
-                        <Input placeholder="如: https://api.openai.com/v1/chat/completions" />
+                        <Input
+                            placeholder={isLocalEngine ? '留空使用自带模型，或如: D:\\models\\PP-OCRv4' : '如: https://api.openai.com/v1/chat/completions'}
+                        />
                     </Form.Item>
 
                     <Form.Item
                         name="apiKey"
                         label="API Key"
-                        rules={[{ required: true, message: '请输入 API Key' }]}
+                        hidden={isLocalEngine}
+                        rules={[{ required: !isLocalEngine, message: '请输入 API Key' }]}
                     >
                         <Input.Password placeholder="请输入 API Key" />
                     </Form.Item>
@@ -332,8 +498,28 @@ export default function ConfigPage() {
                         label="模型名称"
                         rules={[{ required: true, message: '请输入模型名称' }]}
                     >
-                        <Input placeholder="如: gpt-4-vision-preview" />
+                        {editingId !== null && !isLocalEngine ? (
+                            <AutoComplete
+                                options={models.map(model => ({ value: model }))}
+                                filterOption={(input, option) => (option?.value ?? '').toLowerCase().includes(input.toLowerCase())}
+                                placeholder="如: gpt-4-vision-preview"
+                            />
+                        ) : (
+                            <Input placeholder={isLocalEngine ? '如: PP-OCRv4' : '如: gpt-4-vision-preview'} />
+                        )}
                     </Form.Item>
+                    {editingId !== null && !isLocalEngine && (
+                        <Button
+                            type="link"
+                            size="small"
+                            icon={<ReloadOutlined />}
+                            loading={loadingModels}
+                            onClick={() => loadModels(editingId, true)}
+                            style={{ marginTop: -16, marginBottom: 8, paddingLeft: 0 }}
+                        >
+                            刷新模型列表{models.length > 0 ? `（${models.length} 个）` : ''}
+                        </Button>
+                    )}
 
                     <Divider />
 
@@ -344,6 +530,111 @@ export default function ConfigPage() {
                         <InputNumber min={100} max={128000} style={{ width: '100%' }} />
                     </Form.Item>
 
+                    <Form.Item
+                        name="requestsPerMinute"
+                        label="每分钟请求上限"
+                        tooltip="批量识别时按此速率排队发送，避免触发 429；留空表示不限制"
+                    >
+                        <InputNumber min={1} max={10000} placeholder="不限制" style={{ width: '100%' }} />
+                    </Form.Item>
+
+                    <Form.Item
+                        name="monthlyBudgetUsd"
+                        label="每月预算 (USD)"
+                        tooltip="按下方价格和历史 Token 用量估算本月花费，达到 80% 和 100% 时提醒；留空表示不提醒"
+                    >
+                        <InputNumber min={0.01} step={1} precision={2} placeholder="不设置" style={{ width: '100%' }} />
+                    </Form.Item>
+
+                    <Form.Item name="inputPricePerMtok" label="输入价格 (USD / 百万 Token)">
+                        <InputNumber min={0} step={0.1} placeholder="未设置" style={{ width: '100%' }} />
+                    </Form.Item>
+
+                    <Form.Item name="outputPricePerMtok" label="输出价格 (USD / 百万 Token)">
+                        <InputNumber min={0} step={0.1} placeholder="未设置" style={{ width: '100%' }} />
+                    </Form.Item>
+
+                    <Form.Item
+                        name="imageDetail"
+                        label="图片精度"
+                        tooltip="OpenAI 兼容接口的 detail 参数：低精度按固定小图计费，简单截图可省下约九成费用；密集表格请选高精度。识别时可单独指定"
+                    >
+                        <Select
+                            allowClear
+                            placeholder="由接口决定"
+                            options={[
+                                { value: 'low', label: '低精度（low）' },
+                                { value: 'high', label: '高精度（high）' },
+                                { value: 'auto', label: '自动（auto）' }
+                            ]}
+                        />
+                    </Form.Item>
+
+                    <Form.Item
+                        name="reasoningModel"
+                        label="推理模型"
+                        valuePropName="checked"
+                        tooltip="OpenAI o 系列改用 max_completion_tokens 且不发送温度参数；Anthropic 开启扩展思考。思考过程不计入识别结果"
+                    >
+                        <Switch />
+                    </Form.Item>
+
+                    {usesThinkingBudget && (
+                        <Form.Item
+                            name="thinkingBudgetTokens"
+                            label="思考预算 (Token)"
+                            tooltip="扩展思考可用的 Token 数，另计在最大 Token 数之外；留空使用默认值 4096"
+                        >
+                            <InputNumber min={1024} max={64000} placeholder="4096" style={{ width: '100%' }} />
+                        </Form.Item>
+                    )}
+
+                    {usesOpenAiHeaders && (
+                        <>
+                            <Form.Item
+                                name="organization"
+                                label="组织 ID"
+                                tooltip="作为 OpenAI-Organization 请求头发送，企业账户属于多个组织时需要指定；留空不发送"
+                            >
+                                <Input placeholder="org-..." />
+                            </Form.Item>
+                            <Form.Item
+                                name="project"
+                                label="项目 ID"
+                                tooltip="作为 OpenAI-Project 请求头发送；留空不发送"
+                            >
+                                <Input placeholder="proj_..." />
+                            </Form.Item>
+                        </>
+                    )}
+
+                    <Form.Item
+                        name="redactSensitive"
+                        label="上传前脱敏"
+                        valuePropName="checked"
+                        tooltip="发送前先用设置中选择的本地配置识别文字，遮盖邮箱、身份证号和银行卡号；本地或内网地址的配置不处理"
+                    >
+                        <Switch />
+                    </Form.Item>
+
+                    <Form.Item
+                        name="skipTlsVerify"
+                        label="跳过证书校验"
+                        valuePropName="checked"
+                        tooltip="接受该地址的任何 TLS 证书。优先在设置中配置 CA 证书路径，仅在无法获取证书时使用"
+                    >
+                        <Switch />
+                    </Form.Item>
+
+                    {skipTlsVerify && (
+                        <Alert
+                            type="warning"
+                            showIcon
+                            style={{ marginBottom: 24 }}
+                            message="已关闭证书校验，请求可能被中间人截获，API Key 和图片会因此泄露"
+                        />
+                    )}
+
                     <Form.Item
                         name="isActive"
                         label="启用状态"
@@ -352,6 +643,15 @@ export default function ConfigPage() {
                         <Switch checkedChildren="启用" unCheckedChildren="禁用" />
                     </Form.Item>
 
+                    <Form.Item
+                        name="validate"
+                        label="保存前验证密钥"
+                        valuePropName="checked"
+                        tooltip="保存前发送一次测试请求，密钥无效时不保存"
+                    >
+                        <Switch />
+                    </Form.Item>
+
                     <Divider />
 
                     <Button
@@ -1,4 +1,6 @@
 import { useEffect, useState } from 'react'
+import type { DragEvent } from 'react'
+import { startDrag } from '@crabnebula/tauri-plugin-drag'
 import {
     Table,
     Button,
@@ -14,7 +16,9 @@ import {
     Empty,
     Image,
     Tabs,
-    Descriptions
+    Descriptions,
+    Tag,
+    List
 } from 'antd'
 import {
     SearchOutlined,
@@ -22,14 +26,24 @@ import {
     ExportOutlined,
     EyeOutlined,
     ReloadOutlined,
-    ClearOutlined
+    ClearOutlined,
+    SelectOutlined,
+    BookOutlined,
+    FolderOpenOutlined,
+    CheckOutlined,
+    CloseOutlined,
+    AuditOutlined,
+    DragOutlined,
+    ShareAltOutlined,
+    MailOutlined,
+    CloudUploadOutlined
 } from '@ant-design/icons'
 import type { ColumnsType } from 'antd/es/table'
 import dayjs from 'dayjs'
 import ReactMarkdown from 'react-markdown'
 import remarkGfm from 'remark-gfm'
 import { useHistoryStore, useConfigStore, useRecognitionStore } from '../../store'
-import type { HistoryRecord } from '@shared/types'
+import type { HistoryRecord, HistoryStatus, ReviewBatch, ReviewStatus } from '@shared/types'
 import { useNavigate } from 'react-router-dom'
 import { api } from '../../api'
 
@@ -51,10 +65,12 @@ export default function HistoryPage() {
         setFilters,
         clearFilters,
         selectRecord,
+        markReviewed,
         deleteRecord,
         deleteRecords,
         clearAll,
-        exportRecords
+        exportRecords,
+        exportBundle
     } = useHistoryStore()
 
     const { activeConfigs, fetchActiveConfigs } = useConfigStore()
@@ -63,6 +79,16 @@ export default function HistoryPage() {
     const [selectedRowKeys, setSelectedRowKeys] = useState<number[]>([])
     const [drawerVisible, setDrawerVisible] = useState(false)
     const [activeTab, setActiveTab] = useState('result')
+    const [originalImage, setOriginalImage] = useState<string | null>(null)
+    const [reviewVisible, setReviewVisible] = useState(false)
+    const [reviewBatches, setReviewBatches] = useState<ReviewBatch[]>([])
+    const [reviewBatchId, setReviewBatchId] = useState<string>()
+    const [pendingReviews, setPendingReviews] = useState<HistoryRecord[]>([])
+    const [reviewEdits, setReviewEdits] = useState<Record<number, string>>({})
+    const [rerunVisible, setRerunVisible] = useState(false)
+    const [rerunConfigId, setRerunConfigId] = useState<number>()
+    const [rerunProgress, setRerunProgress] = useState<{ completed: number; total: number } | null>(null)
+    const [dragFormat, setDragFormat] = useState<'md' | 'txt' | 'csv'>('md')
 
     useEffect(() => {
         fetchRecords()
@@ -76,6 +102,14 @@ export default function HistoryPage() {
         }
     }, [drawerVisible, selectedRecord?.id])
 
+    // 由系统原生拖放把当前记录的结果文件拖到其他应用
+    const handleDragStart = (event: DragEvent, historyId: number) => {
+        event.preventDefault()
+        api.history.createDragFile(historyId, dragFormat)
+            .then((file) => startDrag({ item: [file.path], icon: file.icon }))
+            .catch((error) => message.error(String(error)))
+    }
+
     const handleSearch = (keyword: string) => {
         setFilters({ keyword })
     }
@@ -83,11 +117,11 @@ export default function HistoryPage() {
     const handleDateChange = (dates: any) => {
         if (dates) {
             setFilters({
-                startDate: dates[0].format('YYYY-MM-DD'),
-                endDate: dates[1].format('YYYY-MM-DD')
+                startTime: dates[0].startOf('day').valueOf(),
+                endTime: dates[1].endOf('day').valueOf()
             })
         } else {
-            setFilters({ startDate: undefined, endDate: undefined })
+            setFilters({ startTime: undefined, endTime: undefined })
         }
     }
 
@@ -95,17 +129,38 @@ export default function HistoryPage() {
         setFilters({ configId })
     }
 
+    const handleStatusFilter = (status: HistoryStatus | undefined) => {
+        setFilters({ status })
+    }
+
+    const handleReviewFilter = (needsReview: boolean | undefined) => {
+        setFilters({ needsReview })
+    }
+
     const handleView = (record: HistoryRecord) => {
         console.log('[History] Viewing record:', record.id)
         console.log('[History] Record prompt:', record.prompt?.substring(0, 100))
         console.log('[History] Record result:', record.result?.substring(0, 100))
         selectRecord(record)
         setDrawerVisible(true)
+
+        // 按需加载原图，缩略图先行显示
+        setOriginalImage(null)
+        api.history.getImage(record.id).then((image) => {
+            if (image) {
+                setOriginalImage(`data:${image.mimeType};base64,${image.base64}`)
+            }
+        }).catch((error) => {
+            console.error('[History] Failed to load original image:', error)
+        })
     }
 
-    const handleReRecognize = (record: HistoryRecord) => {
-        // 设置图片和提示词
-        if (record.imageThumbnail) {
+    const handleReRecognize = async (record: HistoryRecord) => {
+        // 设置图片和提示词，优先使用原图
+        const image = await api.history.getImage(record.id).catch(() => null)
+        if (image) {
+            setImage(image.base64, image.mimeType, null, record.imagePath)
+        } else if (record.imageThumbnail) {
             const match = record.imageThumbnail.match(/^data:(.*?);base64,(.*)$/)
             if (match) {
                 setImage(match[2], match[1])
@@ -135,13 +190,41 @@ export default function HistoryPage() {
         message.success(`已删除 ${selectedRowKeys.length} 条记录`)
     }
 
+    const handleRerun = async () => {
+        if (!rerunConfigId || selectedRowKeys.length === 0) return
+        setRerunProgress({ completed: 0, total: selectedRowKeys.length })
+        const unlisten = await api.history.onRerunProgress((progress) => {
+            setRerunProgress({ completed: progress.completed, total: progress.total })
+        })
+        try {
+            const report = await api.history.rerunMany(selectedRowKeys, rerunConfigId)
+            if (report.failed.length > 0) {
+                message.warning(`${report.succeeded} 条重新识别完成，${report.failed.length} 条失败：${report.failed[0].error}`)
+            } else {
+                message.success(`${report.succeeded} 条重新识别完成，可在审核队列的批次 ${report.batchId} 中查看`)
+            }
+            setRerunVisible(false)
+            setSelectedRowKeys([])
+            fetchRecords()
+        } catch (error) {
+            message.error(String(error))
+        } finally {
+            unlisten()
+            setRerunProgress(null)
+        }
+    }
+
     const handleClearAll = async () => {
         const count = await clearAll()
         message.success(`已清空 ${count} 条记录`)
     }
 
-    const handleExport = async (format: 'json' | 'csv') => {
-        const data = await exportRecords()
+    const handleExport = async (
+        format: 'json' | 'csv',
+        load: () => Promise<HistoryRecord[]> = exportRecords,
+        name = '历史记录'
+    ) => {
+        const data = await load()
 
         let content: string
         let extension: string
@@ -151,14 +234,18 @@ export default function HistoryPage() {
             extension = 'json'
         } else {
             // CSV 格式
-            const headers = ['ID', '配置名称', '提示词', '结果', 'Token消耗', '耗时(ms)', '时间']
+            const headers = ['ID', '配置名称', '提示词', '结果', 'Token消耗', '输入Token', '输出Token', '耗时(ms)', '首Token(ms)', 'Token/秒', '时间']
             const rows = data.map(r => [
                 r.id,
                 r.configName,
                 `"${r.prompt.replace(/"/g, '""')}"`,
                 `"${r.result.replace(/"/g, '""')}"`,
                 r.tokensUsed || '',
+                r.inputTokens || '',
+                r.outputTokens || '',
                 r.durationMs || '',
+                r.ttftMs ?? '',
+                r.tokensPerSecond != null ? r.tokensPerSecond.toFixed(1) : '',
                 r.createdAt
             ])
             content = [headers.join(','), ...rows.map(r => r.join(','))].join('\n')
@@ -167,7 +254,7 @@ export default function HistoryPage() {
 
         const success = await api.dialog.saveFile({
             content,
-            defaultName: `历史记录_${Date.now()}.${extension}`,
+            defaultName: `${name}_${Date.now()}.${extension}`,
             filters: [{ name: format.toUpperCase(), extensions: [extension] }]
         })
 
@@ -176,6 +263,53 @@ export default function HistoryPage() {
         }
     }
 
+    const loadPendingReviews = async (batchId: string) => {
+        setReviewBatchId(batchId)
+        setReviewEdits({})
+        const pending = await api.history.listPendingReviews(batchId, 1, 100)
+        setPendingReviews(pending.records)
+    }
+
+    const handleOpenReview = async () => {
+        setReviewVisible(true)
+        try {
+            const batches = await api.history.getReviewBatches()
+            setReviewBatches(batches)
+            const first = batches.find((b) => b.pending > 0) ?? batches[0]
+            if (first) {
+                await loadPendingReviews(first.batchId)
+            } else {
+                setPendingReviews([])
+            }
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleReview = async (record: HistoryRecord, status: ReviewStatus) => {
+        const edited = reviewEdits[record.id]
+        try {
+            await api.history.review(record.id, status, edited !== undefined && edited !== record.result ? edited : undefined)
+            setPendingReviews((list) => list.filter((item) => item.id !== record.id))
+            setReviewBatches(await api.history.getReviewBatches())
+            fetchRecords()
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    // 导出为 ZIP，包含清单 (JSON/CSV) 与原始图片
+    const handleExportBundle = async () => {
+        try {
+            const success = await exportBundle(`历史记录_${Date.now()}`)
+            if (success) {
+                message.success('导出成功')
+            }
+        } catch (error) {
+            message.error(`导出失败: ${String(error)}`)
+        }
+    }
+
     const columns: ColumnsType<HistoryRecord> = [
         {
             title: '缩略图',
@@ -216,10 +350,21 @@ export default function HistoryPage() {
             dataIndex: 'result',
             key: 'result',
             ellipsis: true,
-            render: (result: string) => (
-                <Text ellipsis style={{ maxWidth: 200 }}>
-                    {result}
-                </Text>
+            render: (result: string, record) => (
+                record.status === 'failed' ? (
+                    <Text ellipsis type="danger" style={{ maxWidth: 200 }}>
+                        <Tag color="error">失败</Tag>
+                        {record.error}
+                    </Text>
+                ) : (
+                    <Text ellipsis style={{ maxWidth: 200 }}>
+                        {record.status === 'partial' && <Tag color="warning">部分</Tag>}
+                        {record.needsReview && <Tag color="orange">待复核</Tag>}
+                        {record.reviewStatus === 'pending' && <Tag color="processing">待审核</Tag>}
+                        {record.reviewStatus === 'rejected' && <Tag color="error">已驳回</Tag>}
+                        {result}
+                    </Text>
+                )
             )
         },
         {
@@ -292,6 +437,27 @@ export default function HistoryPage() {
                                 label: c.name
                             }))}
                         />
+                        <Select
+                            placeholder="状态"
+                            allowClear
+                            style={{ width: 100 }}
+                            onChange={handleStatusFilter}
+                            options={[
+                                { value: 'success', label: '成功' },
+                                { value: 'failed', label: '失败' },
+                                { value: 'partial', label: '部分' }
+                            ]}
+                        />
+                        <Select
+                            placeholder="复核"
+                            allowClear
+                            style={{ width: 110 }}
+                            onChange={handleReviewFilter}
+                            options={[
+                                { value: true, label: '待复核' },
+                                { value: false, label: '无需复核' }
+                            ]}
+                        />
                         <Button onClick={clearFilters}>
                             清除筛选
                         </Button>
@@ -309,12 +475,23 @@ export default function HistoryPage() {
                                 </Button>
                             </Popconfirm>
                         )}
+                        {selectedRowKeys.length > 0 && (
+                            <Button icon={<ReloadOutlined />} onClick={() => setRerunVisible(true)}>
+                                换模型重新识别
+                            </Button>
+                        )}
                         <Button icon={<ExportOutlined />} onClick={() => handleExport('json')}>
                             导出 JSON
                         </Button>
                         <Button icon={<ExportOutlined />} onClick={() => handleExport('csv')}>
                             导出 CSV
                         </Button>
+                        <Button icon={<ExportOutlined />} onClick={handleExportBundle}>
+                            导出 ZIP（含图片）
+                        </Button>
+                        <Button icon={<AuditOutlined />} onClick={handleOpenReview}>
+                            审核队列
+                        </Button>
                         <Popconfirm
                             title="确定要清空所有记录吗？此操作不可恢复！"
                             onConfirm={handleClearAll}
@@ -360,12 +537,22 @@ export default function HistoryPage() {
                     <div style={{ display: 'flex', flexDirection: 'column', height: '100%' }}>
                         {selectedRecord.imageThumbnail && (
                             <div style={{ marginBottom: 16, textAlign: 'center', background: '#f5f5f5', padding: 8, borderRadius: 8 }}>
-                                <Image
-                                    src={selectedRecord.imageThumbnail}
-                                    alt="图片"
-                                    style={{ maxHeight: 200, objectFit: 'contain' }}
-                                    preview={{ src: selectedRecord.imageThumbnail }}
-                                />
+                                <Space>
+                                    <Image
+                                        src={originalImage ?? selectedRecord.imageThumbnail}
+                                        alt="图片"
+                                        style={{ maxHeight: 200, objectFit: 'contain' }}
+                                        preview={{ src: originalImage ?? selectedRecord.imageThumbnail }}
+                                    />
+                                    {/* 对比识别的第二张图片只保存了缩略图 */}
+                                    {selectedRecord.compareThumbnail && (
+                                        <Image
+                                            src={selectedRecord.compareThumbnail}
+                                            alt="对比图片"
+                                            style={{ maxHeight: 200, objectFit: 'contain' }}
+                                        />
+                                    )}
+                                </Space>
                             </div>
                         )
                         }
@@ -384,9 +571,13 @@ export default function HistoryPage() {
                                         label: '识别结果',
                                         children: (
                                             <div className="result-content" style={{ height: '100%', overflowY: 'auto', padding: '0 4px' }}>
-                                                <ReactMarkdown remarkPlugins={[remarkGfm]}>
-                                                    {selectedRecord.result}
-                                                </ReactMarkdown>
+                                                {selectedRecord.status === 'failed' ? (
+                                                    <Text type="danger">{selectedRecord.error}</Text>
+                                                ) : (
+                                                    <ReactMarkdown remarkPlugins={[remarkGfm]}>
+                                                        {selectedRecord.result}
+                                                    </ReactMarkdown>
+                                                )}
                                             </div>
                                         )
                                     },
@@ -401,19 +592,64 @@ export default function HistoryPage() {
                                             </div>
                                         )
                                     },
+                                    ...(selectedRecord.reasoning ? [{
+                                        key: 'reasoning',
+                                        label: '推理过程',
+                                        children: (
+                                            <div style={{ height: '100%', overflowY: 'auto' }}>
+                                                <Paragraph style={{ background: '#f5f5f5', padding: 12, borderRadius: 4, whiteSpace: 'pre-wrap' }}>
+                                                    {selectedRecord.reasoning}
+                                                </Paragraph>
+                                            </div>
+                                        )
+                                    }] : []),
                                     {
                                         key: 'info',
                                         label: '详细信息',
                                         children: (
                                             <Descriptions column={1} bordered size="small">
                                                 <Descriptions.Item label="模型配置">{selectedRecord.configName}</Descriptions.Item>
+                                                {selectedRecord.modelName && (
+                                                    <Descriptions.Item label="模型">
+                                                        {selectedRecord.provider} / {selectedRecord.modelName}
+                                                    </Descriptions.Item>
+                                                )}
+                                                {selectedRecord.maxTokens != null && (
+                                                    <Descriptions.Item label="参数">
+                                                        {[
+                                                            selectedRecord.temperature != null && `temperature ${selectedRecord.temperature}`,
+                                                            selectedRecord.topP != null && `top_p ${selectedRecord.topP}`,
+                                                            `max_tokens ${selectedRecord.maxTokens}`
+                                                        ].filter(Boolean).join(', ')}
+                                                    </Descriptions.Item>
+                                                )}
                                                 <Descriptions.Item label="创建时间">{dayjs(selectedRecord.createdAt).format('YYYY-MM-DD HH:mm:ss')}</Descriptions.Item>
                                                 {selectedRecord.tokensUsed && (
-                                                    <Descriptions.Item label="Token消耗">{selectedRecord.tokensUsed}</Descriptions.Item>
+                                                    <Descriptions.Item label="Token消耗">
+                                                        {selectedRecord.tokensUsed}
+                                                        {selectedRecord.inputTokens != null && selectedRecord.outputTokens != null &&
+                                                            ` (输入 ${selectedRecord.inputTokens} / 输出 ${selectedRecord.outputTokens})`}
+                                                    </Descriptions.Item>
                                                 )}
                                                 {selectedRecord.durationMs && (
                                                     <Descriptions.Item label="耗时">{(selectedRecord.durationMs / 1000).toFixed(2)}s</Descriptions.Item>
                                                 )}
+                                                {selectedRecord.ttftMs != null && (
+                                                    <Descriptions.Item label="首 Token">
+                                                        {(selectedRecord.ttftMs / 1000).toFixed(2)}s
+                                                        {selectedRecord.tokensPerSecond != null &&
+                                                            ` (${selectedRecord.tokensPerSecond.toFixed(1)} tokens/s)`}
+                                                    </Descriptions.Item>
+                                                )}
+                                                {selectedRecord.rerunOf != null && (
+                                                    <Descriptions.Item label="重新识别自">#{selectedRecord.rerunOf}</Descriptions.Item>
+                                                )}
+                                                {selectedRecord.confidence != null && (
+                                                    <Descriptions.Item label="置信度">
+                                                        {Math.round(selectedRecord.confidence * 100)}%
+                                                        {selectedRecord.needsReview && <Tag color="orange" style={{ marginLeft: 8 }}>待复核</Tag>}
+                                                    </Descriptions.Item>
+                                                )}
                                             </Descriptions>
                                         )
                                     }
@@ -423,16 +659,95 @@ export default function HistoryPage() {
                         </div>
 
                         <div style={{ marginTop: 16, paddingTop: 16, borderTop: '1px solid #f0f0f0', textAlign: 'right' }}>
-                            <Button
-                                type="primary"
-                                icon={<ReloadOutlined />}
-                                onClick={() => {
-                                    handleReRecognize(selectedRecord)
-                                    setDrawerVisible(false)
-                                }}
-                            >
-                                重新识别
-                            </Button>
+                            <Space>
+                                <Button
+                                    icon={<SelectOutlined />}
+                                    onClick={() => api.window.openResult(selectedRecord.id).catch((error) => message.error(String(error)))}
+                                >
+                                    新窗口打开
+                                </Button>
+                                <Button
+                                    icon={<BookOutlined />}
+                                    onClick={() => api.history.appendToNotebook(selectedRecord.id)
+                                        .then(() => message.success('已追加到笔记'))
+                                        .catch((error) => message.error(String(error)))}
+                                >
+                                    追加到笔记
+                                </Button>
+                                {selectedRecord.needsReview && (
+                                    <Button
+                                        icon={<CheckOutlined />}
+                                        onClick={() => markReviewed(selectedRecord.id)
+                                            .catch((error) => message.error(String(error)))}
+                                    >
+                                        标记为已复核
+                                    </Button>
+                                )}
+                                <Button
+                                    icon={<ShareAltOutlined />}
+                                    onClick={() => api.share.result(selectedRecord.reviewedResult ?? selectedRecord.result)
+                                        .then((link) => message.success(`分享链接已复制: ${link}`))
+                                        .catch((error) => message.error(String(error)))}
+                                >
+                                    分享
+                                </Button>
+                                <Button
+                                    icon={<MailOutlined />}
+                                    title="通过 SMTP 发送给设置中的默认收件人，并附带原图"
+                                    onClick={() => api.mail.sendResult(selectedRecord.id)
+                                        .then((recipients) => message.success(`已发送至 ${recipients.join(', ')}`))
+                                        .catch((error) => message.error(String(error)))}
+                                >
+                                    发送邮件
+                                </Button>
+                                <Button
+                                    icon={<CloudUploadOutlined />}
+                                    onClick={() => api.notion.exportHistory(selectedRecord.id)
+                                        .then((url) => message.success(`已导出到 Notion: ${url}`))
+                                        .catch((error) => message.error(String(error)))}
+                                >
+                                    导出到 Notion
+                                </Button>
+                                <Space.Compact>
+                                    <Select
+                                        value={dragFormat}
+                                        onChange={setDragFormat}
+                                        style={{ width: 110 }}
+                                        options={[
+                                            { value: 'md', label: 'Markdown' },
+                                            { value: 'txt', label: '纯文本' },
+                                            { value: 'csv', label: 'CSV 表格' }
+                                        ]}
+                                    />
+                                    <Button
+                                        icon={<DragOutlined />}
+                                        draggable
+                                        title="拖到其他应用中保存为文件"
+                                        onDragStart={(event) => handleDragStart(event, selectedRecord.id)}
+                                    >
+                                        拖出文件
+                                    </Button>
+                                </Space.Compact>
+                                {selectedRecord.imagePath && (
+                                    <Button
+                                        icon={<FolderOpenOutlined />}
+                                        onClick={() => api.history.revealInFolder(selectedRecord.id)
+                                            .catch((error) => message.error(String(error)))}
+                                    >
+                                        打开所在文件夹
+                                    </Button>
+                                )}
+                                <Button
+                                    type="primary"
+                                    icon={<ReloadOutlined />}
+                                    onClick={() => {
+                                        handleReRecognize(selectedRecord)
+                                        setDrawerVisible(false)
+                                    }}
+                                >
+                                    重新识别
+                                </Button>
+                            </Space>
                         </div>
                     </div >
                 ) : (
@@ -440,6 +755,117 @@ export default function HistoryPage() {
                 )
                 }
             </Drawer >
+
+            <Modal
+                title={`用其他模型重新识别 ${selectedRowKeys.length} 条记录`}
+                open={rerunVisible}
+                onOk={handleRerun}
+                okText="开始"
+                okButtonProps={{ disabled: !rerunConfigId, loading: rerunProgress !== null }}
+                onCancel={() => setRerunVisible(false)}
+                cancelButtonProps={{ disabled: rerunProgress !== null }}
+                closable={rerunProgress === null}
+                maskClosable={false}
+            >
+                <Select
+                    placeholder="选择模型"
+                    value={rerunConfigId}
+                    onChange={setRerunConfigId}
+                    style={{ width: '100%' }}
+                    options={activeConfigs.map(c => ({ value: c.id, label: c.name }))}
+                />
+                <Paragraph type="secondary" style={{ marginTop: 12, marginBottom: 0 }}>
+                    使用原记录保存的图片和提示词，新记录关联原记录并进入新批次的审核队列；没有保存原图的记录会跳过
+                </Paragraph>
+                {rerunProgress && (
+                    <Paragraph style={{ marginTop: 12, marginBottom: 0 }}>
+                        进度 {rerunProgress.completed} / {rerunProgress.total}
+                    </Paragraph>
+                )}
+            </Modal>
+
+            {/* 批次审核队列 */}
+            <Drawer
+                title="审核队列"
+                placement="right"
+                width={640}
+                open={reviewVisible}
+                onClose={() => setReviewVisible(false)}
+                extra={
+                    <Space>
+                        <Button
+                            icon={<ExportOutlined />}
+                            disabled={!reviewBatchId}
+                            onClick={() => handleExport('json', () => api.history.exportApproved(reviewBatchId), reviewBatchId)}
+                        >
+                            导出已通过 JSON
+                        </Button>
+                        <Button
+                            icon={<ExportOutlined />}
+                            disabled={!reviewBatchId}
+                            onClick={() => handleExport('csv', () => api.history.exportApproved(reviewBatchId), reviewBatchId)}
+                        >
+                            CSV
+                        </Button>
+                    </Space>
+                }
+            >
+                {reviewBatches.length === 0 ? (
+                    <Empty description="没有批次记录，使用命令行 --batch 参数识别的结果会进入审核队列" />
+                ) : (
+                    <>
+                        <Select
+                            value={reviewBatchId}
+                            style={{ width: '100%', marginBottom: 16 }}
+                            onChange={(batchId: string) => loadPendingReviews(batchId).catch((error) => message.error(String(error)))}
+                            options={reviewBatches.map((b) => ({
+                                value: b.batchId,
+                                label: `${b.batchId}（待审核 ${b.pending} / 通过 ${b.approved} / 驳回 ${b.rejected}）`
+                            }))}
+                        />
+                        <List
+                            dataSource={pendingReviews}
+                            locale={{ emptyText: '该批次没有待审核的记录' }}
+                            renderItem={(record) => (
+                                <List.Item
+                                    actions={[
+                                        <Button
+                                            key="approve"
+                                            type="primary"
+                                            size="small"
+                                            icon={<CheckOutlined />}
+                                            onClick={() => handleReview(record, 'approved')}
+                                        >
+                                            通过
+                                        </Button>,
+                                        <Button
+                                            key="reject"
+                                            danger
+                                            size="small"
+                                            icon={<CloseOutlined />}
+                                            onClick={() => handleReview(record, 'rejected')}
+                                        >
+                                            驳回
+                                        </Button>
+                                    ]}
+                                >
+                                    <Space align="start" style={{ width: '100%' }}>
+                                        {record.imageThumbnail && (
+                                            <Image src={record.imageThumbnail} alt="缩略图" width={96} />
+                                        )}
+                                        <Input.TextArea
+                                            value={reviewEdits[record.id] ?? record.reviewedResult ?? record.result}
+                                            onChange={(e) => setReviewEdits((edits) => ({ ...edits, [record.id]: e.target.value }))}
+                                            autoSize={{ minRows: 3, maxRows: 10 }}
+                                            style={{ width: 360 }}
+                                        />
+                                    </Space>
+                                </List.Item>
+                            )}
+                        />
+                    </>
+                )}
+            </Drawer>
         </>
     )
 }
@@ -0,0 +1,129 @@
+import { useCallback, useEffect, useState } from 'react'
+import ReactMarkdown from 'react-markdown'
+import remarkGfm from 'remark-gfm'
+import { Button, Empty, Spin, Typography, message } from 'antd'
+import { CopyOutlined, InboxOutlined } from '@ant-design/icons'
+import type { RecognitionResult } from '@shared/types'
+import { api } from '../../api'
+
+const { Text } = Typography
+
+// 使用默认预设识别；没有默认预设时使用默认配置和默认模板
+const recognizeImage = async (imageData: string, imageMimeType: string): Promise<RecognitionResult> => {
+    const preset = await api.preset.getDefault()
+    if (preset) {
+        return api.recognition.recognizeWithPreset(preset.id, imageData, imageMimeType)
+    }
+
+    const [config, template] = await Promise.all([api.config.getDefault(), api.template.getDefault()])
+    if (!config || !template) {
+        return { success: false, error: '请先设置默认模型配置和提示词模板' }
+    }
+    return api.recognition.recognize({
+        configId: config.id,
+        imageData,
+        imageMimeType,
+        prompt: template.content,
+        options: { stream: true }
+    })
+}
+
+const readFile = (file: File): Promise<string> =>
+    new Promise((resolve, reject) => {
+        const reader = new FileReader()
+        reader.onload = (e) => resolve((e.target?.result as string).split(',')[1])
+        reader.onerror = () => reject(reader.error)
+        reader.readAsDataURL(file)
+    })
+
+// 置顶的快速识别小窗口：拖入或粘贴图片即开始识别
+export default function MiniCapturePage() {
+    const [busy, setBusy] = useState(false)
+    const [content, setContent] = useState('')
+    const [error, setError] = useState<string | null>(null)
+
+    const start = useCallback(async (imageData: string, imageMimeType: string) => {
+        if (busy) return
+        setBusy(true)
+        setContent('')
+        setError(null)
+
+        const unlisten = await api.recognition.onStreamChunk((chunk) => {
+            setContent((prev) => prev + chunk)
+        })
+        try {
+            const result = await recognizeImage(imageData, imageMimeType)
+            if (result.success) {
+                setContent(result.content || '')
+            } else {
+                setError(result.error || '识别失败')
+            }
+        } catch (e) {
+            setError(String(e))
+        } finally {
+            unlisten()
+            setBusy(false)
+        }
+    }, [busy])
+
+    const handleFile = useCallback(async (file: File) => {
+        if (!file.type.startsWith('image/')) return
+        start(await readFile(file), file.type)
+    }, [start])
+
+    useEffect(() => {
+        const handlePaste = async (e: ClipboardEvent) => {
+            for (const item of e.clipboardData?.items ?? []) {
+                const file = item.type.startsWith('image/') ? item.getAsFile() : null
+                if (file) {
+                    handleFile(file)
+                    return
+                }
+            }
+            const clipboardImage = await api.clipboard.readImage().catch(() => null)
+            if (clipboardImage) {
+                start(clipboardImage.base64, clipboardImage.mimeType)
+            }
+        }
+        document.addEventListener('paste', handlePaste)
+        return () => document.removeEventListener('paste', handlePaste)
+    }, [handleFile, start])
+
+    const handleCopy = async () => {
+        await api.clipboard.writeText(content)
+        message.success('已复制')
+    }
+
+    return (
+        <div
+            style={{ height: '100vh', display: 'flex', flexDirection: 'column', padding: 12, boxSizing: 'border-box' }}
+            onDragOver={(e) => e.preventDefault()}
+            onDrop={(e) => {
+                e.preventDefault()
+                const file = e.dataTransfer.files?.[0]
+                if (file) handleFile(file)
+            }}
+        >
+            <div style={{ flex: 1, overflow: 'auto' }}>
+                {error ? (
+                    <Text type="danger">识别失败: {error}</Text>
+                ) : content ? (
+                    <ReactMarkdown remarkPlugins={[remarkGfm]}>{content}</ReactMarkdown>
+                ) : busy ? (
+                    <div style={{ textAlign: 'center', marginTop: 80 }}><Spin /></div>
+                ) : (
+                    <Empty
+                        image={<InboxOutlined style={{ fontSize: 40, color: '#1890ff' }} />}
+                        description="拖入图片或按 Ctrl+V 粘贴"
+                        style={{ marginTop: 60 }}
+                    />
+                )}
+            </div>
+            {content && !busy && (
+                <Button icon={<CopyOutlined />} size="small" onClick={handleCopy} style={{ marginTop: 8 }}>
+                    复制结果
+                </Button>
+            )}
+        </div>
+    )
+}
@@ -11,7 +11,9 @@ import {
     Space,
     Switch,
     InputNumber,
-    Segmented
+    Segmented,
+    AutoComplete,
+    Alert
 } from 'antd'
 import {
     PlayCircleOutlined,
@@ -27,19 +29,32 @@ import {
     ThunderboltOutlined,
     PlusOutlined,
     DeleteOutlined,
-    StopOutlined
+    StopOutlined,
+    SoundOutlined,
+    DesktopOutlined,
+    SafetyCertificateOutlined,
+    ShareAltOutlined
 } from '@ant-design/icons'
 import type { MenuProps } from 'antd'
+import dayjs from 'dayjs'
 import { useConfigStore, useRecognitionStore } from '../../store'
 import ImageUploader from '../../components/ImageUploader'
 import ResultViewer from '../../components/ResultViewer'
-import type { PromptTemplate } from '@shared/types'
-import { api } from '../../api'
+import type { PromptTemplate, PromptHistoryEntry, SimilarHistory, InferenceDevice } from '@shared/types'
+import { OUTPUT_LANGUAGES } from '@shared/types'
+import { api, AppError } from '../../api'
 
 const { TextArea } = Input
 
 type ViewMode = 'preview' | 'source'
 
+const deviceLabels: Record<InferenceDevice, string> = {
+    cpu: 'CPU',
+    cuda: 'CUDA',
+    directml: 'DirectML',
+    metal: 'Core ML'
+}
+
 
 export default function RecognitionPage() {
     const { activeConfigs, fetchActiveConfigs } = useConfigStore()
@@ -59,16 +74,68 @@ export default function RecognitionPage() {
         setTopP,
         setMaxTokens,
         setStream,
+        streamFile,
+        setStreamFile,
+        exportToNotion,
+        setExportToNotion,
+        outputLanguage,
+        setOutputLanguage,
         recognize,
         cancelRecognition,
         reset,
         loadSettings,
         customParams,
-        setCustomParams
+        setCustomParams,
+        originalImageData
     } = useRecognitionStore()
 
     const [templates, setTemplates] = useState<PromptTemplate[]>([])
+    const [promptSuggestions, setPromptSuggestions] = useState<PromptHistoryEntry[]>([])
     const [viewMode, setViewMode] = useState<ViewMode>('preview')
+    const [similar, setSimilar] = useState<SimilarHistory[]>([])
+    const [sharing, setSharing] = useState(false)
+
+    // 上传图片后查找识别过的相同图片，避免重复消耗 Token
+    useEffect(() => {
+        setSimilar([])
+        if (!originalImageData) return
+        let cancelled = false
+        api.history.findSimilar(originalImageData)
+            .then((items) => {
+                if (!cancelled) setSimilar(items)
+            })
+            .catch((e) => console.error('Failed to find similar history:', e))
+        return () => {
+            cancelled = true
+        }
+    }, [originalImageData])
+
+    useEffect(() => {
+        if (!prompt.trim()) {
+            setPromptSuggestions([])
+            return
+        }
+        const timer = setTimeout(() => {
+            api.promptHistory.getSuggestions(prompt, 8)
+                .then(setPromptSuggestions)
+                .catch(() => setPromptSuggestions([]))
+        }, 300)
+        return () => clearTimeout(timer)
+    }, [prompt])
+
+    // 导出到 Notion 在识别返回后进行，完成后提示
+    useEffect(() => {
+        const unlisten = api.recognition.onNotionExport(({ url, error }) => {
+            if (url) {
+                message.success(`已导出到 Notion: ${url}`)
+            } else {
+                message.warning(`导出到 Notion 失败: ${error}`)
+            }
+        })
+        return () => {
+            unlisten.then(fn => fn())
+        }
+    }, [])
 
     useEffect(() => {
         fetchActiveConfigs()
@@ -111,6 +178,28 @@ export default function RecognitionPage() {
         }
     }
 
+    const handleShare = async () => {
+        if (!result?.content) return
+        setSharing(true)
+        try {
+            const link = await api.share.result(result.content)
+            message.success(`分享链接已复制: ${link}`)
+        } catch (error) {
+            message.error(String(error))
+        } finally {
+            setSharing(false)
+        }
+    }
+
+    const handleSpeak = async () => {
+        if (!result?.content) return
+        try {
+            await api.speech.speak(result.content)
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
     const handleExport = async (format: 'txt' | 'md') => {
         if (!result?.content) return
 
@@ -128,8 +217,18 @@ export default function RecognitionPage() {
         }
     }
 
+    const handleSelectStreamFile = async () => {
+        const file = await api.dialog.selectSavePath(`识别结果_${Date.now()}.md`, [
+            { name: 'Markdown', extensions: ['md'] },
+            { name: 'Text', extensions: ['txt'] }
+        ])
+        if (file) {
+            setStreamFile(file)
+        }
+    }
+
     const handleTemplateSelect = (template: PromptTemplate) => {
-        setPrompt(template.content)
+        setPrompt(template.content, template.id)
         api.template.incrementUse(template.id)
     }
 
@@ -140,7 +239,17 @@ export default function RecognitionPage() {
         }
         const name = window.prompt('请输入模板名称:')
         if (name) {
-            await api.template.create(name, prompt)
+            try {
+                await api.template.create(name, prompt)
+            } catch (error) {
+                if (!(error instanceof AppError && error.code?.code === 'duplicate_template')) {
+                    throw error
+                }
+                if (!window.confirm(`${error.message}，仍然保存？`)) {
+                    return
+                }
+                await api.template.create(name, prompt, undefined, undefined, true)
+            }
             await loadTemplates()
             message.success('模板保存成功')
         }
@@ -184,6 +293,19 @@ export default function RecognitionPage() {
 
                     {/* 图片上传 */}
                     <ImageUploader />
+                    {similar.length > 0 && (
+                        <Alert
+                            style={{ marginTop: 8 }}
+                            type="info"
+                            showIcon
+                            message={`此图片可能已识别过：${dayjs(similar[0].record.createdAt).format('YYYY-MM-DD HH:mm')}（${similar[0].record.configName}）`}
+                            action={
+                                <Button size="small" onClick={() => api.window.openResult(similar[0].record.id)}>
+                                    查看结果
+                                </Button>
+                            }
+                        />
+                    )}
 
                     {/* 提示词输入 */}
                     <div style={{ marginTop: 16 }}>
@@ -200,13 +322,21 @@ export default function RecognitionPage() {
                                 </Button>
                             </Space>
                         </div>
-                        <TextArea
-                            autoSize={{ minRows: 4, maxRows: 12 }}
+                        {/* 输入时提示曾经使用过的提示词 */}
+                        <AutoComplete
                             value={prompt}
-                            onChange={(e) => setPrompt(e.target.value)}
-                            placeholder="请输入提示词 (支持 Markdown)..."
-                            style={{ fontFamily: 'Consolas, Monaco, monospace' }}
-                        />
+                            onChange={value => setPrompt(value)}
+                            options={promptSuggestions
+                                .filter(entry => entry.prompt !== prompt)
+                                .map(entry => ({ value: entry.prompt, label: entry.prompt }))}
+                            style={{ width: '100%' }}
+                        >
+                            <TextArea
+                                autoSize={{ minRows: 4, maxRows: 12 }}
+                                placeholder="请输入提示词 (支持 Markdown)..."
+                                style={{ fontFamily: 'Consolas, Monaco, monospace' }}
+                            />
+                        </AutoComplete>
                     </div>
 
 
@@ -225,6 +355,41 @@ export default function RecognitionPage() {
                                         <span className="label">流式输出:</span>
                                         <Switch checked={stream} onChange={setStream} />
                                     </div>
+                                    <div className="param-row">
+                                        <span className="label">写入文件:</span>
+                                        <Space style={{ flex: 1, minWidth: 0 }}>
+                                            <Button size="small" icon={<SaveOutlined />} onClick={handleSelectStreamFile} disabled={isProcessing}>
+                                                {streamFile ? '更换' : '选择文件'}
+                                            </Button>
+                                            {streamFile && (
+                                                <>
+                                                    <span title={streamFile.path} style={{ color: '#888', fontSize: 12 }}>
+                                                        {streamFile.path.split(/[\\/]/).pop()}
+                                                    </span>
+                                                    <Button
+                                                        size="small"
+                                                        type="text"
+                                                        icon={<DeleteOutlined />}
+                                                        onClick={() => setStreamFile(null)}
+                                                        disabled={isProcessing}
+                                                    />
+                                                </>
+                                            )}
+                                        </Space>
+                                    </div>
+                                    <div className="param-row">
+                                        <span className="label">导出到 Notion:</span>
+                                        <Switch checked={exportToNotion} onChange={setExportToNotion} />
+                                    </div>
+                                    <div className="param-row">
+                                        <span className="label">回答语言:</span>
+                                        <Select
+                                            style={{ flex: 1 }}
+                                            value={outputLanguage}
+                                            onChange={setOutputLanguage}
+                                            options={[{ value: '', label: '不指定' }, ...OUTPUT_LANGUAGES]}
+                                        />
+                                    </div>
                                     <div className="param-row">
                                         <span className="label">Max Tokens:</span>
                                         <Slider
@@ -373,12 +538,42 @@ export default function RecognitionPage() {
                                             {(result.durationMs / 1000).toFixed(2)}s
                                         </span>
                                     )}
+                                    {result.ttftMs != null && (
+                                        <span title="首 Token 时间和流式输出速度">
+                                            TTFT {(result.ttftMs / 1000).toFixed(2)}s
+                                            {result.tokensPerSecond != null && ` · ${result.tokensPerSecond.toFixed(1)} tokens/s`}
+                                        </span>
+                                    )}
                                     {result.tokensUsed && (
                                         <span>
                                             <ThunderboltOutlined style={{ marginRight: 4 }} />
                                             {result.tokensUsed} tokens
                                         </span>
                                     )}
+                                    {result.confidence && (
+                                        <span
+                                            title={[
+                                                result.confidence.method === 'logprobs' ? '根据 token 概率估计' : '模型自评',
+                                                ...result.confidence.sections.map((s) => `${s.section}: ${Math.round(s.score * 100)}%`)
+                                            ].join('\n')}
+                                            style={result.confidence.needsReview ? { color: '#fa8c16' } : undefined}
+                                        >
+                                            <SafetyCertificateOutlined style={{ marginRight: 4 }} />
+                                            置信度 {Math.round(result.confidence.score * 100)}%
+                                            {result.confidence.needsReview && ' · 待复核'}
+                                        </span>
+                                    )}
+                                    {result.glossaryCorrections && result.glossaryCorrections.length > 0 && (
+                                        <span title={result.glossaryCorrections.map((c) => `${c.term} → ${c.preferred} ×${c.count}`).join('\n')}>
+                                            词表修正 {result.glossaryCorrections.reduce((sum, c) => sum + c.count, 0)} 处
+                                        </span>
+                                    )}
+                                    {result.inference && (
+                                        <span title="本地模型的计算设备和推理耗时">
+                                            <DesktopOutlined style={{ marginRight: 4 }} />
+                                            {deviceLabels[result.inference.device]} · {result.inference.inferenceMs}ms
+                                        </span>
+                                    )}
                                 </Space>
                             )}
                         </div>
@@ -391,6 +586,20 @@ export default function RecognitionPage() {
                             >
                                 复制
                             </Button>
+                            <Button
+                                size="small"
+                                icon={<SoundOutlined />}
+                                onClick={handleSpeak}
+                                disabled={!result?.content}
+                            >
+                                朗读
+                            </Button>
+                            <Button
+                                size="small"
+                                icon={<StopOutlined />}
+                                onClick={() => api.speech.stop()}
+                                disabled={!result?.content}
+                            />
                             <Button
                                 size="small"
                                 icon={<DownloadOutlined />}
@@ -407,8 +616,20 @@ export default function RecognitionPage() {
                             >
                                 导出文本
                             </Button>
+                            <Button
+                                size="small"
+                                icon={<ShareAltOutlined />}
+                                onClick={handleShare}
+                                loading={sharing}
+                                disabled={!result?.content}
+                            >
+                                分享
+                            </Button>
                         </Space>
                     </div>
+                    {result?.warnings?.map((warning) => (
+                        <Alert key={warning} style={{ marginBottom: 12 }} type="warning" showIcon message={warning} />
+                    ))}
                     <ResultViewer viewMode={viewMode} />
                 </div>
             </div>
@@ -0,0 +1,68 @@
+import { useEffect, useState } from 'react'
+import { useParams } from 'react-router-dom'
+import ReactMarkdown from 'react-markdown'
+import remarkGfm from 'remark-gfm'
+import { Empty, Segmented, Spin, Typography } from 'antd'
+import dayjs from 'dayjs'
+import type { HistoryRecord } from '@shared/types'
+import type { ViewMode } from '../../components/ResultViewer'
+import { api } from '../../api'
+
+const { Text } = Typography
+
+// 独立窗口中显示单条识别结果，便于在其他工作时保持可见
+export default function ResultWindowPage() {
+    const { id } = useParams()
+    const [record, setRecord] = useState<HistoryRecord | null>(null)
+    const [loading, setLoading] = useState(true)
+    const [viewMode, setViewMode] = useState<ViewMode>('preview')
+
+    useEffect(() => {
+        api.history.getById(Number(id))
+            .then(setRecord)
+            .catch((error) => console.error('Failed to load result:', error))
+            .finally(() => setLoading(false))
+    }, [id])
+
+    if (loading) {
+        return (
+            <div style={{ display: 'flex', height: '100vh', alignItems: 'center', justifyContent: 'center' }}>
+                <Spin size="large" />
+            </div>
+        )
+    }
+
+    if (!record) {
+        return <Empty description="记录不存在" style={{ marginTop: 120 }} />
+    }
+
+    return (
+        <div style={{ padding: 16, height: '100vh', overflow: 'auto', boxSizing: 'border-box' }}>
+            <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center' }}>
+                <Text type="secondary">
+                    {record.configName} · {dayjs(record.createdAt).format('YYYY-MM-DD HH:mm')}
+                </Text>
+                <Segmented
+                    size="small"
+                    value={viewMode}
+                    onChange={(value) => setViewMode(value as ViewMode)}
+                    options={[
+                        { value: 'preview', label: '预览' },
+                        { value: 'source', label: '源码' }
+                    ]}
+                />
+            </div>
+            <div className="result-content" style={{ marginTop: 16 }}>
+                {viewMode === 'preview' ? (
+                    <ReactMarkdown remarkPlugins={[remarkGfm]}>
+                        {record.result}
+                    </ReactMarkdown>
+                ) : (
+                    <pre style={{ whiteSpace: 'pre-wrap', wordBreak: 'break-word', fontSize: 13 }}>
+                        {record.result}
+                    </pre>
+                )}
+            </div>
+        </div>
+    )
+}
@@ -14,31 +14,133 @@ import {
     Input,
     Popconfirm,
     Drawer,
-    Spin
+    Spin,
+    Alert,
+    Modal,
+    Image,
+    List
 } from 'antd'
 import {
     SaveOutlined,
     UndoOutlined,
     PlusOutlined,
     EditOutlined,
-    DeleteOutlined
+    DeleteOutlined,
+    PictureOutlined,
+    ArrowUpOutlined,
+    ArrowDownOutlined
 } from '@ant-design/icons'
 import type { ColumnsType } from 'antd/es/table'
-import type { AppSettings, PromptTemplate } from '@shared/types'
+import dayjs from 'dayjs'
+import type {
+    AppSettings,
+    GlossaryEntry,
+    PaginatedResult,
+    PostprocessRule,
+    PostprocessRuleInput,
+    RuleTestResult,
+    PromptTemplate,
+    PromptLintIssue,
+    Preset,
+    SelectedImage,
+    TemplateExample,
+    TemplateStats,
+    TemplateStatsParams,
+    UpdateInfo,
+    LockStatus,
+    SyncCredentialsState,
+    VoiceInfo,
+    InferenceStatus,
+    InferenceDevice
+} from '@shared/types'
+import { MAX_TEMPLATE_EXAMPLES, OUTPUT_LANGUAGES } from '@shared/types'
 
 import { useSettingsStore } from '../../store/settingsStore'
-import { api } from '../../api'
+import { useConfigStore, useRecognitionStore } from '../../store'
+import { api, AppError } from '../../api'
 
 const { Title, Text } = Typography
 
+// Extra headers are edited as "Name: value" lines
+const headersToText = (headers: Record<string, string>) =>
+    Object.entries(headers).map(([name, value]) => `${name}: ${value}`).join('\n')
+
+const textToHeaders = (text: string): Record<string, string> =>
+    Object.fromEntries(
+        text.split('\n')
+            .map(line => line.split(/:(.*)/s).map(part => part.trim()))
+            .filter(([name, value]) => name && value)
+            .map(([name, value]) => [name, value])
+    )
+
+// Provider concurrency limits are edited as "provider: limit" lines
+const concurrencyToText = (limits: Record<string, number>) =>
+    Object.entries(limits).map(([provider, limit]) => `${provider}: ${limit}`).join('\n')
+
+const textToConcurrency = (text: string): Record<string, number> =>
+    Object.fromEntries(
+        text.split('\n')
+            .map(line => line.split(':').map(part => part.trim()))
+            .filter(([provider, limit]) => provider && Number(limit) >= 1)
+            .map(([provider, limit]) => [provider, Math.floor(Number(limit))])
+    )
+
+const DEVICE_LABELS: Record<InferenceDevice, string> = {
+    cpu: 'CPU',
+    cuda: 'CUDA',
+    directml: 'DirectML',
+    metal: 'Core ML'
+}
+
+const deviceLabel = (device: InferenceDevice) => DEVICE_LABELS[device]
+
 export default function SettingsPage() {
     const settings = useSettingsStore()
+    const { activeConfigs, fetchActiveConfigs } = useConfigStore()
     const [templates, setTemplates] = useState<PromptTemplate[]>([])
     const [loading, setLoading] = useState(true)
     const [templateModalVisible, setTemplateModalVisible] = useState(false)
     const [editingTemplate, setEditingTemplate] = useState<PromptTemplate | null>(null)
+    const [lintIssues, setLintIssues] = useState<PromptLintIssue[]>([])
+    const [lintPresets, setLintPresets] = useState<Preset[]>([])
+    const { selectedConfigId } = useRecognitionStore()
+    const [exampleTemplate, setExampleTemplate] = useState<PromptTemplate | null>(null)
+    const [examples, setExamples] = useState<TemplateExample[]>([])
+    const [glossary, setGlossary] = useState<GlossaryEntry[]>([])
+    const [glossaryForm] = Form.useForm<{ term: string; preferred: string }>()
+    const [rules, setRules] = useState<PostprocessRule[]>([])
+    const [editingRule, setEditingRule] = useState<PostprocessRule | null>(null)
+    const [ruleModalVisible, setRuleModalVisible] = useState(false)
+    const [ruleSample, setRuleSample] = useState('')
+    const [ruleTest, setRuleTest] = useState<RuleTestResult | null>(null)
+    const [ruleForm] = Form.useForm<PostprocessRuleInput>()
+    const [exampleImages, setExampleImages] = useState<Record<number, string>>({})
+    const [newExampleImage, setNewExampleImage] = useState<SelectedImage | null>(null)
+    const [newExampleOutput, setNewExampleOutput] = useState('')
+    const [templateStats, setTemplateStats] = useState<PaginatedResult<TemplateStats> | null>(null)
+    const [statsParams, setStatsParams] = useState<TemplateStatsParams>({ page: 1, pageSize: 10, sort: 'lastUsed' })
+    const [update, setUpdate] = useState<UpdateInfo | null>(null)
+    const [updateStatus, setUpdateStatus] = useState<'idle' | 'checking' | 'downloading' | 'installing'>('idle')
+    const [updatePercent, setUpdatePercent] = useState(0)
+    const [lockStatus, setLockStatus] = useState<LockStatus | null>(null)
+    const [voices, setVoices] = useState<VoiceInfo[]>([])
+    const [inferenceStatus, setInferenceStatus] = useState<InferenceStatus | null>(null)
+    const [passcodeMode, setPasscodeMode] = useState<'set' | 'remove' | null>(null)
+    const [syncCredentials, setSyncCredentials] = useState<SyncCredentialsState | null>(null)
+    const [syncCredentialsVisible, setSyncCredentialsVisible] = useState(false)
+    const [syncing, setSyncing] = useState(false)
+    const [shareToken, setShareToken] = useState('')
+    const [hasShareToken, setHasShareToken] = useState(false)
+    const [smtpPassword, setSmtpPassword] = useState('')
+    const [hasSmtpPassword, setHasSmtpPassword] = useState(false)
+    const [notionToken, setNotionToken] = useState('')
+    const [hasNotionToken, setHasNotionToken] = useState(false)
     const [form] = Form.useForm()
     const [templateForm] = Form.useForm()
+    const [passcodeForm] = Form.useForm()
+    const [syncCredentialsForm] = Form.useForm()
+    const syncBackend: string | undefined = Form.useWatch('syncBackend', form)
+    const shareTarget: string | undefined = Form.useWatch('shareTarget', form)
 
     // Sync settings to form when they change
     useEffect(() => {
@@ -49,25 +151,121 @@ export default function SettingsPage() {
                 imageMaxSize: settings.imageMaxSize,
                 compressThreshold: settings.compressThreshold,
                 autoCompress: settings.autoCompress,
+                autoRotate: settings.autoRotate,
+                autoCrop: settings.autoCrop,
+                decodeCodes: settings.decodeCodes,
                 defaultTemperature: settings.defaultTemperature,
                 defaultTopP: settings.defaultTopP,
                 defaultMaxTokens: settings.defaultMaxTokens,
-                defaultStream: settings.defaultStream // Ensure this is synced
+                defaultStream: settings.defaultStream, // Ensure this is synced
+                outputLanguage: settings.outputLanguage,
+                userAgent: settings.userAgent,
+                extraHeaders: headersToText(settings.extraHeaders),
+                http2Enabled: settings.http2Enabled,
+                tcpKeepaliveSecs: settings.tcpKeepaliveSecs,
+                http2KeepAliveSecs: settings.http2KeepAliveSecs,
+                connectTimeoutSecs: settings.connectTimeoutSecs,
+                poolIdleTimeoutSecs: settings.poolIdleTimeoutSecs,
+                caBundlePath: settings.caBundlePath,
+                providerConcurrency: concurrencyToText(settings.providerConcurrency),
+                healthCheckMinutes: settings.healthCheckMinutes,
+                saveFailedRecognitions: settings.saveFailedRecognitions,
+                storeReasoning: settings.storeReasoning,
+                scrubHistoryPii: settings.scrubHistoryPii,
+                estimateConfidence: settings.estimateConfidence,
+                lowConfidenceThreshold: settings.lowConfidenceThreshold,
+                updateChannel: settings.updateChannel,
+                redactionConfigId: settings.redactionConfigId,
+                encryptHistory: settings.encryptHistory,
+                lockIdleMinutes: settings.lockIdleMinutes,
+                ttsVoice: settings.ttsVoice,
+                ttsRate: settings.ttsRate,
+                notebookPath: settings.notebookPath,
+                notebookThumbnails: settings.notebookThumbnails,
+                inferenceDevice: settings.inferenceDevice,
+                syncEnabled: settings.syncEnabled,
+                syncBackend: settings.syncBackend,
+                syncEndpoint: settings.syncEndpoint,
+                syncBucket: settings.syncBucket,
+                syncRegion: settings.syncRegion,
+                syncUsername: settings.syncUsername,
+                shareTarget: settings.shareTarget,
+                shareEndpoint: settings.shareEndpoint,
+                sharePublic: settings.sharePublic,
+                smtpHost: settings.smtpHost,
+                smtpPort: settings.smtpPort,
+                smtpSecurity: settings.smtpSecurity,
+                smtpUsername: settings.smtpUsername,
+                smtpFrom: settings.smtpFrom,
+                emailRecipients: settings.emailRecipients,
+                notionDatabaseId: settings.notionDatabaseId
             })
         }
     }, [settings, form])
 
     useEffect(() => {
         loadData()
+        fetchActiveConfigs()
+        api.lock.getStatus().then(setLockStatus).catch(() => setLockStatus(null))
+        api.sync.getCredentialsState().then(setSyncCredentials).catch(() => setSyncCredentials(null))
+        api.share.hasToken().then(setHasShareToken).catch(() => setHasShareToken(false))
+        api.mail.hasPassword().then(setHasSmtpPassword).catch(() => setHasSmtpPassword(false))
+        api.notion.hasToken().then(setHasNotionToken).catch(() => setHasNotionToken(false))
+        // 没有可用语音引擎时只能使用默认语音
+        api.speech.getVoices().then(setVoices).catch(() => setVoices([]))
+        api.settings.getInferenceStatus().then(setInferenceStatus).catch(() => setInferenceStatus(null))
     }, [])
 
+    useEffect(() => {
+        loadTemplateStats()
+    }, [statsParams])
+
+    const loadTemplateStats = async () => {
+        try {
+            setTemplateStats(await api.template.getStats(statsParams))
+        } catch (error) {
+            console.error('Failed to load template stats:', error)
+        }
+    }
+
+    // Lint the template content while editing
+    const templateContent: string | undefined = Form.useWatch('content', templateForm)
+    useEffect(() => {
+        if (!templateModalVisible) return
+        api.preset.getAll()
+            .then(setLintPresets)
+            .catch(() => setLintPresets([]))
+    }, [templateModalVisible])
+
+    // 按使用该模板的预设（没有时为默认预设）的配置和 JSON 模式检查，没有预设时按识别页所选配置
+    const lintPreset = lintPresets.find(p => editingTemplate && p.templateId === editingTemplate.id)
+        ?? lintPresets.find(p => p.isDefault)
+    const lintConfigId = lintPreset?.configId ?? selectedConfigId ?? undefined
+    const lintJsonMode = lintPreset?.options.jsonMode
+    useEffect(() => {
+        if (!templateModalVisible || !templateContent) {
+            setLintIssues([])
+            return
+        }
+        const timer = setTimeout(() => {
+            api.template.lint(templateContent, lintConfigId, lintJsonMode)
+                .then(setLintIssues)
+                .catch(() => setLintIssues([]))
+        }, 400)
+        return () => clearTimeout(timer)
+    }, [templateContent, templateModalVisible, lintConfigId, lintJsonMode])
+
     const loadData = async () => {
         setLoading(true)
         try {
-            const [templatesData] = await Promise.all([
-                api.template.getAll()
+            const [templatesData, glossaryData, rulesData] = await Promise.all([
+                api.template.getAll(),
+                api.glossary.getAll(),
+                api.postprocess.getRules()
             ])
             setTemplates(templatesData)
+            setGlossary(glossaryData)
+            setRules(rulesData)
             // Settings are loaded by App.tsx via store
         } catch (error) {
             console.error('Failed to load data:', error)
@@ -77,9 +275,108 @@ export default function SettingsPage() {
         }
     }
 
-    const handleSaveSettings = async (values: Partial<AppSettings>) => {
-        await settings.updateSettings(values)
-        message.success('设置已保存')
+    const handleSaveSettings = async (
+        values: Omit<Partial<AppSettings>, 'extraHeaders' | 'providerConcurrency'> & {
+            extraHeaders?: string
+            providerConcurrency?: string
+        }
+    ) => {
+        // Existing records are converted together with the setting
+        const { encryptHistory, ...rest } = values
+        if (encryptHistory !== undefined && encryptHistory !== settings.encryptHistory) {
+            const converted = await api.history.setEncryption(encryptHistory)
+            message.info(`已${encryptHistory ? '加密' : '解密'} ${converted} 条历史记录`)
+        }
+        try {
+            await settings.updateSettings({
+                ...rest,
+                extraHeaders: textToHeaders(values.extraHeaders ?? ''),
+                providerConcurrency: textToConcurrency(values.providerConcurrency ?? ''),
+                // Cleared selects come back undefined, send null so the setting is removed
+                redactionConfigId: values.redactionConfigId ?? null
+            })
+            message.success('设置已保存')
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSavePasscode = async () => {
+        const values = await passcodeForm.validateFields()
+        try {
+            const status = await api.lock.setPasscode(
+                values.current || null,
+                passcodeMode === 'set' ? values.passcode : null
+            )
+            setLockStatus(status)
+            setPasscodeMode(null)
+            passcodeForm.resetFields()
+            message.success(status.enabled ? '密码已保存' : '密码已移除')
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSaveSyncCredentials = async () => {
+        const values = await syncCredentialsForm.validateFields()
+        try {
+            // 留空的项保持原值
+            await api.sync.setCredentials(values.secret || undefined, values.passphrase || undefined)
+            setSyncCredentials(await api.sync.getCredentialsState())
+            setSyncCredentialsVisible(false)
+            syncCredentialsForm.resetFields()
+            message.success('同步凭据已保存')
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSaveShareToken = async () => {
+        try {
+            await api.share.setToken(shareToken)
+            setHasShareToken(shareToken.trim() !== '')
+            setShareToken('')
+            message.success(shareToken.trim() ? 'Token 已保存' : 'Token 已删除')
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSaveSmtpPassword = async () => {
+        try {
+            await api.mail.setPassword(smtpPassword)
+            setHasSmtpPassword(smtpPassword !== '')
+            setSmtpPassword('')
+            message.success(smtpPassword ? '密码已保存' : '密码已删除')
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSaveNotionToken = async () => {
+        try {
+            await api.notion.setToken(notionToken)
+            setHasNotionToken(notionToken.trim() !== '')
+            setNotionToken('')
+            message.success(notionToken.trim() ? 'Token 已保存' : 'Token 已删除')
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSyncNow = async () => {
+        setSyncing(true)
+        try {
+            const report = await api.sync.now()
+            message.success(
+                `同步完成：配置 ${report.configs} 个，模板 ${report.templates} 个，历史记录 ${report.history} 条，删除 ${report.deleted} 项`
+            )
+            loadData()
+        } catch (error) {
+            message.error(String(error))
+        } finally {
+            setSyncing(false)
+        }
     }
 
     const handleResetSettings = async () => {
@@ -87,6 +384,41 @@ export default function SettingsPage() {
         message.success('设置已重置为默认值')
     }
 
+    const handleCheckUpdate = async () => {
+        setUpdateStatus('checking')
+        try {
+            const info = await api.updater.check()
+            setUpdate(info)
+            if (!info) {
+                message.success('已是最新版本')
+            }
+        } catch (error) {
+            message.error(String(error))
+        } finally {
+            setUpdateStatus('idle')
+        }
+    }
+
+    const handleInstallUpdate = async () => {
+        setUpdateStatus('downloading')
+        setUpdatePercent(0)
+        const unlisten = await api.updater.onProgress(({ downloaded, total }) => {
+            if (total) {
+                setUpdatePercent(Math.round(downloaded / total * 100))
+            }
+        })
+        try {
+            await api.updater.download()
+            setUpdateStatus('installing')
+            await api.updater.install()
+        } catch (error) {
+            message.error(String(error))
+            setUpdateStatus('idle')
+        } finally {
+            unlisten()
+        }
+    }
+
     const handleAddTemplate = () => {
         setEditingTemplate(null)
         templateForm.resetFields()
@@ -101,6 +433,7 @@ export default function SettingsPage() {
 
     const handleDeleteTemplate = async (id: number) => {
         await api.template.delete(id)
+        loadTemplateStats()
         message.success('模板已删除')
         loadData()
     }
@@ -110,13 +443,268 @@ export default function SettingsPage() {
             await api.template.update(editingTemplate.id, values)
             message.success('模板已更新')
         } else {
-            await api.template.create(values.name, values.content, values.isDefault)
+            try {
+                await api.template.create(values.name, values.content, values.isDefault)
+            } catch (error) {
+                if (!(error instanceof AppError && error.code?.code === 'duplicate_template')) {
+                    throw error
+                }
+                const confirmed = await new Promise<boolean>((resolve) => {
+                    Modal.confirm({
+                        title: '模板内容重复',
+                        content: `${error.message}，仍然创建？`,
+                        okText: '仍然创建',
+                        onOk: () => resolve(true),
+                        onCancel: () => resolve(false)
+                    })
+                })
+                if (!confirmed) {
+                    return
+                }
+                await api.template.create(values.name, values.content, values.isDefault, undefined, true)
+            }
             message.success('模板已创建')
         }
         setTemplateModalVisible(false)
         loadData()
     }
 
+    const handleDedupeTemplates = async () => {
+        try {
+            const merges = await api.template.dedupe()
+            const removed = merges.reduce((sum, merge) => sum + merge.removedIds.length, 0)
+            message.success(removed > 0 ? `已合并 ${removed} 个重复模板` : '没有重复的模板')
+            loadData()
+            loadTemplateStats()
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const loadExamples = async (templateId: number) => {
+        const list = await api.template.examples(templateId)
+        setExamples(list)
+        const images: Record<number, string> = {}
+        await Promise.all(list.map(async (example) => {
+            const image = await api.template.exampleImage(example.id).catch(() => null)
+            if (image) {
+                images[example.id] = `data:${image.mimeType};base64,${image.base64}`
+            }
+        }))
+        setExampleImages(images)
+    }
+
+    const handleOpenExamples = (template: PromptTemplate) => {
+        setExampleTemplate(template)
+        setExamples([])
+        setExampleImages({})
+        setNewExampleImage(null)
+        setNewExampleOutput('')
+        loadExamples(template.id).catch((error) => message.error(String(error)))
+    }
+
+    const handleSelectExampleImage = async () => {
+        try {
+            const image = await api.dialog.selectImage()
+            if (image) {
+                setNewExampleImage(image)
+            }
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleAddExample = async () => {
+        if (!exampleTemplate || !newExampleImage) return
+        try {
+            await api.template.addExample(
+                exampleTemplate.id,
+                newExampleImage.base64,
+                newExampleImage.mimeType,
+                newExampleOutput
+            )
+            setNewExampleImage(null)
+            setNewExampleOutput('')
+            await loadExamples(exampleTemplate.id)
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleUpdateExample = async (example: TemplateExample, output: string) => {
+        if (output === example.output) return
+        try {
+            await api.template.updateExample(example.id, output)
+            setExamples((list) => list.map((item) => item.id === example.id ? { ...item, output } : item))
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleDeleteExample = async (id: number) => {
+        try {
+            await api.template.deleteExample(id)
+            setExamples((list) => list.filter((item) => item.id !== id))
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleSaveGlossaryEntry = async (values: { term: string; preferred: string }) => {
+        try {
+            const entry = await api.glossary.save(values.term, values.preferred)
+            setGlossary((list) => [...list.filter((item) => item.id !== entry.id), entry]
+                .sort((a, b) => a.term.localeCompare(b.term)))
+            glossaryForm.resetFields()
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleDeleteGlossaryEntry = async (id: number) => {
+        try {
+            await api.glossary.delete(id)
+            setGlossary((list) => list.filter((item) => item.id !== id))
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const glossaryColumns: ColumnsType<GlossaryEntry> = [
+        {
+            title: '识别结果中的写法',
+            dataIndex: 'term',
+            key: 'term'
+        },
+        {
+            title: '正确写法',
+            dataIndex: 'preferred',
+            key: 'preferred'
+        },
+        {
+            title: '操作',
+            key: 'action',
+            width: 80,
+            render: (_, record) => (
+                <Popconfirm title="确定要删除此词条吗？" onConfirm={() => handleDeleteGlossaryEntry(record.id)}>
+                    <Button type="link" size="small" danger icon={<DeleteOutlined />} />
+                </Popconfirm>
+            )
+        }
+    ]
+
+    const handleOpenRule = (rule: PostprocessRule | null) => {
+        setEditingRule(rule)
+        setRuleTest(null)
+        ruleForm.setFieldsValue(rule ?? { name: '', pattern: '', replacement: '', enabled: true })
+        setRuleModalVisible(true)
+    }
+
+    const handleSaveRule = async (values: PostprocessRuleInput) => {
+        try {
+            const input = { ...values, replacement: values.replacement ?? '' }
+            if (editingRule) {
+                await api.postprocess.update(editingRule.id, input)
+            } else {
+                await api.postprocess.create(input)
+            }
+            setRuleModalVisible(false)
+            setRules(await api.postprocess.getRules())
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleTestRule = async () => {
+        const { pattern, replacement } = ruleForm.getFieldsValue()
+        try {
+            setRuleTest(await api.postprocess.test(pattern ?? '', replacement ?? '', ruleSample))
+        } catch (error) {
+            setRuleTest(null)
+            message.error(String(error))
+        }
+    }
+
+    const handleToggleRule = async (rule: PostprocessRule, enabled: boolean) => {
+        try {
+            const { name, pattern, replacement } = rule
+            const updated = await api.postprocess.update(rule.id, { name, pattern, replacement, enabled })
+            if (updated) {
+                setRules((list) => list.map((item) => item.id === updated.id ? updated : item))
+            }
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const handleMoveRule = async (index: number, offset: number) => {
+        const reordered = [...rules]
+        const [moved] = reordered.splice(index, 1)
+        reordered.splice(index + offset, 0, moved)
+        setRules(reordered)
+        try {
+            await api.postprocess.reorder(reordered.map((rule) => rule.id))
+        } catch (error) {
+            message.error(String(error))
+            setRules(await api.postprocess.getRules())
+        }
+    }
+
+    const handleDeleteRule = async (id: number) => {
+        try {
+            await api.postprocess.delete(id)
+            setRules((list) => list.filter((item) => item.id !== id))
+        } catch (error) {
+            message.error(String(error))
+        }
+    }
+
+    const ruleColumns: ColumnsType<PostprocessRule> = [
+        {
+            title: '名称',
+            dataIndex: 'name',
+            key: 'name'
+        },
+        {
+            title: '正则',
+            dataIndex: 'pattern',
+            key: 'pattern',
+            ellipsis: true,
+            render: (pattern: string) => <Text code>{pattern}</Text>
+        },
+        {
+            title: '替换为',
+            dataIndex: 'replacement',
+            key: 'replacement',
+            ellipsis: true,
+            render: (replacement: string) => replacement ? <Text code>{replacement}</Text> : <Text type="secondary">（删除）</Text>
+        },
+        {
+            title: '启用',
+            dataIndex: 'enabled',
+            key: 'enabled',
+            width: 70,
+            render: (enabled: boolean, record) => (
+                <Switch size="small" checked={enabled} onChange={(checked) => handleToggleRule(record, checked)} />
+            )
+        },
+        {
+            title: '操作',
+            key: 'action',
+            width: 160,
+            render: (_, record, index) => (
+                <Space size={0}>
+                    <Button type="link" size="small" icon={<ArrowUpOutlined />} disabled={index === 0} onClick={() => handleMoveRule(index, -1)} />
+                    <Button type="link" size="small" icon={<ArrowDownOutlined />} disabled={index === rules.length - 1} onClick={() => handleMoveRule(index, 1)} />
+                    <Button type="link" size="small" icon={<EditOutlined />} onClick={() => handleOpenRule(record)} />
+                    <Popconfirm title="确定要删除此规则吗？" onConfirm={() => handleDeleteRule(record.id)}>
+                        <Button type="link" size="small" danger icon={<DeleteOutlined />} />
+                    </Popconfirm>
+                </Space>
+            )
+        }
+    ]
+
     const templateColumns: ColumnsType<PromptTemplate> = [
         {
             title: '名称',
@@ -145,7 +733,7 @@ export default function SettingsPage() {
         {
             title: '操作',
             key: 'actions',
-            width: 120,
+            width: 150,
             render: (_, record) => (
                 <Space>
                     <Button
@@ -153,6 +741,12 @@ export default function SettingsPage() {
                         size="small"
                         onClick={() => handleEditTemplate(record)}
                     />
+                    <Button
+                        icon={<PictureOutlined />}
+                        size="small"
+                        title="示例"
+                        onClick={() => handleOpenExamples(record)}
+                    />
                     <Popconfirm
                         title="确定要删除此模板吗？"
                         onConfirm={() => handleDeleteTemplate(record.id)}
@@ -168,6 +762,34 @@ export default function SettingsPage() {
         }
     ]
 
+    const statsColumns: ColumnsType<TemplateStats> = [
+        {
+            title: '模板',
+            dataIndex: 'templateName',
+            key: 'templateName'
+        },
+        {
+            title: '识别次数',
+            dataIndex: 'runCount',
+            key: 'runCount',
+            width: 100
+        },
+        {
+            title: '成功率',
+            dataIndex: 'successRate',
+            key: 'successRate',
+            width: 100,
+            render: (rate?: number) => rate == null ? '-' : `${(rate * 100).toFixed(0)}%`
+        },
+        {
+            title: '最近使用',
+            dataIndex: 'lastUsedAt',
+            key: 'lastUsedAt',
+            width: 160,
+            render: (time?: string) => time ? dayjs(time).format('YYYY-MM-DD HH:mm') : '从未使用'
+        }
+    ]
+
     if (loading) {
         return (
             <div style={{ display: 'flex', justifyContent: 'center', alignItems: 'center', height: '100%', padding: 50 }}>
@@ -191,6 +813,21 @@ export default function SettingsPage() {
                         wrapperCol={{ span: 18 }}
                         onFinish={handleSaveSettings}
                     >
+                        <Form.Item
+                            name="language"
+                            label="语言"
+                            tooltip="同时用于后端返回的错误信息"
+                        >
+                            <Select
+                                style={{ width: 160 }}
+                                options={[
+                                    { value: 'zh-CN', label: '简体中文' },
+                                    { value: 'en-US', label: 'English' },
+                                    { value: 'ja-JP', label: '日本語' }
+                                ]}
+                            />
+                        </Form.Item>
+
                         <Form.Item
                             name="imageMaxSize"
                             label="图片最大尺寸 (MB)"
@@ -214,8 +851,173 @@ export default function SettingsPage() {
                             <Switch />
                         </Form.Item>
 
+                        <Form.Item
+                            name="autoRotate"
+                            label="自动纠正方向"
+                            tooltip="识别前先用缩略图检测图片方向，横置或倒置的扫描件会自动旋转（额外消耗少量 Token）"
+                            valuePropName="checked"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="autoCrop"
+                            label="自动裁剪边距"
+                            tooltip="上传前裁掉截图四周的纯色边距和黑边，减少 Token 消耗并让模型聚焦内容"
+                            valuePropName="checked"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="decodeCodes"
+                            label="识别二维码/条形码"
+                            tooltip="识别时同时在本地读取图片中的二维码和条形码，比模型识别更可靠"
+                            valuePropName="checked"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="inferenceDevice"
+                            label="本地模型计算设备"
+                            tooltip="本地 OCR 引擎运行模型的设备；所选 GPU 不可用时自动回退到 CPU，识别结果中会显示实际使用的设备"
+                            extra={inferenceStatus && (
+                                `可用设备：${inferenceStatus.available.map(deviceLabel).join('、')}；` +
+                                (inferenceStatus.inUse ? `当前使用：${deviceLabel(inferenceStatus.inUse)}` : '模型尚未加载')
+                            )}
+                        >
+                            <Select
+                                options={[
+                                    { value: 'auto', label: '自动（优先 GPU）' },
+                                    { value: 'cpu', label: 'CPU' },
+                                    { value: 'cuda', label: 'CUDA（NVIDIA）' },
+                                    { value: 'directml', label: 'DirectML（Windows）' },
+                                    { value: 'metal', label: 'Metal / Core ML（macOS）' }
+                                ]}
+                            />
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item name="ttsVoice" label="朗读语音">
+                            <Select
+                                showSearch
+                                optionFilterProp="label"
+                                options={[
+                                    { value: '', label: '系统默认' },
+                                    ...voices.map(voice => ({
+                                        value: voice.id,
+                                        label: voice.language ? `${voice.name} (${voice.language})` : voice.name
+                                    }))
+                                ]}
+                            />
+                        </Form.Item>
+
+                        <Form.Item name="ttsRate" label="朗读语速" tooltip="1 为正常语速">
+                            <InputNumber min={0.5} max={2} step={0.1} />
+                        </Form.Item>
+
                         <Divider />
 
+                        <Form.Item
+                            name="notebookPath"
+                            label="笔记文件"
+                            tooltip="在历史记录中可将识别结果追加到该 Markdown 文件，适合在 Obsidian、Logseq 中收集摘录"
+                        >
+                            <Input placeholder="例如 D:\Notes\识别摘录.md" />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="notebookThumbnails"
+                            label="附带缩略图"
+                            tooltip="缩略图保存在笔记文件旁的 attachments 文件夹中"
+                            valuePropName="checked"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item
+                            name="saveFailedRecognitions"
+                            label="记录失败的识别"
+                            valuePropName="checked"
+                            tooltip="失败的识别也保存到历史记录，便于重试和分析"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="storeReasoning"
+                            label="保存推理过程"
+                            valuePropName="checked"
+                            tooltip="推理模型的思考过程不会出现在识别结果中，开启后随历史记录一起保存，便于排查识别错误"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="scrubHistoryPii"
+                            label="历史记录脱敏"
+                            valuePropName="checked"
+                            tooltip="保存到历史记录的结果中遮盖邮箱、手机号、身份证号和银行卡号，仅当前识别结果保留完整文本；原图仍会保存"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="estimateConfidence"
+                            label="估计置信度"
+                            valuePropName="checked"
+                            tooltip="OpenAI 兼容接口的非流式识别使用 token 概率；其他情况会再请求一次让模型为转写自评，额外消耗 token"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="lowConfidenceThreshold"
+                            label="待复核阈值"
+                            tooltip="置信度低于此值的识别在历史记录中标记为待复核"
+                        >
+                            <InputNumber min={0} max={1} step={0.05} style={{ width: 120 }} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="encryptHistory"
+                            label="加密历史记录"
+                            valuePropName="checked"
+                            tooltip="加密保存历史记录中的提示词和识别结果，适合在共用电脑上识别机密文件；切换时会转换已有记录"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item label="应用密码" tooltip="设置后，启动和闲置超时后需输入密码才能使用">
+                            <Space>
+                                <Button onClick={() => setPasscodeMode('set')}>
+                                    {lockStatus?.enabled ? '修改密码' : '设置密码'}
+                                </Button>
+                                {lockStatus?.enabled && (
+                                    <>
+                                        <Button danger onClick={() => setPasscodeMode('remove')}>
+                                            移除密码
+                                        </Button>
+                                        <Button onClick={() => api.lock.lock().then(() => window.location.reload())}>
+                                            立即锁定
+                                        </Button>
+                                    </>
+                                )}
+                            </Space>
+                        </Form.Item>
+
+                        <Form.Item
+                            name="lockIdleMinutes"
+                            label="闲置锁定"
+                            tooltip="设置密码后，闲置超过该时间自动锁定，0 表示只在启动时锁定"
+                        >
+                            <InputNumber min={0} max={1440} addonAfter="分钟" />
+                        </Form.Item>
+
                         <Form.Item
                             name="defaultTemperature"
                             label="默认 Temperature"
@@ -230,6 +1032,295 @@ export default function SettingsPage() {
                             <InputNumber min={0} max={1} step={0.1} />
                         </Form.Item>
 
+                        <Form.Item
+                            name="outputLanguage"
+                            label="回答语言"
+                            tooltip="在提示词末尾要求模型使用该语言回答，所有模板通用；识别时可单独指定"
+                        >
+                            <Select
+                                options={[{ value: '', label: '不指定（由提示词决定）' }, ...OUTPUT_LANGUAGES]}
+                            />
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item
+                            name="userAgent"
+                            label="User-Agent"
+                            tooltip="部分企业网关要求特定的 User-Agent 才能放行，留空使用默认值"
+                        >
+                            <Input placeholder="默认: image-recognition-app/1.0.0" />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="extraHeaders"
+                            label="全局请求头"
+                            tooltip="附加到所有供应商请求的请求头，每行一个，格式为 Name: value"
+                        >
+                            <Input.TextArea rows={3} placeholder="X-Client-Id: my-desktop" />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="http2Enabled"
+                            label="启用 HTTP/2"
+                            valuePropName="checked"
+                            tooltip="部分代理会中断 HTTP/2 流式响应，关闭后强制使用 HTTP/1.1"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="http2KeepAliveSecs"
+                            label="HTTP/2 心跳间隔 (秒)"
+                            tooltip="流式响应期间定期发送 HTTP/2 PING，避免代理静默断开长时间无数据的连接；0 表示关闭"
+                        >
+                            <InputNumber min={0} max={3600} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="connectTimeoutSecs"
+                            label="连接超时 (秒)"
+                            tooltip="建立连接（含 TLS 握手）的最长等待时间；0 表示只受整个请求的超时限制"
+                        >
+                            <InputNumber min={0} max={300} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="tcpKeepaliveSecs"
+                            label="TCP Keepalive (秒)"
+                            tooltip="0 表示关闭"
+                        >
+                            <InputNumber min={0} max={3600} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="poolIdleTimeoutSecs"
+                            label="空闲连接保留 (秒)"
+                            tooltip="0 表示不复用连接"
+                        >
+                            <InputNumber min={0} max={3600} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="caBundlePath"
+                            label="CA 证书路径"
+                            tooltip="企业内部网关使用自签名或内部 CA 签发的证书时，填写 PEM 格式的根证书文件路径；留空仅信任系统证书"
+                        >
+                            <Input placeholder="/etc/ssl/corp-ca.pem" />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="providerConcurrency"
+                            label="供应商并发数"
+                            tooltip="同一账号（API 地址和 Key 相同的配置）同时发出的请求数，超出的请求排队等待；按供应商类型设置，每行一个，格式为 供应商: 数量，未列出的供应商默认 4"
+                        >
+                            <Input.TextArea rows={3} placeholder={'openai: 4\nollama: 1'} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="healthCheckMinutes"
+                            label="健康检查间隔 (分钟)"
+                            tooltip="定期向启用的配置发送测试请求，在模型配置页标记响应慢或不可用的配置；每次检查都会产生计费请求，0（默认）表示关闭"
+                        >
+                            <InputNumber min={0} max={1440} />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="redactionConfigId"
+                            label="脱敏识别配置"
+                            tooltip="开启了上传前脱敏的配置，会先用这里选择的本地或内网模型识别文字并遮盖敏感信息；未选择时这些配置无法识别"
+                        >
+                            <Select
+                                allowClear
+                                placeholder="未选择"
+                                options={activeConfigs.map(c => ({
+                                    value: c.id,
+                                    label: `${c.name} (${c.apiUrl})`
+                                }))}
+                            />
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item
+                            name="syncEnabled"
+                            label="启动时同步"
+                            valuePropName="checked"
+                            tooltip="启动时将配置、提示词模板和历史记录与自己的 WebDAV 或 S3 存储同步，数据上传前会用同步密码加密"
+                        >
+                            <Switch />
+                        </Form.Item>
+
+                        <Form.Item name="syncBackend" label="同步存储">
+                            <Select
+                                options={[
+                                    { value: 'webdav', label: 'WebDAV' },
+                                    { value: 's3', label: 'S3 兼容存储' }
+                                ]}
+                            />
+                        </Form.Item>
+
+                        <Form.Item
+                            name="syncEndpoint"
+                            label="服务地址"
+                            tooltip={syncBackend === 's3' ? '例如 https://s3.us-east-1.amazonaws.com' : '同步文件所在的 WebDAV 目录'}
+                        >
+                            <Input placeholder="https://" />
+                        </Form.Item>
+
+                        {syncBackend === 's3' && (
+                            <>
+                                <Form.Item name="syncBucket" label="存储桶">
+                                    <Input />
+                                </Form.Item>
+
+                                <Form.Item name="syncRegion" label="区域">
+                                    <Input placeholder="us-east-1" />
+                                </Form.Item>
+                            </>
+                        )}
+
+                        <Form.Item name="syncUsername" label={syncBackend === 's3' ? 'Access Key' : '用户名'}>
+                            <Input />
+                        </Form.Item>
+
+                        <Form.Item label="同步凭据" tooltip="修改存储设置后请先保存设置再同步">
+                            <Space>
+                                <Button onClick={() => setSyncCredentialsVisible(true)}>
+                                    {syncCredentials?.hasSecret && syncCredentials?.hasPassphrase ? '修改凭据' : '设置凭据'}
+                                </Button>
+                                <Button
+                                    loading={syncing}
+                                    disabled={!syncCredentials?.hasPassphrase}
+                                    onClick={handleSyncNow}
+                                >
+                                    立即同步
+                                </Button>
+                            </Space>
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item name="shareTarget" label="分享方式" tooltip="在识别结果中点击分享后上传，链接会复制到剪贴板">
+                            <Select
+                                options={[
+                                    { value: 'gist', label: 'GitHub Gist' },
+                                    { value: 'custom', label: '自定义地址' }
+                                ]}
+                            />
+                        </Form.Item>
+
+                        {shareTarget === 'custom' ? (
+                            <Form.Item
+                                name="shareEndpoint"
+                                label="分享地址"
+                                tooltip="以 POST 请求体发送文本，服务返回链接（纯文本，或 JSON 中的 url / link 字段）"
+                            >
+                                <Input placeholder="https://" />
+                            </Form.Item>
+                        ) : (
+                            <Form.Item name="sharePublic" label="公开 Gist" valuePropName="checked" tooltip="关闭时创建仅凭链接可见的 secret Gist">
+                                <Switch />
+                            </Form.Item>
+                        )}
+
+                        <Form.Item
+                            label="分享 Token"
+                            tooltip={shareTarget === 'custom' ? '可选，作为 Bearer Token 发送' : '需要 gist 权限的 GitHub Token'}
+                        >
+                            <Space>
+                                <Input.Password
+                                    value={shareToken}
+                                    onChange={(e) => setShareToken(e.target.value)}
+                                    placeholder={hasShareToken ? '已保存，留空并保存可删除' : '未设置'}
+                                />
+                                <Button onClick={handleSaveShareToken}>保存 Token</Button>
+                            </Space>
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item name="smtpHost" label="SMTP 服务器" tooltip="在历史记录中发送邮件时使用，附带识别结果和原图">
+                            <Input placeholder="smtp.example.com" />
+                        </Form.Item>
+
+                        <Form.Item label="端口与加密">
+                            <Space>
+                                <Form.Item name="smtpPort" noStyle>
+                                    <InputNumber min={1} max={65535} />
+                                </Form.Item>
+                                <Form.Item name="smtpSecurity" noStyle>
+                                    <Select
+                                        style={{ width: 140 }}
+                                        options={[
+                                            { value: 'starttls', label: 'STARTTLS' },
+                                            { value: 'tls', label: 'SSL/TLS' },
+                                            { value: 'none', label: '不加密' }
+                                        ]}
+                                    />
+                                </Form.Item>
+                            </Space>
+                        </Form.Item>
+
+                        <Form.Item name="smtpUsername" label="SMTP 用户名">
+                            <Input />
+                        </Form.Item>
+
+                        <Form.Item label="SMTP 密码">
+                            <Space>
+                                <Input.Password
+                                    value={smtpPassword}
+                                    onChange={(e) => setSmtpPassword(e.target.value)}
+                                    placeholder={hasSmtpPassword ? '已保存，留空并保存可删除' : '未设置'}
+                                />
+                                <Button onClick={handleSaveSmtpPassword}>保存密码</Button>
+                            </Space>
+                        </Form.Item>
+
+                        <Form.Item name="smtpFrom" label="发件人" tooltip="可写作“名称 <地址>”，留空时使用用户名">
+                            <Input placeholder="OCR <ocr@example.com>" />
+                        </Form.Item>
+
+                        <Form.Item name="emailRecipients" label="默认收件人" tooltip="多个地址以逗号分隔">
+                            <Input placeholder="case-files@example.com" />
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item
+                            name="notionDatabaseId"
+                            label="Notion 数据库 ID"
+                            tooltip="数据库链接中的 32 位 ID；需先在 Notion 中将数据库共享给该集成"
+                        >
+                            <Input />
+                        </Form.Item>
+
+                        <Form.Item label="Notion Token" tooltip="Notion 内部集成（Internal Integration）的 Token">
+                            <Space>
+                                <Input.Password
+                                    value={notionToken}
+                                    onChange={(e) => setNotionToken(e.target.value)}
+                                    placeholder={hasNotionToken ? '已保存，留空并保存可删除' : '未设置'}
+                                />
+                                <Button onClick={handleSaveNotionToken}>保存 Token</Button>
+                            </Space>
+                        </Form.Item>
+
+                        <Divider />
+
+                        <Form.Item
+                            name="updateChannel"
+                            label="更新渠道"
+                            tooltip="测试版可提前体验新功能，但可能不够稳定"
+                        >
+                            <Select
+                                options={[
+                                    { value: 'stable', label: '正式版' },
+                                    { value: 'beta', label: '测试版' }
+                                ]}
+                            />
+                        </Form.Item>
+
                         <Form.Item wrapperCol={{ offset: 6 }}>
                             <Space>
                                 <Button type="primary" htmlType="submit" icon={<SaveOutlined />}>
@@ -258,6 +1349,12 @@ export default function SettingsPage() {
                     >
                         添加模板
                     </Button>
+                    <Popconfirm
+                        title="合并内容相同的模板？使用记录会并入保留的模板"
+                        onConfirm={handleDedupeTemplates}
+                    >
+                        <Button style={{ marginBottom: 16, marginLeft: 8 }}>合并重复模板</Button>
+                    </Popconfirm>
 
                     <Table
                         columns={templateColumns}
@@ -267,12 +1364,123 @@ export default function SettingsPage() {
                     />
                 </Card>
 
+                {/* 词表 */}
+                <Card title="词表校正" style={{ marginTop: 16 }}>
+                    <Text type="secondary" style={{ display: 'block', marginBottom: 16 }}>
+                        识别完成后，在保存和返回结果前把这些写法替换为正确写法（不区分大小写，英文按整词匹配）
+                    </Text>
+                    <Form form={glossaryForm} layout="inline" onFinish={handleSaveGlossaryEntry} style={{ marginBottom: 16 }}>
+                        <Form.Item name="term" rules={[{ required: true, whitespace: true, message: '请输入识别结果中的写法' }]}>
+                            <Input placeholder="识别结果中的写法，如 Open AI" />
+                        </Form.Item>
+                        <Form.Item name="preferred" rules={[{ required: true, whitespace: true, message: '请输入正确写法' }]}>
+                            <Input placeholder="正确写法，如 OpenAI" />
+                        </Form.Item>
+                        <Form.Item>
+                            <Button type="primary" htmlType="submit" icon={<PlusOutlined />}>
+                                添加
+                            </Button>
+                        </Form.Item>
+                    </Form>
+                    <Table
+                        columns={glossaryColumns}
+                        dataSource={glossary}
+                        rowKey="id"
+                        size="small"
+                        pagination={false}
+                    />
+                </Card>
+
+                {/* 后处理规则 */}
+                <Card title="后处理规则" style={{ marginTop: 16 }}>
+                    <Text type="secondary" style={{ display: 'block', marginBottom: 16 }}>
+                        识别成功后按顺序对结果执行正则查找替换，如统一电话号码格式、去掉水印文字
+                    </Text>
+                    <Button
+                        type="primary"
+                        icon={<PlusOutlined />}
+                        onClick={() => handleOpenRule(null)}
+                        style={{ marginBottom: 16 }}
+                    >
+                        添加规则
+                    </Button>
+                    <Table
+                        columns={ruleColumns}
+                        dataSource={rules}
+                        rowKey="id"
+                        size="small"
+                        pagination={false}
+                    />
+                </Card>
+
+                {/* 模板使用统计 */}
+                <Card title="模板使用统计" style={{ marginTop: 16 }}>
+                    <Space style={{ marginBottom: 16 }}>
+                        <Input.Search
+                            placeholder="搜索模板名称或内容"
+                            allowClear
+                            onSearch={(keyword) => setStatsParams(prev => ({ ...prev, keyword, page: 1 }))}
+                            style={{ width: 240 }}
+                        />
+                        <Select
+                            value={statsParams.sort}
+                            onChange={(sort) => setStatsParams(prev => ({ ...prev, sort, page: 1 }))}
+                            style={{ width: 140 }}
+                            options={[
+                                { value: 'lastUsed', label: '最近使用' },
+                                { value: 'runs', label: '识别次数' },
+                                { value: 'successRate', label: '成功率' }
+                            ]}
+                        />
+                    </Space>
+                    <Table
+                        columns={statsColumns}
+                        dataSource={templateStats?.records}
+                        rowKey="templateId"
+                        size="small"
+                        pagination={{
+                            current: statsParams.page,
+                            pageSize: statsParams.pageSize,
+                            total: templateStats?.total,
+                            onChange: (page, pageSize) => setStatsParams(prev => ({ ...prev, page, pageSize }))
+                        }}
+                    />
+                </Card>
+
                 {/* 关于 */}
                 <Card title="关于" style={{ marginTop: 16 }}>
                     <div style={{ display: 'flex', flexDirection: 'column', gap: 8 }}>
                         <Text>图片识别工具 v1.0.0</Text>
                         <Text type="secondary">一款支持多 LLM 供应商的桌面图片识别应用</Text>
                         <Text type="secondary">支持 OpenAI、Anthropic 及兼容 OpenAI 格式的 API</Text>
+                        {update && (
+                            <Alert
+                                type="info"
+                                showIcon
+                                message={`发现新版本 v${update.version}（当前 v${update.currentVersion}）`}
+                                description={update.notes}
+                            />
+                        )}
+                        <Space>
+                            <Button
+                                onClick={handleCheckUpdate}
+                                loading={updateStatus === 'checking'}
+                                disabled={updateStatus !== 'idle' && updateStatus !== 'checking'}
+                            >
+                                检查更新
+                            </Button>
+                            {update && (
+                                <Button
+                                    type="primary"
+                                    onClick={handleInstallUpdate}
+                                    loading={updateStatus === 'downloading' || updateStatus === 'installing'}
+                                >
+                                    {updateStatus === 'downloading'
+                                        ? `下载中 ${updatePercent}%`
+                                        : updateStatus === 'installing' ? '正在安装' : '下载并安装'}
+                                </Button>
+                            )}
+                        </Space>
                     </div>
                 </Card>
             </div>
@@ -314,6 +1522,16 @@ export default function SettingsPage() {
                         <Input.TextArea rows={6} placeholder="请输入提示词..." />
                     </Form.Item>
 
+                    {lintIssues.map(issue => (
+                        <Alert
+                            key={issue.code}
+                            type={issue.severity}
+                            message={issue.message}
+                            showIcon
+                            style={{ marginBottom: 8 }}
+                        />
+                    ))}
+
                     <Form.Item
                         name="isDefault"
                         label="设为默认"
@@ -323,6 +1541,210 @@ export default function SettingsPage() {
                     </Form.Item>
                 </Form>
             </Drawer>
+
+            <Modal
+                title={editingRule ? '编辑规则' : '添加规则'}
+                open={ruleModalVisible}
+                onCancel={() => setRuleModalVisible(false)}
+                onOk={() => ruleForm.submit()}
+                okText={editingRule ? '更新' : '创建'}
+                cancelText="取消"
+            >
+                <Form form={ruleForm} layout="vertical" onFinish={handleSaveRule}>
+                    <Form.Item name="name" label="名称" rules={[{ required: true, message: '请输入规则名称' }]}>
+                        <Input placeholder="如: 统一手机号格式" />
+                    </Form.Item>
+                    <Form.Item name="pattern" label="正则表达式" rules={[{ required: true, message: '请输入正则表达式' }]}>
+                        <Input placeholder="如: (\d{3})[ -]?(\d{4})[ -]?(\d{4})" style={{ fontFamily: 'monospace' }} />
+                    </Form.Item>
+                    <Form.Item name="replacement" label="替换为" extra="可用 $1 或 ${name} 引用捕获组，留空则删除匹配内容">
+                        <Input placeholder="如: $1-$2-$3" style={{ fontFamily: 'monospace' }} />
+                    </Form.Item>
+                    <Form.Item name="enabled" label="启用" valuePropName="checked">
+                        <Switch />
+                    </Form.Item>
+                </Form>
+                <Divider style={{ margin: '8px 0 16px' }}>测试</Divider>
+                <Input.TextArea
+                    rows={3}
+                    value={ruleSample}
+                    onChange={(e) => setRuleSample(e.target.value)}
+                    placeholder="输入示例文本"
+                />
+                <Button style={{ marginTop: 8 }} onClick={handleTestRule} disabled={!ruleSample}>
+                    测试
+                </Button>
+                {ruleTest && (
+                    <div style={{ marginTop: 8 }}>
+                        <Text type="secondary">匹配 {ruleTest.matches} 处</Text>
+                        <pre style={{ whiteSpace: 'pre-wrap', marginTop: 4 }}>{ruleTest.output}</pre>
+                    </div>
+                )}
+            </Modal>
+
+            <Modal
+                title={`示例 - ${exampleTemplate?.name ?? ''}`}
+                open={exampleTemplate !== null}
+                onCancel={() => setExampleTemplate(null)}
+                footer={null}
+                width={640}
+                destroyOnClose
+            >
+                <Alert
+                    type="info"
+                    showIcon
+                    style={{ marginBottom: 16 }}
+                    message="示例图片和期望输出会作为之前的对话发送给模型，仅 OpenAI、Azure、Anthropic 和 OneAPI 支持；每个示例都会增加请求的图片数量"
+                />
+                <List
+                    dataSource={examples}
+                    locale={{ emptyText: '暂无示例' }}
+                    renderItem={(example) => (
+                        <List.Item
+                            actions={[
+                                <Popconfirm
+                                    key="delete"
+                                    title="确定要删除此示例吗？"
+                                    onConfirm={() => handleDeleteExample(example.id)}
+                                >
+                                    <Button icon={<DeleteOutlined />} size="small" danger />
+                                </Popconfirm>
+                            ]}
+                        >
+                            <Space align="start" style={{ width: '100%' }}>
+                                <Image
+                                    src={exampleImages[example.id]}
+                                    alt="示例图片"
+                                    width={96}
+                                    style={{ maxHeight: 96, objectFit: 'contain' }}
+                                />
+                                <Input.TextArea
+                                    defaultValue={example.output}
+                                    autoSize={{ minRows: 2, maxRows: 6 }}
+                                    style={{ width: 400 }}
+                                    onBlur={(e) => handleUpdateExample(example, e.target.value)}
+                                />
+                            </Space>
+                        </List.Item>
+                    )}
+                />
+                {examples.length < MAX_TEMPLATE_EXAMPLES && (
+                    <>
+                        <Divider />
+                        <Space align="start" style={{ width: '100%' }}>
+                            {newExampleImage ? (
+                                <Image
+                                    src={`data:${newExampleImage.mimeType};base64,${newExampleImage.base64}`}
+                                    alt={newExampleImage.fileName}
+                                    width={96}
+                                    style={{ maxHeight: 96, objectFit: 'contain' }}
+                                />
+                            ) : (
+                                <Button icon={<PictureOutlined />} onClick={handleSelectExampleImage}>
+                                    选择图片
+                                </Button>
+                            )}
+                            <Input.TextArea
+                                value={newExampleOutput}
+                                onChange={(e) => setNewExampleOutput(e.target.value)}
+                                placeholder="这张图片的期望输出"
+                                autoSize={{ minRows: 2, maxRows: 6 }}
+                                style={{ width: 400 }}
+                            />
+                        </Space>
+                        <div style={{ marginTop: 12, textAlign: 'right' }}>
+                            <Button
+                                type="primary"
+                                icon={<PlusOutlined />}
+                                disabled={!newExampleImage || !newExampleOutput.trim()}
+                                onClick={handleAddExample}
+                            >
+                                添加示例
+                            </Button>
+                        </div>
+                    </>
+                )}
+            </Modal>
+
+            <Modal
+                title={passcodeMode === 'remove' ? '移除密码' : lockStatus?.enabled ? '修改密码' : '设置密码'}
+                open={passcodeMode !== null}
+                onOk={handleSavePasscode}
+                onCancel={() => {
+                    setPasscodeMode(null)
+                    passcodeForm.resetFields()
+                }}
+                destroyOnClose
+            >
+                <Form form={passcodeForm} layout="vertical" preserve={false}>
+                    {lockStatus?.enabled && (
+                        <Form.Item
+                            name="current"
+                            label="当前密码"
+                            rules={[{ required: true, message: '请输入当前密码' }]}
+                        >
+                            <Input.Password />
+                        </Form.Item>
+                    )}
+                    {passcodeMode === 'set' && (
+                        <>
+                            <Form.Item
+                                name="passcode"
+                                label="新密码"
+                                rules={[{ required: true, min: 4, message: '密码至少 4 位' }]}
+                            >
+                                <Input.Password />
+                            </Form.Item>
+                            <Form.Item
+                                name="confirm"
+                                label="确认新密码"
+                                dependencies={['passcode']}
+                                rules={[
+                                    { required: true, message: '请再次输入新密码' },
+                                    ({ getFieldValue }) => ({
+                                        validator: (_, value) => !value || value === getFieldValue('passcode')
+                                            ? Promise.resolve()
+                                            : Promise.reject(new Error('两次输入的密码不一致'))
+                                    })
+                                ]}
+                            >
+                                <Input.Password />
+                            </Form.Item>
+                        </>
+                    )}
+                </Form>
+            </Modal>
+
+            <Modal
+                title="同步凭据"
+                open={syncCredentialsVisible}
+                onOk={handleSaveSyncCredentials}
+                onCancel={() => {
+                    setSyncCredentialsVisible(false)
+                    syncCredentialsForm.resetFields()
+                }}
+                destroyOnClose
+            >
+                <Form form={syncCredentialsForm} layout="vertical" preserve={false}>
+                    <Form.Item
+                        name="secret"
+                        label={syncBackend === 's3' ? 'Secret Key' : 'WebDAV 密码'}
+                        extra={syncCredentials?.hasSecret ? '已保存，留空保持不变' : undefined}
+                    >
+                        <Input.Password />
+                    </Form.Item>
+                    <Form.Item
+                        name="passphrase"
+                        label="同步密码"
+                        extra={syncCredentials?.hasPassphrase
+                            ? '已保存，留空保持不变'
+                            : '用于加密同步数据，所有设备需使用相同的同步密码'}
+                        rules={[{ required: !syncCredentials?.hasPassphrase, message: '请输入同步密码' }]}
+                    >
+                        <Input.Password />
+                    </Form.Item>
+                </Form>
+            </Modal>
         </>
     )
 }
@@ -13,8 +13,8 @@ interface ConfigState {
     fetchConfigs: () => Promise<void>
     fetchActiveConfigs: () => Promise<void>
     fetchDefaultConfig: () => Promise<void>
-    createConfig: (input: ModelConfigInput) => Promise<ModelConfigListItem>
-    updateConfig: (id: number, input: Partial<ModelConfigInput>) => Promise<ModelConfigListItem | null>
+    createConfig: (input: ModelConfigInput, validate?: boolean) => Promise<ModelConfigListItem>
+    updateConfig: (id: number, input: Partial<ModelConfigInput>, validate?: boolean) => Promise<ModelConfigListItem | null>
     deleteConfig: (id: number) => Promise<boolean>
     setDefaultConfig: (id: number) => Promise<boolean>
     testConnection: (id: number) => Promise<{ success: boolean; message: string }>
@@ -55,15 +55,15 @@ export const useConfigStore = create<ConfigState>((set, get) => ({
         }
     },
 
-    createConfig: async (input) => {
-        const config = await api.config.create(input)
+    createConfig: async (input, validate) => {
+        const config = await api.config.create(input, validate)
         await get().fetchConfigs()
         await get().fetchActiveConfigs()
         return config
     },
 
-    updateConfig: async (id, input) => {
-        const config = await api.config.update(id, input)
+    updateConfig: async (id, input, validate) => {
+        const config = await api.config.update(id, input, validate)
         await get().fetchConfigs()
         await get().fetchActiveConfigs()
         if (get().defaultConfig?.id === id) {
@@ -91,6 +91,9 @@ export const useConfigStore = create<ConfigState>((set, get) => ({
     },
 
     testConnection: async (id) => {
-        return await api.config.testConnection(id)
+        const result = await api.config.testConnection(id)
+        // 测试结果会记录为配置的验证状态
+        await get().fetchConfigs()
+        return result
     }
 }))
@@ -1,6 +1,6 @@
 import { create } from 'zustand'
 import { api } from '../api'
-import type { HistoryRecord, HistoryQueryParams, HistoryPaginatedResult } from '@shared/types'
+import type { HistoryRecord, HistoryQueryParams, HistoryPaginatedResult, HistoryStatus } from '@shared/types'
 
 interface HistoryState {
     records: HistoryRecord[]
@@ -13,10 +13,12 @@ interface HistoryState {
 
     // 筛选参数
     filters: {
-        startDate?: string
-        endDate?: string
+        startTime?: number
+        endTime?: number
         configId?: number
         keyword?: string
+        status?: HistoryStatus
+        needsReview?: boolean
     }
 
     // Actions
@@ -26,10 +28,12 @@ interface HistoryState {
     setFilters: (filters: Partial<HistoryState['filters']>) => void
     clearFilters: () => void
     selectRecord: (record: HistoryRecord | null) => void
+    markReviewed: (id: number) => Promise<void>
     deleteRecord: (id: number) => Promise<boolean>
     deleteRecords: (ids: number[]) => Promise<number>
     clearAll: () => Promise<number>
     exportRecords: () => Promise<HistoryRecord[]>
+    exportBundle: (defaultName: string) => Promise<boolean>
 }
 
 export const useHistoryStore = create<HistoryState>((set, get) => ({
@@ -90,6 +94,15 @@ export const useHistoryStore = create<HistoryState>((set, get) => ({
 
     selectRecord: (record) => set({ selectedRecord: record }),
 
+    markReviewed: async (id) => {
+        await api.history.markReviewed(id)
+        const reviewed = (record: HistoryRecord) => record.id === id ? { ...record, needsReview: false } : record
+        set((state) => ({
+            records: state.records.map(reviewed),
+            selectedRecord: state.selectedRecord && reviewed(state.selectedRecord)
+        }))
+    },
+
     deleteRecord: async (id) => {
         const result = await api.history.delete(id)
         if (result) {
@@ -122,5 +135,9 @@ export const useHistoryStore = create<HistoryState>((set, get) => ({
 
     exportRecords: async () => {
         return await api.history.export(get().filters)
+    },
+
+    exportBundle: async (defaultName) => {
+        return await api.history.exportBundle(get().filters, defaultName)
     }
 }))
@@ -1,6 +1,6 @@
 import { create } from 'zustand'
-import { api } from '../api'
-import type { RecognitionResult, RecognitionStatus } from '@shared/types'
+import { api, AppError } from '../api'
+import type { RecognitionResult, RecognitionStatus, RecognitionStageEvent, SelectedSavePath, UploadProgress } from '@shared/types'
 
 interface RecognitionState {
     // 图片数据
@@ -9,31 +9,41 @@ interface RecognitionState {
     processedImageData: string | null // 处理后的图片
     imageMimeType: string | null
     imageFileName: string | null
+    imagePath: string | null // 图片来自本地文件时的绝对路径
     showProcessed: boolean // 是否显示处理后的图片
 
     // 识别参数
     selectedConfigId: number | null
     prompt: string
+    templateId: number | null // 提示词来自的模板，手动修改后清空
     temperature: number
     topP: number
     maxTokens: number
     stream: boolean
+    streamFile: SelectedSavePath | null // 流式内容同时写入的文件，识别前通过保存对话框选择
+    exportToNotion: boolean // 识别成功后导出到 Notion
+    outputLanguage: string // 回答语言，空字符串表示由提示词决定
     customParams: Array<{ id: string; key: string; value: string }>
 
     // 识别状态
     status: RecognitionStatus
     result: RecognitionResult | null
+    uploadProgress: UploadProgress | null // 当前识别的图片上传进度
+    stage: RecognitionStageEvent | null // 当前识别所处的阶段
     isAborting: boolean
 
     // Actions
-    setImage: (data: string | null, mimeType: string | null, fileName?: string | null) => void
+    setImage: (data: string | null, mimeType: string | null, fileName?: string | null, filePath?: string | null) => void
     clearImage: () => void
     setConfigId: (id: number | null) => void
-    setPrompt: (prompt: string) => void
+    setPrompt: (prompt: string, templateId?: number) => void
     setTemperature: (value: number) => void
     setTopP: (value: number) => void
     setMaxTokens: (value: number) => void
     setStream: (value: boolean) => void
+    setStreamFile: (file: SelectedSavePath | null) => void
+    setExportToNotion: (value: boolean) => void
+    setOutputLanguage: (value: string) => void
     setCustomParams: (params: Array<{ id: string; key: string; value: string }>) => void
     loadSettings: () => Promise<void>
     recognize: () => Promise<RecognitionResult>
@@ -50,26 +60,34 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
     processedImageData: null,
     imageMimeType: null,
     imageFileName: null,
+    imagePath: null,
     showProcessed: false,
 
     selectedConfigId: null,
     prompt: DEFAULT_PROMPT,
+    templateId: null,
     temperature: 0,
     topP: 0.4,
     maxTokens: 2048,
     stream: true,
+    streamFile: null,
+    exportToNotion: false,
+    outputLanguage: '',
     customParams: [],
     status: 'idle',
     result: null,
+    uploadProgress: null,
+    stage: null,
     isAborting: false,
 
-    setImage: (data, mimeType, fileName = null) => {
+    setImage: (data, mimeType, fileName = null, filePath = null) => {
         set({
             imageData: data,
             originalImageData: data,
             processedImageData: null,
             imageMimeType: mimeType,
             imageFileName: fileName,
+            imagePath: filePath,
             showProcessed: false,
             status: 'idle',
             result: null
@@ -83,6 +101,7 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
             processedImageData: null,
             imageMimeType: null,
             imageFileName: null,
+            imagePath: null,
             showProcessed: false,
             status: 'idle',
             result: null
@@ -101,11 +120,14 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
     },
 
     setConfigId: (id) => set({ selectedConfigId: id }),
-    setPrompt: (prompt) => set({ prompt }),
+    setPrompt: (prompt, templateId) => set({ prompt, templateId: templateId ?? null }),
     setTemperature: (temperature) => set({ temperature }),
     setTopP: (topP) => set({ topP }),
     setMaxTokens: (maxTokens) => set({ maxTokens }),
     setStream: (stream) => set({ stream }),
+    setStreamFile: (streamFile) => set({ streamFile }),
+    setExportToNotion: (exportToNotion) => set({ exportToNotion }),
+    setOutputLanguage: (outputLanguage) => set({ outputLanguage }),
     setCustomParams: (customParams) => set({ customParams }),
 
     loadSettings: async () => {
@@ -115,7 +137,8 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
                 temperature: (settings as any).defaultTemperature ?? 0,
                 topP: (settings as any).defaultTopP ?? 0.4,
                 maxTokens: (settings as any).defaultMaxTokens ?? 2048,
-                stream: (settings as any).defaultStream ?? true
+                stream: (settings as any).defaultStream ?? true,
+                outputLanguage: settings.outputLanguage ?? ''
             })
         } catch (e) {
             console.error('Failed to load settings:', e)
@@ -137,12 +160,14 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
             throw new Error('请输入提示词')
         }
 
-        set({ status: 'uploading' })
+        set({ status: 'uploading', uploadProgress: null, stage: null })
 
         // Note: Streaming is not yet implemented in Tauri version
         // For now, we'll use non-streaming mode
         // Note: Streaming is implemented via Tauri events
         let removeListener: (() => void) | undefined;
+        let removeProgressListener: (() => void) | undefined
+        let removeStageListener: (() => void) | undefined
 
         try {
             removeListener = await api.recognition.onStreamChunk((content: string) => {
@@ -156,6 +181,13 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
                 }))
             })
 
+            removeProgressListener = await api.recognition.onUploadProgress((uploadProgress) => {
+                set({ uploadProgress })
+            })
+            removeStageListener = await api.recognition.onStage((stage) => {
+                set({ stage })
+            })
+
             set({
                 status: 'analyzing',
                 result: { success: true, content: '' }
@@ -182,11 +214,16 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
                 imageData: state.originalImageData || state.imageData, // 优先使用原图
                 imageMimeType: state.imageMimeType,
                 prompt: state.prompt,
+                templateId: state.templateId ?? undefined,
                 options: {
                     temperature: state.temperature,
                     topP: state.topP,
                     maxTokens: state.maxTokens,
                     stream: state.stream,
+                    outputLanguage: state.outputLanguage,
+                    sourcePath: state.imagePath ?? undefined,
+                    streamToFile: state.streamFile?.token,
+                    exportToNotion: state.exportToNotion,
                     customParams: customParamsRecord
                 }
             })
@@ -214,7 +251,7 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
             const errorMessage = (error as Error).message || String(error)
 
             // Don't show error for cancellation
-            if (errorMessage.includes('取消')) {
+            if (error instanceof AppError && error.kind === 'cancelled') {
                 set({ status: 'idle', result: null })
                 return { success: false, error: errorMessage }
             }
@@ -229,7 +266,9 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
             if (removeListener) {
                 removeListener()
             }
-            set({ isAborting: false })
+            removeProgressListener?.()
+            removeStageListener?.()
+            set({ isAborting: false, uploadProgress: null, stage: null })
         }
     },
 
@@ -253,10 +292,12 @@ export const useRecognitionStore = create<RecognitionState>((set, get) => ({
             processedImageData: null,
             imageMimeType: null,
             imageFileName: null,
+            imagePath: null,
             showProcessed: false,
             status: 'idle',
             result: null,
-            prompt: DEFAULT_PROMPT
+            prompt: DEFAULT_PROMPT,
+            templateId: null
         })
     }
 }))
@@ -38,6 +38,8 @@ export const useSettingsStore = create<SettingsState>((set, get) => ({
             set({ ...newSettings })
         } catch (error) {
             console.error('Failed to update settings:', error)
+            // 未知的设置项或无效的值由调用方提示
+            throw error
         } finally {
             set({ loading: false })
         }
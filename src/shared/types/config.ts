@@ -1,5 +1,8 @@
+import type { ErrorCode } from './errors'
+import type { ImageDetail } from './recognition'
+
 // 供应商类型
-export type ProviderType = 'openai' | 'anthropic' | 'azure' | 'oneapi' | 'custom'
+export type ProviderType = 'openai' | 'anthropic' | 'azure' | 'oneapi' | 'custom' | 'rapidocr'
 
 // 模型配置
 export interface ModelConfig {
@@ -11,6 +14,20 @@ export interface ModelConfig {
     apiKeyEncrypted?: string // 加密后的值，存储在数据库
     modelName: string
     maxTokens: number
+    requestsPerMinute?: number // 客户端限流，未设置表示不限制
+    monthlyBudgetUsd?: number // 每月预算（美元），达到 80% / 100% 时提醒
+    inputPricePerMtok?: number // 每百万输入 Token 价格（美元），用于估算花费
+    outputPricePerMtok?: number
+    lastValidationStatus?: 'valid' | 'invalid' // 最近一次密钥验证结果，未验证时为空
+    lastValidatedAt?: string
+    redactSensitive: boolean // 上传前遮盖图片中的邮箱、身份证号、银行卡号
+    imageDetail?: ImageDetail // OpenAI 图片精度默认值，为空时由接口决定
+    reasoningModel: boolean // 推理模型（OpenAI o 系列、Anthropic 扩展思考）
+    thinkingBudgetTokens?: number // Anthropic 思考预算，未设置时使用默认值
+    organization?: string // 作为 OpenAI-Organization 请求头发送
+    project?: string // 作为 OpenAI-Project 请求头发送
+    skipTlsVerify: boolean // 跳过 TLS 证书校验，不安全，仅用于无法安装 CA 的内部网关
+    profileId?: number // 所属配置档案，为空表示所有档案共享
     isActive: boolean
     isDefault: boolean
     createdAt: string
@@ -25,6 +42,18 @@ export interface ModelConfigInput {
     apiKey: string
     modelName: string
     maxTokens?: number
+    requestsPerMinute?: number // 0 表示清除限制
+    monthlyBudgetUsd?: number // 0 表示清除，价格同理
+    inputPricePerMtok?: number
+    outputPricePerMtok?: number
+    redactSensitive?: boolean
+    imageDetail?: ImageDetail | '' // 更新时传空字符串表示清除
+    reasoningModel?: boolean
+    thinkingBudgetTokens?: number // 0 表示恢复默认预算
+    organization?: string // 更新时传空字符串表示清除，项目同理
+    project?: string
+    skipTlsVerify?: boolean
+    profileId?: number // 未传时归属当前档案，0 表示共享
     isActive?: boolean
     isDefault?: boolean
 }
@@ -38,8 +67,55 @@ export interface ModelConfigListItem {
     apiKeyMasked: string // 如 sk-***abc
     modelName: string
     maxTokens: number
+    requestsPerMinute?: number
+    monthlyBudgetUsd?: number
+    inputPricePerMtok?: number
+    outputPricePerMtok?: number
+    lastValidationStatus?: 'valid' | 'invalid' // 最近一次密钥验证结果，未验证时为空
+    lastValidatedAt?: string
+    redactSensitive: boolean // 上传前遮盖图片中的邮箱、身份证号、银行卡号
+    imageDetail?: ImageDetail // OpenAI 图片精度默认值，为空时由接口决定
+    reasoningModel: boolean // 推理模型（OpenAI o 系列、Anthropic 扩展思考）
+    thinkingBudgetTokens?: number // Anthropic 思考预算，未设置时使用默认值
+    organization?: string // 作为 OpenAI-Organization 请求头发送
+    project?: string // 作为 OpenAI-Project 请求头发送
+    skipTlsVerify: boolean // 跳过 TLS 证书校验，不安全，仅用于无法安装 CA 的内部网关
+    profileId?: number // 所属配置档案，为空表示所有档案共享
     isActive: boolean
     isDefault: boolean
     createdAt: string
     updatedAt: string
 }
+
+// 配置查询参数
+export interface ConfigQueryParams {
+    page?: number
+    pageSize?: number
+    keyword?: string
+    provider?: ProviderType
+    activeOnly?: boolean
+}
+
+// 后台健康检查的结果
+export type HealthStatus = 'healthy' | 'degraded' | 'down'
+
+export interface ConfigHealth {
+    configId: number
+    status: HealthStatus // 测试请求超过 5 秒为 degraded，失败为 down
+    latencyMs: number
+    error?: string
+    errorCode?: ErrorCode
+    checkedAt: string
+}
+
+// 本月按 Token 用量估算的花费
+export type BudgetLevel = 'ok' | 'warning' | 'exceeded'
+
+export interface BudgetStatus {
+    configId: number
+    configName: string
+    budgetUsd: number
+    spentUsd: number
+    level: BudgetLevel // 达到 80% 为 warning，100% 为 exceeded
+    newlyReached: boolean // 本次识别使花费进入当前级别
+}
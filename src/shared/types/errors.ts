@@ -0,0 +1,64 @@
+// 后端错误码，与 Rust 端 ErrorCode 对应；message 为后端按语言设置生成的文案
+export type ErrorCode =
+    | { code: 'config_not_found' }
+    | { code: 'config_disabled' }
+    | { code: 'config_load_failed'; params: { detail: string } }
+    | { code: 'unsupported_provider'; params: { provider: string } }
+    | { code: 'empty_image' }
+    | { code: 'image_processing_failed'; params: { detail: string } }
+    | { code: 'unsupported_image_file'; params: { name: string } }
+    | { code: 'image_too_large'; params: { max_mb: number } }
+    | { code: 'http_client_failed'; params: { detail: string } }
+    | { code: 'request_timeout' }
+    | { code: 'connect_failed' }
+    | { code: 'request_failed'; params: { detail: string } }
+    | { code: 'invalid_api_key' }
+    | { code: 'api_key_forbidden' }
+    | { code: 'endpoint_not_found' }
+    | { code: 'rate_limited' }
+    | { code: 'provider_message'; params: { status: number; message: string } }
+    | { code: 'server_error'; params: { status: number; body: string } }
+    | { code: 'invalid_response'; params: { detail: string } }
+    | { code: 'unexpected_response' }
+    | { code: 'recognition_cancelled' }
+    | { code: 'recognition_task_failed'; params: { detail: string } }
+    | { code: 'orientation_failed' }
+    | { code: 'experiment_not_found' }
+    | { code: 'template_not_found'; params: { id: number } }
+    | { code: 'duplicate_template'; params: { id: number; name: string } }
+    | { code: 'too_many_examples'; params: { max: number } }
+    | { code: 'no_images_selected' }
+    | { code: 'invalid_rating' }
+    | { code: 'invalid_regex'; params: { detail: string } }
+    | { code: 'share_not_configured' }
+    | { code: 'share_failed'; params: { detail: string } }
+    | { code: 'email_not_configured' }
+    | { code: 'invalid_email_address'; params: { address: string } }
+    | { code: 'email_failed'; params: { detail: string } }
+    | { code: 'notion_not_configured' }
+    | { code: 'notion_failed'; params: { detail: string } }
+
+// 命令失败时的错误类型，与 Rust 端 AppError 对应
+export type AppErrorKind = 'db' | 'provider' | 'image' | 'validation' | 'cancelled' | 'internal'
+
+// 命令失败时后端返回的错误；前端按 kind 判断，message 用于显示
+export interface AppErrorPayload {
+    kind: AppErrorKind
+    message: string
+    code?: ErrorCode | null
+    // 服务商返回的 HTTP 状态码，仅 provider 类型
+    status?: number | null
+}
+
+// 连接测试结果
+export interface TestConnectionResult {
+    success: boolean
+    message: string
+    errorCode?: ErrorCode
+}
+
+// 批量重新验证时单个配置的结果
+export interface ConfigValidation extends TestConnectionResult {
+    configId: number
+    configName: string
+}
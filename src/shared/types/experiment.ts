@@ -0,0 +1,109 @@
+// 模板 A/B 实验
+export interface Experiment {
+    id: number
+    name: string
+    configId: number
+    templateAId: number
+    templateBId: number
+    imagePaths: string[]
+    status: 'draft' | 'running' | 'paused' | 'completed' | 'failed' // paused / failed：可继续运行剩余和失败的任务
+    createdAt: string
+}
+
+export interface ExperimentInput {
+    name: string
+    configId: number
+    templateAId: number
+    templateBId: number
+    imagePaths: string[]
+}
+
+export interface ExperimentRun {
+    id: number
+    experimentId: number
+    imagePath: string
+    variant: 'a' | 'b'
+    success: boolean
+    content?: string
+    error?: string
+    tokensUsed?: number
+    inputTokens?: number
+    outputTokens?: number
+    durationMs?: number
+    charCount?: number
+    rating?: number
+    createdAt: string
+}
+
+export interface ExperimentVariantSummary {
+    variant: 'a' | 'b'
+    templateId: number
+    templateName?: string
+    runs: number
+    successes: number
+    avgChars?: number
+    avgDurationMs?: number
+    totalTokens: number
+    inputTokens: number
+    outputTokens: number
+    avgRating?: number
+    ratedRuns: number
+}
+
+export interface ExperimentReport {
+    experiment: Experiment
+    variants: ExperimentVariantSummary[]
+    winsA: number
+    winsB: number
+    ties: number
+    runs: ExperimentRun[]
+}
+
+export interface ExperimentProgress {
+    experimentId: number
+    completed: number
+    total: number
+    imagePath: string
+    variant: 'a' | 'b'
+    queuePosition?: number // 等待供应商并发名额时的排队位置，运行完成后为空
+}
+
+// 单图多提示词 A/B 测试（2-4 个变体）
+export interface AbTestVariant {
+    prompt: string
+    templateId?: number // 提示词来自的模板
+}
+
+export interface AbTestRequest {
+    configId: number
+    imageData: string // Base64
+    imageMimeType: string
+    variants: AbTestVariant[]
+    judge?: boolean // 全部完成后让模型评选最佳结果
+}
+
+export interface AbTestResult {
+    id: number
+    abTestId: number
+    variant: number // 变体在请求中的序号，从 0 开始
+    prompt: string
+    templateId?: number
+    success: boolean
+    content?: string
+    error?: string
+    tokensUsed?: number
+    durationMs?: number
+    charCount?: number
+}
+
+export interface AbTest {
+    id: number
+    configId: number
+    configName: string
+    imageThumbnail?: string // Base64
+    judgeWinner?: number // 模型评选出的变体序号
+    judgeReason?: string
+    judgeError?: string // 评选失败时的错误信息
+    createdAt: string
+    results: AbTestResult[]
+}
@@ -0,0 +1,72 @@
+// 结构化提取的内置模板：小票/发票、名片
+export type ExtractionKind = 'receipt' | 'business_card'
+
+// 校验问题
+// 小票：缺少合计、日期无法识别、明细与小计不符、小计加税额与合计不符
+// 名片：缺少姓名、邮箱格式错误
+export type ExtractionIssue =
+    | 'missing_total'
+    | 'invalid_date'
+    | 'line_items_mismatch'
+    | 'total_mismatch'
+    | 'missing_name'
+    | 'invalid_email'
+
+// 小票/发票明细行
+export interface ReceiptLineItem {
+    description: string
+    quantity?: number
+    unitPrice?: number
+    amount?: number
+}
+
+// 小票/发票字段
+export interface Receipt {
+    merchant?: string
+    date?: string // 能识别时为 YYYY-MM-DD，否则保留原文
+    currency?: string // ISO 4217 货币代码
+    lineItems: ReceiptLineItem[]
+    subtotal?: number
+    tax?: number
+    total?: number
+}
+
+// 名片字段
+export interface BusinessCard {
+    name?: string
+    title?: string // 职位
+    company?: string
+    phones: string[]
+    emails: string[]
+    website?: string
+    address?: string
+}
+
+// 识别结果中的结构化提取，只有与 kind 对应的字段有值
+export interface Extraction {
+    kind: ExtractionKind
+    receipt?: Receipt
+    businessCard?: BusinessCard
+    issues: ExtractionIssue[] // 为空表示校验通过
+}
+
+// 已保存的提取结果
+export interface ExtractionRecord extends Extraction {
+    id: number
+    historyId: number
+    createdAt: string
+}
+
+// 提取结果查询参数
+export interface ExtractionQueryParams {
+    page?: number
+    pageSize?: number
+    kind?: ExtractionKind
+    merchant?: string // 商户名模糊匹配
+    currency?: string
+    dateFrom?: string // YYYY-MM-DD，含当天
+    dateTo?: string
+    minTotal?: number
+    maxTotal?: number
+    validOnly?: boolean // 只返回校验通过的结果
+}
@@ -1,31 +1,158 @@
 // 历史记录
 export interface HistoryRecord {
     id: number
+    uuid: string // 跨设备同步时标识记录
     configId: number
     configName: string
-    imagePath?: string
+    imagePath?: string // 识别的本地图片文件的绝对路径，粘贴或截图的图片为空
+    imageFile?: string // 应用图片目录中保存的原图文件名
     imageThumbnail?: string // Base64
+    compareThumbnail?: string // 对比识别中第二张图片的缩略图
     prompt: string
     result: string
-    tokensUsed?: number
+    reasoning?: string // 推理模型的思考过程，开启“保存推理过程”时记录
+    tokensUsed?: number // 输入 + 输出合计
+    inputTokens?: number
+    outputTokens?: number
     durationMs?: number
-    createdAt: string
+    ttftMs?: number // 首 token 时间，仅流式识别记录
+    tokensPerSecond?: number // 流式输出速度，仅流式识别记录
+    charCount?: number
+    wordCount?: number
+    language?: string // 检测到的主要语言，如 zh / en / ja
+    readingTimeSecs?: number
+    // 识别时的配置与参数快照，旧记录为空
+    provider?: string
+    modelName?: string
+    temperature?: number
+    topP?: number
+    maxTokens?: number
+    appVersion?: string
+    status: HistoryStatus
+    error?: string // 失败记录的错误信息
+    errorCode?: string
+    confidence?: number // 估计的识别置信度 0-1，开启置信度估计时记录
+    needsReview: boolean // 置信度低于阈值且尚未标记为已复核
+    batchId?: string // 批次名称，如命令行的 --batch，批次中的记录进入审核队列
+    reviewStatus?: ReviewStatus
+    reviewedResult?: string // 审核时修改后的文本，result 保留模型的原始结果
+    rerunOf?: number // 用其他模型批量重新识别时对应的原记录 ID
+    tags: string[] // 识别时所在工作区的标签
+    templateId?: number // 发送了其少样本示例的模板
+    createdAt: string // UTC ISO-8601，如 2024-03-31T01:30:00Z
 }
 
+// 审核队列中记录的状态
+export type ReviewStatus = 'pending' | 'approved' | 'rejected'
+
+// 审核队列中的批次及各状态的记录数
+export interface ReviewBatch {
+    batchId: string
+    pending: number
+    approved: number
+    rejected: number
+    lastCreatedAt: string
+}
+
+// 批量重新识别的进度，每完成一条记录发送一次
+export interface RerunProgress {
+    batchId: string
+    completed: number
+    total: number
+    historyId: number // 刚处理完的原记录
+    success: boolean
+}
+
+// 批量重新识别的结果，新记录进入 batchId 批次的审核队列
+export interface RerunReport {
+    batchId: string
+    total: number
+    succeeded: number
+    failed: { historyId: number; error: string }[]
+}
+
+// 记录状态，失败记录在设置中开启时保存；partial 为流式识别取消前已收到的部分结果
+export type HistoryStatus = 'success' | 'failed' | 'partial'
+
 // 历史记录查询参数
 export interface HistoryQueryParams {
     page?: number
     pageSize?: number
-    startDate?: string
+    startDate?: string // 本地日期 YYYY-MM-DD，含当天
     endDate?: string
+    startTime?: number // Unix 毫秒时间戳，含边界，优先于日期
+    endTime?: number
     configId?: number
     keyword?: string
+    status?: HistoryStatus
+    needsReview?: boolean // 只返回待复核（或无需复核）的记录
+    batchId?: string
+    reviewStatus?: ReviewStatus
+    fields?: (keyof HistoryRecord)[] // 只返回这些字段，id 始终返回
 }
 
-// 历史记录分页结果
-export interface HistoryPaginatedResult {
-    records: HistoryRecord[]
+// 分页结果
+export interface PaginatedResult<T> {
+    records: T[]
     total: number
     page: number
     pageSize: number
 }
+
+// 历史记录分页结果
+export type HistoryPaginatedResult<T = HistoryRecord> = PaginatedResult<T>
+
+// 历史记录原图
+export interface HistoryImage {
+    base64: string
+    mimeType: string
+}
+
+// 拖出到其他应用的结果文件
+export interface DragFile {
+    path: string
+    // 拖动时显示的图标
+    icon: string
+}
+
+// 历史图片完整性检查
+export interface IntegrityIssue {
+    historyId: number
+    imageFile: string
+    problem: 'missing' | 'corrupted'
+}
+
+export interface IntegrityReport {
+    checked: number
+    issues: IntegrityIssue[]
+    cleared: number
+}
+
+// 历史记录导入结果，已存在（创建时间与图片相同）的记录会被跳过
+export interface ImportReport {
+    imported: number
+    skipped: number
+    images: number
+}
+
+// 统计的时间粒度
+export type StatsGranularity = 'day' | 'week' | 'month'
+
+// 某时间段内某个配置的使用统计
+export interface HistoryStatsRow {
+    bucket: string // 日期、周一日期或 YYYY-MM
+    configId: number
+    configName: string
+    count: number // 成功次数
+    failureCount: number
+    avgDurationMs?: number
+    avgTokens?: number
+    avgTtftMs?: number // 流式识别的平均首 token 时间
+    avgTokensPerSecond?: number
+}
+
+// 同一或近似图片的历史识别记录
+export interface SimilarHistory {
+    record: HistoryRecord
+    distance: number // 感知哈希相差的位数，0 表示同一图片
+}
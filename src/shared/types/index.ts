@@ -2,3 +2,11 @@ export * from './config'
 export * from './recognition'
 export * from './history'
 export * from './settings'
+export * from './experiment'
+export * from './errors'
+export * from './workspace'
+export * from './recipe'
+export * from './preset'
+export * from './profile'
+export * from './extraction'
+export * from './postprocess'
@@ -0,0 +1,23 @@
+// 后处理规则：识别成功后按顺序对结果执行正则查找替换
+export interface PostprocessRule {
+    id: number
+    name: string
+    pattern: string // Rust regex 语法
+    replacement: string // 可用 $1 或 ${name} 引用捕获组
+    enabled: boolean
+    sortOrder: number
+    createdAt: string
+}
+
+export interface PostprocessRuleInput {
+    name: string
+    pattern: string
+    replacement: string
+    enabled: boolean
+}
+
+// 用示例文本试运行规则的结果
+export interface RuleTestResult {
+    output: string
+    matches: number
+}
@@ -0,0 +1,43 @@
+import type { ImageDetail, PreprocessMode } from './recognition'
+import type { ExtractionKind } from './extraction'
+
+// 识别预设：模型配置、提示词模板、参数和后处理选项的组合
+export interface PresetOptions {
+    temperature?: number
+    topP?: number
+    maxTokens?: number
+    stream?: boolean
+    customParams?: Record<string, string | number | boolean>
+    autoRotate?: boolean
+    autoCrop?: boolean
+    segmentLanguages?: boolean
+    layout?: boolean
+    validateMath?: boolean
+    mathml?: boolean
+    frameIndex?: number
+    preprocess?: PreprocessMode
+    extract?: ExtractionKind
+    jsonMode?: boolean
+    decodeCodes?: boolean
+    imageDetail?: ImageDetail
+    outputLanguage?: string
+}
+
+export interface Preset {
+    id: number
+    name: string
+    configId: number
+    templateId?: number // 未设置时使用默认模板
+    options: PresetOptions
+    isDefault: boolean // 未指定预设时使用（快捷键、托盘等入口）
+    createdAt: string
+    updatedAt: string
+}
+
+export interface PresetInput {
+    name: string
+    configId: number
+    templateId?: number
+    options?: PresetOptions
+    isDefault?: boolean
+}
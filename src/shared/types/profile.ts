@@ -0,0 +1,10 @@
+// 配置档案：隔离模型配置、模板和设置（如"工作"与"个人"）
+export interface Profile {
+    id: number
+    name: string
+    defaultConfigId?: number // 上次使用该档案时的默认配置，切换回来时恢复
+    defaultTemplateId?: number
+    isActive: boolean
+    createdAt: string
+    updatedAt: string
+}
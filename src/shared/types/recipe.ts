@@ -0,0 +1,41 @@
+import type { RecognitionResult } from './recognition'
+
+// 可分享的识别流程配方，模型按供应商 + 模型名引用
+export interface Recipe {
+    version: number
+    name: string
+    model: {
+        provider: string
+        modelName: string
+    }
+    template: {
+        name?: string
+        content: string
+    }
+    preprocessing?: {
+        autoCompress?: boolean
+        compressThresholdKb?: number
+        autoRotate?: boolean
+        autoCrop?: boolean
+    }
+    postprocessing?: {
+        glossary?: string[]
+        layout?: boolean
+        segmentLanguages?: boolean
+    }
+    sink?: {
+        exportFormat?: string
+        outputDir?: string
+    }
+}
+
+export interface RecipeRunItem {
+    input: string
+    result: RecognitionResult
+}
+
+export interface RecipeRunReport {
+    recipe: string
+    configId: number
+    items: RecipeRunItem[]
+}
@@ -1,3 +1,15 @@
+import type { ErrorCode } from './errors'
+import type { BudgetStatus } from './config'
+import type { Extraction, ExtractionKind } from './extraction'
+import type { PresetOptions } from './preset'
+import type { GlossaryCorrection } from './workspace'
+
+// 图片预处理模式：handwriting 为手写笔记增强笔画（对比度拉伸、放大、自适应二值化）
+export type PreprocessMode = 'handwriting'
+
+// OpenAI 图片精度：low 按固定小图计费，便宜得多；high 适合密集表格；auto 由模型决定
+export type ImageDetail = 'low' | 'high' | 'auto'
+
 // 识别请求参数
 export interface RecognitionRequest {
     configId: number
@@ -8,18 +20,168 @@ export interface RecognitionRequest {
     topP?: number
     maxTokens?: number
     stream?: boolean
+    segmentLanguages?: boolean // 返回按段落标注的语言
+    layout?: boolean // 请求带边界框的版面结构
+    validateMath?: boolean // 校验结果中的 LaTeX 公式
+    mathml?: boolean // 同时将有效公式转换为 MathML
+    frameIndex?: number // 动图（GIF/WebP）识别的帧序号，默认取中间帧
+    preprocess?: PreprocessMode // 识别前的图片预处理
+    extract?: ExtractionKind // 按内置模板提取结构化字段
+    jsonMode?: boolean // 要求 OpenAI 兼容接口直接输出 JSON（response_format）
+    decodeCodes?: boolean // 同时在本地识别二维码和条形码，默认取设置中的值
+    autoCrop?: boolean // 上传前裁掉纯色边距和黑边，默认取设置中的值
+    imageDetail?: ImageDetail // OpenAI 兼容接口的图片精度，默认取模型配置中的值
+    outputLanguage?: string // 回答语言，默认取设置中的值，空字符串表示不指定
+    estimateConfidence?: boolean // 估计识别置信度，默认取设置中的值
+    batchId?: string // 所属批次，识别结果进入该批次的审核队列
+    sourcePath?: string // 图片来自本地文件时的绝对路径，记录到历史中以便打开所在文件夹
+    streamToFile?: string // select_save_path 返回的 token；流式内容边收到边写入该文件，识别成功后替换为最终结果
+    exportToNotion?: boolean // 识别成功后将结果添加为设置中 Notion 数据库的新页面
     customParams?: Record<string, string | number | boolean>
     detailLevel?: 'concise' | 'standard' | 'detailed'
 }
 
+// 对比两张图片的请求，如“这两张截图有什么变化？”，结果作为一条历史记录保存
+export interface CompareImagesRequest {
+    configId: number
+    firstImage: string // Base64
+    secondImage: string
+    prompt: string
+    options?: PresetOptions // 与预设相同的识别参数
+}
+
+// 从对话框选择或拖入窗口的图片
+export interface SelectedImage {
+    base64: string
+    mimeType: string
+    fileName: string
+    filePath: string // 绝对路径
+}
+
+// 保存对话框选择的位置；后端只接受 token，不接受渲染进程传来的路径
+export interface SelectedSavePath {
+    token: string
+    path: string // 仅用于显示
+}
+
+// 本地识别出的二维码或条形码
+export interface DecodedCode {
+    format: string // 如 QR_CODE、EAN_13
+    text: string
+}
+
+// 本地模型的计算设备，metal 对应 macOS 的 Core ML
+export type InferenceDevice = 'cpu' | 'cuda' | 'directml' | 'metal'
+
+// 本地模型可用的计算设备和实际使用的设备
+export interface InferenceStatus {
+    available: InferenceDevice[] // 当前 onnxruntime 支持的设备，CPU 在最后
+    inUse?: InferenceDevice // 已加载模型实际运行的设备，尚未加载时为空
+}
+
+// 本地引擎单次识别的运行信息
+export interface LocalInference {
+    device: InferenceDevice // 实际使用的设备，GPU 不可用时回退为 cpu
+    inferenceMs: number // 模型推理耗时，不含排队和图片解码
+}
+
+// 识别所处的阶段
+export type RecognitionStage = 'queued' | 'compressing' | 'uploading' | 'waiting_first_token' | 'streaming' | 'saving'
+
+export interface RecognitionStageEvent {
+    stage: RecognitionStage
+    queuePosition?: number // 排队时的位置，从 1 开始
+}
+
+// 识别返回后在后台导出到 Notion 的结果
+export interface NotionExport {
+    title: string
+    url?: string // 新页面的链接
+    error?: string
+}
+
+// 图片上传进度（字节）
+export interface UploadProgress {
+    sent: number
+    total: number
+}
+
 // 识别结果
 export interface RecognitionResult {
     success: boolean
     content?: string
+    reasoning?: string // 推理模型在答案之外返回的思考过程，不计入 content
     error?: string
+    errorCode?: ErrorCode
+    providerIncident?: ProviderIncident // 连续失败后供应商状态页报告的故障
+    segments?: LanguageSegment[] // 请求 segmentLanguages 时按段落标注的语言
+    layout?: LayoutResult // 请求 layout 且模型返回有效结构时存在
+    formulas?: FormulaCheck[] // 请求 validateMath / mathml 时的公式校验结果
+    extraction?: Extraction // 请求 extract 且模型返回 JSON 时存在
+    codes?: DecodedCode[] // 本地识别出的二维码和条形码
+    inference?: LocalInference // 使用本地引擎识别时存在
     tokensUsed?: number
+    inputTokens?: number
+    outputTokens?: number
     durationMs?: number
+    ttftMs?: number // 流式识别从发出请求到收到首个 token 的时间
+    tokensPerSecond?: number // 流式输出速度（输出 token / 秒）
     processedImage?: string // 如果图片被压缩/处理，返回处理后的图片数据
+    partial?: boolean // 流式识别被取消，content 为取消前收到的部分内容（已存入历史）
+    budgetAlert?: BudgetStatus // 配置本月花费达到预算的 80% 后存在
+    confidence?: Confidence // 开启置信度估计时存在
+    warnings?: string[] // 发送前被调整的请求参数说明，如最大 Token 数超出模型上限
+    glossaryCorrections?: GlossaryCorrection[] // 按词表修正的术语
+}
+
+// 识别置信度：logprobs 为答案的 token 概率，selfRating 为模型对自身转写的二次评分
+export interface Confidence {
+    score: number // 0-1
+    method: 'logprobs' | 'selfRating'
+    sections: { section: string; score: number }[] // 仅 selfRating 有分段评分
+    needsReview: boolean // 低于设置中的置信度阈值
+}
+
+// 供应商状态页报告的故障
+export interface ProviderIncident {
+    indicator: 'minor' | 'major' | 'critical' | string
+    description: string
+    statusPage: string
+}
+
+// 按段落划分的语言片段
+export interface LanguageSegment {
+    text: string
+    language?: string // 如 zh / en / ja，无法判断时为空
+}
+
+// 版面结构，坐标为相对图片尺寸的比例 (0-1)
+export interface BoundingBox {
+    x: number
+    y: number
+    width: number
+    height: number
+}
+
+export interface LayoutWord {
+    text: string
+    bbox?: BoundingBox
+}
+
+export interface LayoutLine {
+    text: string
+    bbox?: BoundingBox
+    words: LayoutWord[]
+}
+
+export interface LayoutBlock {
+    text: string
+    bbox?: BoundingBox
+    lines: LayoutLine[]
+}
+
+export interface LayoutResult {
+    blocks: LayoutBlock[]
 }
 
 // 识别状态
@@ -68,3 +230,42 @@ export interface AnthropicRequest {
     temperature?: number
     top_p?: number
 }
+
+// 从结果中提取的表格，首行为表头（如有）
+export interface ExtractedTable {
+    rows: string[][]
+}
+
+// LaTeX 公式校验
+export interface LatexIssue {
+    code: string
+    severity: 'error' | 'warning'
+    message: string
+    offset: number
+}
+
+export interface FormulaCheck {
+    latex: string
+    display: boolean
+    valid: boolean // 无 error 级问题
+    issues: LatexIssue[]
+    mathml?: string
+}
+
+// 动图中提取的单帧（PNG）
+export interface AnimationFrame {
+    base64: string
+    mimeType: string
+    frameIndex: number
+    // 只解码到所选帧时为空
+    frameCount?: number
+}
+
+// 长截图拼接结果 (PNG)
+export interface StitchedImage {
+    base64: string
+    mimeType: string
+    width: number
+    height: number
+    overlaps: number[] // 每张截图与上一张重叠的行数，0 表示未找到重叠、直接拼接
+}
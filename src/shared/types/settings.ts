@@ -1,3 +1,5 @@
+import type { InferenceDevice } from './recognition'
+
 // 提示词模板
 export interface PromptTemplate {
     id: number
@@ -5,20 +7,215 @@ export interface PromptTemplate {
     content: string
     isDefault: boolean
     useCount: number
+    profileId?: number // 所属配置档案，为空表示所有档案共享
+    createdAt: string
+}
+
+// 合并重复模板的结果：保留的模板和并入其中后删除的模板
+export interface TemplateMerge {
+    keptId: number
+    removedIds: number[]
+}
+
+// 模板的少样本示例：示例图片和期望的输出，识别时作为之前的对话轮次发送
+export interface TemplateExample {
+    id: number
+    templateId: number
+    imageFile: string
+    mimeType: string
+    output: string
+    sortOrder: number
     createdAt: string
 }
 
+// 每个模板最多的示例数，与 Rust 端 MAX_EXAMPLES_PER_TEMPLATE 一致
+export const MAX_TEMPLATE_EXAMPLES = 5
+
+// 提示词模板查询参数
+export interface TemplateQueryParams {
+    page?: number
+    pageSize?: number
+    keyword?: string
+}
+
+// 模板使用统计的排序方式
+export type TemplateStatsSort = 'lastUsed' | 'runs' | 'successRate'
+
+// 模板使用统计查询参数
+export interface TemplateStatsParams {
+    page?: number
+    pageSize?: number
+    keyword?: string
+    configId?: number // 只统计该配置的识别
+    sort?: TemplateStatsSort
+}
+
+// 单个模板的识别统计
+export interface TemplateStats {
+    templateId: number
+    templateName: string
+    useCount: number // 选用次数
+    runCount: number // 使用该模板的识别次数
+    successCount: number
+    successRate?: number // 0-1，未识别过时为空
+    lastUsedAt?: string // UTC ISO-8601
+}
+
+// 使用过的提示词（用于输入联想）
+export interface PromptHistoryEntry {
+    id: number
+    prompt: string
+    useCount: number
+    lastUsedAt: string
+}
+
+// 提示词检查结果
+export interface PromptLintIssue {
+    code: 'bounding_boxes_unsupported' | 'json_mode_disabled' | 'conflicting_languages'
+    severity: 'warning' | 'info'
+    message: string
+}
+
 // 应用设置
 export interface AppSettings {
     theme: 'light' | 'dark' | 'system'
-    language: 'zh-CN' | 'en-US'
+    language: 'zh-CN' | 'en-US' | 'ja-JP' // 同时决定后端错误信息的语言
     imageMaxSize: number // MB
     compressThreshold: number // KB
     autoCompress: boolean
+    autoRotate: boolean // 识别前自动检测并纠正图片方向
+    autoCrop: boolean // 上传前裁掉纯色边距和黑边
+    decodeCodes: boolean // 识别时同时在本地读取二维码和条形码
     defaultTemperature: number
     defaultTopP: number
     defaultMaxTokens: number
     defaultStream: boolean
+    outputLanguage: string // 要求模型使用的回答语言，如 ja；留空由提示词决定
+    userAgent: string // 留空使用默认 User-Agent
+    extraHeaders: Record<string, string> // 附加到所有供应商请求的请求头
+    http2Enabled: boolean // 部分代理会中断 HTTP/2 流式响应，可关闭后强制使用 HTTP/1.1
+    tcpKeepaliveSecs: number // 0 表示关闭
+    http2KeepAliveSecs: number // HTTP/2 心跳间隔，防止代理断开长时间的流式响应；0 表示关闭
+    connectTimeoutSecs: number // 建立连接的超时，0 表示只受请求超时限制
+    poolIdleTimeoutSecs: number // 0 表示不复用连接
+    caBundlePath: string // 额外信任的 CA 证书（PEM），如企业内部网关的根证书；留空仅使用系统证书
+    providerConcurrency: Record<string, number> // 按供应商类型设置的每个账号（API 地址 + Key）并发请求数，未列出的默认 4
+    healthCheckMinutes: number // 后台检查启用配置连通性的间隔，0 表示关闭
+    saveFailedRecognitions: boolean // 失败的识别也记录到历史，便于重试和分析
+    storeReasoning: boolean // 推理模型的思考过程随历史记录保存
+    scrubHistoryPii: boolean // 历史记录中保存的结果和推理过程遮盖邮箱、手机号、身份证号等，识别返回的结果不受影响
+    estimateConfidence: boolean // 估计每次识别的置信度，优先使用 token 概率，否则再请求一次让模型自评
+    lowConfidenceThreshold: number // 置信度低于此值（0-1）的记录标记为待复核
+    updateChannel: 'stable' | 'beta' // 检查更新的发布渠道
+    redactionConfigId: number | null // 脱敏时用于识别文字的本地配置
+    encryptHistory: boolean // 加密保存历史记录中的提示词和识别结果，需通过 history.setEncryption 切换
+    lockIdleMinutes: number // 设置密码后，闲置多少分钟自动锁定，0 表示只在启动时锁定
+    ttsVoice: string // 朗读使用的语音 ID，留空使用系统默认语音
+    ttsRate: number // 朗读语速，1 为正常语速，范围 0.5-2
+    notebookPath: string // 追加识别结果的 Markdown 笔记文件，留空表示未设置
+    notebookThumbnails: boolean // 在笔记旁保存缩略图并在条目中引用
+    inferenceDevice: 'auto' | InferenceDevice // 本地模型使用的计算设备，auto 优先使用可用的 GPU
+    syncEnabled: boolean // 启动时通过 WebDAV/S3 同步配置、模板和历史记录
+    syncBackend: SyncBackend
+    syncEndpoint: string // WebDAV 文件夹地址，或 S3 服务地址
+    syncBucket: string // S3 存储桶，WebDAV 不使用
+    syncRegion: string // S3 签名区域
+    syncUsername: string // WebDAV 用户名或 S3 Access Key ID；密码与同步密码单独保存
+    shareTarget: 'gist' | 'custom' // 分享结果的方式：GitHub Gist 或自定义地址
+    shareEndpoint: string // 自定义分享地址，以请求体接收文本并返回链接
+    sharePublic: boolean // 创建公开 Gist，默认为不公开（secret）
+    smtpHost: string // 发送邮件的 SMTP 服务器；密码单独保存
+    smtpPort: number
+    smtpSecurity: 'starttls' | 'tls' | 'none' // 连接加密方式，tls 通常使用 465 端口
+    smtpUsername: string
+    smtpFrom: string // 发件人，可写作 "名称 <地址>"；留空时使用用户名
+    emailRecipients: string // 默认收件人，多个地址以逗号分隔
+    notionDatabaseId: string // 导出到 Notion 时新建页面所在的数据库；Token 单独保存
+}
+
+// 设置项的取值类型，用于通用地渲染设置表单
+export type SettingType =
+    | { type: 'boolean' }
+    | { type: 'integer'; min: number; max: number }
+    | { type: 'number'; min: number; max: number }
+    | { type: 'string' }
+    | { type: 'enum'; options: string[] }
+    | { type: 'string_map' } // 字符串值的对象，如额外请求头
+    | { type: 'integer_map'; min: number; max: number } // 整数值的对象，如各服务商并发数
+    | { type: 'optional_id' } // 记录 id，null 表示未设置
+
+// 设置项的键、类型和默认值；update 时未知的键或无效的值会被拒绝
+export type SettingSchema = SettingType & {
+    key: keyof AppSettings
+    default: unknown
+}
+
+// settings-changed 事件：保存或重置后广播给所有窗口
+export interface SettingsChanged {
+    keys: (keyof AppSettings)[]
+    // 为工作区或档案的覆盖值时设置
+    workspaceId: number | null
+    profileId: number | null
+}
+
+// 可用更新
+export interface UpdateInfo {
+    version: string
+    currentVersion: string
+    channel: string
+    notes?: string
+    date?: string
+}
+
+// 更新下载进度
+export interface UpdateProgress {
+    downloaded: number // 已下载字节数
+    total?: number
+}
+
+// 系统朗读语音
+export interface VoiceInfo {
+    id: string
+    name: string
+    language: string // BCP 47 语言标签，如 zh-CN
+}
+
+// 应用锁状态
+export type SyncBackend = 'webdav' | 's3'
+
+// 同步结果，数量为从远端取回并写入本地的条目
+export interface SyncReport {
+    configs: number
+    templates: number
+    history: number
+    deleted: number // 因其他设备已删除而在本机移除的条目数
+    syncedAt: string
+}
+
+// 同步凭据（WebDAV 密码/S3 Secret Key 与同步密码）是否已保存
+export interface SyncCredentialsState {
+    hasSecret: boolean
+    hasPassphrase: boolean
+}
+
+export interface LockStatus {
+    enabled: boolean // 已设置密码
+    locked: boolean
+}
+
+// 首次运行初始化结果
+export interface InitializationReport {
+    freshInstall: boolean // 初始化前没有任何配置和历史记录
+    configsCreated: number
+    templatesCreated: number
+}
+
+// 数据库恢复结果
+export interface RestoreReport {
+    configs: number
+    templates: number
+    historyRecords: number
+    previousBackup?: string // 恢复前自动保存的旧数据库路径
 }
 
 // 默认设置
@@ -28,12 +225,69 @@ export const DEFAULT_SETTINGS: AppSettings = {
     imageMaxSize: 10,
     compressThreshold: 2048, // 2MB
     autoCompress: true,
+    autoRotate: false,
+    autoCrop: false,
+    decodeCodes: true,
     defaultTemperature: 0,
     defaultTopP: 0.4,
     defaultMaxTokens: 2048,
-    defaultStream: true
+    defaultStream: true,
+    outputLanguage: '',
+    userAgent: '',
+    extraHeaders: {},
+    http2Enabled: true,
+    tcpKeepaliveSecs: 60,
+    http2KeepAliveSecs: 30,
+    connectTimeoutSecs: 10,
+    poolIdleTimeoutSecs: 90,
+    caBundlePath: '',
+    providerConcurrency: {},
+    healthCheckMinutes: 0,
+    saveFailedRecognitions: true,
+    storeReasoning: false,
+    scrubHistoryPii: false,
+    estimateConfidence: false,
+    lowConfidenceThreshold: 0.7,
+    updateChannel: 'stable',
+    redactionConfigId: null,
+    encryptHistory: false,
+    lockIdleMinutes: 10,
+    ttsVoice: '',
+    ttsRate: 1,
+    notebookPath: '',
+    notebookThumbnails: true,
+    inferenceDevice: 'auto',
+    syncEnabled: false,
+    syncBackend: 'webdav',
+    syncEndpoint: '',
+    syncBucket: '',
+    syncRegion: 'us-east-1',
+    syncUsername: '',
+    shareTarget: 'gist',
+    shareEndpoint: '',
+    sharePublic: false,
+    smtpHost: '',
+    smtpPort: 587,
+    smtpSecurity: 'starttls',
+    smtpUsername: '',
+    smtpFrom: '',
+    emailRecipients: '',
+    notionDatabaseId: ''
 }
 
+// 常用的回答语言，也可以传入其他语言名称
+export const OUTPUT_LANGUAGES: { value: string; label: string }[] = [
+    { value: 'zh-CN', label: '简体中文' },
+    { value: 'zh-TW', label: '繁體中文' },
+    { value: 'en', label: 'English' },
+    { value: 'ja', label: '日本語' },
+    { value: 'ko', label: '한국어' },
+    { value: 'fr', label: 'Français' },
+    { value: 'de', label: 'Deutsch' },
+    { value: 'es', label: 'Español' },
+    { value: 'ru', label: 'Русский' }
+]
+
 // 默认提示词
 export const DEFAULT_PROMPTS: Omit<PromptTemplate, 'id' | 'useCount' | 'createdAt'>[] = [
     {
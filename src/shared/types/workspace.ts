@@ -0,0 +1,39 @@
+// 工作区：打包预设、术语表、标签、导出配置和输出目录
+export interface Workspace {
+    id: number
+    name: string
+    configId?: number // 预设模型配置
+    templateId?: number // 预设提示词模板
+    glossary: string[]
+    tags: string[] // 随识别记录一起保存到历史
+    exportFormat: 'json' | 'csv' // 写入输出目录的文件格式，带上时间和标签
+    outputDir?: string // 识别成功后结果同时写入该目录
+    isActive: boolean
+    createdAt: string
+    updatedAt: string
+}
+
+// 词表：识别后把模型常写错的术语替换为正确写法，与工作区术语表（写入提示词）不同
+export interface GlossaryEntry {
+    id: number
+    term: string // 模型常写出的错误形式，不区分大小写
+    preferred: string
+    createdAt: string
+}
+
+// 识别结果中按词表修正的术语
+export interface GlossaryCorrection {
+    term: string
+    preferred: string
+    count: number
+}
+
+export interface WorkspaceInput {
+    name: string
+    configId?: number
+    templateId?: number
+    glossary?: string[]
+    tags?: string[]
+    exportFormat?: 'json' | 'csv'
+    outputDir?: string
+}